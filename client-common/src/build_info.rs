@@ -0,0 +1,169 @@
+//! Build metadata: the commit, build time and profile a binary came from, so
+//! users can check they run the build they expect, and notice when their app
+//! and the server weren't built from the same release.
+//!
+//! The apps call [`emit`] from their build script and read the result with
+//! [`build_info!`](crate::build_info!). The server reports the same fields
+//! under `build` in `/api/server-info`. The build time is the commit's (or
+//! `SOURCE_DATE_EPOCH`), never the wall clock, so building the same commit
+//! twice gives the same binary.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    #[serde(default)]
+    pub version: String,
+    /// Full git commit hash, when built from a checkout or with `GIT_COMMIT`
+    pub commit: Option<String>,
+    /// RFC 3339, UTC
+    pub timestamp: Option<String>,
+    /// Cargo profile: "release" or "debug"
+    #[serde(default)]
+    pub profile: String,
+}
+
+/// The [`BuildInfo`] of the crate this is expanded in, as set by [`emit`]
+#[macro_export]
+macro_rules! build_info {
+    () => {
+        $crate::BuildInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: option_env!("TOR_CHAT_BUILD_COMMIT").map(str::to_string),
+            timestamp: option_env!("TOR_CHAT_BUILD_TIMESTAMP").map(str::to_string),
+            profile: option_env!("TOR_CHAT_BUILD_PROFILE")
+                .unwrap_or_default()
+                .to_string(),
+        }
+    };
+}
+
+impl BuildInfo {
+    /// First 12 characters of the commit, enough to look it up
+    pub fn short_commit(&self) -> Option<&str> {
+        self.commit.as_deref().map(|c| c.get(..12).unwrap_or(c))
+    }
+
+    /// One line for display: "0.3.8 (1a2b3c4d5e6f, release, 2026-10-16T09:30:00Z)"
+    pub fn describe(&self) -> String {
+        let details: Vec<&str> = [
+            self.short_commit(),
+            Some(self.profile.as_str()).filter(|p| !p.is_empty()),
+            self.timestamp.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if details.is_empty() {
+            self.version.clone()
+        } else {
+            format!("{} ({})", self.version, details.join(", "))
+        }
+    }
+
+    /// Hint for the settings screen when this client and the server it talks
+    /// to weren't built from the same release. None when they match, or when
+    /// the server is too old to say.
+    pub fn mismatch_hint(&self, server: &BuildInfo) -> Option<String> {
+        if server.version.is_empty() {
+            return None;
+        }
+        if self.version != server.version {
+            return Some(format!(
+                "This app is version {} but the server runs {}. Some features may be missing or behave differently until both are on the same release.",
+                self.version, server.version
+            ));
+        }
+        match (self.short_commit(), server.short_commit()) {
+            (Some(ours), Some(theirs)) if self.commit != server.commit => Some(format!(
+                "Same version, but this app ({}) and the server ({}) were built from different commits. If you expected an official release, compare both with the release notes.",
+                ours, theirs
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// For build scripts: set the variables [`build_info!`](crate::build_info!)
+/// reads. Without a git checkout (Docker builds), `GIT_COMMIT` and
+/// `SOURCE_DATE_EPOCH` supply the commit and time.
+pub fn emit() {
+    use std::env;
+
+    let commit = env::var("GIT_COMMIT")
+        .ok()
+        .filter(|c| !c.trim().is_empty())
+        .or_else(|| git(&["rev-parse", "HEAD"]));
+    let epoch = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .or_else(|| git(&["log", "-1", "--format=%ct"]))
+        .and_then(|s| s.trim().parse::<i64>().ok());
+
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=TOR_CHAT_BUILD_COMMIT={}", commit.trim());
+    }
+    if let Some(epoch) = epoch {
+        println!(
+            "cargo:rustc-env=TOR_CHAT_BUILD_TIMESTAMP={}",
+            rfc3339(epoch)
+        );
+    }
+    println!(
+        "cargo:rustc-env=TOR_CHAT_BUILD_PROFILE={}",
+        env::var("PROFILE").unwrap_or_default()
+    );
+
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    // Rebuild when HEAD moves: the HEAD file changes on checkout, the ref
+    // it points at on commit
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        let git_dir = std::path::Path::new(git_dir.trim());
+        println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            let ref_file = git_dir.join(head_ref.trim());
+            if ref_file.exists() {
+                println!("cargo:rerun-if-changed={}", ref_file.display());
+            }
+        }
+    }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
+
+/// Unix time as "2026-10-16T09:30:00Z", without pulling a date crate into
+/// every build script (days-to-civil conversion from Howard Hinnant)
+fn rfc3339(epoch: i64) -> String {
+    let days = epoch.div_euclid(86_400);
+    let secs = epoch.rem_euclid(86_400);
+
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
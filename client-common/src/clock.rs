@@ -0,0 +1,63 @@
+//! Offset between this device's clock and the server's. Some users keep
+//! their clock deliberately wrong so it can't help identify them; the apps
+//! correct for it when showing relative times, countdowns and timestamps of
+//! messages still on their way, so those line up with the server's.
+//!
+//! The server reports its time as `serverTime` (Unix milliseconds) in
+//! `/api/server-info` and the socket's `authenticated` event. Like the rest
+//! of this crate, nothing here reads the clock: callers pass local times in.
+
+/// Skew below this is normal clock drift plus network delay, not worth
+/// mentioning to the user
+const NOTICEABLE_MS: i64 = 2 * 60 * 1000;
+
+/// Milliseconds to add to local time to get the server's time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClockOffset(i64);
+
+impl ClockOffset {
+    pub fn from_millis(millis: i64) -> Self {
+        Self(millis)
+    }
+
+    /// From one exchange: the request left at `sent`, the reply stamped
+    /// `server` by the server arrived at `received` (all Unix ms). The server
+    /// most likely read its clock halfway through the round trip.
+    pub fn measure(sent: i64, server: i64, received: i64) -> Self {
+        let midpoint = sent + (received - sent).max(0) / 2;
+        Self(server - midpoint)
+    }
+
+    pub fn millis(&self) -> i64 {
+        self.0
+    }
+
+    /// Local Unix ms to the server's
+    pub fn to_server(&self, local: i64) -> i64 {
+        local + self.0
+    }
+
+    /// Server Unix ms to the local clock's, e.g. to wait for a server time
+    pub fn to_local(&self, server: i64) -> i64 {
+        server - self.0
+    }
+
+    /// Whether the local clock is off by more than drift and latency explain
+    pub fn is_noticeable(&self) -> bool {
+        self.0.abs() >= NOTICEABLE_MS
+    }
+
+    /// "3h 12m ahead of the server" style description, for settings screens
+    pub fn describe(&self) -> String {
+        let minutes = self.0.abs() / 60_000;
+        let amount = match (minutes / 60, minutes % 60) {
+            (0, 0) => return "in sync with the server".to_string(),
+            (0, m) => format!("{}m", m),
+            (h, 0) => format!("{}h", h),
+            (h, m) => format!("{}h {}m", h, m),
+        };
+        // A positive offset means the server is later, so we're behind
+        let direction = if self.0 > 0 { "behind" } else { "ahead of" };
+        format!("{} {} the server", amount, direction)
+    }
+}
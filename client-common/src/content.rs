@@ -0,0 +1,133 @@
+use serde_json::Value;
+
+/// What a message's `content` string actually holds, decided from its
+/// `messageType` and `metadata`. Renderers match on this instead of poking at
+/// the raw string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageContent {
+    Plaintext(String),
+    /// Ciphertext we have no key for (yet). `key_id` comes from `metadata.keyId`.
+    Encrypted {
+        key_id: String,
+        ciphertext: String,
+    },
+    System(String),
+    /// An uploaded file. `content` is the URL; the rest mirrors the upload
+    /// response (`originalName`, `mimetype`, `size`) when the sender stored it.
+    File {
+        url: String,
+        name: Option<String>,
+        mime_type: Option<String>,
+        size: Option<u64>,
+    },
+}
+
+/// Source of room keys used to open `Encrypted` messages
+pub trait ContentDecryptor {
+    fn decrypt(&self, key_id: &str, ciphertext: &str) -> Option<String>;
+}
+
+/// Decryptor for clients without key material; leaves ciphertext sealed
+pub struct NoKeys;
+
+impl ContentDecryptor for NoKeys {
+    fn decrypt(&self, _key_id: &str, _ciphertext: &str) -> Option<String> {
+        None
+    }
+}
+
+impl MessageContent {
+    /// Decode a message as received from the API or socket and decrypt it if
+    /// the decryptor holds the key. This is the only place clients should
+    /// interpret `content`.
+    pub fn decode(
+        message_type: &str,
+        content: &str,
+        metadata: Option<&Value>,
+        keys: &dyn ContentDecryptor,
+    ) -> Self {
+        let meta_str = |key: &str| {
+            metadata
+                .and_then(|m| m.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+
+        match message_type {
+            "system" => MessageContent::System(content.to_string()),
+            "image" | "file" | "video" | "audio" => MessageContent::File {
+                url: content.to_string(),
+                name: meta_str("originalName"),
+                mime_type: meta_str("mimetype").or_else(|| {
+                    // Older image messages were sent without metadata
+                    (message_type == "image").then(|| "image/*".to_string())
+                }),
+                size: metadata
+                    .and_then(|m| m.get("size"))
+                    .and_then(|v| v.as_u64()),
+            },
+            "encrypted" => {
+                let key_id = meta_str("keyId").unwrap_or_default();
+                match keys.decrypt(&key_id, content) {
+                    Some(plain) => MessageContent::Plaintext(plain),
+                    None => MessageContent::Encrypted {
+                        key_id,
+                        ciphertext: content.to_string(),
+                    },
+                }
+            }
+            _ => MessageContent::Plaintext(content.to_string()),
+        }
+    }
+
+    /// Same as [`MessageContent::decode`] for a raw message object, e.g. the
+    /// embedded `replyMessage` or a socket payload
+    pub fn from_json(message: &Value, keys: &dyn ContentDecryptor) -> Self {
+        Self::decode(
+            message
+                .get("messageType")
+                .and_then(|v| v.as_str())
+                .unwrap_or("text"),
+            message
+                .get("content")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            message.get("metadata"),
+            keys,
+        )
+    }
+
+    /// Readable text, if the message has any
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            MessageContent::Plaintext(text) | MessageContent::System(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn is_image(&self) -> bool {
+        matches!(
+            self,
+            MessageContent::File { mime_type: Some(mime), .. } if mime.starts_with("image/")
+        )
+    }
+
+    /// Single-line summary for reply quotes, pinned lists and notifications
+    pub fn preview(&self, max_chars: usize) -> String {
+        let text = match self {
+            MessageContent::Plaintext(text) | MessageContent::System(text) => text.clone(),
+            MessageContent::Encrypted { .. } => "🔒 Encrypted message".to_string(),
+            MessageContent::File { .. } if self.is_image() => "📷 Image".to_string(),
+            MessageContent::File { name, .. } => {
+                format!("📎 {}", name.as_deref().unwrap_or("File"))
+            }
+        };
+
+        if text.chars().count() > max_chars {
+            let cut: String = text.chars().take(max_chars).collect();
+            format!("{}...", cut)
+        } else {
+            text
+        }
+    }
+}
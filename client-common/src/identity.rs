@@ -0,0 +1,207 @@
+use crate::sealed::Sealed;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// `format` field of backup files, so random JSON isn't mistaken for one
+const BACKUP_FORMAT: &str = "tor-chat-identity";
+const BACKUP_VERSION: u32 = 1;
+/// Shortest password accepted when exporting
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// The user's long-term X25519 keypair. The public half is what the server
+/// stores as the user's `publicKey`; the secret half never leaves the device
+/// except inside a password-protected backup.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityKeys {
+    /// Base64, same encoding as `publicKey` in the API
+    pub public_key: String,
+    secret_key: String,
+}
+
+// Keep the secret key out of logs
+impl fmt::Debug for IdentityKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdentityKeys")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdentityError {
+    /// Not a backup file, or a damaged one
+    InvalidBackup,
+    UnsupportedVersion(u32),
+    /// Wrong password (or a tampered file; the two look the same)
+    WrongPassword,
+    WeakPassword,
+}
+
+impl fmt::Display for IdentityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentityError::InvalidBackup => write!(f, "This is not a valid identity backup file"),
+            IdentityError::UnsupportedVersion(v) => write!(
+                f,
+                "This backup was made by a newer version of the app (format {})",
+                v
+            ),
+            IdentityError::WrongPassword => write!(f, "Wrong password"),
+            IdentityError::WeakPassword => write!(
+                f,
+                "Use a password of at least {} characters",
+                MIN_PASSWORD_LEN
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IdentityError {}
+
+/// On-disk backup: the keypair as JSON, sealed under the password. The
+/// public key is repeated in the clear so the import screen can show whose
+/// identity it is before unlocking.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Backup {
+    format: String,
+    version: u32,
+    public_key: String,
+    #[serde(flatten)]
+    sealed: Sealed,
+}
+
+impl IdentityKeys {
+    /// Keypair from 32 random bytes. The caller supplies the randomness so
+    /// this crate needs no RNG and builds the same for wasm and native.
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        let secret = StaticSecret::from(seed);
+        let public = PublicKey::from(&secret);
+        Self {
+            public_key: BASE64.encode(public.as_bytes()),
+            secret_key: BASE64.encode(secret.to_bytes()),
+        }
+    }
+
+    pub fn secret_key(&self) -> &str {
+        &self.secret_key
+    }
+
+    /// Short fingerprint of the public key for comparing devices by eye
+    /// ("3f2a 91c0 7b44 e815 0d6e")
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.public_key)
+    }
+
+    /// Whether the public key really belongs to the secret key
+    fn is_consistent(&self) -> bool {
+        let Some(secret) = decode_key(&self.secret_key) else {
+            return false;
+        };
+        let public = PublicKey::from(&StaticSecret::from(secret));
+        BASE64.encode(public.as_bytes()) == self.public_key
+    }
+
+    /// Password-protected backup file contents (JSON). `salt` and `nonce`
+    /// must be fresh random bytes.
+    pub fn export(
+        &self,
+        password: &str,
+        salt: [u8; 16],
+        nonce: [u8; 12],
+    ) -> Result<String, IdentityError> {
+        let plaintext = serde_json::to_vec(self).map_err(|_| IdentityError::InvalidBackup)?;
+        let backup = Backup {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            public_key: self.public_key.clone(),
+            sealed: Sealed::seal(password, &plaintext, salt, nonce)?,
+        };
+        serde_json::to_string_pretty(&backup).map_err(|_| IdentityError::InvalidBackup)
+    }
+
+    /// Public key fingerprint of a backup, readable without the password
+    pub fn backup_fingerprint(backup: &str) -> Result<String, IdentityError> {
+        parse_backup(backup).map(|b| fingerprint(&b.public_key))
+    }
+
+    /// Unlock a backup made by `export`. The keys inside must be the ones
+    /// its clear `publicKey` (and so its fingerprint) promised.
+    pub fn import(backup: &str, password: &str) -> Result<Self, IdentityError> {
+        let backup = parse_backup(backup)?;
+        let plaintext = backup.sealed.open(password)?;
+        let keys: IdentityKeys =
+            serde_json::from_slice(&plaintext).map_err(|_| IdentityError::InvalidBackup)?;
+        if !keys.is_consistent() || keys.public_key != backup.public_key {
+            return Err(IdentityError::InvalidBackup);
+        }
+        Ok(keys)
+    }
+}
+
+fn parse_backup(backup: &str) -> Result<Backup, IdentityError> {
+    let backup: Backup = serde_json::from_str(backup).map_err(|_| IdentityError::InvalidBackup)?;
+    if backup.format != BACKUP_FORMAT || !backup.sealed.is_valid() {
+        return Err(IdentityError::InvalidBackup);
+    }
+    if backup.version > BACKUP_VERSION {
+        return Err(IdentityError::UnsupportedVersion(backup.version));
+    }
+    Ok(backup)
+}
+
+fn decode_key(b64: &str) -> Option<[u8; 32]> {
+    BASE64.decode(b64).ok()?.try_into().ok()
+}
+
+/// Fingerprint of a base64 public key: the first 10 bytes of its SHA-256,
+/// hex in groups of four
+pub fn fingerprint(public_key: &str) -> String {
+    let digest = Sha256::digest(public_key.as_bytes());
+    digest[..10]
+        .chunks(2)
+        .map(|pair| format!("{:02x}{:02x}", pair[0], pair[1]))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "correct horse";
+
+    fn export(keys: &IdentityKeys) -> String {
+        keys.export(PASSWORD, [7; 16], [9; 12]).unwrap()
+    }
+
+    #[test]
+    fn imports_what_it_exported() {
+        let keys = IdentityKeys::from_seed([1; 32]);
+        let backup = export(&keys);
+        assert_eq!(
+            IdentityKeys::backup_fingerprint(&backup).unwrap(),
+            keys.fingerprint()
+        );
+        assert_eq!(IdentityKeys::import(&backup, PASSWORD).unwrap(), keys);
+    }
+
+    #[test]
+    fn refuses_a_backup_whose_public_key_is_someone_elses() {
+        let keys = IdentityKeys::from_seed([1; 32]);
+        let other = IdentityKeys::from_seed([2; 32]);
+        let backup = export(&keys).replace(&keys.public_key, &other.public_key);
+        assert_eq!(
+            IdentityKeys::backup_fingerprint(&backup).unwrap(),
+            other.fingerprint()
+        );
+        assert_eq!(
+            IdentityKeys::import(&backup, PASSWORD),
+            Err(IdentityError::InvalidBackup)
+        );
+    }
+}
@@ -0,0 +1,24 @@
+//! Client-side code shared by the web and desktop apps.
+//!
+//! Anything here must compile for both `wasm32-unknown-unknown` and native
+//! targets, so it only depends on serde and pure-Rust crypto crates, and
+//! takes randomness from the caller.
+
+pub mod build_info;
+pub mod clock;
+pub mod content;
+pub mod identity;
+pub mod links;
+pub mod markdown;
+pub mod sealed;
+pub mod trust;
+pub mod window;
+
+pub use build_info::BuildInfo;
+pub use clock::ClockOffset;
+pub use content::{ContentDecryptor, MessageContent, NoKeys};
+pub use identity::{IdentityError, IdentityKeys};
+pub use links::MessageLink;
+pub use sealed::Sealed;
+pub use trust::{KeyChange, KeyCheck, KnownKeys};
+pub use window::MessageWindow;
@@ -0,0 +1,52 @@
+//! Links to a message, for copying into other conversations or notes:
+//! `tor-chat://room/<room id>/message/<message id>`. They carry no server
+//! address, so they only mean something to members of the same server.
+
+/// URL scheme of message links
+pub const SCHEME: &str = "tor-chat://";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageLink {
+    pub room_id: String,
+    pub message_id: String,
+}
+
+impl MessageLink {
+    pub fn new(room_id: impl ToString, message_id: impl ToString) -> Self {
+        Self {
+            room_id: room_id.to_string(),
+            message_id: message_id.to_string(),
+        }
+    }
+
+    /// Read a link made by [`MessageLink::to_string`]; ids aren't checked to
+    /// be UUIDs, the apps do that when they look them up
+    pub fn parse(link: &str) -> Option<Self> {
+        let path = link.trim().strip_prefix(SCHEME)?;
+        let mut parts = path.split('/');
+        match (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) {
+            (Some("room"), Some(room), Some("message"), Some(message), None)
+                if !room.is_empty() && !message.is_empty() =>
+            {
+                Some(Self::new(room, message))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MessageLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}room/{}/message/{}",
+            SCHEME, self.room_id, self.message_id
+        )
+    }
+}
@@ -0,0 +1,138 @@
+//! The little markdown chat messages use: `**bold**`, `*italic*` (or
+//! `_italic_`), `~~strike~~`, `` `code` ``, fenced code blocks, `>` quotes
+//! and `-` lists. Links and images stay plain text, so formatting never
+//! makes the app fetch anything; the exception is [message links], which
+//! open a message in the app.
+//!
+//! [message links]: crate::links
+//!
+//! [`parse`] returns a tree the apps turn into their own elements; nothing
+//! here produces HTML, so message text can't inject markup.
+
+use crate::links::{MessageLink, SCHEME};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Bold(Vec<Inline>),
+    Italic(Vec<Inline>),
+    Strike(Vec<Inline>),
+    Code(String),
+    /// The link as written, and where it points
+    Link(String, MessageLink),
+}
+
+/// One line of a message, or a whole fenced code block
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Line(Vec<Inline>),
+    Quote(Vec<Inline>),
+    ListItem(Vec<Inline>),
+    Code(String),
+}
+
+/// Split a message into blocks. Line breaks are kept as they were typed,
+/// unlike in document markdown: each line is its own block.
+pub fn parse(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        if line.trim_start().starts_with("```") {
+            // The rest of the fence line names a language, which we ignore;
+            // an unclosed fence runs to the end of the message
+            let code: Vec<&str> = lines
+                .by_ref()
+                .take_while(|l| !l.trim_start().starts_with("```"))
+                .collect();
+            blocks.push(Block::Code(code.join("\n")));
+        } else if let Some(quote) = line.strip_prefix('>') {
+            blocks.push(Block::Quote(inlines(quote.trim_start())));
+        } else if let Some(item) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+            blocks.push(Block::ListItem(inlines(item)));
+        } else {
+            blocks.push(Block::Line(inlines(line)));
+        }
+    }
+    blocks
+}
+
+/// Whether `text` uses any formatting, i.e. whether a preview would look
+/// different from the text itself
+pub fn has_formatting(text: &str) -> bool {
+    parse(text).iter().any(|block| match block {
+        Block::Line(inlines) => inlines.iter().any(|i| !matches!(i, Inline::Text(_))),
+        _ => true,
+    })
+}
+
+const MARKERS: &[&str] = &["`", "**", "~~", "*", "_"];
+
+fn inlines(text: &str) -> Vec<Inline> {
+    let mut out = Vec::new();
+    let mut plain = String::new();
+    let mut prev = None;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some((node, used)) = link(rest, prev).or_else(|| span(rest, prev)) {
+            if !plain.is_empty() {
+                out.push(Inline::Text(std::mem::take(&mut plain)));
+            }
+            out.push(node);
+            prev = rest[..used].chars().next_back();
+            rest = &rest[used..];
+        } else {
+            plain.push(c);
+            prev = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    if !plain.is_empty() {
+        out.push(Inline::Text(plain));
+    }
+    out
+}
+
+/// A message link at the beginning of `rest`: it runs to the next
+/// whitespace and must not start in the middle of a word
+fn link(rest: &str, prev: Option<char>) -> Option<(Inline, usize)> {
+    if !rest.starts_with(SCHEME) || prev.is_some_and(char::is_alphanumeric) {
+        return None;
+    }
+    let used = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let text = &rest[..used];
+    let target = MessageLink::parse(text)?;
+    Some((Inline::Link(text.to_string(), target), used))
+}
+
+/// A formatted span starting at the beginning of `rest`, and how many bytes
+/// it covers. Markers only count when they hug the text (`* not this *`),
+/// and `_` only around whole words, so snake_case stays as typed.
+fn span(rest: &str, prev: Option<char>) -> Option<(Inline, usize)> {
+    for &marker in MARKERS {
+        let Some(after) = rest.strip_prefix(marker) else {
+            continue;
+        };
+        let Some(end) = after.find(marker) else {
+            continue;
+        };
+        let inner = &after[..end];
+        if inner.is_empty() || inner.starts_with(' ') || inner.ends_with(' ') {
+            continue;
+        }
+        let used = marker.len() * 2 + end;
+        if marker == "_"
+            && (prev.is_some_and(char::is_alphanumeric)
+                || rest[used..].starts_with(char::is_alphanumeric))
+        {
+            continue;
+        }
+        let node = match marker {
+            "`" => Inline::Code(inner.to_string()),
+            "**" => Inline::Bold(inlines(inner)),
+            "~~" => Inline::Strike(inlines(inner)),
+            _ => Inline::Italic(inlines(inner)),
+        };
+        return Some((node, used));
+    }
+    None
+}
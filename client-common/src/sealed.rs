@@ -0,0 +1,127 @@
+//! Data sealed under a password: ChaCha20-Poly1305 with a key derived from
+//! the password by PBKDF2-HMAC-SHA256. Identity backups and exported
+//! settings use it for what mustn't be readable from the file alone.
+
+use crate::identity::{IdentityError, MIN_PASSWORD_LEN};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+const KDF: &str = "pbkdf2-sha256";
+/// PBKDF2-HMAC-SHA256 rounds for new seals (OWASP's 2023 recommendation)
+const KDF_ROUNDS: u32 = 600_000;
+/// Most rounds `open` will run, so a crafted file can't hang the app
+const MAX_KDF_ROUNDS: u32 = 10 * KDF_ROUNDS;
+
+/// Sealed bytes and what's needed to open them, besides the password
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sealed {
+    kdf: String,
+    rounds: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Sealed {
+    /// Seal `plaintext`; `salt` and `nonce` must be fresh random bytes
+    pub fn seal(
+        password: &str,
+        plaintext: &[u8],
+        salt: [u8; 16],
+        nonce: [u8; 12],
+    ) -> Result<Self, IdentityError> {
+        if password.chars().count() < MIN_PASSWORD_LEN {
+            return Err(IdentityError::WeakPassword);
+        }
+        let ciphertext = cipher(password, &salt, KDF_ROUNDS)
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| IdentityError::InvalidBackup)?;
+        Ok(Self {
+            kdf: KDF.to_string(),
+            rounds: KDF_ROUNDS,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    /// Whether this is something `open` can work with, without the password
+    pub fn is_valid(&self) -> bool {
+        self.kdf == KDF && (1..=MAX_KDF_ROUNDS).contains(&self.rounds)
+    }
+
+    /// The sealed bytes, if `password` is the one they were sealed with
+    pub fn open(&self, password: &str) -> Result<Vec<u8>, IdentityError> {
+        if !self.is_valid() {
+            return Err(IdentityError::InvalidBackup);
+        }
+        let salt = BASE64
+            .decode(&self.salt)
+            .map_err(|_| IdentityError::InvalidBackup)?;
+        let nonce = BASE64
+            .decode(&self.nonce)
+            .ok()
+            .filter(|n| n.len() == 12)
+            .ok_or(IdentityError::InvalidBackup)?;
+        let ciphertext = BASE64
+            .decode(&self.ciphertext)
+            .map_err(|_| IdentityError::InvalidBackup)?;
+
+        cipher(password, &salt, self.rounds)
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| IdentityError::WrongPassword)
+    }
+}
+
+fn cipher(password: &str, salt: &[u8], rounds: u32) -> ChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, rounds, &mut key);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sealed(plaintext: &[u8]) -> Sealed {
+        Sealed::seal("correct horse", plaintext, [7; 16], [9; 12]).unwrap()
+    }
+
+    #[test]
+    fn opens_with_the_password_it_was_sealed_with() {
+        let sealed = sealed(b"secret");
+        assert!(sealed.is_valid());
+        assert_eq!(sealed.open("correct horse").unwrap(), b"secret");
+    }
+
+    #[test]
+    fn refuses_a_wrong_password() {
+        assert_eq!(
+            sealed(b"secret").open("wrong horse"),
+            Err(IdentityError::WrongPassword)
+        );
+    }
+
+    #[test]
+    fn refuses_a_weak_password() {
+        assert_eq!(
+            Sealed::seal("short", b"secret", [7; 16], [9; 12]),
+            Err(IdentityError::WeakPassword)
+        );
+    }
+
+    #[test]
+    fn refuses_oversized_rounds_without_deriving() {
+        let mut sealed = sealed(b"secret");
+        sealed.rounds = u32::MAX;
+        assert!(!sealed.is_valid());
+        assert_eq!(
+            sealed.open("correct horse"),
+            Err(IdentityError::InvalidBackup)
+        );
+    }
+}
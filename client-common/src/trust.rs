@@ -0,0 +1,62 @@
+use crate::identity::fingerprint;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Other users' public keys as first seen on this device (trust on first
+/// use). The server is the one handing out keys, so a key that differs from
+/// the remembered one is reported instead of silently replacing it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KnownKeys(HashMap<String, String>);
+
+/// A correspondent's key no longer matches the one remembered for them
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyChange {
+    pub user_id: String,
+    pub username: String,
+    pub previous: String,
+    pub current: String,
+}
+
+impl KeyChange {
+    pub fn previous_fingerprint(&self) -> String {
+        fingerprint(&self.previous)
+    }
+
+    pub fn current_fingerprint(&self) -> String {
+        fingerprint(&self.current)
+    }
+}
+
+pub enum KeyCheck {
+    /// First key seen for this user; now remembered (the caller should save)
+    New,
+    Unchanged,
+    /// Differs from the remembered key, which stays until `accept`
+    Changed(KeyChange),
+}
+
+impl KnownKeys {
+    /// Compare `public_key` with the one remembered for `user_id`
+    pub fn observe(&mut self, user_id: &str, username: &str, public_key: &str) -> KeyCheck {
+        match self.0.get(user_id) {
+            None => {
+                self.0.insert(user_id.to_string(), public_key.to_string());
+                KeyCheck::New
+            }
+            Some(known) if known == public_key => KeyCheck::Unchanged,
+            Some(known) => KeyCheck::Changed(KeyChange {
+                user_id: user_id.to_string(),
+                username: username.to_string(),
+                previous: known.clone(),
+                current: public_key.to_string(),
+            }),
+        }
+    }
+
+    /// The user acknowledged the change: remember the new key
+    pub fn accept(&mut self, change: &KeyChange) {
+        self.0
+            .insert(change.user_id.clone(), change.current.clone());
+    }
+}
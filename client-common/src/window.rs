@@ -0,0 +1,71 @@
+//! Windowed rendering of a room's messages. Only a slice of the loaded
+//! messages is rendered, and it slides as the list is scrolled towards one
+//! of its ends, so long histories stay quick to scroll and update (Tor
+//! Browser in particular struggles with thousands of message nodes).
+
+use std::ops::Range;
+
+/// Which slice of a message list is rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageWindow {
+    /// First rendered message; None follows the newest ones
+    start: Option<usize>,
+}
+
+impl MessageWindow {
+    /// Messages rendered at once
+    pub const SIZE: usize = 150;
+    /// How far the slice moves at a time
+    pub const STEP: usize = 50;
+
+    /// The slice ending at the newest message, following new ones as they
+    /// arrive
+    pub fn latest() -> Self {
+        Self::default()
+    }
+
+    /// Whether the newest message is rendered and new ones will be too
+    pub fn is_latest(&self) -> bool {
+        self.start.is_none()
+    }
+
+    /// The rendered part of `len` messages
+    pub fn range(&self, len: usize) -> Range<usize> {
+        match self.start {
+            Some(start) if start + Self::SIZE < len => start..start + Self::SIZE,
+            _ => len.saturating_sub(Self::SIZE)..len,
+        }
+    }
+
+    /// Moved towards older messages, if there are any before the slice
+    pub fn earlier(&self, len: usize) -> Self {
+        let start = self.range(len).start;
+        Self {
+            start: Some(start.saturating_sub(Self::STEP)),
+        }
+    }
+
+    /// Moved towards newer messages, following them again once it reaches
+    /// the newest
+    pub fn later(&self, len: usize) -> Self {
+        let range = self.range(len);
+        if range.end + Self::STEP < len {
+            Self {
+                start: Some(range.start + Self::STEP),
+            }
+        } else {
+            Self::latest()
+        }
+    }
+
+    /// Moved so message `index` is rendered, unless it already is
+    pub fn around(&self, index: usize, len: usize) -> Self {
+        if self.range(len).contains(&index) {
+            *self
+        } else {
+            Self {
+                start: Some(index.saturating_sub(Self::SIZE / 2)),
+            }
+        }
+    }
+}
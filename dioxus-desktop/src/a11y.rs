@@ -0,0 +1,29 @@
+//! Keyboard support for clickable elements that aren't native controls.
+//! Room entries, reply quotes, drawer items and the like are divs with
+//! `role="button"` and `tabindex="0"`; these helpers make Enter and Space
+//! activate them and Escape close dialogs, as a screen reader user expects.
+
+use dioxus::prelude::*;
+
+/// Enter or Space: the keys that press a focused button
+pub fn is_activation_key(e: &KeyboardEvent) -> bool {
+    match e.key() {
+        Key::Enter => true,
+        Key::Character(c) => c == " ",
+        _ => false,
+    }
+}
+
+/// Press handler for a `role="button"` element: runs `action` on Enter or
+/// Space, without also scrolling the page on Space
+pub fn on_activate(e: KeyboardEvent, action: impl FnOnce()) {
+    if is_activation_key(&e) {
+        e.prevent_default();
+        action();
+    }
+}
+
+/// Move keyboard focus to the element with `id`, e.g. from the skip link
+pub fn focus_element(id: &str) {
+    document::eval(&format!("document.getElementById('{id}')?.focus();"));
+}
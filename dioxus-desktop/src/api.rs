@@ -0,0 +1,1082 @@
+use crate::models::{
+    ApiToken, AuthResponse, CreatedApiToken, GuestSession, Message, NotificationSettings,
+    QueueStatus, Registration, Room, RoomFile, RoomMember, ServerInfo, Translation, UnreadCount,
+    User,
+};
+use crate::proxy::ProxyConfig;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex, RwLock};
+use tor_chat_common::ClockOffset;
+use uuid::Uuid;
+
+/// Requests whose 401 means bad credentials, not an expired session
+const NO_RENEWAL_PATHS: [&str; 4] = [
+    "/api/auth/login",
+    "/api/auth/register",
+    "/api/auth/refresh",
+    "/api/auth/logout",
+];
+
+/// Header with the server's signature over the server-info body
+const SIGNATURE_HEADER: &str = "x-torchat-signature";
+
+/// Whether `signature` (base64) is `key`'s (base64 Ed25519) over `body`
+fn verify_signature(key: &str, signature: &str, body: &[u8]) -> bool {
+    let key: Option<[u8; 32]> = BASE64.decode(key).ok().and_then(|k| k.try_into().ok());
+    let signature: Option<[u8; 64]> = BASE64
+        .decode(signature)
+        .ok()
+        .and_then(|s| s.try_into().ok());
+    let (Some(key), Some(signature)) = (key, signature) else {
+        return false;
+    };
+    VerifyingKey::from_bytes(&key)
+        .and_then(|key| key.verify_strict(body, &Signature::from_bytes(&signature)))
+        .is_ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// The request never got an answer (DNS, TLS, Tor circuit, timeout, ...)
+    Network(String),
+    /// The server answered with a non-success status
+    Server { status: u16, message: String },
+    /// The response body didn't have the expected shape
+    Decode(String),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network(e) => write!(f, "Network error: {}", e),
+            ApiError::Server { message, .. } => write!(f, "{}", message),
+            ApiError::Decode(e) => write!(f, "Unexpected response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ApiError {
+    /// The server refused our credentials. Expired sessions are dealt with
+    /// by the client itself (see `SessionEvent`), so callers rarely need this.
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, ApiError::Server { status: 401, .. })
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            ApiError::Decode(e.to_string())
+        } else {
+            ApiError::Network(e.to_string())
+        }
+    }
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+/// Session changes the client makes on its own, for the app to save or act on
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEvent {
+    /// The refresh token bought a new token pair
+    Renewed {
+        token: String,
+        refresh_token: String,
+    },
+    /// A 401 the refresh token couldn't fix: the user has to log in again
+    Expired,
+}
+
+#[derive(Clone)]
+pub struct ApiClient {
+    /// Client for direct connections, through the upstream proxy if one is set
+    clearnet_client: Arc<RwLock<Client>>,
+    direct_proxy: Arc<RwLock<Option<ProxyConfig>>>,
+    tor_client: Arc<RwLock<Option<Client>>>,
+    /// Tor's SOCKS proxy (embedded or external) while requests are routed through it
+    tor_proxy: Arc<RwLock<Option<ProxyConfig>>>,
+    base_url: Arc<RwLock<String>>,
+    token: Arc<RwLock<Option<String>>>,
+    refresh_token: Arc<RwLock<Option<String>>>,
+    /// Held while renewing the session, so parallel 401s share one refresh
+    renewing: Arc<Mutex<()>>,
+    session: Arc<watch::Sender<Option<SessionEvent>>>,
+    /// Last measured `ClockOffset`, in ms
+    clock_offset: Arc<AtomicI64>,
+}
+
+impl ApiClient {
+    pub fn new(base_url: String, token: Option<String>, refresh_token: Option<String>) -> Self {
+        Self {
+            clearnet_client: Arc::new(RwLock::new(Client::new())),
+            direct_proxy: Arc::new(RwLock::new(None)),
+            tor_client: Arc::new(RwLock::new(None)),
+            tor_proxy: Arc::new(RwLock::new(None)),
+            base_url: Arc::new(RwLock::new(base_url)),
+            token: Arc::new(RwLock::new(token)),
+            refresh_token: Arc::new(RwLock::new(refresh_token)),
+            renewing: Arc::new(Mutex::new(())),
+            session: Arc::new(watch::channel(None).0),
+            clock_offset: Arc::new(AtomicI64::new(0)),
+        }
+    }
+
+    pub async fn set_base_url(&self, url: String) {
+        *self.base_url.write().await = url;
+    }
+
+    pub async fn get_base_url(&self) -> String {
+        self.base_url.read().await.clone()
+    }
+
+    /// How far our clock is from the server's, as last measured
+    pub fn clock(&self) -> ClockOffset {
+        ClockOffset::from_millis(self.clock_offset.load(Ordering::Relaxed))
+    }
+
+    pub fn set_clock(&self, offset: ClockOffset) {
+        self.clock_offset.store(offset.millis(), Ordering::Relaxed);
+    }
+
+    /// The current time by the server's clock, for comparing with times the
+    /// server sent
+    pub fn server_now(&self) -> DateTime<Utc> {
+        let local = Utc::now().timestamp_millis();
+        DateTime::from_timestamp_millis(self.clock().to_server(local)).unwrap_or_else(Utc::now)
+    }
+
+    pub async fn set_token(&self, token: Option<String>) {
+        *self.token.write().await = token;
+    }
+
+    pub async fn get_token(&self) -> Option<String> {
+        self.token.read().await.clone()
+    }
+
+    pub async fn set_refresh_token(&self, refresh_token: Option<String>) {
+        *self.refresh_token.write().await = refresh_token;
+    }
+
+    /// Renewed and expired sessions, as they happen
+    pub fn session_receiver(&self) -> watch::Receiver<Option<SessionEvent>> {
+        self.session.subscribe()
+    }
+
+    /// Configure a reqwest Client that routes through Tor's SOCKS proxy
+    pub async fn configure_tor_proxy(&self, proxy: ProxyConfig) {
+        let client = Client::builder()
+            .proxy(proxy.reqwest().expect("Invalid SOCKS5 proxy URL"))
+            .build()
+            .expect("Failed to build Tor HTTP client");
+        *self.tor_client.write().await = Some(client);
+        *self.tor_proxy.write().await = Some(proxy);
+    }
+
+    /// Disable Tor proxy, revert to clearnet
+    pub async fn disable_tor_proxy(&self) {
+        *self.tor_proxy.write().await = None;
+        *self.tor_client.write().await = None;
+    }
+
+    /// Route direct (non-Tor) connections through an upstream proxy, e.g. a
+    /// corporate HTTP or SOCKS proxy; `None` connects straight out
+    pub async fn set_direct_proxy(&self, proxy: Option<ProxyConfig>) -> ApiResult<()> {
+        let mut builder = Client::builder();
+        if let Some(proxy) = &proxy {
+            builder = builder.proxy(proxy.reqwest().map_err(ApiError::Network)?);
+        }
+        *self.clearnet_client.write().await = builder.build()?;
+        *self.direct_proxy.write().await = proxy;
+        Ok(())
+    }
+
+    pub async fn is_using_tor(&self) -> bool {
+        self.tor_proxy.read().await.is_some()
+    }
+
+    /// Proxy for connections made outside this client (the Socket.IO
+    /// WebSocket), so they take the same route as API requests
+    pub async fn connection_proxy(&self) -> Option<ProxyConfig> {
+        match self.tor_proxy.read().await.clone() {
+            Some(proxy) => Some(proxy),
+            None => self.direct_proxy.read().await.clone(),
+        }
+    }
+
+    /// The Tor client when Tor is on, so nothing bypasses the proxy
+    async fn client(&self) -> Client {
+        if self.tor_proxy.read().await.is_some() {
+            if let Some(client) = self.tor_client.read().await.as_ref() {
+                return client.clone();
+            }
+        }
+        self.clearnet_client.read().await.clone()
+    }
+
+    async fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let base = self.base_url.read().await.clone();
+        let url = format!("{}{}", base, path);
+
+        let mut req = self.client().await.request(method, &url);
+
+        if let Some(token) = self.token.read().await.as_ref() {
+            req = req.header("Authorization", format!("Bearer {}", token));
+        }
+
+        req
+    }
+
+    /// Send a request and turn non-success statuses into `ApiError::Server`.
+    /// A 401 on an authenticated request renews the session with the refresh
+    /// token and resends once; if that fails the session has expired
+    /// (`SessionEvent::Expired`). Callers never have to handle 401s themselves.
+    async fn send(&self, req: RequestBuilder, fallback: &str) -> ApiResult<Response> {
+        let (client, request) = req.build_split();
+        let request = request?;
+        let sent_token = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(str::to_string)
+            .filter(|_| !NO_RENEWAL_PATHS.contains(&request.url().path()));
+        let retry = request.try_clone();
+
+        let mut response = client.execute(request).await?;
+        if let Some(sent_token) =
+            sent_token.filter(|_| response.status() == StatusCode::UNAUTHORIZED)
+        {
+            if !self.renew_session(&sent_token).await? {
+                self.session.send_replace(Some(SessionEvent::Expired));
+            } else if let Some(mut retry) = retry {
+                if let Some(token) = self.token.read().await.as_ref() {
+                    if let Ok(header) = format!("Bearer {}", token).parse() {
+                        retry.headers_mut().insert(AUTHORIZATION, header);
+                    }
+                }
+                response = client.execute(retry).await?;
+            } else {
+                // Multipart bodies can't be resent
+                return Err(ApiError::Server {
+                    status: 401,
+                    message: "Your session was renewed, please try again".to_string(),
+                });
+            }
+        }
+
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(Self::error_from(response, fallback).await)
+        }
+    }
+
+    /// Trade the refresh token for a new token pair. `failed_token` is the
+    /// access token that got the 401; if another request has replaced it
+    /// meanwhile, the session is already renewed. Ok(false) when it can't be:
+    /// no refresh token, or the server refused it.
+    async fn renew_session(&self, failed_token: &str) -> ApiResult<bool> {
+        let _renewing = self.renewing.lock().await;
+        if self
+            .token
+            .read()
+            .await
+            .as_deref()
+            .is_some_and(|token| token != failed_token)
+        {
+            return Ok(true);
+        }
+        let Some(refresh_token) = self.refresh_token.read().await.clone() else {
+            return Ok(false);
+        };
+
+        let base = self.base_url.read().await.clone();
+        let response = self
+            .client()
+            .await
+            .post(format!("{}/api/auth/refresh", base))
+            .json(&serde_json::json!({ "refreshToken": refresh_token }))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            // Spent or revoked; don't offer it again
+            *self.refresh_token.write().await = None;
+            return Ok(false);
+        }
+        let auth: AuthResponse = response.json().await?;
+        let Some(refresh_token) = auth.refresh_token else {
+            return Ok(false);
+        };
+
+        *self.token.write().await = Some(auth.token.clone());
+        *self.refresh_token.write().await = Some(refresh_token.clone());
+        self.session.send_replace(Some(SessionEvent::Renewed {
+            token: auth.token,
+            refresh_token,
+        }));
+        Ok(true)
+    }
+
+    /// Build an error from the server's `{details|error|message}` body
+    async fn error_from(response: Response, fallback: &str) -> ApiError {
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        let message = ["details", "error", "message"]
+            .iter()
+            .find_map(|key| body[key].as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}: {}", fallback, status));
+
+        ApiError::Server {
+            status: status.as_u16(),
+            message,
+        }
+    }
+
+    /// Decode one field of the JSON envelope, e.g. `{"rooms": [...]}`
+    async fn field<T: DeserializeOwned>(response: Response, key: &str) -> ApiResult<T> {
+        let mut data: Value = response.json().await?;
+        serde_json::from_value(data[key].take()).map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    pub async fn health_check(&self) -> ApiResult<()> {
+        let req = self.request(Method::GET, "/health").await;
+        self.send(req, "Server not responding").await?;
+        Ok(())
+    }
+
+    /// Server description. A signed one is checked against the identity key
+    /// it names and marked `signed`; whether that key is the one expected
+    /// for this server is up to the caller (see `storage::check_server_key`).
+    pub async fn get_server_info(&self) -> ApiResult<ServerInfo> {
+        let req = self.request(Method::GET, "/api/server-info").await;
+        let sent = Utc::now().timestamp_millis();
+        let response = self.send(req, "Failed to get server info").await?;
+        let received = Utc::now().timestamp_millis();
+        let signature = response
+            .headers()
+            .get(SIGNATURE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?;
+        let mut info: ServerInfo =
+            serde_json::from_slice(&body).map_err(|e| ApiError::Decode(e.to_string()))?;
+        if let Some(signature) = signature {
+            let key = info.identity_key.as_deref().unwrap_or_default();
+            if !verify_signature(key, &signature, &body) {
+                return Err(ApiError::Decode(
+                    "server info signature doesn't match its identity key".to_string(),
+                ));
+            }
+            info.signed = true;
+        }
+        if let Some(server_time) = info.server_time {
+            self.set_clock(ClockOffset::measure(sent, server_time, received));
+        }
+        Ok(info)
+    }
+
+    pub async fn register(
+        &self,
+        username: &str,
+        password: &str,
+        email: Option<&str>,
+    ) -> ApiResult<Registration> {
+        let mut body = serde_json::json!({
+            "username": username,
+            "password": password
+        });
+        if let Some(email) = email {
+            body["email"] = Value::String(email.to_string());
+        }
+
+        let req = self
+            .request(Method::POST, "/api/auth/register")
+            .await
+            .json(&body);
+        let response = self.send(req, "Registration failed").await?;
+        let data: Value = response.json().await?;
+        if data["queued"].as_bool() == Some(true) {
+            return Ok(Registration::Queued {
+                queue_token: data["queueToken"].as_str().unwrap_or_default().to_string(),
+                status: serde_json::from_value(data["status"].clone()).unwrap_or_default(),
+            });
+        }
+        serde_json::from_value(data)
+            .map(Registration::Active)
+            .map_err(|e| ApiError::Decode(e.to_string()))
+    }
+
+    pub async fn get_registration_status(&self, queue_token: &str) -> ApiResult<QueueStatus> {
+        let req = self
+            .request(
+                Method::GET,
+                &format!("/api/auth/registration-queue/{}", queue_token),
+            )
+            .await;
+        let response = self.send(req, "Failed to get queue status").await?;
+        Self::field(response, "status").await
+    }
+
+    pub async fn login(&self, username: &str, password: &str) -> ApiResult<AuthResponse> {
+        let body = serde_json::json!({
+            "username": username,
+            "password": password
+        });
+
+        let req = self
+            .request(Method::POST, "/api/auth/login")
+            .await
+            .json(&body);
+        let response = self.send(req, "Login failed").await?;
+        Ok(response.json().await?)
+    }
+
+    /// Start a guest session, on servers that allow them. There's no
+    /// refresh token: the session ends at `expires_at`.
+    pub async fn start_guest_session(&self, display_name: Option<&str>) -> ApiResult<GuestSession> {
+        let req = self
+            .request(Method::POST, "/api/auth/guest")
+            .await
+            .json(&serde_json::json!({ "displayName": display_name }));
+        let response = self.send(req, "Couldn't join as a guest").await?;
+        Ok(response.json().await?)
+    }
+
+    /// End the session on the server. Best effort: the local session is
+    /// dropped either way.
+    pub async fn logout(&self) {
+        // The refresh token outlives the access token, so it's revoked on its own
+        if let Some(refresh_token) = self.refresh_token.write().await.take() {
+            let req = self
+                .request(Method::DELETE, "/api/auth/refresh")
+                .await
+                .json(&serde_json::json!({ "refreshToken": refresh_token }));
+            let _ = self.send(req, "Failed to revoke refresh token").await;
+        }
+        let req = self.request(Method::POST, "/api/auth/logout").await;
+        let _ = self.send(req, "Logout failed").await;
+    }
+
+    pub async fn get_me(&self) -> ApiResult<User> {
+        let req = self.request(Method::GET, "/api/auth/me").await;
+        let response = self.send(req, "Failed to get user").await?;
+        Self::field(response, "user").await
+    }
+
+    /// Publish the identity public key this device holds
+    pub async fn update_public_key(&self, public_key: &str) -> ApiResult<()> {
+        let body = serde_json::json!({ "publicKey": public_key });
+        let req = self
+            .request(Method::PUT, "/api/auth/public-key")
+            .await
+            .json(&body);
+        self.send(req, "Failed to publish identity key").await?;
+        Ok(())
+    }
+
+    pub async fn list_api_tokens(&self) -> ApiResult<Vec<ApiToken>> {
+        let req = self.request(Method::GET, "/api/auth/tokens").await;
+        let response = self.send(req, "Failed to get API tokens").await?;
+        Self::field(response, "tokens").await
+    }
+
+    /// New API token; `room_ids` limits it to those rooms
+    pub async fn create_api_token(
+        &self,
+        name: &str,
+        scopes: &[&str],
+        room_ids: Option<&[Uuid]>,
+        expires_in_days: Option<i64>,
+    ) -> ApiResult<CreatedApiToken> {
+        let body = serde_json::json!({
+            "name": name,
+            "scopes": scopes,
+            "roomIds": room_ids,
+            "expiresInDays": expires_in_days,
+        });
+        let req = self
+            .request(Method::POST, "/api/auth/tokens")
+            .await
+            .json(&body);
+        let response = self.send(req, "Failed to create API token").await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn revoke_api_token(&self, token_id: Uuid) -> ApiResult<()> {
+        let req = self
+            .request(Method::DELETE, &format!("/api/auth/tokens/{}", token_id))
+            .await;
+        self.send(req, "Failed to revoke API token").await?;
+        Ok(())
+    }
+
+    /// Cover traffic no-op. The reply is read in full so it crosses the
+    /// connection like a real one.
+    pub async fn cover(&self, padding: &str, response_size: usize) -> ApiResult<()> {
+        let body = serde_json::json!({ "padding": padding, "responseSize": response_size });
+        let req = self.request(Method::POST, "/api/cover").await.json(&body);
+        let response = self.send(req, "Cover request failed").await?;
+        response.bytes().await?;
+        Ok(())
+    }
+
+    pub async fn get_rooms(&self) -> ApiResult<Vec<Room>> {
+        let req = self.request(Method::GET, "/api/rooms").await;
+        let response = self.send(req, "Failed to get rooms").await?;
+        Self::field(response, "rooms").await
+    }
+
+    pub async fn update_room_notifications(
+        &self,
+        room_id: &str,
+        settings: &NotificationSettings,
+    ) -> ApiResult<NotificationSettings> {
+        let req = self
+            .request(
+                Method::PUT,
+                &format!("/api/rooms/{}/notifications", room_id),
+            )
+            .await
+            .json(settings);
+        let response = self
+            .send(req, "Failed to update notification settings")
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn get_unread_counts(&self) -> ApiResult<Vec<UnreadCount>> {
+        let req = self.request(Method::GET, "/api/rooms/unread").await;
+        let response = self.send(req, "Failed to get unread counts").await?;
+        Self::field(response, "rooms").await
+    }
+
+    pub async fn create_room(
+        &self,
+        name: &str,
+        description: Option<&str>,
+        is_public: bool,
+        max_members: Option<i32>,
+    ) -> ApiResult<Room> {
+        let body = serde_json::json!({
+            "name": name,
+            "description": description,
+            "isPublic": is_public,
+            "maxMembers": max_members,
+        });
+
+        let req = self.request(Method::POST, "/api/rooms").await.json(&body);
+        let response = self.send(req, "Failed to create room").await?;
+        Self::field(response, "room").await
+    }
+
+    /// Switch the room to end-to-end encryption (creator only, irreversible)
+    pub async fn enable_e2ee(&self, room_id: &str) -> ApiResult<Room> {
+        let req = self
+            .request(Method::POST, &format!("/api/rooms/{}/e2ee", room_id))
+            .await;
+        let response = self.send(req, "Failed to enable encryption").await?;
+        Self::field(response, "room").await
+    }
+
+    /// Delete the user's own messages after `days`, in one room or
+    /// (`room_id: None`) everywhere; `days: None` removes the rule
+    pub async fn set_retention_rule(
+        &self,
+        room_id: Option<&str>,
+        days: Option<i32>,
+    ) -> ApiResult<()> {
+        let body = serde_json::json!({ "roomId": room_id, "days": days });
+        let req = self
+            .request(Method::PUT, "/api/retention")
+            .await
+            .json(&body);
+        self.send(req, "Failed to update retention").await?;
+        Ok(())
+    }
+
+    pub async fn get_room(&self, room_id: &str) -> ApiResult<Room> {
+        let req = self
+            .request(Method::GET, &format!("/api/rooms/{}", room_id))
+            .await;
+        let response = self.send(req, "Failed to get room").await?;
+        Self::field(response, "room").await
+    }
+
+    pub async fn get_messages(
+        &self,
+        room_id: &str,
+        limit: u32,
+        offset: usize,
+    ) -> ApiResult<Vec<Message>> {
+        let req = self
+            .request(
+                Method::GET,
+                &format!(
+                    "/api/rooms/{}/messages?limit={}&offset={}",
+                    room_id, limit, offset
+                ),
+            )
+            .await;
+        let response = self.send(req, "Failed to get messages").await?;
+        Self::field(response, "messages").await
+    }
+
+    /// A message with up to `around` messages on each side, oldest first
+    pub async fn get_message_context(
+        &self,
+        room_id: &str,
+        message_id: &str,
+        around: u32,
+    ) -> ApiResult<Vec<Message>> {
+        let req = self
+            .request(
+                Method::GET,
+                &format!(
+                    "/api/rooms/{}/messages/{}/context?around={}",
+                    room_id, message_id, around
+                ),
+            )
+            .await;
+        let response = self.send(req, "Failed to open message").await?;
+        Self::field(response, "messages").await
+    }
+
+    pub async fn send_message(
+        &self,
+        room_id: &str,
+        content: &str,
+        expires_in: Option<i32>,
+    ) -> ApiResult<Message> {
+        let mut body = serde_json::json!({
+            "content": content,
+            "messageType": "text"
+        });
+        if let Some(seconds) = expires_in {
+            body["expiresIn"] = serde_json::json!(seconds);
+        }
+
+        let req = self
+            .request(Method::POST, &format!("/api/rooms/{}/messages", room_id))
+            .await
+            .json(&body);
+        let response = self.send(req, "Failed to send message").await?;
+        Self::field(response, "message").await
+    }
+
+    /// Download an uploaded file (e.g. `/uploads/...`) over the same
+    /// connection as the API. Returns the content type and bytes.
+    pub async fn fetch_file(&self, url: &str) -> ApiResult<(String, Vec<u8>)> {
+        let req = if url.starts_with("http://") || url.starts_with("https://") {
+            self.client().await.get(url)
+        } else {
+            self.request(Method::GET, url).await
+        };
+        let response = self.send(req, "Failed to download file").await?;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        Ok((content_type, response.bytes().await?.to_vec()))
+    }
+
+    /// Translate text into `target` via the server's own LibreTranslate
+    pub async fn translate(&self, text: &str, target: &str) -> ApiResult<Translation> {
+        let body = serde_json::json!({
+            "text": text,
+            "source": "auto",
+            "target": target,
+        });
+
+        let req = self
+            .request(Method::POST, "/api/translate")
+            .await
+            .json(&body);
+        let response = self.send(req, "Translation failed").await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn leave_room(&self, room_id: &str) -> ApiResult<()> {
+        let req = self
+            .request(Method::POST, &format!("/api/rooms/{}/leave", room_id))
+            .await;
+        self.send(req, "Failed to leave room").await?;
+        Ok(())
+    }
+
+    pub async fn delete_room(&self, room_id: &str) -> ApiResult<()> {
+        let req = self
+            .request(Method::DELETE, &format!("/api/rooms/{}", room_id))
+            .await;
+        self.send(req, "Failed to delete room").await?;
+        Ok(())
+    }
+
+    /// Most recent attachments shared in a room
+    pub async fn get_room_files(&self, room_id: &str, limit: u32) -> ApiResult<Vec<RoomFile>> {
+        let req = self
+            .request(
+                Method::GET,
+                &format!("/api/rooms/{}/files?limit={}", room_id, limit),
+            )
+            .await;
+        let response = self.send(req, "Failed to get files").await?;
+        Self::field(response, "files").await
+    }
+
+    pub async fn get_members(&self, room_id: &str) -> ApiResult<Vec<RoomMember>> {
+        let req = self
+            .request(Method::GET, &format!("/api/rooms/{}/members", room_id))
+            .await;
+        let response = self.send(req, "Failed to get members").await?;
+        Self::field(response, "members").await
+    }
+
+    pub async fn get_users(&self) -> ApiResult<Vec<User>> {
+        let req = self.request(Method::GET, "/api/auth/users").await;
+        let response = self.send(req, "Failed to get users").await?;
+        Self::field(response, "users").await
+    }
+
+    pub async fn add_member(&self, room_id: &str, user_id: &str) -> ApiResult<()> {
+        let body = serde_json::json!({ "userId": user_id });
+        let req = self
+            .request(Method::POST, &format!("/api/rooms/{}/members", room_id))
+            .await
+            .json(&body);
+        self.send(req, "Failed to add member").await?;
+        Ok(())
+    }
+
+    pub async fn remove_member(&self, room_id: &str, user_id: &str) -> ApiResult<()> {
+        let req = self
+            .request(
+                Method::DELETE,
+                &format!("/api/rooms/{}/members/{}", room_id, user_id),
+            )
+            .await;
+        self.send(req, "Failed to remove member").await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// A request as the mock server received it
+    #[derive(Debug, Clone)]
+    struct Seen {
+        method: String,
+        path: String,
+        authorization: Option<String>,
+        body: Value,
+    }
+
+    /// HTTP/1.1 server on a free local port. Every request is recorded and
+    /// answered by `respond` with a status and a JSON body.
+    struct MockServer {
+        url: String,
+        seen: Arc<std::sync::Mutex<Vec<Seen>>>,
+    }
+
+    impl MockServer {
+        async fn start(respond: impl Fn(&Seen) -> (u16, Value) + Send + Sync + 'static) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let respond = Arc::new(respond);
+
+            let log = seen.clone();
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let (respond, log) = (respond.clone(), log.clone());
+                    tokio::spawn(async move {
+                        let Some(request) = read_request(&mut stream).await else {
+                            return;
+                        };
+                        let (status, body) = respond(&request);
+                        log.lock().unwrap().push(request);
+                        let body = body.to_string();
+                        let response = format!(
+                            "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\n\
+                             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            status,
+                            body.len(),
+                            body
+                        );
+                        let _ = stream.write_all(response.as_bytes()).await;
+                    });
+                }
+            });
+
+            Self { url, seen }
+        }
+
+        fn client(&self, token: Option<&str>, refresh_token: Option<&str>) -> ApiClient {
+            ApiClient::new(
+                self.url.clone(),
+                token.map(str::to_string),
+                refresh_token.map(str::to_string),
+            )
+        }
+
+        fn paths(&self) -> Vec<String> {
+            self.seen
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|s| format!("{} {}", s.method, s.path))
+                .collect()
+        }
+
+        fn seen(&self, index: usize) -> Seen {
+            self.seen.lock().unwrap()[index].clone()
+        }
+    }
+
+    async fn read_request(stream: &mut TcpStream) -> Option<Seen> {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let head_end = loop {
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        };
+
+        let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+        let mut lines = head.lines();
+        let mut start = lines.next()?.split(' ');
+        let method = start.next()?.to_string();
+        let path = start.next()?.to_string();
+        let mut length = 0;
+        let mut authorization = None;
+        for (name, value) in lines.filter_map(|l| l.split_once(':')) {
+            match name.to_ascii_lowercase().as_str() {
+                "content-length" => length = value.trim().parse().unwrap_or(0),
+                "authorization" => authorization = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+
+        while buf.len() < head_end + length {
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+        Some(Seen {
+            method,
+            path,
+            authorization,
+            body: serde_json::from_slice(&buf[head_end..]).unwrap_or(Value::Null),
+        })
+    }
+
+    fn user_json() -> Value {
+        json!({
+            "id": "6f1c7a4e-8d1b-4c55-9d0a-2f8e0b6c1a11",
+            "username": "alice",
+            "displayName": "Alice",
+            "isAdmin": false,
+        })
+    }
+
+    #[tokio::test]
+    async fn login_decodes_the_auth_response() {
+        let server = MockServer::start(|_| {
+            (
+                200,
+                json!({ "token": "t1", "refreshToken": "r1", "user": user_json() }),
+            )
+        })
+        .await;
+
+        let auth = server
+            .client(None, None)
+            .login("alice", "hunter22")
+            .await
+            .unwrap();
+        assert_eq!(auth.token, "t1");
+        assert_eq!(auth.refresh_token.as_deref(), Some("r1"));
+        assert_eq!(auth.user.unwrap().username, "alice");
+
+        let seen = server.seen(0);
+        assert_eq!(server.paths(), ["POST /api/auth/login"]);
+        assert_eq!(seen.authorization, None);
+        assert_eq!(
+            seen.body,
+            json!({ "username": "alice", "password": "hunter22" })
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_are_read_from_their_envelope_with_the_token() {
+        let server = MockServer::start(|_| {
+            (
+                200,
+                json!({ "rooms": [{
+                    "id": "0b7d3c52-1e6a-4f0e-8a43-5c9b2d7e6f10",
+                    "name": "lobby",
+                    "isPublic": true,
+                    "memberCount": 3,
+                }] }),
+            )
+        })
+        .await;
+
+        let rooms = server.client(Some("t1"), None).get_rooms().await.unwrap();
+        assert_eq!(rooms.len(), 1);
+        assert_eq!(rooms[0].name, "lobby");
+        assert!(rooms[0].is_public);
+        assert_eq!(rooms[0].member_count, 3);
+        assert_eq!(server.seen(0).authorization.as_deref(), Some("Bearer t1"));
+    }
+
+    #[tokio::test]
+    async fn error_statuses_carry_the_server_details() {
+        let server = MockServer::start(|seen| match seen.path.as_str() {
+            "/api/rooms" => (
+                403,
+                json!({ "error": "access_denied", "details": "Access denied: nope" }),
+            ),
+            _ => (500, Value::Null),
+        })
+        .await;
+        let client = server.client(Some("t1"), None);
+
+        let error = client.get_rooms().await.unwrap_err();
+        assert_eq!(
+            error,
+            ApiError::Server {
+                status: 403,
+                message: "Access denied: nope".to_string(),
+            }
+        );
+        assert!(!error.is_unauthorized());
+
+        // No details in the body: the caller's fallback with the status
+        match client.get_me().await.unwrap_err() {
+            ApiError::Server { status, message } => {
+                assert_eq!(status, 500);
+                assert!(message.starts_with("Failed to get user"), "{}", message);
+            }
+            other => panic!("expected a server error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn unexpected_bodies_are_decode_errors() {
+        let server = MockServer::start(|_| (200, json!({ "rooms": "not a list" }))).await;
+
+        let error = server.client(None, None).get_rooms().await.unwrap_err();
+        assert!(matches!(error, ApiError::Decode(_)), "{:?}", error);
+    }
+
+    #[tokio::test]
+    async fn unreachable_servers_are_network_errors() {
+        // A port that was free a moment ago and has nothing listening now
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let error = ApiClient::new(url, None, None)
+            .health_check()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::Network(_)), "{:?}", error);
+    }
+
+    #[tokio::test]
+    async fn expired_tokens_are_renewed_and_the_request_resent() {
+        let server = MockServer::start(|seen| match seen.path.as_str() {
+            "/api/auth/refresh" => (
+                200,
+                json!({ "token": "t2", "refreshToken": "r2", "user": user_json() }),
+            ),
+            _ if seen.authorization.as_deref() == Some("Bearer t1") => {
+                (401, json!({ "error": "authentication_failed" }))
+            }
+            _ => (200, json!({ "rooms": [] })),
+        })
+        .await;
+        let client = server.client(Some("t1"), Some("r1"));
+        let session = client.session_receiver();
+
+        assert!(client.get_rooms().await.unwrap().is_empty());
+        assert_eq!(
+            server.paths(),
+            ["GET /api/rooms", "POST /api/auth/refresh", "GET /api/rooms"]
+        );
+        assert_eq!(server.seen(1).body, json!({ "refreshToken": "r1" }));
+        assert_eq!(server.seen(2).authorization.as_deref(), Some("Bearer t2"));
+        assert_eq!(client.get_token().await.as_deref(), Some("t2"));
+        assert_eq!(
+            *session.borrow(),
+            Some(SessionEvent::Renewed {
+                token: "t2".to_string(),
+                refresh_token: "r2".to_string(),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn a_refused_refresh_expires_the_session() {
+        let server =
+            MockServer::start(|_| (401, json!({ "details": "Invalid or expired token" }))).await;
+        let client = server.client(Some("t1"), Some("r1"));
+        let session = client.session_receiver();
+
+        let error = client.get_rooms().await.unwrap_err();
+        assert!(error.is_unauthorized());
+        assert_eq!(*session.borrow(), Some(SessionEvent::Expired));
+
+        // The spent refresh token isn't tried again
+        client.get_rooms().await.unwrap_err();
+        assert_eq!(
+            server.paths(),
+            ["GET /api/rooms", "POST /api/auth/refresh", "GET /api/rooms"]
+        );
+    }
+
+    #[tokio::test]
+    async fn wrong_credentials_are_not_a_session_to_renew() {
+        let server =
+            MockServer::start(|_| (401, json!({ "details": "Invalid credentials" }))).await;
+        let client = server.client(Some("t1"), Some("r1"));
+        let session = client.session_receiver();
+
+        let error = client.login("alice", "wrong").await.unwrap_err();
+        assert_eq!(
+            error,
+            ApiError::Server {
+                status: 401,
+                message: "Invalid credentials".to_string(),
+            }
+        );
+        assert_eq!(server.paths(), ["POST /api/auth/login"]);
+        assert_eq!(*session.borrow(), None);
+    }
+}
@@ -0,0 +1,114 @@
+//! Background operation: starting at login with the window hidden, keeping
+//! the app (and with it the Tor connection and socket) running when the
+//! window is closed, a tray icon to bring it back, and desktop notifications
+//! for messages that arrive meanwhile.
+
+use auto_launch::{AutoLaunch, AutoLaunchBuilder};
+use dioxus::desktop::trayicon::menu::{Menu, MenuItem, PredefinedMenuItem};
+use dioxus::desktop::trayicon::{init_tray_icon, DioxusTrayIcon};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Passed by the login item: start with the window hidden and connect on
+/// our own, since nobody is there to press Connect
+pub const BACKGROUND_ARG: &str = "--background";
+
+const APP_NAME: &str = "TOR Chat";
+const MENU_SHOW: &str = "show";
+const MENU_QUIT: &str = "quit";
+
+static AUTO_CONNECT_PENDING: AtomicBool = AtomicBool::new(true);
+
+pub fn launched_in_background() -> bool {
+    std::env::args().any(|arg| arg == BACKGROUND_ARG)
+}
+
+/// True once per process when started by the login item, for the settings
+/// page to connect with the saved server and session
+pub fn take_auto_connect() -> bool {
+    launched_in_background() && AUTO_CONNECT_PENDING.swap(false, Ordering::Relaxed)
+}
+
+fn auto_launch() -> Result<AutoLaunch, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    AutoLaunchBuilder::new()
+        .set_app_name(APP_NAME)
+        .set_app_path(&exe.to_string_lossy())
+        .set_use_launch_agent(true)
+        .set_args(&[BACKGROUND_ARG])
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Register or remove the OS login item (autostart entry, registry Run key
+/// or launch agent, depending on the platform)
+pub fn set_start_at_login(enabled: bool) -> Result<(), String> {
+    let launcher = auto_launch()?;
+    let result = if enabled {
+        launcher.enable()
+    } else {
+        launcher.disable()
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// Tray icon with Show/Quit, the way back to a hidden window
+pub fn init_tray() -> DioxusTrayIcon {
+    let menu = Menu::new();
+    let _ = menu.append(&MenuItem::with_id(MENU_SHOW, "Show TOR Chat", true, None));
+    let _ = menu.append(&PredefinedMenuItem::separator());
+    let _ = menu.append(&MenuItem::with_id(MENU_QUIT, "Quit", true, None));
+    init_tray_icon(menu, None)
+}
+
+/// Tray menu clicks
+pub fn on_tray_menu(id: &str) {
+    match id {
+        MENU_SHOW => show_window(),
+        // Closing the window only hides it in background mode
+        MENU_QUIT => std::process::exit(0),
+        _ => {}
+    }
+}
+
+pub fn show_window() {
+    let window = dioxus::desktop::window();
+    window.set_visible(true);
+    window.set_minimized(false);
+    window.set_focus();
+}
+
+/// Whether the user can't see the chat right now
+pub fn window_hidden() -> bool {
+    !dioxus::desktop::window().is_visible()
+}
+
+/// Desktop notification for a message that arrived while the window was
+/// hidden. Names the room and sender only: notification centers keep a
+/// history, and the message text shouldn't end up in it.
+pub fn notify_message(room_name: &str, sender: &str) {
+    show(format!("{} in {}", sender, room_name), "New message");
+}
+
+/// Desktop notification for a room admin's `@room` (`scope` "room") or
+/// `@here`, with the same privacy as [`notify_message`]
+pub fn notify_mention(room_name: &str, sender: &str, scope: &str) {
+    let body = if scope == "here" {
+        "Mentioned everyone online"
+    } else {
+        "Mentioned everyone"
+    };
+    show(format!("{} in {}", sender, room_name), body);
+}
+
+fn show(summary: String, body: &'static str) {
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = notify_rust::Notification::new()
+            .appname(APP_NAME)
+            .summary(&summary)
+            .body(body)
+            .show()
+        {
+            tracing::warn!("Failed to show notification: {}", e);
+        }
+    });
+}
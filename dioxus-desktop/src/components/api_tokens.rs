@@ -0,0 +1,281 @@
+use super::copy_to_clipboard;
+use crate::models::{ApiToken, CreatedApiToken, Room};
+use crate::state::AppState;
+use crate::storage::load_config;
+use crate::time_format::TimeFormat;
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Expiry choices, in days; None never expires
+const TOKEN_EXPIRY: [(&str, Option<i64>); 4] = [
+    ("Never", None),
+    ("30 days", Some(30)),
+    ("90 days", Some(90)),
+    ("1 year", Some(365)),
+];
+
+/// The user's personal access tokens: scoped credentials for scripts and
+/// bridges, so they don't need the account's password or session. New
+/// tokens pick their scopes and, optionally, the rooms they're limited to;
+/// the secret is shown once, right after creating it.
+#[component]
+pub fn ApiTokensModal(rooms: Vec<Room>, on_close: EventHandler<()>) -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let mut tokens = use_signal(Vec::<ApiToken>::new);
+    let mut error = use_signal(|| None::<String>);
+    let mut busy = use_signal(|| false);
+    let mut created = use_signal(|| None::<CreatedApiToken>);
+
+    let mut name = use_signal(String::new);
+    let mut read = use_signal(|| true);
+    let mut post = use_signal(|| false);
+    let mut limit_rooms = use_signal(|| false);
+    let mut picked_rooms = use_signal(BTreeSet::<Uuid>::new);
+    let mut expiry = use_signal(|| 0usize);
+
+    use_hook(move || {
+        spawn(async move {
+            let api = state.read().api.clone();
+            match api.list_api_tokens().await {
+                Ok(list) => tokens.set(list),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    });
+
+    let create = move |_| {
+        let token_name = name().trim().to_string();
+        let scopes: Vec<&str> = [("read", read()), ("post", post())]
+            .into_iter()
+            .filter_map(|(scope, on)| on.then_some(scope))
+            .collect();
+        if token_name.is_empty() {
+            error.set(Some("Give the token a name".to_string()));
+            return;
+        }
+        if scopes.is_empty() {
+            error.set(Some("Pick at least one scope".to_string()));
+            return;
+        }
+        let room_ids: Option<Vec<Uuid>> =
+            limit_rooms().then(|| picked_rooms().into_iter().collect());
+        if room_ids.as_ref().is_some_and(|ids| ids.is_empty()) {
+            error.set(Some("Pick the rooms the token may use".to_string()));
+            return;
+        }
+        let expires_in_days = TOKEN_EXPIRY[expiry()].1;
+
+        busy.set(true);
+        spawn(async move {
+            let api = state.read().api.clone();
+            match api
+                .create_api_token(&token_name, &scopes, room_ids.as_deref(), expires_in_days)
+                .await
+            {
+                Ok(new_token) => {
+                    tokens.write().insert(0, new_token.api_token.clone());
+                    created.set(Some(new_token));
+                    name.set(String::new());
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            busy.set(false);
+        });
+    };
+
+    let revoke = move |token_id: Uuid| {
+        spawn(async move {
+            let api = state.read().api.clone();
+            match api.revoke_api_token(token_id).await {
+                Ok(()) => {
+                    tokens.write().retain(|t| t.id != token_id);
+                    if created().is_some_and(|c| c.api_token.id == token_id) {
+                        created.set(None);
+                    }
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    let format = TimeFormat::from_config(&load_config());
+    let now = Utc::now();
+
+    rsx! {
+        div {
+            class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    on_close.call(());
+                }
+            },
+            div {
+                class: "modal tokens-modal",
+                role: "dialog",
+                aria_modal: "true",
+                aria_labelledby: "tokens-title",
+                onclick: move |e| e.stop_propagation(),
+                h2 { id: "tokens-title", class: "modal-title", "API Tokens" }
+
+                if let Some(err) = error() {
+                    div { class: "error", role: "alert", "{err}" }
+                }
+                if let Some(new_token) = created() {
+                    div { class: "success", role: "status",
+                        "Copy \"{new_token.api_token.name}\" now; it won't be shown again."
+                    }
+                    div { class: "token-secret",
+                        code { "{new_token.token}" }
+                        button {
+                            class: "btn btn-secondary",
+                            onclick: move |_| copy_to_clipboard(&new_token.token),
+                            "Copy"
+                        }
+                    }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", "Your tokens" }
+                    if tokens().is_empty() {
+                        p { class: "hint", "No API tokens yet." }
+                    }
+                    for token in tokens() {
+                        div { key: "{token.id}", class: "token-row",
+                            div { class: "token-info",
+                                div { class: "token-name", "{token.name}" }
+                                div { class: "hint", "{reach(&token, &rooms)}" }
+                                div { class: "hint", "{usage(&token, &format, now)}" }
+                            }
+                            button {
+                                class: "btn btn-cancel token-revoke",
+                                aria_label: "Revoke {token.name}",
+                                onclick: move |_| revoke(token.id),
+                                "Revoke"
+                            }
+                        }
+                    }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "token-name", "New token" }
+                    input {
+                        id: "token-name",
+                        class: "input",
+                        r#type: "text",
+                        maxlength: "64",
+                        placeholder: "Name, e.g. IRC bridge",
+                        value: "{name}",
+                        oninput: move |e| name.set(e.value()),
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "token-read",
+                            checked: read(),
+                            onchange: move |e| read.set(e.checked()),
+                        }
+                        label { r#for: "token-read", "Read rooms and messages" }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "token-post",
+                            checked: post(),
+                            onchange: move |e| post.set(e.checked()),
+                        }
+                        label { r#for: "token-post", "Send messages and files" }
+                    }
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "token-limit-rooms",
+                            checked: limit_rooms(),
+                            onchange: move |e| limit_rooms.set(e.checked()),
+                        }
+                        label { r#for: "token-limit-rooms", "Only some rooms" }
+                    }
+                    if limit_rooms() {
+                        div { class: "token-rooms",
+                            for room in rooms.clone() {
+                                div { key: "{room.id}", class: "checkbox-group",
+                                    input {
+                                        r#type: "checkbox",
+                                        id: "token-room-{room.id}",
+                                        checked: picked_rooms().contains(&room.id),
+                                        onchange: move |e: FormEvent| {
+                                            if e.checked() {
+                                                picked_rooms.write().insert(room.id);
+                                            } else {
+                                                picked_rooms.write().remove(&room.id);
+                                            }
+                                        },
+                                    }
+                                    label { r#for: "token-room-{room.id}", "{room.name}" }
+                                }
+                            }
+                        }
+                    }
+                    select {
+                        class: "input",
+                        aria_label: "Token expiry",
+                        onchange: move |e| expiry.set(e.value().parse().unwrap_or(0)),
+                        for (i, (label, _)) in TOKEN_EXPIRY.iter().enumerate() {
+                            option { value: "{i}", selected: expiry() == i, "Expires: {label}" }
+                        }
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: busy(),
+                        onclick: create,
+                        if busy() { "Creating..." } else { "Create Token" }
+                    }
+                    p { class: "hint",
+                        "Send a token as \"Authorization: Bearer ...\". Tokens can't change your account or make other tokens; revoke one as soon as it's no longer needed."
+                    }
+                }
+
+                button {
+                    class: "btn btn-cancel",
+                    onclick: move |_| on_close.call(()),
+                    "Close"
+                }
+            }
+        }
+    }
+}
+
+/// "read + post · General, Ops": what a token may do, and where
+fn reach(token: &ApiToken, rooms: &[Room]) -> String {
+    let rooms = match &token.room_ids {
+        Some(ids) => ids
+            .iter()
+            .map(|id| {
+                rooms
+                    .iter()
+                    .find(|r| r.id == *id)
+                    .map(|r| r.name.as_str())
+                    .unwrap_or("a room you left")
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        None => "all rooms".to_string(),
+    };
+    format!("{} \u{00B7} {}", token.scopes.join(" + "), rooms)
+}
+
+/// When a token was last used, and when it expires
+fn usage(token: &ApiToken, format: &TimeFormat, now: DateTime<Utc>) -> String {
+    let used = match token.last_used_at {
+        Some(t) => format!("Last used {}", format.full(t, now)),
+        None => "Never used".to_string(),
+    };
+    match token.expires_at {
+        Some(t) if t <= now => format!("{} \u{00B7} expired", used),
+        Some(t) => format!("{} \u{00B7} expires {}", used, format.full(t, now)),
+        None => used,
+    }
+}
@@ -0,0 +1,76 @@
+use crate::a11y::on_activate;
+use crate::state::AppState;
+use base64::Engine;
+use dioxus::prelude::*;
+
+/// Image message. The bytes are fetched through the API client (so over Tor
+/// when connected via onion) and inlined as a data URL; the webview never
+/// contacts the server itself. With `auto_load` off the image waits for a click.
+#[component]
+pub fn ImageAttachment(
+    url: String,
+    name: Option<String>,
+    size: Option<u64>,
+    auto_load: bool,
+) -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let mut requested = use_signal(|| auto_load);
+
+    let mut image = use_resource(move || {
+        let url = url.clone();
+        async move {
+            if !requested() {
+                return None;
+            }
+            let api = state.read().api.clone();
+            Some(api.fetch_file(&url).await.map(|(content_type, bytes)| {
+                format!(
+                    "data:{};base64,{}",
+                    content_type,
+                    base64::engine::general_purpose::STANDARD.encode(bytes)
+                )
+            }))
+        }
+    });
+
+    let label = name.unwrap_or_else(|| "Image".to_string());
+    let size_text = size.map(format_size).unwrap_or_default();
+
+    match &*image.read() {
+        Some(Some(Ok(src))) => rsx! {
+            img { class: "message-image", src: "{src}", alt: "{label}" }
+        },
+        Some(Some(Err(e))) => rsx! {
+            div {
+                class: "message-attachment",
+                title: "{e}",
+                role: "button",
+                tabindex: "0",
+                onclick: move |_| image.restart(),
+                onkeydown: move |e| on_activate(e, || image.restart()),
+                "Image failed to load \u{2014} click to retry"
+            }
+        },
+        Some(None) => rsx! {
+            div {
+                class: "message-attachment",
+                role: "button",
+                tabindex: "0",
+                onclick: move |_| requested.set(true),
+                onkeydown: move |e| on_activate(e, || requested.set(true)),
+                "\u{1F4F7} {label} {size_text} \u{2014} click to load"
+            }
+        },
+        None => rsx! {
+            div { class: "message-attachment", role: "status", "Loading image..." }
+        },
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("({:.1} MB)", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("({} KB)", bytes.div_ceil(1024))
+    }
+}
@@ -0,0 +1,62 @@
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+use std::time::Duration;
+
+/// Expiry timers offered next to the message input (seconds, label)
+pub const EXPIRY_CHOICES: &[(i32, &str)] = &[
+    (30, "30 seconds"),
+    (300, "5 minutes"),
+    (3600, "1 hour"),
+    (86400, "1 day"),
+];
+
+/// Countdown on an expiring message. Burn-after-read messages show their
+/// timer until someone reads them; the server deletes them at `expires_at`.
+#[component]
+pub fn ExpiryBadge(
+    expires_in: i32,
+    after_read: bool,
+    expires_at: Option<DateTime<Utc>>,
+) -> Element {
+    let state = use_context::<Signal<AppState>>();
+    // expires_at is the server's time, so count down on its clock
+    let mut now = use_signal(|| state.peek().api.server_now());
+    use_future(move || async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            now.set(state.peek().api.server_now());
+        }
+    });
+
+    let text = match expires_at {
+        Some(at) => format_duration((at - now()).num_seconds().max(0)),
+        None if after_read => format!("{} after reading", format_duration(expires_in as i64)),
+        None => format_duration(expires_in as i64),
+    };
+
+    rsx! {
+        div { class: "message-expiry", title: "This message will be deleted for everyone",
+            "\u{1F525} {text}"
+        }
+    }
+}
+
+/// Compact duration with the two largest units ("45s", "4m 10s", "1d 3h")
+fn format_duration(seconds: i64) -> String {
+    let (days, hours, minutes, secs) = (
+        seconds / 86400,
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+    );
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) if secs == 0 => format!("{}m", minutes),
+        (0, 0, _) => format!("{}m {}s", minutes, secs),
+        (0, _, 0) => format!("{}h", hours),
+        (0, _, _) => format!("{}h {}m", hours, minutes),
+        (_, 0, _) => format!("{}d", days),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
@@ -0,0 +1,270 @@
+use crate::models::User;
+use crate::state::AppState;
+use crate::storage::{default_backup_path, load_identity, save_identity};
+use dioxus::prelude::*;
+use std::path::PathBuf;
+use tor_chat_common::identity::{fingerprint, MIN_PASSWORD_LEN};
+use tor_chat_common::IdentityKeys;
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut buf = [0u8; N];
+    getrandom::fill(&mut buf).expect("OS random number generator unavailable");
+    buf
+}
+
+/// The user's identity keypair: which key this device holds, creating one,
+/// and backing it up to / restoring it from a password-protected file so a
+/// reinstall doesn't lose it. `on_published` gets the new public key.
+#[component]
+pub fn IdentityKeysModal(
+    user: User,
+    on_close: EventHandler<()>,
+    on_published: EventHandler<String>,
+) -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let user_id = user.id.to_string();
+
+    let mut local = use_signal({
+        let user_id = user_id.clone();
+        move || load_identity(&user_id)
+    });
+    let mut error = use_signal(|| None::<String>);
+    let mut success = use_signal(|| None::<String>);
+    let mut busy = use_signal(|| false);
+
+    let mut export_path = use_signal({
+        let username = user.username.clone();
+        move || default_backup_path(&username).display().to_string()
+    });
+    let mut export_password = use_signal(String::new);
+    let mut export_confirm = use_signal(String::new);
+    let mut import_path = use_signal(String::new);
+    let mut import_password = use_signal(String::new);
+
+    // Save on this device and make it the account's published key
+    let adopt = move |keys: IdentityKeys, message: &'static str| {
+        let user_id = user_id.clone();
+        spawn(async move {
+            let api = state.read().api.clone();
+            match api.update_public_key(&keys.public_key).await {
+                Ok(()) => match save_identity(&user_id, &keys) {
+                    Ok(()) => {
+                        on_published.call(keys.public_key.clone());
+                        local.set(Some(keys));
+                        success.set(Some(message.to_string()));
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(format!("Failed to save identity: {}", e))),
+                },
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            busy.set(false);
+        });
+    };
+    let adopt_new = adopt.clone();
+    let adopt_imported = adopt;
+
+    let export = move |_| {
+        let Some(keys) = local() else {
+            return;
+        };
+        let password = export_password();
+        if password != export_confirm() {
+            error.set(Some("Passwords don't match".to_string()));
+            return;
+        }
+        let path = PathBuf::from(export_path().trim());
+        busy.set(true);
+        spawn(async move {
+            // The key derivation is deliberately slow; keep it off the UI thread
+            let result = tokio::task::spawn_blocking(move || {
+                let backup = keys
+                    .export(&password, random_bytes(), random_bytes())
+                    .map_err(|e| e.to_string())?;
+                std::fs::write(&path, backup)
+                    .map_err(|e| format!("Failed to write backup: {}", e))?;
+                Ok::<_, String>(path)
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+            match result {
+                Ok(path) => {
+                    success.set(Some(format!("Backup saved to {}", path.display())));
+                    error.set(None);
+                    export_password.set(String::new());
+                    export_confirm.set(String::new());
+                }
+                Err(e) => error.set(Some(e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let import = move |_| {
+        let path = PathBuf::from(import_path().trim());
+        let password = import_password();
+        busy.set(true);
+        spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                let backup = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read backup: {}", e))?;
+                IdentityKeys::import(&backup, &password).map_err(|e| e.to_string())
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+            match result {
+                Ok(keys) => {
+                    import_password.set(String::new());
+                    adopt_imported(keys, "Identity imported and published.");
+                }
+                Err(e) => {
+                    error.set(Some(e));
+                    busy.set(false);
+                }
+            }
+        });
+    };
+
+    let local_keys = local();
+    let published = local_keys
+        .as_ref()
+        .is_some_and(|keys| user.public_key.as_deref() == Some(keys.public_key.as_str()));
+    // Whose identity the chosen file holds, before asking for the password
+    let import_fingerprint = std::fs::read_to_string(import_path().trim())
+        .ok()
+        .map(|backup| IdentityKeys::backup_fingerprint(&backup));
+
+    rsx! {
+        div {
+            class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    on_close.call(());
+                }
+            },
+            div {
+                class: "modal",
+                role: "dialog",
+                aria_modal: "true",
+                aria_labelledby: "identity-title",
+                onclick: move |e| e.stop_propagation(),
+                h2 { id: "identity-title", class: "modal-title", "Identity Keys" }
+
+                if let Some(err) = error() {
+                    div { class: "error", role: "alert", "{err}" }
+                }
+                if let Some(msg) = success() {
+                    div { class: "success", role: "status", "{msg}" }
+                }
+
+                div { class: "form-group",
+                    if let Some(keys) = &local_keys {
+                        label { class: "label", "This device" }
+                        div { class: "fingerprint", "{keys.fingerprint()}" }
+                        if !published {
+                            p { class: "hint",
+                                "Your account currently publishes a different key. Other devices using it will need this one imported."
+                            }
+                        }
+                    } else {
+                        p { class: "hint", "No identity key on this device." }
+                        if let Some(server) = &user.public_key {
+                            p { class: "hint",
+                                "Account key: {fingerprint(server)}. Import your backup to use it here."
+                            }
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            disabled: busy(),
+                            onclick: move |_| {
+                                busy.set(true);
+                                adopt_new(IdentityKeys::from_seed(random_bytes()), "New identity created. Export a backup now.");
+                            },
+                            "Create New Identity"
+                        }
+                    }
+                }
+
+                if local_keys.is_some() {
+                    div { class: "form-group",
+                        label { class: "label", r#for: "export-path", "Export backup" }
+                        input {
+                            id: "export-path",
+                            class: "input",
+                            r#type: "text",
+                            value: "{export_path}",
+                            oninput: move |e| export_path.set(e.value()),
+                        }
+                        input {
+                            class: "input",
+                            r#type: "password",
+                            placeholder: "Backup password (min. {MIN_PASSWORD_LEN} characters)",
+                            aria_label: "Backup password",
+                            value: "{export_password}",
+                            oninput: move |e| export_password.set(e.value()),
+                        }
+                        input {
+                            class: "input",
+                            r#type: "password",
+                            placeholder: "Repeat password",
+                            aria_label: "Repeat backup password",
+                            value: "{export_confirm}",
+                            oninput: move |e| export_confirm.set(e.value()),
+                        }
+                        button {
+                            class: "btn btn-primary",
+                            disabled: busy(),
+                            onclick: export,
+                            if busy() { "Working..." } else { "Save Backup" }
+                        }
+                    }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "import-path", "Import backup" }
+                    input {
+                        id: "import-path",
+                        class: "input",
+                        r#type: "text",
+                        placeholder: "Path to tor-chat-identity-*.json",
+                        value: "{import_path}",
+                        oninput: move |e| import_path.set(e.value()),
+                    }
+                    match import_fingerprint {
+                        Some(Ok(fp)) => rsx! {
+                            p { class: "hint", "Backup key: {fp}" }
+                        },
+                        Some(Err(e)) => rsx! {
+                            p { class: "hint", "{e}" }
+                        },
+                        None => rsx! {},
+                    }
+                    input {
+                        class: "input",
+                        r#type: "password",
+                        placeholder: "Backup password",
+                        aria_label: "Password of the backup to import",
+                        value: "{import_password}",
+                        oninput: move |e| import_password.set(e.value()),
+                    }
+                    button {
+                        class: "btn btn-primary",
+                        disabled: busy() || import_path().trim().is_empty(),
+                        onclick: import,
+                        "Import"
+                    }
+                    p { class: "hint",
+                        "Importing replaces the key on this device and publishes it for your account."
+                    }
+                }
+
+                button {
+                    class: "btn btn-cancel",
+                    onclick: move |_| on_close.call(()),
+                    "Close"
+                }
+            }
+        }
+    }
+}
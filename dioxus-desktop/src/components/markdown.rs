@@ -0,0 +1,61 @@
+use crate::a11y::on_activate;
+use dioxus::prelude::*;
+use tor_chat_common::markdown::{self, Block, Inline};
+use tor_chat_common::MessageLink;
+
+/// Message text with its markdown applied; used for messages and for the
+/// preview above the input. Message links call `on_link` when clicked.
+#[component]
+pub fn Markdown(text: String, on_link: Option<EventHandler<MessageLink>>) -> Element {
+    let blocks = markdown::parse(&text);
+    rsx! {
+        for block in blocks {
+            match block {
+                Block::Line(line) if line.is_empty() => rsx! { div { class: "md-line", "\u{a0}" } },
+                Block::Line(line) => rsx! { div { class: "md-line", {inlines(&line, on_link)} } },
+                Block::Quote(line) => rsx! { div { class: "md-quote", {inlines(&line, on_link)} } },
+                Block::ListItem(line) => rsx! { div { class: "md-item", "\u{2022} ", {inlines(&line, on_link)} } },
+                Block::Code(code) => rsx! { pre { class: "md-pre", "{code}" } },
+            }
+        }
+    }
+}
+
+fn inlines(nodes: &[Inline], on_link: Option<EventHandler<MessageLink>>) -> Element {
+    rsx! {
+        for node in nodes {
+            match node {
+                Inline::Text(text) => rsx! { "{text}" },
+                Inline::Bold(inner) => rsx! { strong { {inlines(inner, on_link)} } },
+                Inline::Italic(inner) => rsx! { em { {inlines(inner, on_link)} } },
+                Inline::Strike(inner) => rsx! { s { {inlines(inner, on_link)} } },
+                Inline::Code(code) => rsx! { code { class: "md-code", "{code}" } },
+                Inline::Link(text, target) => {
+                    let target = target.clone();
+                    let key_target = target.clone();
+                    rsx! {
+                        span {
+                            class: "md-link",
+                            title: "Open message",
+                            role: "link",
+                            tabindex: if on_link.is_some() { "0" },
+                            onclick: move |e| {
+                                if let Some(handler) = on_link {
+                                    e.stop_propagation();
+                                    handler.call(target.clone());
+                                }
+                            },
+                            onkeydown: move |e| {
+                                if let Some(handler) = on_link {
+                                    e.stop_propagation();
+                                    on_activate(e, || handler.call(key_target.clone()));
+                                }
+                            },
+                            "{text}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use super::{ExpiryBadge, ImageAttachment, Markdown};
+use crate::a11y::on_activate;
+use crate::models::{Message, Outgoing};
+use dioxus::prelude::*;
+use tor_chat_common::{MessageContent, MessageLink};
+
+/// One message in the list. Its own component so that a change to the list
+/// only re-renders the bubbles whose props changed: everything it shows
+/// comes in as plain values, worked out by the chat page.
+#[component]
+pub fn MessageBubble(
+    msg: Message,
+    own: bool,
+    /// Picking messages to copy, and whether this one is picked
+    selecting: bool,
+    selected: bool,
+    /// Author and snippet of the message this one replies to
+    reply: Option<(String, String)>,
+    /// Short and full send time
+    time: Option<(String, String)>,
+    auto_load_images: bool,
+    /// Translation is available; `translation` is this message's, once asked
+    translate: bool,
+    translation: Option<Result<String, String>>,
+    on_menu: EventHandler<(f64, f64)>,
+    on_toggle: EventHandler<()>,
+    on_open_reply: EventHandler<()>,
+    on_link: EventHandler<MessageLink>,
+    on_translate: EventHandler<String>,
+    on_retry: EventHandler<()>,
+) -> Element {
+    let content = msg.decoded();
+    let is_image = content.is_image();
+
+    rsx! {
+        div {
+            id: "msg-{msg.id}",
+            // Focusable so the context menu key opens the menu
+            tabindex: "0",
+            aria_selected: if selecting { "{selected}" },
+            class: match (&msg.outgoing, own) {
+                (Some(Outgoing::Pending), _) => "message message-own message-pending",
+                (Some(Outgoing::Failed(_)), _) => "message message-own message-failed",
+                (None, true) => "message message-own",
+                (None, false) => "message message-other",
+            },
+            class: if selecting && selected { "message-selected" },
+            oncontextmenu: move |e: MouseEvent| {
+                e.prevent_default();
+                let point = e.client_coordinates();
+                on_menu.call((point.x, point.y));
+            },
+            onclick: move |_| {
+                if selecting {
+                    on_toggle.call(());
+                }
+            },
+            onkeydown: move |e| {
+                if selecting {
+                    on_activate(e, || on_toggle.call(()));
+                }
+            },
+            if !own {
+                div { class: "message-user",
+                    "{msg.user.as_ref().map(|u| u.username.as_str()).unwrap_or(\"Unknown\")}"
+                }
+            }
+            if let Some((author, snippet)) = reply {
+                div {
+                    class: "message-reply",
+                    title: "Jump to original",
+                    role: "button",
+                    tabindex: "0",
+                    aria_label: "Reply to {author}: {snippet}. Jump to original",
+                    onclick: move |_| on_open_reply.call(()),
+                    onkeydown: move |e| on_activate(e, || on_open_reply.call(())),
+                    div { class: "message-reply-author", "\u{21A9} {author}" }
+                    div { class: "message-reply-text", "{snippet}" }
+                }
+            }
+            match content {
+                MessageContent::File { url, name, size, .. } if is_image => rsx! {
+                    ImageAttachment {
+                        key: "{msg.id}",
+                        url,
+                        name,
+                        size,
+                        auto_load: auto_load_images,
+                    }
+                },
+                MessageContent::Plaintext(text) if translate && !msg.is_pending() => rsx! {
+                    div { class: "message-content", Markdown { text: text.clone(), on_link } }
+                    match translation {
+                        Some(Ok(translated)) => rsx! {
+                            div { class: "message-translation", "\u{1F310} {translated}" }
+                        },
+                        Some(Err(e)) => rsx! {
+                            div { class: "message-translate", "Translation failed: {e}" }
+                        },
+                        None => {
+                            let on_key = text.clone();
+                            rsx! {
+                                div {
+                                    class: "message-translate",
+                                    role: "button",
+                                    tabindex: "0",
+                                    onclick: move |_| on_translate.call(text.clone()),
+                                    onkeydown: move |e| on_activate(e, || on_translate.call(on_key.clone())),
+                                    "Translate"
+                                }
+                            }
+                        }
+                    }
+                },
+                MessageContent::Plaintext(text) => rsx! {
+                    div { class: "message-content", Markdown { text, on_link } }
+                },
+                _ => rsx! {
+                    div { class: "message-content", "{msg.display_text()}" }
+                },
+            }
+            if let Some(expires_in) = msg.expires_in {
+                ExpiryBadge {
+                    expires_in,
+                    after_read: msg.expires_after_read,
+                    expires_at: msg.expires_at,
+                }
+            }
+            if let Some(Outgoing::Failed(reason)) = &msg.outgoing {
+                div {
+                    class: "message-retry",
+                    title: "{reason}",
+                    role: "button",
+                    tabindex: "0",
+                    onclick: move |_| on_retry.call(()),
+                    onkeydown: move |e| on_activate(e, || on_retry.call(())),
+                    "Not sent \u{2014} tap to retry"
+                }
+            } else if msg.is_pending() {
+                div { class: "message-time", "Sending..." }
+            } else if let Some((time_str, full)) = time {
+                div { class: "message-time", title: "{full}", "{time_str}" }
+            }
+        }
+    }
+}
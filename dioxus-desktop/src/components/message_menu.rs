@@ -0,0 +1,71 @@
+use dioxus::prelude::*;
+
+/// Entries of the message context menu
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageAction {
+    CopyText,
+    CopyLink,
+    CopySender,
+    /// Start picking messages to copy together
+    Select,
+}
+
+/// Right-click menu for a message, at the pointer. `can_link` is false for
+/// messages not yet on the server, which have no id to link to. It takes
+/// focus when it opens so the keyboard can use it; Escape closes it.
+#[component]
+pub fn MessageMenu(
+    x: f64,
+    y: f64,
+    can_link: bool,
+    on_action: EventHandler<MessageAction>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let pick = move |action| {
+        on_action.call(action);
+        on_close.call(());
+    };
+
+    rsx! {
+        div {
+            class: "context-overlay",
+            onclick: move |_| on_close.call(()),
+            oncontextmenu: move |e| {
+                e.prevent_default();
+                on_close.call(());
+            },
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    on_close.call(());
+                }
+            },
+            div {
+                class: "context-menu",
+                style: "left: {x}px; top: {y}px;",
+                role: "menu",
+                aria_label: "Message actions",
+                onclick: move |e| e.stop_propagation(),
+                button {
+                    class: "context-item",
+                    role: "menuitem",
+                    onmounted: move |e| async move {
+                        let _ = e.set_focus(true).await;
+                    },
+                    onclick: move |_| pick(MessageAction::CopyText),
+                    "Copy text"
+                }
+                if can_link {
+                    button { class: "context-item", role: "menuitem", onclick: move |_| pick(MessageAction::CopyLink), "Copy message link" }
+                }
+                button { class: "context-item", role: "menuitem", onclick: move |_| pick(MessageAction::CopySender), "Copy sender name" }
+                button { class: "context-item", role: "menuitem", onclick: move |_| pick(MessageAction::Select), "Select messages" }
+            }
+        }
+    }
+}
+
+/// Put `text` on the system clipboard
+pub fn copy_to_clipboard(text: &str) {
+    let quoted = serde_json::to_string(text).unwrap_or_default();
+    document::eval(&format!("navigator.clipboard.writeText({quoted});"));
+}
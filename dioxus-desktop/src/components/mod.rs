@@ -0,0 +1,31 @@
+mod api_tokens;
+mod attachment;
+mod expiry;
+mod identity_keys;
+mod markdown;
+mod message_bubble;
+mod message_menu;
+mod new_room;
+mod pinned_drawer;
+mod room_notifications;
+mod server_key;
+mod session_guard;
+mod settings_transfer;
+mod status_center;
+mod update_notice;
+
+pub use api_tokens::ApiTokensModal;
+pub use attachment::ImageAttachment;
+pub use expiry::{ExpiryBadge, EXPIRY_CHOICES};
+pub use identity_keys::IdentityKeysModal;
+pub use markdown::Markdown;
+pub use message_bubble::MessageBubble;
+pub use message_menu::{copy_to_clipboard, MessageAction, MessageMenu};
+pub use new_room::NewRoomModal;
+pub use pinned_drawer::{jump_to_message, PinnedDrawer};
+pub use room_notifications::RoomNotificationsMenu;
+pub use server_key::ServerKeyWarning;
+pub use session_guard::SessionGuard;
+pub use settings_transfer::SettingsTransfer;
+pub use status_center::StatusCenter;
+pub use update_notice::UpdateModal;
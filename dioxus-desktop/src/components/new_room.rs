@@ -0,0 +1,259 @@
+use crate::a11y::on_activate;
+use crate::models::{Room, ServerLimits};
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+/// Retention periods offered for the user's own messages, in days
+const RETENTION_DAYS: [(&str, i32); 6] = [
+    ("1 day", 1),
+    ("1 week", 7),
+    ("30 days", 30),
+    ("90 days", 90),
+    ("6 months", 180),
+    ("1 year", 365),
+];
+
+/// Creating a room: name, description and (for admins) visibility, plus
+/// advanced options for its size, end-to-end encryption and how long the
+/// user's own messages in it are kept. Encryption and retention are applied
+/// right after the room is created; if either fails the dialog says what
+/// didn't stick before handing the room to `on_created`.
+#[component]
+pub fn NewRoomModal(
+    is_admin: bool,
+    on_close: EventHandler<()>,
+    on_created: EventHandler<Room>,
+) -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let mut error = use_signal(|| None::<String>);
+    let mut busy = use_signal(|| false);
+
+    let mut name = use_signal(String::new);
+    let mut description = use_signal(String::new);
+    let mut is_public = use_signal(|| is_admin);
+    let mut show_advanced = use_signal(|| false);
+    let mut max_members = use_signal(String::new);
+    let mut e2ee = use_signal(|| false);
+    let mut retention_days = use_signal(|| None::<i32>);
+    // The room, once created with options that failed to apply
+    let mut created = use_signal(|| None::<Room>);
+
+    let mut limits = use_signal(ServerLimits::default);
+    use_hook(move || {
+        spawn(async move {
+            let api = state.read().api.clone();
+            if let Ok(info) = api.get_server_info().await {
+                limits.set(info.limits);
+            }
+        });
+    });
+
+    let create = move |_| {
+        let room_name = name().trim().to_string();
+        if room_name.is_empty() {
+            error.set(Some("Room name is required".to_string()));
+            return;
+        }
+        let desc = Some(description().trim().to_string()).filter(|d| !d.is_empty());
+        let max = match max_members().trim() {
+            "" => None,
+            n => match n.parse::<i32>() {
+                Ok(n) => Some(n),
+                Err(_) => {
+                    error.set(Some("Max members must be a number".to_string()));
+                    return;
+                }
+            },
+        };
+
+        busy.set(true);
+        error.set(None);
+        spawn(async move {
+            let api = state.read().api.clone();
+            let mut room = match api
+                .create_room(&room_name, desc.as_deref(), is_public(), max)
+                .await
+            {
+                Ok(room) => room,
+                Err(e) => {
+                    error.set(Some(e.to_string()));
+                    busy.set(false);
+                    return;
+                }
+            };
+            let room_id = room.id.to_string();
+            let mut problems = Vec::new();
+
+            if e2ee() {
+                match api.enable_e2ee(&room_id).await {
+                    Ok(updated) => room = updated,
+                    Err(e) => problems.push(format!("Encryption: {}", e)),
+                }
+            }
+            if let Some(days) = retention_days() {
+                if let Err(e) = api.set_retention_rule(Some(&room_id), Some(days)).await {
+                    problems.push(format!("Retention: {}", e));
+                }
+            }
+
+            busy.set(false);
+            if problems.is_empty() {
+                on_created.call(room);
+            } else {
+                error.set(Some(format!(
+                    "The room was created, but not everything was set. {}",
+                    problems.join(". ")
+                )));
+                created.set(Some(room));
+            }
+        });
+    };
+
+    let size_hint = match (limits().default_room_members, limits().max_room_members) {
+        (Some(default), Some(cap)) => {
+            format!("Empty for the default of {}; at most {}", default, cap)
+        }
+        _ => "Empty for the server's default".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    on_close.call(());
+                }
+            },
+            div {
+                class: "modal",
+                role: "dialog",
+                aria_modal: "true",
+                aria_labelledby: "new-room-title",
+                onclick: move |e| e.stop_propagation(),
+                h2 { id: "new-room-title", class: "modal-title", "Create New Room" }
+
+                if let Some(err) = error() {
+                    div { class: "error", role: "alert", "{err}" }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "new-room-name", "Room Name" }
+                    input {
+                        id: "new-room-name",
+                        class: "input",
+                        autofocus: true,
+                        r#type: "text",
+                        maxlength: "100",
+                        placeholder: "Enter room name",
+                        value: "{name}",
+                        oninput: move |e| name.set(e.value()),
+                    }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "new-room-desc", "Description (optional)" }
+                    input {
+                        id: "new-room-desc",
+                        class: "input",
+                        r#type: "text",
+                        maxlength: "500",
+                        placeholder: "Enter description",
+                        value: "{description}",
+                        oninput: move |e| description.set(e.value()),
+                    }
+                }
+
+                if is_admin {
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "new-room-public",
+                            checked: is_public(),
+                            onchange: move |e| is_public.set(e.checked()),
+                        }
+                        label { r#for: "new-room-public", "Public room (visible to all users)" }
+                    }
+                } else {
+                    div {
+                        style: "font-size: 12px; color: #888; margin-bottom: 15px;",
+                        "Room will be private. Only admins can create public rooms."
+                    }
+                }
+
+                div {
+                    class: "advanced-toggle",
+                    role: "button",
+                    tabindex: "0",
+                    aria_expanded: "{show_advanced()}",
+                    onclick: move |_| show_advanced.set(!show_advanced()),
+                    onkeydown: move |e| on_activate(e, || show_advanced.set(!show_advanced())),
+                    if show_advanced() { "\u{25BE} Advanced options" } else { "\u{25B8} Advanced options" }
+                }
+                if show_advanced() {
+                    div { class: "form-group",
+                        label { class: "label", r#for: "new-room-size", "Max members" }
+                        input {
+                            id: "new-room-size",
+                            class: "input",
+                            r#type: "number",
+                            min: "2",
+                            max: limits().max_room_members.map(|cap| cap.to_string()),
+                            value: "{max_members}",
+                            oninput: move |e| max_members.set(e.value()),
+                        }
+                        p { class: "hint", "{size_hint}" }
+                    }
+
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "new-room-e2ee",
+                            checked: e2ee(),
+                            onchange: move |e| e2ee.set(e.checked()),
+                        }
+                        label { r#for: "new-room-e2ee", "End-to-end encrypted" }
+                    }
+                    p { class: "hint",
+                        "The server can't read, search or translate messages in the room. This can't be turned off later."
+                    }
+
+                    div { class: "form-group",
+                        label { class: "label", r#for: "new-room-retention", "Delete my messages after" }
+                        select {
+                            id: "new-room-retention",
+                            class: "input",
+                            onchange: move |e| retention_days.set(e.value().parse().ok()),
+                            option { value: "", selected: retention_days().is_none(), "Keep forever" }
+                            for (label, days) in RETENTION_DAYS {
+                                option { value: "{days}", selected: retention_days() == Some(days), "{label}" }
+                            }
+                        }
+                        p { class: "hint", "Only your own messages, deleted for everyone once they are this old." }
+                    }
+                }
+
+                if let Some(room) = created() {
+                    button {
+                        class: "btn btn-primary",
+                        onclick: move |_| on_created.call(room.clone()),
+                        "Open Room"
+                    }
+                } else {
+                    button {
+                        class: "btn btn-primary",
+                        disabled: busy(),
+                        onclick: create,
+                        if busy() { "Creating..." } else { "Create Room" }
+                    }
+                }
+
+                button {
+                    class: "btn btn-cancel",
+                    onclick: move |_| on_close.call(()),
+                    "Cancel"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::a11y::on_activate;
+use crate::models::{Message, RoomFile};
+use crate::state::AppState;
+use crate::storage::load_config;
+use crate::time_format::TimeFormat;
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+/// Recent files shown in the drawer; older ones are reachable from the web client
+const FILES_LIMIT: u32 = 30;
+
+/// Side drawer with the room's pinned messages (from the loaded history) and
+/// its shared files. Clicking an entry calls `on_open` with its message.
+#[component]
+pub fn PinnedDrawer(
+    room_id: Uuid,
+    messages: Signal<Vec<Message>>,
+    on_open: EventHandler<Uuid>,
+) -> Element {
+    let state = use_context::<Signal<AppState>>();
+
+    let files = use_resource(move || async move {
+        let api = state.read().api.clone();
+        api.get_room_files(&room_id.to_string(), FILES_LIMIT).await
+    });
+
+    let mut pinned: Vec<Message> = messages
+        .read()
+        .iter()
+        .filter(|m| m.pinned_by.is_some())
+        .cloned()
+        .collect();
+    pinned.sort_by_key(|m| std::cmp::Reverse(m.pinned_at));
+
+    rsx! {
+        aside { class: "members-panel", aria_label: "Pinned messages and files",
+            div { class: "members-header",
+                span { style: "font-weight: 600;", "Pinned" }
+            }
+            div { style: "flex: 1; overflow-y: auto;",
+                if pinned.is_empty() {
+                    div { class: "drawer-empty", "No pinned messages" }
+                }
+                for msg in pinned {
+                    {
+                        let id = msg.id;
+                        let author = msg.user.as_ref().map(|u| u.username.clone()).unwrap_or_default();
+                        let preview = msg.decoded().preview(80);
+                        rsx! {
+                            div {
+                                class: "drawer-item",
+                                role: "button",
+                                tabindex: "0",
+                                onclick: move |_| on_open.call(id),
+                                onkeydown: move |e| on_activate(e, || on_open.call(id)),
+                                div { class: "member-role", "{author}" }
+                                div { class: "drawer-text", "{preview}" }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "members-header drawer-section",
+                    span { style: "font-weight: 600;", "Files" }
+                }
+                match &*files.read() {
+                    None => rsx! {
+                        div { class: "drawer-empty", "Loading..." }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "drawer-empty", "{e}" }
+                    },
+                    Some(Ok(list)) if list.is_empty() => rsx! {
+                        div { class: "drawer-empty", "No files shared yet" }
+                    },
+                    Some(Ok(list)) => rsx! {
+                        for file in list.clone() {
+                            FileItem { key: "{file.message_id}", file, on_open }
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn FileItem(file: RoomFile, on_open: EventHandler<Uuid>) -> Element {
+    let id = file.message_id;
+    let icon = match file.message_type.as_str() {
+        "image" => "\u{1F5BC}",
+        "video" => "\u{1F3AC}",
+        "audio" => "\u{1F3B5}",
+        _ => "\u{1F4C4}",
+    };
+    let name = file.display_name();
+    let time_format = use_hook(|| TimeFormat::from_config(&load_config()));
+    let meta = [
+        file.user.as_ref().map(|u| u.username.clone()),
+        file.created_at.map(|t| time_format.date(t)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" \u{00B7} ");
+
+    rsx! {
+        div {
+            class: "drawer-item",
+            title: "{name}",
+            role: "button",
+            tabindex: "0",
+            onclick: move |_| on_open.call(id),
+            onkeydown: move |e| on_activate(e, || on_open.call(id)),
+            div { class: "drawer-text", "{icon} {name}" }
+            div { class: "member-role", "{meta}" }
+        }
+    }
+}
+
+/// Scroll the message list to `msg-{id}` and flash it. Retries briefly so
+/// a message that was just loaded has time to render.
+pub fn jump_to_message(id: Uuid) {
+    document::eval(&format!(
+        "let tries = 0;
+         const jump = () => {{
+             const el = document.getElementById('msg-{id}');
+             if (!el) {{
+                 if (++tries < 10) setTimeout(jump, 100);
+                 return;
+             }}
+             el.scrollIntoView({{ behavior: 'smooth', block: 'center' }});
+             el.classList.add('message-highlight');
+             setTimeout(() => el.classList.remove('message-highlight'), 1500);
+         }};
+         jump();"
+    ));
+}
@@ -0,0 +1,201 @@
+use crate::models::{NotificationSettings, Room};
+use crate::sound;
+use crate::state::AppState;
+use crate::storage::{load_config, room_sound, save_room_sound, RoomSound};
+use crate::time_format::TimeFormat;
+use chrono::{Duration, Utc};
+use dioxus::prelude::*;
+
+const LEVELS: [(&str, &str); 3] = [
+    ("all", "All messages"),
+    ("mentions", "Only mentions"),
+    ("none", "Nothing"),
+];
+
+/// Mute presets, in minutes
+const MUTE_FOR: [(&str, i64); 5] = [
+    ("15 minutes", 15),
+    ("1 hour", 60),
+    ("8 hours", 8 * 60),
+    ("1 day", 24 * 60),
+    ("1 week", 7 * 24 * 60),
+];
+
+/// Right-click menu of a sidebar room with its notification settings. Level
+/// and mute are saved on the server (so other devices follow) and reported
+/// through `on_change`; the sound is this device's own.
+#[component]
+pub fn RoomNotificationsMenu(
+    room: Room,
+    x: f64,
+    y: f64,
+    on_change: EventHandler<NotificationSettings>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let mut settings = use_signal(|| room.notifications.clone());
+    let mut sound = use_signal(|| room_sound(room.id));
+    let mut sound_file = use_signal(|| sound.peek().file.clone().unwrap_or_default());
+    let mut error = use_signal(|| None::<String>);
+    let room_id = room.id;
+
+    let save = move |updated: NotificationSettings| {
+        spawn(async move {
+            error.set(None);
+            let api = state.read().api.clone();
+            match api
+                .update_room_notifications(&room_id.to_string(), &updated)
+                .await
+            {
+                Ok(saved) => {
+                    settings.set(saved.clone());
+                    on_change.call(saved);
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    let mut set_sound = move |updated: RoomSound| {
+        save_room_sound(room_id, updated.clone());
+        sound.set(updated);
+    };
+
+    let apply_file = move |_| {
+        let path = sound_file().trim().to_string();
+        let file = if path.is_empty() {
+            None
+        } else if let Err(e) = sound::check_file(&path) {
+            error.set(Some(e));
+            return;
+        } else {
+            Some(path)
+        };
+        error.set(None);
+        set_sound(RoomSound { file, ..sound() });
+    };
+
+    let current = settings();
+    let now = Utc::now();
+    let muted_until = current
+        .muted_until
+        .filter(|_| current.is_muted(now))
+        .map(|t| TimeFormat::from_config(&load_config()).full(t, now));
+
+    rsx! {
+        div {
+            class: "context-overlay",
+            onclick: move |_| on_close.call(()),
+            oncontextmenu: move |e| {
+                e.prevent_default();
+                on_close.call(());
+            },
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    on_close.call(());
+                }
+            },
+            div {
+                class: "context-menu notify-menu",
+                style: "left: {x}px; top: {y}px;",
+                role: "menu",
+                aria_label: "Notifications for {room.name}",
+                onclick: move |e| e.stop_propagation(),
+
+                div { class: "context-heading", "Notify me about" }
+                for (i, (level, label)) in LEVELS.into_iter().enumerate() {
+                    button {
+                        class: "context-item",
+                        role: "menuitemradio",
+                        aria_checked: "{current.level == level}",
+                        onmounted: move |e| async move {
+                            if i == 0 {
+                                let _ = e.set_focus(true).await;
+                            }
+                        },
+                        onclick: move |_| {
+                            save(NotificationSettings {
+                                level: level.to_string(),
+                                ..settings()
+                            })
+                        },
+                        span { class: "context-check", if current.level == level { "\u{2713}" } }
+                        "{label}"
+                    }
+                }
+
+                div { class: "context-separator", role: "separator" }
+                if let Some(until) = muted_until {
+                    button {
+                        class: "context-item",
+                        role: "menuitem",
+                        onclick: move |_| {
+                            save(NotificationSettings {
+                                muted_until: None,
+                                ..settings()
+                            })
+                        },
+                        span { class: "context-check" }
+                        "Unmute (muted until {until})"
+                    }
+                } else {
+                    div { class: "context-heading", "Mute for" }
+                    for (label, minutes) in MUTE_FOR {
+                        button {
+                            class: "context-item",
+                            role: "menuitem",
+                            onclick: move |_| {
+                                save(NotificationSettings {
+                                    muted_until: Some(Utc::now() + Duration::minutes(minutes)),
+                                    ..settings()
+                                })
+                            },
+                            span { class: "context-check" }
+                            "{label}"
+                        }
+                    }
+                }
+
+                div { class: "context-separator", role: "separator" }
+                button {
+                    class: "context-item",
+                    role: "menuitemcheckbox",
+                    aria_checked: "{!sound().silent}",
+                    onclick: move |_| {
+                        let current = sound();
+                        set_sound(RoomSound {
+                            silent: !current.silent,
+                            ..current
+                        })
+                    },
+                    span { class: "context-check", if !sound().silent { "\u{2713}" } }
+                    "Play a sound"
+                }
+                div { class: "notify-sound",
+                    input {
+                        class: "input",
+                        aria_label: "Sound file for this room",
+                        placeholder: "Sound file (default chime if empty)",
+                        value: "{sound_file}",
+                        oninput: move |e| sound_file.set(e.value()),
+                        onchange: apply_file,
+                    }
+                    button {
+                        class: "btn btn-secondary",
+                        title: "Play this room's sound",
+                        onclick: move |_| {
+                            sound::play(&RoomSound {
+                                silent: false,
+                                ..sound()
+                            })
+                        },
+                        "\u{25B6}"
+                    }
+                }
+                if let Some(err) = error() {
+                    div { class: "notify-error", role: "alert", "{err}" }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::storage::ServerKeyChange;
+use dioxus::prelude::*;
+
+/// Warning that the server no longer proves it's the one first connected to.
+/// Nothing is sent to it until the user trusts the new key.
+#[component]
+pub fn ServerKeyWarning(change: ServerKeyChange, on_trust: EventHandler<()>) -> Element {
+    let previous = change.previous_fingerprint();
+    let current = change.current_fingerprint();
+    let what = if change.current.is_some() {
+        "identity key changed"
+    } else {
+        "stopped signing with its identity key"
+    };
+
+    rsx! {
+        div { class: "key-change server-key-change", role: "alert",
+            div { class: "key-change-title", "\u{26A0} {change.origin} {what}" }
+            p {
+                "This happens when the server is reinstalled without its old key, but it could also mean someone took over its onion address or is intercepting a clearnet connection. Sending is blocked until you check the new fingerprint with the server's admin over another channel."
+            }
+            div { class: "key-change-fingerprints",
+                div { "Pinned: {previous}" }
+                div { "Now: {current}" }
+            }
+            button {
+                class: "btn btn-primary",
+                onclick: move |_| on_trust.call(()),
+                "Trust New Key"
+            }
+        }
+    }
+}
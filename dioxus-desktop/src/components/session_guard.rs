@@ -0,0 +1,52 @@
+use super::StatusCenter;
+use crate::api::SessionEvent;
+use crate::state::AppState;
+use crate::storage::{load_config, save_config};
+use crate::Route;
+use dioxus::prelude::*;
+
+/// Layout around every page that acts on the API client's session events:
+/// renewed tokens are saved, and an expired session goes back to Login. The
+/// chat keeps its place in `AppState::chat_place`, so logging in again returns
+/// to the same room with the unsent draft. Problems reported on any page are
+/// listed by the `StatusCenter` it shows.
+#[component]
+pub fn SessionGuard() -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let nav = use_navigator();
+
+    use_future(move || async move {
+        let mut session_rx = state.read().api.session_receiver();
+        while session_rx.changed().await.is_ok() {
+            let event = session_rx.borrow_and_update().clone();
+            match event {
+                Some(SessionEvent::Renewed {
+                    token,
+                    refresh_token,
+                }) => {
+                    let mut config = load_config();
+                    config.token = Some(token);
+                    config.refresh_token = Some(refresh_token);
+                    save_config(&config);
+                }
+                Some(SessionEvent::Expired) => {
+                    tracing::info!("Session expired, logging in again");
+                    state.read().clear_auth().await;
+
+                    let mut config = load_config();
+                    config.token = None;
+                    config.refresh_token = None;
+                    save_config(&config);
+
+                    nav.push(Route::Login {});
+                }
+                None => {}
+            }
+        }
+    });
+
+    rsx! {
+        Outlet::<Route> {}
+        StatusCenter {}
+    }
+}
@@ -0,0 +1,188 @@
+use crate::storage::{
+    default_settings_path, export_settings, import_settings, load_config, save_config,
+    settings_include_session,
+};
+use dioxus::prelude::*;
+use std::path::PathBuf;
+use tor_chat_common::identity::MIN_PASSWORD_LEN;
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut buf = [0u8; N];
+    getrandom::fill(&mut buf).expect("OS random number generator unavailable");
+    buf
+}
+
+/// Exporting the app's settings (server, pinned server keys, network and
+/// display preferences) to a file and importing them on another machine.
+/// The session is only exported when asked for, sealed under a passphrase;
+/// identity keys have their own backup. `on_imported` fires once the
+/// imported settings are saved.
+#[component]
+pub fn SettingsTransfer(on_imported: EventHandler<()>) -> Element {
+    let mut error = use_signal(|| None::<String>);
+    let mut success = use_signal(|| None::<String>);
+    let mut busy = use_signal(|| false);
+
+    let mut export_path = use_signal(|| default_settings_path().display().to_string());
+    let mut include_session = use_signal(|| false);
+    let mut export_passphrase = use_signal(String::new);
+    let mut export_confirm = use_signal(String::new);
+    let mut import_path = use_signal(String::new);
+    let mut import_passphrase = use_signal(String::new);
+
+    let export = move |_| {
+        let passphrase = include_session().then(|| export_passphrase());
+        if passphrase.as_ref().is_some_and(|p| *p != export_confirm()) {
+            error.set(Some("Passphrases don't match".to_string()));
+            return;
+        }
+        let path = PathBuf::from(export_path().trim());
+        busy.set(true);
+        spawn(async move {
+            // Sealing the session is deliberately slow; keep it off the UI thread
+            let result = tokio::task::spawn_blocking(move || {
+                let file = export_settings(
+                    &load_config(),
+                    passphrase.as_deref(),
+                    random_bytes(),
+                    random_bytes(),
+                )?;
+                std::fs::write(&path, file)
+                    .map_err(|e| format!("Failed to write settings: {}", e))?;
+                Ok::<_, String>(path)
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+            match result {
+                Ok(path) => {
+                    success.set(Some(format!("Settings saved to {}", path.display())));
+                    error.set(None);
+                    export_passphrase.set(String::new());
+                    export_confirm.set(String::new());
+                }
+                Err(e) => error.set(Some(e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    // Whether the chosen file has a session to unlock
+    let import_has_session = std::fs::read_to_string(import_path().trim())
+        .is_ok_and(|file| settings_include_session(&file));
+
+    let import = move |_| {
+        let path = PathBuf::from(import_path().trim());
+        let passphrase = Some(import_passphrase()).filter(|p| !p.is_empty());
+        busy.set(true);
+        spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                let file = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("Failed to read settings: {}", e))?;
+                let config = import_settings(&file, passphrase.as_deref(), &load_config())?;
+                save_config(&config);
+                Ok::<_, String>(config.token.is_some())
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+            match result {
+                Ok(signed_in) => {
+                    success.set(Some(if signed_in {
+                        "Settings imported. Connect to use them.".to_string()
+                    } else {
+                        "Settings imported. Connect, then sign in.".to_string()
+                    }));
+                    error.set(None);
+                    import_passphrase.set(String::new());
+                    on_imported.call(());
+                }
+                Err(e) => error.set(Some(e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    rsx! {
+        if let Some(err) = error() {
+            div { class: "error", role: "alert", "{err}" }
+        }
+        if let Some(msg) = success() {
+            div { class: "success", role: "status", "{msg}" }
+        }
+
+        div { class: "form-group",
+            label { class: "label", r#for: "settings-export-path", "Export settings" }
+            input {
+                id: "settings-export-path",
+                class: "input",
+                r#type: "text",
+                value: "{export_path}",
+                oninput: move |e| export_path.set(e.value()),
+            }
+            div { class: "checkbox-group",
+                input {
+                    r#type: "checkbox",
+                    id: "settings-include-session",
+                    checked: include_session(),
+                    onchange: move |e| include_session.set(e.checked()),
+                }
+                label { r#for: "settings-include-session", "Include my sign-in, protected by a passphrase" }
+            }
+            if include_session() {
+                input {
+                    class: "input",
+                    r#type: "password",
+                    placeholder: "Passphrase (min. {MIN_PASSWORD_LEN} characters)",
+                    aria_label: "Passphrase for the sign-in",
+                    value: "{export_passphrase}",
+                    oninput: move |e| export_passphrase.set(e.value()),
+                }
+                input {
+                    class: "input",
+                    r#type: "password",
+                    placeholder: "Repeat passphrase",
+                    aria_label: "Repeat passphrase",
+                    value: "{export_confirm}",
+                    oninput: move |e| export_confirm.set(e.value()),
+                }
+            }
+            button {
+                class: "btn btn-secondary",
+                disabled: busy(),
+                onclick: export,
+                if busy() { "Working..." } else { "Export" }
+            }
+            p { class: "hint",
+                "Without a passphrase the file holds no sign-in, but it still shows which server you use. Back up your identity keys separately."
+            }
+        }
+
+        div { class: "form-group",
+            label { class: "label", r#for: "settings-import-path", "Import settings" }
+            input {
+                id: "settings-import-path",
+                class: "input",
+                r#type: "text",
+                placeholder: "Path to tor-chat-settings.json",
+                value: "{import_path}",
+                oninput: move |e| import_path.set(e.value()),
+            }
+            if import_has_session {
+                input {
+                    class: "input",
+                    r#type: "password",
+                    placeholder: "Passphrase, to import the sign-in too",
+                    aria_label: "Passphrase of the settings file",
+                    value: "{import_passphrase}",
+                    oninput: move |e| import_passphrase.set(e.value()),
+                }
+            }
+            button {
+                class: "btn btn-secondary",
+                disabled: busy() || import_path().trim().is_empty(),
+                onclick: import,
+                "Import"
+            }
+            p { class: "hint", "Importing replaces all settings on this device." }
+        }
+    }
+}
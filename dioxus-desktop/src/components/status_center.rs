@@ -0,0 +1,121 @@
+use crate::issues::{Fix, Issues, Source};
+use crate::state::AppState;
+use crate::storage::load_config;
+use crate::time_format::TimeFormat;
+use crate::tor_manager::TorStatus;
+use crate::Route;
+use dioxus::prelude::*;
+
+/// Corner button counting the app's current problems, opening a list of
+/// them with what to do about each: retry, reconnect, restart Tor or log in
+/// again. Also reports Tor failing, and clears that once Tor is back.
+#[component]
+pub fn StatusCenter() -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let issues = use_context::<Issues>();
+    let nav = use_navigator();
+    let mut open = use_signal(|| false);
+    let mut tor_status = use_signal(|| state.peek().tor_manager.current_status());
+
+    use_future(move || async move {
+        let mut status_rx = state.read().tor_manager.status_receiver();
+        while status_rx.changed().await.is_ok() {
+            let status = status_rx.borrow_and_update().clone();
+            match &status {
+                TorStatus::Error(e) => issues.report(Source::Tor, e, Some(Fix::RestartTor)),
+                TorStatus::Connected { .. } | TorStatus::External => issues.resolve(Source::Tor),
+                _ => {}
+            }
+            tor_status.set(status);
+        }
+    });
+
+    let mut run_fix = move |id: u64, fix: Fix| {
+        issues.dismiss(id);
+        match fix {
+            Fix::Retry(run) | Fix::Reconnect(run) => run(),
+            Fix::RestartTor => {
+                spawn(async move {
+                    let app = state.read().clone();
+                    if let Err(e) = app.restart_tor().await {
+                        issues.report(Source::Tor, format!("Tor restart failed: {}", e), None);
+                    }
+                });
+            }
+            Fix::LogIn => {
+                open.set(false);
+                nav.push(Route::Login {});
+            }
+        }
+    };
+
+    let list = issues.list();
+    if list.is_empty() {
+        return rsx! {};
+    }
+    let format = TimeFormat::from_config(&load_config());
+    let now = chrono::Utc::now();
+
+    rsx! {
+        button {
+            class: "status-toggle",
+            aria_expanded: "{open()}",
+            title: "Problems and how to fix them",
+            onclick: move |_| open.set(!open()),
+            if list.len() == 1 { "\u{26A0} 1 problem" } else { "\u{26A0} {list.len()} problems" }
+        }
+
+        if open() {
+            aside {
+                class: "status-panel",
+                role: "dialog",
+                aria_label: "Problems",
+                onkeydown: move |e| {
+                    if e.key() == Key::Escape {
+                        open.set(false);
+                    }
+                },
+                div { class: "status-header",
+                    span { style: "font-weight: 600;", "Problems" }
+                    button {
+                        class: "btn btn-secondary btn-small",
+                        onclick: move |_| {
+                            issues.clear();
+                            open.set(false);
+                        },
+                        "Clear all"
+                    }
+                }
+                if let TorStatus::Bootstrapping(pct) = tor_status() {
+                    div { class: "status-note", "Starting Tor... {pct}%" }
+                }
+                for issue in list.into_iter().rev() {
+                    div { key: "{issue.id}", class: "status-item",
+                        div { class: "status-meta",
+                            span { class: "status-source", "{issue.source.label()}" }
+                            span { "{format.message_time(issue.at, now)}" }
+                            if issue.count > 1 {
+                                span { "\u{00D7}{issue.count}" }
+                            }
+                        }
+                        div { class: "status-text", "{issue.text}" }
+                        div { class: "status-actions",
+                            if let Some(fix) = issue.fix.clone() {
+                                button {
+                                    class: "btn btn-primary btn-small",
+                                    onclick: move |_| run_fix(issue.id, fix.clone()),
+                                    "{fix.label()}"
+                                }
+                            }
+                            button {
+                                class: "btn btn-secondary btn-small",
+                                onclick: move |_| issues.dismiss(issue.id),
+                                "Dismiss"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::updates::UpdateInfo;
+use dioxus::prelude::*;
+
+/// A newer release is available. The download link is shown for copying
+/// rather than opened: the system browser would fetch it without Tor.
+#[component]
+pub fn UpdateModal(update: UpdateInfo, on_close: EventHandler<()>) -> Element {
+    rsx! {
+        div {
+            class: "modal-overlay",
+            onclick: move |_| on_close.call(()),
+            onkeydown: move |e| {
+                if e.key() == Key::Escape {
+                    on_close.call(());
+                }
+            },
+            div {
+                class: "modal",
+                role: "dialog",
+                aria_modal: "true",
+                aria_labelledby: "update-title",
+                onclick: move |e| e.stop_propagation(),
+                h2 { id: "update-title", class: "modal-title", "Update available: {update.version}" }
+                if !update.changelog.is_empty() {
+                    div { class: "changelog", "{update.changelog}" }
+                }
+                div { class: "form-group",
+                    label { class: "label", r#for: "update-url", "Download" }
+                    input {
+                        id: "update-url",
+                        class: "input",
+                        r#type: "text",
+                        readonly: true,
+                        value: "{update.download_url}",
+                    }
+                    p { class: "hint",
+                        "Open this link in Tor Browser so the download doesn't reveal your IP address."
+                    }
+                }
+                button {
+                    class: "btn btn-cancel",
+                    onclick: move |_| on_close.call(()),
+                    "Close"
+                }
+            }
+        }
+    }
+}
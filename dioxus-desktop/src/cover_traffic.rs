@@ -0,0 +1,38 @@
+//! Opt-in cover traffic. While enabled, the chat page sends padded no-op
+//! requests (`POST /api/cover`) at random intervals, so someone watching the
+//! connection can't line up bursts of traffic with the user's real activity.
+
+use std::time::Duration;
+
+/// Average gap between cover requests
+const MEAN_DELAY_SECS: f64 = 20.0;
+const MIN_DELAY: Duration = Duration::from_secs(2);
+const MAX_DELAY: Duration = Duration::from_secs(120);
+/// Upper bound of the random filler added to each request
+const MAX_REQUEST_PADDING: usize = 4 * 1024;
+/// Upper bound of the filler asked for in the reply (the server caps it too)
+const MAX_RESPONSE_PADDING: usize = 16 * 1024;
+
+fn random_u32() -> u32 {
+    let mut buf = [0u8; 4];
+    getrandom::fill(&mut buf).expect("OS random number generator unavailable");
+    u32::from_le_bytes(buf)
+}
+
+/// Time until the next cover request. Exponentially distributed, like the
+/// gaps between independent events, so there's no rhythm to filter out.
+pub fn next_delay() -> Duration {
+    // Uniform in (0, 1]; never 0 so ln() stays finite
+    let uniform = (random_u32() as f64 + 1.0) / (u32::MAX as f64 + 1.0);
+    Duration::from_secs_f64(-MEAN_DELAY_SECS * uniform.ln()).clamp(MIN_DELAY, MAX_DELAY)
+}
+
+/// Filler for the request body, of random length
+pub fn padding() -> String {
+    "0".repeat(random_u32() as usize % (MAX_REQUEST_PADDING + 1))
+}
+
+/// Random reply size to ask the server for
+pub fn response_size() -> usize {
+    random_u32() as usize % (MAX_RESPONSE_PADDING + 1)
+}
@@ -0,0 +1,163 @@
+use crate::api::ApiError;
+use chrono::{DateTime, Utc};
+use dioxus::prelude::*;
+use std::rc::Rc;
+
+/// Problems kept at once; past this the oldest are dropped
+const MAX_ISSUES: usize = 30;
+
+/// Where a problem came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Api,
+    Socket,
+    Tor,
+}
+
+impl Source {
+    pub fn label(self) -> &'static str {
+        match self {
+            Source::Api => "Server",
+            Source::Socket => "Live updates",
+            Source::Tor => "Tor",
+        }
+    }
+}
+
+/// What the user can do about a problem
+#[derive(Clone)]
+pub enum Fix {
+    /// Run the failed operation again
+    Retry(Rc<dyn Fn()>),
+    /// Open the live connection to the server again
+    Reconnect(Rc<dyn Fn()>),
+    /// Start the embedded Tor again, with new circuits
+    RestartTor,
+    /// Go to the login page
+    LogIn,
+}
+
+impl Fix {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Fix::Retry(_) => "Retry",
+            Fix::Reconnect(_) => "Reconnect",
+            Fix::RestartTor => "Restart Tor",
+            Fix::LogIn => "Log in again",
+        }
+    }
+
+    /// Retries and reconnects call back into the page that reported them
+    fn is_local(&self) -> bool {
+        matches!(self, Fix::Retry(_) | Fix::Reconnect(_))
+    }
+}
+
+#[derive(Clone)]
+pub struct Issue {
+    pub id: u64,
+    pub source: Source,
+    pub text: String,
+    /// Last time it happened
+    pub at: DateTime<Utc>,
+    /// Times it happened since it was first reported
+    pub count: u32,
+    pub fix: Option<Fix>,
+}
+
+/// Problems the user should know about (failed requests, a dropped
+/// connection, Tor failing), shared through context so any page can report
+/// one and `StatusCenter` lists them with their fix
+#[derive(Clone, Copy)]
+pub struct Issues {
+    list: Signal<Vec<Issue>>,
+    next_id: Signal<u64>,
+}
+
+impl Default for Issues {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Issues {
+    pub fn new() -> Self {
+        Self {
+            list: Signal::new(Vec::new()),
+            next_id: Signal::new(0),
+        }
+    }
+
+    pub fn list(&self) -> Vec<Issue> {
+        self.list.read().clone()
+    }
+
+    /// Add a problem. The same one again moves to the end with its count
+    /// raised and the newest fix.
+    pub fn report(&self, source: Source, text: impl Into<String>, fix: Option<Fix>) {
+        let text = text.into();
+        tracing::warn!("{}: {}", source.label(), text);
+        let (mut list, mut next_id) = (self.list, self.next_id);
+        let mut list = list.write();
+
+        let count = match list
+            .iter()
+            .position(|i| i.source == source && i.text == text)
+        {
+            Some(pos) => list.remove(pos).count + 1,
+            None => 1,
+        };
+        let id = *next_id.peek() + 1;
+        next_id.set(id);
+        list.push(Issue {
+            id,
+            source,
+            text,
+            at: Utc::now(),
+            count,
+            fix,
+        });
+        let excess = list.len().saturating_sub(MAX_ISSUES);
+        list.drain(..excess);
+    }
+
+    /// Report a failed request. A refused session suggests logging in
+    /// again; `retry` is only offered when trying again could help, that is
+    /// when the server didn't answer or had an error of its own.
+    pub fn report_api(&self, what: &str, e: &ApiError, retry: Option<Rc<dyn Fn()>>) {
+        let fix = match e {
+            _ if e.is_unauthorized() => Some(Fix::LogIn),
+            ApiError::Network(_) => retry.map(Fix::Retry),
+            ApiError::Server { status, .. } if *status >= 500 => retry.map(Fix::Retry),
+            _ => None,
+        };
+        self.report(Source::Api, format!("{}: {}", what, e), fix);
+    }
+
+    /// Drop the problems from `source`, once it works again
+    pub fn resolve(&self, source: Source) {
+        let mut list = self.list;
+        list.write().retain(|i| i.source != source);
+    }
+
+    pub fn dismiss(&self, id: u64) {
+        let mut list = self.list;
+        list.write().retain(|i| i.id != id);
+    }
+
+    pub fn clear(&self) {
+        let mut list = self.list;
+        list.write().clear();
+    }
+
+    /// Take back the retries and reconnects a page offered, when it closes;
+    /// the problems themselves stay listed
+    pub fn withdraw_local_fixes(&self) {
+        let mut list = self.list;
+        for issue in list.write().iter_mut() {
+            if issue.fix.as_ref().is_some_and(Fix::is_local) {
+                issue.fix = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,486 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tor_chat_common::{BuildInfo, MessageContent, NoKeys};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct User {
+    pub id: Uuid,
+    pub username: String,
+    #[serde(rename = "displayName", alias = "display_name")]
+    pub display_name: Option<String>,
+    pub avatar: Option<String>,
+    #[serde(rename = "publicKey", alias = "public_key")]
+    pub public_key: Option<String>,
+    #[serde(rename = "isOnline", alias = "is_online", default)]
+    pub is_online: bool,
+    #[serde(rename = "lastSeen", alias = "last_seen")]
+    pub last_seen: Option<DateTime<Utc>>,
+    #[serde(rename = "isAdmin", alias = "is_admin", default)]
+    pub is_admin: bool,
+    #[serde(rename = "isBanned", alias = "is_banned", default)]
+    pub is_banned: bool,
+    #[serde(rename = "createdAt", alias = "created_at")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Set for guest sessions, which end (and can't be renewed) then
+    #[serde(rename = "guestExpiresAt", default)]
+    pub guest_expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Room {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "isPublic", alias = "is_public", default)]
+    pub is_public: bool,
+    #[serde(rename = "creatorId", alias = "creator_id")]
+    pub creator_id: Option<Uuid>,
+    #[serde(
+        rename = "encryptionKey",
+        alias = "encryption_key",
+        alias = "roomKey",
+        alias = "room_key"
+    )]
+    pub encryption_key: Option<String>,
+    #[serde(rename = "maxMembers", alias = "max_members", default)]
+    pub max_members: i32,
+    #[serde(rename = "memberCount", alias = "member_count", default)]
+    pub member_count: i64,
+    #[serde(rename = "createdAt", alias = "created_at")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "unreadCount", alias = "unread_count", default)]
+    pub unread_count: i64,
+    /// The user's settings for this room; everything notifies when the
+    /// server doesn't send them
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+}
+
+impl Room {
+    /// "48/50" once the room is nine-tenths full, for a "nearly full" hint.
+    /// None from servers that don't report the member count.
+    pub fn capacity_hint(&self) -> Option<String> {
+        let max = i64::from(self.max_members);
+        (max > 0 && self.member_count * 10 >= max * 9)
+            .then(|| format!("{}/{}", self.member_count, max))
+    }
+}
+
+/// How a room notifies the user, kept on the server so all their devices
+/// agree; from the room list and `GET /api/rooms/:id/notifications`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    /// `all`, `mentions` or `none`
+    pub level: String,
+    /// Silent until then, whatever the level
+    pub muted_until: Option<DateTime<Utc>>,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            level: "all".to_string(),
+            muted_until: None,
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn is_muted(&self, now: DateTime<Utc>) -> bool {
+        self.muted_until.is_some_and(|until| until > now)
+    }
+
+    /// Whether a message should notify; `mention` when it names the user or
+    /// the whole room
+    pub fn notifies(&self, mention: bool, now: DateTime<Utc>) -> bool {
+        !self.is_muted(now)
+            && match self.level.as_str() {
+                "all" => true,
+                "mentions" => mention,
+                _ => false,
+            }
+    }
+}
+
+/// A personal access token for scripts and bridges, from
+/// `GET /api/auth/tokens`; the secret is only ever in `CreatedApiToken`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    /// `read` and/or `post`
+    pub scopes: Vec<String>,
+    /// Rooms the token is limited to; all of the user's when None
+    pub room_ids: Option<Vec<Uuid>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A token just created, with the secret that is shown this once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiToken {
+    pub token: String,
+    pub api_token: ApiToken,
+}
+
+/// Server read state of a joined room, from `GET /api/rooms/unread`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnreadCount {
+    #[serde(rename = "roomId")]
+    pub room_id: Uuid,
+    #[serde(rename = "unreadCount")]
+    pub unread_count: i64,
+    #[serde(rename = "lastReadMessageId")]
+    pub last_read_message_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub id: Uuid,
+    #[serde(rename = "roomId", alias = "room_id")]
+    pub room_id: Uuid,
+    #[serde(
+        rename = "userId",
+        alias = "user_id",
+        alias = "senderId",
+        alias = "sender_id"
+    )]
+    pub user_id: Uuid,
+    #[serde(alias = "encryptedContent", alias = "encrypted_content")]
+    pub content: String,
+    #[serde(rename = "messageType", alias = "message_type", default)]
+    pub message_type: String,
+    #[serde(default)]
+    pub reactions: serde_json::Map<String, Value>,
+    #[serde(default)]
+    pub metadata: Option<Value>,
+    #[serde(rename = "createdAt", alias = "created_at")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(rename = "updatedAt", alias = "updated_at")]
+    pub updated_at: Option<DateTime<Utc>>,
+    pub user: Option<User>,
+    #[serde(rename = "pinnedBy", alias = "pinned_by", default)]
+    pub pinned_by: Option<Uuid>,
+    #[serde(rename = "pinnedAt", alias = "pinned_at", default)]
+    pub pinned_at: Option<DateTime<Utc>>,
+    #[serde(rename = "replyTo", alias = "reply_to", default)]
+    pub reply_to: Option<Uuid>,
+    /// Snapshot of the message being replied to, embedded by the server
+    #[serde(rename = "replyMessage", alias = "reply_message", default)]
+    pub reply_message: Option<Value>,
+    /// Lifetime in seconds of an expiring message
+    #[serde(rename = "expiresIn", default)]
+    pub expires_in: Option<i32>,
+    /// The countdown starts when someone else first reads it
+    #[serde(rename = "expiresAfterRead", default)]
+    pub expires_after_read: bool,
+    /// When the server deletes it; None until the countdown has started
+    #[serde(rename = "expiresAt", default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Set on messages we sent that the server hasn't confirmed yet
+    #[serde(skip)]
+    pub outgoing: Option<Outgoing>,
+}
+
+/// Delivery state of a locally rendered outgoing message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outgoing {
+    Pending,
+    Failed(String),
+}
+
+impl Message {
+    /// Placeholder shown right away while the send is in flight. The random id
+    /// is local only and gets replaced by the server's on ack.
+    pub fn pending(room_id: Uuid, user: &User, content: String, sent_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            room_id,
+            user_id: user.id,
+            content,
+            message_type: "text".to_string(),
+            reactions: serde_json::Map::new(),
+            metadata: None,
+            created_at: Some(sent_at),
+            updated_at: None,
+            user: Some(user.clone()),
+            pinned_by: None,
+            pinned_at: None,
+            reply_to: None,
+            reply_message: None,
+            expires_in: None,
+            expires_after_read: false,
+            expires_at: None,
+            outgoing: Some(Outgoing::Pending),
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.outgoing == Some(Outgoing::Pending)
+    }
+
+    /// Whether the text names `username` (`@alice`) or the whole room
+    /// (`@room`, `@here`), for rooms that only notify about mentions
+    pub fn mentions(&self, username: &str) -> bool {
+        self.content
+            .split(|c: char| !(c.is_alphanumeric() || matches!(c, '@' | '_' | '.' | '-')))
+            .filter_map(|word| word.strip_prefix('@'))
+            .map(|name| name.trim_end_matches(['.', '-']))
+            .any(|name| name.eq_ignore_ascii_case(username) || name == "room" || name == "here")
+    }
+
+    /// Swap the placeholder `local_id` for the confirmed message. If the
+    /// socket echo already delivered it, just drop the placeholder.
+    pub fn confirm(messages: &mut Vec<Message>, local_id: Uuid, confirmed: Message) {
+        if messages.iter().any(|m| m.id == confirmed.id) {
+            messages.retain(|m| m.id != local_id);
+        } else if let Some(slot) = messages.iter_mut().find(|m| m.id == local_id) {
+            *slot = confirmed;
+        } else {
+            messages.push(confirmed);
+        }
+    }
+
+    /// Match a socket-delivered message of ours to its pending placeholder, so
+    /// an echo that beats the REST response doesn't render twice
+    pub fn confirm_echo(messages: &mut Vec<Message>, echoed: Message) {
+        if messages.iter().any(|m| m.id == echoed.id) {
+            return;
+        }
+        let placeholder = messages.iter_mut().find(|m| {
+            m.is_pending()
+                && m.user_id == echoed.user_id
+                && m.room_id == echoed.room_id
+                && m.content == echoed.content
+        });
+        match placeholder {
+            Some(slot) => *slot = echoed,
+            None => messages.push(echoed),
+        }
+    }
+
+    /// Decoded view of `content`; renderers should use this rather than the raw string
+    pub fn decoded(&self) -> MessageContent {
+        MessageContent::decode(
+            &self.message_type,
+            &self.content,
+            self.metadata.as_ref(),
+            &NoKeys,
+        )
+    }
+
+    /// Decoded view of the embedded `replyMessage`, if any
+    pub fn decoded_reply(&self) -> Option<MessageContent> {
+        self.reply_message
+            .as_ref()
+            .map(|reply| MessageContent::from_json(reply, &NoKeys))
+    }
+
+    /// Author name of the embedded `replyMessage`
+    pub fn reply_author(&self) -> Option<String> {
+        let user = self.reply_message.as_ref()?.get("user")?;
+        user.get("displayName")
+            .and_then(Value::as_str)
+            .or_else(|| user.get("username").and_then(Value::as_str))
+            .map(str::to_string)
+    }
+
+    /// Text shown in the message bubble
+    pub fn display_text(&self) -> String {
+        let content = self.decoded();
+        match content.text() {
+            Some(text) => text.to_string(),
+            None => content.preview(200),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerInfo {
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub features: ServerFeatures,
+    #[serde(default)]
+    pub maintenance: MaintenanceStatus,
+    #[serde(default)]
+    pub addresses: ServerAddresses,
+    #[serde(default)]
+    pub limits: ServerLimits,
+    /// Commit, build time and profile (older servers only report `version`)
+    #[serde(default)]
+    pub build: Option<BuildInfo>,
+    /// The server's clock when it answered, Unix ms (older servers omit it)
+    #[serde(rename = "serverTime", default)]
+    pub server_time: Option<i64>,
+    /// base64 Ed25519 key the server signs with (older servers omit it)
+    #[serde(rename = "identityKey", default)]
+    pub identity_key: Option<String>,
+    /// The response carried a signature that verified with `identity_key`
+    #[serde(skip)]
+    pub signed: bool,
+}
+
+impl ServerInfo {
+    /// The identity key, if this response was signed with it
+    pub fn signed_key(&self) -> Option<&str> {
+        self.identity_key.as_deref().filter(|_| self.signed)
+    }
+
+    pub fn build_info(&self) -> BuildInfo {
+        self.build.clone().unwrap_or_else(|| BuildInfo {
+            version: self.version.clone(),
+            ..BuildInfo::default()
+        })
+    }
+}
+
+/// Read-only maintenance mode, from server-info and the `maintenance` socket event
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    #[serde(default)]
+    pub enabled: bool,
+    pub message: Option<String>,
+}
+
+impl MaintenanceStatus {
+    pub fn banner(&self) -> Option<String> {
+        self.enabled.then(|| {
+            self.message
+                .clone()
+                .unwrap_or_else(|| "The server is in maintenance mode".to_string())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerFeatures {
+    #[serde(default)]
+    pub email: bool,
+    #[serde(default)]
+    pub translation: bool,
+    /// Guest sessions can be started without an account
+    #[serde(default)]
+    pub guests: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Translation {
+    pub translated_text: String,
+    pub detected_language: Option<String>,
+}
+
+/// Room sizes from server-info; older servers don't report them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerLimits {
+    pub default_room_members: Option<i32>,
+    pub max_room_members: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerAddresses {
+    pub clearnet: Option<String>,
+    pub onion: Option<String>,
+}
+
+/// Body of a successful login or register
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthResponse {
+    pub token: String,
+    /// For `POST /api/auth/refresh` once `token` expires; older servers
+    /// don't issue one
+    #[serde(rename = "refreshToken", default)]
+    pub refresh_token: Option<String>,
+    pub user: Option<User>,
+}
+
+/// Body of `POST /api/auth/guest`: a session in the server's guest rooms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuestSession {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub user: User,
+    #[serde(default)]
+    pub room_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub can_post: bool,
+}
+
+/// Place of a queued registration (servers with REGISTRATION_MAX_PER_MINUTE)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+    pub activated: bool,
+    pub position: i64,
+    pub estimated_wait_seconds: u64,
+}
+
+/// Outcome of `POST /api/auth/register`
+#[derive(Debug, Clone)]
+pub enum Registration {
+    Active(AuthResponse),
+    /// Accepted into the activation queue; no token until activated
+    Queued {
+        queue_token: String,
+        status: QueueStatus,
+    },
+}
+
+/// Entry of `GET /api/rooms/{id}/members`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomMember {
+    #[serde(rename = "userId", alias = "user_id")]
+    pub user_id: Uuid,
+    #[serde(default = "RoomMember::default_role")]
+    pub role: String,
+    #[serde(rename = "joinedAt", alias = "joined_at")]
+    pub joined_at: Option<DateTime<Utc>>,
+    pub user: Option<User>,
+}
+
+impl RoomMember {
+    fn default_role() -> String {
+        "member".to_string()
+    }
+}
+
+/// Entry of `GET /api/rooms/{id}/files`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomFile {
+    #[serde(rename = "messageId")]
+    pub message_id: Uuid,
+    pub url: String,
+    #[serde(rename = "messageType")]
+    pub message_type: String,
+    pub name: Option<String>,
+    #[serde(rename = "mimeType")]
+    pub mime_type: Option<String>,
+    pub size: Option<u64>,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<DateTime<Utc>>,
+    pub user: Option<User>,
+}
+
+/// Files are immutable once posted, so the message id identifies them
+impl PartialEq for RoomFile {
+    fn eq(&self, other: &Self) -> bool {
+        self.message_id == other.message_id
+    }
+}
+
+impl RoomFile {
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| self.url.rsplit('/').next().unwrap_or(&self.url).to_string())
+    }
+}
@@ -0,0 +1,1986 @@
+use crate::a11y::{focus_element, on_activate};
+use crate::background;
+use crate::components::{
+    copy_to_clipboard, jump_to_message, ApiTokensModal, IdentityKeysModal, Markdown, MessageAction,
+    MessageBubble, MessageMenu, NewRoomModal, PinnedDrawer, RoomNotificationsMenu,
+    ServerKeyWarning, UpdateModal, EXPIRY_CHOICES,
+};
+use crate::cover_traffic;
+use crate::issues::{Fix, Issues, Source};
+use crate::models::{MaintenanceStatus, Message, Outgoing, Room, RoomMember, User};
+use crate::sound;
+use crate::state::{AppState, ChatPlace};
+use crate::storage::{
+    check_server_key, history_page_size, load_config, load_known_keys, room_sound, save_config,
+    save_known_keys, system_language, trust_server_key, ServerKeyChange, SPELLCHECK_LANGUAGES,
+};
+use crate::styles::{HIGH_CONTRAST, STYLES};
+use crate::time_format::TimeFormat;
+use crate::tor_manager::ConnectionMode;
+use crate::updates::{self, UpdateInfo};
+use crate::Route;
+use dioxus::prelude::*;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use tor_chat_common::{ClockOffset, KeyChange, KeyCheck, KnownKeys, MessageLink, MessageWindow};
+use uuid::Uuid;
+
+#[component]
+pub fn Chat() -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let nav = use_navigator();
+
+    // Dioxus Signals for reactive state
+    let mut rooms = use_signal(Vec::<Room>::new);
+    let mut current_room = use_signal(|| None::<Room>);
+    let mut messages = use_signal(Vec::<Message>::new);
+    let mut current_user = use_signal(|| None::<User>);
+    let mut message_input = use_signal(String::new);
+    // Expiry timer applied to messages sent from the input
+    let mut message_expiry = use_signal(|| None::<i32>);
+    let mut show_new_room = use_signal(|| false);
+    let mut show_identity = use_signal(|| false);
+    let mut show_tokens = use_signal(|| false);
+    // The room list has been fetched, and any place kept from an expired
+    // session put back
+    let mut rooms_loaded = use_signal(|| false);
+    let mut place_restored = use_signal(|| false);
+    let mut low_bandwidth = use_signal(|| load_config().low_bandwidth);
+    let mut cover_traffic_enabled = use_signal(|| load_config().cover_traffic);
+    let mut run_in_background = use_signal(|| load_config().run_in_background);
+    let mut start_at_login = use_signal(|| load_config().start_at_login);
+    let mut time_format = use_signal(|| TimeFormat::from_config(&load_config()));
+    let mut spellcheck = use_signal(|| !load_config().disable_spellcheck);
+    let mut spellcheck_language = use_signal(|| load_config().spellcheck_language);
+    let mut high_contrast = use_signal(|| load_config().high_contrast);
+    let mut show_preview = use_signal(|| false);
+    // Right-clicked message and where the menu opens
+    let mut context_menu = use_signal(|| None::<(Uuid, f64, f64)>);
+    // Room whose notification menu is open, and where
+    let mut room_menu = use_signal(|| None::<(Uuid, f64, f64)>);
+    // "Select messages" mode: clicks pick messages to copy together
+    let mut selecting = use_signal(|| false);
+    let mut selected: Signal<HashSet<Uuid>> = use_signal(HashSet::new);
+    // Message to show instead of the latest ones when the next room opens
+    let mut jump_target = use_signal(|| None::<Uuid>);
+    // Rendered slice of `messages`
+    let mut message_window = use_signal(MessageWindow::latest);
+    // Ticks so relative times ("5m ago") stay current
+    // by the server's clock, which the user's may deliberately not match
+    let mut now = use_signal(|| state.peek().api.server_now());
+
+    // Slide the rendered slice of a long history towards its older or newer
+    // end, as the list is scrolled there
+    let mut show_earlier = move || {
+        let moved = message_window.peek().earlier(messages.peek().len());
+        message_window.set(moved);
+    };
+    let mut show_later = move || {
+        let moved = message_window.peek().later(messages.peek().len());
+        message_window.set(moved);
+    };
+    // Scroll to a loaded message, first moving the slice to it if needed
+    let mut show_message = move |id: Uuid| {
+        let len = messages.peek().len();
+        if let Some(i) = messages.peek().iter().position(|m| m.id == id) {
+            let moved = message_window.peek().around(i, len);
+            message_window.set(moved);
+        }
+        jump_to_message(id);
+    };
+
+    // Translation (only when the server has a LibreTranslate instance)
+    let mut translation_enabled = use_signal(|| false);
+    let mut translations: Signal<HashMap<Uuid, Result<String, String>>> = use_signal(HashMap::new);
+
+    // Banner text while the server is read-only for maintenance
+    let mut maintenance = use_signal(|| None::<String>);
+    let mut connection = use_signal(|| None::<ConnectionMode>);
+    let mut available_update = use_signal(|| None::<UpdateInfo>);
+
+    // Problems go to the status center, with a way to run failed operations
+    // again through `redo`; those retries call back into this page, so
+    // they're withdrawn when it closes
+    let issues = use_context::<Issues>();
+    let mut redo = use_signal(|| None::<Redo>);
+    let redo_fix = move |op: Redo| -> Rc<dyn Fn()> {
+        Rc::new(move || {
+            let mut redo = redo;
+            redo.set(Some(op.clone()));
+        })
+    };
+    use_drop(move || issues.withdraw_local_fixes());
+    // Bumped to open the live connection, and to open it again after it drops
+    let mut socket_attempt = use_signal(|| 0u32);
+
+    // Members panel
+    let mut show_members = use_signal(|| false);
+    let mut show_pinned = use_signal(|| false);
+    let mut members: Signal<Vec<RoomMember>> = use_signal(Vec::new);
+
+    // Add member modal
+    let mut show_add_member = use_signal(|| false);
+    let mut all_users: Signal<Vec<User>> = use_signal(Vec::new);
+    let mut add_search = use_signal(String::new);
+
+    // Typing indicator
+    let mut typing_users: Signal<Vec<String>> = use_signal(Vec::new);
+
+    // Last message marked read per room, seeded from the server's read state
+    let mut last_read: Signal<HashMap<Uuid, Uuid>> = use_signal(HashMap::new);
+
+    // Other users' keys as first seen on this device; changes wait for the
+    // user to accept them, and block sending in rooms they're in until then
+    let mut known_keys = use_signal(KnownKeys::default);
+    let mut key_changes: Signal<Vec<KeyChange>> = use_signal(Vec::new);
+    let mut room_member_ids: Signal<Vec<Uuid>> = use_signal(Vec::new);
+    // The server itself signs with a different key than the pinned one;
+    // blocks sending everywhere until the user trusts it
+    let mut server_key_change = use_signal(|| None::<ServerKeyChange>);
+
+    let mut check_key = move |other: &User| {
+        let (Some(me), Some(key)) = (
+            current_user.peek().as_ref().map(|u| u.id),
+            &other.public_key,
+        ) else {
+            return;
+        };
+        if other.id == me {
+            return;
+        }
+        let check = known_keys
+            .write()
+            .observe(&other.id.to_string(), &other.username, key);
+        match check {
+            KeyCheck::New => save_known_keys(&me.to_string(), &known_keys.peek()),
+            KeyCheck::Unchanged => {}
+            KeyCheck::Changed(change) => {
+                let mut changes = key_changes.write();
+                changes.retain(|c| c.user_id != change.user_id);
+                changes.push(change);
+            }
+        }
+    };
+
+    // Unacknowledged key changes of people in the open room
+    let pending_key_changes = move || -> Vec<KeyChange> {
+        let members = room_member_ids.read();
+        let msgs = messages.read();
+        key_changes
+            .read()
+            .iter()
+            .filter(|c| {
+                members.iter().any(|id| id.to_string() == c.user_id)
+                    || msgs.iter().any(|m| m.user_id.to_string() == c.user_id)
+            })
+            .cloned()
+            .collect()
+    };
+
+    // Pull unread counts and read positions from the server; the open room
+    // keeps a zero badge since it's being read
+    let sync_unread = move || async move {
+        let Ok(counts) = state.read().api.get_unread_counts().await else {
+            return;
+        };
+        let open = current_room().map(|r| r.id);
+        let mut positions = last_read.write();
+        let mut rooms = rooms.write();
+        for room in rooms.iter_mut() {
+            let entry = counts.iter().find(|c| c.room_id == room.id);
+            if let Some(id) = entry.and_then(|c| c.last_read_message_id) {
+                positions.insert(room.id, id);
+            }
+            if Some(room.id) != open {
+                room.unread_count = entry.map_or(0, |c| c.unread_count);
+            }
+        }
+    };
+
+    // Fetch the room list, offering a retry while it fails
+    let load_rooms = move || async move {
+        match state.read().api.get_rooms().await {
+            Ok(r) => {
+                rooms.set(r);
+                sync_unread().await;
+            }
+            Err(e) => issues.report_api("Couldn't load rooms", &e, Some(redo_fix(Redo::LoadRooms))),
+        }
+    };
+
+    // Mark the newest confirmed message of the open room as read
+    let mut mark_read = move |room_id: Uuid| {
+        if let Some(room) = rooms.write().iter_mut().find(|r| r.id == room_id) {
+            room.unread_count = 0;
+        }
+        let latest = messages
+            .read()
+            .iter()
+            .rev()
+            .find(|m| m.room_id == room_id && m.outgoing.is_none())
+            .map(|m| m.id);
+        let Some(latest) = latest else {
+            return;
+        };
+        if last_read.read().get(&room_id) == Some(&latest) {
+            return;
+        }
+        last_read.write().insert(room_id, latest);
+        spawn(async move {
+            state
+                .read()
+                .socket
+                .send_event(
+                    "mark_read",
+                    &serde_json::json!({
+                        "roomId": room_id.to_string(),
+                        "messageId": latest.to_string(),
+                    }),
+                )
+                .await;
+        });
+    };
+
+    use_future(move || async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+            if time_format.peek().relative {
+                now.set(state.peek().api.server_now());
+            }
+        }
+    });
+
+    // Slide the rendered slice when the list is scrolled near one of its
+    // ends; restarted with the room, as the list may be a new element
+    let _window_scroll = use_resource(move || async move {
+        if current_room().is_none() {
+            return;
+        }
+        let mut edges = document::eval(WINDOW_SCROLL_JS);
+        while let Ok(edge) = edges.recv::<String>().await {
+            match edge.as_str() {
+                "earlier" => show_earlier(),
+                "later" => show_later(),
+                _ => {}
+            }
+        }
+    });
+
+    // Cover traffic: padded no-op requests at random intervals, only while
+    // enabled and logged in
+    use_future(move || async move {
+        loop {
+            tokio::time::sleep(cover_traffic::next_delay()).await;
+            if !*cover_traffic_enabled.peek() || current_user.peek().is_none() {
+                continue;
+            }
+            let api = state.read().api.clone();
+            let result = api
+                .cover(&cover_traffic::padding(), cover_traffic::response_size())
+                .await;
+            if let Err(e) = result {
+                tracing::debug!("Cover request failed: {}", e);
+            }
+        }
+    });
+
+    // Initial data load, then the live connection
+    use_effect(move || {
+        spawn(async move {
+            // Load current user
+            let user = match state.read().api.get_me().await {
+                Ok(u) => u,
+                Err(e) => {
+                    // An expired session is sent to Login by SessionGuard
+                    if !e.is_unauthorized() {
+                        issues.report_api("Couldn't load your account", &e, None);
+                        nav.push(Route::Login {});
+                    }
+                    return;
+                }
+            };
+            known_keys.set(load_known_keys(&user.id.to_string()));
+            current_user.set(Some(user));
+
+            load_rooms().await;
+            rooms_loaded.set(true);
+
+            // Also catches a server whose key changed since Settings checked it
+            let info = state.read().api.get_server_info().await;
+            let base_url = state.read().api.get_base_url().await;
+            let key = info.as_ref().ok().and_then(|i| i.signed_key());
+            server_key_change.set(check_server_key(&base_url, key));
+            if let Ok(info) = info {
+                translation_enabled.set(info.features.translation);
+                maintenance.set(info.maintenance.banner());
+            }
+
+            if load_config().check_for_updates && updates::take_startup_check() {
+                let app = state.read().clone();
+                spawn(async move {
+                    match app.check_for_update().await {
+                        Ok(update) => available_update.set(update),
+                        Err(e) => tracing::warn!("Update check failed: {}", e),
+                    }
+                });
+            }
+
+            socket_attempt.set(1);
+        });
+    });
+
+    // Live updates over Socket.IO, through the same proxy as the API. When
+    // the connection can't be opened or drops, the status center offers to
+    // reconnect, which runs this again.
+    use_effect(move || {
+        let attempt = socket_attempt();
+        if attempt == 0 {
+            return;
+        }
+        spawn(async move {
+            let Some(token) = state.read().api.get_token().await else {
+                return;
+            };
+            let base_url = state.read().api.get_base_url().await;
+            let socket = state.read().socket.clone();
+
+            let api = state.read().api.clone();
+            connection.set(Some(ConnectionMode::detect(
+                &base_url,
+                api.is_using_tor().await,
+            )));
+            let mut event_rx = socket
+                .connect(&base_url, &token, api.connection_proxy().await)
+                .await;
+            let connected = socket.is_connected().await;
+            if connected {
+                issues.resolve(Source::Socket);
+                // Messages sent to the open room while we were away
+                if attempt > 1 {
+                    if let Some(room) = current_room.peek().clone() {
+                        redo.set(Some(Redo::OpenRoom(room)));
+                    }
+                }
+            }
+
+            // Spawn a local task (runs on the main thread) to poll socket events
+            // and update Dioxus signals safely.
+            spawn(async move {
+                while let Some(ev) = event_rx.recv().await {
+                    match ev.name.as_str() {
+                        "new_message" => {
+                            if let Ok(msg) = serde_json::from_value::<Message>(ev.payload) {
+                                if let Some(author) = &msg.user {
+                                    check_key(author);
+                                }
+                                let room_id = msg.room_id;
+                                let me = current_user();
+                                let own = me.as_ref().map(|u| u.id) == Some(msg.user_id);
+                                let watching = !background::window_hidden()
+                                    && current_room().map(|r| r.id) == Some(room_id);
+                                let mentioned = me.is_some_and(|u| msg.mentions(&u.username));
+                                // The room's notification settings decide
+                                // whether this one makes a sound or a popup
+                                let notify_in = rooms
+                                    .read()
+                                    .iter()
+                                    .find(|r| r.id == room_id)
+                                    .filter(|r| {
+                                        !own && !watching
+                                            && r.notifications
+                                                .notifies(mentioned, chrono::Utc::now())
+                                    })
+                                    .map(|r| r.name.clone());
+                                if let Some(room_name) = notify_in {
+                                    sound::play(&room_sound(room_id));
+                                    if background::window_hidden() {
+                                        let sender = msg
+                                            .user
+                                            .as_ref()
+                                            .map(|u| {
+                                                u.display_name
+                                                    .clone()
+                                                    .unwrap_or_else(|| u.username.clone())
+                                            })
+                                            .unwrap_or_else(|| "Someone".to_string());
+                                        background::notify_message(&room_name, &sender);
+                                    }
+                                }
+                                if current_room().map(|r| r.id) == Some(room_id) {
+                                    Message::confirm_echo(&mut messages.write(), msg);
+                                    mark_read(room_id);
+                                } else if !own {
+                                    if let Some(room) =
+                                        rooms.write().iter_mut().find(|r| r.id == room_id)
+                                    {
+                                        room.unread_count += 1;
+                                    }
+                                }
+                            }
+                        }
+                        "room_created" => {
+                            if let Ok(room) = serde_json::from_value::<Room>(ev.payload) {
+                                let mut r = rooms.write();
+                                if !r.iter().any(|existing| existing.id == room.id) {
+                                    r.push(room);
+                                }
+                            }
+                        }
+                        "room_deleted" => {
+                            if let Some(room_id_str) =
+                                ev.payload.get("roomId").and_then(|v| v.as_str())
+                            {
+                                if let Ok(room_id) = Uuid::parse_str(room_id_str) {
+                                    rooms.write().retain(|r| r.id != room_id);
+                                }
+                            }
+                        }
+                        "message_edited" => {
+                            if let (Some(msg_id), Some(content)) = (
+                                ev.payload.get("messageId").and_then(|v| v.as_str()),
+                                ev.payload.get("content").and_then(|v| v.as_str()),
+                            ) {
+                                if let Ok(id) = Uuid::parse_str(msg_id) {
+                                    let mut msgs = messages.write();
+                                    if let Some(m) = msgs.iter_mut().find(|m| m.id == id) {
+                                        m.content = content.to_string();
+                                    }
+                                }
+                            }
+                        }
+                        "message_pinned" | "message_unpinned" => {
+                            let pinned = ev.name == "message_pinned";
+                            if let Some(id) = ev
+                                .payload
+                                .get("messageId")
+                                .and_then(|v| v.as_str())
+                                .and_then(|s| Uuid::parse_str(s).ok())
+                            {
+                                if let Some(m) = messages.write().iter_mut().find(|m| m.id == id) {
+                                    m.pinned_by = if pinned {
+                                        ev.payload
+                                            .get("pinnedBy")
+                                            .and_then(|v| v.as_str())
+                                            .and_then(|s| Uuid::parse_str(s).ok())
+                                    } else {
+                                        None
+                                    };
+                                    m.pinned_at = if pinned {
+                                        ev.payload
+                                            .get("pinnedAt")
+                                            .and_then(|v| serde_json::from_value(v.clone()).ok())
+                                    } else {
+                                        None
+                                    };
+                                }
+                            }
+                        }
+                        "maintenance" => {
+                            if let Ok(status) =
+                                serde_json::from_value::<MaintenanceStatus>(ev.payload)
+                            {
+                                maintenance.set(status.banner());
+                            }
+                        }
+                        "message_expiry_started" => {
+                            let id = ev
+                                .payload
+                                .get("messageId")
+                                .and_then(|v| v.as_str())
+                                .and_then(|s| Uuid::parse_str(s).ok());
+                            let expires_at = ev
+                                .payload
+                                .get("expiresAt")
+                                .and_then(|v| serde_json::from_value(v.clone()).ok());
+                            if let (Some(id), Some(expires_at)) = (id, expires_at) {
+                                if let Some(m) = messages.write().iter_mut().find(|m| m.id == id) {
+                                    m.expires_at = Some(expires_at);
+                                }
+                            }
+                        }
+                        "message_deleted" | "message_expired" => {
+                            if let Some(msg_id) =
+                                ev.payload.get("messageId").and_then(|v| v.as_str())
+                            {
+                                if let Ok(id) = Uuid::parse_str(msg_id) {
+                                    messages.write().retain(|m| m.id != id);
+                                }
+                            }
+                        }
+                        "user_typing" => {
+                            let username = ev
+                                .payload
+                                .get("username")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or_default()
+                                .to_string();
+                            let is_typing = ev
+                                .payload
+                                .get("typing")
+                                .and_then(|v| v.as_bool())
+                                .unwrap_or(false);
+                            if !username.is_empty() {
+                                let mut users = typing_users.write();
+                                if is_typing {
+                                    if !users.contains(&username) {
+                                        users.push(username);
+                                    }
+                                } else {
+                                    users.retain(|u| u != &username);
+                                }
+                            }
+                        }
+                        "reaction_added" => {
+                            if let (Some(msg_id), Some(emoji), Some(user_id)) = (
+                                ev.payload.get("messageId").and_then(|v| v.as_str()),
+                                ev.payload.get("emoji").and_then(|v| v.as_str()),
+                                ev.payload.get("userId").and_then(|v| v.as_str()),
+                            ) {
+                                if let Ok(id) = Uuid::parse_str(msg_id) {
+                                    let mut msgs = messages.write();
+                                    if let Some(m) = msgs.iter_mut().find(|m| m.id == id) {
+                                        let entry = m
+                                            .reactions
+                                            .entry(emoji.to_string())
+                                            .or_insert_with(|| Value::Array(vec![]));
+                                        if let Value::Array(arr) = entry {
+                                            let uid_val = Value::String(user_id.to_string());
+                                            if !arr.contains(&uid_val) {
+                                                arr.push(uid_val);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        "reaction_removed" => {
+                            if let (Some(msg_id), Some(emoji), Some(user_id)) = (
+                                ev.payload.get("messageId").and_then(|v| v.as_str()),
+                                ev.payload.get("emoji").and_then(|v| v.as_str()),
+                                ev.payload.get("userId").and_then(|v| v.as_str()),
+                            ) {
+                                if let Ok(id) = Uuid::parse_str(msg_id) {
+                                    let mut msgs = messages.write();
+                                    if let Some(m) = msgs.iter_mut().find(|m| m.id == id) {
+                                        let uid_val = Value::String(user_id.to_string());
+                                        if let Some(Value::Array(arr)) = m.reactions.get_mut(emoji)
+                                        {
+                                            arr.retain(|v| v != &uid_val);
+                                            if arr.is_empty() {
+                                                m.reactions.remove(emoji);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        "public_key_changed" => {
+                            let user_id = ev
+                                .payload
+                                .get("userId")
+                                .and_then(|v| v.as_str())
+                                .and_then(|s| Uuid::parse_str(s).ok());
+                            if let (Some(id), Some(username), Some(key)) = (
+                                user_id,
+                                ev.payload.get("username").and_then(|v| v.as_str()),
+                                ev.payload.get("publicKey").and_then(|v| v.as_str()),
+                            ) {
+                                check_key(&User {
+                                    id,
+                                    username: username.to_string(),
+                                    public_key: Some(key.to_string()),
+                                    ..Default::default()
+                                });
+                            }
+                        }
+                        "room_mention" => {
+                            // Shown unless the room is already in front of the user
+                            let room_id = ev
+                                .payload
+                                .get("roomId")
+                                .and_then(|v| v.as_str())
+                                .and_then(|s| Uuid::parse_str(s).ok());
+                            let watching = !background::window_hidden()
+                                && current_room().map(|r| r.id) == room_id;
+                            if !watching {
+                                let room_name = rooms
+                                    .read()
+                                    .iter()
+                                    .find(|r| Some(r.id) == room_id)
+                                    .map(|r| r.name.clone())
+                                    .unwrap_or_default();
+                                let from = &ev.payload["from"];
+                                let sender = from["displayName"]
+                                    .as_str()
+                                    .or(from["username"].as_str())
+                                    .unwrap_or("Someone");
+                                let scope = ev.payload["scope"].as_str().unwrap_or("room");
+                                background::notify_mention(&room_name, sender, scope);
+                            }
+                        }
+                        "member_joined" => {
+                            tracing::info!("Member joined: {:?}", ev.payload);
+                        }
+                        "member_left" => {
+                            tracing::info!("Member left: {:?}", ev.payload);
+                        }
+                        "authenticated" => {
+                            tracing::info!("Socket authenticated");
+                            if let Some(server_time) =
+                                ev.payload.get("serverTime").and_then(|v| v.as_i64())
+                            {
+                                // Measured on arrival, so this includes the
+                                // one-way delay; server-info refines it
+                                let received = chrono::Utc::now().timestamp_millis();
+                                state.peek().api.set_clock(ClockOffset::measure(
+                                    received,
+                                    server_time,
+                                    received,
+                                ));
+                                now.set(state.peek().api.server_now());
+                            }
+                            // Catch up on anything missed while disconnected
+                            spawn(sync_unread());
+                        }
+                        _ => {
+                            tracing::debug!("Socket event: {} {:?}", ev.name, ev.payload);
+                        }
+                    }
+                }
+
+                let text = if connected {
+                    "Lost the live connection to the server; new messages won't arrive"
+                } else {
+                    "Couldn't open the live connection to the server"
+                };
+                issues.report(
+                    Source::Socket,
+                    text,
+                    Some(Fix::Reconnect(Rc::new(move || {
+                        let mut attempt = socket_attempt;
+                        *attempt.write() += 1;
+                    }))),
+                );
+            });
+        });
+    });
+
+    let mut select_room = move |room: Room| {
+        let reopen = room.clone();
+        let room_id = room.id.to_string();
+        let room_uuid = room.id;
+        current_room.set(Some(room));
+        messages.set(Vec::new());
+        message_window.set(MessageWindow::latest());
+        show_members.set(false);
+        typing_users.set(Vec::new());
+        room_member_ids.set(Vec::new());
+        selecting.set(false);
+        selected.write().clear();
+        let target = jump_target.take();
+
+        spawn(async move {
+            // Join room via socket
+            state
+                .read()
+                .socket
+                .send_event("join_room", &serde_json::json!({"roomId": room_id}))
+                .await;
+
+            // Load messages via API: the latest ones, or the ones around a
+            // message being opened
+            let limit = history_page_size(low_bandwidth());
+            let loaded = match target {
+                Some(target) => {
+                    let api = state.read().api.clone();
+                    match api
+                        .get_message_context(&room_id, &target.to_string(), limit / 2)
+                        .await
+                    {
+                        Ok(msgs) => Some((msgs, Some(target))),
+                        Err(e) => {
+                            tracing::warn!("Failed to open message: {}", e);
+                            None
+                        }
+                    }
+                }
+                None => None,
+            };
+            let loaded = match loaded {
+                Some(loaded) => Ok(loaded),
+                None => state
+                    .read()
+                    .api
+                    .get_messages(&room_id, limit, 0)
+                    .await
+                    .map(|msgs| (msgs, None)),
+            };
+            match loaded {
+                Ok((msgs, target)) => {
+                    for author in msgs.iter().filter_map(|m| m.user.as_ref()) {
+                        check_key(author);
+                    }
+                    messages.set(msgs);
+                    match target {
+                        Some(target) => show_message(target),
+                        None => mark_read(room_uuid),
+                    }
+                }
+                Err(e) => issues.report_api(
+                    "Couldn't load messages",
+                    &e,
+                    Some(redo_fix(Redo::OpenRoom(reopen))),
+                ),
+            }
+
+            // Members who haven't written recently can still have changed keys
+            if let Ok(list) = state.read().api.get_members(&room_id).await {
+                for user in list.iter().filter_map(|m| m.user.as_ref()) {
+                    check_key(user);
+                }
+                room_member_ids.set(list.iter().map(|m| m.user_id).collect());
+            }
+        });
+    };
+
+    // Back from an expired session: reopen the room and draft the user had
+    use_effect(move || {
+        if !rooms_loaded() || *place_restored.peek() {
+            return;
+        }
+        let me = current_user.peek().as_ref().map(|u| u.id);
+        let place = state.peek().chat_place.lock().unwrap().take();
+        if let Some(place) = place.filter(|p| Some(p.user_id) == me) {
+            message_input.set(place.draft);
+            let room = place
+                .room_id
+                .and_then(|id| rooms.peek().iter().find(|r| r.id == id).cloned());
+            if let Some(room) = room {
+                select_room(room);
+            }
+        }
+        place_restored.set(true);
+    });
+
+    // Keep the current place for the next time the session expires
+    use_effect(move || {
+        if !place_restored() {
+            return;
+        }
+        let Some(user_id) = current_user().map(|u| u.id) else {
+            return;
+        };
+        let place = ChatPlace {
+            user_id,
+            room_id: current_room().map(|r| r.id),
+            draft: message_input(),
+        };
+        *state.peek().chat_place.lock().unwrap() = Some(place);
+    });
+
+    // Open a message from a reply, a pin, a file or a message link: scroll
+    // to it if it's loaded, otherwise load the messages around it, opening
+    // its room first if needed
+    let mut open_message = move |room_id: Uuid, message_id: Uuid| {
+        if current_room.peek().as_ref().map(|r| r.id) != Some(room_id) {
+            let room = rooms.peek().iter().find(|r| r.id == room_id).cloned();
+            match room {
+                Some(room) => {
+                    jump_target.set(Some(message_id));
+                    select_room(room);
+                }
+                None => tracing::warn!("Message link to a room we're not in: {}", room_id),
+            }
+            return;
+        }
+        if messages.peek().iter().any(|m| m.id == message_id) {
+            show_message(message_id);
+            return;
+        }
+        spawn(async move {
+            let limit = history_page_size(low_bandwidth());
+            let api = state.read().api.clone();
+            match api
+                .get_message_context(&room_id.to_string(), &message_id.to_string(), limit / 2)
+                .await
+            {
+                Ok(msgs) if current_room.peek().as_ref().map(|r| r.id) == Some(room_id) => {
+                    for author in msgs.iter().filter_map(|m| m.user.as_ref()) {
+                        check_key(author);
+                    }
+                    message_window.set(MessageWindow::latest());
+                    messages.set(msgs);
+                    show_message(message_id);
+                }
+                Ok(_) => {}
+                Err(e) => issues.report_api("Couldn't open the message", &e, None),
+            }
+        });
+    };
+    let mut open_link = move |link: MessageLink| {
+        if let (Ok(room_id), Ok(message_id)) = (
+            Uuid::parse_str(&link.room_id),
+            Uuid::parse_str(&link.message_id),
+        ) {
+            open_message(room_id, message_id);
+        }
+    };
+
+    // Render the message immediately as pending, then reconcile with the
+    // server's copy; over Tor the round trip can take several seconds
+    let deliver = move |local_id: Uuid, room_id: Uuid, content: String, expires_in: Option<i32>| {
+        spawn(async move {
+            let result = state
+                .read()
+                .api
+                .send_message(&room_id.to_string(), &content, expires_in)
+                .await;
+            let mut msgs = messages.write();
+            match result {
+                Ok(msg) => Message::confirm(&mut msgs, local_id, msg),
+                Err(e) => {
+                    if let Some(m) = msgs.iter_mut().find(|m| m.id == local_id) {
+                        m.outgoing = Some(Outgoing::Failed(e.to_string()));
+                    }
+                    issues.report_api("Message not sent", &e, Some(redo_fix(Redo::Send(local_id))));
+                }
+            }
+        });
+    };
+
+    // Run what the status center asked to retry
+    use_effect(move || {
+        let Some(op) = redo() else {
+            return;
+        };
+        redo.set(None);
+        match op {
+            Redo::LoadRooms => {
+                spawn(load_rooms());
+            }
+            Redo::OpenRoom(room) => {
+                // Only if the room is still there and nothing else was opened
+                let open = current_room.peek().as_ref().map(|r| r.id);
+                let known = rooms.peek().iter().any(|r| r.id == room.id);
+                if known && open.is_none_or(|id| id == room.id) {
+                    select_room(room);
+                }
+            }
+            Redo::Send(id) => {
+                let mut msgs = messages.write();
+                let Some(m) = msgs
+                    .iter_mut()
+                    .find(|m| m.id == id && matches!(m.outgoing, Some(Outgoing::Failed(_))))
+                else {
+                    return;
+                };
+                m.outgoing = Some(Outgoing::Pending);
+                let (room_id, content, expires_in) = (m.room_id, m.content.clone(), m.expires_in);
+                drop(msgs);
+                deliver(id, room_id, content, expires_in);
+            }
+        }
+    });
+
+    let do_send_message = move || {
+        let content = message_input().trim().to_string();
+        if content.is_empty()
+            || !pending_key_changes().is_empty()
+            || server_key_change.peek().is_some()
+        {
+            return;
+        }
+
+        let (Some(room), Some(user)) = (current_room(), current_user()) else {
+            return;
+        };
+        message_input.set(String::new());
+
+        let sent_at = state.peek().api.server_now();
+        let mut pending = Message::pending(room.id, &user, content.clone(), sent_at);
+        pending.expires_in = message_expiry();
+        pending.expires_after_read = pending.expires_in.is_some();
+        let local_id = pending.id;
+        messages.write().push(pending);
+
+        deliver(local_id, room.id, content, message_expiry());
+    };
+
+    let logout = move |_| {
+        spawn(async move {
+            state.read().api.logout().await;
+            state.read().clear_auth().await;
+            *state.read().chat_place.lock().unwrap() = None;
+
+            let mut config = load_config();
+            config.token = None;
+            config.refresh_token = None;
+            save_config(&config);
+
+            nav.push(Route::Login {});
+        });
+    };
+
+    let user = current_user();
+    let user_id = user.as_ref().map(|u| u.id).unwrap_or_default();
+    let is_admin = user.as_ref().map(|u| u.is_admin).unwrap_or(false);
+    // Guest sessions end at a set time and can't be renewed
+    let guest_ends = user
+        .as_ref()
+        .and_then(|u| u.guest_expires_at)
+        .map(|ends| TimeFormat::from_config(&load_config()).full(ends, chrono::Utc::now()));
+
+    let cur_room = current_room();
+    let is_creator = cur_room
+        .as_ref()
+        .and_then(|r| r.creator_id)
+        .map(|c| c == user_id)
+        .unwrap_or(false);
+    let can_delete = is_creator || is_admin;
+    // The input grows with the message up to a few lines, then scrolls
+    let input_rows = message_input().split('\n').count().min(8);
+    let can_manage_members = is_creator || is_admin;
+    let room_key_changes = pending_key_changes();
+    let send_blocked = !room_key_changes.is_empty() || server_key_change().is_some();
+    // Our own messages may not carry the author
+    let sender_name = move |m: &Message| -> String {
+        m.user
+            .as_ref()
+            .map(|u| u.username.clone())
+            .or_else(|| {
+                current_user
+                    .peek()
+                    .as_ref()
+                    .filter(|u| u.id == m.user_id)
+                    .map(|u| u.username.clone())
+            })
+            .unwrap_or_else(|| "Unknown".to_string())
+    };
+
+    rsx! {
+        style { {STYLES} }
+        if high_contrast() {
+            style { {HIGH_CONTRAST} }
+        }
+
+        if current_room().is_some() {
+            button {
+                class: "skip-link",
+                onclick: move |_| focus_element("message-input"),
+                "Skip to message box"
+            }
+        }
+
+        if let Some(notice) = maintenance() {
+            div { class: "maintenance-banner",
+                role: "alert",
+                "{notice} \u{2014} messages can't be sent right now."
+            }
+        }
+
+        div { class: "chat-container",
+            // Sidebar
+            nav { class: "sidebar", aria_label: "Rooms and settings",
+                div { class: "sidebar-header",
+                    h2 { class: "sidebar-title", "TOR Chat" }
+                    if let Some(mode) = connection() {
+                        span {
+                            class: "connection-badge {mode.class()}",
+                            title: "{mode.description()}",
+                            "{mode.label()}"
+                        }
+                    }
+                }
+
+                div { class: "room-list",
+                    for room in rooms() {
+                        div {
+                            class: match (current_room().as_ref().map(|r| r.id) == Some(room.id), room.unread_count > 0) {
+                                (true, _) => "room-item active",
+                                (false, true) => "room-item unread",
+                                (false, false) => "room-item",
+                            },
+                            role: "button",
+                            tabindex: "0",
+                            aria_current: if current_room().as_ref().map(|r| r.id) == Some(room.id) { "true" },
+                            aria_label: if room.unread_count > 0 {
+                                "{room.name}, {room.unread_count} unread"
+                            } else {
+                                "{room.name}"
+                            },
+                            onclick: {
+                                let room = room.clone();
+                                move |_| select_room(room.clone())
+                            },
+                            onkeydown: {
+                                let room = room.clone();
+                                move |e| on_activate(e, || select_room(room.clone()))
+                            },
+                            oncontextmenu: {
+                                let id = room.id;
+                                move |e: MouseEvent| {
+                                    e.prevent_default();
+                                    let point = e.client_coordinates();
+                                    room_menu.set(Some((id, point.x, point.y)));
+                                }
+                            },
+                            div { class: "room-name",
+                                "{room.name}"
+                                if room.notifications.level == "none" || room.notifications.is_muted(chrono::Utc::now()) {
+                                    span { class: "muted-icon", title: "Notifications off", "\u{1F515}" }
+                                }
+                                span {
+                                    class: if room.is_public { "badge badge-public" } else { "badge badge-private" },
+                                    if room.is_public { "Public" } else { "Private" }
+                                }
+                                if room.unread_count > 0 {
+                                    span { class: "unread-badge", "{room.unread_count}" }
+                                }
+                            }
+                            div { class: "room-desc",
+                                "{room.description.clone().unwrap_or_default()}"
+                            }
+                        }
+                    }
+
+                    div {
+                        class: "new-room-btn",
+                        role: "button",
+                        tabindex: "0",
+                        onclick: move |_| show_new_room.set(true),
+                        onkeydown: move |e| on_activate(e, || show_new_room.set(true)),
+                        "+ New Room"
+                    }
+                }
+
+                if let Some((menu_room, x, y)) = room_menu() {
+                    if let Some(room) = rooms.peek().iter().find(|r| r.id == menu_room).cloned() {
+                        RoomNotificationsMenu {
+                            key: "{menu_room}",
+                            room,
+                            x,
+                            y,
+                            on_change: move |settings| {
+                                if let Some(room) = rooms.write().iter_mut().find(|r| r.id == menu_room) {
+                                    room.notifications = settings;
+                                }
+                            },
+                            on_close: move |_| room_menu.set(None),
+                        }
+                    }
+                }
+
+                div { class: "checkbox-group bandwidth-toggle",
+                    input {
+                        r#type: "checkbox",
+                        id: "low-bandwidth",
+                        checked: low_bandwidth(),
+                        onchange: move |e| {
+                            low_bandwidth.set(e.checked());
+                            let mut config = load_config();
+                            config.low_bandwidth = e.checked();
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "low-bandwidth", "Low bandwidth" }
+                }
+                div {
+                    class: "checkbox-group bandwidth-toggle",
+                    title: "Send dummy requests at random times so your real activity is harder to spot in network traffic. Uses extra bandwidth.",
+                    input {
+                        r#type: "checkbox",
+                        id: "cover-traffic",
+                        checked: cover_traffic_enabled(),
+                        onchange: move |e| {
+                            cover_traffic_enabled.set(e.checked());
+                            let mut config = load_config();
+                            config.cover_traffic = e.checked();
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "cover-traffic", "Cover traffic" }
+                }
+                div {
+                    class: "checkbox-group bandwidth-toggle",
+                    title: "Closing the window keeps TOR Chat connected in the tray and shows a notification for new messages. Takes effect after a restart.",
+                    input {
+                        r#type: "checkbox",
+                        id: "run-in-background",
+                        checked: run_in_background(),
+                        onchange: move |e| {
+                            run_in_background.set(e.checked());
+                            let mut config = load_config();
+                            config.run_in_background = e.checked();
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "run-in-background", "Run in background" }
+                }
+                div {
+                    class: "checkbox-group bandwidth-toggle",
+                    title: "Start hidden in the tray when you log in to your computer, and connect with the saved server",
+                    input {
+                        r#type: "checkbox",
+                        id: "start-at-login",
+                        checked: start_at_login(),
+                        onchange: move |e| {
+                            let enabled = e.checked();
+                            if let Err(err) = background::set_start_at_login(enabled) {
+                                tracing::warn!("Failed to update login item: {}", err);
+                                return;
+                            }
+                            start_at_login.set(enabled);
+                            let mut config = load_config();
+                            config.start_at_login = enabled;
+                            // Starting hidden only makes sense if closing hides too
+                            if enabled {
+                                config.run_in_background = true;
+                                run_in_background.set(true);
+                            }
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "start-at-login", "Start at login" }
+                }
+                div {
+                    class: "checkbox-group bandwidth-toggle",
+                    title: "Show \"5m ago\" instead of the time for messages from the last week",
+                    input {
+                        r#type: "checkbox",
+                        id: "relative-timestamps",
+                        checked: time_format().relative,
+                        onchange: move |e| {
+                            time_format.write().relative = e.checked();
+                            now.set(state.peek().api.server_now());
+                            let mut config = load_config();
+                            config.relative_timestamps = e.checked();
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "relative-timestamps", "Relative times" }
+                }
+                div {
+                    class: "checkbox-group bandwidth-toggle",
+                    title: "Show times in UTC instead of your timezone, so screenshots don't give away where you are",
+                    input {
+                        r#type: "checkbox",
+                        id: "utc-timestamps",
+                        checked: time_format().utc,
+                        onchange: move |e| {
+                            time_format.write().utc = e.checked();
+                            let mut config = load_config();
+                            config.utc_timestamps = e.checked();
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "utc-timestamps", "UTC times" }
+                }
+                div {
+                    class: "checkbox-group bandwidth-toggle",
+                    title: "Underline misspelled words in the message box",
+                    input {
+                        r#type: "checkbox",
+                        id: "spellcheck",
+                        checked: spellcheck(),
+                        onchange: move |e| {
+                            spellcheck.set(e.checked());
+                            let mut config = load_config();
+                            config.disable_spellcheck = !e.checked();
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "spellcheck", "Spell check" }
+                    if spellcheck() {
+                        select {
+                            class: "spellcheck-select",
+                            title: "Language to check against",
+                            aria_label: "Spell check language",
+                            onchange: move |e| {
+                                let language = Some(e.value()).filter(|v| !v.is_empty());
+                                spellcheck_language.set(language.clone());
+                                let mut config = load_config();
+                                config.spellcheck_language = language;
+                                save_config(&config);
+                            },
+                            option { value: "", selected: spellcheck_language().is_none(), "System" }
+                            for (code, name) in SPELLCHECK_LANGUAGES {
+                                option {
+                                    value: "{code}",
+                                    selected: spellcheck_language().as_deref() == Some(*code),
+                                    "{name}"
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "checkbox-group bandwidth-toggle",
+                    title: "Black and white with yellow highlights and heavier borders",
+                    input {
+                        r#type: "checkbox",
+                        id: "high-contrast",
+                        checked: high_contrast(),
+                        onchange: move |e| {
+                            high_contrast.set(e.checked());
+                            let mut config = load_config();
+                            config.high_contrast = e.checked();
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "high-contrast", "High contrast" }
+                }
+
+                if let Some(user) = user.clone() {
+                    div { class: "user-info",
+                        span { class: "username", "{user.username}" }
+                        if let Some(ends) = guest_ends.clone() {
+                            // Guests can't manage keys or tokens
+                            span {
+                                class: "guest-badge",
+                                title: "Guest session, ends {ends}",
+                                "guest"
+                            }
+                        } else {
+                            button {
+                                class: "identity-btn",
+                                title: "Identity keys",
+                                aria_label: "Identity keys",
+                                onclick: move |_| show_identity.set(true),
+                                "\u{1F511}"
+                            }
+                            button {
+                                class: "identity-btn",
+                                title: "API tokens",
+                                aria_label: "API tokens",
+                                onclick: move |_| show_tokens.set(true),
+                                "\u{1F9E9}"
+                            }
+                        }
+                        button {
+                            class: "logout-btn",
+                            onclick: logout,
+                            "Logout"
+                        }
+                    }
+                }
+            }
+
+            // Main content
+            main { class: "main-content",
+                if let Some(room) = current_room() {
+                    // Chat header
+                    div { class: "chat-header",
+                        div {
+                            h2 { class: "chat-title", "{room.name}" }
+                            span {
+                                class: if room.is_public { "badge badge-public" } else { "badge badge-private" },
+                                if room.is_public { "Public" } else { "Private" }
+                            }
+                            if let Some(capacity) = room.capacity_hint() {
+                                span {
+                                    class: "badge badge-full",
+                                    title: "This room is close to its member limit",
+                                    "Nearly full ({capacity})"
+                                }
+                            }
+                        }
+                        div { class: "chat-actions",
+                            // Members button
+                            {
+                                let room_id = room.id.to_string();
+                                rsx! {
+                                    button {
+                                        class: "btn btn-secondary btn-small",
+                                        aria_expanded: "{show_members()}",
+                                        onclick: move |_| {
+                                            let showing = show_members();
+                                            show_members.set(!showing);
+                                            show_pinned.set(false);
+                                            if !showing {
+                                                let rid = room_id.clone();
+                                                spawn(async move {
+                                                    match state.read().api.get_members(&rid).await {
+                                                        Ok(m) => members.set(m),
+                                                        Err(e) => issues.report_api("Couldn't load members", &e, None),
+                                                    }
+                                                });
+                                            }
+                                        },
+                                        "Members"
+                                    }
+                                }
+                            }
+                            button {
+                                class: "btn btn-secondary btn-small",
+                                aria_expanded: "{show_pinned()}",
+                                onclick: move |_| {
+                                    show_pinned.set(!show_pinned());
+                                    show_members.set(false);
+                                },
+                                "Pinned"
+                            }
+                            // Leave button (not for creator)
+                            if !is_creator {
+                                {
+                                    let room_id = room.id.to_string();
+                                    rsx! {
+                                        button {
+                                            class: "btn btn-warning btn-small",
+                                            onclick: move |_| {
+                                                let rid = room_id.clone();
+                                                spawn(async move {
+                                                    let result = state.read().api.leave_room(&rid).await;
+                                                    match result {
+                                                        Ok(()) => {
+                                                            current_room.set(None);
+                                                            messages.set(Vec::new());
+                                                            show_members.set(false);
+                                                            load_rooms().await;
+                                                        }
+                                                        Err(e) => issues.report_api("Couldn't leave the room", &e, None),
+                                                    }
+                                                });
+                                            },
+                                            "Leave"
+                                        }
+                                    }
+                                }
+                            }
+                            // Delete button
+                            if can_delete {
+                                {
+                                    let room_id = room.id.to_string();
+                                    rsx! {
+                                        button {
+                                            class: "btn btn-danger btn-small",
+                                            onclick: move |_| {
+                                                let rid = room_id.clone();
+                                                spawn(async move {
+                                                    let result = state.read().api.delete_room(&rid).await;
+                                                    match result {
+                                                        Ok(()) => {
+                                                            current_room.set(None);
+                                                            messages.set(Vec::new());
+                                                            show_members.set(false);
+                                                            load_rooms().await;
+                                                        }
+                                                        Err(e) => issues.report_api("Couldn't delete the room", &e, None),
+                                                    }
+                                                });
+                                            },
+                                            "Delete"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Chat body (messages + optional members panel)
+                    div {
+                        style: "display: flex; flex: 1; overflow: hidden;",
+
+                        // Messages
+                        div {
+                            id: "message-log",
+                            class: "messages",
+                            style: "flex: 1;",
+                            role: "log",
+                            aria_label: "Messages",
+                            {
+                                let all = messages.read();
+                                let range = message_window().range(all.len());
+                                let (start, end) = (range.start, range.end);
+                                let more_after = end < all.len();
+                                let format = time_format();
+                                let now = now();
+                                let picked = selected.read();
+                                let bubbles: Vec<_> = all[start..end]
+                                    .iter()
+                                    .map(|msg| {
+                                        let reply = msg.reply_to.map(|original_id| {
+                                            let loaded = all.iter().find(|m| m.id == original_id);
+                                            let author = msg
+                                                .reply_author()
+                                                .or_else(|| loaded.and_then(|m| m.user.as_ref()).map(|u| u.username.clone()))
+                                                .unwrap_or_else(|| "Unknown".to_string());
+                                            let snippet = msg
+                                                .decoded_reply()
+                                                .or_else(|| loaded.map(|m| m.decoded()))
+                                                .map(|c| c.preview(100))
+                                                .unwrap_or_else(|| "Original message".to_string());
+                                            (author, snippet)
+                                        });
+                                        let time = msg
+                                            .created_at
+                                            .map(|t| (format.message_time(t, now), format.full(t, now)));
+                                        let translation = translations.read().get(&msg.id).cloned();
+                                        (msg.clone(), picked.contains(&msg.id), reply, time, translation)
+                                    })
+                                    .collect();
+                                rsx! {
+                                    if start > 0 {
+                                        button {
+                                            id: "window-earlier",
+                                            class: "message-window-edge",
+                                            onclick: move |_| show_earlier(),
+                                            "Show earlier messages"
+                                        }
+                                    }
+                                    for (msg, is_selected, reply, time, translation) in bubbles {
+                                        {
+                                            let id = msg.id;
+                                            let room_id = msg.room_id;
+                                            let original_id = msg.reply_to;
+                                            let own = msg.user_id == user_id;
+                                            let resend = (msg.content.clone(), msg.expires_in);
+                                            rsx! {
+                                                MessageBubble {
+                                                    key: "{id}",
+                                                    msg,
+                                                    own,
+                                                    selecting: selecting(),
+                                                    selected: is_selected,
+                                                    reply,
+                                                    time,
+                                                    auto_load_images: !low_bandwidth(),
+                                                    translate: translation_enabled(),
+                                                    translation,
+                                                    on_menu: move |(x, y): (f64, f64)| context_menu.set(Some((id, x, y))),
+                                                    on_toggle: move |_| {
+                                                        let mut picked = selected.write();
+                                                        if !picked.remove(&id) {
+                                                            picked.insert(id);
+                                                        }
+                                                    },
+                                                    on_open_reply: move |_| {
+                                                        if let Some(original_id) = original_id {
+                                                            open_message(room_id, original_id);
+                                                        }
+                                                    },
+                                                    on_link: open_link,
+                                                    on_translate: move |text: String| {
+                                                        spawn(async move {
+                                                            let api = state.read().api.clone();
+                                                            let result = api
+                                                                .translate(&text, &system_language())
+                                                                .await
+                                                                .map(|t| t.translated_text)
+                                                                .map_err(|e| e.to_string());
+                                                            translations.write().insert(id, result);
+                                                        });
+                                                    },
+                                                    on_retry: move |_| {
+                                                        if let Some(m) = messages.write().iter_mut().find(|m| m.id == id) {
+                                                            m.outgoing = Some(Outgoing::Pending);
+                                                        }
+                                                        deliver(id, room_id, resend.0.clone(), resend.1);
+                                                    },
+                                                }
+                                            }
+                                        }
+                                    }
+                                    if more_after {
+                                        button {
+                                            id: "window-later",
+                                            class: "message-window-edge",
+                                            onclick: move |_| show_later(),
+                                            "Show newer messages"
+                                        }
+                                    }
+                                }
+                            }
+                            for change in room_key_changes.clone() {
+                                {
+                                    let previous = change.previous_fingerprint();
+                                    let current = change.current_fingerprint();
+                                    let username = change.username.clone();
+                                    rsx! {
+                                        div {
+                                            key: "key-change-{username}",
+                                            class: "key-change",
+                                            div { class: "key-change-title",
+                                                "\u{26A0} {username}'s identity key changed"
+                                            }
+                                            p {
+                                                "This happens when they reinstall or set up a new device, but it could also mean the server is impersonating them. Compare the new fingerprint with them over another channel before continuing."
+                                            }
+                                            div { class: "key-change-fingerprints",
+                                                div { "Before: {previous}" }
+                                                div { "Now: {current}" }
+                                            }
+                                            button {
+                                                class: "btn btn-primary",
+                                                onclick: move |_| {
+                                                    known_keys.write().accept(&change);
+                                                    if let Some(me) = current_user.peek().as_ref() {
+                                                        save_known_keys(&me.id.to_string(), &known_keys.peek());
+                                                    }
+                                                    key_changes.write().retain(|c| c.user_id != change.user_id);
+                                                },
+                                                "Accept New Key"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
+                        // Pinned messages & files drawer
+                        if show_pinned() {
+                            PinnedDrawer {
+                                key: "{room.id}",
+                                room_id: room.id,
+                                messages,
+                                on_open: move |id| open_message(room.id, id),
+                            }
+                        }
+
+                        // Members panel
+                        if show_members() {
+                            aside { class: "members-panel", aria_label: "Members",
+                                div { class: "members-header",
+                                    span { style: "font-weight: 600;", "Members" }
+                                    if can_manage_members {
+                                        button {
+                                            class: "add-btn",
+                                            onclick: move |_| {
+                                                show_add_member.set(true);
+                                                add_search.set(String::new());
+                                                spawn(async move {
+                                                    match state.read().api.get_users().await {
+                                                        Ok(u) => all_users.set(u),
+                                                        Err(e) => issues.report_api("Couldn't load users", &e, None),
+                                                    }
+                                                });
+                                            },
+                                            aria_label: "Add member",
+                                            "+ Add"
+                                        }
+                                    }
+                                }
+                                div { style: "flex: 1; overflow-y: auto;",
+                                    for member in members() {
+                                        {
+                                            let member_uid = member.user_id.to_string();
+                                            let uname = member.user.as_ref().map(|u| u.username.clone()).unwrap_or_else(|| "?".to_string());
+                                            let is_online = member.user.as_ref().map(|u| u.is_online).unwrap_or(false);
+                                            let role = member.role.clone();
+                                            let is_member_creator = current_room()
+                                                .as_ref()
+                                                .and_then(|r| r.creator_id)
+                                                .map(|c| c.to_string() == member_uid)
+                                                .unwrap_or(false);
+                                            let can_remove = can_manage_members
+                                                && !is_member_creator
+                                                && member_uid != user_id.to_string();
+                                            let room_id = current_room().as_ref().map(|r| r.id.to_string()).unwrap_or_default();
+                                            let uid_for_remove = member_uid.clone();
+                                            let rid_for_refresh = room_id.clone();
+                                            rsx! {
+                                                div { class: "member-item",
+                                                    div {
+                                                        div {
+                                                            class: "member-name",
+                                                            span {
+                                                                class: if is_online { "online-dot on" } else { "online-dot off" },
+                                                                role: "img",
+                                                                aria_label: if is_online { "Online" } else { "Offline" },
+                                                            }
+                                                            "{uname}"
+                                                        }
+                                                        if role == "admin" {
+                                                            div { class: "member-role", "Admin" }
+                                                        }
+                                                    }
+                                                    if can_remove {
+                                                        button {
+                                                            class: "member-remove",
+                                                            aria_label: "Remove {uname}",
+                                                            onclick: move |_| {
+                                                                let rid = room_id.clone();
+                                                                let uid = uid_for_remove.clone();
+                                                                let rid_r = rid_for_refresh.clone();
+                                                                spawn(async move {
+                                                                    let result = state.read().api.remove_member(&rid, &uid).await;
+                                                                    match result {
+                                                                        Ok(()) => {
+                                                                            if let Ok(m) = state.read().api.get_members(&rid_r).await {
+                                                                                members.set(m);
+                                                                            }
+                                                                        }
+                                                                        Err(e) => issues.report_api("Couldn't remove the member", &e, None),
+                                                                    }
+                                                                });
+                                                            },
+                                                            "Remove"
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Typing indicator
+                    {
+                        let users = typing_users();
+                        if !users.is_empty() {
+                            let text = if users.len() == 1 {
+                                format!("{} is typing...", users[0])
+                            } else {
+                                format!("{} are typing...", users.join(", "))
+                            };
+                            rsx! {
+                                div { class: "typing-indicator",
+                                    role: "status",
+                                    style: "padding: 4px 16px; font-size: 12px; color: #888; font-style: italic;",
+                                    "{text}"
+                                }
+                            }
+                        } else {
+                            rsx! {}
+                        }
+                    }
+
+                    if show_preview() && !message_input().trim().is_empty() {
+                        div { class: "message-preview",
+                            div { class: "message-content", Markdown { text: message_input() } }
+                        }
+                    }
+
+                    if let Some((menu_id, x, y)) = context_menu() {
+                        if let Some(msg) = messages.peek().iter().find(|m| m.id == menu_id).cloned() {
+                            MessageMenu {
+                                x,
+                                y,
+                                can_link: msg.outgoing.is_none(),
+                                on_action: move |action| match action {
+                                    MessageAction::CopyText => copy_to_clipboard(&msg.display_text()),
+                                    MessageAction::CopyLink => {
+                                        copy_to_clipboard(&MessageLink::new(msg.room_id, msg.id).to_string())
+                                    }
+                                    MessageAction::CopySender => copy_to_clipboard(&sender_name(&msg)),
+                                    MessageAction::Select => {
+                                        selecting.set(true);
+                                        selected.set(HashSet::from([msg.id]));
+                                    }
+                                },
+                                on_close: move |_| context_menu.set(None),
+                            }
+                        }
+                    }
+
+                    if selecting() {
+                        div { class: "selection-bar",
+                            role: "toolbar",
+                            aria_label: "Selected messages",
+                            span { class: "selection-count",
+                                role: "status",
+                                "{selected.read().len()} selected \u{2014} click messages to add or remove them"
+                            }
+                            button {
+                                class: "add-btn",
+                                disabled: selected.read().is_empty(),
+                                onclick: move |_| {
+                                    let format = time_format();
+                                    let lines: Vec<String> = messages
+                                        .peek()
+                                        .iter()
+                                        .filter(|m| selected.peek().contains(&m.id))
+                                        .map(|m| match m.created_at {
+                                            Some(time) => format!(
+                                                "[{}] {}: {}",
+                                                format.full(time, now()),
+                                                sender_name(m),
+                                                m.display_text()
+                                            ),
+                                            None => format!("{}: {}", sender_name(m), m.display_text()),
+                                        })
+                                        .collect();
+                                    copy_to_clipboard(&lines.join("\n"));
+                                    selecting.set(false);
+                                    selected.write().clear();
+                                },
+                                "Copy"
+                            }
+                            button {
+                                class: "member-remove",
+                                onclick: move |_| {
+                                    selecting.set(false);
+                                    selected.write().clear();
+                                },
+                                "Cancel"
+                            }
+                        }
+                    }
+
+                    if let Some(change) = server_key_change() {
+                        ServerKeyWarning {
+                            change: change.clone(),
+                            on_trust: move |_| {
+                                trust_server_key(&change);
+                                server_key_change.set(None);
+                            },
+                        }
+                    }
+
+                    // Message input
+                    div { class: "message-input-area",
+                        textarea {
+                            id: "message-input",
+                            class: "message-input",
+                            aria_label: "Message",
+                            rows: "{input_rows}",
+                            spellcheck: if spellcheck() { "true" } else { "false" },
+                            lang: spellcheck_language().unwrap_or_else(system_language),
+                            placeholder: if send_blocked { "Review the key change above before sending" } else { "Type a message..." },
+                            disabled: send_blocked,
+                            value: "{message_input}",
+                            oninput: move |e| message_input.set(e.value()),
+                            onkeydown: {
+                                let mut do_send = do_send_message;
+                                move |e| {
+                                    // Shift+Enter is a line break
+                                    if e.key() == Key::Enter && !e.modifiers().shift() {
+                                        e.prevent_default();
+                                        do_send();
+                                    }
+                                }
+                            },
+                        }
+                        button {
+                            class: if show_preview() { "preview-btn active" } else { "preview-btn" },
+                            title: "Preview formatting: **bold**, *italic*, ~~strike~~, `code`, ``` blocks, > quotes, - lists",
+                            aria_label: "Preview formatting",
+                            aria_pressed: "{show_preview()}",
+                            onclick: move |_| show_preview.toggle(),
+                            "Aa"
+                        }
+                        select {
+                            class: if message_expiry().is_some() { "expiry-select active" } else { "expiry-select" },
+                            title: "Delete messages after they've been read",
+                            aria_label: "Disappearing messages",
+                            onchange: move |e| message_expiry.set(e.value().parse().ok()),
+                            option { value: "", selected: message_expiry().is_none(), "\u{1F525} Off" }
+                            for (seconds, label) in EXPIRY_CHOICES {
+                                option {
+                                    value: "{seconds}",
+                                    selected: message_expiry() == Some(*seconds),
+                                    "\u{1F525} {label}"
+                                }
+                            }
+                        }
+                        button {
+                            class: "send-btn",
+                            disabled: send_blocked,
+                            onclick: {
+                                let mut do_send = do_send_message;
+                                move |_| do_send()
+                            },
+                            "Send"
+                        }
+                    }
+                } else {
+                    div { class: "empty-state",
+                        "Select a room to start chatting"
+                    }
+                }
+            }
+        }
+
+        if let Some(update) = available_update() {
+            UpdateModal {
+                update,
+                on_close: move |_| available_update.set(None),
+            }
+        }
+
+        // Identity keys (backup / restore)
+        if show_identity() {
+            if let Some(user) = user.clone() {
+                IdentityKeysModal {
+                    user,
+                    on_close: move |_| show_identity.set(false),
+                    on_published: move |public_key: String| {
+                        if let Some(u) = current_user.write().as_mut() {
+                            u.public_key = Some(public_key);
+                        }
+                    },
+                }
+            }
+        }
+
+        // Personal access tokens for scripts and bridges
+        if show_tokens() {
+            ApiTokensModal {
+                rooms: rooms(),
+                on_close: move |_| show_tokens.set(false),
+            }
+        }
+
+        // New Room Modal
+        if show_new_room() {
+            NewRoomModal {
+                is_admin,
+                on_close: move |_| show_new_room.set(false),
+                on_created: move |room: Room| {
+                    rooms.write().push(room.clone());
+                    current_room.set(Some(room));
+                    messages.set(Vec::new());
+                    show_new_room.set(false);
+                },
+            }
+        }
+
+        // Add Member Modal
+        if show_add_member() {
+            div {
+                class: "modal-overlay",
+                onclick: move |_| show_add_member.set(false),
+                onkeydown: move |e| {
+                    if e.key() == Key::Escape {
+                        show_add_member.set(false);
+                    }
+                },
+                div {
+                    class: "modal",
+                    role: "dialog",
+                    aria_modal: "true",
+                    aria_labelledby: "add-member-title",
+                    onclick: move |e| e.stop_propagation(),
+                    h2 { id: "add-member-title", class: "modal-title", "Add Member" }
+
+                    div { class: "form-group",
+                        input {
+                            class: "input",
+                            aria_label: "Search users",
+                            autofocus: true,
+                            r#type: "text",
+                            placeholder: "Search users...",
+                            value: "{add_search}",
+                            oninput: move |e| add_search.set(e.value()),
+                        }
+                    }
+
+                    div { class: "user-list",
+                        {
+                            let search = add_search().to_lowercase();
+                            let member_ids: Vec<Uuid> = members().iter()
+                                .map(|m| m.user_id)
+                                .collect();
+                            let filtered: Vec<_> = all_users().iter()
+                                .filter(|u| {
+                                    let uname = u.username.to_lowercase();
+                                    !member_ids.contains(&u.id) &&
+                                    (search.is_empty() || uname.contains(&search))
+                                })
+                                .cloned()
+                                .collect();
+                            rsx! {
+                                if filtered.is_empty() {
+                                    div { style: "text-align: center; color: #999; padding: 20px;",
+                                        "No users to add"
+                                    }
+                                } else {
+                                    for user_val in filtered {
+                                        {
+                                            let uid = user_val.id.to_string();
+                                            let uname = user_val.username.clone();
+                                            let room_id = current_room().as_ref().map(|r| r.id.to_string()).unwrap_or_default();
+                                            let rid_refresh = room_id.clone();
+                                            rsx! {
+                                                div { class: "user-item",
+                                                    span { "{uname}" }
+                                                    button {
+                                                        class: "add-btn",
+                                                        aria_label: "Add {uname}",
+                                                        onclick: move |_| {
+                                                            let rid = room_id.clone();
+                                                            let uid = uid.clone();
+                                                            let rid_r = rid_refresh.clone();
+                                                            spawn(async move {
+                                                                let result = state.read().api.add_member(&rid, &uid).await;
+                                                                match result {
+                                                                    Ok(()) => {
+                                                                        if let Ok(m) = state.read().api.get_members(&rid_r).await {
+                                                                            members.set(m);
+                                                                        }
+                                                                    }
+                                                                    Err(e) => issues.report_api("Couldn't add the member", &e, None),
+                                                                }
+                                                            });
+                                                        },
+                                                        "Add"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    button {
+                        class: "btn btn-cancel",
+                        style: "margin-top: 15px;",
+                        onclick: move |_| show_add_member.set(false),
+                        "Close"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Tells the page when the message list is scrolled near an end that has
+/// more messages past it, then keeps the message at the top of the view in
+/// place while the slice changes, so the list doesn't jump
+const WINDOW_SCROLL_JS: &str = r#"
+const attach = () => {
+    const log = document.getElementById('message-log');
+    if (!log) {
+        setTimeout(attach, 100);
+        return;
+    }
+    if (log._windowScroll) log.removeEventListener('scroll', log._windowScroll);
+    let busy = false;
+    const move = (edge) => {
+        if (busy || !document.getElementById('window-' + edge)) return;
+        const anchor = Array.from(log.querySelectorAll('.message'))
+            .find(el => el.offsetTop + el.offsetHeight > log.scrollTop);
+        if (!anchor) return;
+        const offset = anchor.offsetTop - log.scrollTop;
+        busy = true;
+        const restore = new MutationObserver(() => {
+            restore.disconnect();
+            requestAnimationFrame(() => {
+                if (anchor.isConnected) log.scrollTop = anchor.offsetTop - offset;
+                busy = false;
+            });
+        });
+        restore.observe(log, { childList: true });
+        dioxus.send(edge);
+    };
+    log._windowScroll = () => {
+        if (log.scrollTop < 300) move('earlier');
+        else if (log.scrollHeight - log.scrollTop - log.clientHeight < 300) move('later');
+    };
+    log.addEventListener('scroll', log._windowScroll, { passive: true });
+};
+attach();
+"#;
+
+/// Something that failed, to run again from the status center
+#[derive(Clone)]
+enum Redo {
+    LoadRooms,
+    OpenRoom(Room),
+    Send(Uuid),
+}
@@ -0,0 +1,188 @@
+use crate::a11y::on_activate;
+use crate::state::AppState;
+use crate::storage::{load_config, save_config};
+use crate::styles::{HIGH_CONTRAST, STYLES};
+use crate::Route;
+use dioxus::prelude::*;
+
+#[component]
+pub fn Login() -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let nav = use_navigator();
+
+    let mut username = use_signal(String::new);
+    let mut password = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+    let mut loading = use_signal(|| false);
+    let high_contrast = use_hook(|| load_config().high_contrast);
+    // Offered when the server lets visitors in without an account
+    let mut guests_allowed = use_signal(|| false);
+    let mut guest_name = use_signal(String::new);
+
+    use_hook(move || {
+        spawn(async move {
+            let api = state.read().api.clone();
+            if let Ok(info) = api.get_server_info().await {
+                guests_allowed.set(info.features.guests);
+            }
+        });
+    });
+
+    let login = move |_| {
+        let user = username().trim().to_string();
+        let pass = password().trim().to_string();
+
+        if user.is_empty() || pass.is_empty() {
+            error.set(Some("Please fill in all fields".to_string()));
+            return;
+        }
+
+        spawn(async move {
+            loading.set(true);
+            error.set(None);
+
+            match state.read().api.login(&user, &pass).await {
+                Ok(auth) => {
+                    state.read().api.set_token(Some(auth.token.clone())).await;
+                    state
+                        .read()
+                        .api
+                        .set_refresh_token(auth.refresh_token.clone())
+                        .await;
+
+                    let mut config = load_config();
+                    config.token = Some(auth.token);
+                    config.refresh_token = auth.refresh_token;
+                    save_config(&config);
+
+                    nav.push(Route::Chat {});
+                }
+                Err(e) => {
+                    error.set(Some(e.to_string()));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
+    let join_as_guest = move |_| {
+        let name = guest_name().trim().to_string();
+        spawn(async move {
+            loading.set(true);
+            error.set(None);
+
+            let api = state.read().api.clone();
+            match api
+                .start_guest_session(Some(name.as_str()).filter(|n| !n.is_empty()))
+                .await
+            {
+                Ok(session) => {
+                    // Nothing to renew it with: it simply ends
+                    api.set_token(Some(session.token.clone())).await;
+                    api.set_refresh_token(None).await;
+
+                    let mut config = load_config();
+                    config.token = Some(session.token);
+                    config.refresh_token = None;
+                    save_config(&config);
+
+                    nav.push(Route::Chat {});
+                }
+                Err(e) => {
+                    error.set(Some(e.to_string()));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
+    rsx! {
+        style { {STYLES} }
+        if high_contrast {
+            style { {HIGH_CONTRAST} }
+        }
+        main { class: "container",
+            div { class: "card",
+                h1 { class: "title", "Login" }
+                p { class: "subtitle", "Welcome back!" }
+
+                if let Some(err) = error() {
+                    div { class: "error", role: "alert", "{err}" }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "username", "Username" }
+                    input {
+                        id: "username",
+                        class: "input",
+                        autofocus: true,
+                        r#type: "text",
+                        placeholder: "Enter username",
+                        value: "{username}",
+                        oninput: move |e| username.set(e.value()),
+                    }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "password", "Password" }
+                    input {
+                        id: "password",
+                        class: "input",
+                        r#type: "password",
+                        placeholder: "Enter password",
+                        value: "{password}",
+                        oninput: move |e| password.set(e.value()),
+                    }
+                }
+
+                button {
+                    class: "btn btn-primary",
+                    disabled: loading(),
+                    onclick: login,
+                    if loading() { "Logging in..." } else { "Login" }
+                }
+
+                p { class: "text-center",
+                    "Don't have an account? "
+                    span {
+                        class: "link",
+                        role: "link",
+                        tabindex: "0",
+                        onclick: move |_| { nav.push(Route::Register {}); },
+                        onkeydown: move |e| on_activate(e, || { nav.push(Route::Register {}); }),
+                        "Register"
+                    }
+                }
+
+                if guests_allowed() {
+                    div { class: "guest-join",
+                        p { class: "hint",
+                            "No account? Join this server's public rooms as a guest. Guest sessions are temporary and are deleted, with their messages, when they end."
+                        }
+                        input {
+                            class: "input",
+                            r#type: "text",
+                            maxlength: "100",
+                            aria_label: "Guest name (optional)",
+                            placeholder: "Name to show (optional)",
+                            value: "{guest_name}",
+                            oninput: move |e| guest_name.set(e.value()),
+                        }
+                        button {
+                            class: "btn btn-secondary",
+                            disabled: loading(),
+                            onclick: join_as_guest,
+                            "Join as Guest"
+                        }
+                    }
+                }
+
+                button {
+                    class: "btn btn-secondary",
+                    onclick: move |_| { nav.push(Route::Settings {}); },
+                    "← Change Server"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+mod chat;
+mod login;
+mod register;
+mod settings;
+
+pub use chat::Chat;
+pub use login::Login;
+pub use register::Register;
+pub use settings::Settings;
@@ -0,0 +1,196 @@
+use crate::a11y::on_activate;
+use crate::models::{QueueStatus, Registration};
+use crate::state::AppState;
+use crate::storage::{load_config, save_config};
+use crate::styles::{HIGH_CONTRAST, STYLES};
+use crate::Route;
+use dioxus::prelude::*;
+use std::time::Duration;
+
+/// How often a queued registration checks whether it has been activated
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[component]
+pub fn Register() -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let nav = use_navigator();
+
+    let mut username = use_signal(String::new);
+    let mut password = use_signal(String::new);
+    let mut email = use_signal(String::new);
+    let mut email_supported = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+    let mut loading = use_signal(|| false);
+    let high_contrast = use_hook(|| load_config().high_contrast);
+    // Set while the account waits in the server's registration queue
+    let mut queue_status = use_signal(|| None::<QueueStatus>);
+
+    // Only offer the email field when the server says it stores it
+    use_effect(move || {
+        spawn(async move {
+            if let Ok(info) = state.read().api.get_server_info().await {
+                email_supported.set(info.features.email);
+            }
+        });
+    });
+
+    let register = move |_| {
+        let user = username().trim().to_string();
+        let pass = password().trim().to_string();
+        let mail = email().trim().to_string();
+
+        if user.is_empty() || pass.is_empty() {
+            error.set(Some("Please fill in all fields".to_string()));
+            return;
+        }
+
+        if pass.len() < 8 {
+            error.set(Some("Password must be at least 8 characters".to_string()));
+            return;
+        }
+
+        spawn(async move {
+            loading.set(true);
+            error.set(None);
+
+            let mail = (email_supported() && !mail.is_empty()).then_some(mail);
+            let registered = match state
+                .read()
+                .api
+                .register(&user, &pass, mail.as_deref())
+                .await
+            {
+                Ok(Registration::Queued {
+                    queue_token,
+                    status,
+                }) => {
+                    queue_status.set(Some(status));
+                    // Wait for activation, then log in with the same credentials
+                    loop {
+                        tokio::time::sleep(QUEUE_POLL_INTERVAL).await;
+                        let api = state.read().api.clone();
+                        match api.get_registration_status(&queue_token).await {
+                            Ok(status) if status.activated => break,
+                            Ok(status) => queue_status.set(Some(status)),
+                            Err(e) => tracing::warn!("Queue status check failed: {}", e),
+                        }
+                    }
+                    queue_status.set(None);
+                    let api = state.read().api.clone();
+                    api.login(&user, &pass).await
+                }
+                Ok(Registration::Active(auth)) => Ok(auth),
+                Err(e) => Err(e),
+            };
+
+            match registered {
+                Ok(auth) => {
+                    state.read().api.set_token(Some(auth.token.clone())).await;
+                    state
+                        .read()
+                        .api
+                        .set_refresh_token(auth.refresh_token.clone())
+                        .await;
+
+                    let mut config = load_config();
+                    config.token = Some(auth.token);
+                    config.refresh_token = auth.refresh_token;
+                    save_config(&config);
+
+                    nav.push(Route::Chat {});
+                }
+                Err(e) => {
+                    error.set(Some(e.to_string()));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
+    let queue_notice = queue_status().map(|status| {
+        format!(
+            "Registration accepted \u{2014} you're #{} in line. Your account will be activated in about {} min; keep this window open.",
+            status.position,
+            status.estimated_wait_seconds.div_ceil(60).max(1)
+        )
+    });
+
+    rsx! {
+        style { {STYLES} }
+        if high_contrast {
+            style { {HIGH_CONTRAST} }
+        }
+        main { class: "container",
+            div { class: "card",
+                h1 { class: "title", "Register" }
+                p { class: "subtitle", "Create your account" }
+
+                if let Some(notice) = queue_notice {
+                    div { class: "queue-notice", role: "status", "{notice}" }
+                }
+
+                if let Some(err) = error() {
+                    div { class: "error", role: "alert", "{err}" }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "username", "Username" }
+                    input {
+                        id: "username",
+                        class: "input",
+                        autofocus: true,
+                        r#type: "text",
+                        placeholder: "Choose a username",
+                        value: "{username}",
+                        oninput: move |e| username.set(e.value()),
+                    }
+                }
+
+                if email_supported() {
+                    div { class: "form-group",
+                        label { class: "label", r#for: "email", "Email (optional)" }
+                        input {
+                            id: "email",
+                            class: "input",
+                            r#type: "email",
+                            placeholder: "you@example.com",
+                            value: "{email}",
+                            oninput: move |e| email.set(e.value()),
+                        }
+                    }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "password", "Password" }
+                    input {
+                        id: "password",
+                        class: "input",
+                        r#type: "password",
+                        placeholder: "At least 6 characters",
+                        value: "{password}",
+                        oninput: move |e| password.set(e.value()),
+                    }
+                }
+
+                button {
+                    class: "btn btn-primary",
+                    disabled: loading(),
+                    onclick: register,
+                    if loading() { "Creating account..." } else { "Register" }
+                }
+
+                p { class: "text-center",
+                    "Already have an account? "
+                    span {
+                        class: "link",
+                        role: "link",
+                        tabindex: "0",
+                        onclick: move |_| { nav.push(Route::Login {}); },
+                        onkeydown: move |e| on_activate(e, || { nav.push(Route::Login {}); }),
+                        "Login"
+                    }
+                }
+            }
+        }
+    }
+}
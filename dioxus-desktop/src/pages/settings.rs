@@ -0,0 +1,521 @@
+use crate::a11y::on_activate;
+use crate::background;
+use crate::components::{ServerKeyWarning, SettingsTransfer, UpdateModal};
+use crate::proxy::ProxyConfig;
+use crate::state::AppState;
+use crate::storage::{
+    check_server_key, load_config, save_config, trust_server_key, ServerKeyChange,
+};
+use crate::styles::{HIGH_CONTRAST, STYLES};
+use crate::tor_manager::{ConnectionMode, TorManager, TorStatus};
+use crate::updates::UpdateInfo;
+use crate::Route;
+use dioxus::prelude::*;
+use tor_chat_common::{build_info, BuildInfo};
+
+#[component]
+pub fn Settings() -> Element {
+    let state = use_context::<Signal<AppState>>();
+    let nav = use_navigator();
+
+    let mut server_url = use_signal(|| {
+        let config = load_config();
+        config
+            .server_url
+            .unwrap_or_else(|| "http://localhost:3000".to_string())
+    });
+    let mut error = use_signal(|| None::<String>);
+    let mut success = use_signal(|| None::<String>);
+    let mut loading = use_signal(|| false);
+    let mut tor_status_text = use_signal(|| None::<String>);
+    let mut tor_progress = use_signal(|| 0u8);
+    // The server's identity key differs from the one pinned on first connect
+    let mut server_key_change = use_signal(|| None::<ServerKeyChange>);
+    let mut via_tor = use_signal(|| load_config().clearnet_via_tor);
+
+    // Advanced network settings
+    let mut show_advanced = use_signal(|| false);
+    let mut high_contrast = use_signal(|| load_config().high_contrast);
+    let mut tor_proxy = use_signal(|| load_config().tor_proxy.unwrap_or_default());
+    let mut upstream_proxy = use_signal(|| load_config().upstream_proxy.unwrap_or_default());
+
+    let mut show_transfer = use_signal(|| false);
+
+    // Update check
+    let mut check_for_updates = use_signal(|| load_config().check_for_updates);
+    let mut update_url = use_signal(|| load_config().update_url.unwrap_or_default());
+    let mut update_status = use_signal(|| None::<String>);
+    let mut checking_update = use_signal(|| false);
+    let mut available_update = use_signal(|| None::<UpdateInfo>);
+
+    // This app's build next to the server's, once connected
+    let client_build = build_info!();
+    let mut server_build = use_signal(|| None::<BuildInfo>);
+    let clock = state.peek().api.clock();
+    use_hook(move || {
+        spawn(async move {
+            let build = state.read().server_build.read().await.clone();
+            server_build.set(build);
+        });
+    });
+
+    let check_update = move |_| {
+        let mut config = load_config();
+        config.update_url = Some(update_url().trim().to_string()).filter(|u| !u.is_empty());
+        save_config(&config);
+        checking_update.set(true);
+        update_status.set(Some("Checking for updates through Tor...".to_string()));
+        spawn(async move {
+            let app = state.read().clone();
+            match app.check_for_update().await {
+                Ok(Some(update)) => {
+                    update_status.set(None);
+                    available_update.set(Some(update));
+                }
+                Ok(None) => update_status.set(Some(format!(
+                    "You're running the latest version ({})",
+                    env!("CARGO_PKG_VERSION")
+                ))),
+                Err(e) => update_status.set(Some(e)),
+            }
+            checking_update.set(false);
+        });
+    };
+
+    let is_onion = TorManager::is_onion_url(&server_url());
+    let mode = ConnectionMode::detect(&server_url(), via_tor());
+    let exit_warning = (mode == ConnectionMode::TorExit)
+        .then(|| TorManager::exit_port_warning(&server_url()))
+        .flatten();
+
+    let connect = move || {
+        let raw_url = server_url().trim().to_string();
+        let clearnet_via_tor = via_tor();
+        let tor_proxy_raw = tor_proxy().trim().to_string();
+        let upstream_proxy_raw = upstream_proxy().trim().to_string();
+
+        spawn(async move {
+            loading.set(true);
+            error.set(None);
+            success.set(None);
+            server_key_change.set(None);
+            tor_status_text.set(None);
+            tor_progress.set(0);
+
+            // Apply the advanced network settings before anything connects
+            let applied = async {
+                let external = ProxyConfig::parse_optional(&tor_proxy_raw)?;
+                state
+                    .read()
+                    .tor_manager
+                    .set_external_proxy(external)
+                    .await?;
+                let upstream = ProxyConfig::parse_optional(&upstream_proxy_raw)?;
+                state
+                    .read()
+                    .api
+                    .set_direct_proxy(upstream)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            .await;
+            if let Err(e) = applied {
+                error.set(Some(e));
+                loading.set(false);
+                return;
+            }
+
+            let is_onion = TorManager::is_onion_url(&raw_url);
+            let url = if is_onion {
+                TorManager::normalize_onion_url(&raw_url)
+            } else {
+                raw_url.clone()
+            };
+            let use_tor = is_onion || clearnet_via_tor;
+
+            // Onion addresses (and clearnet servers the user wants behind Tor)
+            // need Tor bootstrapped first
+            if use_tor {
+                tor_status_text.set(Some("Starting Tor...".to_string()));
+
+                let mut status_rx = state.read().tor_manager.status_receiver();
+                let progress_done = std::rc::Rc::new(std::cell::Cell::new(false));
+                let progress_done_clone = progress_done.clone();
+                spawn(async move {
+                    while status_rx.changed().await.is_ok() {
+                        if progress_done_clone.get() {
+                            break;
+                        }
+                        let status = status_rx.borrow().clone();
+                        match &status {
+                            TorStatus::Bootstrapping(pct) => {
+                                tor_progress.set(*pct);
+                                tor_status_text
+                                    .set(Some(format!("Connecting to Tor network... {}%", pct)));
+                            }
+                            TorStatus::Connected { .. } | TorStatus::External => {
+                                tor_progress.set(100);
+                                tor_status_text.set(Some("Tor connected!".to_string()));
+                                break;
+                            }
+                            TorStatus::Error(e) => {
+                                tor_status_text.set(Some(format!("Tor error: {}", e)));
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+                });
+
+                match state.read().tor_manager.proxy().await {
+                    Ok(proxy) => {
+                        state.read().api.configure_tor_proxy(proxy).await;
+                        tor_status_text.set(Some("Tor connected!".to_string()));
+                        tor_progress.set(100);
+                    }
+                    Err(e) => {
+                        // Never fall back to a direct connection on our own
+                        error.set(Some(if is_onion {
+                            format!("Tor bootstrap failed: {}", e)
+                        } else {
+                            format!(
+                                "Tor bootstrap failed: {}. Not connecting directly, which would reveal your IP address; untick \"Route through Tor\" to connect without Tor.",
+                                e
+                            )
+                        }));
+                        loading.set(false);
+                        progress_done.set(true);
+                        return;
+                    }
+                }
+                progress_done.set(true);
+            } else {
+                state.read().api.disable_tor_proxy().await;
+            }
+
+            // Update API base URL
+            state.read().api.set_base_url(url.clone()).await;
+
+            // Check server health
+            match state.read().api.health_check().await {
+                Ok(_) => {
+                    // Same server as last time? Pins its key on first connect
+                    let info = state.read().api.get_server_info().await;
+                    let key = info.as_ref().ok().and_then(|i| i.signed_key());
+                    if let Some(change) = check_server_key(&url, key) {
+                        server_key_change.set(Some(change));
+                        loading.set(false);
+                        return;
+                    }
+
+                    // Prefer the server's onion address when it has one
+                    let url = if is_onion {
+                        url
+                    } else {
+                        tor_status_text.set(Some("Checking for onion address...".to_string()));
+                        let app = state.read().clone();
+                        match app.prefer_onion(&url).await {
+                            Some(onion) => {
+                                tor_status_text.set(Some("Switched to onion address".to_string()));
+                                onion
+                            }
+                            None => {
+                                tor_status_text.set(None);
+                                url
+                            }
+                        }
+                    };
+
+                    if use_tor || TorManager::is_onion_url(&url) {
+                        success.set(Some("Connected to server through Tor!".to_string()));
+                    } else {
+                        success.set(Some(
+                            "Connected directly \u{2014} the server can see your IP address."
+                                .to_string(),
+                        ));
+                    }
+
+                    let mut config = load_config();
+                    config.server_url = Some(url.clone());
+                    config.clearnet_via_tor = clearnet_via_tor;
+                    config.tor_proxy = Some(tor_proxy_raw).filter(|p| !p.is_empty());
+                    config.upstream_proxy = Some(upstream_proxy_raw).filter(|p| !p.is_empty());
+                    save_config(&config);
+
+                    *state.read().server_url.write().await = url;
+                    if let Ok(info) = state.read().api.get_server_info().await {
+                        *state.read().server_build.write().await = Some(info.build_info());
+                    }
+
+                    // Check if we have a token
+                    if let Some(token) = config.token {
+                        state.read().api.set_token(Some(token)).await;
+                        if state.read().api.get_me().await.is_ok() {
+                            nav.push(Route::Chat {});
+                            return;
+                        }
+                    }
+
+                    nav.push(Route::Login {});
+                }
+                Err(e) => {
+                    let hint = if use_tor && !is_onion {
+                        TorManager::exit_port_warning(&url)
+                            .map(|w| format!(" ({})", w))
+                            .unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+                    error.set(Some(format!("Failed to connect: {}{}", e, hint)));
+                }
+            }
+            loading.set(false);
+        });
+    };
+
+    // Started by the login item with the window hidden: reconnect with the
+    // saved settings straight away
+    use_hook(move || {
+        if background::take_auto_connect() && load_config().server_url.is_some() {
+            connect();
+        }
+    });
+
+    rsx! {
+        style { {STYLES} }
+        if high_contrast() {
+            style { {HIGH_CONTRAST} }
+        }
+        main { class: "container",
+            div { class: "card",
+                h1 { class: "title", "TOR Chat" }
+                p { class: "subtitle", "Secure Desktop Messenger" }
+
+                if let Some(err) = error() {
+                    div { class: "error", role: "alert", "{err}" }
+                }
+                if let Some(change) = server_key_change() {
+                    ServerKeyWarning {
+                        change: change.clone(),
+                        on_trust: move |_| {
+                            trust_server_key(&change);
+                            connect();
+                        },
+                    }
+                }
+                if let Some(msg) = success() {
+                    div { class: "success", role: "status", "{msg}" }
+                }
+
+                div { class: "form-group",
+                    label { class: "label", r#for: "server-url", "Server URL" }
+                    input {
+                        id: "server-url",
+                        class: "input",
+                        autofocus: true,
+                        r#type: "url",
+                        placeholder: "http://your-server:9274 or .onion",
+                        value: "{server_url}",
+                        oninput: move |e| server_url.set(e.value()),
+                    }
+                }
+
+                if is_onion {
+                    div { class: "tor-indicator onion",
+                        "Onion address detected — will connect via embedded Tor"
+                    }
+                } else if !server_url().trim().is_empty() {
+                    div { class: "checkbox-group",
+                        input {
+                            r#type: "checkbox",
+                            id: "clearnet-via-tor",
+                            checked: via_tor(),
+                            onchange: move |e| via_tor.set(e.checked()),
+                        }
+                        label { r#for: "clearnet-via-tor", "Route through Tor" }
+                    }
+                    if mode == ConnectionMode::TorExit {
+                        div { class: "tor-indicator onion",
+                            "Clearnet via Tor — the server sees a Tor exit, not your IP"
+                        }
+                        if let Some(warning) = exit_warning {
+                            div { class: "tor-indicator warning", "{warning}" }
+                        }
+                    } else {
+                        div { class: "tor-indicator warning",
+                            "\u{26A0} Direct connection — NOT anonymous. The server and your network can see your IP address."
+                        }
+                    }
+                }
+
+                div {
+                    class: "advanced-toggle",
+                    role: "button",
+                    tabindex: "0",
+                    aria_expanded: "{show_advanced()}",
+                    onclick: move |_| show_advanced.set(!show_advanced()),
+                    onkeydown: move |e| on_activate(e, || show_advanced.set(!show_advanced())),
+                    if show_advanced() { "\u{25BE} Advanced network settings" } else { "\u{25B8} Advanced network settings" }
+                }
+                if show_advanced() {
+                    div { class: "form-group",
+                        label { class: "label", r#for: "tor-proxy", "Tor SOCKS proxy" }
+                        input {
+                            id: "tor-proxy",
+                            class: "input",
+                            r#type: "text",
+                            placeholder: "Empty: embedded Tor. e.g. socks5h://127.0.0.1:9050",
+                            value: "{tor_proxy}",
+                            oninput: move |e| tor_proxy.set(e.value()),
+                        }
+                        p { class: "hint",
+                            "Use an existing Tor instead of the embedded one. To get through a corporate proxy, point this at a tor configured with Socks5Proxy/HTTPSProxy."
+                        }
+                    }
+                    div { class: "form-group",
+                        label { class: "label", r#for: "upstream-proxy", "Upstream proxy for direct connections" }
+                        input {
+                            id: "upstream-proxy",
+                            class: "input",
+                            r#type: "text",
+                            placeholder: "e.g. http://proxy.corp:3128 or socks5h://proxy:1080",
+                            value: "{upstream_proxy}",
+                            oninput: move |e| upstream_proxy.set(e.value()),
+                        }
+                        p { class: "hint",
+                            "Only used when not routing through Tor. Not anonymous: the proxy operator sees where you connect."
+                        }
+                    }
+                    div { class: "form-group",
+                        label { class: "label", "Updates" }
+                        div { class: "checkbox-group",
+                            input {
+                                r#type: "checkbox",
+                                id: "check-for-updates",
+                                checked: check_for_updates(),
+                                onchange: move |e| {
+                                    check_for_updates.set(e.checked());
+                                    let mut config = load_config();
+                                    config.check_for_updates = e.checked();
+                                    save_config(&config);
+                                },
+                            }
+                            label { r#for: "check-for-updates", "Check for updates at startup" }
+                        }
+                        input {
+                            class: "input",
+                            r#type: "url",
+                            placeholder: "Empty: the release channel built into this app",
+                            aria_label: "Update manifest URL",
+                            value: "{update_url}",
+                            oninput: move |e| update_url.set(e.value()),
+                        }
+                        button {
+                            class: "btn btn-secondary",
+                            disabled: checking_update(),
+                            onclick: check_update,
+                            "Check now"
+                        }
+                        if let Some(status) = update_status() {
+                            p { class: "hint", "{status}" }
+                        }
+                        p { class: "hint",
+                            "Updates are only looked up through Tor and only trusted if signed with the release key."
+                        }
+                    }
+                }
+
+                div {
+                    class: "advanced-toggle",
+                    role: "button",
+                    tabindex: "0",
+                    aria_expanded: "{show_transfer()}",
+                    onclick: move |_| show_transfer.set(!show_transfer()),
+                    onkeydown: move |e| on_activate(e, || show_transfer.set(!show_transfer())),
+                    if show_transfer() { "\u{25BE} Move settings to another device" } else { "\u{25B8} Move settings to another device" }
+                }
+                if show_transfer() {
+                    SettingsTransfer {
+                        on_imported: move |_| {
+                            let config = load_config();
+                            server_url.set(config.server_url.unwrap_or_default());
+                            via_tor.set(config.clearnet_via_tor);
+                            high_contrast.set(config.high_contrast);
+                            tor_proxy.set(config.tor_proxy.unwrap_or_default());
+                            upstream_proxy.set(config.upstream_proxy.unwrap_or_default());
+                            check_for_updates.set(config.check_for_updates);
+                            update_url.set(config.update_url.unwrap_or_default());
+                        },
+                    }
+                }
+
+                if let Some(status) = tor_status_text() {
+                    div { class: "tor-status", role: "status", "{status}" }
+                    div { class: "progress-bar",
+                        role: "progressbar",
+                        aria_label: "Tor connection",
+                        aria_valuemin: "0",
+                        aria_valuemax: "100",
+                        aria_valuenow: "{tor_progress()}",
+                        div {
+                            class: "progress-fill",
+                            style: "width: {tor_progress()}%",
+                        }
+                    }
+                }
+
+                button {
+                    class: "btn btn-primary",
+                    disabled: loading(),
+                    onclick: move |_| connect(),
+                    if loading() {
+                        if mode != ConnectionMode::Direct { "Connecting via Tor..." } else { "Connecting..." }
+                    } else {
+                        "Connect"
+                    }
+                }
+
+                p { class: "text-center",
+                    "Enter your TOR Chat server URL to connect"
+                }
+
+                div { class: "checkbox-group", style: "margin-top: 15px;",
+                    input {
+                        r#type: "checkbox",
+                        id: "high-contrast",
+                        checked: high_contrast(),
+                        onchange: move |e| {
+                            high_contrast.set(e.checked());
+                            let mut config = load_config();
+                            config.high_contrast = e.checked();
+                            save_config(&config);
+                        },
+                    }
+                    label { r#for: "high-contrast", "High contrast" }
+                }
+
+                div { class: "build-info",
+                    p { class: "hint", "This app: {client_build.describe()}" }
+                    if let Some(server) = server_build() {
+                        p { class: "hint", "Server: {server.describe()}" }
+                        if let Some(hint) = client_build.mismatch_hint(&server) {
+                            div { class: "tor-indicator warning", "{hint}" }
+                        }
+                    }
+                    if clock.is_noticeable() {
+                        p { class: "hint",
+                            "Your clock is {clock.describe()}. Message times are shown by the server's clock."
+                        }
+                    }
+                }
+            }
+
+            if let Some(update) = available_update() {
+                UpdateModal {
+                    update,
+                    on_close: move |_| available_update.set(None),
+                }
+            }
+        }
+    }
+}
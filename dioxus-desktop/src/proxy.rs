@@ -0,0 +1,120 @@
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProxyKind {
+    /// SOCKS5; hostnames are resolved by the proxy (socks5h)
+    Socks5,
+    /// HTTP proxy using CONNECT tunnels
+    Http,
+}
+
+/// A proxy given as a URL: `socks5h://host:port`, `socks5://host:port` or
+/// `http://host:port`. Used for an external Tor/SOCKS proxy instead of the
+/// embedded Tor, and for the upstream proxy of direct connections.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProxyConfig {
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+}
+
+impl ProxyConfig {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let parsed = url::Url::parse(raw.trim()).map_err(|e| format!("Invalid proxy URL: {e}"))?;
+        let kind = match parsed.scheme() {
+            "socks5" | "socks5h" => ProxyKind::Socks5,
+            "http" => ProxyKind::Http,
+            other => return Err(format!("Unsupported proxy scheme: {other}")),
+        };
+        let host = parsed
+            .host_str()
+            .ok_or("Proxy URL has no host")?
+            .to_string();
+        let port = parsed.port().unwrap_or(match kind {
+            ProxyKind::Socks5 => 1080,
+            ProxyKind::Http => 8080,
+        });
+        Ok(Self { kind, host, port })
+    }
+
+    /// The embedded Tor's local SOCKS bridge
+    pub fn local_socks(port: u16) -> Self {
+        Self {
+            kind: ProxyKind::Socks5,
+            host: "127.0.0.1".to_string(),
+            port,
+        }
+    }
+
+    /// Parse an optional setting; blank means no proxy
+    pub fn parse_optional(raw: &str) -> Result<Option<Self>, String> {
+        if raw.trim().is_empty() {
+            Ok(None)
+        } else {
+            Self::parse(raw).map(Some)
+        }
+    }
+
+    pub fn url(&self) -> String {
+        match self.kind {
+            // socks5h: DNS goes through the proxy too, so nothing leaks locally
+            ProxyKind::Socks5 => format!("socks5h://{}:{}", self.host, self.port),
+            ProxyKind::Http => format!("http://{}:{}", self.host, self.port),
+        }
+    }
+
+    pub fn reqwest(&self) -> Result<reqwest::Proxy, String> {
+        reqwest::Proxy::all(self.url()).map_err(|e| format!("Invalid proxy: {e}"))
+    }
+
+    /// Open a TCP tunnel to `host:port` through the proxy, for connections
+    /// reqwest doesn't make (the Socket.IO WebSocket)
+    pub async fn connect(&self, host: &str, port: u16) -> Result<TcpStream, String> {
+        match self.kind {
+            ProxyKind::Socks5 => {
+                Socks5Stream::connect((self.host.as_str(), self.port), (host, port))
+                    .await
+                    .map(Socks5Stream::into_inner)
+                    .map_err(|e| format!("SOCKS proxy error: {e}"))
+            }
+            ProxyKind::Http => self.http_connect(host, port).await,
+        }
+    }
+
+    async fn http_connect(&self, host: &str, port: u16) -> Result<TcpStream, String> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .map_err(|e| format!("HTTP proxy unreachable: {e}"))?;
+        let request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n\r\n");
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // Status line, then headers up to the blank line. The reader is only
+        // used for the proxy's reply, which ends before any tunnel data.
+        let mut reader = BufReader::new(&mut stream);
+        let mut status = String::new();
+        reader
+            .read_line(&mut status)
+            .await
+            .map_err(|e| e.to_string())?;
+        if status.split_whitespace().nth(1) != Some("200") {
+            return Err(format!("HTTP proxy refused tunnel: {}", status.trim()));
+        }
+        loop {
+            let mut line = String::new();
+            let read = reader
+                .read_line(&mut line)
+                .await
+                .map_err(|e| e.to_string())?;
+            if read == 0 || line == "\r\n" || line == "\n" {
+                break;
+            }
+        }
+
+        Ok(stream)
+    }
+}
@@ -0,0 +1,190 @@
+use crate::proxy::ProxyConfig;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, RwLock};
+use tokio_tungstenite::{tungstenite, MaybeTlsStream, WebSocketStream};
+
+/// Socket event received from the server
+#[derive(Debug, Clone)]
+pub struct SocketEvent {
+    pub name: String,
+    pub payload: Value,
+}
+
+/// Channel-based socket client that communicates with the backend's Socket.IO server.
+/// Events are delivered via an mpsc channel so the UI thread can poll them.
+#[derive(Clone)]
+pub struct SocketClient {
+    sender: Arc<RwLock<Option<mpsc::UnboundedSender<String>>>>,
+    connected: Arc<RwLock<bool>>,
+}
+
+impl Default for SocketClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SocketClient {
+    pub fn new() -> Self {
+        Self {
+            sender: Arc::new(RwLock::new(None)),
+            connected: Arc::new(RwLock::new(false)),
+        }
+    }
+
+    /// Connect to the Socket.IO server. Returns a receiver for incoming events.
+    /// With a `proxy` (Tor's SOCKS port, an external proxy, ...) the WebSocket
+    /// goes the same way as the API client, so it never bypasses it.
+    pub async fn connect(
+        &self,
+        server_url: &str,
+        token: &str,
+        proxy: Option<ProxyConfig>,
+    ) -> mpsc::UnboundedReceiver<SocketEvent> {
+        let (event_tx, event_rx) = mpsc::unbounded_channel::<SocketEvent>();
+
+        // Build Engine.IO WebSocket URL
+        let ws_url = server_url
+            .replace("http://", "ws://")
+            .replace("https://", "wss://");
+        let url = format!(
+            "{}/socket.io/?EIO=4&transport=websocket",
+            ws_url.trim_end_matches('/')
+        );
+
+        let connect_result = match &proxy {
+            Some(proxy) => Self::connect_via_proxy(&url, proxy).await,
+            None => tokio_tungstenite::connect_async(&url)
+                .await
+                .map(|(ws_stream, _)| ws_stream)
+                .map_err(|e| e.to_string()),
+        };
+        match connect_result {
+            Ok(ws_stream) => self.start(ws_stream, token, event_tx).await,
+            Err(e) => tracing::error!("WebSocket connect failed: {}", e),
+        }
+
+        event_rx
+    }
+
+    /// Open the WebSocket through a proxy tunnel; with SOCKS the hostname is
+    /// resolved by the proxy, so neither DNS nor the connection leak
+    async fn connect_via_proxy(
+        url: &str,
+        proxy: &ProxyConfig,
+    ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, String> {
+        let parsed = url::Url::parse(url).map_err(|e| e.to_string())?;
+        let host = parsed.host_str().ok_or("URL has no host")?.to_string();
+        let port = parsed.port_or_known_default().ok_or("URL has no port")?;
+
+        let tcp = proxy.connect(&host, port).await?;
+        let (ws_stream, _) = tokio_tungstenite::client_async_tls(url, tcp)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(ws_stream)
+    }
+
+    /// Run the Engine.IO/Socket.IO session on an open WebSocket
+    async fn start(
+        &self,
+        ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+        token: &str,
+        event_tx: mpsc::UnboundedSender<SocketEvent>,
+    ) {
+        let (mut write, mut read) = ws_stream.split();
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+
+        *self.sender.write().await = Some(tx.clone());
+        *self.connected.write().await = true;
+
+        let connected = self.connected.clone();
+        let token = token.to_string();
+
+        // Spawn writer task
+        let writer_connected = connected.clone();
+        tokio::spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                if write
+                    .send(tungstenite::Message::Text(msg.into()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+            *writer_connected.write().await = false;
+        });
+
+        // Spawn reader task
+        let ws_sender = tx;
+        tokio::spawn(async move {
+            let mut engine_io_open = false;
+
+            while let Some(msg_result) = read.next().await {
+                let msg = match msg_result {
+                    Ok(tungstenite::Message::Text(t)) => t.to_string(),
+                    Ok(tungstenite::Message::Ping(_)) => continue,
+                    Ok(tungstenite::Message::Close(_)) => break,
+                    Err(_) => break,
+                    _ => continue,
+                };
+
+                // Engine.IO protocol:
+                // '0' = open, '2' = ping, '3' = pong, '4' = message (Socket.IO)
+                if msg.starts_with('0') && !engine_io_open {
+                    engine_io_open = true;
+                    continue;
+                }
+
+                if msg == "2" {
+                    // Engine.IO ping -> respond with pong
+                    let _ = ws_sender.send("3".to_string());
+                    continue;
+                }
+
+                if msg.starts_with("40") {
+                    // Socket.IO CONNECT acknowledgment - authenticate
+                    let auth_payload = serde_json::json!({"token": token});
+                    let auth_msg = format!("42[\"authenticate\",{}]", auth_payload);
+                    let _ = ws_sender.send(auth_msg);
+                    continue;
+                }
+
+                if let Some(json_part) = msg.strip_prefix("42") {
+                    // Socket.IO EVENT packet
+                    if let Ok(arr) = serde_json::from_str::<Vec<Value>>(json_part) {
+                        if arr.len() >= 2 {
+                            if let Some(event_name) = arr[0].as_str() {
+                                let _ = event_tx.send(SocketEvent {
+                                    name: event_name.to_string(),
+                                    payload: arr[1].clone(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+
+            *connected.write().await = false;
+        });
+    }
+
+    pub async fn send_event(&self, event: &str, data: &Value) {
+        if let Some(tx) = self.sender.read().await.as_ref() {
+            let msg = format!("42[\"{}\",{}]", event, data);
+            let _ = tx.send(msg);
+        }
+    }
+
+    pub async fn is_connected(&self) -> bool {
+        *self.connected.read().await
+    }
+
+    pub async fn disconnect(&self) {
+        *self.sender.write().await = None;
+        *self.connected.write().await = false;
+    }
+}
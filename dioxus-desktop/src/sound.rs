@@ -0,0 +1,77 @@
+//! Notification sounds, played by the webview so no audio library is needed:
+//! a short built-in chime, or an audio file the user chose for a room.
+
+use crate::storage::RoomSound;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use dioxus::prelude::*;
+use std::path::Path;
+
+/// Larger files are refused; a notification sound is a second or two
+const MAX_SOUND_BYTES: u64 = 2 * 1024 * 1024;
+
+const CHIME: &str = r#"(() => {
+    const ctx = new AudioContext();
+    const osc = ctx.createOscillator();
+    const gain = ctx.createGain();
+    osc.frequency.value = 880;
+    gain.gain.setValueAtTime(0.15, ctx.currentTime);
+    gain.gain.exponentialRampToValueAtTime(0.001, ctx.currentTime + 0.4);
+    osc.connect(gain);
+    gain.connect(ctx.destination);
+    osc.onended = () => ctx.close();
+    osc.start();
+    osc.stop(ctx.currentTime + 0.4);
+})();"#;
+
+/// Play a room's sound; its file if it has one that still loads, otherwise
+/// the chime
+pub fn play(sound: &RoomSound) {
+    if sound.silent {
+        return;
+    }
+    let custom = sound.file.as_deref().map(data_url);
+    match custom {
+        Some(Ok(url)) => {
+            let quoted = serde_json::to_string(&url).unwrap_or_default();
+            document::eval(&format!("new Audio({quoted}).play().catch(() => {{}});"));
+        }
+        Some(Err(e)) => {
+            tracing::warn!("Notification sound unavailable, using the default: {}", e);
+            document::eval(CHIME);
+        }
+        None => {
+            document::eval(CHIME);
+        }
+    }
+}
+
+/// Whether `path` can be used as a notification sound; the error is for the
+/// user
+pub fn check_file(path: &str) -> Result<(), String> {
+    data_url(path).map(|_| ())
+}
+
+fn data_url(path: &str) -> Result<String, String> {
+    let path = Path::new(path);
+    let mime = match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("ogg" | "oga" | "opus") => "audio/ogg",
+        Some("mp3") => "audio/mpeg",
+        Some("wav") => "audio/wav",
+        Some("flac") => "audio/flac",
+        Some("m4a") => "audio/mp4",
+        _ => return Err("Use an .ogg, .mp3, .wav, .flac or .m4a file".to_string()),
+    };
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Can't read {}: {}", path.display(), e))?
+        .len();
+    if size > MAX_SOUND_BYTES {
+        return Err("Sound files can be at most 2 MB".to_string());
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+    Ok(format!("data:{};base64,{}", mime, BASE64.encode(bytes)))
+}
@@ -0,0 +1,118 @@
+use crate::api::ApiClient;
+use crate::socket::SocketClient;
+use crate::storage::{check_server_key, load_config};
+use crate::tor_manager::TorManager;
+use crate::updates::{self, UpdateInfo};
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+use tor_chat_common::BuildInfo;
+use uuid::Uuid;
+
+/// Where the user was in the chat, kept across an expired session so logging
+/// in again picks up from there
+#[derive(Debug, Clone)]
+pub struct ChatPlace {
+    pub user_id: Uuid,
+    pub room_id: Option<Uuid>,
+    /// Unsent message text
+    pub draft: String,
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub api: ApiClient,
+    pub socket: SocketClient,
+    pub tor_manager: Arc<TorManager>,
+    pub server_url: Arc<RwLock<String>>,
+    /// Build of the server last connected to, for the settings page
+    pub server_build: Arc<RwLock<Option<BuildInfo>>>,
+    pub chat_place: Arc<Mutex<Option<ChatPlace>>>,
+}
+
+impl AppState {
+    pub fn new(server_url: String, token: Option<String>, refresh_token: Option<String>) -> Self {
+        Self {
+            api: ApiClient::new(server_url.clone(), token, refresh_token),
+            socket: SocketClient::new(),
+            tor_manager: Arc::new(TorManager::new()),
+            server_url: Arc::new(RwLock::new(server_url)),
+            server_build: Arc::new(RwLock::new(None)),
+            chat_place: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Look for a newer release through Tor (bootstrapping it if needed)
+    pub async fn check_for_update(&self) -> Result<Option<UpdateInfo>, String> {
+        let configured = load_config().update_url;
+        let endpoint = updates::endpoint(configured.as_deref())
+            .ok_or("This build has no update channel configured")?;
+        let proxy = self.tor_manager.proxy().await?;
+        updates::check(&endpoint, &proxy).await
+    }
+
+    /// Start the embedded Tor again and move the client onto the new one,
+    /// for when its circuits stopped working
+    pub async fn restart_tor(&self) -> Result<(), String> {
+        if !self.api.is_using_tor().await {
+            return Err("Not connected through Tor".to_string());
+        }
+        self.tor_manager.stop().await;
+        let proxy = self.tor_manager.proxy().await?;
+        self.api.configure_tor_proxy(proxy).await;
+        Ok(())
+    }
+
+    pub async fn clear_auth(&self) {
+        self.api.set_token(None).await;
+        self.api.set_refresh_token(None).await;
+        self.socket.disconnect().await;
+    }
+
+    /// If the server advertises an onion address, move the API client onto it
+    /// through the embedded Tor. The onion has to sign with the same identity
+    /// key as the clearnet server, so a wrong address it was told can't lead
+    /// the client elsewhere. Returns the onion URL on success; on failure the
+    /// client stays on `clearnet_url`, still through Tor if it already was.
+    pub async fn prefer_onion(&self, clearnet_url: &str) -> Option<String> {
+        let was_tor = self.api.is_using_tor().await;
+        let clearnet = self.api.get_server_info().await.ok()?;
+        let onion = TorManager::normalize_onion_url(clearnet.addresses.onion.as_deref()?);
+
+        let proxy = match self.tor_manager.proxy().await {
+            Ok(proxy) => proxy,
+            Err(e) => {
+                tracing::warn!("Tor unavailable, staying on clearnet: {}", e);
+                return None;
+            }
+        };
+
+        self.api.configure_tor_proxy(proxy).await;
+        self.api.set_base_url(onion.clone()).await;
+        match self.api.get_server_info().await {
+            Ok(info)
+                if clearnet
+                    .signed_key()
+                    .is_some_and(|k| info.signed_key() != Some(k)) =>
+            {
+                tracing::warn!(
+                    "Onion address {} has a different identity key, staying on clearnet",
+                    onion
+                );
+            }
+            Ok(info) => match check_server_key(&onion, info.signed_key()) {
+                None => return Some(onion),
+                Some(_) => tracing::warn!(
+                    "Onion address {} doesn't match the key pinned for it, staying on clearnet",
+                    onion
+                ),
+            },
+            Err(_) => tracing::warn!("Onion address {} unreachable, staying on clearnet", onion),
+        }
+
+        if !was_tor {
+            self.api.disable_tor_proxy().await;
+        }
+        self.api.set_base_url(clearnet_url.to_string()).await;
+        None
+    }
+}
@@ -0,0 +1,403 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tor_chat_common::identity::fingerprint;
+use tor_chat_common::{IdentityError, IdentityKeys, KnownKeys, Sealed};
+use uuid::Uuid;
+
+fn get_config_dir() -> PathBuf {
+    directories::ProjectDirs::from("com", "torchat", "desktop")
+        .map(|dirs| dirs.config_dir().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn get_config_path() -> PathBuf {
+    get_config_dir().join("config.json")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AppConfig {
+    pub server_url: Option<String>,
+    pub token: Option<String>,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Smaller history pages and images only loaded on click; for slow or
+    /// metered Tor circuits
+    #[serde(default)]
+    pub low_bandwidth: bool,
+    /// Send dummy requests at random intervals to mask activity patterns
+    #[serde(default)]
+    pub cover_traffic: bool,
+    /// Route clearnet servers through the embedded Tor as well, so the
+    /// server only sees a Tor exit instead of the user's IP
+    #[serde(default)]
+    pub clearnet_via_tor: bool,
+    /// External Tor SOCKS proxy (e.g. socks5h://127.0.0.1:9050) used instead
+    /// of the embedded Tor
+    #[serde(default)]
+    pub tor_proxy: Option<String>,
+    /// Upstream HTTP/SOCKS proxy for direct (non-Tor) connections
+    #[serde(default)]
+    pub upstream_proxy: Option<String>,
+    /// Closing the window hides it to the tray; the connection stays up and
+    /// new messages show desktop notifications
+    #[serde(default)]
+    pub run_in_background: bool,
+    /// Registered as an OS login item that starts hidden
+    #[serde(default)]
+    pub start_at_login: bool,
+    /// Look for a new release (through Tor) once per launch
+    #[serde(default)]
+    pub check_for_updates: bool,
+    /// Release manifest URL instead of the one built in
+    #[serde(default)]
+    pub update_url: Option<String>,
+    /// "5m ago" instead of the clock time for recent messages
+    #[serde(default)]
+    pub relative_timestamps: bool,
+    /// Show times in UTC, so screenshots don't reveal the user's timezone
+    #[serde(default)]
+    pub utc_timestamps: bool,
+    /// Turn off the webview's spell checking in the message input
+    #[serde(default)]
+    pub disable_spellcheck: bool,
+    /// Language the message input is checked against; the system's if unset
+    #[serde(default)]
+    pub spellcheck_language: Option<String>,
+    /// Black and white theme with yellow accents and heavier borders
+    #[serde(default)]
+    pub high_contrast: bool,
+    /// Identity key each server signed its info with the first time we
+    /// connected, by origin ("http://abc.onion"); see `check_server_key`
+    #[serde(default)]
+    pub server_keys: HashMap<String, String>,
+    /// Per-room notification sounds; rooms not listed play the default one
+    #[serde(default)]
+    pub room_sounds: HashMap<Uuid, RoomSound>,
+}
+
+/// A room's notification sound. Kept on this device, unlike the room's
+/// notification level: the file is only here, and another device may
+/// well want to stay quiet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoomSound {
+    #[serde(default)]
+    pub silent: bool,
+    /// Audio file played instead of the built-in chime
+    #[serde(default)]
+    pub file: Option<String>,
+}
+
+pub fn room_sound(room_id: Uuid) -> RoomSound {
+    load_config()
+        .room_sounds
+        .get(&room_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub fn save_room_sound(room_id: Uuid, sound: RoomSound) {
+    let mut config = load_config();
+    if sound == RoomSound::default() {
+        config.room_sounds.remove(&room_id);
+    } else {
+        config.room_sounds.insert(room_id, sound);
+    }
+    save_config(&config);
+}
+
+/// Messages fetched when opening a room
+/// Translation target from the system locale ("de_DE.UTF-8" -> "de")
+pub fn system_language() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|v| !v.is_empty() && v != "C" && v != "POSIX")
+        .and_then(|v| v.split(['_', '.', '@', '-']).next().map(str::to_lowercase))
+        .filter(|lang| !lang.is_empty())
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// Languages offered for spell checking. The webview uses the dictionaries
+/// the system has; one that isn't installed just checks nothing.
+pub const SPELLCHECK_LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("de", "Deutsch"),
+    ("es", "Español"),
+    ("fr", "Français"),
+    ("it", "Italiano"),
+    ("nl", "Nederlands"),
+    ("pl", "Polski"),
+    ("pt", "Português"),
+    ("ru", "Русский"),
+    ("uk", "Українська"),
+    ("tr", "Türkçe"),
+    ("fa", "فارسی"),
+    ("ar", "العربية"),
+    ("he", "עברית"),
+];
+
+/// Scheme, host and port of `url`, which is what server keys are pinned by
+fn server_origin(url: &str) -> String {
+    url::Url::parse(url)
+        .map(|u| u.origin().ascii_serialization())
+        .unwrap_or_else(|_| url.trim_end_matches('/').to_string())
+}
+
+/// A server answered with a different identity key than the one pinned for
+/// its address, or stopped signing at all
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerKeyChange {
+    pub origin: String,
+    pub previous: String,
+    /// None when the server info wasn't signed
+    pub current: Option<String>,
+}
+
+impl ServerKeyChange {
+    pub fn previous_fingerprint(&self) -> String {
+        fingerprint(&self.previous)
+    }
+
+    pub fn current_fingerprint(&self) -> String {
+        self.current
+            .as_deref()
+            .map(fingerprint)
+            .unwrap_or_else(|| "not signed".to_string())
+    }
+}
+
+/// Check the identity key a server at `url` signed its info with (`None`
+/// when unsigned) against the one pinned for it (trust on first use). The
+/// first key seen is pinned; after that a different key, or no signature at
+/// all, is reported and the pinned key stays until `trust_server_key`.
+pub fn check_server_key(url: &str, key: Option<&str>) -> Option<ServerKeyChange> {
+    let origin = server_origin(url);
+    let mut config = load_config();
+    match (config.server_keys.get(&origin), key) {
+        (Some(pinned), Some(key)) if pinned == key => None,
+        (Some(pinned), current) => Some(ServerKeyChange {
+            previous: pinned.clone(),
+            current: current.map(str::to_string),
+            origin,
+        }),
+        (None, Some(key)) => {
+            config.server_keys.insert(origin, key.to_string());
+            save_config(&config);
+            None
+        }
+        // Servers from before signing: nothing to pin yet
+        (None, None) => None,
+    }
+}
+
+/// The user accepted `change`: pin the new key, or nothing if the server no
+/// longer signs
+pub fn trust_server_key(change: &ServerKeyChange) {
+    let mut config = load_config();
+    match &change.current {
+        Some(key) => config
+            .server_keys
+            .insert(change.origin.clone(), key.clone()),
+        None => config.server_keys.remove(&change.origin),
+    };
+    save_config(&config);
+}
+
+pub fn history_page_size(low_bandwidth: bool) -> u32 {
+    if low_bandwidth {
+        20
+    } else {
+        50
+    }
+}
+
+pub fn load_config() -> AppConfig {
+    let path = get_config_path();
+    if path.exists() {
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    } else {
+        AppConfig::default()
+    }
+}
+
+fn get_identity_path(user_id: &str) -> PathBuf {
+    get_config_dir().join(format!("identity-{}.json", user_id))
+}
+
+/// The user's identity keypair on this device, per account
+pub fn load_identity(user_id: &str) -> Option<IdentityKeys> {
+    fs::read_to_string(get_identity_path(user_id))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+pub fn save_identity(user_id: &str, keys: &IdentityKeys) -> std::io::Result<()> {
+    let path = get_identity_path(user_id);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(keys).unwrap_or_default(),
+    )
+}
+
+/// Other users' public keys as first seen by this account on this device
+pub fn load_known_keys(user_id: &str) -> KnownKeys {
+    fs::read_to_string(get_config_dir().join(format!("known-keys-{}.json", user_id)))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_known_keys(user_id: &str, keys: &KnownKeys) {
+    let dir = get_config_dir();
+    let _ = fs::create_dir_all(&dir);
+    let _ = fs::write(
+        dir.join(format!("known-keys-{}.json", user_id)),
+        serde_json::to_string_pretty(keys).unwrap_or_default(),
+    );
+}
+
+fn download_dir() -> PathBuf {
+    directories::UserDirs::new()
+        .and_then(|dirs| dirs.download_dir().map(|d| d.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Suggested location for a new identity backup
+pub fn default_backup_path(username: &str) -> PathBuf {
+    download_dir().join(format!("tor-chat-identity-{}.json", username))
+}
+
+/// Suggested location for exported settings
+pub fn default_settings_path() -> PathBuf {
+    download_dir().join("tor-chat-settings.json")
+}
+
+/// `format` field of exported settings, so other JSON isn't mistaken for them
+const SETTINGS_FORMAT: &str = "tor-chat-desktop-settings";
+const SETTINGS_VERSION: u32 = 1;
+
+/// Exported settings: the whole `AppConfig` but the session, which is only
+/// there sealed under a passphrase
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsFile {
+    format: String,
+    version: u32,
+    exported_at: DateTime<Utc>,
+    settings: AppConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session: Option<Sealed>,
+}
+
+/// What a settings file's sealed `session` holds
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionTokens {
+    token: Option<String>,
+    refresh_token: Option<String>,
+}
+
+fn parse_settings(file: &str) -> Result<SettingsFile, String> {
+    let file: SettingsFile = serde_json::from_str(file)
+        .ok()
+        .filter(|f: &SettingsFile| f.format == SETTINGS_FORMAT)
+        .ok_or_else(|| "This is not a TOR Chat settings file".to_string())?;
+    if file.version > SETTINGS_VERSION {
+        return Err("These settings were exported by a newer version of the app".to_string());
+    }
+    Ok(file)
+}
+
+/// Settings file contents for moving the app to another machine. The
+/// session is left out unless there's a passphrase to seal it with; `salt`
+/// and `nonce` must be fresh random bytes.
+pub fn export_settings(
+    config: &AppConfig,
+    passphrase: Option<&str>,
+    salt: [u8; 16],
+    nonce: [u8; 12],
+) -> Result<String, String> {
+    let session = match passphrase {
+        Some(passphrase) => {
+            let tokens = serde_json::to_vec(&SessionTokens {
+                token: config.token.clone(),
+                refresh_token: config.refresh_token.clone(),
+            })
+            .map_err(|e| e.to_string())?;
+            Some(Sealed::seal(passphrase, &tokens, salt, nonce).map_err(|e| e.to_string())?)
+        }
+        None => None,
+    };
+    let file = SettingsFile {
+        format: SETTINGS_FORMAT.to_string(),
+        version: SETTINGS_VERSION,
+        exported_at: Utc::now(),
+        settings: AppConfig {
+            token: None,
+            refresh_token: None,
+            ..config.clone()
+        },
+        session,
+    };
+    serde_json::to_string_pretty(&file).map_err(|e| e.to_string())
+}
+
+/// Whether exported settings carry a sealed session, i.e. importing them
+/// can ask for the passphrase
+pub fn settings_include_session(file: &str) -> bool {
+    parse_settings(file).is_ok_and(|f| f.session.is_some())
+}
+
+/// The config to replace `current` with from exported settings. The
+/// session comes along when `passphrase` opens the file's; otherwise the
+/// current one is kept if it's for the same server, and signing in again is
+/// needed if not.
+pub fn import_settings(
+    file: &str,
+    passphrase: Option<&str>,
+    current: &AppConfig,
+) -> Result<AppConfig, String> {
+    let file = parse_settings(file)?;
+    let mut config = file.settings;
+    match (file.session, passphrase) {
+        (Some(sealed), Some(passphrase)) => {
+            let tokens = sealed.open(passphrase).map_err(|e| match e {
+                IdentityError::WrongPassword => "Wrong passphrase".to_string(),
+                _ => "The saved session in this file is damaged".to_string(),
+            })?;
+            let tokens: SessionTokens = serde_json::from_slice(&tokens)
+                .map_err(|_| "The saved session in this file is damaged".to_string())?;
+            config.token = tokens.token;
+            config.refresh_token = tokens.refresh_token;
+        }
+        _ if config.server_url == current.server_url => {
+            config.token = current.token.clone();
+            config.refresh_token = current.refresh_token.clone();
+        }
+        _ => {
+            config.token = None;
+            config.refresh_token = None;
+        }
+    }
+    Ok(config)
+}
+
+pub fn save_config(config: &AppConfig) {
+    let path = get_config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(
+        &path,
+        serde_json::to_string_pretty(config).unwrap_or_default(),
+    );
+}
@@ -0,0 +1,220 @@
+/// Inline CSS shared by every page
+pub const STYLES: &str = r#"
+* { box-sizing: border-box; margin: 0; padding: 0; }
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; background: #1a1a2e; color: #eee; }
+.container { max-width: 400px; margin: 50px auto; padding: 20px; }
+.card { background: #16213e; border-radius: 12px; padding: 30px; box-shadow: 0 4px 20px rgba(0,0,0,0.3); }
+.title { font-size: 28px; text-align: center; margin-bottom: 10px; color: #9d4edd; }
+.subtitle { text-align: center; color: #888; margin-bottom: 25px; font-size: 14px; }
+.form-group { margin-bottom: 18px; }
+.label { display: block; margin-bottom: 6px; color: #aaa; font-size: 13px; }
+.input { width: 100%; padding: 12px 14px; border: 1px solid #333; border-radius: 8px; background: #0f0f23; color: #fff; font-size: 15px; outline: none; }
+.input:focus { border-color: #9d4edd; }
+.btn { width: 100%; padding: 14px; border: none; border-radius: 8px; font-size: 16px; font-weight: 600; cursor: pointer; transition: all 0.2s; }
+.btn-primary { background: linear-gradient(135deg, #9d4edd, #7b2cbf); color: #fff; }
+.btn-primary:hover { transform: translateY(-2px); box-shadow: 0 4px 15px rgba(157,78,221,0.4); }
+.btn-secondary { background: #333; color: #fff; margin-top: 10px; }
+.btn-secondary:hover { background: #444; }
+.btn-small { width: auto; padding: 6px 12px; font-size: 12px; }
+.btn-danger { background: #ff6b6b; color: #fff; }
+.btn-danger:hover { background: #e05555; }
+.btn-warning { background: #ffa726; color: #fff; }
+.btn-warning:hover { background: #e09020; }
+.error { background: #ff6b6b22; border: 1px solid #ff6b6b; color: #ff6b6b; padding: 12px; border-radius: 8px; margin-bottom: 15px; font-size: 14px; }
+.success { background: #51cf6622; border: 1px solid #51cf66; color: #51cf66; padding: 12px; border-radius: 8px; margin-bottom: 15px; font-size: 14px; }
+.link { color: #9d4edd; text-decoration: none; cursor: pointer; }
+.link:hover { text-decoration: underline; }
+.text-center { text-align: center; margin-top: 15px; font-size: 14px; color: #888; }
+.chat-container { display: flex; height: 100vh; }
+.sidebar { width: 280px; background: #16213e; border-right: 1px solid #333; display: flex; flex-direction: column; }
+.sidebar-header { padding: 20px; border-bottom: 1px solid #333; }
+.sidebar-title { font-size: 18px; font-weight: 600; color: #9d4edd; }
+.room-list { flex: 1; overflow-y: auto; }
+.room-item { padding: 15px 20px; border-bottom: 1px solid #222; cursor: pointer; transition: background 0.2s; }
+.room-item:hover { background: #1a1a2e; }
+.room-item.active { background: #9d4edd22; border-left: 3px solid #9d4edd; }
+.room-name { font-weight: 500; margin-bottom: 4px; display: flex; align-items: center; gap: 8px; }
+.room-desc { font-size: 12px; color: #999; }
+.room-item.unread .room-name { font-weight: 700; color: #fff; }
+.unread-badge { margin-left: auto; background: #9d4edd; color: #fff; font-size: 11px; font-weight: 700; border-radius: 9px; min-width: 18px; padding: 1px 6px; text-align: center; }
+.badge { font-size: 10px; padding: 2px 6px; border-radius: 4px; font-weight: 600; }
+.badge-public { background: #51cf6633; color: #51cf66; }
+.badge-private { background: #ffa72633; color: #ffa726; }
+.badge-full { background: #ff6b6b33; color: #ff6b6b; margin-left: 6px; }
+.main-content { flex: 1; display: flex; flex-direction: column; background: #1a1a2e; }
+.chat-header { padding: 20px; border-bottom: 1px solid #333; display: flex; justify-content: space-between; align-items: center; }
+.chat-title { font-size: 18px; font-weight: 600; }
+.chat-actions { display: flex; gap: 8px; }
+.messages { flex: 1; overflow-y: auto; padding: 20px; display: flex; flex-direction: column; gap: 12px; }
+.message { max-width: 70%; padding: 12px 16px; border-radius: 12px; }
+.message-own { align-self: flex-end; background: #9d4edd; color: #fff; border-bottom-right-radius: 4px; }
+.message-other { align-self: flex-start; background: #333; border-bottom-left-radius: 4px; }
+.message-user { font-size: 12px; font-weight: 600; margin-bottom: 4px; opacity: 0.8; }
+.message-content { font-size: 14px; line-height: 1.4; word-wrap: break-word; }
+.message-image { max-width: 320px; max-height: 320px; border-radius: 6px; display: block; }
+.message-attachment { font-size: 13px; padding: 8px 10px; border: 1px dashed #555; border-radius: 6px; cursor: pointer; opacity: 0.85; }
+.message-attachment:hover { opacity: 1; }
+.message-time { font-size: 10px; opacity: 0.6; margin-top: 4px; }
+.message-pending { opacity: 0.6; }
+.message-failed { background: #9d4edd88; border: 1px solid #ff6b6b; }
+.message-translate { font-size: 11px; opacity: 0.7; margin-top: 4px; cursor: pointer; text-decoration: underline; }
+.message-translation { font-size: 13px; margin-top: 6px; padding-left: 8px; border-left: 2px solid rgba(255,255,255,0.3); }
+.maintenance-banner { position: fixed; top: 0; left: 0; right: 0; z-index: 100; background: #b7791f; color: #fff; font-size: 13px; text-align: center; padding: 4px 12px; }
+.guest-join { border-top: 1px solid #333; margin-top: 15px; padding-top: 12px; }
+.guest-join .input { margin: 8px 0; }
+.guest-badge { font-size: 11px; color: #ffa94d; }
+.queue-notice { background: #1f2b4d; border: 1px solid #3a4d8f; color: #cdd8ff; padding: 10px 12px; border-radius: 6px; margin-bottom: 12px; font-size: 13px; }
+.key-change { background: #ff922b22; border: 1px solid #ff922b; border-radius: 8px; padding: 12px 14px; font-size: 13px; color: #ccc; }
+.key-change-title { color: #ffa94d; font-weight: 600; margin-bottom: 6px; }
+.key-change-fingerprints { font-family: monospace; font-size: 12px; color: #999; margin: 8px 0; }
+.key-change .btn { width: auto; padding: 6px 14px; margin: 0; }
+.server-key-change { margin: 0 0 15px; border-width: 2px; }
+.main-content > .server-key-change { margin: 8px 16px; }
+.message-expiry { font-size: 11px; color: #ffa94d; margin-top: 4px; }
+.expiry-select { padding: 0 10px; border: 1px solid #333; border-radius: 24px; background: #0f0f23; color: #888; font-size: 12px; outline: none; cursor: pointer; }
+.expiry-select.active { border-color: #ff922b; color: #ffa94d; }
+.message-retry { font-size: 11px; color: #ffd0d0; margin-top: 4px; cursor: pointer; text-decoration: underline; }
+.message-input-area { padding: 20px; border-top: 1px solid #333; display: flex; gap: 12px; }
+.message-input { flex: 1; padding: 14px; border: 1px solid #333; border-radius: 24px; background: #0f0f23; color: #fff; font-size: 14px; font-family: inherit; line-height: 1.4; outline: none; resize: none; max-height: 200px; }
+.message-preview { margin: 0 20px; padding: 10px 14px; border: 1px dashed #333; border-bottom: none; border-radius: 8px 8px 0 0; color: #ccc; max-height: 200px; overflow-y: auto; }
+.preview-btn { padding: 0 14px; border: 1px solid #333; border-radius: 24px; background: #0f0f23; color: #888; font-size: 13px; cursor: pointer; }
+.preview-btn.active { border-color: #9d4edd; color: #c77dff; }
+.md-line { min-height: 1.4em; white-space: pre-wrap; }
+.md-quote { border-left: 3px solid rgba(255,255,255,0.3); padding-left: 8px; opacity: 0.85; white-space: pre-wrap; }
+.md-item { padding-left: 6px; white-space: pre-wrap; }
+.md-pre { font-family: monospace; font-size: 13px; background: rgba(0,0,0,0.3); padding: 8px; border-radius: 6px; margin: 4px 0; white-space: pre-wrap; }
+.md-code { font-family: monospace; font-size: 13px; background: rgba(0,0,0,0.3); padding: 1px 4px; border-radius: 4px; }
+.md-link { color: #c77dff; text-decoration: underline; cursor: pointer; word-break: break-all; }
+.message-input:focus { border-color: #9d4edd; }
+.send-btn { padding: 14px 24px; background: #9d4edd; color: #fff; border: none; border-radius: 24px; cursor: pointer; font-weight: 600; }
+.send-btn:hover { background: #7b2cbf; }
+.empty-state { flex: 1; display: flex; align-items: center; justify-content: center; color: #999; font-size: 16px; }
+.new-room-btn { margin: 15px 20px; padding: 10px; background: #333; border: 1px dashed #555; border-radius: 8px; color: #aaa; cursor: pointer; text-align: center; font-size: 13px; }
+.new-room-btn:hover { background: #3a3a5a; border-color: #9d4edd; color: #9d4edd; }
+.user-info { padding: 15px 20px; border-top: 1px solid #333; display: flex; justify-content: space-between; align-items: center; }
+.username { font-weight: 500; }
+.logout-btn { padding: 6px 12px; background: #ff6b6b22; color: #ff6b6b; border: none; border-radius: 6px; cursor: pointer; font-size: 12px; }
+.logout-btn:hover { background: #ff6b6b33; }
+.identity-btn { margin-left: auto; margin-right: 8px; padding: 6px 8px; background: none; border: none; border-radius: 6px; cursor: pointer; font-size: 14px; }
+.identity-btn:hover { background: #ffffff11; }
+.tokens-modal { max-width: 520px; max-height: 85vh; overflow-y: auto; }
+.token-row { display: flex; align-items: center; gap: 10px; padding: 8px 0; border-bottom: 1px solid #ffffff11; }
+.token-info { flex: 1; min-width: 0; }
+.token-name { font-size: 14px; color: #eee; }
+.token-revoke { width: auto; padding: 6px 12px; }
+.token-secret { display: flex; align-items: center; gap: 8px; margin-bottom: 15px; }
+.token-secret code { flex: 1; font-family: monospace; font-size: 12px; color: #c77dff; word-break: break-all; background: #1a1a2e; border-radius: 6px; padding: 8px; }
+.token-secret .btn { width: auto; margin-top: 0; }
+.token-rooms { max-height: 150px; overflow-y: auto; margin-bottom: 10px; padding-left: 8px; }
+.fingerprint { font-family: monospace; font-size: 15px; color: #c77dff; letter-spacing: 1px; }
+.modal-overlay { position: fixed; top: 0; left: 0; right: 0; bottom: 0; background: rgba(0,0,0,0.7); display: flex; align-items: center; justify-content: center; z-index: 1000; }
+.modal { background: #16213e; border-radius: 12px; padding: 30px; width: 90%; max-width: 400px; }
+.modal-title { font-size: 20px; margin-bottom: 20px; text-align: center; color: #9d4edd; }
+.btn-cancel { background: #333; }
+.tor-indicator { display: flex; align-items: center; gap: 8px; padding: 10px 14px; border-radius: 8px; margin-bottom: 15px; font-size: 13px; }
+.tor-indicator.onion { background: #9d4edd22; border: 1px solid #9d4edd; color: #c77dff; }
+.advanced-toggle { font-size: 13px; color: #888; cursor: pointer; margin-bottom: 15px; user-select: none; }
+.advanced-toggle:hover { color: #c77dff; }
+.hint { font-size: 12px; color: #777; margin-top: 6px; }
+.build-info { margin-top: 20px; word-break: break-all; }
+.changelog { max-height: 200px; overflow-y: auto; white-space: pre-wrap; font-size: 13px; color: #ccc; background: #1a1a2e; border-radius: 8px; padding: 12px; margin-bottom: 15px; }
+.tor-indicator.warning { background: #ff922b22; border: 1px solid #ff922b; color: #ffa94d; }
+.connection-badge { display: inline-block; margin-top: 6px; padding: 2px 8px; border-radius: 10px; font-size: 11px; }
+.connection-badge.onion, .connection-badge.tor { background: #9d4edd22; border: 1px solid #9d4edd; color: #c77dff; }
+.connection-badge.direct { background: #ff922b22; border: 1px solid #ff922b; color: #ffa94d; }
+.progress-bar { width: 100%; height: 6px; background: #333; border-radius: 3px; overflow: hidden; margin-top: 8px; }
+.progress-fill { height: 100%; background: linear-gradient(90deg, #9d4edd, #c77dff); border-radius: 3px; transition: width 0.3s ease; }
+.tor-status { font-size: 13px; color: #c77dff; text-align: center; margin-bottom: 10px; }
+.members-panel { width: 250px; background: #16213e; border-left: 1px solid #333; display: flex; flex-direction: column; }
+.members-header { padding: 15px; border-bottom: 1px solid #333; display: flex; justify-content: space-between; align-items: center; }
+.drawer-item { padding: 10px 15px; border-bottom: 1px solid #222; cursor: pointer; }
+.drawer-item:hover { background: #1f2b4d; }
+.drawer-text { font-size: 13px; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+.drawer-empty { padding: 10px 15px; font-size: 12px; color: #888; }
+.drawer-section { border-top: 1px solid #333; }
+.message-highlight { outline: 2px solid #9d4edd; transition: outline 0.3s; }
+.message-reply { border-left: 3px solid #9d4edd; background: rgba(0, 0, 0, 0.2); padding: 4px 8px; margin-bottom: 6px; border-radius: 4px; cursor: pointer; }
+.message-reply:hover { background: rgba(0, 0, 0, 0.3); }
+.message-reply-author { font-size: 11px; font-weight: 600; color: #c77dff; }
+.message-reply-text { font-size: 12px; opacity: 0.8; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+.member-item { padding: 10px 15px; border-bottom: 1px solid #222; display: flex; justify-content: space-between; align-items: center; }
+.member-name { font-size: 14px; }
+.member-role { font-size: 11px; color: #9d4edd; }
+.member-remove { background: none; border: none; color: #ff6b6b; cursor: pointer; font-size: 11px; padding: 2px 6px; }
+.member-remove:hover { color: #ff4444; }
+.online-dot { width: 8px; height: 8px; border-radius: 50%; display: inline-block; margin-right: 6px; }
+.online-dot.on { background: #51cf66; }
+.online-dot.off { background: #555; }
+.checkbox-group { display: flex; align-items: center; gap: 8px; margin-bottom: 15px; }
+.checkbox-group input { width: 16px; height: 16px; }
+.checkbox-group label { color: #aaa; font-size: 13px; }
+.bandwidth-toggle { padding: 10px 20px 0; margin-bottom: 0; }
+.spellcheck-select { margin-left: auto; background: #0f0f23; color: #888; border: 1px solid #333; border-radius: 4px; font-size: 12px; outline: none; }
+.user-list { max-height: 300px; overflow-y: auto; }
+.user-item { padding: 8px 12px; display: flex; justify-content: space-between; align-items: center; border-bottom: 1px solid #222; }
+.add-btn { padding: 4px 10px; background: #9d4edd; color: #fff; border: none; border-radius: 4px; cursor: pointer; font-size: 12px; }
+.add-btn:hover { background: #7b2cbf; }
+.add-btn:disabled { opacity: 0.5; cursor: default; }
+.context-overlay { position: fixed; inset: 0; z-index: 50; }
+.context-menu { position: fixed; min-width: 180px; background: #16213e; border: 1px solid #333; border-radius: 6px; padding: 4px 0; box-shadow: 0 4px 16px rgba(0, 0, 0, 0.5); }
+.context-item { display: block; width: 100%; padding: 7px 14px; background: none; border: none; color: inherit; font: inherit; font-size: 13px; text-align: left; cursor: pointer; }
+.context-item:hover, .context-item:focus { background: #1f2b4d; color: #c77dff; }
+.context-heading { padding: 6px 14px 2px; font-size: 11px; text-transform: uppercase; color: #999; }
+.context-separator { height: 1px; margin: 4px 0; background: #333; }
+.context-check { display: inline-block; width: 18px; }
+.notify-menu { width: 260px; max-height: calc(100vh - 16px); overflow-y: auto; }
+.notify-sound { display: flex; gap: 6px; padding: 6px 14px; }
+.notify-sound .input { padding: 6px 8px; font-size: 12px; }
+.notify-sound .btn { width: auto; margin: 0; padding: 6px 10px; }
+.notify-error { padding: 4px 14px 6px; font-size: 12px; color: #ff6b6b; }
+.muted-icon { margin-left: 4px; font-size: 11px; opacity: 0.7; }
+.message-window-edge { align-self: center; padding: 4px 12px; background: transparent; border: 1px solid #333; border-radius: 12px; color: #aaa; font-size: 12px; cursor: pointer; }
+.message-selected { outline: 2px solid #c77dff; }
+.selection-bar { display: flex; align-items: center; gap: 10px; padding: 8px 16px; background: #16213e; border-top: 1px solid #333; font-size: 13px; }
+.selection-count { flex: 1; color: #aaa; }
+.skip-link { position: absolute; left: -9999px; top: 8px; z-index: 2000; padding: 8px 14px; background: #9d4edd; color: #fff; border: none; border-radius: 6px; font-size: 13px; cursor: pointer; }
+.skip-link:focus { left: 8px; }
+:focus-visible { outline: 2px solid #c77dff; outline-offset: 2px; }
+.status-toggle { position: fixed; right: 16px; bottom: 16px; z-index: 900; padding: 6px 12px; background: #ff6b6b22; border: 1px solid #ff6b6b; border-radius: 14px; color: #ff6b6b; font-size: 12px; cursor: pointer; }
+.status-panel { position: fixed; right: 16px; bottom: 52px; z-index: 900; width: 340px; max-height: 60vh; overflow-y: auto; background: #16213e; border: 1px solid #333; border-radius: 8px; box-shadow: 0 4px 16px rgba(0, 0, 0, 0.5); }
+.status-header { display: flex; justify-content: space-between; align-items: center; padding: 10px 14px; border-bottom: 1px solid #333; }
+.status-header .btn { margin-top: 0; }
+.status-note { padding: 8px 14px; font-size: 12px; color: #c77dff; border-bottom: 1px solid #222; }
+.status-item { padding: 10px 14px; border-bottom: 1px solid #222; }
+.status-meta { display: flex; gap: 8px; font-size: 11px; color: #888; margin-bottom: 4px; }
+.status-source { text-transform: uppercase; color: #ff6b6b; }
+.status-text { font-size: 13px; word-break: break-word; }
+.status-actions { display: flex; gap: 8px; margin-top: 8px; }
+.status-actions .btn { margin-top: 0; }
+"#;
+
+/// High contrast theme, layered over [`STYLES`]: black backgrounds, white
+/// text and borders, and yellow for accents and the focus ring
+pub const HIGH_CONTRAST: &str = r#"
+body, .main-content, .card, .modal, .sidebar, .members-panel, .context-menu, .selection-bar, .changelog { background: #000; color: #fff; }
+.sidebar, .members-panel, .sidebar-header, .chat-header, .members-header, .message-input-area, .user-info, .selection-bar { border-color: #fff; }
+.card, .modal, .context-menu { border: 2px solid #fff; }
+.room-item, .member-item, .drawer-item, .user-item { border-bottom: 1px solid #fff; }
+.room-item:hover, .drawer-item:hover, .context-item:hover, .context-item:focus { background: #333; color: #fff; }
+.room-item.active { background: #000; border-left: 4px solid #ffd400; }
+.title, .sidebar-title, .modal-title, .link, .md-link, .member-role, .fingerprint, .message-reply-author { color: #ffd400; }
+.subtitle, .label, .hint, .text-center, .room-desc, .empty-state, .drawer-empty, .selection-count, .checkbox-group label, .advanced-toggle, .tor-status { color: #fff; }
+.message-time, .message-translate, .message-user, .message-pending, .message-attachment { opacity: 1; }
+.message-own { background: #000; color: #fff; border: 2px solid #ffd400; }
+.message-other { background: #000; color: #fff; border: 2px solid #fff; }
+.message-failed { border: 2px dashed #ff8080; }
+.message-reply, .md-pre, .md-code { background: #000; border: 1px solid #fff; }
+.input, .message-input, .expiry-select, .preview-btn, .spellcheck-select { background: #000; color: #fff; border: 2px solid #fff; }
+.input::placeholder, .message-input::placeholder { color: #ccc; }
+.btn-primary, .send-btn, .add-btn, .unread-badge, .skip-link { background: #ffd400; color: #000; }
+.btn-secondary, .btn-cancel, .new-room-btn { background: #000; color: #fff; border: 2px solid #fff; }
+.btn-primary:hover, .send-btn:hover, .add-btn:hover { background: #fff; color: #000; box-shadow: none; transform: none; }
+.badge-public { background: #000; color: #7dff9a; border: 1px solid #7dff9a; }
+.badge-private, .badge-full { background: #000; color: #ffd400; border: 1px solid #ffd400; }
+.error { background: #000; color: #ff8080; border: 2px solid #ff8080; }
+.status-toggle { background: #000; color: #ff8080; border: 2px solid #ff8080; }
+.status-panel { background: #000; color: #fff; border: 2px solid #fff; }
+.success { background: #000; color: #7dff9a; border: 2px solid #7dff9a; }
+.online-dot.off { background: #000; border: 1px solid #fff; }
+:focus-visible { outline: 3px solid #ffd400; outline-offset: 2px; }
+"#;
@@ -0,0 +1,135 @@
+//! How message times are shown: relative ("5m ago") or absolute, in the
+//! system's locale and timezone, or in UTC for users who don't want their
+//! timezone readable from a screenshot.
+
+use crate::storage::AppConfig;
+use chrono::{DateTime, Datelike, Local, Locale, TimeZone, Utc};
+use std::fmt::Display;
+
+/// Relative times give way to the date after this many days
+const RELATIVE_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeFormat {
+    pub relative: bool,
+    pub utc: bool,
+    locale: Locale,
+}
+
+impl TimeFormat {
+    pub fn new(relative: bool, utc: bool) -> Self {
+        Self {
+            relative,
+            utc,
+            locale: system_locale(),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(config.relative_timestamps, config.utc_timestamps)
+    }
+
+    /// Shown under a message: relative, or the time alone for today and
+    /// date and time before that. `now` is the server's, like `time`.
+    pub fn message_time(&self, time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        if self.relative {
+            if let Some(ago) = relative(time, now) {
+                return ago;
+            }
+        }
+        if self.utc {
+            let today = now.date_naive() == time.date_naive();
+            self.absolute(time, now.year(), today, " UTC")
+        } else {
+            let (local, now) = (time.with_timezone(&Local), now.with_timezone(&Local));
+            let today = now.date_naive() == local.date_naive();
+            self.absolute(local, now.year(), today, "")
+        }
+    }
+
+    /// Date and time, for tooltips
+    pub fn full(&self, time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        if self.utc {
+            self.absolute(time, now.year(), false, " UTC")
+        } else {
+            let year = now.with_timezone(&Local).year();
+            self.absolute(time.with_timezone(&Local), year, false, "")
+        }
+    }
+
+    /// Date alone, e.g. for file lists
+    pub fn date(&self, time: DateTime<Utc>) -> String {
+        if self.utc {
+            self.localized(time, "%x")
+        } else {
+            self.localized(time.with_timezone(&Local), "%x")
+        }
+    }
+
+    /// `this_year` decides whether the year is worth showing
+    fn absolute<Tz: TimeZone>(
+        &self,
+        time: DateTime<Tz>,
+        this_year: i32,
+        time_only: bool,
+        suffix: &str,
+    ) -> String
+    where
+        Tz::Offset: Display,
+    {
+        // The locale's own time format (%X) has seconds, which are noise here
+        let clock = if self.uses_am_pm() {
+            "%I:%M %p"
+        } else {
+            "%H:%M"
+        };
+        let pattern = if time_only {
+            clock.to_string()
+        } else if time.year() == this_year {
+            format!("%e %b, {}", clock)
+        } else {
+            format!("%x {}", clock)
+        };
+        format!("{}{}", self.localized(time, &pattern).trim(), suffix)
+    }
+
+    fn localized<Tz: TimeZone>(&self, time: DateTime<Tz>, pattern: &str) -> String
+    where
+        Tz::Offset: Display,
+    {
+        time.format_localized(pattern, self.locale).to_string()
+    }
+
+    /// Whether the locale writes times with AM/PM. Without a locale (POSIX,
+    /// also what Windows ends up with) it's the 24-hour clock.
+    fn uses_am_pm(&self) -> bool {
+        self.locale != Locale::POSIX && !self.localized(Utc::now(), "%p").is_empty()
+    }
+}
+
+/// "just now", "5m ago", "3h ago", "2d ago"; None once it's too old to
+/// read well that way
+fn relative(time: DateTime<Utc>, now: DateTime<Utc>) -> Option<String> {
+    let secs = (now - time).num_seconds().max(0);
+    Some(match secs {
+        0..60 => "just now".to_string(),
+        60..3_600 => format!("{}m ago", secs / 60),
+        3_600..86_400 => format!("{}h ago", secs / 3_600),
+        _ if secs < RELATIVE_DAYS * 86_400 => format!("{}d ago", secs / 86_400),
+        _ => return None,
+    })
+}
+
+/// Locale for dates and times from the environment ("de_DE.UTF-8" ->
+/// de_DE), POSIX when unset or unknown
+fn system_locale() -> Locale {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|v| !v.is_empty())
+        .and_then(|v| {
+            let name = v.split(['.', '@']).next().unwrap_or_default().to_string();
+            Locale::try_from(name.as_str()).ok()
+        })
+        .unwrap_or(Locale::POSIX)
+}
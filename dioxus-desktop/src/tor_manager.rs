@@ -1,3 +1,4 @@
+use crate::proxy::{ProxyConfig, ProxyKind};
 use arti_client::config::CfgPath;
 use arti_client::{BootstrapBehavior, TorClient, TorClientConfig};
 use futures_util::StreamExt;
@@ -13,14 +14,70 @@ use tracing::{error, info, warn};
 pub enum TorStatus {
     Stopped,
     Bootstrapping(u8),
-    Connected { socks_port: u16 },
+    Connected {
+        socks_port: u16,
+    },
+    /// Using an external Tor/SOCKS proxy; the embedded Tor isn't started
+    External,
     Error(String),
 }
 
+/// How the app reaches the server; shown next to the server URL and in the chat sidebar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionMode {
+    /// Onion service, end to end inside Tor
+    Onion,
+    /// Clearnet server reached through a Tor exit
+    TorExit,
+    /// Straight connection; the server and network see the user's IP
+    Direct,
+}
+
+impl ConnectionMode {
+    pub fn detect(server_url: &str, via_tor: bool) -> Self {
+        if TorManager::is_onion_url(server_url) {
+            ConnectionMode::Onion
+        } else if via_tor {
+            ConnectionMode::TorExit
+        } else {
+            ConnectionMode::Direct
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConnectionMode::Onion => "Onion",
+            ConnectionMode::TorExit => "Via Tor",
+            ConnectionMode::Direct => "Direct",
+        }
+    }
+
+    pub fn class(self) -> &'static str {
+        match self {
+            ConnectionMode::Onion => "onion",
+            ConnectionMode::TorExit => "tor",
+            ConnectionMode::Direct => "direct",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ConnectionMode::Onion => "Connected to an onion service through the embedded Tor",
+            ConnectionMode::TorExit => {
+                "Clearnet server reached through a Tor exit; the server doesn't see your IP"
+            }
+            ConnectionMode::Direct => {
+                "Direct connection; the server and your network can see your IP address"
+            }
+        }
+    }
+}
+
 pub struct TorManager {
     status: watch::Sender<TorStatus>,
     status_rx: watch::Receiver<TorStatus>,
     tor_client: Arc<RwLock<Option<TorClient<PreferredRuntime>>>>,
+    external: RwLock<Option<ProxyConfig>>,
 }
 
 impl TorManager {
@@ -30,9 +87,32 @@ impl TorManager {
             status: tx,
             status_rx: rx,
             tor_client: Arc::new(RwLock::new(None)),
+            external: RwLock::new(None),
         }
     }
 
+    /// Reach Tor through an already-running SOCKS proxy (system tor, Tor
+    /// Browser, a tor configured with its own Socks5Proxy/HTTPSProxy to get
+    /// past a corporate proxy, ...) instead of the embedded client. The
+    /// embedded client can't itself be chained behind another proxy.
+    pub async fn set_external_proxy(&self, proxy: Option<ProxyConfig>) -> Result<(), String> {
+        if proxy.as_ref().is_some_and(|p| p.kind != ProxyKind::Socks5) {
+            return Err("The Tor proxy must be a SOCKS5 proxy".to_string());
+        }
+        *self.external.write().await = proxy;
+        Ok(())
+    }
+
+    /// Proxy that reaches the Tor network: the external proxy when set,
+    /// otherwise the embedded Tor's SOCKS bridge, bootstrapped on first use
+    pub async fn proxy(&self) -> Result<ProxyConfig, String> {
+        if let Some(proxy) = self.external.read().await.clone() {
+            let _ = self.status.send(TorStatus::External);
+            return Ok(proxy);
+        }
+        self.bootstrap().await.map(ProxyConfig::local_socks)
+    }
+
     pub fn status_receiver(&self) -> watch::Receiver<TorStatus> {
         self.status_rx.clone()
     }
@@ -46,6 +126,19 @@ impl TorManager {
         lower.contains(".onion") && !lower.contains(".onion.")
     }
 
+    /// Many Tor exit relays only allow web ports (reduced exit policy), so
+    /// clearnet servers on other ports may be unreachable through Tor
+    pub fn exit_port_warning(url: &str) -> Option<String> {
+        let parsed = url::Url::parse(url.trim()).ok()?;
+        let port = parsed.port_or_known_default()?;
+        (!matches!(port, 80 | 443)).then(|| {
+            format!(
+                "Port {} may be blocked by many Tor exits, which often only allow ports 80 and 443",
+                port
+            )
+        })
+    }
+
     /// Normalize a .onion URL: ensure it has http:// prefix (not https, Tor provides encryption)
     pub fn normalize_onion_url(url: &str) -> String {
         let trimmed = url.trim();
@@ -68,7 +161,7 @@ impl TorManager {
 
     /// Bootstrap the Tor client and start a local SOCKS5 proxy.
     /// Returns the SOCKS5 port on success.
-    pub async fn bootstrap(&self) -> Result<u16, String> {
+    async fn bootstrap(&self) -> Result<u16, String> {
         if let TorStatus::Connected { socks_port } = self.current_status() {
             return Ok(socks_port);
         }
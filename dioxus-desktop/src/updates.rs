@@ -0,0 +1,151 @@
+//! Update check. A signed release manifest is fetched through Tor (never a
+//! direct connection, so checking doesn't reveal who runs the app) and only
+//! trusted if it verifies against the release key built into the app.
+//!
+//! The endpoint serves `{"manifest": base64(JSON), "signature": base64}`,
+//! an Ed25519 signature over the exact manifest bytes.
+
+use crate::proxy::ProxyConfig;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Release signing key (base64 Ed25519 public key), set by release builds
+const RELEASE_KEY: Option<&str> = option_env!("TOR_CHAT_UPDATE_KEY");
+/// Manifest URL used unless the user configured another one
+const DEFAULT_ENDPOINT: Option<&str> = option_env!("TOR_CHAT_UPDATE_URL");
+/// Hidden services can be slow to answer
+const FETCH_TIMEOUT: Duration = Duration::from_secs(90);
+/// Manifests are a few KB; anything much bigger isn't one
+const MAX_MANIFEST_BYTES: usize = 256 * 1024;
+/// `app` field of our manifests, so another app's signed manifest can't be replayed
+const APP_ID: &str = "tor-chat-desktop";
+
+static STARTUP_CHECK_PENDING: AtomicBool = AtomicBool::new(true);
+
+#[derive(Deserialize)]
+struct Envelope {
+    manifest: String,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    app: String,
+    version: String,
+    #[serde(default)]
+    changelog: String,
+    /// Download per `std::env::consts::OS` ("linux", "windows", "macos")
+    #[serde(default)]
+    downloads: HashMap<String, String>,
+    /// Release page, for platforms without a direct download
+    release_url: String,
+}
+
+/// A newer release than the running one
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub changelog: String,
+    pub download_url: String,
+}
+
+/// Manifest URL: the user's setting, else the one built in. None when this
+/// build can't check (no endpoint, or no release key to verify against).
+pub fn endpoint(configured: Option<&str>) -> Option<String> {
+    RELEASE_KEY?;
+    configured
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .or(DEFAULT_ENDPOINT)
+        .map(str::to_string)
+}
+
+/// True once per process, for the chat page's check at startup
+pub fn take_startup_check() -> bool {
+    STARTUP_CHECK_PENDING.swap(false, Ordering::Relaxed)
+}
+
+/// Fetch and verify the manifest through `tor`; `Ok(None)` when up to date
+pub async fn check(endpoint: &str, tor: &ProxyConfig) -> Result<Option<UpdateInfo>, String> {
+    let client = reqwest::Client::builder()
+        .proxy(tor.reqwest()?)
+        .timeout(FETCH_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let response = client
+        .get(endpoint)
+        .send()
+        .await
+        .map_err(|e| format!("Update server unreachable: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Update server returned {}", response.status()));
+    }
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    if body.len() > MAX_MANIFEST_BYTES {
+        return Err("Update manifest is too large".to_string());
+    }
+
+    let manifest = verify(&body)?;
+    if !is_newer(&manifest.version, env!("CARGO_PKG_VERSION")) {
+        return Ok(None);
+    }
+    let download_url = manifest
+        .downloads
+        .get(std::env::consts::OS)
+        .cloned()
+        .unwrap_or(manifest.release_url);
+    Ok(Some(UpdateInfo {
+        version: manifest.version,
+        changelog: manifest.changelog,
+        download_url,
+    }))
+}
+
+fn verify(body: &[u8]) -> Result<Manifest, String> {
+    const INVALID: &str = "Update manifest signature is invalid";
+
+    let key: [u8; 32] = RELEASE_KEY
+        .and_then(|k| BASE64.decode(k).ok())
+        .and_then(|k| k.try_into().ok())
+        .ok_or("This build has no valid release key")?;
+    let key = VerifyingKey::from_bytes(&key).map_err(|_| "This build has no valid release key")?;
+
+    let envelope: Envelope = serde_json::from_slice(body).map_err(|_| INVALID)?;
+    let manifest = BASE64.decode(&envelope.manifest).map_err(|_| INVALID)?;
+    let signature: [u8; 64] = BASE64
+        .decode(&envelope.signature)
+        .ok()
+        .and_then(|s| s.try_into().ok())
+        .ok_or(INVALID)?;
+    key.verify_strict(&manifest, &Signature::from_bytes(&signature))
+        .map_err(|_| INVALID)?;
+
+    let manifest: Manifest = serde_json::from_slice(&manifest)
+        .map_err(|e| format!("Unreadable update manifest: {}", e))?;
+    if manifest.app != APP_ID {
+        return Err("Update manifest is for a different app".to_string());
+    }
+    Ok(manifest)
+}
+
+/// Compare dotted versions numerically ("0.10.0" > "0.9.2"); pre-release
+/// suffixes ("-beta") are ignored
+fn is_newer(candidate: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> {
+        v.trim_start_matches('v')
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(candidate) > parse(current)
+}
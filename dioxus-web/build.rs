@@ -0,0 +1,6 @@
+//! Embeds the git commit, build time and profile for the version display
+//! (`tor_chat_common::build_info!`)
+
+fn main() {
+    tor_chat_common::build_info::emit();
+}
@@ -1,11 +1,34 @@
-use crate::models::{LoginRequest, Message, RegisterRequest, Room, User};
+use crate::models::{
+    Digest, DigestSettings, InvitePreview, LoginRequest, MaintenanceStatus, Message,
+    NotificationSettings, QueueStatus, RegisterRequest, RequestSettings, RetentionRule, Room,
+    RoomFilesPage, RoomInvite, RoomRequest, RoomSearchHits, RoomStats, ServerInfo, Translation,
+    UnreadCount, User,
+};
+use crate::state::auth;
 use crate::utils::storage;
-use reqwest::Client;
+use chrono::{DateTime, Utc};
+use futures::lock::Mutex;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, StatusCode};
 use serde_json::Value;
+use std::sync::atomic::{AtomicI64, Ordering};
+use tor_chat_common::ClockOffset;
+
+/// Requests whose 401 means bad credentials, not an expired session
+const NO_RENEWAL_PATHS: [&str; 4] = [
+    "/api/auth/login",
+    "/api/auth/register",
+    "/api/auth/refresh",
+    "/api/auth/logout",
+];
 
 pub struct ApiClient {
     client: Client,
     base_url: String,
+    /// Held while renewing the session, so parallel 401s share one refresh
+    renewing: Mutex<()>,
+    /// Last measured `ClockOffset`, in ms
+    clock_offset: AtomicI64,
 }
 
 impl ApiClient {
@@ -15,6 +38,97 @@ impl ApiClient {
         Self {
             client: Client::new(),
             base_url,
+            renewing: Mutex::new(()),
+            clock_offset: AtomicI64::new(0),
+        }
+    }
+
+    /// How far the browser's clock is from the server's, as last measured
+    pub fn clock(&self) -> ClockOffset {
+        ClockOffset::from_millis(self.clock_offset.load(Ordering::Relaxed))
+    }
+
+    pub fn set_clock(&self, offset: ClockOffset) {
+        self.clock_offset.store(offset.millis(), Ordering::Relaxed);
+    }
+
+    /// The current time by the server's clock, for comparing with times the
+    /// server sent
+    pub fn server_now(&self) -> DateTime<Utc> {
+        let local = Utc::now().timestamp_millis();
+        DateTime::from_timestamp_millis(self.clock().to_server(local)).unwrap_or_else(Utc::now)
+    }
+
+    /// Send a request. A 401 on an authenticated request renews the session
+    /// with the refresh token and resends once; only if that fails is the
+    /// session over, and the app goes to the login page (see
+    /// `auth::expire_session`). Pages never have to look for 401s themselves.
+    async fn send(&self, builder: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+        let request = builder.build().map_err(|e| e.to_string())?;
+        let sent_token = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .map(str::to_string)
+            .filter(|_| !NO_RENEWAL_PATHS.contains(&request.url().path()));
+        let retry = request.try_clone();
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(|e| e.to_string())?;
+        let Some(sent_token) = sent_token.filter(|_| response.status() == StatusCode::UNAUTHORIZED)
+        else {
+            return Ok(response);
+        };
+
+        if !self.renew_session(&sent_token).await? {
+            auth::expire_session();
+            return Ok(response);
+        }
+        // Multipart bodies can't be resent
+        let Some(mut retry) = retry else {
+            return Err("Your session was renewed, please try again".to_string());
+        };
+        if let Some(header) = self.get_auth_header().and_then(|h| h.parse().ok()) {
+            retry.headers_mut().insert(AUTHORIZATION, header);
+        }
+        self.client.execute(retry).await.map_err(|e| e.to_string())
+    }
+
+    /// Trade the refresh token for a new token pair. `failed_token` is the
+    /// access token that got the 401; if another request has replaced it
+    /// meanwhile, the session is already renewed. Ok(false) when it can't be:
+    /// no refresh token, or the server refused it.
+    async fn renew_session(&self, failed_token: &str) -> Result<bool, String> {
+        let _renewing = self.renewing.lock().await;
+        if storage::get_token().is_some_and(|token| token != failed_token) {
+            return Ok(true);
+        }
+        let Some(refresh_token) = storage::get_refresh_token() else {
+            return Ok(false);
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/api/auth/refresh", self.base_url))
+            .json(&serde_json::json!({ "refreshToken": refresh_token }))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Ok(false);
+        }
+        let body: Value = response.json().await.map_err(|e| e.to_string())?;
+        match (body["token"].as_str(), body["refreshToken"].as_str()) {
+            (Some(token), Some(refresh_token)) => {
+                storage::save_token(token);
+                storage::save_refresh_token(refresh_token);
+                Ok(true)
+            }
+            _ => Ok(false),
         }
     }
 
@@ -64,15 +178,33 @@ impl ApiClient {
         req
     }
 
+    // Server endpoints
+    pub async fn get_server_info(&self) -> Result<ServerInfo, String> {
+        let request = self.request(reqwest::Method::GET, "/api/server-info").await;
+        let sent = Utc::now().timestamp_millis();
+        let response = self.send(request).await?;
+        let received = Utc::now().timestamp_millis();
+
+        if response.status().is_success() {
+            let info: ServerInfo = response.json().await.map_err(|e| e.to_string())?;
+            if let Some(server_time) = info.server_time {
+                self.set_clock(ClockOffset::measure(sent, server_time, received));
+            }
+            Ok(info)
+        } else {
+            Err(format!("Failed to get server info: {}", response.status()))
+        }
+    }
+
     // Auth endpoints
     pub async fn register(&self, req: RegisterRequest) -> Result<Value, String> {
         let response = self
-            .request(reqwest::Method::POST, "/api/auth/register")
-            .await
-            .json(&req)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(
+                self.request(reqwest::Method::POST, "/api/auth/register")
+                    .await
+                    .json(&req),
+            )
+            .await?;
 
         if response.status().is_success() {
             response.json().await.map_err(|e| e.to_string())
@@ -81,14 +213,33 @@ impl ApiClient {
         }
     }
 
+    pub async fn get_registration_status(&self, queue_token: &str) -> Result<QueueStatus, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::GET,
+                    &format!("/api/auth/registration-queue/{}", queue_token),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["status"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get queue status").await)
+        }
+    }
+
     pub async fn login(&self, req: LoginRequest) -> Result<Value, String> {
         let response = self
-            .request(reqwest::Method::POST, "/api/auth/login")
-            .await
-            .json(&req)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(
+                self.request(reqwest::Method::POST, "/api/auth/login")
+                    .await
+                    .json(&req),
+            )
+            .await?;
 
         if response.status().is_success() {
             response.json().await.map_err(|e| e.to_string())
@@ -98,12 +249,22 @@ impl ApiClient {
     }
 
     pub async fn logout(&self) -> Result<(), String> {
+        // The refresh token outlives the access token, so it's revoked on its own
+        if let Some(refresh_token) = storage::get_refresh_token() {
+            let _ = self
+                .client
+                .delete(format!("{}/api/auth/refresh", self.base_url))
+                .json(&serde_json::json!({ "refreshToken": refresh_token }))
+                .send()
+                .await;
+        }
+
         let response = self
-            .request(reqwest::Method::POST, "/api/auth/logout")
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(
+                self.request(reqwest::Method::POST, "/api/auth/logout")
+                    .await,
+            )
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -114,11 +275,8 @@ impl ApiClient {
 
     pub async fn get_me(&self) -> Result<User, String> {
         let response = self
-            .request(reqwest::Method::GET, "/api/auth/me")
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(self.request(reqwest::Method::GET, "/api/auth/me").await)
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -128,14 +286,29 @@ impl ApiClient {
         }
     }
 
+    /// Publish the identity public key this device holds
+    pub async fn update_public_key(&self, public_key: &str) -> Result<(), String> {
+        let body = serde_json::json!({ "publicKey": public_key });
+        let response = self
+            .send(
+                self.request(reqwest::Method::PUT, "/api/auth/public-key")
+                    .await
+                    .json(&body),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to publish identity key").await)
+        }
+    }
+
     // Room endpoints
     pub async fn get_rooms(&self) -> Result<Vec<Room>, String> {
         let response = self
-            .request(reqwest::Method::GET, "/api/rooms")
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(self.request(reqwest::Method::GET, "/api/rooms").await)
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -145,25 +318,43 @@ impl ApiClient {
         }
     }
 
+    pub async fn get_unread_counts(&self) -> Result<Vec<UnreadCount>, String> {
+        let response = self
+            .send(
+                self.request(reqwest::Method::GET, "/api/rooms/unread")
+                    .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["rooms"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get unread counts").await)
+        }
+    }
+
     pub async fn create_room(
         &self,
         name: String,
         description: Option<String>,
         is_public: bool,
+        max_members: Option<i32>,
     ) -> Result<Room, String> {
         let body = serde_json::json!({
             "name": name,
             "description": description,
             "isPublic": is_public,
+            "maxMembers": max_members,
         });
 
         let response = self
-            .request(reqwest::Method::POST, "/api/rooms")
-            .await
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(
+                self.request(reqwest::Method::POST, "/api/rooms")
+                    .await
+                    .json(&body),
+            )
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -184,11 +375,8 @@ impl ApiClient {
             room_id, limit, offset
         );
         let response = self
-            .request(reqwest::Method::GET, &url)
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(self.request(reqwest::Method::GET, &url).await)
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -198,11 +386,36 @@ impl ApiClient {
         }
     }
 
+    /// A message with up to `around` messages on each side, oldest first
+    pub async fn get_message_context(
+        &self,
+        room_id: &str,
+        message_id: &str,
+        around: usize,
+    ) -> Result<Vec<Message>, String> {
+        let url = format!(
+            "/api/rooms/{}/messages/{}/context?around={}",
+            room_id, message_id, around
+        );
+        let response = self
+            .send(self.request(reqwest::Method::GET, &url).await)
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["messages"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to open message").await)
+        }
+    }
+
     pub async fn send_message(
         &self,
         room_id: &str,
         content: &str,
         reply_to: Option<&str>,
+        client_nonce: Option<&str>,
+        expires_in: Option<i32>,
     ) -> Result<Message, String> {
         let mut body = serde_json::json!({
             "content": content,
@@ -211,17 +424,23 @@ impl ApiClient {
         if let Some(reply_id) = reply_to {
             body["replyTo"] = serde_json::json!(reply_id);
         }
+        if let Some(nonce) = client_nonce {
+            body["clientNonce"] = serde_json::json!(nonce);
+        }
+        if let Some(seconds) = expires_in {
+            body["expiresIn"] = serde_json::json!(seconds);
+        }
 
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/rooms/{}/messages", room_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/rooms/{}/messages", room_id),
+                )
+                .await
+                .json(&body),
             )
-            .await
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -233,14 +452,14 @@ impl ApiClient {
 
     pub async fn join_room(&self, room_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/rooms/{}/join", room_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/rooms/{}/join", room_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -267,8 +486,8 @@ impl ApiClient {
             req = req.header("Authorization", auth);
         }
 
-        let response = req
-            .send()
+        let response = self
+            .send(req)
             .await
             .map_err(|e| format!("Upload request failed: {}", e))?;
 
@@ -279,43 +498,50 @@ impl ApiClient {
         }
     }
 
-    pub async fn send_image_message(
-        &self,
-        room_id: &str,
-        image_url: &str,
-    ) -> Result<Message, String> {
+    /// Post an uploaded file to a room. `file` is the `file` object from the
+    /// upload response; its details go into the message metadata.
+    pub async fn send_file_message(&self, room_id: &str, file: &Value) -> Result<Message, String> {
+        let mime_type = file["mimetype"].as_str().unwrap_or_default();
+        let message_type = match mime_type.split('/').next() {
+            Some("image") => "image",
+            Some("video") => "video",
+            Some("audio") => "audio",
+            _ => "file",
+        };
         let body = serde_json::json!({
-            "content": image_url,
-            "messageType": "image",
+            "content": file["url"],
+            "messageType": message_type,
+            "metadata": {
+                "originalName": file["originalName"],
+                "mimetype": file["mimetype"],
+                "size": file["size"],
+            },
         });
 
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/rooms/{}/messages", room_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/rooms/{}/messages", room_id),
+                )
+                .await
+                .json(&body),
             )
-            .await
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
             serde_json::from_value(data["message"].clone()).map_err(|e| e.to_string())
         } else {
-            Err(Self::parse_error(response, "Failed to send image message").await)
+            Err(Self::parse_error(response, "Failed to send file").await)
         }
     }
 
     // Admin endpoints
     pub async fn get_users(&self) -> Result<Vec<Value>, String> {
         let response = self
-            .request(reqwest::Method::GET, "/api/auth/users")
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(self.request(reqwest::Method::GET, "/api/auth/users").await)
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -328,15 +554,15 @@ impl ApiClient {
     pub async fn add_room_member(&self, room_id: &str, user_id: &str) -> Result<(), String> {
         let body = serde_json::json!({ "userId": user_id });
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/rooms/{}/members", room_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/rooms/{}/members", room_id),
+                )
+                .await
+                .json(&body),
             )
-            .await
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -347,14 +573,14 @@ impl ApiClient {
 
     pub async fn remove_room_member(&self, room_id: &str, user_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::DELETE,
-                &format!("/api/rooms/{}/members/{}", room_id, user_id),
+            .send(
+                self.request(
+                    reqwest::Method::DELETE,
+                    &format!("/api/rooms/{}/members/{}", room_id, user_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -365,11 +591,8 @@ impl ApiClient {
 
     pub async fn admin_get_stats(&self) -> Result<Value, String> {
         let response = self
-            .request(reqwest::Method::GET, "/api/admin/stats")
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(self.request(reqwest::Method::GET, "/api/admin/stats").await)
+            .await?;
 
         if response.status().is_success() {
             response.json().await.map_err(|e| e.to_string())
@@ -378,13 +601,26 @@ impl ApiClient {
         }
     }
 
+    /// Delete uploads nothing references; returns `{removedFiles, removedBytes}`
+    pub async fn admin_clean_orphans(&self) -> Result<Value, String> {
+        let response = self
+            .send(
+                self.request(reqwest::Method::POST, "/api/admin/storage/clean-orphans")
+                    .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to clean orphaned files").await)
+        }
+    }
+
     pub async fn admin_get_users(&self) -> Result<Vec<User>, String> {
         let response = self
-            .request(reqwest::Method::GET, "/api/admin/users")
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(self.request(reqwest::Method::GET, "/api/admin/users").await)
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -396,14 +632,14 @@ impl ApiClient {
 
     pub async fn admin_ban_user(&self, user_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/admin/users/{}/ban", user_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/admin/users/{}/ban", user_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -412,16 +648,156 @@ impl ApiClient {
         }
     }
 
+    pub async fn admin_set_shadow_ban(&self, user_id: &str, banned: bool) -> Result<(), String> {
+        let action = if banned { "shadow-ban" } else { "shadow-unban" };
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/admin/users/{}/{}", user_id, action),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to update shadow ban").await)
+        }
+    }
+
+    pub async fn admin_get_maintenance(&self) -> Result<MaintenanceStatus, String> {
+        let response = self
+            .send(
+                self.request(reqwest::Method::GET, "/api/admin/maintenance")
+                    .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["maintenance"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(format!(
+                "Failed to get maintenance status: {}",
+                response.status()
+            ))
+        }
+    }
+
+    pub async fn admin_set_maintenance(
+        &self,
+        enabled: bool,
+        message: Option<&str>,
+    ) -> Result<(), String> {
+        let body = serde_json::json!({ "enabled": enabled, "message": message });
+        let response = self
+            .send(
+                self.request(reqwest::Method::POST, "/api/admin/maintenance")
+                    .await
+                    .json(&body),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to update maintenance mode").await)
+        }
+    }
+
+    // Digest endpoints
+    pub async fn get_digest(&self) -> Result<Option<Digest>, String> {
+        let response = self
+            .send(self.request(reqwest::Method::GET, "/api/digest").await)
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get digest").await)
+        }
+    }
+
+    pub async fn get_digest_settings(&self) -> Result<DigestSettings, String> {
+        let response = self
+            .send(
+                self.request(reqwest::Method::GET, "/api/digest/settings")
+                    .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get digest settings").await)
+        }
+    }
+
+    pub async fn update_digest_settings(&self, settings: &DigestSettings) -> Result<(), String> {
+        let response = self
+            .send(
+                self.request(reqwest::Method::PUT, "/api/digest/settings")
+                    .await
+                    .json(settings),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to update digest settings").await)
+        }
+    }
+
+    // Retention endpoints
+    pub async fn get_retention_rules(&self) -> Result<Vec<RetentionRule>, String> {
+        let response = self
+            .send(self.request(reqwest::Method::GET, "/api/retention").await)
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["rules"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get retention rules").await)
+        }
+    }
+
+    /// Set the global (`room_id: None`) or a room rule; `days: None` removes it
+    pub async fn set_retention_rule(
+        &self,
+        room_id: Option<&str>,
+        days: Option<i32>,
+    ) -> Result<Vec<RetentionRule>, String> {
+        let body = serde_json::json!({ "roomId": room_id, "days": days });
+        let response = self
+            .send(
+                self.request(reqwest::Method::PUT, "/api/retention")
+                    .await
+                    .json(&body),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["rules"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to update retention").await)
+        }
+    }
+
     pub async fn leave_room(&self, room_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/rooms/{}/leave", room_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/rooms/{}/leave", room_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -432,11 +808,11 @@ impl ApiClient {
 
     pub async fn delete_room(&self, room_id: &str) -> Result<(), String> {
         let response = self
-            .request(reqwest::Method::DELETE, &format!("/api/rooms/{}", room_id))
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(
+                self.request(reqwest::Method::DELETE, &format!("/api/rooms/{}", room_id))
+                    .await,
+            )
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -445,16 +821,397 @@ impl ApiClient {
         }
     }
 
+    pub async fn get_room_stats(&self, room_id: &str, days: u32) -> Result<RoomStats, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::GET,
+                    &format!("/api/rooms/{}/stats?days={}", room_id, days),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get room stats").await)
+        }
+    }
+
+    /// Edit room settings (room admins); `None` fields are left unchanged
+    pub async fn update_room(
+        &self,
+        room_id: &str,
+        name: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<Room, String> {
+        let body = serde_json::json!({ "name": name, "description": description });
+        let response = self
+            .send(
+                self.request(reqwest::Method::PATCH, &format!("/api/rooms/{}", room_id))
+                    .await
+                    .json(&body),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["room"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to update room").await)
+        }
+    }
+
+    /// Switch the room to end-to-end encryption (creator only, irreversible)
+    pub async fn enable_e2ee(&self, room_id: &str) -> Result<Room, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/rooms/{}/e2ee", room_id),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["room"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to enable encryption").await)
+        }
+    }
+
+    /// Make a member `admin` or `member` of the room
+    pub async fn set_member_role(
+        &self,
+        room_id: &str,
+        user_id: &str,
+        role: &str,
+    ) -> Result<(), String> {
+        let body = serde_json::json!({ "role": role });
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::PUT,
+                    &format!("/api/rooms/{}/members/{}/role", room_id, user_id),
+                )
+                .await
+                .json(&body),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to change role").await)
+        }
+    }
+
+    pub async fn get_pins(&self, room_id: &str) -> Result<Vec<Message>, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::GET,
+                    &format!("/api/rooms/{}/pins", room_id),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["pins"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get pinned messages").await)
+        }
+    }
+
+    pub async fn get_notification_settings(
+        &self,
+        room_id: &str,
+    ) -> Result<NotificationSettings, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::GET,
+                    &format!("/api/rooms/{}/notifications", room_id),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get notification settings").await)
+        }
+    }
+
+    pub async fn update_notification_settings(
+        &self,
+        room_id: &str,
+        settings: &NotificationSettings,
+    ) -> Result<NotificationSettings, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::PUT,
+                    &format!("/api/rooms/{}/notifications", room_id),
+                )
+                .await
+                .json(settings),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to update notification settings").await)
+        }
+    }
+
+    pub async fn list_invites(&self, room_id: &str) -> Result<Vec<RoomInvite>, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::GET,
+                    &format!("/api/rooms/{}/invites", room_id),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["invites"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get invites").await)
+        }
+    }
+
+    /// Create an invite code; `None` means unlimited uses / never expires
+    pub async fn create_invite(
+        &self,
+        room_id: &str,
+        max_uses: Option<i32>,
+        expires_in_hours: Option<i64>,
+    ) -> Result<RoomInvite, String> {
+        let body = serde_json::json!({ "maxUses": max_uses, "expiresInHours": expires_in_hours });
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/rooms/{}/invites", room_id),
+                )
+                .await
+                .json(&body),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["invite"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to create invite").await)
+        }
+    }
+
+    pub async fn revoke_invite(&self, room_id: &str, code: &str) -> Result<(), String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::DELETE,
+                    &format!("/api/rooms/{}/invites/{}", room_id, code),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to revoke invite").await)
+        }
+    }
+
+    pub async fn preview_invite(&self, code: &str) -> Result<InvitePreview, String> {
+        let response = self
+            .send(
+                self.request(reqwest::Method::GET, &format!("/api/invites/{}", code))
+                    .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Invite not found or expired").await)
+        }
+    }
+
+    /// Join the invite's room; returns the room as the room list has it
+    pub async fn accept_invite(&self, code: &str) -> Result<Room, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/invites/{}/accept", code),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["room"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to accept invite").await)
+        }
+    }
+
+    // Room request endpoints
+    pub async fn list_requests(&self) -> Result<Vec<RoomRequest>, String> {
+        let response = self
+            .send(self.request(reqwest::Method::GET, "/api/requests").await)
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["requests"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get requests").await)
+        }
+    }
+
+    /// Join the room a request is for; returns the room as the room list has it
+    pub async fn accept_request(&self, room_id: &str) -> Result<Room, String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/requests/{}/accept", room_id),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["room"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to accept request").await)
+        }
+    }
+
+    pub async fn decline_request(&self, room_id: &str) -> Result<(), String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::DELETE,
+                    &format!("/api/requests/{}", room_id),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to decline request").await)
+        }
+    }
+
+    pub async fn get_request_settings(&self) -> Result<RequestSettings, String> {
+        let response = self
+            .send(
+                self.request(reqwest::Method::GET, "/api/requests/settings")
+                    .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get request settings").await)
+        }
+    }
+
+    pub async fn update_request_settings(&self, settings: &RequestSettings) -> Result<(), String> {
+        let response = self
+            .send(
+                self.request(reqwest::Method::PUT, "/api/requests/settings")
+                    .await
+                    .json(settings),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to update request settings").await)
+        }
+    }
+
+    /// Search every room the user is in that the server can read, grouped
+    /// by room with the room of the newest match first
+    pub async fn search_all_messages(&self, query: &str) -> Result<Vec<RoomSearchHits>, String> {
+        let q = String::from(js_sys::encode_uri_component(query));
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::GET,
+                    &format!("/api/search/messages?q={}", q),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["rooms"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Search failed").await)
+        }
+    }
+
+    pub async fn get_room_files(
+        &self,
+        room_id: &str,
+        kind: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<RoomFilesPage, String> {
+        let mut path = format!(
+            "/api/rooms/{}/files?limit={}&offset={}",
+            room_id, limit, offset
+        );
+        if let Some(kind) = kind {
+            path.push_str(&format!("&type={}", kind));
+        }
+
+        let response = self
+            .send(self.request(reqwest::Method::GET, &path).await)
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Failed to get files").await)
+        }
+    }
+
     pub async fn get_room_members(&self, room_id: &str) -> Result<Vec<Value>, String> {
         let response = self
-            .request(
-                reqwest::Method::GET,
-                &format!("/api/rooms/{}/members", room_id),
+            .send(
+                self.request(
+                    reqwest::Method::GET,
+                    &format!("/api/rooms/{}/members", room_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -466,14 +1223,14 @@ impl ApiClient {
 
     pub async fn admin_promote_user(&self, user_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/admin/users/{}/promote", user_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/admin/users/{}/promote", user_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -484,14 +1241,14 @@ impl ApiClient {
 
     pub async fn admin_demote_user(&self, user_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/admin/users/{}/demote", user_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/admin/users/{}/demote", user_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -502,14 +1259,14 @@ impl ApiClient {
 
     pub async fn admin_unban_user(&self, user_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/api/admin/users/{}/unban", user_id),
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/admin/users/{}/unban", user_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -520,14 +1277,14 @@ impl ApiClient {
 
     pub async fn admin_delete_user(&self, user_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::DELETE,
-                &format!("/api/admin/users/{}", user_id),
+            .send(
+                self.request(
+                    reqwest::Method::DELETE,
+                    &format!("/api/admin/users/{}", user_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -538,11 +1295,8 @@ impl ApiClient {
 
     pub async fn admin_get_rooms(&self) -> Result<Vec<Value>, String> {
         let response = self
-            .request(reqwest::Method::GET, "/api/admin/rooms")
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .send(self.request(reqwest::Method::GET, "/api/admin/rooms").await)
+            .await?;
 
         if response.status().is_success() {
             let data: Value = response.json().await.map_err(|e| e.to_string())?;
@@ -554,14 +1308,14 @@ impl ApiClient {
 
     pub async fn admin_delete_room(&self, room_id: &str) -> Result<(), String> {
         let response = self
-            .request(
-                reqwest::Method::DELETE,
-                &format!("/api/admin/rooms/{}", room_id),
+            .send(
+                self.request(
+                    reqwest::Method::DELETE,
+                    &format!("/api/admin/rooms/{}", room_id),
+                )
+                .await,
             )
-            .await
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
         if response.status().is_success() {
             Ok(())
@@ -570,6 +1324,60 @@ impl ApiClient {
         }
     }
 
+    pub async fn admin_get_spam(&self) -> Result<Vec<Value>, String> {
+        let response = self
+            .send(self.request(reqwest::Method::GET, "/api/admin/spam").await)
+            .await?;
+
+        if response.status().is_success() {
+            let data: Value = response.json().await.map_err(|e| e.to_string())?;
+            serde_json::from_value(data["messages"].clone()).map_err(|e| e.to_string())
+        } else {
+            Err(format!("Failed to get spam queue: {}", response.status()))
+        }
+    }
+
+    pub async fn admin_approve_spam(&self, message_id: &str) -> Result<(), String> {
+        let response = self
+            .send(
+                self.request(
+                    reqwest::Method::POST,
+                    &format!("/api/admin/spam/{}/approve", message_id),
+                )
+                .await,
+            )
+            .await?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Self::parse_error(response, "Failed to approve message").await)
+        }
+    }
+
+    /// Translate text into `target` via the server's own LibreTranslate
+    pub async fn translate(&self, text: &str, target: &str) -> Result<Translation, String> {
+        let body = serde_json::json!({
+            "text": text,
+            "source": "auto",
+            "target": target,
+        });
+
+        let response = self
+            .send(
+                self.request(reqwest::Method::POST, "/api/translate")
+                    .await
+                    .json(&body),
+            )
+            .await?;
+
+        if response.status().is_success() {
+            response.json().await.map_err(|e| e.to_string())
+        } else {
+            Err(Self::parse_error(response, "Translation failed").await)
+        }
+    }
+
     fn mime_from_filename(filename: &str) -> &'static str {
         let ext = filename
             .rsplit('.')
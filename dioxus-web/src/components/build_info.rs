@@ -0,0 +1,61 @@
+use crate::state::AppState;
+use dioxus::prelude::*;
+use tor_chat_common::build_info;
+
+/// Which build of the app and of the server the user is running, to check
+/// against a release, with a hint when the two don't match
+#[component]
+pub fn BuildInfoDialog(on_close: EventHandler<()>) -> Element {
+    let state = use_context::<AppState>();
+    let client = build_info!();
+    let server = state.server_build.read().clone();
+    let mismatch = server.as_ref().and_then(|s| client.mismatch_hint(s));
+    let clock = state.api.clock();
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black bg-opacity-60 flex items-center justify-center z-50",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "bg-dc-sidebar rounded-lg p-5 w-[28rem] max-w-full mx-4 border border-dc-border shadow-xl text-sm text-dc-text",
+                onclick: move |e| e.stop_propagation(),
+                h2 { class: "text-lg font-semibold text-white mb-3", "About this build" }
+                p {
+                    class: "mb-1 break-all",
+                    span { class: "text-dc-text-muted", "This app: " }
+                    "{client.describe()}"
+                }
+                p {
+                    class: "mb-4 break-all",
+                    span { class: "text-dc-text-muted", "Server: " }
+                    if let Some(server) = &server {
+                        "{server.describe()}"
+                    } else {
+                        "unknown"
+                    }
+                }
+                if let Some(hint) = mismatch {
+                    div { class: "p-2 rounded mb-3 border border-yellow-600 bg-yellow-900/30", "{hint}" }
+                }
+                if clock.is_noticeable() {
+                    p {
+                        class: "mb-3",
+                        "Your clock is {clock.describe()}. Message times are shown by the server's clock."
+                    }
+                }
+                p {
+                    class: "text-dc-text-muted mb-4",
+                    "Release builds list their commit in the release notes. A different commit means this isn't the build that was published."
+                }
+                div {
+                    class: "flex justify-end",
+                    button {
+                        class: "px-4 py-1 rounded text-dc-text-muted hover:bg-dc-hover",
+                        onclick: move |_| on_close.call(()),
+                        "Close"
+                    }
+                }
+            }
+        }
+    }
+}
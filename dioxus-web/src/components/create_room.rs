@@ -0,0 +1,412 @@
+use crate::components::{
+    retention_settings::PERIODS,
+    room_info::{INVITE_EXPIRY, INVITE_USES},
+};
+use crate::models::ServerLimits;
+use crate::state::AppState;
+use crate::utils;
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Details,
+    Options,
+    Done,
+}
+
+/// The room once it exists, and what came of the options applied after it
+#[derive(Clone, PartialEq)]
+struct Created {
+    room_id: Uuid,
+    name: String,
+    invite_link: Option<String>,
+    /// Options that couldn't be applied; the room itself was created
+    problems: Vec<String>,
+}
+
+const LABEL: &str = "block text-xs font-semibold text-dc-text-muted uppercase tracking-wide mb-1";
+const INPUT: &str = "w-full px-3 py-2 bg-dc-input border border-dc-border rounded text-dc-text placeholder-dc-text-faint focus:outline-none focus:border-dc-accent text-sm";
+const SELECT: &str =
+    "w-full bg-dc-input border border-dc-border rounded px-2 py-1.5 text-dc-text text-sm";
+
+/// Create-room modal in three steps: name and description; visibility,
+/// size, encryption, retention and an invite link; then the link to share.
+/// Encryption, retention and the invite are applied right after the room is
+/// created. `on_created` fires with the new room's id once the room list has
+/// it, when the user chooses to open it.
+#[component]
+pub fn CreateRoomWizard(
+    is_admin: bool,
+    on_close: EventHandler<()>,
+    on_created: EventHandler<Uuid>,
+) -> Element {
+    let state = use_context::<AppState>();
+    let mut step = use_signal(|| Step::Details);
+    let mut error = use_signal(|| None::<String>);
+    let mut busy = use_signal(|| false);
+
+    let mut name = use_signal(String::new);
+    let mut description = use_signal(String::new);
+    let mut is_public = use_signal(|| is_admin);
+    let mut max_members = use_signal(String::new);
+    let mut e2ee = use_signal(|| false);
+    let mut retention_days = use_signal(|| None::<i32>);
+    let mut make_invite = use_signal(|| true);
+    let mut invite_uses = use_signal(|| 0i32);
+    let mut invite_hours = use_signal(|| 24i64);
+    let mut created = use_signal(|| None::<Created>);
+
+    let mut limits = use_signal(ServerLimits::default);
+    use_hook({
+        let api = state.api.clone();
+        move || {
+            spawn(async move {
+                if let Ok(info) = api.get_server_info().await {
+                    limits.set(info.limits);
+                }
+            });
+        }
+    });
+
+    let next = move |_| {
+        if name().trim().is_empty() {
+            error.set(Some("Channel name is required".to_string()));
+            return;
+        }
+        error.set(None);
+        step.set(Step::Options);
+    };
+
+    let create = {
+        let state = state.clone();
+        move |_| {
+            let max = match max_members().trim() {
+                "" => None,
+                n => match n.parse::<i32>() {
+                    Ok(n) => Some(n),
+                    Err(_) => {
+                        error.set(Some("Max members must be a number".to_string()));
+                        return;
+                    }
+                },
+            };
+            let room_name = name().trim().to_string();
+            let desc = Some(description().trim().to_string()).filter(|d| !d.is_empty());
+            let state = state.clone();
+            busy.set(true);
+            error.set(None);
+            spawn(async move {
+                let room = match state
+                    .api
+                    .create_room(room_name.clone(), desc, is_public(), max)
+                    .await
+                {
+                    Ok(room) => room,
+                    Err(e) => {
+                        error.set(Some(e));
+                        busy.set(false);
+                        return;
+                    }
+                };
+                let room_id = room.id.to_string();
+                let mut problems = Vec::new();
+
+                if e2ee() {
+                    if let Err(e) = state.api.enable_e2ee(&room_id).await {
+                        problems.push(format!("Encryption: {}", e));
+                    }
+                }
+                if let Some(days) = retention_days() {
+                    if let Err(e) = state
+                        .api
+                        .set_retention_rule(Some(&room_id), Some(days))
+                        .await
+                    {
+                        problems.push(format!("Retention: {}", e));
+                    }
+                }
+                let mut invite_link = None;
+                if make_invite() {
+                    let uses = Some(invite_uses()).filter(|n| *n > 0);
+                    let hours = Some(invite_hours()).filter(|h| *h > 0);
+                    match state.api.create_invite(&room_id, uses, hours).await {
+                        Ok(invite) => {
+                            let origin = web_sys::window()
+                                .and_then(|w| w.location().origin().ok())
+                                .unwrap_or_default();
+                            invite_link = Some(format!("{}/invite/{}", origin, invite.code));
+                        }
+                        Err(e) => problems.push(format!("Invite link: {}", e)),
+                    }
+                }
+
+                let _ = state.load_rooms().await;
+                created.set(Some(Created {
+                    room_id: room.id,
+                    name: room_name,
+                    invite_link,
+                    problems,
+                }));
+                busy.set(false);
+                step.set(Step::Done);
+            });
+        }
+    };
+
+    let size_hint = match (limits().default_room_members, limits().max_room_members) {
+        (Some(default), Some(cap)) => format!("Default {}, at most {}", default, cap),
+        _ => "Leave empty for the server's default".to_string(),
+    };
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black bg-opacity-60 flex items-center justify-center z-50",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "bg-dc-sidebar rounded-lg p-5 w-96 max-w-full mx-4 max-h-[85vh] overflow-y-auto border border-dc-border shadow-xl",
+                onclick: move |e| e.stop_propagation(),
+                h2 {
+                    class: "text-lg font-semibold text-white mb-1",
+                    if step() == Step::Done { "Channel created" } else { "Create Channel" }
+                }
+                if step() != Step::Done {
+                    p {
+                        class: "text-xs text-dc-text-faint mb-4",
+                        if step() == Step::Details { "Step 1 of 2: name" } else { "Step 2 of 2: options" }
+                    }
+                }
+                if let Some(err) = error() {
+                    div {
+                        class: "bg-red-900/50 text-red-200 p-2 rounded mb-3 text-sm",
+                        role: "alert",
+                        "{err}"
+                    }
+                }
+
+                match step() {
+                    Step::Details => rsx! {
+                        div {
+                            class: "space-y-4",
+                            div {
+                                label { class: LABEL, r#for: "new-room-name", "Channel Name" }
+                                input {
+                                    id: "new-room-name",
+                                    r#type: "text",
+                                    class: INPUT,
+                                    placeholder: "new-channel",
+                                    maxlength: "100",
+                                    value: "{name}",
+                                    oninput: move |e| name.set(e.value()),
+                                }
+                            }
+                            div {
+                                label { class: LABEL, r#for: "new-room-desc", "Description (optional)" }
+                                input {
+                                    id: "new-room-desc",
+                                    r#type: "text",
+                                    class: INPUT,
+                                    placeholder: "What's this channel about?",
+                                    maxlength: "500",
+                                    value: "{description}",
+                                    oninput: move |e| description.set(e.value()),
+                                }
+                            }
+                            div {
+                                class: "flex gap-2 pt-2",
+                                button {
+                                    class: "flex-1 bg-dc-accent hover:bg-indigo-500 text-white py-2 px-4 rounded text-sm font-medium",
+                                    onclick: next,
+                                    "Next"
+                                }
+                                button {
+                                    class: "flex-1 bg-dc-input hover:bg-dc-hover text-dc-text py-2 px-4 rounded text-sm",
+                                    onclick: move |_| on_close.call(()),
+                                    "Cancel"
+                                }
+                            }
+                        }
+                    },
+                    Step::Options => rsx! {
+                        div {
+                            class: "space-y-4 text-sm",
+                            div {
+                                span { class: LABEL, "Visibility" }
+                                if is_admin {
+                                    label {
+                                        class: "flex items-center gap-2 text-dc-text-muted",
+                                        input {
+                                            r#type: "checkbox",
+                                            class: "w-4 h-4 accent-dc-accent",
+                                            checked: is_public(),
+                                            onchange: move |e| is_public.set(e.checked()),
+                                        }
+                                        "Public channel (visible to all users)"
+                                    }
+                                } else {
+                                    p {
+                                        class: "text-xs text-dc-text-faint",
+                                        "Channel will be private. Only admins can create public channels."
+                                    }
+                                }
+                            }
+                            div {
+                                label { class: LABEL, r#for: "new-room-size", "Max members" }
+                                input {
+                                    id: "new-room-size",
+                                    r#type: "number",
+                                    class: INPUT,
+                                    min: "2",
+                                    max: limits().max_room_members.map(|cap| cap.to_string()),
+                                    placeholder: limits()
+                                        .default_room_members
+                                        .map(|n| n.to_string())
+                                        .unwrap_or_default(),
+                                    value: "{max_members}",
+                                    oninput: move |e| max_members.set(e.value()),
+                                }
+                                p { class: "text-xs text-dc-text-faint mt-1", "{size_hint}" }
+                            }
+                            div {
+                                span { class: LABEL, "Encryption" }
+                                label {
+                                    class: "flex items-center gap-2 text-dc-text-muted",
+                                    input {
+                                        r#type: "checkbox",
+                                        class: "w-4 h-4 accent-dc-accent",
+                                        checked: e2ee(),
+                                        onchange: move |e| e2ee.set(e.checked()),
+                                    }
+                                    "End-to-end encrypted"
+                                }
+                                p {
+                                    class: "text-xs text-dc-text-faint mt-1",
+                                    "The server can't read messages, search them or show previews. This can't be turned off later."
+                                }
+                            }
+                            div {
+                                label { class: LABEL, r#for: "new-room-retention", "Delete my messages after" }
+                                select {
+                                    id: "new-room-retention",
+                                    class: SELECT,
+                                    onchange: move |e| retention_days.set(e.value().parse().ok()),
+                                    option { value: "", selected: retention_days().is_none(), "Keep forever" }
+                                    for (days, label) in PERIODS {
+                                        option { value: "{days}", selected: retention_days() == Some(days), "{label}" }
+                                    }
+                                }
+                                p {
+                                    class: "text-xs text-dc-text-faint mt-1",
+                                    "Applies to your own messages in this channel; change it later under Message retention."
+                                }
+                            }
+                            div {
+                                span { class: LABEL, "Invite link" }
+                                label {
+                                    class: "flex items-center gap-2 text-dc-text-muted mb-2",
+                                    input {
+                                        r#type: "checkbox",
+                                        class: "w-4 h-4 accent-dc-accent",
+                                        checked: make_invite(),
+                                        onchange: move |e| make_invite.set(e.checked()),
+                                    }
+                                    "Create an invite link"
+                                }
+                                if make_invite() {
+                                    div {
+                                        class: "flex gap-2",
+                                        select {
+                                            class: SELECT,
+                                            aria_label: "Invite uses",
+                                            onchange: move |e| invite_uses.set(e.value().parse().unwrap_or(0)),
+                                            for (label, uses) in INVITE_USES {
+                                                option { value: "{uses}", selected: invite_uses() == uses, "{label}" }
+                                            }
+                                        }
+                                        select {
+                                            class: SELECT,
+                                            aria_label: "Invite expiry",
+                                            onchange: move |e| invite_hours.set(e.value().parse().unwrap_or(0)),
+                                            for (label, hours) in INVITE_EXPIRY {
+                                                option { value: "{hours}", selected: invite_hours() == hours, "{label}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            div {
+                                class: "flex gap-2 pt-2",
+                                button {
+                                    class: "flex-1 bg-dc-accent hover:bg-indigo-500 text-white py-2 px-4 rounded text-sm font-medium disabled:opacity-50",
+                                    disabled: busy(),
+                                    onclick: create,
+                                    if busy() { "Creating..." } else { "Create" }
+                                }
+                                button {
+                                    class: "flex-1 bg-dc-input hover:bg-dc-hover text-dc-text py-2 px-4 rounded text-sm",
+                                    disabled: busy(),
+                                    onclick: move |_| {
+                                        error.set(None);
+                                        step.set(Step::Details);
+                                    },
+                                    "Back"
+                                }
+                            }
+                        }
+                    },
+                    Step::Done => rsx! {
+                        if let Some(done) = created() {
+                            div {
+                                class: "space-y-4 text-sm",
+                                p { class: "text-dc-text-muted", "#{done.name} is ready." }
+                                if let Some(link) = done.invite_link.clone() {
+                                    {
+                                        let copied = link.clone();
+                                        rsx! {
+                                            div {
+                                                label { class: LABEL, r#for: "new-room-invite", "Invite link" }
+                                                div {
+                                                    class: "flex gap-2",
+                                                    input {
+                                                        id: "new-room-invite",
+                                                        class: "flex-1 min-w-0 bg-dc-dark rounded px-2 py-1 text-xs text-dc-text font-mono",
+                                                        readonly: true,
+                                                        value: "{link}",
+                                                    }
+                                                    button {
+                                                        class: "px-3 py-1 rounded bg-dc-input hover:bg-dc-hover text-dc-text",
+                                                        onclick: move |_| utils::copy_to_clipboard(&copied),
+                                                        "Copy"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                for problem in done.problems.iter() {
+                                    div {
+                                        class: "bg-red-900/50 text-red-200 p-2 rounded",
+                                        "{problem}"
+                                    }
+                                }
+                                div {
+                                    class: "flex gap-2 pt-2",
+                                    button {
+                                        class: "flex-1 bg-dc-accent hover:bg-indigo-500 text-white py-2 px-4 rounded text-sm font-medium",
+                                        onclick: move |_| on_created.call(done.room_id),
+                                        "Open channel"
+                                    }
+                                    button {
+                                        class: "flex-1 bg-dc-input hover:bg-dc-hover text-dc-text py-2 px-4 rounded text-sm",
+                                        onclick: move |_| on_close.call(()),
+                                        "Close"
+                                    }
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+        }
+    }
+}
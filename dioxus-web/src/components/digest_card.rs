@@ -0,0 +1,152 @@
+use crate::models::DigestSettings;
+use crate::state::AppState;
+use crate::utils::storage;
+use dioxus::prelude::*;
+
+/// Sidebar card with the latest activity digest (rooms the user hasn't
+/// opened for a day) and the opt-in settings for it
+#[component]
+pub fn DigestCard() -> Element {
+    let state = use_context::<AppState>();
+    let mut show_settings = use_signal(|| false);
+    let mut settings = use_signal(|| None::<DigestSettings>);
+    let mut error = use_signal(|| None::<String>);
+
+    let open_settings = {
+        let api = state.api.clone();
+        move |_| {
+            let open = !show_settings();
+            show_settings.set(open);
+            if open && settings.peek().is_none() {
+                let api = api.clone();
+                spawn(async move {
+                    match api.get_digest_settings().await {
+                        Ok(s) => settings.set(Some(s)),
+                        Err(e) => error.set(Some(e)),
+                    }
+                });
+            }
+        }
+    };
+
+    let save = {
+        let api = state.api.clone();
+        move |updated: DigestSettings| {
+            let api = api.clone();
+            spawn(async move {
+                error.set(None);
+                match api.update_digest_settings(&updated).await {
+                    Ok(()) => settings.set(Some(updated)),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    let digest = state.digest.read().clone();
+
+    rsx! {
+        div {
+            class: "mx-2 mt-2 rounded bg-dc-dark text-xs",
+            div {
+                class: "flex items-center px-2.5 py-1.5",
+                span { class: "flex-1 font-semibold text-dc-text-muted uppercase tracking-wide", "Daily digest" }
+                button {
+                    class: "text-dc-text-muted hover:text-dc-text px-1",
+                    title: "Digest settings",
+                    onclick: open_settings,
+                    "\u{2699}"
+                }
+                if let Some(d) = digest.as_ref() {
+                    {
+                        let id = d.id.to_string();
+                        let mut digest_sig = state.digest;
+                        rsx! {
+                            button {
+                                class: "text-dc-text-muted hover:text-dc-text px-1",
+                                title: "Dismiss",
+                                onclick: move |_| {
+                                    storage::dismiss_digest(&id);
+                                    digest_sig.set(None);
+                                },
+                                "\u{2715}"
+                            }
+                        }
+                    }
+                }
+            }
+
+            if show_settings() {
+                div {
+                    class: "px-2.5 pb-2 text-dc-text-muted space-y-1",
+                    if let Some(current) = settings() {
+                        {
+                            let toggled = DigestSettings { enabled: !current.enabled, ..current.clone() };
+                            let save_toggle = save.clone();
+                            let save_hour = save.clone();
+                            rsx! {
+                                label {
+                                    class: "flex items-center gap-2 cursor-pointer",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: current.enabled,
+                                        onchange: move |_| save_toggle(toggled.clone()),
+                                    }
+                                    "Send me a daily summary of rooms I haven't opened"
+                                }
+                                label {
+                                    class: "flex items-center gap-2",
+                                    "At"
+                                    select {
+                                        class: "bg-dc-sidebar text-dc-text rounded px-1",
+                                        disabled: !current.enabled,
+                                        onchange: move |e| {
+                                            if let Ok(hour) = e.value().parse() {
+                                                save_hour(DigestSettings { enabled: true, hour_utc: hour });
+                                            }
+                                        },
+                                        for hour in 0..24i16 {
+                                            option {
+                                                value: "{hour}",
+                                                selected: hour == current.hour_utc,
+                                                "{hour:02}:00"
+                                            }
+                                        }
+                                    }
+                                    "UTC"
+                                }
+                            }
+                        }
+                    } else if error().is_none() {
+                        "Loading..."
+                    }
+                    if let Some(err) = error() {
+                        div { class: "text-red-400", "{err}" }
+                    }
+                }
+            }
+
+            if let Some(d) = digest {
+                ul {
+                    class: "px-2.5 pb-2 space-y-1",
+                    for room in d.rooms {
+                        {
+                            let participants = room.top_participants.join(", ");
+                            rsx! {
+                                li {
+                                    key: "{room.room_id}",
+                                    class: "text-dc-text",
+                                    span { class: "font-semibold", "{room.room_name}" }
+                                    span { class: "text-dc-text-muted", " \u{2014} {room.message_count} new" }
+                                    if !participants.is_empty() {
+                                        div { class: "text-dc-text-faint truncate", "{participants}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::models::RoomFile;
+use crate::state::AppState;
+use crate::utils;
+use dioxus::prelude::*;
+
+const PAGE_SIZE: u32 = 30;
+
+/// Filter tabs: label and the `type` query value
+const FILTERS: [(&str, Option<&str>); 5] = [
+    ("All", None),
+    ("Images", Some("image")),
+    ("Videos", Some("video")),
+    ("Audio", Some("audio")),
+    ("Files", Some("file")),
+];
+
+/// Side panel listing every attachment shared in a room, newest first.
+/// Render it with `key: "{room_id}"` so switching rooms starts over.
+/// `embedded` drops the sidebar frame for use inside the room info panel.
+#[component]
+pub fn FilesPanel(room_id: String, #[props(default)] embedded: bool) -> Element {
+    let state = use_context::<AppState>();
+    let mut filter = use_signal(|| None::<&'static str>);
+    let mut files = use_signal(Vec::<RoomFile>::new);
+    let mut total = use_signal(|| 0i64);
+    let mut loading = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let load = {
+        let state = state.clone();
+        move |reset: bool| {
+            let api = state.api.clone();
+            let room_id = room_id.clone();
+            let offset = if reset { 0 } else { files.read().len() as u32 };
+            let kind = filter();
+            spawn(async move {
+                loading.set(true);
+                error.set(None);
+                match api.get_room_files(&room_id, kind, PAGE_SIZE, offset).await {
+                    Ok(page) => {
+                        if reset {
+                            files.set(page.files);
+                        } else {
+                            files.write().extend(page.files);
+                        }
+                        total.set(page.total);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+                loading.set(false);
+            });
+        }
+    };
+
+    // The parent keys this panel by room, so this runs once per room
+    use_hook({
+        let load = load.clone();
+        move || load(true)
+    });
+
+    let has_more = (files.read().len() as i64) < total();
+
+    rsx! {
+        div {
+            class: if embedded {
+                "flex flex-col max-h-96"
+            } else {
+                "fixed inset-y-0 right-0 z-40 w-72 max-w-[85vw] md:static md:z-auto bg-dc-sidebar border-l border-dc-border flex flex-col flex-shrink-0"
+            },
+            // Panel header
+            div {
+                class: "px-4 py-3",
+                h3 {
+                    class: "text-xs font-semibold text-dc-text-muted uppercase tracking-wide",
+                    "Files \u{2014} {total}"
+                }
+            }
+            // Type filter
+            div {
+                class: "px-3 pb-2 flex flex-wrap gap-1",
+                for (label, kind) in FILTERS {
+                    {
+                        let load = load.clone();
+                        rsx! {
+                            button {
+                                class: if filter() == kind {
+                                    "px-2 py-0.5 rounded text-xs bg-dc-accent text-white"
+                                } else {
+                                    "px-2 py-0.5 rounded text-xs text-dc-text-muted hover:bg-dc-hover hover:text-dc-text"
+                                },
+                                onclick: move |_| {
+                                    filter.set(kind);
+                                    load(true);
+                                },
+                                "{label}"
+                            }
+                        }
+                    }
+                }
+            }
+
+            div {
+                class: "flex-1 overflow-y-auto px-2 pb-2",
+                if let Some(err) = error() {
+                    div { class: "px-2 py-1 text-xs text-red-400", "{err}" }
+                }
+                if files.read().is_empty() && !loading() {
+                    div { class: "px-2 py-4 text-sm text-dc-text-faint text-center", "No files shared yet" }
+                }
+                for file in files() {
+                    FileRow { key: "{file.message_id}", room_id: room_id.clone(), file }
+                }
+                if has_more {
+                    {
+                        let load = load.clone();
+                        rsx! {
+                            button {
+                                class: "w-full mt-1 py-1.5 text-xs text-dc-accent hover:bg-dc-hover rounded",
+                                disabled: loading(),
+                                onclick: move |_| load(false),
+                                if loading() { "Loading..." } else { "Load more" }
+                            }
+                        }
+                    }
+                } else if loading() {
+                    div { class: "px-2 py-2 text-xs text-dc-text-faint", "Loading..." }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn FileRow(room_id: String, file: RoomFile) -> Element {
+    let state = use_context::<AppState>();
+    let icon = match file.message_type.as_str() {
+        "image" => "\u{1F5BC}",
+        "video" => "\u{1F3AC}",
+        "audio" => "\u{1F3B5}",
+        _ => "\u{1F4C4}",
+    };
+    let name = file.display_name();
+    let sender = file
+        .user
+        .display_name
+        .clone()
+        .unwrap_or_else(|| file.user.username.clone());
+    let date = utils::format_date(&file.created_at);
+    let size = file.size.map(format_size).unwrap_or_default();
+    let msg_id = file.message_id;
+    let room_id = uuid::Uuid::parse_str(&room_id).ok();
+
+    rsx! {
+        div {
+            class: "group flex items-start gap-2 px-2 py-1.5 rounded hover:bg-dc-hover",
+            span { class: "text-lg leading-none mt-0.5", "{icon}" }
+            div {
+                class: "flex-1 min-w-0",
+                a {
+                    class: "block text-sm text-blue-400 hover:underline truncate",
+                    href: "{file.url}",
+                    target: "_blank",
+                    rel: "noopener noreferrer",
+                    title: "{name}",
+                    "{name}"
+                }
+                div {
+                    class: "text-[11px] text-dc-text-faint truncate",
+                    "{sender} \u{00B7} {date} {size}"
+                }
+            }
+            button {
+                class: "opacity-0 group-hover:opacity-100 text-xs text-dc-text-muted hover:text-dc-text",
+                title: "Jump to message",
+                onclick: move |_| {
+                    if let Some(room_id) = room_id {
+                        state.open_message(room_id, msg_id);
+                    }
+                },
+                "\u{21AA}"
+            }
+        }
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("\u{00B7} {:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("\u{00B7} {} KB", bytes.div_ceil(1024))
+    }
+}
@@ -0,0 +1,88 @@
+use crate::models::Message;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+/// Room picker for forwarding a message. Emits `forward_message`; the server
+/// broadcasts the copy as `new_message` in the target room.
+#[component]
+pub fn ForwardPicker(message: Message, on_close: EventHandler<()>) -> Element {
+    let state = use_context::<AppState>();
+    let mut filter = use_signal(String::new);
+    let mut sent_to = use_signal(|| None::<String>);
+
+    let query = filter().to_lowercase();
+    let rooms: Vec<_> = state
+        .rooms
+        .read()
+        .iter()
+        .filter(|r| r.id != message.room_id && !r.is_e2ee())
+        .filter(|r| query.is_empty() || r.name.to_lowercase().contains(&query))
+        .cloned()
+        .collect();
+    let preview = message.decoded().preview(80);
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black bg-opacity-60 flex items-center justify-center z-50",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "bg-dc-sidebar rounded-lg p-5 w-96 max-w-full mx-4 max-h-[80vh] flex flex-col border border-dc-border shadow-xl text-sm text-dc-text",
+                onclick: move |e| e.stop_propagation(),
+                h2 { class: "text-lg font-semibold text-white mb-2", "Forward message" }
+                div {
+                    class: "pl-2 mb-3 border-l-2 border-dc-border text-dc-text-muted truncate",
+                    "{preview}"
+                }
+                if let Some(name) = sent_to() {
+                    div { class: "bg-green-900/50 text-green-200 p-2 rounded mb-3", "Forwarded to #{name}" }
+                }
+                input {
+                    class: "w-full bg-dc-input border border-dc-border rounded px-3 py-2 mb-3 text-dc-text outline-none",
+                    placeholder: "Search rooms...",
+                    value: "{filter}",
+                    oninput: move |e| filter.set(e.value()),
+                }
+                div {
+                    class: "flex-1 overflow-y-auto",
+                    if rooms.is_empty() {
+                        div { class: "text-dc-text-faint py-2", "No other rooms" }
+                    }
+                    for room in rooms {
+                        {
+                            let socket = state.socket.clone();
+                            let message_id = message.id.to_string();
+                            let room_id = room.id.to_string();
+                            let name = room.name.clone();
+                            rsx! {
+                                button {
+                                    key: "{room.id}",
+                                    class: "w-full text-left px-2 py-1.5 rounded hover:bg-dc-hover",
+                                    onclick: move |_| {
+                                        let socket = socket.clone();
+                                        let payload = serde_json::json!({
+                                            "messageId": message_id,
+                                            "targetRoomId": room_id,
+                                        });
+                                        sent_to.set(Some(name.clone()));
+                                        spawn(async move {
+                                            socket.emit("forward_message", payload).await;
+                                        });
+                                    },
+                                    "# {room.name}"
+                                }
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "flex justify-end mt-4",
+                    button {
+                        class: "px-4 py-2 rounded bg-dc-accent text-white hover:opacity-90",
+                        onclick: move |_| on_close.call(()),
+                        "Done"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use crate::state::AppState;
+use crate::utils::{self, storage};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use dioxus::prelude::*;
+use tor_chat_common::identity::{fingerprint, MIN_PASSWORD_LEN};
+use tor_chat_common::IdentityKeys;
+
+/// The user's identity keypair: shows which key this browser holds, creates
+/// one, and moves it between devices as a password-protected backup file so
+/// clearing browser storage doesn't lose it
+#[component]
+pub fn IdentityKeysDialog(on_close: EventHandler<()>) -> Element {
+    let state = use_context::<AppState>();
+    let user = state.current_user.read().clone();
+    let user_id = user.as_ref().map(|u| u.id.to_string()).unwrap_or_default();
+    let username = user
+        .as_ref()
+        .map(|u| u.username.clone())
+        .unwrap_or_default();
+    let server_key = user.as_ref().and_then(|u| u.public_key.clone());
+
+    let mut local = use_signal({
+        let user_id = user_id.clone();
+        move || storage::get_identity(&user_id)
+    });
+    let mut error = use_signal(|| None::<String>);
+    let mut notice = use_signal(|| None::<String>);
+    let mut busy = use_signal(|| false);
+
+    // Export form
+    let mut export_password = use_signal(String::new);
+    let mut export_confirm = use_signal(String::new);
+    let mut export_href = use_signal(|| None::<String>);
+
+    // Import form
+    let mut import_file = use_signal(|| None::<String>);
+    let mut import_password = use_signal(String::new);
+
+    // Save on this device and make it the account's published key
+    let adopt = {
+        let state = state.clone();
+        let user_id = user_id.clone();
+        move |keys: IdentityKeys, message: &'static str| {
+            let state = state.clone();
+            let user_id = user_id.clone();
+            spawn(async move {
+                match state.api.update_public_key(&keys.public_key).await {
+                    Ok(()) => {
+                        storage::save_identity(&user_id, &keys);
+                        let mut current_user = state.current_user;
+                        if let Some(u) = current_user.write().as_mut() {
+                            u.public_key = Some(keys.public_key.clone());
+                        }
+                        local.set(Some(keys));
+                        notice.set(Some(message.to_string()));
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+                busy.set(false);
+            });
+        }
+    };
+
+    let adopt_new = adopt.clone();
+    let local_keys = local();
+    let published = local_keys
+        .as_ref()
+        .zip(server_key.as_ref())
+        .is_some_and(|(keys, server)| &keys.public_key == server);
+    let import_fingerprint = import_file().map(|f| IdentityKeys::backup_fingerprint(&f));
+    let file_name = format!("tor-chat-identity-{}.json", username);
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black bg-opacity-60 flex items-center justify-center z-50",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "bg-dc-sidebar rounded-lg p-5 w-[28rem] max-w-full mx-4 max-h-[85vh] overflow-y-auto border border-dc-border shadow-xl text-sm text-dc-text",
+                onclick: move |e| e.stop_propagation(),
+                h2 { class: "text-lg font-semibold text-white mb-2", "Identity keys" }
+                p {
+                    class: "text-dc-text-muted mb-3",
+                    "Your identity key is stored in this browser only. Export a backup before clearing browser data, and import it on new devices to keep the same identity."
+                }
+
+                if let Some(err) = error() {
+                    div { class: "bg-red-900/50 text-red-200 p-2 rounded mb-3", "{err}" }
+                }
+                if let Some(msg) = notice() {
+                    div { class: "bg-green-900/50 text-green-200 p-2 rounded mb-3", "{msg}" }
+                }
+
+                // Current state
+                div {
+                    class: "bg-dc-dark rounded p-3 mb-4",
+                    if let Some(keys) = &local_keys {
+                        div { class: "text-xs text-dc-text-faint", "This device" }
+                        div { class: "font-mono text-white", "{keys.fingerprint()}" }
+                        if !published {
+                            div {
+                                class: "text-xs text-yellow-400 mt-1",
+                                "Your account currently publishes a different key. Other devices using it will need this one imported."
+                            }
+                        }
+                    } else {
+                        div { class: "text-dc-text-muted", "No identity key on this device." }
+                        if let Some(server) = &server_key {
+                            div {
+                                class: "text-xs text-dc-text-faint mt-1",
+                                "Account key: "
+                                span { class: "font-mono", "{fingerprint(server)}" }
+                                ". Import your backup to use it here."
+                            }
+                        }
+                        button {
+                            class: "mt-2 px-3 py-1 rounded bg-dc-accent text-white hover:opacity-90",
+                            disabled: busy(),
+                            title: "Creates a new identity and publishes it; keys on your other devices stop matching",
+                            onclick: move |_| {
+                                busy.set(true);
+                                let keys = IdentityKeys::from_seed(utils::random_bytes());
+                                adopt_new(keys, "New identity created. Export a backup now.");
+                            },
+                            "Create new identity"
+                        }
+                    }
+                }
+
+                // Export
+                if let Some(keys) = local_keys.clone() {
+                    h3 { class: "font-semibold text-white mb-1", "Export backup" }
+                    input {
+                        class: "w-full bg-dc-input border border-dc-border rounded px-3 py-2 mb-2 text-dc-text outline-none",
+                        r#type: "password",
+                        placeholder: "Backup password (min. {MIN_PASSWORD_LEN} characters)",
+                        value: "{export_password}",
+                        oninput: move |e| {
+                            export_password.set(e.value());
+                            export_href.set(None);
+                        },
+                    }
+                    input {
+                        class: "w-full bg-dc-input border border-dc-border rounded px-3 py-2 mb-2 text-dc-text outline-none",
+                        r#type: "password",
+                        placeholder: "Repeat password",
+                        value: "{export_confirm}",
+                        oninput: move |e| {
+                            export_confirm.set(e.value());
+                            export_href.set(None);
+                        },
+                    }
+                    div {
+                        class: "flex items-center justify-end gap-3 mb-4",
+                        if let Some(href) = export_href() {
+                            a {
+                                class: "text-blue-400 hover:underline",
+                                href: "{href}",
+                                download: "{file_name}",
+                                "Download {file_name}"
+                            }
+                        }
+                        button {
+                            class: "px-3 py-1 rounded bg-dc-accent text-white hover:opacity-90",
+                            disabled: busy(),
+                            onclick: move |_| {
+                                if export_password() != export_confirm() {
+                                    error.set(Some("Passwords don't match".to_string()));
+                                    return;
+                                }
+                                let result = keys.export(
+                                    &export_password(),
+                                    utils::random_bytes(),
+                                    utils::random_bytes(),
+                                );
+                                match result {
+                                    Ok(backup) => {
+                                        let encoded = BASE64.encode(backup);
+                                        export_href.set(Some(format!("data:application/json;base64,{}", encoded)));
+                                        error.set(None);
+                                    }
+                                    Err(e) => error.set(Some(e.to_string())),
+                                }
+                            },
+                            "Create backup"
+                        }
+                    }
+                }
+
+                // Import
+                h3 { class: "font-semibold text-white mb-1", "Import backup" }
+                input {
+                    class: "w-full text-dc-text-muted mb-2",
+                    r#type: "file",
+                    accept: ".json,application/json",
+                    onchange: move |evt| {
+                        if let Some(file) = evt.files().first().cloned() {
+                            spawn(async move {
+                                match file.read_bytes().await {
+                                    Ok(bytes) => {
+                                        import_file.set(Some(String::from_utf8_lossy(&bytes).into_owned()));
+                                    }
+                                    Err(e) => error.set(Some(format!("Error reading file: {}", e))),
+                                }
+                            });
+                        }
+                    },
+                }
+                match import_fingerprint {
+                    Some(Ok(fp)) => rsx! {
+                        div {
+                            class: "text-xs text-dc-text-faint mb-2",
+                            "Backup key: "
+                            span { class: "font-mono text-dc-text", "{fp}" }
+                        }
+                    },
+                    Some(Err(e)) => rsx! {
+                        div { class: "text-xs text-red-400 mb-2", "{e}" }
+                    },
+                    None => rsx! {},
+                }
+                input {
+                    class: "w-full bg-dc-input border border-dc-border rounded px-3 py-2 mb-2 text-dc-text outline-none",
+                    r#type: "password",
+                    placeholder: "Backup password",
+                    value: "{import_password}",
+                    oninput: move |e| import_password.set(e.value()),
+                }
+                div {
+                    class: "flex justify-end gap-2 mt-2",
+                    button {
+                        class: "px-3 py-1 rounded bg-dc-accent text-white hover:opacity-90",
+                        disabled: busy() || import_file.read().is_none(),
+                        onclick: move |_| {
+                            let Some(backup) = import_file() else {
+                                return;
+                            };
+                            match IdentityKeys::import(&backup, &import_password()) {
+                                Ok(keys) => {
+                                    busy.set(true);
+                                    import_password.set(String::new());
+                                    adopt(keys, "Identity imported and published.");
+                                }
+                                Err(e) => error.set(Some(e.to_string())),
+                            }
+                        },
+                        "Import"
+                    }
+                    button {
+                        class: "px-4 py-1 rounded text-dc-text-muted hover:bg-dc-hover",
+                        onclick: move |_| on_close.call(()),
+                        "Close"
+                    }
+                }
+            }
+        }
+    }
+}
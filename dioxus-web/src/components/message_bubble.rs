@@ -1,7 +1,9 @@
-use crate::models::Message;
+use crate::models::{Message, SendState, Translation};
+use crate::state::AppState;
 use crate::utils;
 use dioxus::prelude::*;
 use regex::Regex;
+use tor_chat_common::{KeyChange, MessageContent, MessageLink};
 
 /// Color palette for avatar backgrounds based on username hash
 const AVATAR_COLORS: &[&str] = &[
@@ -34,14 +36,32 @@ pub fn MessageBubble(
     message: Message,
     is_continuation: Option<bool>,
     on_reply: Option<EventHandler<Message>>,
+    on_forward: Option<EventHandler<Message>>,
     on_pin: Option<EventHandler<Message>>,
     on_unpin: Option<EventHandler<Message>>,
+    on_retry: Option<EventHandler<Message>>,
     is_admin: Option<bool>,
 ) -> Element {
     let msg = message;
-    let is_image = msg.message_type == "image";
-    let is_youtube =
-        msg.content.contains("youtube.com/watch?v=") || msg.content.contains("youtu.be/");
+    let content = msg.decoded();
+    let app_state = use_context::<AppState>();
+    let text_only = *app_state.low_bandwidth.read();
+    let mut translation = use_signal(|| None::<Result<Translation, String>>);
+    let mut translating = use_signal(|| false);
+    // Only readable text is sent for translation, never file URLs or
+    // ciphertext, and nothing from after a room switched to E2EE
+    let translatable = (*app_state.translation_enabled.read() && msg.key_epoch.is_none())
+        .then(|| match &content {
+            MessageContent::Plaintext(text) => Some(text.clone()),
+            _ => None,
+        })
+        .flatten();
+    // Unsent messages have a local id only, so reply/pin don't apply yet
+    let actions_class = if msg.send_state.is_none() {
+        "msg-actions absolute right-4 -top-3 z-10 flex items-center bg-dc-sidebar border border-dc-border rounded shadow-lg"
+    } else {
+        "hidden"
+    };
     let is_pinned = msg.pinned_by.is_some();
     let admin = is_admin.unwrap_or(false);
     let continuation = is_continuation.unwrap_or(false);
@@ -69,7 +89,7 @@ pub fn MessageBubble(
 
             // Hover action toolbar
             div {
-                class: "msg-actions absolute right-4 -top-3 z-10 flex items-center bg-dc-sidebar border border-dc-border rounded shadow-lg",
+                class: "{actions_class}",
                 if let Some(handler) = &on_reply {
                     {
                         let msg_clone = msg.clone();
@@ -84,6 +104,63 @@ pub fn MessageBubble(
                         }
                     }
                 }
+                // The server refuses to copy messages in or out of E2EE rooms,
+                // and to copy expiring ones at all
+                if let Some(handler) = on_forward
+                    .as_ref()
+                    .filter(|_| !msg.in_e2ee_room() && msg.expires_in.is_none())
+                {
+                    {
+                        let msg_clone = msg.clone();
+                        let handler = *handler;
+                        rsx! {
+                            button {
+                                class: "px-2 py-1 text-dc-text-muted hover:text-dc-text hover:bg-dc-hover text-sm",
+                                title: "Forward",
+                                onclick: move |_| handler.call(msg_clone.clone()),
+                                "\u{21AA}"
+                            }
+                        }
+                    }
+                }
+                {
+                    let link = MessageLink::new(msg.room_id, msg.id).to_string();
+                    rsx! {
+                        button {
+                            class: "px-2 py-1 text-dc-text-muted hover:text-dc-text hover:bg-dc-hover text-sm",
+                            title: "Copy message link",
+                            onclick: move |_| utils::copy_to_clipboard(&link),
+                            "\u{1F517}"
+                        }
+                    }
+                }
+                if let Some(text) = translatable {
+                    {
+                        let api = app_state.api.clone();
+                        rsx! {
+                            button {
+                                class: "px-2 py-1 text-dc-text-muted hover:text-dc-text hover:bg-dc-hover text-sm",
+                                title: "Translate",
+                                disabled: translating(),
+                                onclick: move |_| {
+                                    if translation.read().is_some() {
+                                        translation.set(None);
+                                        return;
+                                    }
+                                    let api = api.clone();
+                                    let text = text.clone();
+                                    translating.set(true);
+                                    spawn(async move {
+                                        let result = api.translate(&text, &utils::browser_language()).await;
+                                        translation.set(Some(result));
+                                        translating.set(false);
+                                    });
+                                },
+                                "\u{1F310}"
+                            }
+                        }
+                    }
+                }
                 if admin {
                     if is_pinned {
                         if let Some(handler) = &on_unpin {
@@ -125,7 +202,11 @@ pub fn MessageBubble(
                 // Avatar column (40px)
                 div {
                     class: "flex-shrink-0 w-10",
-                    if !continuation {
+                    if text_only {
+                        if continuation {
+                            span { class: "text-[10px] text-dc-text-faint", "{timestamp}" }
+                        }
+                    } else if !continuation {
                         div {
                             class: "w-10 h-10 rounded-full {color} flex items-center justify-center text-white font-semibold text-sm select-none",
                             "{initials}"
@@ -165,6 +246,26 @@ pub fn MessageBubble(
                                     "\u{1F4CC} Pinned"
                                 }
                             }
+                            if msg.legacy_plaintext {
+                                span {
+                                    class: "text-xs text-dc-text-faint",
+                                    title: "Sent before this room switched to end-to-end encryption; the server can read it",
+                                    "\u{1F513}"
+                                }
+                            }
+                            if let Some(score) = msg.spam_score.filter(|_| msg.spam_action.is_some()) {
+                                {
+                                    let action = msg.spam_action.clone().unwrap_or_default();
+                                    let percent = (score * 100.0).round();
+                                    rsx! {
+                                        span {
+                                            class: "text-xs text-red-400",
+                                            title: "Spam score {percent}%",
+                                            "\u{26A0} Spam {percent}% ({action})"
+                                        }
+                                    }
+                                }
+                            }
                         }
                     }
 
@@ -175,25 +276,22 @@ pub fn MessageBubble(
                                 .and_then(|u| u.get("username"))
                                 .and_then(|v| v.as_str())
                                 .unwrap_or("Unknown");
-                            let reply_content = reply.get("content")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("");
-                            let truncated: String = if reply_content.len() > 100 {
-                                format!("{}...", &reply_content[..100])
-                            } else {
-                                reply_content.to_string()
-                            };
+                            let truncated = msg
+                                .decoded_reply()
+                                .map(|c| c.preview(100))
+                                .unwrap_or_default();
                             let reply_id = reply.get("id")
                                 .and_then(|v| v.as_str())
-                                .unwrap_or("")
-                                .to_string();
+                                .and_then(|s| uuid::Uuid::parse_str(s).ok());
+                            let room_id = msg.room_id;
+                            let state = app_state.clone();
                             rsx! {
                                 div {
                                     class: "flex items-center gap-1 mb-1 cursor-pointer group/reply",
                                     onclick: move |_| {
-                                        let rid = reply_id.clone();
-                                        utils::scroll_to_message(&rid);
-                                        utils::highlight_message(&rid);
+                                        if let Some(rid) = reply_id {
+                                            state.open_message(room_id, rid);
+                                        }
                                     },
                                     div {
                                         class: "w-0.5 h-full self-stretch bg-dc-text-muted rounded-full mr-1"
@@ -214,42 +312,87 @@ pub fn MessageBubble(
                         }
                     }
 
+                    if msg.forwarded_from.is_some() {
+                        div {
+                            class: "text-xs italic text-dc-text-faint mb-0.5",
+                            "\u{21AA} Forwarded"
+                        }
+                    }
+
                     // Message content
-                    if is_image {
-                        img {
-                            class: "max-w-md rounded-lg cursor-pointer hover:opacity-90 mt-1",
-                            src: "{msg.content}",
-                            alt: "Uploaded image",
-                            style: "max-height: 350px;",
+                    div {
+                        class: if msg.is_pending() { "opacity-60" } else { "" },
+                        MessageBody { content, text_only }
+                    }
+                    if let Some(expires_in) = msg.expires_in {
+                        ExpiryBadge {
+                            expires_in,
+                            after_read: msg.expires_after_read,
+                            expires_at: msg.expires_at,
                         }
-                    } else if is_youtube {
-                        {
-                            let video_id = extract_youtube_id(&msg.content);
-                            if let Some(id) = video_id {
-                                rsx! {
+                    }
+
+                    // Translation, shown under the original text
+                    if translating() {
+                        div { class: "mt-0.5 text-xs text-dc-text-faint", "Translating..." }
+                    }
+                    match &*translation.read() {
+                        Some(Ok(t)) => {
+                            let from = t
+                                .detected_language
+                                .as_deref()
+                                .map(|lang| format!(" from {}", lang))
+                                .unwrap_or_default();
+                            rsx! {
+                                div {
+                                    class: "mt-1 pl-2 border-l-2 border-dc-border",
+                                    div { class: "text-dc-text text-[0.9375rem] break-words", "{t.translated_text}" }
                                     div {
-                                        class: "max-w-lg mt-1",
-                                        div {
-                                            class: "aspect-video rounded-lg overflow-hidden bg-black",
-                                            iframe {
-                                                class: "w-full h-full",
-                                                src: "https://www.youtube.com/embed/{id}",
-                                                title: "YouTube video player",
-                                                frame_border: "0",
-                                                allow: "accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture",
-                                                allowfullscreen: true,
-                                            }
+                                        class: "text-xs text-dc-text-faint",
+                                        "\u{1F310} Translated{from} \u{00B7} "
+                                        button {
+                                            class: "hover:underline",
+                                            onclick: move |_| translation.set(None),
+                                            "Show original only"
                                         }
                                     }
                                 }
-                            } else {
-                                rsx! {
-                                    RichTextContent { text: msg.content.clone() }
-                                }
                             }
                         }
-                    } else {
-                        RichTextContent { text: msg.content.clone() }
+                        Some(Err(e)) => rsx! {
+                            div { class: "mt-0.5 text-xs text-red-400", "Translation failed: {e}" }
+                        },
+                        None => rsx! {},
+                    }
+
+                    // Delivery state of our own unsent messages
+                    match &msg.send_state {
+                        Some(SendState::Pending) => rsx! {
+                            div {
+                                class: "flex items-center gap-1 mt-0.5 text-xs text-dc-text-faint",
+                                span { class: "inline-block w-3 h-3 border-2 border-dc-text-faint border-t-transparent rounded-full animate-spin" }
+                                "Sending..."
+                            }
+                        },
+                        Some(SendState::Failed(reason)) => rsx! {
+                            div {
+                                class: "flex items-center gap-2 mt-0.5 text-xs text-red-400",
+                                span { title: "{reason}", "\u{26A0} Failed to send" }
+                                if let Some(handler) = on_retry {
+                                    {
+                                        let msg_clone = msg.clone();
+                                        rsx! {
+                                            button {
+                                                class: "underline hover:text-red-300",
+                                                onclick: move |_| handler.call(msg_clone.clone()),
+                                                "Retry"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        None => rsx! {},
                     }
                 }
             }
@@ -257,10 +400,82 @@ pub fn MessageBubble(
     }
 }
 
-/// Renders text with clickable URL links
+/// Renders a decoded message body. `text_only` replaces images with a
+/// click-to-load link and embeds with plain links.
+#[component]
+fn MessageBody(content: MessageContent, text_only: bool) -> Element {
+    let mut show_image = use_signal(|| false);
+
+    match &content {
+        MessageContent::File { url, name, .. }
+            if content.is_image() && text_only && !show_image() =>
+        {
+            let label = name.as_deref().unwrap_or("Image");
+            rsx! {
+                button {
+                    class: "inline-flex items-center gap-2 mt-1 px-3 py-2 bg-dc-sidebar rounded-lg text-blue-400 hover:underline",
+                    title: "{url}",
+                    onclick: move |_| show_image.set(true),
+                    "\u{1F4F7} {label} \u{2014} click to load"
+                }
+            }
+        }
+        MessageContent::File { url, .. } if content.is_image() => rsx! {
+            img {
+                class: "max-w-md rounded-lg cursor-pointer hover:opacity-90 mt-1",
+                src: "{url}",
+                alt: "Uploaded image",
+                style: "max-height: 350px;",
+            }
+        },
+        MessageContent::File { url, name, .. } => {
+            let label = name.as_deref().unwrap_or("Download file");
+            rsx! {
+                a {
+                    class: "inline-flex items-center gap-2 mt-1 px-3 py-2 bg-dc-sidebar rounded-lg text-blue-400 hover:underline",
+                    href: "{url}",
+                    target: "_blank",
+                    rel: "noopener noreferrer",
+                    "\u{1F4CE} {label}"
+                }
+            }
+        }
+        MessageContent::System(text) => rsx! {
+            div { class: "text-sm italic text-dc-text-muted", "{text}" }
+        },
+        MessageContent::Encrypted { .. } => rsx! {
+            div { class: "text-sm italic text-dc-text-faint", "\u{1F512} Encrypted message" }
+        },
+        MessageContent::Plaintext(text) => match youtube_id(text).filter(|_| !text_only) {
+            Some(id) => rsx! {
+                div {
+                    class: "max-w-lg mt-1",
+                    div {
+                        class: "aspect-video rounded-lg overflow-hidden bg-black",
+                        iframe {
+                            class: "w-full h-full",
+                            src: "https://www.youtube.com/embed/{id}",
+                            title: "YouTube video player",
+                            frame_border: "0",
+                            allow: "accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture",
+                            allowfullscreen: true,
+                        }
+                    }
+                }
+            },
+            None => rsx! {
+                RichTextContent { text: text.clone() }
+            },
+        },
+    }
+}
+
+/// Renders text with clickable URL links. Message links open the message
+/// here instead of in a new tab.
 #[component]
 fn RichTextContent(text: String) -> Element {
-    let url_re = Regex::new(r"(https?://[^\s<>\)\]]+)").unwrap();
+    let state = use_context::<AppState>();
+    let url_re = Regex::new(r"(https?://[^\s<>\)\]]+|tor-chat://[^\s<>\)\]]+)").unwrap();
 
     let mut parts: Vec<(bool, String)> = Vec::new();
     let mut last_end = 0;
@@ -284,7 +499,22 @@ fn RichTextContent(text: String) -> Element {
         div {
             class: "text-dc-text text-[0.9375rem] leading-[1.375rem] break-words",
             for (is_url, segment) in parts.iter() {
-                if *is_url {
+                if let Some((room_id, message_id)) = is_url
+                    .then(|| message_link_target(segment))
+                    .flatten()
+                {
+                    {
+                        let state = state.clone();
+                        rsx! {
+                            button {
+                                class: "text-blue-400 hover:underline",
+                                title: "Open message",
+                                onclick: move |_| state.open_message(room_id, message_id),
+                                "{segment}"
+                            }
+                        }
+                    }
+                } else if *is_url && !segment.starts_with("tor-chat://") {
                     a {
                         class: "text-blue-400 hover:underline",
                         href: "{segment}",
@@ -300,6 +530,14 @@ fn RichTextContent(text: String) -> Element {
     }
 }
 
+fn message_link_target(text: &str) -> Option<(uuid::Uuid, uuid::Uuid)> {
+    let link = MessageLink::parse(text)?;
+    Some((
+        uuid::Uuid::parse_str(&link.room_id).ok()?,
+        uuid::Uuid::parse_str(&link.message_id).ok()?,
+    ))
+}
+
 /// Date separator between different days
 #[component]
 pub fn DateSeparator(date_text: String) -> Element {
@@ -316,6 +554,123 @@ pub fn DateSeparator(date_text: String) -> Element {
     }
 }
 
+/// Countdown on an expiring message. Burn-after-read messages show their
+/// timer until someone reads them; the server removes them at `expires_at`.
+#[component]
+fn ExpiryBadge(
+    expires_in: i32,
+    after_read: bool,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Element {
+    let api = use_context::<AppState>().api;
+    // expires_at is the server's time, so count down on its clock
+    let mut now = use_signal({
+        let api = api.clone();
+        move || api.server_now()
+    });
+    use_future(move || {
+        let api = api.clone();
+        async move {
+            loop {
+                gloo_timers::future::TimeoutFuture::new(1000).await;
+                now.set(api.server_now());
+            }
+        }
+    });
+
+    let text = match expires_at {
+        Some(at) => {
+            let left = (at - now()).num_seconds().max(0);
+            format!("\u{1F525} {}", utils::format_duration(left))
+        }
+        None if after_read => format!(
+            "\u{1F525} {} after reading",
+            utils::format_duration(expires_in as i64)
+        ),
+        None => format!("\u{1F525} {}", utils::format_duration(expires_in as i64)),
+    };
+
+    rsx! {
+        div {
+            class: "text-xs text-orange-400",
+            title: "This message will be deleted for everyone",
+            "{text}"
+        }
+    }
+}
+
+/// Marks where the messages the user hasn't read yet begin
+#[component]
+pub fn UnreadDivider() -> Element {
+    rsx! {
+        div {
+            class: "flex items-center my-2 px-4",
+            div { class: "flex-1 h-px bg-red-500" }
+            span {
+                class: "px-2 text-xs font-semibold text-red-400",
+                "New messages"
+            }
+        }
+    }
+}
+
+/// Marks where a room switched to end-to-end encryption; messages above it
+/// were stored readable by the server
+#[component]
+pub fn E2eeDivider() -> Element {
+    rsx! {
+        div {
+            class: "flex items-center my-2 px-4",
+            div { class: "flex-1 h-px bg-emerald-600" }
+            span {
+                class: "px-2 text-xs font-semibold text-emerald-400",
+                "\u{1F6E1} End-to-end encryption enabled \u{2014} earlier messages were readable by the server"
+            }
+            div { class: "flex-1 h-px bg-emerald-600" }
+        }
+    }
+}
+
+/// System message shown when a correspondent's public key differs from the
+/// one remembered in this browser. Sending in the room is blocked until the
+/// user accepts the new key.
+#[component]
+pub fn KeyChangeNotice(change: KeyChange, on_accept: EventHandler<KeyChange>) -> Element {
+    let previous = change.previous_fingerprint();
+    let current = change.current_fingerprint();
+    rsx! {
+        div {
+            class: "mx-4 my-2 p-3 rounded border border-yellow-600 bg-yellow-900/30 text-sm",
+            div {
+                class: "font-semibold text-yellow-300 mb-1",
+                "\u{26A0} {change.username}'s identity key changed"
+            }
+            p {
+                class: "text-dc-text-muted mb-2",
+                "This happens when they reinstall or set up a new device, but it could also mean the server is impersonating them. Compare the new fingerprint with them over another channel before continuing."
+            }
+            div {
+                class: "font-mono text-xs text-dc-text-faint mb-2",
+                div { "Before: {previous}" }
+                div { class: "text-dc-text", "Now:    {current}" }
+            }
+            button {
+                class: "px-3 py-1 rounded bg-yellow-600 text-white hover:bg-yellow-500",
+                onclick: move |_| on_accept.call(change.clone()),
+                "Accept new key"
+            }
+        }
+    }
+}
+
+fn youtube_id(text: &str) -> Option<String> {
+    if text.contains("youtube.com/watch?v=") || text.contains("youtu.be/") {
+        extract_youtube_id(text)
+    } else {
+        None
+    }
+}
+
 fn extract_youtube_id(content: &str) -> Option<String> {
     if let Some(pos) = content.find("youtu.be/") {
         let start = pos + 9;
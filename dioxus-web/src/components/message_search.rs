@@ -0,0 +1,129 @@
+use crate::models::RoomSearchHits;
+use crate::state::AppState;
+use crate::utils;
+use dioxus::prelude::*;
+
+/// Characters of a message shown in a result
+const SNIPPET_CHARS: usize = 120;
+
+/// Search box in the sidebar header: Enter searches the messages of every
+/// room the user is in (end-to-end encrypted rooms excepted) and lists the
+/// matches by room below it; picking one opens the message.
+#[component]
+pub fn MessageSearch() -> Element {
+    let state = use_context::<AppState>();
+    let mut query = use_signal(String::new);
+    let mut results = use_signal(|| None::<Vec<RoomSearchHits>>);
+    let mut searching = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    let api = state.api.clone();
+    let run_search = move || {
+        let text = query().trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        let api = api.clone();
+        spawn(async move {
+            searching.set(true);
+            error.set(None);
+            match api.search_all_messages(&text).await {
+                Ok(found) => results.set(Some(found)),
+                Err(e) => {
+                    results.set(None);
+                    error.set(Some(e));
+                }
+            }
+            searching.set(false);
+        });
+    };
+
+    let mut close = move || {
+        results.set(None);
+        error.set(None);
+    };
+    let open = results().is_some() || error().is_some();
+
+    rsx! {
+        div {
+            class: "relative px-2 pt-2",
+            input {
+                class: "w-full bg-dc-input border border-dc-border rounded px-2.5 py-1.5 text-sm text-dc-text outline-none focus:border-dc-accent",
+                r#type: "search",
+                placeholder: "Search messages...",
+                aria_label: "Search messages in all rooms",
+                value: "{query}",
+                oninput: move |e| query.set(e.value()),
+                onkeydown: move |e| match e.key() {
+                    Key::Enter => run_search(),
+                    Key::Escape => close(),
+                    _ => {}
+                },
+            }
+            if searching() {
+                div { class: "absolute right-4 top-3.5 text-xs text-dc-text-faint", "Searching..." }
+            }
+            if open {
+                div {
+                    class: "absolute left-2 right-2 mt-1 z-30 max-h-[60vh] overflow-y-auto rounded bg-dc-dark border border-dc-border shadow-xl text-sm",
+                    div {
+                        class: "flex items-center px-2.5 py-1.5 border-b border-dc-border text-xs text-dc-text-muted",
+                        span { class: "flex-1", "Encrypted rooms aren't searched" }
+                        button {
+                            class: "hover:text-dc-text px-1",
+                            title: "Close",
+                            onclick: move |_| close(),
+                            "\u{2715}"
+                        }
+                    }
+                    if let Some(err) = error() {
+                        div { class: "px-2.5 py-2 text-red-400", "{err}" }
+                    }
+                    if let Some(groups) = results() {
+                        if groups.is_empty() {
+                            div { class: "px-2.5 py-2 text-dc-text-faint", "No messages found" }
+                        }
+                        for group in groups {
+                            div {
+                                key: "{group.room_id}",
+                                div {
+                                    class: "px-2.5 pt-2 pb-1 text-xs font-semibold uppercase tracking-wide text-dc-text-muted truncate",
+                                    "# {group.room_name.as_deref().unwrap_or_default()}"
+                                }
+                                for msg in group.messages.iter().cloned() {
+                                    {
+                                        let sender = msg
+                                            .user
+                                            .as_ref()
+                                            .map(|u| u.display_name.clone().unwrap_or_else(|| u.username.clone()))
+                                            .unwrap_or_default();
+                                        let snippet = msg.decoded().preview(SNIPPET_CHARS);
+                                        let when = utils::format_date(&msg.created_at);
+                                        let (room_id, message_id) = (msg.room_id, msg.id);
+                                        let state = state.clone();
+                                        rsx! {
+                                            button {
+                                                key: "{message_id}",
+                                                class: "w-full text-left px-2.5 py-1.5 hover:bg-dc-hover",
+                                                onclick: move |_| {
+                                                    state.open_message(room_id, message_id);
+                                                    close();
+                                                },
+                                                div {
+                                                    class: "flex gap-2 text-xs text-dc-text-faint",
+                                                    span { class: "flex-1 truncate font-medium text-dc-text-muted", "{sender}" }
+                                                    span { "{when}" }
+                                                }
+                                                div { class: "text-dc-text break-words", "{snippet}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
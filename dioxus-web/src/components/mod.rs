@@ -1,4 +1,19 @@
 // Component modules for reusable UI elements
 
+pub mod build_info;
+pub mod create_room;
+pub mod digest_card;
+pub mod files_panel;
+pub mod forward_picker;
+pub mod identity_keys;
 pub mod message_bubble;
+pub mod message_search;
+pub mod onion_prompt;
+pub mod quick_switcher;
+pub mod requests_card;
+pub mod retention_settings;
+pub mod room_info;
 pub mod room_list_item;
+pub mod room_stats;
+pub mod session_guard;
+pub mod toasts;
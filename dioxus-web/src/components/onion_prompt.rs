@@ -0,0 +1,62 @@
+use crate::{state::AppState, utils::storage};
+use dioxus::prelude::*;
+
+/// Banner offering to reopen the app on the server's onion address when it
+/// was loaded over clearnet
+#[component]
+pub fn OnionPrompt() -> Element {
+    let state = use_context::<AppState>();
+    let mut dismissed = use_signal(storage::onion_prompt_dismissed);
+
+    let api_client = state.api.clone();
+    let server_info = use_resource(move || {
+        let api = api_client.clone();
+        async move { api.get_server_info().await.ok() }
+    });
+
+    let location = web_sys::window().map(|w| w.location());
+    let on_onion = location
+        .as_ref()
+        .and_then(|l| l.hostname().ok())
+        .map(|host| host.ends_with(".onion"))
+        .unwrap_or(false);
+
+    let onion = server_info
+        .read()
+        .as_ref()
+        .and_then(|info| info.as_ref())
+        .and_then(|info| info.addresses.onion.clone());
+
+    let Some(onion) = onion.filter(|_| !on_onion && !dismissed()) else {
+        return rsx! {};
+    };
+
+    // Keep the user on the same page after switching
+    let path = location
+        .and_then(|l| Some(format!("{}{}", l.pathname().ok()?, l.hash().ok()?)))
+        .unwrap_or_default();
+    let target = format!("{}{}", onion.trim_end_matches('/'), path);
+
+    rsx! {
+        div {
+            class: "flex items-center justify-between gap-4 px-4 py-2 bg-purple-900 text-purple-100 text-sm",
+            span { "\u{1F9C5} This server is also available as an onion service. Open it in Tor Browser for better privacy." }
+            div {
+                class: "flex items-center gap-3 flex-shrink-0",
+                a {
+                    class: "font-semibold underline hover:text-white",
+                    href: "{target}",
+                    "Switch to onion"
+                }
+                button {
+                    class: "text-purple-300 hover:text-white",
+                    onclick: move |_| {
+                        storage::dismiss_onion_prompt();
+                        dismissed.set(true);
+                    },
+                    "\u{2715}"
+                }
+            }
+        }
+    }
+}
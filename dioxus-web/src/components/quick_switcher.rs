@@ -0,0 +1,125 @@
+use crate::models::Room;
+use crate::state::AppState;
+use crate::utils;
+use dioxus::prelude::*;
+use std::cmp::Reverse;
+use uuid::Uuid;
+
+/// Rooms listed at most
+const MAX_RESULTS: usize = 8;
+
+/// Ctrl+K overlay: type a few letters of a room's name and press Enter.
+/// With nothing typed it lists the rooms opened recently, then ones with
+/// unread messages.
+#[component]
+pub fn QuickSwitcher(
+    /// Rooms opened this session, most recent first
+    recent: Vec<Uuid>,
+    on_select: EventHandler<Uuid>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let state = use_context::<AppState>();
+    let mut query = use_signal(String::new);
+    let mut selected = use_signal(|| 0usize);
+
+    let text = query();
+    let recency = |id: Uuid| recent.iter().position(|r| *r == id).unwrap_or(usize::MAX);
+    let mut matches: Vec<(i32, usize, Room)> = state
+        .rooms
+        .read()
+        .iter()
+        .filter_map(|room| {
+            let score = if text.trim().is_empty() {
+                0
+            } else {
+                utils::fuzzy_score(&text, &room.name)?
+            };
+            Some((score, recency(room.id), room.clone()))
+        })
+        .collect();
+    if text.trim().is_empty() {
+        matches.sort_by_key(|(_, recency, room)| {
+            (*recency, room.unread_count == 0 && !room.mentioned)
+        });
+    } else {
+        matches.sort_by_key(|(score, recency, _)| (Reverse(*score), *recency));
+    }
+    let rooms: Vec<Room> = matches
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(_, _, room)| room)
+        .collect();
+    let highlighted = selected().min(rooms.len().saturating_sub(1));
+    let ids: Vec<Uuid> = rooms.iter().map(|r| r.id).collect();
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black bg-opacity-60 flex items-start justify-center pt-[15vh] z-50",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "bg-dc-sidebar rounded-lg p-3 w-[28rem] max-w-full mx-4 flex flex-col border border-dc-border shadow-xl text-sm text-dc-text",
+                onclick: move |e| e.stop_propagation(),
+                input {
+                    class: "w-full bg-dc-input border border-dc-border rounded px-3 py-2 mb-2 text-dc-text outline-none",
+                    placeholder: "Jump to a room...",
+                    autofocus: true,
+                    value: "{query}",
+                    onmounted: move |e| async move {
+                        let _ = e.set_focus(true).await;
+                    },
+                    oninput: move |e| {
+                        query.set(e.value());
+                        selected.set(0);
+                    },
+                    onkeydown: move |e| match e.key() {
+                        Key::ArrowDown => {
+                            e.prevent_default();
+                            selected.set((highlighted + 1).min(ids.len().saturating_sub(1)));
+                        }
+                        Key::ArrowUp => {
+                            e.prevent_default();
+                            selected.set(highlighted.saturating_sub(1));
+                        }
+                        Key::Enter => {
+                            if let Some(id) = ids.get(highlighted) {
+                                on_select.call(*id);
+                            }
+                        }
+                        Key::Escape => on_close.call(()),
+                        _ => {}
+                    },
+                }
+                if rooms.is_empty() {
+                    div { class: "text-dc-text-faint px-2 py-1.5", "No matching rooms" }
+                }
+                for (i, room) in rooms.into_iter().enumerate() {
+                    button {
+                        key: "{room.id}",
+                        class: if i == highlighted {
+                            "w-full flex items-center gap-2 text-left px-2 py-1.5 rounded bg-dc-hover text-white"
+                        } else {
+                            "w-full flex items-center gap-2 text-left px-2 py-1.5 rounded text-dc-text-muted"
+                        },
+                        onmouseenter: move |_| selected.set(i),
+                        onclick: move |_| on_select.call(room.id),
+                        span {
+                            class: "opacity-70",
+                            if room.is_public { "#" } else { "\u{1F512}" }
+                        }
+                        span { class: "flex-1 truncate", "{room.name}" }
+                        if room.mentioned {
+                            span { class: "text-red-400 text-xs font-bold", "@" }
+                        }
+                        if room.unread_count > 0 {
+                            span { class: "text-dc-accent text-xs font-bold", "{room.unread_count}" }
+                        }
+                    }
+                }
+                div {
+                    class: "text-dc-text-faint text-xs px-2 pt-2",
+                    "\u{2191}\u{2193} to choose, Enter to open, Esc to close"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::models::{RequestSettings, RoomRequest};
+use crate::state::AppState;
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+/// Sidebar card with adds to private rooms waiting for the user to accept,
+/// and the setting that decides who has to ask first
+#[component]
+pub fn RequestsCard() -> Element {
+    let state = use_context::<AppState>();
+    let mut show_settings = use_signal(|| false);
+    let mut settings = use_signal(|| None::<RequestSettings>);
+    let mut error = use_signal(|| None::<String>);
+
+    let open_settings = {
+        let api = state.api.clone();
+        move |_| {
+            let open = !show_settings();
+            show_settings.set(open);
+            if open && settings.peek().is_none() {
+                let api = api.clone();
+                spawn(async move {
+                    match api.get_request_settings().await {
+                        Ok(s) => settings.set(Some(s)),
+                        Err(e) => error.set(Some(e)),
+                    }
+                });
+            }
+        }
+    };
+
+    let toggle_members_only = {
+        let api = state.api.clone();
+        move |members_only: bool| {
+            let api = api.clone();
+            let updated = RequestSettings {
+                accept_from: if members_only { "members" } else { "everyone" }.to_string(),
+            };
+            spawn(async move {
+                error.set(None);
+                match api.update_request_settings(&updated).await {
+                    Ok(()) => settings.set(Some(updated)),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    let answer = {
+        let state = state.clone();
+        move |room_id: Uuid, accept: bool| {
+            let state = state.clone();
+            spawn(async move {
+                error.set(None);
+                let id = room_id.to_string();
+                let result = if accept {
+                    state.api.accept_request(&id).await.map(|room| {
+                        let mut rooms = state.rooms;
+                        let mut rooms = rooms.write();
+                        if !rooms.iter().any(|r| r.id == room.id) {
+                            rooms.push(room);
+                        }
+                    })
+                } else {
+                    state.api.decline_request(&id).await
+                };
+                match result {
+                    Ok(()) => {
+                        let mut requests = state.room_requests;
+                        requests.write().retain(|r| r.room_id != room_id);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    let requests: Vec<RoomRequest> = state.room_requests.read().clone();
+
+    rsx! {
+        div {
+            class: "mx-2 mt-2 rounded bg-dc-dark text-xs",
+            div {
+                class: "flex items-center px-2.5 py-1.5",
+                span { class: "flex-1 font-semibold text-dc-text-muted uppercase tracking-wide", "Room requests" }
+                if !requests.is_empty() {
+                    span { class: "text-dc-accent font-bold px-1", "{requests.len()}" }
+                }
+                button {
+                    class: "text-dc-text-muted hover:text-dc-text px-1",
+                    title: "Who can add me to rooms",
+                    onclick: open_settings,
+                    "\u{2699}"
+                }
+            }
+
+            if show_settings() {
+                div {
+                    class: "px-2.5 pb-2 text-dc-text-muted",
+                    if let Some(current) = settings() {
+                        {
+                            let members_only = current.accept_from == "members";
+                            let toggle = toggle_members_only.clone();
+                            rsx! {
+                                label {
+                                    class: "flex items-center gap-2 cursor-pointer",
+                                    input {
+                                        r#type: "checkbox",
+                                        checked: members_only,
+                                        onchange: move |_| toggle(!members_only),
+                                    }
+                                    "Ask me first when someone I don't share a room with adds me"
+                                }
+                            }
+                        }
+                    } else if error().is_none() {
+                        "Loading..."
+                    }
+                }
+            }
+            if let Some(err) = error() {
+                div { class: "px-2.5 pb-2 text-red-400", "{err}" }
+            }
+
+            if !requests.is_empty() {
+                ul {
+                    class: "px-2.5 pb-2 space-y-1.5",
+                    for request in requests {
+                        {
+                            let from = request
+                                .from
+                                .display_name
+                                .clone()
+                                .unwrap_or_else(|| request.from.username.clone());
+                            let room_id = request.room_id;
+                            let accept = answer.clone();
+                            let decline = answer.clone();
+                            rsx! {
+                                li {
+                                    key: "{room_id}",
+                                    class: "text-dc-text",
+                                    div {
+                                        class: "truncate",
+                                        span { class: "font-semibold", "{from}" }
+                                        span { class: "text-dc-text-muted", " wants to add you to " }
+                                        span { class: "font-semibold", "{request.room_name}" }
+                                    }
+                                    div {
+                                        class: "flex gap-2 mt-1",
+                                        button {
+                                            class: "bg-dc-accent hover:opacity-90 text-white rounded px-2.5 py-1",
+                                            onclick: move |_| accept(room_id, true),
+                                            "Join"
+                                        }
+                                        button {
+                                            class: "text-dc-text-muted hover:text-dc-text rounded px-2.5 py-1 hover:bg-dc-hover",
+                                            onclick: move |_| decline(room_id, false),
+                                            "Decline"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
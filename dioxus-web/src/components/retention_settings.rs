@@ -0,0 +1,166 @@
+use crate::models::RetentionRule;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+/// Retention periods offered in the UI, in days
+pub const PERIODS: [(i32, &str); 6] = [
+    (1, "1 day"),
+    (7, "1 week"),
+    (30, "30 days"),
+    (90, "90 days"),
+    (180, "6 months"),
+    (365, "1 year"),
+];
+
+fn period_label(days: i32) -> String {
+    PERIODS
+        .iter()
+        .find(|(d, _)| *d == days)
+        .map(|(_, label)| label.to_string())
+        .unwrap_or_else(|| format!("{} days", days))
+}
+
+/// Profile privacy settings: automatic deletion of the user's own messages
+/// after a period, everywhere or per room (room rules win)
+#[component]
+pub fn RetentionSettings(on_close: EventHandler<()>) -> Element {
+    let state = use_context::<AppState>();
+    let mut rules = use_signal(Vec::<RetentionRule>::new);
+    let mut error = use_signal(|| None::<String>);
+    let mut new_room = use_signal(String::new);
+    let mut new_days = use_signal(|| 30i32);
+
+    use_hook({
+        let api = state.api.clone();
+        move || {
+            spawn(async move {
+                match api.get_retention_rules().await {
+                    Ok(r) => rules.set(r),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    });
+
+    let save = {
+        let api = state.api.clone();
+        move |room_id: Option<String>, days: Option<i32>| {
+            let api = api.clone();
+            spawn(async move {
+                error.set(None);
+                match api.set_retention_rule(room_id.as_deref(), days).await {
+                    Ok(r) => rules.set(r),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    let global = rules
+        .read()
+        .iter()
+        .find(|r| r.room_id.is_none())
+        .map(|r| r.days);
+    let room_rules: Vec<RetentionRule> = rules
+        .read()
+        .iter()
+        .filter(|r| r.room_id.is_some())
+        .cloned()
+        .collect();
+    let rooms = state.rooms.read().clone();
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black bg-opacity-60 flex items-center justify-center z-50",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "bg-dc-sidebar rounded-lg p-5 w-[28rem] max-w-full mx-4 max-h-[80vh] overflow-y-auto border border-dc-border shadow-xl text-sm text-dc-text",
+                onclick: move |e| e.stop_propagation(),
+                h2 { class: "text-lg font-semibold text-white mb-1", "Message retention" }
+                p {
+                    class: "text-dc-text-muted mb-4",
+                    "Your own messages are deleted for everyone once they are older than the period you choose. Deleted messages can't be recovered."
+                }
+                if let Some(err) = error() {
+                    div { class: "bg-red-900/50 text-red-200 p-2 rounded mb-3", "{err}" }
+                }
+
+                label {
+                    class: "flex items-center justify-between mb-4",
+                    span { "All rooms" }
+                    select {
+                        class: "bg-dc-input border border-dc-border rounded px-2 py-1",
+                        onchange: {
+                            let save = save.clone();
+                            move |e: Event<FormData>| save(None, e.value().parse().ok())
+                        },
+                        option { value: "", selected: global.is_none(), "Keep forever" }
+                        for (days, label) in PERIODS {
+                            option { value: "{days}", selected: global == Some(days), "{label}" }
+                        }
+                    }
+                }
+
+                h3 { class: "font-semibold text-white mb-2", "Per-room overrides" }
+                for rule in room_rules {
+                    {
+                        let room_id = rule.room_id.map(|id| id.to_string());
+                        let name = rule.room_name.clone().unwrap_or_else(|| "Unknown room".to_string());
+                        let period = period_label(rule.days);
+                        let save = save.clone();
+                        rsx! {
+                            div {
+                                key: "{name}",
+                                class: "flex items-center justify-between py-1",
+                                span { "{name} \u{2014} {period}" }
+                                button {
+                                    class: "text-dc-text-muted hover:text-red-400",
+                                    title: "Remove override",
+                                    onclick: move |_| save(room_id.clone(), None),
+                                    "\u{2715}"
+                                }
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "flex gap-2 mt-2",
+                    select {
+                        class: "flex-1 bg-dc-input border border-dc-border rounded px-2 py-1",
+                        onchange: move |e| new_room.set(e.value()),
+                        option { value: "", "Choose a room..." }
+                        for room in rooms {
+                            option { value: "{room.id}", "{room.name}" }
+                        }
+                    }
+                    select {
+                        class: "bg-dc-input border border-dc-border rounded px-2 py-1",
+                        onchange: move |e| {
+                            if let Ok(days) = e.value().parse() {
+                                new_days.set(days);
+                            }
+                        },
+                        for (days, label) in PERIODS {
+                            option { value: "{days}", selected: new_days() == days, "{label}" }
+                        }
+                    }
+                    button {
+                        class: "bg-dc-accent hover:bg-dc-accent-dim text-white px-3 py-1 rounded disabled:opacity-50",
+                        disabled: new_room().is_empty(),
+                        onclick: move |_| save(Some(new_room()), Some(new_days())),
+                        "Add"
+                    }
+                }
+
+                div {
+                    class: "flex justify-end mt-5",
+                    button {
+                        class: "px-4 py-2 text-dc-text-muted hover:text-white",
+                        onclick: move |_| on_close.call(()),
+                        "Close"
+                    }
+                }
+            }
+        }
+    }
+}
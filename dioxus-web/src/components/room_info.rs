@@ -0,0 +1,679 @@
+use crate::components::{files_panel::FilesPanel, room_stats::RoomStatsView};
+use crate::models::{Message, NotificationSettings, Room, RoomInvite};
+use crate::state::AppState;
+use crate::utils;
+use chrono::Duration;
+use dioxus::prelude::*;
+
+/// Notification levels the server accepts, with their labels
+const LEVELS: [(&str, &str); 3] = [
+    ("all", "All messages"),
+    ("mentions", "Only @mentions"),
+    ("none", "Nothing"),
+];
+
+/// Mute presets in hours (0 = not muted)
+const MUTE_HOURS: [(&str, i64); 5] = [
+    ("Not muted", 0),
+    ("1 hour", 1),
+    ("8 hours", 8),
+    ("1 day", 24),
+    ("1 week", 168),
+];
+
+/// Invite limits offered when creating a link (0 = no limit)
+pub const INVITE_USES: [(&str, i32); 5] = [
+    ("Unlimited uses", 0),
+    ("1 use", 1),
+    ("5 uses", 5),
+    ("25 uses", 25),
+    ("100 uses", 100),
+];
+pub const INVITE_EXPIRY: [(&str, i64); 4] = [
+    ("Never expires", 0),
+    ("Expires in 1 hour", 1),
+    ("Expires in 1 day", 24),
+    ("Expires in 7 days", 168),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tab {
+    Overview,
+    Pinned,
+    Files,
+    Members,
+    Stats,
+}
+
+impl Tab {
+    fn label(self) -> &'static str {
+        match self {
+            Tab::Overview => "Overview",
+            Tab::Pinned => "Pinned",
+            Tab::Files => "Files",
+            Tab::Members => "Members",
+            Tab::Stats => "Stats",
+        }
+    }
+}
+
+/// Everything about a room in one place: description, notification
+/// settings, invites, pinned messages, shared files, member roles and (for
+/// room admins) activity stats. `can_manage` is true for the room creator and
+/// global admins; room admins are recognised from the member list.
+#[component]
+pub fn RoomInfoPanel(room: Room, can_manage: bool, on_close: EventHandler<()>) -> Element {
+    let state = use_context::<AppState>();
+    let mut tab = use_signal(|| Tab::Overview);
+    let mut members = use_signal(Vec::<serde_json::Value>::new);
+
+    let room_id = room.id.to_string();
+    use_hook({
+        let state = state.clone();
+        let room_id = room_id.clone();
+        move || {
+            spawn(async move {
+                match state.api.get_room_members(&room_id).await {
+                    Ok(list) => members.set(list),
+                    Err(e) => state.toast_error(format!("Couldn't load members: {}", e)),
+                }
+            });
+        }
+    });
+
+    let me = state.current_user.read().as_ref().map(|u| u.id.to_string());
+    let is_room_admin = me.is_some()
+        && members
+            .read()
+            .iter()
+            .any(|m| m["userId"].as_str() == me.as_deref() && m["role"].as_str() == Some("admin"));
+    let can_manage = can_manage || is_room_admin;
+
+    let tabs: Vec<Tab> = [Tab::Overview, Tab::Pinned, Tab::Files, Tab::Members]
+        .into_iter()
+        .chain(can_manage.then_some(Tab::Stats))
+        .collect();
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black bg-opacity-60 flex items-center justify-center z-50",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "bg-dc-sidebar rounded-lg p-5 w-[36rem] max-w-full mx-4 max-h-[85vh] flex flex-col border border-dc-border shadow-xl text-sm text-dc-text",
+                onclick: move |e| e.stop_propagation(),
+                h2 { class: "text-lg font-semibold text-white mb-2", "# {room.name}" }
+                div {
+                    class: "flex gap-1 mb-3 border-b border-dc-border",
+                    for t in tabs {
+                        button {
+                            key: "{t.label()}",
+                            class: if tab() == t {
+                                "px-3 py-1.5 text-white border-b-2 border-dc-accent"
+                            } else {
+                                "px-3 py-1.5 text-dc-text-muted hover:text-dc-text"
+                            },
+                            onclick: move |_| tab.set(t),
+                            "{t.label()}"
+                        }
+                    }
+                }
+                div {
+                    class: "flex-1 overflow-y-auto",
+                    match tab() {
+                        Tab::Overview => rsx! {
+                            OverviewTab { room: room.clone(), can_manage }
+                        },
+                        Tab::Pinned => rsx! {
+                            PinsTab { room_id: room_id.clone(), on_jump: move |_| on_close.call(()) }
+                        },
+                        Tab::Files => rsx! {
+                            FilesPanel { room_id: room_id.clone(), embedded: true }
+                        },
+                        Tab::Members => rsx! {
+                            MembersTab { room: room.clone(), can_manage, members }
+                        },
+                        Tab::Stats => rsx! {
+                            RoomStatsView { room_id: room_id.clone() }
+                        },
+                    }
+                }
+                div {
+                    class: "flex justify-end mt-4",
+                    button {
+                        class: "px-4 py-2 rounded bg-dc-accent text-white hover:opacity-90",
+                        onclick: move |_| on_close.call(()),
+                        "Close"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn OverviewTab(room: Room, can_manage: bool) -> Element {
+    let state = use_context::<AppState>();
+    let room_id = room.id.to_string();
+    let mut description = use_signal(|| room.description.clone().unwrap_or_default());
+    let mut status = use_signal(|| None::<Result<String, String>>);
+
+    let save_description = {
+        let state = state.clone();
+        let room_id = room_id.clone();
+        move |_| {
+            let state = state.clone();
+            let room_id = room_id.clone();
+            let text = description();
+            spawn(async move {
+                match state.api.update_room(&room_id, None, Some(&text)).await {
+                    Ok(updated) => {
+                        state.apply_room_update(&updated);
+                        status.set(Some(Ok("Description saved".to_string())));
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+            });
+        }
+    };
+
+    rsx! {
+        h3 { class: "font-semibold text-white mb-1", "Description" }
+        if can_manage {
+            textarea {
+                class: "w-full bg-dc-input border border-dc-border rounded px-3 py-2 text-dc-text outline-none resize-none",
+                rows: "3",
+                maxlength: "500",
+                placeholder: "What is this room about?",
+                value: "{description}",
+                oninput: move |e| description.set(e.value()),
+            }
+            div {
+                class: "flex items-center justify-end gap-2 mt-1",
+                match status() {
+                    Some(Ok(msg)) => rsx! { span { class: "text-xs text-green-400", "{msg}" } },
+                    Some(Err(e)) => rsx! { span { class: "text-xs text-red-400", "{e}" } },
+                    None => rsx! {},
+                }
+                button {
+                    class: "px-3 py-1 rounded bg-dc-accent text-white hover:opacity-90",
+                    onclick: save_description,
+                    "Save"
+                }
+            }
+        } else if let Some(desc) = &room.description {
+            p { class: "text-dc-text-muted", "{desc}" }
+        } else {
+            p { class: "text-dc-text-faint", "No description" }
+        }
+
+        NotificationSection { room_id: room_id.clone() }
+
+        EncryptionSection { room: room.clone() }
+
+        if can_manage {
+            InviteSection { room_id }
+        }
+    }
+}
+
+#[component]
+fn NotificationSection(room_id: String) -> Element {
+    let state = use_context::<AppState>();
+    let mut settings = use_signal(|| None::<NotificationSettings>);
+    let mut error = use_signal(|| None::<String>);
+
+    use_hook({
+        let api = state.api.clone();
+        let room_id = room_id.clone();
+        move || {
+            spawn(async move {
+                match api.get_notification_settings(&room_id).await {
+                    Ok(s) => settings.set(Some(s)),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    });
+
+    let save = {
+        let api = state.api.clone();
+        move |new: NotificationSettings| {
+            let api = api.clone();
+            let room_id = room_id.clone();
+            spawn(async move {
+                match api.update_notification_settings(&room_id, &new).await {
+                    Ok(saved) => {
+                        settings.set(Some(saved));
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    let current = settings();
+    let muted_until = current
+        .as_ref()
+        .and_then(|s| s.muted_until)
+        .filter(|until| *until > state.api.server_now())
+        .map(|until| utils::format_full_timestamp(&until));
+    let api = state.api.clone();
+
+    rsx! {
+        h3 { class: "font-semibold text-white mt-4 mb-1", "Notifications" }
+        if let Some(err) = error() {
+            div { class: "text-xs text-red-400 mb-1", "{err}" }
+        }
+        if let Some(current) = current {
+            div {
+                class: "flex gap-2",
+                {
+                    let save = save.clone();
+                    let current = current.clone();
+                    rsx! {
+                        select {
+                            class: "flex-1 bg-dc-input border border-dc-border rounded px-2 py-1.5 text-dc-text",
+                            value: "{current.level}",
+                            onchange: move |e| save(NotificationSettings {
+                                level: e.value(),
+                                muted_until: current.muted_until,
+                            }),
+                            for (level, label) in LEVELS {
+                                option { value: "{level}", selected: current.level == level, "{label}" }
+                            }
+                        }
+                    }
+                }
+                select {
+                    class: "flex-1 bg-dc-input border border-dc-border rounded px-2 py-1.5 text-dc-text",
+                    onchange: move |e| {
+                        let hours: i64 = e.value().parse().unwrap_or(0);
+                        // The server compares this with its own clock
+                        let from = api.server_now();
+                        save(NotificationSettings {
+                            level: current.level.clone(),
+                            muted_until: (hours > 0).then(|| from + Duration::hours(hours)),
+                        })
+                    },
+                    option { value: "", disabled: true, selected: true, "Mute for..." }
+                    for (label, hours) in MUTE_HOURS {
+                        option { value: "{hours}", "{label}" }
+                    }
+                }
+            }
+            if let Some(until) = muted_until {
+                p { class: "text-xs text-dc-text-faint mt-1", "Muted until {until}" }
+            }
+        } else if error().is_none() {
+            div { class: "text-dc-text-faint", "Loading..." }
+        }
+    }
+}
+
+/// Current encryption mode, and for the room creator the one-way switch to
+/// E2EE with an explanation of what changes
+#[component]
+fn EncryptionSection(room: Room) -> Element {
+    let state = use_context::<AppState>();
+    let mut confirming = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+    let is_creator = room.creator_id.is_some_and(|creator| {
+        state
+            .current_user
+            .read()
+            .as_ref()
+            .is_some_and(|u| u.id == creator)
+    });
+
+    let enable = {
+        let state = state.clone();
+        let room_id = room.id.to_string();
+        move |_| {
+            let state = state.clone();
+            let room_id = room_id.clone();
+            spawn(async move {
+                match state.api.enable_e2ee(&room_id).await {
+                    Ok(updated) => {
+                        state.apply_e2ee_switch(updated.id, updated.key_epoch, updated.e2ee_since);
+                        confirming.set(false);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    // The panel holds a snapshot of the room; the live copy reflects the switch
+    let live = state
+        .rooms
+        .read()
+        .iter()
+        .find(|r| r.id == room.id)
+        .unwrap_or(&room)
+        .clone();
+
+    rsx! {
+        h3 { class: "font-semibold text-white mt-4 mb-1", "Encryption" }
+        if live.is_e2ee() {
+            {
+                let since = live
+                    .e2ee_since
+                    .map(|at| utils::format_full_timestamp(&at))
+                    .unwrap_or_default();
+                rsx! {
+                    p {
+                        class: "text-emerald-300",
+                        "\u{1F6E1} End-to-end encrypted since {since}."
+                    }
+                    p {
+                        class: "text-xs text-dc-text-faint mt-1",
+                        "Earlier messages are kept as they were and marked \u{1F513}. Server-side search, translation and forwarding are off for this room."
+                    }
+                }
+            }
+        } else {
+            p {
+                class: "text-dc-text-muted",
+                "Messages in this room are stored so the server can read them. This keeps search, translation and forwarding available."
+            }
+            if is_creator && !confirming() {
+                button {
+                    class: "mt-2 px-3 py-1 rounded bg-emerald-700 text-white hover:opacity-90",
+                    onclick: move |_| confirming.set(true),
+                    "Switch to end-to-end encryption..."
+                }
+            }
+            if confirming() {
+                div {
+                    class: "mt-2 p-3 rounded bg-dc-dark border border-dc-border",
+                    p { class: "text-white font-semibold mb-1", "Switch this room to end-to-end encryption?" }
+                    ul {
+                        class: "list-disc pl-5 text-dc-text-muted space-y-0.5",
+                        li { "New messages can only be read by room members, not by the server." }
+                        li { "Existing messages stay as they are and are marked as sent before encryption." }
+                        li { "Server-side search, translation and forwarding stop working in this room." }
+                        li { "This can't be undone." }
+                    }
+                    if let Some(err) = error() {
+                        div { class: "text-xs text-red-400 mt-2", "{err}" }
+                    }
+                    div {
+                        class: "flex justify-end gap-2 mt-3",
+                        button {
+                            class: "px-3 py-1 rounded text-dc-text-muted hover:bg-dc-hover",
+                            onclick: move |_| confirming.set(false),
+                            "Cancel"
+                        }
+                        button {
+                            class: "px-3 py-1 rounded bg-emerald-700 text-white hover:opacity-90",
+                            onclick: enable,
+                            "Enable encryption"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn InviteSection(room_id: String) -> Element {
+    let state = use_context::<AppState>();
+    let mut invites = use_signal(Vec::<RoomInvite>::new);
+    let mut max_uses = use_signal(|| 0i32);
+    let mut expires_in = use_signal(|| 24i64);
+    let mut error = use_signal(|| None::<String>);
+
+    use_hook({
+        let api = state.api.clone();
+        let room_id = room_id.clone();
+        move || {
+            spawn(async move {
+                match api.list_invites(&room_id).await {
+                    Ok(list) => invites.set(list),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    });
+
+    let create = {
+        let api = state.api.clone();
+        let room_id = room_id.clone();
+        move |_| {
+            let api = api.clone();
+            let room_id = room_id.clone();
+            let uses = Some(max_uses()).filter(|n| *n > 0);
+            let hours = Some(expires_in()).filter(|h| *h > 0);
+            spawn(async move {
+                match api.create_invite(&room_id, uses, hours).await {
+                    Ok(invite) => {
+                        invites.write().insert(0, invite);
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    let origin = web_sys::window()
+        .and_then(|w| w.location().origin().ok())
+        .unwrap_or_default();
+
+    rsx! {
+        h3 { class: "font-semibold text-white mt-4 mb-1", "Invite links" }
+        if let Some(err) = error() {
+            div { class: "text-xs text-red-400 mb-1", "{err}" }
+        }
+        div {
+            class: "flex gap-2 mb-2",
+            select {
+                class: "flex-1 bg-dc-input border border-dc-border rounded px-2 py-1.5 text-dc-text",
+                onchange: move |e| max_uses.set(e.value().parse().unwrap_or(0)),
+                for (label, uses) in INVITE_USES {
+                    option { value: "{uses}", selected: max_uses() == uses, "{label}" }
+                }
+            }
+            select {
+                class: "flex-1 bg-dc-input border border-dc-border rounded px-2 py-1.5 text-dc-text",
+                onchange: move |e| expires_in.set(e.value().parse().unwrap_or(0)),
+                for (label, hours) in INVITE_EXPIRY {
+                    option { value: "{hours}", selected: expires_in() == hours, "{label}" }
+                }
+            }
+            button {
+                class: "px-3 py-1 rounded bg-dc-accent text-white hover:opacity-90",
+                onclick: create,
+                "Create"
+            }
+        }
+        if invites.read().is_empty() {
+            div { class: "text-dc-text-faint", "No active invites" }
+        }
+        for invite in invites() {
+            {
+                let link = format!("{}/invite/{}", origin, invite.code);
+                let uses = match invite.max_uses {
+                    Some(max) => format!("{}/{} uses", invite.uses, max),
+                    None => format!("{} uses", invite.uses),
+                };
+                let expiry = invite
+                    .expires_at
+                    .map(|at| format!(" \u{00B7} expires {}", utils::format_date(&at)))
+                    .unwrap_or_default();
+                let api = state.api.clone();
+                let room_id = room_id.clone();
+                let code = invite.code.clone();
+                rsx! {
+                    div {
+                        key: "{invite.code}",
+                        class: "flex items-center gap-2 py-1",
+                        div {
+                            class: "flex-1 min-w-0",
+                            input {
+                                class: "w-full bg-dc-dark rounded px-2 py-1 text-xs text-dc-text font-mono",
+                                readonly: true,
+                                value: "{link}",
+                            }
+                            div { class: "text-[11px] text-dc-text-faint", "{uses}{expiry}" }
+                        }
+                        button {
+                            class: "text-xs text-dc-text-muted hover:text-red-400",
+                            onclick: move |_| {
+                                let api = api.clone();
+                                let room_id = room_id.clone();
+                                let code = code.clone();
+                                spawn(async move {
+                                    match api.revoke_invite(&room_id, &code).await {
+                                        Ok(()) => invites.write().retain(|i| i.code != code),
+                                        Err(e) => error.set(Some(e)),
+                                    }
+                                });
+                            },
+                            "Revoke"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn PinsTab(room_id: String, on_jump: EventHandler<()>) -> Element {
+    let state = use_context::<AppState>();
+    let pins = use_resource(move || {
+        let api = state.api.clone();
+        let room_id = room_id.clone();
+        async move { api.get_pins(&room_id).await }
+    });
+
+    match &*pins.read() {
+        None => rsx! {
+            div { class: "text-dc-text-faint py-4", "Loading pinned messages..." }
+        },
+        Some(Err(e)) => rsx! {
+            div { class: "bg-red-900/50 text-red-200 p-2 rounded", "{e}" }
+        },
+        Some(Ok(list)) if list.is_empty() => rsx! {
+            div { class: "text-dc-text-faint py-4 text-center", "Nothing pinned yet" }
+        },
+        Some(Ok(list)) => rsx! {
+            for msg in list.clone() {
+                PinRow { key: "{msg.id}", msg, on_jump }
+            }
+        },
+    }
+}
+
+#[component]
+fn PinRow(msg: Message, on_jump: EventHandler<()>) -> Element {
+    let author = msg
+        .user
+        .as_ref()
+        .map(|u| u.display_name.clone().unwrap_or_else(|| u.username.clone()))
+        .unwrap_or_else(|| "Unknown".to_string());
+    let preview = msg.decoded().preview(120);
+    let date = utils::format_date(&msg.created_at);
+    let state = use_context::<AppState>();
+
+    rsx! {
+        button {
+            class: "w-full text-left px-2 py-1.5 rounded hover:bg-dc-hover",
+            title: "Jump to message",
+            onclick: move |_| {
+                on_jump.call(());
+                state.open_message(msg.room_id, msg.id);
+            },
+            div { class: "text-xs text-dc-text-faint", "{author} \u{00B7} {date}" }
+            div { class: "text-dc-text truncate", "{preview}" }
+        }
+    }
+}
+
+#[component]
+fn MembersTab(room: Room, can_manage: bool, members: Signal<Vec<serde_json::Value>>) -> Element {
+    let state = use_context::<AppState>();
+    let mut error = use_signal(|| None::<String>);
+    let room_id = room.id.to_string();
+
+    rsx! {
+        if let Some(err) = error() {
+            div { class: "text-xs text-red-400 mb-1", "{err}" }
+        }
+        for member in members() {
+            {
+                let user_id = member["userId"].as_str().unwrap_or("").to_string();
+                let user = &member["user"];
+                let name = user["displayName"]
+                    .as_str()
+                    .or(user["username"].as_str())
+                    .unwrap_or("?")
+                    .to_string();
+                let is_creator = room.creator_id.is_some_and(|c| c.to_string() == user_id);
+                let role = if is_creator {
+                    "owner".to_string()
+                } else {
+                    member["role"].as_str().unwrap_or("member").to_string()
+                };
+                let next_role = if role == "admin" { "member" } else { "admin" };
+                let api = state.api.clone();
+                let room_id = room_id.clone();
+                let uid = user_id.clone();
+                rsx! {
+                    div {
+                        key: "{user_id}",
+                        class: "flex items-center gap-2 px-2 py-1.5 rounded hover:bg-dc-hover",
+                        span { class: "flex-1 truncate", "{name}" }
+                        span { class: "text-xs text-dc-text-faint", "{role}" }
+                        if can_manage && !is_creator {
+                            button {
+                                class: "text-xs text-dc-accent hover:underline",
+                                onclick: move |_| {
+                                    let api = api.clone();
+                                    let room_id = room_id.clone();
+                                    let uid = uid.clone();
+                                    spawn(async move {
+                                        match api.set_member_role(&room_id, &uid, next_role).await {
+                                            Ok(()) => set_role(members, &uid, next_role),
+                                            Err(e) => error.set(Some(e)),
+                                        }
+                                    });
+                                },
+                                if next_role == "admin" { "Make admin" } else { "Remove admin" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Update a member's role in a member list fetched from `/members`
+pub fn set_role(mut members: Signal<Vec<serde_json::Value>>, user_id: &str, role: &str) {
+    if let Some(m) = members
+        .write()
+        .iter_mut()
+        .find(|m| m["userId"].as_str() == Some(user_id))
+    {
+        m["role"] = serde_json::Value::from(role);
+    }
+}
+
+/// Update a member's presence in a member list fetched from `/members`, from
+/// `user_online`/`user_offline`
+pub fn set_online(mut members: Signal<Vec<serde_json::Value>>, user_id: &str, online: bool) {
+    if let Some(m) = members
+        .write()
+        .iter_mut()
+        .find(|m| m["userId"].as_str() == Some(user_id))
+    {
+        m["user"]["isOnline"] = serde_json::Value::from(online);
+        if !online {
+            m["user"]["lastSeen"] = serde_json::Value::from(chrono::Utc::now().to_rfc3339());
+        }
+    }
+}
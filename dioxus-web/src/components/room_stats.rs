@@ -0,0 +1,120 @@
+use crate::models::RoomStats;
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+/// Days of history shown
+const STATS_DAYS: u32 = 30;
+
+/// Bar heights as percentages of the largest value (empty bars stay visible)
+fn bar_heights(values: &[i64]) -> Vec<i64> {
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values.iter().map(|v| (v * 100 / max).max(2)).collect()
+}
+
+/// Room activity for room admins: members, messages per day and the busiest
+/// hours of the day, drawn as small bar charts. Shown in the room info panel.
+#[component]
+pub fn RoomStatsView(room_id: String) -> Element {
+    let state = use_context::<AppState>();
+    let stats = use_resource(move || {
+        let api = state.api.clone();
+        let room_id = room_id.clone();
+        async move { api.get_room_stats(&room_id, STATS_DAYS).await }
+    });
+
+    match &*stats.read() {
+        None => rsx! {
+            div { class: "text-dc-text-faint py-4", "Loading statistics..." }
+        },
+        Some(Err(e)) => rsx! {
+            div { class: "bg-red-900/50 text-red-200 p-2 rounded", "{e}" }
+        },
+        Some(Ok(s)) => rsx! {
+            StatsBody { stats: s.clone() }
+        },
+    }
+}
+
+#[component]
+fn StatsBody(stats: RoomStats) -> Element {
+    let daily_counts: Vec<i64> = stats.daily.iter().map(|d| d.messages).collect();
+    let daily_heights = bar_heights(&daily_counts);
+    let recent: i64 = daily_counts.iter().sum();
+    let hour_heights = bar_heights(&stats.active_hours);
+    let busiest = stats
+        .active_hours
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, count)| **count)
+        .filter(|(_, count)| **count > 0)
+        .map(|(hour, _)| format!("{:02}:00\u{2013}{:02}:00 UTC", hour, (hour + 1) % 24));
+
+    rsx! {
+        div {
+            class: "grid grid-cols-3 gap-2 mb-4",
+            StatTile { label: "Members", value: stats.member_count }
+            StatTile { label: "Messages", value: stats.total_messages }
+            StatTile { label: "Last {STATS_DAYS} days", value: recent }
+        }
+
+        h3 { class: "font-semibold text-white mb-1", "Messages per day" }
+        div {
+            class: "flex items-end gap-px h-20 mb-1 bg-dc-dark rounded p-1",
+            for (day, height) in stats.daily.iter().zip(daily_heights) {
+                div {
+                    key: "{day.date}",
+                    class: "flex-1 bg-dc-accent rounded-t-sm",
+                    style: "height: {height}%",
+                    title: "{day.date}: {day.messages}",
+                }
+            }
+        }
+        if let (Some(first), Some(last)) = (stats.daily.first(), stats.daily.last()) {
+            div {
+                class: "flex justify-between text-xs text-dc-text-faint mb-4",
+                span { "{first.date}" }
+                span { "{last.date}" }
+            }
+        }
+
+        h3 { class: "font-semibold text-white mb-1", "Active hours (UTC)" }
+        div {
+            class: "flex items-end gap-px h-16 mb-1 bg-dc-dark rounded p-1",
+            for (hour, height) in hour_heights.into_iter().enumerate() {
+                {
+                    let title = format!("{:02}:00 \u{2014} {}", hour, stats.active_hours[hour]);
+                    rsx! {
+                        div {
+                            key: "{hour}",
+                            class: "flex-1 bg-emerald-600 rounded-t-sm",
+                            style: "height: {height}%",
+                            title: "{title}",
+                        }
+                    }
+                }
+            }
+        }
+        div {
+            class: "flex justify-between text-xs text-dc-text-faint",
+            span { "00" }
+            span { "06" }
+            span { "12" }
+            span { "18" }
+            span { "23" }
+        }
+        if let Some(busiest) = busiest {
+            p { class: "text-xs text-dc-text-muted mt-2", "Busiest: {busiest}" }
+        }
+    }
+}
+
+#[component]
+fn StatTile(label: String, value: i64) -> Element {
+    rsx! {
+        div {
+            class: "bg-dc-dark rounded p-2 text-center",
+            div { class: "text-lg font-semibold text-white", "{value}" }
+            div { class: "text-xs text-dc-text-faint", "{label}" }
+        }
+    }
+}
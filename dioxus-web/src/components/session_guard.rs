@@ -0,0 +1,39 @@
+use crate::state::auth::SESSION_EXPIRED;
+use crate::state::AppState;
+use crate::utils::storage;
+use crate::Route;
+use dioxus::prelude::*;
+
+/// Layout around every page. When the API gives up on the session, it
+/// remembers where the user was (page and open room) and goes to the login
+/// page, which brings them back there afterwards.
+#[component]
+pub fn SessionGuard() -> Element {
+    let state = use_context::<AppState>();
+    let nav = navigator();
+
+    use_effect(move || {
+        if !SESSION_EXPIRED() {
+            return;
+        }
+        *SESSION_EXPIRED.write() = false;
+
+        let path = web_sys::window()
+            .and_then(|w| w.location().pathname().ok())
+            .unwrap_or_default();
+        if path != "/login" {
+            let room_id = state.current_room.peek().as_ref().map(|r| r.id.to_string());
+            storage::save_return_to(&path, room_id.as_deref());
+        }
+
+        let state = state.clone();
+        spawn(async move {
+            state.clear_auth().await;
+            nav.push(Route::Login {});
+        });
+    });
+
+    rsx! {
+        Outlet::<Route> {}
+    }
+}
@@ -0,0 +1,48 @@
+use crate::state::AppState;
+use dioxus::prelude::*;
+
+/// Stack of error toasts in the bottom corner, each with its action button
+/// (if any) and a close button
+#[component]
+pub fn ToastHost() -> Element {
+    let state = use_context::<AppState>();
+    let toasts = state.toasts.read().clone();
+
+    rsx! {
+        div {
+            class: "fixed bottom-4 right-4 z-50 flex flex-col gap-2 w-80 max-w-[calc(100vw-2rem)]",
+            role: "status",
+            aria_live: "polite",
+            for toast in toasts {
+                {
+                    let id = toast.id;
+                    let state_action = state.clone();
+                    let state_close = state.clone();
+                    rsx! {
+                        div {
+                            key: "{id}",
+                            class: "flex items-start gap-3 px-3 py-2.5 rounded bg-dc-sidebar border-l-4 border-red-500 shadow-lg text-sm text-dc-text",
+                            span { class: "flex-1 break-words", "{toast.text}" }
+                            if let Some((label, action)) = toast.action {
+                                button {
+                                    class: "flex-shrink-0 font-semibold text-dc-accent hover:text-white",
+                                    onclick: move |_| {
+                                        state_action.dismiss_toast(id);
+                                        action();
+                                    },
+                                    "{label}"
+                                }
+                            }
+                            button {
+                                class: "flex-shrink-0 text-dc-text-muted hover:text-dc-text",
+                                title: "Dismiss",
+                                onclick: move |_| state_close.dismiss_toast(id),
+                                "\u{2715}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -6,6 +6,7 @@ mod socket;
 mod state;
 mod utils;
 
+use components::session_guard::SessionGuard;
 use dioxus::prelude::*;
 // Router and Routable available from dioxus::prelude::*
 use pages::*;
@@ -13,16 +14,19 @@ use pages::*;
 #[derive(Clone, Routable, PartialEq)]
 #[rustfmt::skip]
 enum Route {
-    #[route("/")]
-    Home {},
-    #[route("/login")]
-    Login {},
-    #[route("/register")]
-    Register {},
-    #[route("/chat")]
-    Chat {},
-    #[route("/admin")]
-    Admin {},
+    #[layout(SessionGuard)]
+        #[route("/")]
+        Home {},
+        #[route("/login")]
+        Login {},
+        #[route("/register")]
+        Register {},
+        #[route("/chat")]
+        Chat {},
+        #[route("/admin")]
+        Admin {},
+        #[route("/invite/:code")]
+        Invite { code: String },
 }
 
 fn main() {
@@ -38,7 +42,9 @@ fn App() -> Element {
     use_context_provider(state::AppState::new);
 
     rsx! {
+        components::onion_prompt::OnionPrompt {}
         Router::<Route> {}
+        components::toasts::ToastHost {}
     }
 }
 
@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tor_chat_common::{BuildInfo, MessageContent, NoKeys};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,6 +20,9 @@ pub struct User {
     pub is_admin: bool,
     #[serde(rename = "isBanned", default)]
     pub is_banned: bool,
+    /// Only present in admin listings
+    #[serde(rename = "isShadowBanned", default)]
+    pub is_shadow_banned: bool,
     #[serde(rename = "createdAt", default = "chrono::Utc::now")]
     pub created_at: DateTime<Utc>,
 }
@@ -36,10 +40,50 @@ pub struct Room {
     pub room_key: Option<String>,
     #[serde(rename = "maxMembers", default)]
     pub max_members: i32,
+    #[serde(rename = "memberCount", default)]
+    pub member_count: i64,
     #[serde(rename = "createdAt", default = "chrono::Utc::now")]
     pub created_at: DateTime<Utc>,
     #[serde(rename = "unreadCount", default)]
     pub unread_count: i64,
+    /// `server` (readable by the server) or `e2ee`
+    #[serde(rename = "encryptionMode", default = "default_encryption_mode")]
+    pub encryption_mode: String,
+    #[serde(rename = "keyEpoch", default)]
+    pub key_epoch: i32,
+    /// When the room switched to E2EE
+    #[serde(rename = "e2eeSince", default)]
+    pub e2ee_since: Option<DateTime<Utc>>,
+    /// An admin's `@room`/`@here` arrived since the room was last opened
+    #[serde(skip)]
+    pub mentioned: bool,
+}
+
+fn default_encryption_mode() -> String {
+    "server".to_string()
+}
+
+impl Room {
+    pub fn is_e2ee(&self) -> bool {
+        self.encryption_mode == "e2ee"
+    }
+
+    /// "48/50" once the room is nine-tenths full, for a "nearly full" hint.
+    /// None from servers that don't report the member count.
+    pub fn capacity_hint(&self) -> Option<String> {
+        let max = i64::from(self.max_members);
+        (max > 0 && self.member_count * 10 >= max * 9)
+            .then(|| format!("{}/{}", self.member_count, max))
+    }
+}
+
+/// Entry of `GET /api/rooms/unread`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadCount {
+    pub room_id: Uuid,
+    pub unread_count: i64,
+    pub last_read_message_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -69,6 +113,166 @@ pub struct Message {
     pub pinned_at: Option<DateTime<Utc>>,
     #[serde(rename = "replyMessage")]
     pub reply_message: Option<serde_json::Value>,
+    #[serde(
+        rename = "clientNonce",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub client_nonce: Option<String>,
+    /// Spam filter result; only sent to room moderators
+    #[serde(rename = "spamScore", default, skip_serializing_if = "Option::is_none")]
+    pub spam_score: Option<f32>,
+    /// `flagged` or `hidden` while awaiting review
+    #[serde(
+        rename = "spamAction",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub spam_action: Option<String>,
+    /// Key epoch of an E2EE message; None when the server can read it
+    #[serde(rename = "keyEpoch", default)]
+    pub key_epoch: Option<i32>,
+    /// Sent before the room switched to E2EE
+    #[serde(rename = "legacyPlaintext", default)]
+    pub legacy_plaintext: bool,
+    /// Lifetime in seconds of an expiring message
+    #[serde(rename = "expiresIn", default)]
+    pub expires_in: Option<i32>,
+    /// The countdown starts when someone else first reads it
+    #[serde(rename = "expiresAfterRead", default)]
+    pub expires_after_read: bool,
+    /// When the server deletes it; None until the countdown has started
+    #[serde(rename = "expiresAt", default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Delivery state of a message we sent; local only, never on the wire
+    #[serde(skip)]
+    pub send_state: Option<SendState>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendState {
+    Pending,
+    Failed(String),
+}
+
+impl Message {
+    /// Optimistic copy rendered before the server confirms the send. The id
+    /// is local only; `client_nonce` ties it to the server's copy.
+    pub fn pending(
+        room_id: Uuid,
+        user: &User,
+        content: String,
+        reply_to: Option<Uuid>,
+        sent_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            room_id,
+            user_id: user.id,
+            content,
+            message_type: "text".to_string(),
+            reply_to,
+            forwarded_from: None,
+            reactions: serde_json::json!({}),
+            metadata: None,
+            created_at: sent_at,
+            updated_at: None,
+            user: Some(user.clone()),
+            pinned_by: None,
+            pinned_at: None,
+            reply_message: None,
+            client_nonce: Some(Uuid::new_v4().to_string()),
+            spam_score: None,
+            spam_action: None,
+            key_epoch: None,
+            legacy_plaintext: false,
+            expires_in: None,
+            expires_after_read: false,
+            expires_at: None,
+            send_state: Some(SendState::Pending),
+        }
+    }
+
+    /// Whether the message belongs to an E2EE room (sent under a key epoch,
+    /// or legacy history from before the switch)
+    pub fn in_e2ee_room(&self) -> bool {
+        self.key_epoch.is_some() || self.legacy_plaintext
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.send_state == Some(SendState::Pending)
+    }
+
+    /// Merge a server copy (REST response or `new_message` broadcast) into the
+    /// list, replacing our optimistic copy with the same nonce and ignoring
+    /// duplicates
+    pub fn reconcile(messages: &mut Vec<Message>, incoming: Message) {
+        if messages.iter().any(|m| m.id == incoming.id) {
+            return;
+        }
+        let optimistic = incoming.client_nonce.as_ref().and_then(|nonce| {
+            messages
+                .iter_mut()
+                .find(|m| m.send_state.is_some() && m.client_nonce.as_ref() == Some(nonce))
+        });
+        match optimistic {
+            Some(slot) => *slot = incoming,
+            None => messages.push(incoming),
+        }
+    }
+
+    /// Decoded view of `content`; renderers should use this rather than the raw string
+    pub fn decoded(&self) -> MessageContent {
+        MessageContent::decode(
+            &self.message_type,
+            &self.content,
+            self.metadata.as_ref(),
+            &NoKeys,
+        )
+    }
+
+    /// Decoded view of the embedded `replyMessage`, if any
+    pub fn decoded_reply(&self) -> Option<MessageContent> {
+        self.reply_message
+            .as_ref()
+            .map(|reply| MessageContent::from_json(reply, &NoKeys))
+    }
+}
+
+/// Attachment listed by `GET /api/rooms/:id/files`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomFile {
+    pub message_id: Uuid,
+    pub url: String,
+    pub message_type: String,
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    pub size: Option<u64>,
+    pub created_at: DateTime<Utc>,
+    pub user: FileSender,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileSender {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: Option<String>,
+}
+
+impl RoomFile {
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| self.url.rsplit('/').next().unwrap_or(&self.url).to_string())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoomFilesPage {
+    pub files: Vec<RoomFile>,
+    pub total: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,4 +287,211 @@ pub struct RegisterRequest {
     pub password: String,
     #[serde(rename = "displayName")]
     pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub features: ServerFeatures,
+    #[serde(default)]
+    pub maintenance: MaintenanceStatus,
+    #[serde(default)]
+    pub addresses: ServerAddresses,
+    #[serde(default)]
+    pub limits: ServerLimits,
+    /// Commit, build time and profile (older servers only report `version`)
+    #[serde(default)]
+    pub build: Option<BuildInfo>,
+    /// The server's clock when it answered, Unix ms (older servers omit it)
+    #[serde(rename = "serverTime", default)]
+    pub server_time: Option<i64>,
+}
+
+impl ServerInfo {
+    pub fn build_info(&self) -> BuildInfo {
+        self.build.clone().unwrap_or_else(|| BuildInfo {
+            version: self.version.clone(),
+            ..BuildInfo::default()
+        })
+    }
+}
+
+/// Read-only maintenance mode, from server-info and the `maintenance` socket event
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    #[serde(default)]
+    pub enabled: bool,
+    /// "admin" or "migration"
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
+
+impl MaintenanceStatus {
+    pub fn banner(&self) -> Option<String> {
+        self.enabled.then(|| {
+            self.message
+                .clone()
+                .unwrap_or_else(|| "The server is in maintenance mode".to_string())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerFeatures {
+    #[serde(default)]
+    pub email: bool,
+    #[serde(default)]
+    pub translation: bool,
+}
+
+/// Room sizes from server-info; older servers don't report them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerLimits {
+    pub default_room_members: Option<i32>,
+    pub max_room_members: Option<i32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerAddresses {
+    pub clearnet: Option<String>,
+    pub onion: Option<String>,
+}
+
+/// Place of a queued registration (servers with REGISTRATION_MAX_PER_MINUTE)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+    pub activated: bool,
+    pub position: i64,
+    pub estimated_wait_seconds: u64,
+}
+
+/// Opt-in daily activity digest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestSettings {
+    pub enabled: bool,
+    /// Hour of day (UTC) the digest is sent
+    pub hour_utc: i16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestRoom {
+    pub room_id: Uuid,
+    pub room_name: String,
+    pub message_count: i64,
+    #[serde(default)]
+    pub top_participants: Vec<String>,
+}
+
+/// Activity in rooms the user hasn't opened for a day, from `GET /api/digest`
+/// and the `digest` socket event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Digest {
+    pub id: Uuid,
+    pub rooms: Vec<DigestRoom>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Personal message retention: own messages older than `days` are deleted.
+/// `room_id: None` is the global rule; room rules override it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionRule {
+    pub room_id: Option<Uuid>,
+    pub room_name: Option<String>,
+    pub days: i32,
+}
+
+/// Room activity from `GET /api/rooms/:id/stats` (room admins only)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomStats {
+    pub member_count: i64,
+    pub total_messages: i64,
+    pub daily: Vec<DailyCount>,
+    /// Messages per hour of day, UTC
+    pub active_hours: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyCount {
+    pub date: chrono::NaiveDate,
+    pub messages: i64,
+}
+
+/// Invite link code from `GET/POST /api/rooms/:id/invites`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomInvite {
+    pub code: String,
+    pub room_id: Uuid,
+    pub created_by: Option<Uuid>,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The room an invite leads to, from `GET /api/invites/:code`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvitePreview {
+    pub room_id: Uuid,
+    pub room_name: String,
+    pub description: Option<String>,
+    pub member_count: i64,
+}
+
+/// A room's matches from `GET /api/search/messages`, newest first
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomSearchHits {
+    pub room_id: Uuid,
+    pub room_name: Option<String>,
+    pub messages: Vec<Message>,
+}
+
+/// Someone who isn't a contact asked to add the user to a private room,
+/// from `GET /api/requests` and the `room_request` socket event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomRequest {
+    pub room_id: Uuid,
+    pub room_name: String,
+    pub from: FileSender,
+    /// Missing from the socket event, which is sent as the request is made
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Who may add the user to private rooms without asking
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestSettings {
+    /// `everyone`, or `members`: only people the user shares a room with
+    pub accept_from: String,
+}
+
+/// The user's per-room notification preference
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    /// `all`, `mentions` or `none`
+    pub level: String,
+    pub muted_until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Translation {
+    pub translated_text: String,
+    pub detected_language: Option<String>,
 }
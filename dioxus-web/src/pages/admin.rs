@@ -1,5 +1,48 @@
-use crate::{state::AppState, Route};
+use crate::socket::SocketClient;
+use crate::{state::AppState, utils, Route};
 use dioxus::prelude::*;
+use serde_json::Value;
+use std::rc::Rc;
+
+/// Live events kept in the dashboard feed
+const MAX_LIVE_EVENTS: usize = 20;
+
+/// One-line summary of an `/admin` namespace event
+fn describe_event(event: &str, data: &Value) -> Option<String> {
+    let username = data["username"].as_str().unwrap_or("?");
+    Some(match event {
+        "user_registered" if data["queued"].as_bool() == Some(true) => {
+            format!("{} registered (queued)", username)
+        }
+        "user_registered" => format!("{} registered", username),
+        "message_flagged" => format!(
+            "Message from {} {} (score {:.2})",
+            username,
+            data["action"].as_str().unwrap_or("flagged"),
+            data["score"].as_f64().unwrap_or(0.0)
+        ),
+        "user_banned" | "user_unbanned" => format!(
+            "{} {}{} by {}",
+            username,
+            if data["shadow"].as_bool() == Some(true) {
+                "shadow "
+            } else {
+                ""
+            },
+            if event == "user_banned" {
+                "banned"
+            } else {
+                "unbanned"
+            },
+            data["by"].as_str().unwrap_or("?")
+        ),
+        "error_spike" => format!(
+            "Error spike: {} server errors in {}s",
+            data["errors"], data["windowSeconds"]
+        ),
+        _ => return None,
+    })
+}
 
 #[component]
 pub fn Admin() -> Element {
@@ -9,7 +52,7 @@ pub fn Admin() -> Element {
     let mut action_error = use_signal(|| None::<String>);
 
     let api_client = state.api.clone();
-    let stats = use_resource(move || {
+    let mut stats = use_resource(move || {
         let api = api_client.clone();
         async move { api.admin_get_stats().await }
     });
@@ -26,6 +69,63 @@ pub fn Admin() -> Element {
         async move { api.admin_get_rooms().await }
     });
 
+    let api_client4 = state.api.clone();
+    let mut spam_queue = use_resource(move || {
+        let api = api_client4.clone();
+        async move { api.admin_get_spam().await }
+    });
+
+    let api_client5 = state.api.clone();
+    let mut maintenance = use_resource(move || {
+        let api = api_client5.clone();
+        async move { api.admin_get_maintenance().await }
+    });
+    let mut maintenance_message = use_signal(String::new);
+
+    // Live server events from the /admin namespace refresh the dashboard
+    let live_events = use_signal(Vec::<(String, bool)>::new);
+    let live_connected = use_signal(|| false);
+    let admin_socket = use_hook(|| {
+        let origin = web_sys::window()
+            .and_then(|w| w.location().origin().ok())
+            .unwrap_or_else(|| "http://localhost:3000".to_string());
+        Rc::new(SocketClient::with_namespace(origin, "/admin"))
+    });
+    use_hook({
+        let socket = admin_socket.clone();
+        move || {
+            socket.set_event_handler(move |event, data| {
+                let (mut live_events, mut live_connected) = (live_events, live_connected);
+                let (mut stats, mut users, mut spam_queue) = (stats, users, spam_queue);
+                if event == "authenticated" {
+                    live_connected.set(true);
+                    return;
+                }
+                let Some(text) = describe_event(event, &data) else {
+                    return;
+                };
+                live_events.with_mut(|events| {
+                    events.insert(0, (text, event == "error_spike"));
+                    events.truncate(MAX_LIVE_EVENTS);
+                });
+                stats.restart();
+                match event {
+                    "message_flagged" => spam_queue.restart(),
+                    _ => users.restart(),
+                }
+            });
+            if let Some(token) = crate::utils::storage::get_token() {
+                spawn(async move { socket.connect(&token).await });
+            }
+        }
+    });
+    use_drop({
+        let socket = admin_socket.clone();
+        move || {
+            wasm_bindgen_futures::spawn_local(async move { socket.disconnect().await });
+        }
+    });
+
     rsx! {
         div {
             class: "min-h-screen bg-gray-900 p-8",
@@ -63,7 +163,7 @@ pub fn Admin() -> Element {
                 div {
                     class: "flex gap-1 mb-6 bg-gray-800 p-1 rounded-lg w-fit",
                     {
-                        let tabs = vec![("stats", "Stats"), ("users", "Users"), ("rooms", "Rooms")];
+                        let tabs = vec![("stats", "Stats"), ("users", "Users"), ("rooms", "Rooms"), ("spam", "Spam")];
                         rsx! {
                             for (key, label) in tabs {
                                 button {
@@ -151,6 +251,106 @@ pub fn Admin() -> Element {
                                             span { "Total:" }
                                             span { "{msg_stats[\"total\"]}" }
                                         }
+                                        div {
+                                            class: "flex justify-between text-red-400",
+                                            span { "Spam pending:" }
+                                            span { "{msg_stats[\"spamPending\"]}" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
+                        // Upload storage breakdown
+                        if let Some(storage) = stats_data.get("storage") {
+                            {
+                                let bytes = |v: &serde_json::Value| utils::format_bytes(v.as_u64().unwrap_or(0));
+                                let total = bytes(&storage["totalBytes"]);
+                                let total_files = storage["totalFiles"].as_u64().unwrap_or(0);
+                                let orphaned_files = storage["orphanedFiles"].as_u64().unwrap_or(0);
+                                let orphaned = bytes(&storage["orphanedBytes"]);
+                                let by_type: Vec<(String, u64, String)> = storage["byType"]
+                                    .as_object()
+                                    .map(|types| {
+                                        types
+                                            .iter()
+                                            .map(|(kind, usage)| {
+                                                (kind.clone(), usage["files"].as_u64().unwrap_or(0), bytes(&usage["bytes"]))
+                                            })
+                                            .collect()
+                                    })
+                                    .unwrap_or_default();
+                                let top_rooms: Vec<(String, String)> = storage["topRooms"]
+                                    .as_array()
+                                    .map(|rooms| {
+                                        rooms
+                                            .iter()
+                                            .map(|r| (r["name"].as_str().unwrap_or("?").to_string(), bytes(&r["bytes"])))
+                                            .collect()
+                                    })
+                                    .unwrap_or_default();
+                                rsx! {
+                                    div {
+                                        class: "bg-gray-800 p-6 rounded-lg mt-6",
+                                        div {
+                                            class: "flex justify-between items-center mb-4",
+                                            h3 {
+                                                class: "text-lg font-semibold text-white",
+                                                "Storage"
+                                            }
+                                            span { class: "text-gray-300", "{total} in {total_files} files" }
+                                        }
+                                        div {
+                                            class: "grid grid-cols-1 md:grid-cols-2 gap-6",
+                                            div {
+                                                class: "space-y-2",
+                                                h4 { class: "text-sm text-gray-400", "By type" }
+                                                for (kind, files, size) in by_type {
+                                                    div {
+                                                        key: "{kind}",
+                                                        class: "flex justify-between text-gray-300",
+                                                        span { "{kind} ({files})" }
+                                                        span { "{size}" }
+                                                    }
+                                                }
+                                            }
+                                            div {
+                                                class: "space-y-2",
+                                                h4 { class: "text-sm text-gray-400", "Top rooms" }
+                                                for (name, size) in top_rooms {
+                                                    div {
+                                                        key: "{name}",
+                                                        class: "flex justify-between text-gray-300",
+                                                        span { class: "truncate", "{name}" }
+                                                        span { "{size}" }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        div {
+                                            class: "flex justify-between items-center mt-4 pt-4 border-t border-gray-700",
+                                            span {
+                                                class: if orphaned_files > 0 { "text-yellow-400" } else { "text-gray-400" },
+                                                "Orphaned files: {orphaned_files} ({orphaned})"
+                                            }
+                                            button {
+                                                class: "bg-red-700 hover:bg-red-600 text-white px-4 py-2 rounded disabled:opacity-50",
+                                                disabled: orphaned_files == 0,
+                                                onclick: {
+                                                    let api = state.api.clone();
+                                                    move |_| {
+                                                        let api = api.clone();
+                                                        spawn(async move {
+                                                            match api.admin_clean_orphans().await {
+                                                                Ok(_) => stats.restart(),
+                                                                Err(e) => action_error.set(Some(e)),
+                                                            }
+                                                        });
+                                                    }
+                                                },
+                                                "Clean orphans"
+                                            }
+                                        }
                                     }
                                 }
                             }
@@ -161,6 +361,104 @@ pub fn Admin() -> Element {
                             "Loading stats..."
                         }
                     }
+
+                    // Live event feed (/admin namespace)
+                    div {
+                        class: "bg-gray-800 p-6 rounded-lg mt-6",
+                        div {
+                            class: "flex justify-between items-center mb-4",
+                            h3 {
+                                class: "text-lg font-semibold text-white",
+                                "Live events"
+                            }
+                            if live_connected() {
+                                span { class: "text-xs text-green-400", "● live" }
+                            } else {
+                                span { class: "text-xs text-gray-500", "○ connecting..." }
+                            }
+                        }
+                        if live_events.read().is_empty() {
+                            p {
+                                class: "text-sm text-gray-400",
+                                "Registrations, flagged messages, bans and error spikes appear here as they happen."
+                            }
+                        } else {
+                            ul {
+                                class: "space-y-1 text-sm",
+                                for (i, (text, alert)) in live_events.read().iter().enumerate() {
+                                    li {
+                                        key: "{i}",
+                                        class: if *alert { "text-red-400" } else { "text-gray-300" },
+                                        "{text}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Read-only maintenance mode toggle
+                    if let Some(Ok(status)) = maintenance.read().as_ref() {
+                        {
+                            let enabled = status.enabled;
+                            let migrating = status.reason.as_deref() == Some("migration");
+                            rsx! {
+                                div {
+                                    class: "bg-gray-800 p-6 rounded-lg mt-6",
+                                    h3 {
+                                        class: "text-lg font-semibold text-white mb-2",
+                                        "Maintenance mode"
+                                    }
+                                    p {
+                                        class: "text-sm text-gray-400 mb-4",
+                                        if migrating {
+                                            "Database migrations are running; the server is read-only until they finish."
+                                        } else if enabled {
+                                            "The server is read-only: reads and logins work, everything else is rejected."
+                                        } else {
+                                            "Make the server read-only for database maintenance. Connected clients see a banner."
+                                        }
+                                    }
+                                    div {
+                                        class: "flex gap-2",
+                                        if !enabled {
+                                            input {
+                                                class: "flex-1 bg-gray-700 text-white px-3 py-2 rounded",
+                                                placeholder: "Banner message (optional)",
+                                                value: "{maintenance_message}",
+                                                oninput: move |e| maintenance_message.set(e.value()),
+                                            }
+                                        }
+                                        button {
+                                            class: if enabled {
+                                                "bg-green-700 hover:bg-green-600 text-white px-4 py-2 rounded disabled:opacity-50"
+                                            } else {
+                                                "bg-yellow-700 hover:bg-yellow-600 text-white px-4 py-2 rounded"
+                                            },
+                                            disabled: migrating,
+                                            onclick: {
+                                                let api = state.api.clone();
+                                                move |_| {
+                                                    let api = api.clone();
+                                                    let message = maintenance_message();
+                                                    spawn(async move {
+                                                        let message = Some(message.trim()).filter(|m| !m.is_empty());
+                                                        match api.admin_set_maintenance(!enabled, message).await {
+                                                            Ok(()) => {
+                                                                maintenance_message.set(String::new());
+                                                                maintenance.restart();
+                                                            }
+                                                            Err(e) => action_error.set(Some(e)),
+                                                        }
+                                                    });
+                                                }
+                                            },
+                                            if enabled { "End maintenance" } else { "Enter maintenance" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                 }
 
                 // Users tab
@@ -180,6 +478,7 @@ pub fn Admin() -> Element {
                                         let username = user.username.clone();
                                         let user_is_admin = user.is_admin;
                                         let user_is_banned = user.is_banned;
+                                        let user_is_shadow_banned = user.is_shadow_banned;
                                         let user_is_online = user.is_online;
 
                                         let api_promote = state.api.clone();
@@ -187,11 +486,13 @@ pub fn Admin() -> Element {
                                         let api_ban = state.api.clone();
                                         let api_unban = state.api.clone();
                                         let api_delete = state.api.clone();
+                                        let api_shadow = state.api.clone();
                                         let uid_promote = user_id.clone();
                                         let uid_demote = user_id.clone();
                                         let uid_ban = user_id.clone();
                                         let uid_unban = user_id.clone();
                                         let uid_delete = user_id.clone();
+                                        let uid_shadow = user_id.clone();
 
                                         rsx! {
                                             div {
@@ -218,6 +519,13 @@ pub fn Admin() -> Element {
                                                             "Banned"
                                                         }
                                                     }
+                                                    if user_is_shadow_banned {
+                                                        span {
+                                                            class: "bg-purple-700 text-white px-2 py-0.5 rounded text-xs",
+                                                            title: "Messages are only visible to this user and moderators",
+                                                            "Shadow banned"
+                                                        }
+                                                    }
                                                 }
                                                 div {
                                                     class: "flex gap-1",
@@ -285,6 +593,23 @@ pub fn Admin() -> Element {
                                                             "Ban"
                                                         }
                                                     }
+                                                    // Shadow ban toggle (not offered for admins)
+                                                    if !user_is_admin || user_is_shadow_banned {
+                                                        button {
+                                                            class: "bg-purple-600 hover:bg-purple-700 text-white px-2 py-1 rounded text-xs",
+                                                            onclick: move |_| {
+                                                                let api = api_shadow.clone();
+                                                                let uid = uid_shadow.clone();
+                                                                spawn(async move {
+                                                                    if let Err(e) = api.admin_set_shadow_ban(&uid, !user_is_shadow_banned).await {
+                                                                        action_error.set(Some(e));
+                                                                    }
+                                                                    users.restart();
+                                                                });
+                                                            },
+                                                            if user_is_shadow_banned { "Lift shadow ban" } else { "Shadow ban" }
+                                                        }
+                                                    }
                                                     // Delete
                                                     button {
                                                         class: "bg-red-600 hover:bg-red-700 text-white px-2 py-1 rounded text-xs",
@@ -414,6 +739,101 @@ pub fn Admin() -> Element {
                         }
                     }
                 }
+
+                // Spam review tab
+                if active_tab() == "spam" {
+                    div {
+                        class: "bg-gray-800 rounded-lg p-6",
+                        h2 {
+                            class: "text-2xl font-bold text-white mb-4",
+                            "Spam Review"
+                        }
+                        match spam_queue.read().as_ref() {
+                            Some(Ok(queue)) if queue.is_empty() => rsx! {
+                                div {
+                                    class: "text-center text-gray-400 py-8",
+                                    "Nothing to review"
+                                }
+                            },
+                            Some(Ok(queue)) => rsx! {
+                                div {
+                                    class: "space-y-2",
+                                    for item in queue {
+                                        {
+                                            let msg_id = item["id"].as_str().unwrap_or("").to_string();
+                                            let room_id = item["roomId"].as_str().unwrap_or("").to_string();
+                                            let room_name = item["roomName"].as_str().unwrap_or("?").to_string();
+                                            let username = item["user"]["username"].as_str().unwrap_or("?").to_string();
+                                            let preview = tor_chat_common::MessageContent::from_json(item, &tor_chat_common::NoKeys).preview(200);
+                                            let action = item["spamAction"].as_str().unwrap_or("").to_string();
+                                            let percent = (item["spamScore"].as_f64().unwrap_or(0.0) * 100.0).round();
+                                            let reasons = item["spamReasons"]
+                                                .as_array()
+                                                .map(|r| r.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>().join(", "))
+                                                .unwrap_or_default();
+
+                                            let api_approve = state.api.clone();
+                                            let mid_approve = msg_id.clone();
+
+                                            rsx! {
+                                                div {
+                                                    key: "{msg_id}",
+                                                    class: "flex items-center justify-between gap-4 p-4 bg-gray-700 rounded",
+                                                    div {
+                                                        class: "min-w-0",
+                                                        div {
+                                                            class: "text-white break-words",
+                                                            "{preview}"
+                                                        }
+                                                        div {
+                                                            class: "text-sm text-gray-400",
+                                                            "{username} in {room_name} | score {percent}% ({reasons}) | {action}"
+                                                        }
+                                                    }
+                                                    div {
+                                                        class: "flex gap-1 flex-shrink-0",
+                                                        button {
+                                                            class: "bg-blue-600 hover:bg-blue-700 text-white px-2 py-1 rounded text-xs",
+                                                            onclick: move |_| {
+                                                                let mut avr = state.admin_view_room;
+                                                                avr.set(Some(room_id.clone()));
+                                                                nav.push(Route::Chat {});
+                                                            },
+                                                            "View"
+                                                        }
+                                                        button {
+                                                            class: "bg-green-600 hover:bg-green-700 text-white px-2 py-1 rounded text-xs",
+                                                            onclick: move |_| {
+                                                                let api = api_approve.clone();
+                                                                let mid = mid_approve.clone();
+                                                                spawn(async move {
+                                                                    if let Err(e) = api.admin_approve_spam(&mid).await {
+                                                                        action_error.set(Some(e));
+                                                                    }
+                                                                    spam_queue.restart();
+                                                                });
+                                                            },
+                                                            "Approve"
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            },
+                            Some(Err(e)) => rsx! {
+                                div { class: "text-red-400", "{e}" }
+                            },
+                            None => rsx! {
+                                div {
+                                    class: "text-center text-gray-400 py-8",
+                                    "Loading spam queue..."
+                                }
+                            },
+                        }
+                    }
+                }
             }
         }
     }
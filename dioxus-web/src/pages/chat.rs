@@ -1,10 +1,35 @@
 use crate::{
-    components::message_bubble::{DateSeparator, MessageBubble},
+    components::{
+        build_info::BuildInfoDialog,
+        create_room::CreateRoomWizard,
+        digest_card::DigestCard,
+        files_panel::FilesPanel,
+        forward_picker::ForwardPicker,
+        identity_keys::IdentityKeysDialog,
+        message_bubble::{
+            DateSeparator, E2eeDivider, KeyChangeNotice, MessageBubble, UnreadDivider,
+        },
+        message_search::MessageSearch,
+        quick_switcher::QuickSwitcher,
+        requests_card::RequestsCard,
+        retention_settings::RetentionSettings,
+        room_info::{set_online, set_role, RoomInfoPanel},
+    },
+    models::{Digest, MaintenanceStatus, Message, RoomRequest, SendState, UnreadCount},
     state::AppState,
     utils::{self, storage},
     Route,
 };
 use dioxus::prelude::*;
+use tor_chat_common::ClockOffset;
+
+/// Message expiry timers offered in the composer (seconds, label)
+const EXPIRY_CHOICES: &[(i32, &str)] = &[
+    (30, "30 seconds"),
+    (300, "5 minutes"),
+    (3600, "1 hour"),
+    (86400, "1 day"),
+];
 
 #[component]
 pub fn Chat() -> Element {
@@ -13,17 +38,17 @@ pub fn Chat() -> Element {
     let mut selected_room_idx = use_signal(|| None::<usize>);
     let mut message_input = use_signal(String::new);
     let mut loading = use_signal(|| true);
-    let mut error_msg = use_signal(|| None::<String>);
 
     // Create room modal state
     let mut show_create_modal = use_signal(|| false);
-    let mut new_room_name = use_signal(String::new);
-    let mut new_room_desc = use_signal(String::new);
-    let mut new_room_public = use_signal(|| false);
-    let mut create_error = use_signal(|| None::<String>);
 
     // Members panel state
     let mut show_members = use_signal(|| false);
+    let mut show_files = use_signal(|| false);
+    let mut show_retention = use_signal(|| false);
+    let mut show_identity = use_signal(|| false);
+    let mut show_build_info = use_signal(|| false);
+    let mut show_room_info = use_signal(|| false);
     // Add member modal state
     let mut show_add_member_modal = use_signal(|| false);
     let mut all_users: Signal<Vec<serde_json::Value>> = use_signal(Vec::new);
@@ -34,8 +59,25 @@ pub fn Chat() -> Element {
     let mut upload_status = use_signal(|| None::<String>);
     let mut is_uploading = use_signal(|| false);
     let mut members: Signal<Vec<serde_json::Value>> = use_signal(Vec::new);
+    let mut member_filter = use_signal(String::new);
     // Reply state
     let mut reply_to_msg: Signal<Option<crate::models::Message>> = use_signal(|| None);
+    let mut forward_msg = use_signal(|| None::<Message>);
+    // Expiry timer applied to messages sent from the composer
+    let mut message_expiry = use_signal(|| None::<i32>);
+    // Where the "new messages" divider goes in the open room
+    let mut first_unread = use_signal(|| None::<uuid::Uuid>);
+    // Rooms opened this session, most recent first, for the quick switcher
+    let mut recent_rooms = use_signal(Vec::<uuid::Uuid>::new);
+    let mut show_switcher = use_signal(|| false);
+    // Narrow screens: the sidebar is a drawer over the chat. It starts open
+    // so there's a room list to pick from; wider screens always show it.
+    let mut sidebar_open = use_signal(|| true);
+    // Message to show instead of the latest ones when the next room opens
+    let mut jump_target = use_signal(|| None::<uuid::Uuid>);
+    let mut jump_error = use_signal(|| None::<String>);
+    // The message slice is moving and the view is being kept in place
+    let sliding = use_signal(|| false);
 
     // Auth guard
     let has_token = storage::get_token().is_some();
@@ -45,6 +87,142 @@ pub fn Chat() -> Element {
     let state_for_logout = state.clone();
     let state_for_rooms = state.clone();
 
+    // Opening a room from the sidebar or the quick switcher
+    let open_room = use_callback(move |room_id: uuid::Uuid| {
+        let state = state_for_rooms.clone();
+        let Some((idx, room)) = state
+            .rooms
+            .peek()
+            .iter()
+            .enumerate()
+            .find(|(_, r)| r.id == room_id)
+            .map(|(idx, r)| (idx, r.clone()))
+        else {
+            return;
+        };
+        selected_room_idx.set(Some(idx));
+        show_members.set(false);
+        member_filter.set(String::new());
+        sidebar_open.set(false);
+        reply_to_msg.set(None);
+        recent_rooms.with_mut(|recent| {
+            recent.retain(|id| *id != room.id);
+            recent.insert(0, room.id);
+        });
+        // Set current_room signal for unread tracking
+        {
+            let mut cr = state.current_room;
+            cr.set(Some(room.clone()));
+        }
+        // Clear unread count for this room
+        {
+            let mut rsig = state.rooms;
+            let mut rooms = rsig.write();
+            if let Some(r) = rooms.get_mut(idx) {
+                r.unread_count = 0;
+                r.mentioned = false;
+            }
+        }
+        first_unread.set(None);
+        let target = jump_target.take();
+        spawn(async move {
+            let rid = room.id.to_string();
+            state.socket.join_room(&rid).await;
+            if let Some(target) = target {
+                let tid = target.to_string();
+                match state.load_message_context(&rid, &tid).await {
+                    Ok(()) => {
+                        state.reveal_message(target);
+                        gloo_timers::future::TimeoutFuture::new(0).await;
+                        utils::scroll_to_message(&tid);
+                        utils::highlight_message(&tid);
+                        return;
+                    }
+                    Err(e) => jump_error.set(Some(e)),
+                }
+            }
+            // Read position before this visit marks anything
+            let counts = state.refresh_unread().await.unwrap_or_default();
+            let _ = state.load_messages(&rid).await;
+            let divider = unread_start(
+                &state.messages.peek(),
+                counts.iter().find(|c| c.room_id == room.id),
+            );
+            first_unread.set(divider);
+            if let Some(id) = divider {
+                state.reveal_message(id);
+            }
+            // Wait for the list to render before scrolling
+            gloo_timers::future::TimeoutFuture::new(0).await;
+            match divider {
+                Some(id) => utils::scroll_to_message(&id.to_string()),
+                None => utils::scroll_to_bottom("messages-container"),
+            }
+            state.mark_read_if_visible();
+        });
+    });
+
+    // Following a message link, a reply or a pin: scroll to the message if
+    // it's loaded, otherwise load the messages around it
+    let state_for_jump = state.clone();
+    use_effect(move || {
+        let Some((room_id, message_id)) = *state_for_jump.jump_to.read() else {
+            return;
+        };
+        let mut jump_to = state_for_jump.jump_to;
+        jump_to.set(None);
+        jump_error.set(None);
+
+        let in_room = state_for_jump
+            .current_room
+            .peek()
+            .as_ref()
+            .is_some_and(|r| r.id == room_id);
+        if !in_room {
+            if state_for_jump.rooms.peek().iter().any(|r| r.id == room_id) {
+                jump_target.set(Some(message_id));
+                open_room.call(room_id);
+            } else {
+                jump_error.set(Some(
+                    "That message is in a room you haven't joined".to_string(),
+                ));
+            }
+            return;
+        }
+
+        let id = message_id.to_string();
+        if state_for_jump.reveal_message(message_id) {
+            // Wait for the slice to render in case it had to move
+            spawn(async move {
+                gloo_timers::future::TimeoutFuture::new(0).await;
+                utils::scroll_to_message(&id);
+                utils::highlight_message(&id);
+            });
+            return;
+        }
+        let state = state_for_jump.clone();
+        spawn(async move {
+            match state.load_message_context(&room_id.to_string(), &id).await {
+                Ok(()) => {
+                    gloo_timers::future::TimeoutFuture::new(0).await;
+                    utils::scroll_to_message(&id);
+                    utils::highlight_message(&id);
+                }
+                Err(e) => jump_error.set(Some(e)),
+            }
+        });
+    });
+
+    // Ctrl+K (Cmd+K on macOS) opens the quick switcher from anywhere
+    use_hook(|| {
+        std::rc::Rc::new(utils::KeyListener::new(move |e: web_sys::KeyboardEvent| {
+            if (e.ctrl_key() || e.meta_key()) && e.key().eq_ignore_ascii_case("k") {
+                e.prevent_default();
+                show_switcher.toggle();
+            }
+        }))
+    });
+
     use_effect(move || {
         if !has_token {
             nav.push(Route::Login {});
@@ -56,14 +234,8 @@ pub fn Chat() -> Element {
             // Load current user for admin checks
             match state.api.get_me().await {
                 Ok(user) => state.set_current_user(user),
-                Err(e) => {
-                    tracing::error!("Failed to get current user: {}", e);
-                    if e.contains("401") || e.contains("Unauthorized") {
-                        storage::remove_token();
-                        nav.push(Route::Login {});
-                        return;
-                    }
-                }
+                // An expired session is handled by the API client
+                Err(e) => state.toast_error(format!("Couldn't load your account: {}", e)),
             }
 
             // Set up real-time event handler and connect socket
@@ -71,45 +243,51 @@ pub fn Chat() -> Element {
                 let messages_sig = state.messages;
                 let rooms_sig = state.rooms;
                 let current_room_sig = state.current_room;
+                let maintenance_sig = state.maintenance;
+                let digest_sig = state.digest;
+                let requests_sig = state.room_requests;
+                let state_for_read = state.clone();
+                let api_for_clock = state.api.clone();
+                let state_for_lost = state.clone();
+                state.socket.set_lost_handler(move |_reason: &str| {
+                    let state = state_for_lost.clone();
+                    state_for_lost.toast_action(
+                        "Lost connection to the server",
+                        "Reconnect",
+                        move || reconnect(state.clone()),
+                    );
+                });
                 state
                     .socket
                     .set_event_handler(move |event: &str, payload: serde_json::Value| {
                         match event {
                             "new_message" => {
-                                match serde_json::from_value::<crate::models::Message>(
-                                    payload.clone(),
-                                ) {
+                                match serde_json::from_value::<crate::models::Message>(payload) {
                                     Ok(msg) => {
-                                        let mut sig = messages_sig;
-                                        let mut msgs = sig.write();
-                                        // Avoid duplicates
-                                        if !msgs.iter().any(|m| m.id == msg.id) {
-                                            msgs.push(msg.clone());
-                                        }
-                                        drop(msgs);
-
-                                        // Auto-scroll after new message
-                                        utils::scroll_to_bottom("messages-container");
+                                        let is_current = current_room_sig
+                                            .peek()
+                                            .as_ref()
+                                            .is_some_and(|r| r.id == msg.room_id);
+                                        state_for_read.check_keys(msg.user.as_ref());
+                                        if is_current {
+                                            let mut sig = messages_sig;
+                                            // Replaces our pending copy, skips duplicates
+                                            Message::reconcile(&mut sig.write(), msg);
 
-                                        // Increment unread count only for rooms NOT currently viewed
-                                        if let Some(room_id_str) =
-                                            payload.get("roomId").and_then(|v| v.as_str())
-                                        {
-                                            if let Ok(room_id) = uuid::Uuid::parse_str(room_id_str)
+                                            // Auto-scroll after new message, unless the
+                                            // user is reading older ones
+                                            if state_for_read.message_window.peek().is_latest() {
+                                                utils::scroll_to_bottom("messages-container");
+                                            }
+                                            state_for_read.mark_read_if_visible();
+                                        } else {
+                                            // Other joined rooms only bump their badge
+                                            let mut rsig = rooms_sig;
+                                            let mut rooms = rsig.write();
+                                            if let Some(room) =
+                                                rooms.iter_mut().find(|r| r.id == msg.room_id)
                                             {
-                                                let current = current_room_sig.read();
-                                                let is_current = current
-                                                    .as_ref()
-                                                    .is_some_and(|r| r.id == room_id);
-                                                if !is_current {
-                                                    let mut rsig = rooms_sig;
-                                                    let mut rooms = rsig.write();
-                                                    if let Some(room) =
-                                                        rooms.iter_mut().find(|r| r.id == room_id)
-                                                    {
-                                                        room.unread_count += 1;
-                                                    }
-                                                }
+                                                room.unread_count += 1;
                                             }
                                         }
                                     }
@@ -132,6 +310,67 @@ pub fn Chat() -> Element {
                                     }
                                 }
                             }
+                            "room_updated" => {
+                                match serde_json::from_value::<crate::models::Room>(payload) {
+                                    Ok(room) => state_for_read.apply_room_update(&room),
+                                    Err(e) => {
+                                        tracing::error!("Failed to parse room_updated: {}", e)
+                                    }
+                                }
+                            }
+                            "room_encryption_changed" => {
+                                let room_id = payload
+                                    .get("roomId")
+                                    .and_then(|v| v.as_str())
+                                    .and_then(|s| uuid::Uuid::parse_str(s).ok());
+                                let key_epoch = payload
+                                    .get("keyEpoch")
+                                    .and_then(|v| v.as_i64())
+                                    .unwrap_or(1)
+                                    as i32;
+                                let since = payload
+                                    .get("e2eeSince")
+                                    .cloned()
+                                    .and_then(|v| serde_json::from_value(v).ok());
+                                if let Some(room_id) = room_id {
+                                    state_for_read.apply_e2ee_switch(room_id, key_epoch, since);
+                                }
+                            }
+                            "public_key_changed" => {
+                                let user_id = payload
+                                    .get("userId")
+                                    .and_then(|v| v.as_str())
+                                    .and_then(|s| uuid::Uuid::parse_str(s).ok());
+                                if let (Some(user_id), Some(username), Some(key)) = (
+                                    user_id,
+                                    payload.get("username").and_then(|v| v.as_str()),
+                                    payload.get("publicKey").and_then(|v| v.as_str()),
+                                ) {
+                                    state_for_read.check_key(user_id, username, key);
+                                }
+                            }
+                            "user_online" | "user_offline" => {
+                                if let Some(user_id) =
+                                    payload.get("userId").and_then(|v| v.as_str())
+                                {
+                                    set_online(members, user_id, event == "user_online");
+                                }
+                            }
+                            "member_role_changed" => {
+                                let is_current = payload
+                                    .get("roomId")
+                                    .and_then(|v| v.as_str())
+                                    .and_then(|s| uuid::Uuid::parse_str(s).ok())
+                                    .zip(current_room_sig.peek().as_ref().map(|r| r.id))
+                                    .is_some_and(|(changed, open)| changed == open);
+                                if let (true, Some(user_id), Some(role)) = (
+                                    is_current,
+                                    payload.get("userId").and_then(|v| v.as_str()),
+                                    payload.get("role").and_then(|v| v.as_str()),
+                                ) {
+                                    set_role(members, user_id, role);
+                                }
+                            }
                             "room_deleted" => {
                                 if let Some(room_id_str) =
                                     payload.get("roomId").and_then(|v| v.as_str())
@@ -167,6 +406,44 @@ pub fn Chat() -> Element {
                                     }
                                 }
                             }
+                            "message_deleted" => {
+                                if let Some(msg_id) = payload
+                                    .get("messageId")
+                                    .and_then(|v| v.as_str())
+                                    .and_then(|s| uuid::Uuid::parse_str(s).ok())
+                                {
+                                    let mut sig = messages_sig;
+                                    sig.write().retain(|m| m.id != msg_id);
+                                }
+                            }
+                            "message_expiry_started" => {
+                                let msg_id = payload
+                                    .get("messageId")
+                                    .and_then(|v| v.as_str())
+                                    .and_then(|s| uuid::Uuid::parse_str(s).ok());
+                                let expires_at = payload
+                                    .get("expiresAt")
+                                    .and_then(|v| v.as_str())
+                                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                                    .map(|d| d.with_timezone(&chrono::Utc));
+                                if let (Some(msg_id), Some(expires_at)) = (msg_id, expires_at) {
+                                    let mut sig = messages_sig;
+                                    if let Some(m) = sig.write().iter_mut().find(|m| m.id == msg_id)
+                                    {
+                                        m.expires_at = Some(expires_at);
+                                    }
+                                }
+                            }
+                            "message_expired" => {
+                                if let Some(msg_id) = payload
+                                    .get("messageId")
+                                    .and_then(|v| v.as_str())
+                                    .and_then(|s| uuid::Uuid::parse_str(s).ok())
+                                {
+                                    let mut sig = messages_sig;
+                                    sig.write().retain(|m| m.id != msg_id);
+                                }
+                            }
                             "message_unpinned" => {
                                 if let Some(msg_id_str) =
                                     payload.get("messageId").and_then(|v| v.as_str())
@@ -181,6 +458,69 @@ pub fn Chat() -> Element {
                                     }
                                 }
                             }
+                            "maintenance" => {
+                                match serde_json::from_value::<MaintenanceStatus>(payload) {
+                                    Ok(status) => {
+                                        let mut sig = maintenance_sig;
+                                        sig.set(status.banner());
+                                    }
+                                    Err(e) => {
+                                        tracing::error!("Failed to parse maintenance: {}", e)
+                                    }
+                                }
+                            }
+                            "digest" => match serde_json::from_value::<Digest>(payload) {
+                                Ok(digest) => {
+                                    let mut sig = digest_sig;
+                                    sig.set(Some(digest));
+                                }
+                                Err(e) => tracing::error!("Failed to parse digest: {}", e),
+                            },
+                            "room_request" => {
+                                match serde_json::from_value::<RoomRequest>(payload) {
+                                    Ok(request) => {
+                                        let mut sig = requests_sig;
+                                        let mut requests = sig.write();
+                                        requests.retain(|r| r.room_id != request.room_id);
+                                        requests.insert(0, request);
+                                    }
+                                    Err(e) => {
+                                        tracing::error!("Failed to parse room_request: {}", e)
+                                    }
+                                }
+                            }
+                            "room_mention" => {
+                                let room_id = payload
+                                    .get("roomId")
+                                    .and_then(|v| v.as_str())
+                                    .and_then(|s| uuid::Uuid::parse_str(s).ok());
+                                let is_current = current_room_sig
+                                    .peek()
+                                    .as_ref()
+                                    .is_some_and(|r| Some(r.id) == room_id);
+                                if !is_current {
+                                    let mut rsig = rooms_sig;
+                                    if let Some(room) =
+                                        rsig.write().iter_mut().find(|r| Some(r.id) == room_id)
+                                    {
+                                        room.mentioned = true;
+                                    }
+                                }
+                            }
+                            "authenticated" => {
+                                if let Some(server_time) =
+                                    payload.get("serverTime").and_then(|v| v.as_i64())
+                                {
+                                    // Measured on arrival, so this includes the
+                                    // one-way delay; server-info refines it
+                                    let received = chrono::Utc::now().timestamp_millis();
+                                    api_for_clock.set_clock(ClockOffset::measure(
+                                        received,
+                                        server_time,
+                                        received,
+                                    ));
+                                }
+                            }
                             _ => {
                                 tracing::debug!("Unhandled socket event: {}", event);
                             }
@@ -192,8 +532,38 @@ pub fn Chat() -> Element {
                 }
             }
 
+            if let Ok(info) = state.api.get_server_info().await {
+                let mut translation = state.translation_enabled;
+                translation.set(info.features.translation);
+                let mut maintenance = state.maintenance;
+                maintenance.set(info.maintenance.banner());
+                let mut server_build = state.server_build;
+                server_build.set(Some(info.build_info()));
+            }
+
+            // Show the latest digest once, for a day
+            if let Ok(Some(digest)) = state.api.get_digest().await {
+                let age = state.api.server_now() - digest.created_at;
+                let fresh = age < chrono::Duration::hours(24);
+                if fresh && !storage::digest_dismissed(&digest.id.to_string()) {
+                    let mut sig = state.digest;
+                    sig.set(Some(digest));
+                }
+            }
+
+            match state.api.list_requests().await {
+                Ok(requests) => {
+                    let mut sig = state.room_requests;
+                    sig.set(requests);
+                }
+                Err(e) => tracing::warn!("Failed to load room requests: {}", e),
+            }
+
             match state.load_rooms().await {
                 Ok(()) => {
+                    if let Err(e) = state.refresh_unread().await {
+                        tracing::warn!("Failed to load unread counts: {}", e);
+                    }
                     // If admin clicked "View" on a room, auto-select it
                     if let Some(target_room_id) = state.admin_view_room.peek().clone() {
                         let rooms = state.rooms.read();
@@ -213,60 +583,62 @@ pub fn Chat() -> Element {
                         let mut avr = state.admin_view_room;
                         avr.set(None);
                     }
+                    // Opened from a message link: /chat?room=<id>&message=<id>
+                    if let Some((room_id, message_id)) = utils::message_link_from_url() {
+                        state.open_message(room_id, message_id);
+                    }
                     loading.set(false);
                 }
                 Err(e) => {
-                    tracing::error!("Failed to load rooms: {}", e);
-                    if e.contains("401") || e.contains("Unauthorized") || e.contains("unauthorized")
-                    {
-                        storage::remove_token();
-                        nav.push(Route::Login {});
-                    } else {
-                        error_msg.set(Some(format!("Failed to load rooms: {}", e)));
-                        loading.set(false);
-                    }
+                    loading.set(false);
+                    let retry_state = state.clone();
+                    state.toast_action(format!("Couldn't load rooms: {}", e), "Retry", move || {
+                        retry_rooms(retry_state.clone())
+                    });
                 }
             }
         });
     });
 
+    // Messages appear immediately as pending and are reconciled with the
+    // server's copy (REST response or `new_message` broadcast) by clientNonce
     let on_send = move |e: Event<FormData>| {
         e.prevent_default();
-        let rooms = state_for_send.rooms.read();
-        let selected = selected_room_idx();
-        if let Some(idx) = selected {
-            if let Some(room) = rooms.get(idx) {
-                let content = message_input();
-                if !content.is_empty() {
-                    let room_id = room.id.to_string();
-                    let state = state_for_send.clone();
-                    let reply_id = reply_to_msg().map(|m| m.id.to_string());
-                    spawn(async move {
-                        match state
-                            .api
-                            .send_message(&room_id, &content, reply_id.as_deref())
-                            .await
-                        {
-                            Ok(_) => {
-                                message_input.set(String::new());
-                                reply_to_msg.set(None);
-                                let _ = state.load_messages(&room_id).await;
-                                utils::scroll_to_bottom("messages-container");
-                            }
-                            Err(e) => {
-                                tracing::error!("Failed to send message: {}", e);
-                                error_msg.set(Some(format!("Send failed: {}", e)));
-                            }
-                        }
-                    });
-                }
-            }
+        let content = message_input();
+        if content.is_empty() || !state_for_send.room_key_changes().is_empty() {
+            return;
         }
+
+        let room_id = {
+            let rooms = state_for_send.rooms.read();
+            match selected_room_idx().and_then(|idx| rooms.get(idx)) {
+                Some(room) => room.id,
+                None => return,
+            }
+        };
+        let Some(user) = state_for_send.current_user.read().clone() else {
+            return;
+        };
+
+        let sent_at = state_for_send.api.server_now();
+        let reply_to = reply_to_msg().map(|m| m.id);
+        let mut pending = Message::pending(room_id, &user, content, reply_to, sent_at);
+        pending.expires_in = message_expiry();
+        pending.expires_after_read = pending.expires_in.is_some();
+        let mut messages = state_for_send.messages;
+        messages.write().push(pending.clone());
+        message_input.set(String::new());
+        reply_to_msg.set(None);
+        state_for_send.follow_latest();
+        utils::scroll_to_bottom("messages-container");
+
+        deliver(state_for_send.clone(), pending);
     };
 
     let on_logout = move |_| {
         let state = state_for_logout.clone();
         spawn(async move {
+            let _ = state.api.logout().await;
             state.clear_auth().await;
             nav.push(Route::Login {});
         });
@@ -308,13 +680,36 @@ pub fn Chat() -> Element {
 
     let can_delete_room = is_room_creator || is_admin;
 
+    // Unacknowledged key changes block sending in the room
+    let room_key_changes = state.room_key_changes();
+    let keys_blocked = !room_key_changes.is_empty();
+
     rsx! {
         div {
-            class: "flex h-screen bg-dc-chat",
+            class: "flex h-full bg-dc-chat overflow-hidden",
+
+            if let Some(notice) = state.maintenance.read().clone() {
+                div {
+                    class: "fixed top-0 inset-x-0 z-50 bg-yellow-600 text-white text-sm text-center py-1 px-4",
+                    "\u{1F6A7} {notice} \u{2014} messages can't be sent right now."
+                }
+            }
+
+            // Backdrop behind the drawer on narrow screens
+            if sidebar_open() {
+                div {
+                    class: "md:hidden fixed inset-0 bg-black bg-opacity-50 z-30",
+                    onclick: move |_| sidebar_open.set(false),
+                }
+            }
 
             // ─── SIDEBAR ───────────────────────────────────────────
             div {
-                class: "w-60 bg-dc-sidebar flex flex-col",
+                class: if sidebar_open() {
+                    "fixed inset-y-0 left-0 z-40 w-72 max-w-[85vw] transition-transform md:static md:z-auto md:w-60 md:translate-x-0 bg-dc-sidebar flex flex-col flex-shrink-0"
+                } else {
+                    "fixed inset-y-0 left-0 z-40 w-72 max-w-[85vw] -translate-x-full transition-transform md:static md:z-auto md:w-60 md:translate-x-0 bg-dc-sidebar flex flex-col flex-shrink-0"
+                },
 
                 // Server/App header
                 div {
@@ -334,19 +729,23 @@ pub fn Chat() -> Element {
                     }
                     div { class: "flex-1" }
                     button {
-                        class: "w-7 h-7 flex items-center justify-center bg-dc-accent hover:bg-dc-accent-dim text-white rounded-md text-lg transition-colors",
+                        class: "w-10 h-10 md:w-7 md:h-7 mr-1 flex items-center justify-center text-dc-text-muted hover:text-white hover:bg-dc-hover rounded-md transition-colors",
+                        title: "Jump to a room (Ctrl+K)",
+                        onclick: move |_| show_switcher.set(true),
+                        "\u{1F50D}"
+                    }
+                    button {
+                        class: "w-10 h-10 md:w-7 md:h-7 flex items-center justify-center bg-dc-accent hover:bg-dc-accent-dim text-white rounded-md text-lg transition-colors",
                         title: "Create Room",
-                        onclick: move |_| {
-                            show_create_modal.set(true);
-                            create_error.set(None);
-                            new_room_name.set(String::new());
-                            new_room_desc.set(String::new());
-                            new_room_public.set(is_admin);
-                        },
+                        onclick: move |_| show_create_modal.set(true),
                         "+"
                     }
                 }
 
+                MessageSearch {}
+                DigestCard {}
+                RequestsCard {}
+
                 // Channel list
                 div {
                     class: "flex-1 overflow-y-auto pt-2 px-2",
@@ -355,11 +754,6 @@ pub fn Chat() -> Element {
                             class: "px-2 py-4 text-center text-dc-text-muted text-sm",
                             "Loading rooms..."
                         }
-                    } else if let Some(err) = error_msg() {
-                        div {
-                            class: "px-2 py-4 text-center text-red-400 text-sm",
-                            "{err}"
-                        }
                     } else if rooms.is_empty() {
                         div {
                             class: "px-2 py-8 text-center text-dc-text-faint text-sm",
@@ -373,52 +767,19 @@ pub fn Chat() -> Element {
                                 let room_is_public = room.is_public;
                                 let is_selected = selected_room_idx() == Some(idx);
                                 let unread = room.unread_count;
-                                let state = state_for_rooms.clone();
-                                let room_clone = room.clone();
+                                let mentioned = room.mentioned;
+                                let room_uuid = room.id;
                                 rsx! {
                                     div {
                                         key: "{room_id}",
                                         class: if is_selected {
-                                            "flex items-center gap-2 px-2.5 py-2 rounded cursor-pointer mb-0.5 bg-dc-hover text-white border-l-2 border-dc-accent"
+                                            "flex items-center gap-2 px-2.5 py-3 md:py-2 rounded cursor-pointer mb-0.5 bg-dc-hover text-white border-l-2 border-dc-accent"
                                         } else if unread > 0 {
-                                            "flex items-center gap-2 px-2.5 py-2 rounded cursor-pointer mb-0.5 hover:bg-dc-hover text-white border-l-2 border-transparent"
+                                            "flex items-center gap-2 px-2.5 py-3 md:py-2 rounded cursor-pointer mb-0.5 hover:bg-dc-hover text-white border-l-2 border-transparent"
                                         } else {
-                                            "flex items-center gap-2 px-2.5 py-2 rounded cursor-pointer mb-0.5 hover:bg-dc-hover text-dc-text-muted border-l-2 border-transparent"
-                                        },
-                                        onclick: move |_| {
-                                            selected_room_idx.set(Some(idx));
-                                            show_members.set(false);
-                                            reply_to_msg.set(None);
-                                            // Set current_room signal for unread tracking
-                                            {
-                                                let mut cr = state.current_room;
-                                                cr.set(Some(room_clone.clone()));
-                                            }
-                                            // Clear unread count for this room
-                                            {
-                                                let mut rsig = state.rooms;
-                                                let mut rooms = rsig.write();
-                                                if let Some(r) = rooms.get_mut(idx) {
-                                                    r.unread_count = 0;
-                                                }
-                                            }
-                                            let state = state.clone();
-                                            let rid = room_id.clone();
-                                            spawn(async move {
-                                                state.socket.join_room(&rid).await;
-                                                let _ = state.load_messages(&rid).await;
-                                                // Scroll to bottom after loading
-                                                utils::scroll_to_bottom("messages-container");
-                                                // Mark read with latest message (now last = newest with ASC)
-                                                let msgs = state.messages.read();
-                                                if let Some(latest) = msgs.last() {
-                                                    state.socket.emit("mark_read", serde_json::json!({
-                                                        "roomId": rid,
-                                                        "messageId": latest.id.to_string(),
-                                                    })).await;
-                                                }
-                                            });
+                                            "flex items-center gap-2 px-2.5 py-3 md:py-2 rounded cursor-pointer mb-0.5 hover:bg-dc-hover text-dc-text-muted border-l-2 border-transparent"
                                         },
+                                        onclick: move |_| open_room.call(room_uuid),
                                         // Channel icon
                                         span {
                                             class: "text-xl leading-none flex-shrink-0 opacity-70",
@@ -429,6 +790,14 @@ pub fn Chat() -> Element {
                                             class: if unread > 0 { "flex-1 truncate text-sm font-semibold" } else { "flex-1 truncate text-sm" },
                                             "{room_name}"
                                         }
+                                        // Whole-room mention badge
+                                        if mentioned {
+                                            span {
+                                                class: "bg-red-600 text-white text-xs font-bold rounded-full min-w-[18px] h-[18px] flex items-center justify-center px-1",
+                                                title: "A room admin mentioned everyone",
+                                                "@"
+                                            }
+                                        }
                                         // Unread badge
                                         if unread > 0 {
                                             span {
@@ -473,9 +842,47 @@ pub fn Chat() -> Element {
                         }
                     }
                     // Action buttons
+                    {
+                        let low = state.low_bandwidth.read().to_owned();
+                        let state_bw = state.clone();
+                        rsx! {
+                            button {
+                                class: if low {
+                                    "text-dc-accent hover:text-dc-text p-2.5 md:p-1 rounded hover:bg-dc-hover"
+                                } else {
+                                    "text-dc-text-muted hover:text-dc-text p-2.5 md:p-1 rounded hover:bg-dc-hover"
+                                },
+                                title: if low { "Low bandwidth mode: on" } else { "Low bandwidth mode: off" },
+                                onclick: move |_| state_bw.set_low_bandwidth(!low),
+                                // antenna bars icon
+                                "\u{1F4F6}"
+                            }
+                        }
+                    }
+                    button {
+                        class: "text-dc-text-muted hover:text-dc-text p-2.5 md:p-1 rounded hover:bg-dc-hover",
+                        title: "Message retention",
+                        onclick: move |_| show_retention.set(true),
+                        // hourglass icon
+                        "\u{231B}"
+                    }
+                    button {
+                        class: "text-dc-text-muted hover:text-dc-text p-2.5 md:p-1 rounded hover:bg-dc-hover",
+                        title: "Identity keys",
+                        onclick: move |_| show_identity.set(true),
+                        // key icon
+                        "\u{1F511}"
+                    }
+                    button {
+                        class: "text-dc-text-muted hover:text-dc-text p-2.5 md:p-1 rounded hover:bg-dc-hover",
+                        title: "About this build",
+                        onclick: move |_| show_build_info.set(true),
+                        // info icon
+                        "\u{2139}"
+                    }
                     if is_admin {
                         button {
-                            class: "text-dc-text-muted hover:text-dc-text p-1 rounded hover:bg-dc-hover",
+                            class: "text-dc-text-muted hover:text-dc-text p-2.5 md:p-1 rounded hover:bg-dc-hover",
                             title: "Admin Panel",
                             onclick: move |_| { nav.push(Route::Admin {}); },
                             // gear icon
@@ -483,7 +890,7 @@ pub fn Chat() -> Element {
                         }
                     }
                     button {
-                        class: "text-dc-text-muted hover:text-red-400 p-1 rounded hover:bg-dc-hover",
+                        class: "text-dc-text-muted hover:text-red-400 p-2.5 md:p-1 rounded hover:bg-dc-hover",
                         title: "Logout",
                         onclick: on_logout,
                         // power icon
@@ -498,53 +905,85 @@ pub fn Chat() -> Element {
                 if let Some(room) = &selected_room {
                     // ─── CHAT HEADER ────────────────────────────────
                     div {
-                        class: "h-12 min-h-[48px] px-4 flex items-center border-b border-dc-border bg-dc-chat shadow-sm",
+                        class: "h-14 min-h-[56px] md:h-12 md:min-h-[48px] px-2 md:px-4 flex items-center border-b border-dc-border bg-dc-chat shadow-sm",
+                        // Opens the room list on narrow screens
+                        button {
+                            class: "md:hidden p-2.5 mr-1 rounded text-dc-text-muted hover:bg-dc-hover hover:text-dc-text",
+                            title: "Rooms",
+                            onclick: move |_| sidebar_open.set(true),
+                            "\u{2630}"
+                        }
                         // Channel icon + name
                         span {
                             class: "text-dc-text-muted text-lg mr-1",
                             if room.is_public { "#" } else { "\u{1F512}" }
                         }
                         h2 {
-                            class: "font-semibold text-white text-base",
+                            class: "font-semibold text-white text-base truncate min-w-0",
                             "{room.name}"
                         }
+                        if room.is_e2ee() {
+                            {
+                                let since = room
+                                    .e2ee_since
+                                    .map(|at| format!(" since {}", utils::format_full_timestamp(&at)))
+                                    .unwrap_or_default();
+                                rsx! {
+                                    span {
+                                        class: "ml-2 px-1.5 py-0.5 rounded bg-emerald-900/50 text-emerald-300 text-xs",
+                                        title: "End-to-end encrypted{since}. Server-side search, translation and forwarding are off.",
+                                        "\u{1F6E1} E2EE"
+                                    }
+                                }
+                            }
+                        }
+                        if let Some(capacity) = room.capacity_hint() {
+                            span {
+                                class: "ml-2 px-1.5 py-0.5 rounded bg-amber-900/50 text-amber-300 text-xs",
+                                title: "This room is close to its member limit",
+                                "Nearly full ({capacity})"
+                            }
+                        }
+                        // No room on a phone screen for the description
                         if let Some(desc) = &room.description {
                             div {
-                                class: "mx-3 w-px h-5 bg-dc-border"
+                                class: "hidden md:block mx-3 w-px h-5 bg-dc-border"
                             }
                             p {
-                                class: "text-sm text-dc-text-muted truncate flex-1",
+                                class: "hidden md:block text-sm text-dc-text-muted truncate flex-1",
                                 "{desc}"
                             }
+                            div { class: "flex-1 md:hidden" }
                         }
                         if room.description.is_none() {
                             div { class: "flex-1" }
                         }
                         // Header action buttons
                         div {
-                            class: "flex items-center gap-1 ml-2",
+                            class: "flex items-center gap-0.5 md:gap-1 ml-2 flex-shrink-0",
                             // Members toggle
                             {
                                 let room_id = room.id.to_string();
-                                let api = state.api.clone();
+                                let state_members = state.clone();
                                 rsx! {
                                     button {
                                         class: if show_members() {
-                                            "p-1.5 rounded text-dc-text hover:bg-dc-hover"
+                                            "p-2.5 md:p-1.5 rounded text-dc-text hover:bg-dc-hover"
                                         } else {
-                                            "p-1.5 rounded text-dc-text-muted hover:bg-dc-hover hover:text-dc-text"
+                                            "p-2.5 md:p-1.5 rounded text-dc-text-muted hover:bg-dc-hover hover:text-dc-text"
                                         },
                                         title: "Members",
                                         onclick: move |_| {
                                             let is_showing = show_members();
                                             show_members.set(!is_showing);
+                                            show_files.set(false);
                                             if !is_showing {
-                                                let api = api.clone();
+                                                let state = state_members.clone();
                                                 let rid = room_id.clone();
                                                 spawn(async move {
-                                                    match api.get_room_members(&rid).await {
+                                                    match state.api.get_room_members(&rid).await {
                                                         Ok(m) => members.set(m),
-                                                        Err(e) => tracing::error!("Failed to load members: {}", e),
+                                                        Err(e) => state.toast_error(format!("Couldn't load members: {}", e)),
                                                     }
                                                 });
                                             }
@@ -553,6 +992,27 @@ pub fn Chat() -> Element {
                                     }
                                 }
                             }
+                            // Files toggle
+                            button {
+                                class: if show_files() {
+                                    "p-2.5 md:p-1.5 rounded text-dc-text hover:bg-dc-hover"
+                                } else {
+                                    "p-2.5 md:p-1.5 rounded text-dc-text-muted hover:bg-dc-hover hover:text-dc-text"
+                                },
+                                title: "Files",
+                                onclick: move |_| {
+                                    show_files.set(!show_files());
+                                    show_members.set(false);
+                                },
+                                "\u{1F4C1}"
+                            }
+                            // Room info: settings, invites, pins, files and roles
+                            button {
+                                class: "p-2.5 md:p-1.5 rounded text-dc-text-muted hover:bg-dc-hover hover:text-dc-text",
+                                title: "Room info",
+                                onclick: move |_| show_room_info.set(true),
+                                "\u{2139}"
+                            }
                             // Leave button (hidden for room creator)
                             if !is_room_creator {
                                 {
@@ -560,7 +1020,7 @@ pub fn Chat() -> Element {
                                     let state_leave = state.clone();
                                     rsx! {
                                         button {
-                                            class: "p-1.5 rounded text-dc-text-muted hover:bg-dc-hover hover:text-yellow-400",
+                                            class: "p-2.5 md:p-1.5 rounded text-dc-text-muted hover:bg-dc-hover hover:text-yellow-400",
                                             title: "Leave Room",
                                             onclick: move |_| {
                                                 let state = state_leave.clone();
@@ -573,7 +1033,7 @@ pub fn Chat() -> Element {
                                                             cr.set(None);
                                                             let _ = state.load_rooms().await;
                                                         }
-                                                        Err(e) => tracing::error!("Failed to leave room: {}", e),
+                                                        Err(e) => state.toast_error(format!("Couldn't leave the room: {}", e)),
                                                     }
                                                 });
                                             },
@@ -589,7 +1049,7 @@ pub fn Chat() -> Element {
                                     let state_del = state.clone();
                                     rsx! {
                                         button {
-                                            class: "p-1.5 rounded text-dc-text-muted hover:bg-dc-hover hover:text-red-400",
+                                            class: "p-2.5 md:p-1.5 rounded text-dc-text-muted hover:bg-dc-hover hover:text-red-400",
                                             title: "Delete Room",
                                             onclick: move |_| {
                                                 let state = state_del.clone();
@@ -602,7 +1062,7 @@ pub fn Chat() -> Element {
                                                             cr.set(None);
                                                             let _ = state.load_rooms().await;
                                                         }
-                                                        Err(e) => tracing::error!("Failed to delete room: {}", e),
+                                                        Err(e) => state.toast_error(format!("Couldn't delete the room: {}", e)),
                                                     }
                                                 });
                                             },
@@ -636,11 +1096,7 @@ pub fn Chat() -> Element {
                                             for pm in pinned.iter() {
                                                 {
                                                     let username = pm.user.as_ref().map(|u| u.username.as_str()).unwrap_or("?");
-                                                    let content: String = if pm.content.len() > 80 {
-                                                        format!("{}...", &pm.content[..80])
-                                                    } else {
-                                                        pm.content.clone()
-                                                    };
+                                                    let content = pm.decoded().preview(80);
                                                     let pin_msg_id = pm.id.to_string();
                                                     rsx! {
                                                         div {
@@ -666,10 +1122,29 @@ pub fn Chat() -> Element {
                                 }
                             }
 
+                            if let Some(err) = jump_error() {
+                                div {
+                                    class: "flex items-center gap-2 px-4 py-1.5 text-xs text-red-400 bg-dc-dark",
+                                    span { class: "flex-1", "{err}" }
+                                    button {
+                                        class: "text-dc-text-muted hover:text-dc-text",
+                                        onclick: move |_| jump_error.set(None),
+                                        "\u{2715}"
+                                    }
+                                }
+                            }
+
                             // Messages scroll area
                             div {
                                 id: "messages-container",
                                 class: "flex-1 overflow-y-auto",
+                                onscroll: {
+                                    let state = state.clone();
+                                    move |_| {
+                                        slide_window(&state, sliding);
+                                        state.mark_read_if_visible();
+                                    }
+                                },
                                 if messages.is_empty() {
                                     div {
                                         class: "flex flex-col items-center justify-center h-full text-dc-text-muted",
@@ -697,22 +1172,54 @@ pub fn Chat() -> Element {
                                     // Render messages with grouping and date separators
                                     {
                                         let msgs: Vec<_> = messages.iter().collect();
+                                        let range = state.message_window.read().range(msgs.len());
                                         let mut elements: Vec<Element> = Vec::new();
                                         let mut prev_date: Option<chrono::NaiveDate> = None;
                                         let mut prev_user_id: Option<uuid::Uuid> = None;
                                         let mut prev_time: Option<chrono::DateTime<chrono::Utc>> = None;
+                                        let now = state.api.server_now();
 
-                                        for (i, msg) in msgs.iter().enumerate() {
+                                        // Only a slice of a long history is rendered;
+                                        // grouping carries on from the message before it
+                                        if let Some(before) = range.start.checked_sub(1).map(|i| msgs[i]) {
+                                            prev_date = Some(before.created_at.date_naive());
+                                            prev_user_id = Some(before.user_id);
+                                            prev_time = Some(before.created_at);
+                                            elements.push(rsx! {
+                                                div {
+                                                    key: "window-earlier",
+                                                    class: "text-center text-xs text-dc-text-faint py-2",
+                                                    "Scroll up for earlier messages"
+                                                }
+                                            });
+                                        }
+
+                                        for (i, msg) in msgs.iter().enumerate().take(range.end).skip(range.start) {
                                             let msg_date = msg.created_at.date_naive();
                                             // Date separator
                                             if prev_date.is_none() || prev_date.unwrap() != msg_date {
-                                                let date_text = utils::format_date_separator(&msg.created_at);
+                                                let date_text = utils::format_date_separator(&msg.created_at, &now);
                                                 elements.push(rsx! {
                                                     DateSeparator { key: "date-{i}", date_text: date_text }
                                                 });
                                                 prev_user_id = None;
                                             }
 
+                                            if first_unread() == Some(msg.id) {
+                                                elements.push(rsx! {
+                                                    UnreadDivider { key: "unread-divider" }
+                                                });
+                                                prev_user_id = None;
+                                            }
+
+                                            // Where legacy plaintext history ends
+                                            if i > 0 && msgs[i - 1].legacy_plaintext && !msg.legacy_plaintext {
+                                                elements.push(rsx! {
+                                                    E2eeDivider { key: "e2ee-divider" }
+                                                });
+                                                prev_user_id = None;
+                                            }
+
                                             // Message grouping: same user within 5 minutes
                                             let is_continuation = prev_user_id == Some(msg.user_id)
                                                 && prev_time.is_some_and(|pt| {
@@ -722,6 +1229,7 @@ pub fn Chat() -> Element {
 
                                             let socket_pin = state.socket.clone();
                                             let socket_unpin = state.socket.clone();
+                                            let state_retry = state.clone();
                                             elements.push(rsx! {
                                                 MessageBubble {
                                                     key: "{msg.id}",
@@ -731,6 +1239,9 @@ pub fn Chat() -> Element {
                                                     on_reply: move |m: crate::models::Message| {
                                                         reply_to_msg.set(Some(m));
                                                     },
+                                                    on_forward: move |m: Message| {
+                                                        forward_msg.set(Some(m));
+                                                    },
                                                     on_pin: move |m: crate::models::Message| {
                                                         let socket = socket_pin.clone();
                                                         let mid = m.id.to_string();
@@ -749,6 +1260,7 @@ pub fn Chat() -> Element {
                                                             })).await;
                                                         });
                                                     },
+                                                    on_retry: move |m: Message| resend(state_retry.clone(), m),
                                                 }
                                             });
 
@@ -756,6 +1268,19 @@ pub fn Chat() -> Element {
                                             prev_user_id = Some(msg.user_id);
                                             prev_time = Some(msg.created_at);
                                         }
+                                        if range.end < msgs.len() {
+                                            elements.push(rsx! {
+                                                div {
+                                                    key: "window-later",
+                                                    class: "text-center text-xs text-dc-text-faint py-2",
+                                                    "Scroll down for newer messages"
+                                                }
+                                            });
+                                        } else if msgs.last().is_some_and(|m| m.legacy_plaintext) {
+                                            elements.push(rsx! {
+                                                E2eeDivider { key: "e2ee-divider" }
+                                            });
+                                        }
 
                                         rsx! {
                                             div {
@@ -765,6 +1290,18 @@ pub fn Chat() -> Element {
                                         }
                                     }
                                 }
+                                for change in room_key_changes {
+                                    {
+                                        let state = state.clone();
+                                        rsx! {
+                                            KeyChangeNotice {
+                                                key: "key-change-{change.user_id}",
+                                                change: change.clone(),
+                                                on_accept: move |c: tor_chat_common::KeyChange| state.accept_key_change(&c),
+                                            }
+                                        }
+                                    }
+                                }
                             }
 
                             // ─── INPUT AREA ─────────────────────────
@@ -782,7 +1319,7 @@ pub fn Chat() -> Element {
                                             }
                                             div {
                                                 class: "text-xs text-dc-text-muted truncate",
-                                                "{reply_msg.content}"
+                                                "{reply_msg.decoded().preview(100)}"
                                             }
                                         }
                                         button {
@@ -873,14 +1410,19 @@ pub fn Chat() -> Element {
                                     input {
                                         r#type: "text",
                                         class: "flex-1 bg-transparent px-1 py-3 text-dc-text placeholder-dc-text-faint focus:outline-none text-[0.9375rem]",
-                                        placeholder: "Message #{room.name}",
+                                        placeholder: if keys_blocked {
+                                            "Review the key change above before sending".to_string()
+                                        } else {
+                                            format!("Message #{}", room.name)
+                                        },
+                                        disabled: keys_blocked,
                                         value: "{message_input}",
                                         oninput: move |e| message_input.set(e.value().clone()),
                                     }
                                     // Send file button (shown when file is selected)
                                     {
                                         let file_opt = selected_file();
-                                        if file_opt.is_some() && !is_uploading() {
+                                        if file_opt.is_some() && !is_uploading() && !keys_blocked {
                                             let file = file_opt.unwrap();
                                             let state_upload = state.clone();
                                             let selected_room = selected_room.clone();
@@ -900,10 +1442,9 @@ pub fn Chat() -> Element {
                                                                 let (filename, file_bytes) = file;
                                                                 match state.api.upload_file(file_bytes, &filename).await {
                                                                     Ok(response) => {
-                                                                        if let Some(file_url) = response.get("file")
-                                                                            .and_then(|f| f.get("url"))
-                                                                            .and_then(|u| u.as_str()) {
-                                                                            match state.api.send_image_message(&room_id, file_url).await {
+                                                                        if let Some(file) = response.get("file")
+                                                                            .filter(|f| f["url"].is_string()) {
+                                                                            match state.api.send_file_message(&room_id, file).await {
                                                                                 Ok(_) => {
                                                                                     upload_status.set(None);
                                                                                     selected_file.set(None);
@@ -929,8 +1470,26 @@ pub fn Chat() -> Element {
                                             rsx! {}
                                         }
                                     }
+                                    select {
+                                        class: if message_expiry().is_some() {
+                                            "bg-transparent text-xs text-orange-400 px-1 focus:outline-none cursor-pointer"
+                                        } else {
+                                            "bg-transparent text-xs text-dc-text-faint px-1 focus:outline-none cursor-pointer"
+                                        },
+                                        title: "Delete messages after they've been read",
+                                        onchange: move |e| message_expiry.set(e.value().parse().ok()),
+                                        option { value: "", selected: message_expiry().is_none(), "\u{1F525} Off" }
+                                        for (seconds, label) in EXPIRY_CHOICES {
+                                            option {
+                                                value: "{seconds}",
+                                                selected: message_expiry() == Some(*seconds),
+                                                "\u{1F525} {label}"
+                                            }
+                                        }
+                                    }
                                     button {
                                         r#type: "submit",
+                                        disabled: keys_blocked,
                                         class: "px-3 py-3 text-dc-accent hover:bg-dc-accent hover:text-white rounded-r-lg transition-colors",
                                         title: "Send",
                                         "\u{27A4}"
@@ -939,10 +1498,16 @@ pub fn Chat() -> Element {
                             }
                         }
 
+                        // ─── FILES PANEL ────────────────────────────
+                        if show_files() {
+                            FilesPanel { key: "{room.id}", room_id: room.id.to_string() }
+                        }
+
                         // ─── MEMBERS PANEL ──────────────────────────
                         if show_members() {
                             div {
-                                class: "w-60 bg-dc-sidebar border-l border-dc-border overflow-y-auto flex-shrink-0",
+                                // Slides over the messages on narrow screens
+                                class: "fixed inset-y-0 right-0 z-40 w-72 max-w-[85vw] md:static md:z-auto md:w-60 bg-dc-sidebar border-l border-dc-border overflow-y-auto flex-shrink-0",
                                 // Panel header
                                 div {
                                     class: "px-4 py-3",
@@ -954,7 +1519,7 @@ pub fn Chat() -> Element {
                                 // Add member button (admin only)
                                 if is_room_creator || is_admin {
                                     {
-                                        let api = state.api.clone();
+                                        let state_users = state.clone();
                                         rsx! {
                                             div {
                                                 class: "px-3 mb-2",
@@ -964,11 +1529,11 @@ pub fn Chat() -> Element {
                                                         show_add_member_modal.set(true);
                                                         add_member_error.set(None);
                                                         add_member_search.set(String::new());
-                                                        let api = api.clone();
+                                                        let state = state_users.clone();
                                                         spawn(async move {
-                                                            match api.get_users().await {
+                                                            match state.api.get_users().await {
                                                                 Ok(users) => all_users.set(users),
-                                                                Err(e) => tracing::error!("Failed to load users: {}", e),
+                                                                Err(e) => state.toast_error(format!("Couldn't load users: {}", e)),
                                                             }
                                                         });
                                                     },
@@ -978,37 +1543,52 @@ pub fn Chat() -> Element {
                                         }
                                     }
                                 }
-                                // Admins section
+                                // Filter box
+                                div {
+                                    class: "px-3 mb-2",
+                                    input {
+                                        class: "w-full bg-dc-input text-dc-text text-xs rounded px-2 py-1.5 outline-none placeholder-dc-text-faint",
+                                        r#type: "search",
+                                        placeholder: "Filter members",
+                                        aria_label: "Filter members",
+                                        value: "{member_filter}",
+                                        oninput: move |e| member_filter.set(e.value()),
+                                    }
+                                }
+                                // Online first, each section by role then name
                                 {
-                                    let admin_members: Vec<_> = members.read().iter()
-                                        .filter(|m| m["role"].as_str() == Some("admin"))
-                                        .cloned().collect();
-                                    let regular_members: Vec<_> = members.read().iter()
-                                        .filter(|m| m["role"].as_str() != Some("admin"))
-                                        .cloned().collect();
+                                    let creator_id = selected_room.as_ref().and_then(|r| r.creator_id);
+                                    let (online_members, offline_members) =
+                                        member_sections(&members.read(), &member_filter(), creator_id);
 
                                     rsx! {
-                                        if !admin_members.is_empty() {
+                                        if online_members.is_empty() && offline_members.is_empty() && !member_filter().trim().is_empty() {
+                                            div {
+                                                class: "px-4 py-2 text-xs text-dc-text-faint",
+                                                "No members match"
+                                            }
+                                        }
+                                        if !online_members.is_empty() {
                                             div {
                                                 class: "px-4 pt-2 pb-1",
                                                 h4 {
                                                     class: "text-xs font-semibold text-dc-text-muted uppercase tracking-wide",
-                                                    "Admin \u{2014} {admin_members.len()}"
+                                                    "Online \u{2014} {online_members.len()}"
                                                 }
                                             }
-                                            for member in admin_members.iter() {
+                                            for member in online_members.iter() {
                                                 { render_member_item(member, &selected_room, current_user_id, is_room_creator, is_admin, &state, &mut members) }
                                             }
                                         }
-                                        if !regular_members.is_empty() {
+                                        if !offline_members.is_empty() {
                                             div {
                                                 class: "px-4 pt-3 pb-1",
                                                 h4 {
                                                     class: "text-xs font-semibold text-dc-text-muted uppercase tracking-wide",
-                                                    "Members \u{2014} {regular_members.len()}"
+                                                    "Offline \u{2014} {offline_members.len()}"
                                                 }
                                             }
-                                            for member in regular_members.iter() {
+                                            for member in offline_members.iter() {
                                                 { render_member_item(member, &selected_room, current_user_id, is_room_creator, is_admin, &state, &mut members) }
                                             }
                                         }
@@ -1042,6 +1622,11 @@ pub fn Chat() -> Element {
                                 class: "text-sm mb-4",
                                 "Select a channel from the sidebar to start chatting"
                             }
+                            button {
+                                class: "md:hidden mb-4 px-4 py-2.5 rounded bg-dc-accent text-white text-sm",
+                                onclick: move |_| sidebar_open.set(true),
+                                "Show rooms"
+                            }
                             a {
                                 class: "text-xs text-dc-accent hover:underline",
                                 href: "https://github.com/idan2025/tor-chat-app/releases/latest",
@@ -1054,6 +1639,40 @@ pub fn Chat() -> Element {
                 }
             }
 
+            if show_retention() {
+                RetentionSettings { on_close: move |_| show_retention.set(false) }
+            }
+            if show_identity() {
+                IdentityKeysDialog { on_close: move |_| show_identity.set(false) }
+            }
+            if show_build_info() {
+                BuildInfoDialog { on_close: move |_| show_build_info.set(false) }
+            }
+
+            if let Some(room) = selected_room.clone().filter(|_| show_room_info()) {
+                RoomInfoPanel {
+                    key: "{room.id}",
+                    room,
+                    can_manage: is_room_creator || is_admin,
+                    on_close: move |_| show_room_info.set(false),
+                }
+            }
+
+            if let Some(message) = forward_msg() {
+                ForwardPicker { message, on_close: move |_| forward_msg.set(None) }
+            }
+
+            if show_switcher() {
+                QuickSwitcher {
+                    recent: recent_rooms(),
+                    on_select: move |room_id| {
+                        show_switcher.set(false);
+                        open_room.call(room_id);
+                    },
+                    on_close: move |_| show_switcher.set(false),
+                }
+            }
+
             // ─── ADD MEMBER MODAL ───────────────────────────────────
             if show_add_member_modal() {
                 div {
@@ -1176,109 +1795,13 @@ pub fn Chat() -> Element {
 
             // ─── CREATE ROOM MODAL ──────────────────────────────────
             if show_create_modal() {
-                div {
-                    class: "fixed inset-0 bg-black bg-opacity-60 flex items-center justify-center z-50",
-                    onclick: move |_| show_create_modal.set(false),
-                    div {
-                        class: "bg-dc-sidebar rounded-lg p-5 w-96 max-w-full mx-4 border border-dc-border shadow-xl",
-                        onclick: move |e| e.stop_propagation(),
-                        h2 {
-                            class: "text-lg font-semibold text-white mb-4",
-                            "Create Channel"
-                        }
-                        if let Some(err) = create_error() {
-                            div {
-                                class: "bg-red-900/50 text-red-200 p-2 rounded mb-3 text-sm",
-                                "{err}"
-                            }
-                        }
-                        div {
-                            class: "space-y-4",
-                            div {
-                                label {
-                                    class: "block text-xs font-semibold text-dc-text-muted uppercase tracking-wide mb-1",
-                                    "Channel Name"
-                                }
-                                input {
-                                    r#type: "text",
-                                    class: "w-full px-3 py-2 bg-dc-input border border-dc-border rounded text-dc-text placeholder-dc-text-faint focus:outline-none focus:border-dc-accent text-sm",
-                                    placeholder: "new-channel",
-                                    value: "{new_room_name}",
-                                    oninput: move |e| new_room_name.set(e.value().clone()),
-                                }
-                            }
-                            div {
-                                label {
-                                    class: "block text-xs font-semibold text-dc-text-muted uppercase tracking-wide mb-1",
-                                    "Description (optional)"
-                                }
-                                input {
-                                    r#type: "text",
-                                    class: "w-full px-3 py-2 bg-dc-input border border-dc-border rounded text-dc-text placeholder-dc-text-faint focus:outline-none focus:border-dc-accent text-sm",
-                                    placeholder: "What's this channel about?",
-                                    value: "{new_room_desc}",
-                                    oninput: move |e| new_room_desc.set(e.value().clone()),
-                                }
-                            }
-                            if is_admin {
-                                div {
-                                    class: "flex items-center gap-2",
-                                    input {
-                                        r#type: "checkbox",
-                                        class: "w-4 h-4 accent-dc-accent",
-                                        checked: new_room_public(),
-                                        onchange: move |e| new_room_public.set(e.checked()),
-                                    }
-                                    label {
-                                        class: "text-sm text-dc-text-muted",
-                                        "Public channel (visible to all users)"
-                                    }
-                                }
-                            } else {
-                                p {
-                                    class: "text-xs text-dc-text-faint",
-                                    "Channel will be private. Only admins can create public channels."
-                                }
-                            }
-                            div {
-                                class: "flex gap-2 pt-2",
-                                {
-                                    let state_create = state.clone();
-                                    rsx! {
-                                        button {
-                                            class: "flex-1 bg-dc-accent hover:bg-indigo-500 text-white py-2 px-4 rounded text-sm font-medium",
-                                            onclick: move |_| {
-                                                let name = new_room_name();
-                                                if name.trim().is_empty() {
-                                                    create_error.set(Some("Channel name is required".to_string()));
-                                                    return;
-                                                }
-                                                let desc = new_room_desc();
-                                                let description = if desc.trim().is_empty() { None } else { Some(desc) };
-                                                let is_public = new_room_public();
-                                                let state = state_create.clone();
-                                                spawn(async move {
-                                                    match state.api.create_room(name, description, is_public).await {
-                                                        Ok(_) => {
-                                                            show_create_modal.set(false);
-                                                            let _ = state.load_rooms().await;
-                                                        }
-                                                        Err(e) => create_error.set(Some(e)),
-                                                    }
-                                                });
-                                            },
-                                            "Create"
-                                        }
-                                    }
-                                }
-                                button {
-                                    class: "flex-1 bg-dc-input hover:bg-dc-hover text-dc-text py-2 px-4 rounded text-sm",
-                                    onclick: move |_| show_create_modal.set(false),
-                                    "Cancel"
-                                }
-                            }
-                        }
-                    }
+                CreateRoomWizard {
+                    is_admin,
+                    on_close: move |_| show_create_modal.set(false),
+                    on_created: move |room_id| {
+                        show_create_modal.set(false);
+                        open_room.call(room_id);
+                    },
                 }
             }
         }
@@ -1305,6 +1828,13 @@ fn render_member_item(
         .zip(member_uuid)
         .map(|(c, m)| c == m)
         .unwrap_or(false);
+    let role_label = if is_creator {
+        Some("Owner")
+    } else if member["role"].as_str() == Some("admin") {
+        Some("Admin")
+    } else {
+        None
+    };
     let can_remove = (is_room_creator || is_admin)
         && !is_creator
         && current_user_id
@@ -1316,7 +1846,7 @@ fn render_member_item(
         .as_ref()
         .map(|r| r.id.to_string())
         .unwrap_or_default();
-    let api_for_remove = state.api.clone();
+    let state_for_remove = state.clone();
     let api_for_refresh = state.api.clone();
     let rid_for_refresh = room_id_for_remove.clone();
     let member_uid = member_user_id.clone();
@@ -1354,24 +1884,27 @@ fn render_member_item(
                     "{username}"
                 }
             }
+            if let Some(label) = role_label {
+                span { class: "text-[10px] text-dc-text-faint uppercase", "{label}" }
+            }
             if can_remove {
                 button {
                     class: "text-xs text-dc-text-faint hover:text-red-400 opacity-0 group-hover:opacity-100",
                     title: "Remove",
                     onclick: move |_| {
-                        let api = api_for_remove.clone();
+                        let state = state_for_remove.clone();
                         let rid = room_id_for_remove.clone();
                         let uid = member_uid.clone();
                         let api_refresh = api_for_refresh.clone();
                         let rid_refresh = rid_for_refresh.clone();
                         spawn(async move {
-                            match api.remove_room_member(&rid, &uid).await {
+                            match state.api.remove_room_member(&rid, &uid).await {
                                 Ok(()) => {
                                     if let Ok(m) = api_refresh.get_room_members(&rid_refresh).await {
                                         members.set(m);
                                     }
                                 }
-                                Err(e) => tracing::error!("Failed to remove member: {}", e),
+                                Err(e) => state.toast_error(format!("Couldn't remove the member: {}", e)),
                             }
                         });
                     },
@@ -1381,3 +1914,199 @@ fn render_member_item(
         }
     }
 }
+
+/// Members matching `filter` (by username or display name), split into
+/// online and offline, each ordered by role (creator, admins, members) and
+/// then name
+fn member_sections(
+    members: &[serde_json::Value],
+    filter: &str,
+    creator_id: Option<uuid::Uuid>,
+) -> (Vec<serde_json::Value>, Vec<serde_json::Value>) {
+    let filter = filter.trim().to_lowercase();
+    let name = |m: &serde_json::Value| {
+        m["user"]["displayName"]
+            .as_str()
+            .or(m["user"]["username"].as_str())
+            .unwrap_or("")
+            .to_lowercase()
+    };
+    let rank = |m: &serde_json::Value| {
+        let is_creator = m["isCreator"].as_bool().unwrap_or_else(|| {
+            creator_id.is_some_and(|c| m["userId"].as_str() == Some(c.to_string().as_str()))
+        });
+        match (is_creator, m["role"].as_str()) {
+            (true, _) => 0,
+            (false, Some("admin")) => 1,
+            _ => 2,
+        }
+    };
+
+    let mut matching: Vec<serde_json::Value> = members
+        .iter()
+        .filter(|m| {
+            filter.is_empty()
+                || name(m).contains(&filter)
+                || m["user"]["username"]
+                    .as_str()
+                    .is_some_and(|u| u.to_lowercase().contains(&filter))
+        })
+        .cloned()
+        .collect();
+    matching.sort_by_cached_key(|m| (rank(m), name(m)));
+    matching
+        .into_iter()
+        .partition(|m| m["user"]["isOnline"].as_bool().unwrap_or(false))
+}
+
+/// First message after the user's last read position, where the "new
+/// messages" divider goes. Falls back to counting back from the end when the
+/// last read message isn't in the loaded page.
+fn unread_start(messages: &[Message], unread: Option<&UnreadCount>) -> Option<uuid::Uuid> {
+    let unread = unread.filter(|u| u.unread_count > 0)?;
+    let last_read = unread
+        .last_read_message_id
+        .and_then(|id| messages.iter().position(|m| m.id == id));
+    let start = match last_read {
+        Some(pos) => pos + 1,
+        None => messages.len().saturating_sub(unread.unread_count as usize),
+    };
+    messages.get(start).map(|m| m.id)
+}
+
+/// How close to an end of the list, in pixels, the rendered slice slides
+const SLIDE_EDGE_PX: i32 = 300;
+
+/// Slide the rendered slice of a long history when the list is scrolled
+/// near one of its ends, keeping the message at the top of the view in
+/// place so the list doesn't jump
+fn slide_window(state: &AppState, mut sliding: Signal<bool>) {
+    let Some(container) = element_by_id("messages-container") else {
+        return;
+    };
+    if *sliding.peek() {
+        return;
+    }
+    let len = state.messages.peek().len();
+    let window = *state.message_window.peek();
+    let range = window.range(len);
+    let top = container.scroll_top();
+    let bottom = container.scroll_height() - top - container.client_height();
+    let moved = if top < SLIDE_EDGE_PX && range.start > 0 {
+        window.earlier(len)
+    } else if bottom < SLIDE_EDGE_PX && range.end < len {
+        window.later(len)
+    } else {
+        return;
+    };
+
+    let view_top = container.get_bounding_client_rect().top();
+    let offset_of =
+        |id: &str| element_by_id(id).map(|el| el.get_bounding_client_rect().top() - view_top);
+    let anchor = state.messages.peek()[range]
+        .iter()
+        .map(|m| format!("msg-{}", m.id))
+        .find_map(|id| {
+            offset_of(&id)
+                .filter(|offset| *offset >= 0.0)
+                .map(|o| (id, o))
+        });
+
+    let mut window_sig = state.message_window;
+    window_sig.set(moved);
+    let Some((anchor_id, before)) = anchor else {
+        return;
+    };
+    sliding.set(true);
+    spawn(async move {
+        gloo_timers::future::TimeoutFuture::new(0).await;
+        let view_top = container.get_bounding_client_rect().top();
+        if let Some(el) = element_by_id(&anchor_id) {
+            let after = el.get_bounding_client_rect().top() - view_top;
+            container.set_scroll_top(container.scroll_top() + (after - before) as i32);
+        }
+        sliding.set(false);
+    });
+}
+
+fn element_by_id(id: &str) -> Option<web_sys::Element> {
+    web_sys::window()?.document()?.get_element_by_id(id)
+}
+
+/// Send an optimistic message over REST and fold the result back into the
+/// room: the server copy replaces it on success, or it is marked failed
+fn deliver(state: AppState, pending: Message) {
+    spawn(async move {
+        let room_id = pending.room_id.to_string();
+        let reply_to = pending.reply_to.map(|id| id.to_string());
+        let result = state
+            .api
+            .send_message(
+                &room_id,
+                &pending.content,
+                reply_to.as_deref(),
+                pending.client_nonce.as_deref(),
+                pending.expires_in,
+            )
+            .await;
+
+        let mut messages = state.messages;
+        match result {
+            Ok(msg) => Message::reconcile(&mut messages.write(), msg),
+            Err(e) => {
+                let text = format!("Send failed: {}", e);
+                if let Some(m) = messages.write().iter_mut().find(|m| m.id == pending.id) {
+                    m.send_state = Some(SendState::Failed(e));
+                }
+                let retry_state = state.clone();
+                state.toast_action(text, "Retry", move || {
+                    resend(retry_state.clone(), pending.clone())
+                });
+            }
+        }
+    });
+}
+
+/// Try a failed message again, unless it went through in the meantime
+fn resend(state: AppState, message: Message) {
+    let mut messages = state.messages;
+    match messages.write().iter_mut().find(|m| m.id == message.id) {
+        Some(slot) if matches!(slot.send_state, Some(SendState::Failed(_))) => {
+            slot.send_state = Some(SendState::Pending);
+        }
+        _ => return,
+    }
+    deliver(state, message);
+}
+
+/// Load the room list again, offering another retry while it fails
+fn retry_rooms(state: AppState) {
+    spawn(async move {
+        if let Err(e) = state.load_rooms().await {
+            let retry_state = state.clone();
+            state.toast_action(format!("Couldn't load rooms: {}", e), "Retry", move || {
+                retry_rooms(retry_state.clone())
+            });
+        }
+    });
+}
+
+/// Connect the socket again after it dropped, and catch up on the open room.
+/// A failed attempt raises the lost-connection toast again.
+fn reconnect(state: AppState) {
+    let Some(token) = storage::get_token() else {
+        return;
+    };
+    spawn(async move {
+        state.socket.connect(&token).await;
+        if !state.socket.is_connected() {
+            return;
+        }
+        let room = state.current_room.peek().clone();
+        if let Some(room) = room {
+            if let Err(e) = state.load_messages(&room.id.to_string()).await {
+                state.toast_error(format!("Couldn't load new messages: {}", e));
+            }
+        }
+    });
+}
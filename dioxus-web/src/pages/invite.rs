@@ -0,0 +1,138 @@
+use crate::models::InvitePreview;
+use crate::state::AppState;
+use crate::utils::storage;
+use crate::Route;
+use dioxus::prelude::*;
+
+/// Where invite links (`/invite/<code>`) lead. Signed out, it offers to log
+/// in or register and comes back here afterwards, the way an expired session
+/// does; the invite is then accepted without asking again and the user lands
+/// in the room. Signed in, it shows the room and a Join button.
+#[component]
+pub fn Invite(code: String) -> Element {
+    let state = use_context::<AppState>();
+    let nav = navigator();
+    let signed_in = storage::get_token().is_some();
+    let mut preview = use_signal(|| None::<InvitePreview>);
+    let mut error = use_signal(|| None::<String>);
+    let mut joining = use_signal(|| false);
+
+    let join = use_callback({
+        let state = state.clone();
+        let code = code.clone();
+        move |()| {
+            let state = state.clone();
+            let code = code.clone();
+            spawn(async move {
+                joining.set(true);
+                error.set(None);
+                match state.api.accept_invite(&code).await {
+                    Ok(room) => {
+                        let room_id = room.id.to_string();
+                        let mut rooms = state.rooms;
+                        if !rooms.peek().iter().any(|r| r.id == room.id) {
+                            rooms.write().push(room);
+                        }
+                        // Chat opens this room once it has loaded the list
+                        let mut open_room = state.admin_view_room;
+                        open_room.set(Some(room_id));
+                        nav.push(Route::Chat {});
+                    }
+                    Err(e) => {
+                        error.set(Some(e));
+                        joining.set(false);
+                    }
+                }
+            });
+        }
+    });
+
+    use_hook({
+        let state = state.clone();
+        let code = code.clone();
+        move || {
+            if !signed_in {
+                return;
+            }
+            if storage::take_pending_invite().as_deref() == Some(code.as_str()) {
+                join.call(());
+                return;
+            }
+            spawn(async move {
+                match state.api.preview_invite(&code).await {
+                    Ok(invite) => preview.set(Some(invite)),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    });
+
+    // Log in or register first, then come back here to join
+    let sign_in_then_join = {
+        let code = code.clone();
+        move |route: Route| {
+            let here = Route::Invite { code: code.clone() }.to_string();
+            storage::save_return_to(&here, None);
+            storage::save_pending_invite(&code);
+            nav.push(route);
+        }
+    };
+    let to_login = sign_in_then_join.clone();
+    let to_register = sign_in_then_join;
+
+    rsx! {
+        div {
+            class: "flex items-center justify-center min-h-screen bg-gray-900",
+            div {
+                class: "w-full max-w-md p-8 bg-gray-800 rounded-lg shadow-lg text-center",
+                p { class: "text-gray-400 text-sm mb-2", "TOR Chat" }
+
+                if let Some(err) = error() {
+                    h1 { class: "text-2xl font-bold text-white mb-4", "Can't join this room" }
+                    div {
+                        class: "bg-red-900 border border-red-700 text-red-200 px-4 py-3 rounded mb-4",
+                        "{err}"
+                    }
+                    Link {
+                        to: Route::Chat {},
+                        class: "text-purple-500 hover:text-purple-400",
+                        "Go to your rooms"
+                    }
+                } else if !signed_in {
+                    h1 { class: "text-2xl font-bold text-white mb-2", "You've been invited to a room" }
+                    p {
+                        class: "text-gray-400 mb-6",
+                        "Log in or create an account on this server to join it."
+                    }
+                    button {
+                        class: "w-full bg-purple-600 hover:bg-purple-700 text-white font-bold py-3 px-4 rounded-lg transition duration-200 mb-3",
+                        onclick: move |_| to_login(Route::Login {}),
+                        "Log in to join"
+                    }
+                    button {
+                        class: "w-full bg-gray-700 hover:bg-gray-600 text-white font-bold py-3 px-4 rounded-lg transition duration-200",
+                        onclick: move |_| to_register(Route::Register {}),
+                        "Create an account"
+                    }
+                } else if let Some(invite) = preview() {
+                    h1 { class: "text-2xl font-bold text-white mb-2", "Join {invite.room_name}" }
+                    if let Some(description) = invite.description.as_deref().filter(|d| !d.trim().is_empty()) {
+                        p { class: "text-gray-300 mb-2 whitespace-pre-wrap", "{description}" }
+                    }
+                    p {
+                        class: "text-gray-400 text-sm mb-6",
+                        if invite.member_count == 1 { "1 member" } else { "{invite.member_count} members" }
+                    }
+                    button {
+                        class: "w-full bg-purple-600 hover:bg-purple-700 text-white font-bold py-3 px-4 rounded-lg transition duration-200",
+                        disabled: joining(),
+                        onclick: move |_| join.call(()),
+                        if joining() { "Joining..." } else { "Join room" }
+                    }
+                } else {
+                    div { class: "animate-spin rounded-full h-12 w-12 border-b-2 border-purple-500 mx-auto" }
+                }
+            }
+        }
+    }
+}
@@ -33,6 +33,11 @@ pub fn Login() -> Element {
                 Ok(response) => {
                     if let Some(token) = response.get("token").and_then(|t| t.as_str()) {
                         storage::save_token(token);
+                        if let Some(refresh_token) =
+                            response.get("refreshToken").and_then(|t| t.as_str())
+                        {
+                            storage::save_refresh_token(refresh_token);
+                        }
 
                         if let Some(user_data) = response.get("user") {
                             if let Ok(user) = serde_json::from_value(user_data.clone()) {
@@ -47,7 +52,18 @@ pub fn Login() -> Element {
                             socket.connect(&token_owned).await;
                         });
 
-                        nav.push(Route::Chat {});
+                        // Back to where an expired session left off
+                        let (route, room_id) = match storage::take_return_to() {
+                            Some((path, room_id)) => {
+                                (path.parse::<Route>().unwrap_or(Route::Chat {}), room_id)
+                            }
+                            None => (Route::Chat {}, None),
+                        };
+                        if room_id.is_some() {
+                            let mut open_room = state.admin_view_room;
+                            open_room.set(room_id);
+                        }
+                        nav.push(route);
                     } else {
                         error.set(Some("Login succeeded but no token received".to_string()));
                     }
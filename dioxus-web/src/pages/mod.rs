@@ -1,9 +1,11 @@
 mod admin;
 mod chat;
+mod invite;
 mod login;
 mod register;
 
 pub use admin::Admin;
 pub use chat::Chat;
+pub use invite::Invite;
 pub use login::Login;
 pub use register::Register;
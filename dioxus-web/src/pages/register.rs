@@ -1,15 +1,39 @@
-use crate::{models::RegisterRequest, state::AppState, Route};
+use crate::{
+    models::{QueueStatus, RegisterRequest},
+    state::AppState,
+    Route,
+};
 use dioxus::prelude::*;
 
+/// How often a queued registration checks whether it has been activated
+const QUEUE_POLL_MS: u32 = 10_000;
+
 #[component]
 pub fn Register() -> Element {
     let _state = use_context::<AppState>();
     let nav = navigator();
     let mut username = use_signal(String::new);
     let mut password = use_signal(String::new);
+    let mut email = use_signal(String::new);
     let mut error = use_signal(|| None::<String>);
     let mut success = use_signal(|| false);
     let mut loading = use_signal(|| false);
+    // Set while the account waits in the server's registration queue
+    let mut queue_status = use_signal(|| None::<QueueStatus>);
+
+    // Older servers have no /api/server-info; treat that as "no email"
+    let api_client = _state.api.clone();
+    let server_info = use_resource(move || {
+        let api = api_client.clone();
+        async move { api.get_server_info().await.unwrap_or_default() }
+    });
+    let email_supported = move || {
+        server_info
+            .read()
+            .as_ref()
+            .map(|info| info.features.email)
+            .unwrap_or(false)
+    };
 
     let on_submit = move |e: Event<FormData>| {
         e.prevent_default();
@@ -20,6 +44,7 @@ pub fn Register() -> Element {
 
             let u = username();
             let p = password();
+            let m = email().trim().to_string();
 
             if u.len() < 3 {
                 error.set(Some("Username must be at least 3 characters".to_string()));
@@ -37,9 +62,32 @@ pub fn Register() -> Element {
                 username: u,
                 password: p,
                 display_name: None,
+                email: (email_supported() && !m.is_empty()).then_some(m),
             };
 
             match state.api.register(req).await {
+                Ok(response) if response["queued"].as_bool() == Some(true) => {
+                    let token = response["queueToken"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string();
+                    queue_status.set(serde_json::from_value(response["status"].clone()).ok());
+                    loading.set(false);
+
+                    // Poll until the account is activated
+                    loop {
+                        gloo_timers::future::TimeoutFuture::new(QUEUE_POLL_MS).await;
+                        match state.api.get_registration_status(&token).await {
+                            Ok(status) if status.activated => break,
+                            Ok(status) => queue_status.set(Some(status)),
+                            Err(e) => tracing::warn!("Queue status check failed: {}", e),
+                        }
+                    }
+                    queue_status.set(None);
+                    success.set(true);
+                    gloo_timers::future::TimeoutFuture::new(1000).await;
+                    nav.push(Route::Login {});
+                }
                 Ok(_response) => {
                     success.set(true);
                     // Navigate to login after short delay so user sees success message
@@ -80,6 +128,22 @@ pub fn Register() -> Element {
                     }
                 }
 
+                if let Some(status) = queue_status() {
+                    {
+                        let minutes = status.estimated_wait_seconds.div_ceil(60).max(1);
+                        rsx! {
+                            div {
+                                class: "bg-blue-900 border border-blue-700 text-blue-200 px-4 py-3 rounded mb-4",
+                                p { class: "font-semibold", "Registration accepted \u{2014} you're #{status.position} in line" }
+                                p {
+                                    class: "text-sm",
+                                    "Many people are signing up right now. Your account will be activated in about {minutes} min; keep this page open."
+                                }
+                            }
+                        }
+                    }
+                }
+
                 if let Some(err) = error() {
                     div {
                         class: "bg-red-900 border border-red-700 text-red-200 px-4 py-3 rounded mb-4",
@@ -104,6 +168,23 @@ pub fn Register() -> Element {
                         }
                     }
 
+                    if email_supported() {
+                        div {
+                            class: "mb-4",
+                            label {
+                                class: "block text-gray-300 text-sm font-bold mb-2",
+                                "Email (optional)"
+                            }
+                            input {
+                                r#type: "email",
+                                class: "w-full px-3 py-2 bg-gray-700 border border-gray-600 rounded-lg text-white focus:outline-none focus:border-purple-500",
+                                placeholder: "you@example.com",
+                                value: "{email}",
+                                oninput: move |e| email.set(e.value().clone()),
+                            }
+                        }
+                    }
+
                     div {
                         class: "mb-6",
                         label {
@@ -1,29 +1,153 @@
+use futures::lock::Mutex as AsyncMutex;
 use futures::{SinkExt, StreamExt};
 use gloo_net::websocket::{futures::WebSocket, Message as WsMessage};
 use serde_json::Value;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use wasm_bindgen_futures::spawn_local;
 
 type WsSink = futures::stream::SplitSink<WebSocket, WsMessage>;
+type WsStream = futures::stream::SplitStream<WebSocket>;
 type EventCallback = Box<dyn Fn(&str, Value)>;
+type LostCallback = Box<dyn Fn(&str)>;
 
+/// Separates Engine.IO packets in a polling payload
+const RECORD_SEPARATOR: char = '\u{1e}';
+/// How long an upgrade waits for the in-flight poll to finish
+const UPGRADE_DRAIN_MS: u32 = 5_000;
+
+/// Engine.IO HTTP long-polling session (`transport=polling`)
+#[derive(Clone)]
+struct Polling {
+    client: reqwest::Client,
+    /// Base polling URL including the session id
+    url: String,
+    /// Engine.IO rejects overlapping POSTs on one session
+    send_lock: Rc<AsyncMutex<()>>,
+}
+
+impl Polling {
+    /// Cache-busting URL: some proxies cache identical GETs
+    fn request_url(url: &str) -> String {
+        format!("{}&t={}", url, js_sys::Date::now() as u64)
+    }
+
+    /// Open a session. Returns it with the transports the server offers as upgrades.
+    async fn handshake(base_url: &str) -> Result<(Self, Vec<String>), String> {
+        let client = reqwest::Client::new();
+        let url = format!("{}/socket.io/?EIO=4&transport=polling", base_url);
+        let body = client
+            .get(Self::request_url(&url))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // 0{"sid":"...","upgrades":["websocket"],...}
+        let open = body
+            .split(RECORD_SEPARATOR)
+            .find_map(|p| p.strip_prefix('0'))
+            .ok_or_else(|| format!("Unexpected handshake: {}", body))?;
+        let open: Value = serde_json::from_str(open).map_err(|e| e.to_string())?;
+        let sid = open["sid"].as_str().ok_or("Handshake without sid")?;
+        let upgrades = open["upgrades"]
+            .as_array()
+            .map(|u| {
+                u.iter()
+                    .filter_map(|t| t.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok((
+            Self {
+                client,
+                url: format!("{}&sid={}", url, sid),
+                send_lock: Rc::new(AsyncMutex::new(())),
+            },
+            upgrades,
+        ))
+    }
+
+    /// Long-poll for the next batch of packets
+    async fn poll(&self) -> Result<Vec<String>, String> {
+        let response = self
+            .client
+            .get(Self::request_url(&self.url))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("Poll failed: {}", response.status()));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        Ok(body
+            .split(RECORD_SEPARATOR)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    async fn send(&self, packet: String) -> Result<(), String> {
+        let _guard = self.send_lock.lock().await;
+        let response = self
+            .client
+            .post(Self::request_url(&self.url))
+            .header("Content-Type", "text/plain;charset=UTF-8")
+            .body(packet)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("Send failed: {}", response.status()))
+        }
+    }
+}
+
+/// Current way packets go out: polling until the WebSocket upgrade succeeds
+enum Writer {
+    Polling(Polling),
+    WebSocket(WsSink),
+}
+
+/// Socket.IO client over Engine.IO v4. Connects with HTTP long-polling, which
+/// works through gateways and Tor Browser settings that block WebSockets,
+/// then upgrades to a WebSocket in the background when possible.
 pub struct SocketClient {
-    sink: Rc<RefCell<Option<WsSink>>>,
+    writer: Rc<RefCell<Option<Writer>>>,
     base_url: String,
+    /// Socket.IO namespace, "/" unless created with `with_namespace`
+    namespace: String,
     connected: Rc<RefCell<bool>>,
     token: Rc<RefCell<Option<String>>>,
     event_handler: Rc<RefCell<Option<EventCallback>>>,
+    lost_handler: Rc<RefCell<Option<LostCallback>>>,
+    /// Set once an upgrade starts, so the poll loop stops issuing requests
+    upgrading: Rc<Cell<bool>>,
+    /// A long-poll request is in flight
+    polling_active: Rc<Cell<bool>>,
 }
 
 impl SocketClient {
     pub fn new(base_url: String) -> Self {
+        Self::with_namespace(base_url, "/")
+    }
+
+    pub fn with_namespace(base_url: String, namespace: &str) -> Self {
         Self {
-            sink: Rc::new(RefCell::new(None)),
+            writer: Rc::new(RefCell::new(None)),
             base_url,
+            namespace: namespace.to_string(),
             connected: Rc::new(RefCell::new(false)),
             token: Rc::new(RefCell::new(None)),
             event_handler: Rc::new(RefCell::new(None)),
+            lost_handler: Rc::new(RefCell::new(None)),
+            upgrading: Rc::new(Cell::new(false)),
+            polling_active: Rc::new(Cell::new(false)),
         }
     }
 
@@ -33,6 +157,49 @@ impl SocketClient {
         *self.event_handler.borrow_mut() = Some(Box::new(handler));
     }
 
+    /// Register a callback for when connecting fails or an open connection
+    /// drops, with the reason. Not called after `disconnect`.
+    pub fn set_lost_handler(&self, handler: impl Fn(&str) + 'static) {
+        *self.lost_handler.borrow_mut() = Some(Box::new(handler));
+    }
+
+    fn report_lost(&self, reason: &str) {
+        if let Some(handler) = self.lost_handler.borrow().as_ref() {
+            handler(reason);
+        }
+    }
+
+    /// Mark an open connection as dropped, telling the lost handler once
+    fn connection_lost(&self, reason: &str) {
+        let was_connected = std::mem::replace(&mut *self.connected.borrow_mut(), false);
+        if was_connected {
+            self.report_lost(reason);
+        }
+    }
+
+    fn clone_handles(&self) -> Self {
+        Self {
+            writer: self.writer.clone(),
+            base_url: self.base_url.clone(),
+            namespace: self.namespace.clone(),
+            connected: self.connected.clone(),
+            token: self.token.clone(),
+            event_handler: self.event_handler.clone(),
+            lost_handler: self.lost_handler.clone(),
+            upgrading: self.upgrading.clone(),
+            polling_active: self.polling_active.clone(),
+        }
+    }
+
+    /// Socket.IO packet header: the type, plus the namespace unless it's "/"
+    fn header(&self, packet_type: &str) -> String {
+        if self.namespace == "/" {
+            packet_type.to_string()
+        } else {
+            format!("{}{},", packet_type, self.namespace)
+        }
+    }
+
     pub async fn connect(&self, token: &str) {
         // Don't reconnect if already connected
         if *self.connected.borrow() {
@@ -42,122 +209,149 @@ impl SocketClient {
 
         // Store token for authentication
         *self.token.borrow_mut() = Some(token.to_string());
+        self.upgrading.set(false);
 
-        let ws_url = self
-            .base_url
-            .replace("http://", "ws://")
-            .replace("https://", "wss://");
+        tracing::info!("Connecting to Socket.IO (polling): {}", self.base_url);
+        let (polling, upgrades) = match Polling::handshake(&self.base_url).await {
+            Ok(session) => session,
+            Err(e) => {
+                tracing::error!("Engine.IO handshake failed: {}", e);
+                self.report_lost(&e);
+                return;
+            }
+        };
 
-        // Socket.IO uses this URL format for WebSocket transport
-        let socket_url = format!("{}/socket.io/?EIO=4&transport=websocket", ws_url);
-
-        tracing::info!("Connecting to Socket.IO: {}", socket_url);
-
-        match WebSocket::open(&socket_url) {
-            Ok(ws) => {
-                tracing::info!("WebSocket opened, waiting for Engine.IO handshake...");
-
-                let (mut write, mut read) = ws.split();
-
-                // Wait for Engine.IO open packet (0{...})
-                if let Some(msg) = read.next().await {
-                    match msg {
-                        Ok(WsMessage::Text(text)) => {
-                            tracing::info!("Received Engine.IO message: {}", text);
-
-                            if text.starts_with('0') {
-                                // Send Socket.IO connect packet (40)
-                                if let Err(e) = write.send(WsMessage::Text("40".to_string())).await
-                                {
-                                    tracing::error!("Failed to send connect packet: {:?}", e);
-                                    return;
-                                }
-
-                                // Wait for connect acknowledgment
-                                if let Some(ack) = read.next().await {
-                                    match ack {
-                                        Ok(WsMessage::Text(ack_text)) => {
-                                            if ack_text.starts_with("40") {
-                                                *self.connected.borrow_mut() = true;
-                                                tracing::info!("Socket.IO connected!");
-
-                                                // Store the write half for sending
-                                                *self.sink.borrow_mut() = Some(write);
-
-                                                // Send authentication event
-                                                let auth_data = serde_json::json!({"token": token});
-                                                self.emit_internal("authenticate", auth_data).await;
-
-                                                // Spawn background task to handle pings and events
-                                                let connected = self.connected.clone();
-                                                let sink = self.sink.clone();
-                                                let handler = self.event_handler.clone();
-                                                spawn_local(async move {
-                                                    Self::read_loop(read, connected, sink, handler)
-                                                        .await;
-                                                });
-                                            }
-                                        }
-                                        Ok(_) => {
-                                            tracing::warn!("Unexpected message during handshake");
-                                        }
-                                        Err(e) => {
-                                            tracing::error!("Error receiving ack: {:?}", e);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Ok(_) => {
-                            tracing::warn!("Expected text message during handshake");
-                        }
-                        Err(e) => {
-                            tracing::error!("WebSocket error during handshake: {:?}", e);
-                        }
+        // Socket.IO connect (40), acknowledged with 40{"sid":...}
+        let connect = self.header("40");
+        if let Err(e) = polling.send(connect.clone()).await {
+            tracing::error!("Failed to send connect packet: {}", e);
+            self.report_lost(&e);
+            return;
+        }
+        *self.writer.borrow_mut() = Some(Writer::Polling(polling.clone()));
+
+        let mut acknowledged = false;
+        while !acknowledged {
+            let packets = match polling.poll().await {
+                Ok(packets) => packets,
+                Err(e) => {
+                    tracing::error!("Error waiting for connect ack: {}", e);
+                    *self.writer.borrow_mut() = None;
+                    self.report_lost(&e);
+                    return;
+                }
+            };
+            for packet in packets {
+                if packet.starts_with(&connect) {
+                    acknowledged = true;
+                } else {
+                    self.handle_packet(&packet).await;
+                }
+            }
+        }
+
+        *self.connected.borrow_mut() = true;
+        tracing::info!("Socket.IO connected!");
+
+        // Send authentication event
+        let auth_data = serde_json::json!({"token": token});
+        self.emit_internal("authenticate", auth_data).await;
+
+        let client = self.clone_handles();
+        let poll_session = polling.clone();
+        spawn_local(async move { client.poll_loop(poll_session).await });
+
+        if upgrades.iter().any(|t| t == "websocket") {
+            let client = self.clone_handles();
+            spawn_local(async move { client.upgrade(polling).await });
+        }
+    }
+
+    async fn poll_loop(self, polling: Polling) {
+        while *self.connected.borrow() && !self.upgrading.get() {
+            self.polling_active.set(true);
+            let result = polling.poll().await;
+            self.polling_active.set(false);
+
+            match result {
+                Ok(packets) => {
+                    for packet in packets {
+                        self.handle_packet(&packet).await;
                     }
                 }
+                Err(e) => {
+                    tracing::error!("Polling error: {}", e);
+                    if !self.upgrading.get() {
+                        self.connection_lost(&e);
+                    }
+                    break;
+                }
             }
+        }
+        tracing::debug!("Polling loop ended");
+    }
+
+    /// Engine.IO upgrade: probe a WebSocket on the same session, let the
+    /// in-flight poll drain, then switch writes and reads to the WebSocket.
+    /// On any failure the session simply stays on polling.
+    async fn upgrade(self, polling: Polling) {
+        let ws_url = polling
+            .url
+            .replace("transport=polling", "transport=websocket")
+            .replace("http://", "ws://")
+            .replace("https://", "wss://");
+
+        let ws = match WebSocket::open(&ws_url) {
+            Ok(ws) => ws,
             Err(e) => {
-                tracing::error!("Failed to open WebSocket: {:?}", e);
+                tracing::info!("WebSocket unavailable, staying on polling: {:?}", e);
+                return;
+            }
+        };
+        let (mut write, mut read) = ws.split();
+
+        if write
+            .send(WsMessage::Text("2probe".to_string()))
+            .await
+            .is_err()
+        {
+            tracing::info!("WebSocket probe failed, staying on polling");
+            return;
+        }
+        match read.next().await {
+            Some(Ok(WsMessage::Text(text))) if text == "3probe" => {}
+            _ => {
+                tracing::info!("WebSocket probe unanswered, staying on polling");
+                return;
             }
         }
+
+        // Stop polling and wait for the outstanding request; the server
+        // answers it with a noop once the upgrade starts
+        self.upgrading.set(true);
+        let mut waited = 0;
+        while self.polling_active.get() && waited < UPGRADE_DRAIN_MS {
+            gloo_timers::future::TimeoutFuture::new(50).await;
+            waited += 50;
+        }
+
+        if write.send(WsMessage::Text("5".to_string())).await.is_err() {
+            tracing::warn!("WebSocket upgrade failed, resuming polling");
+            self.upgrading.set(false);
+            let client = self.clone_handles();
+            spawn_local(async move { client.poll_loop(polling).await });
+            return;
+        }
+
+        *self.writer.borrow_mut() = Some(Writer::WebSocket(write));
+        tracing::info!("Socket.IO upgraded to WebSocket");
+        self.read_loop(read).await;
     }
 
-    async fn read_loop(
-        mut read: futures::stream::SplitStream<WebSocket>,
-        connected: Rc<RefCell<bool>>,
-        sink: Rc<RefCell<Option<WsSink>>>,
-        event_handler: Rc<RefCell<Option<EventCallback>>>,
-    ) {
+    async fn read_loop(self, mut read: WsStream) {
         while let Some(msg) = read.next().await {
             match msg {
-                Ok(WsMessage::Text(text)) => {
-                    if text == "2" {
-                        // Engine.IO ping -> respond with pong
-                        let writer = sink.borrow_mut().take();
-                        if let Some(mut w) = writer {
-                            let _ = w.send(WsMessage::Text("3".to_string())).await;
-                            *sink.borrow_mut() = Some(w);
-                        }
-                    } else if let Some(json_str) = text.strip_prefix("42") {
-                        // Socket.IO event: 42["event_name", payload]
-                        if let Ok(arr) = serde_json::from_str::<Vec<Value>>(json_str) {
-                            if arr.len() >= 2 {
-                                if let Some(event_name) = arr[0].as_str() {
-                                    let payload = arr[1].clone();
-                                    tracing::debug!(
-                                        "Received event: {} payload: {}",
-                                        event_name,
-                                        payload
-                                    );
-                                    if let Some(handler) = event_handler.borrow().as_ref() {
-                                        handler(event_name, payload);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+                Ok(WsMessage::Text(text)) => self.handle_packet(&text).await,
                 Ok(WsMessage::Bytes(_)) => {}
                 Err(e) => {
                     tracing::error!("WebSocket read error: {:?}", e);
@@ -166,24 +360,60 @@ impl SocketClient {
             }
         }
         tracing::warn!("WebSocket read loop ended, marking disconnected");
-        *connected.borrow_mut() = false;
+        self.connection_lost("WebSocket closed");
     }
 
-    async fn emit_internal(&self, event: &str, data: Value) {
-        let writer = self.sink.borrow_mut().take();
-        if let Some(mut w) = writer {
-            let msg = format!("42{}", serde_json::json!([event, data]));
-            tracing::info!("Emitting: {}", msg);
+    /// Handle one Engine.IO packet from either transport
+    async fn handle_packet(&self, packet: &str) {
+        if packet == "2" {
+            // Engine.IO ping -> respond with pong
+            self.send_packet("3".to_string()).await;
+        } else if packet == "1" || packet.starts_with("41") {
+            tracing::warn!("Server closed the Socket.IO session");
+            self.connection_lost("Server closed the session");
+        } else if let Some(json_str) = packet.strip_prefix(&self.header("42")) {
+            // Socket.IO event: 42["event_name", payload]
+            if let Ok(arr) = serde_json::from_str::<Vec<Value>>(json_str) {
+                if arr.len() >= 2 {
+                    if let Some(event_name) = arr[0].as_str() {
+                        let payload = arr[1].clone();
+                        tracing::debug!("Received event: {} payload: {}", event_name, payload);
+                        if let Some(handler) = self.event_handler.borrow().as_ref() {
+                            handler(event_name, payload);
+                        }
+                    }
+                }
+            }
+        }
+        // "6" (noop) and anything else needs no action
+    }
 
-            if let Err(e) = w.send(WsMessage::Text(msg)).await {
-                tracing::error!("Failed to send message: {:?}", e);
+    /// Send one Engine.IO packet over the current transport
+    async fn send_packet(&self, packet: String) {
+        let writer = self.writer.borrow_mut().take();
+        match writer {
+            Some(Writer::WebSocket(mut w)) => {
+                if let Err(e) = w.send(WsMessage::Text(packet)).await {
+                    tracing::error!("Failed to send message: {:?}", e);
+                }
+                *self.writer.borrow_mut() = Some(Writer::WebSocket(w));
             }
-            *self.sink.borrow_mut() = Some(w);
-        } else {
-            tracing::warn!("Cannot emit '{}': WebSocket not connected", event);
+            Some(Writer::Polling(p)) => {
+                *self.writer.borrow_mut() = Some(Writer::Polling(p.clone()));
+                if let Err(e) = p.send(packet).await {
+                    tracing::error!("Failed to send message: {}", e);
+                }
+            }
+            None => tracing::warn!("Cannot send packet: socket not connected"),
         }
     }
 
+    async fn emit_internal(&self, event: &str, data: Value) {
+        let msg = format!("{}{}", self.header("42"), serde_json::json!([event, data]));
+        tracing::info!("Emitting: {}", msg);
+        self.send_packet(msg).await;
+    }
+
     pub async fn emit(&self, event: &str, data: Value) {
         if !*self.connected.borrow() {
             tracing::warn!("Socket not connected, cannot emit '{}'", event);
@@ -230,8 +460,11 @@ impl SocketClient {
     }
 
     pub async fn disconnect(&self) {
+        if *self.connected.borrow() {
+            self.send_packet(self.header("41")).await;
+        }
         *self.connected.borrow_mut() = false;
-        *self.sink.borrow_mut() = None;
+        *self.writer.borrow_mut() = None;
         *self.token.borrow_mut() = None;
     }
 }
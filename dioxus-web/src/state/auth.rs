@@ -1,5 +1,10 @@
 use crate::api::ApiClient;
 use crate::utils::storage;
+use dioxus::prelude::*;
+
+/// Set when the session is over for good: a 401 the refresh token couldn't
+/// fix. `SessionGuard` reacts by going to the login page.
+pub static SESSION_EXPIRED: GlobalSignal<bool> = Signal::global(|| false);
 
 pub async fn verify_token(token: &str) -> Result<(), String> {
     storage::save_token(token);
@@ -9,7 +14,13 @@ pub async fn verify_token(token: &str) -> Result<(), String> {
 }
 
 pub async fn logout() {
-    storage::remove_token();
     let api = ApiClient::new();
     let _ = api.logout().await;
+    storage::remove_token();
+}
+
+/// Drop the dead session and have the app ask for a login
+pub fn expire_session() {
+    storage::remove_token();
+    *SESSION_EXPIRED.write() = true;
 }
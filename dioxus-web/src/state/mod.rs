@@ -1,11 +1,15 @@
 pub mod auth;
+pub mod toasts;
 
 use crate::api::ApiClient;
-use crate::models::{Message, Room, User};
+use crate::models::{Digest, Message, Room, RoomRequest, UnreadCount, User};
 use crate::socket::SocketClient;
+use chrono::{DateTime, Utc};
 use dioxus::prelude::*;
 use std::rc::Rc;
 use std::sync::Arc;
+use tor_chat_common::{BuildInfo, KeyChange, KeyCheck, KnownKeys, MessageWindow};
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -14,10 +18,38 @@ pub struct AppState {
     pub current_user: Signal<Option<User>>,
     pub rooms: Signal<Vec<Room>>,
     pub messages: Signal<Vec<Message>>,
+    /// Rendered slice of `messages`, so long histories don't slow the page
+    pub message_window: Signal<MessageWindow>,
     pub current_room: Signal<Option<Room>>,
     pub authenticated: Signal<bool>,
-    /// Set by admin panel "View" button to auto-open a room in chat
+    /// Set by admin panel "View" button, or by logging in again after the
+    /// session expired, to auto-open a room in chat
     pub admin_view_room: Signal<Option<String>>,
+    /// Text-only mode: no avatars, embeds, or automatic media loading
+    pub low_bandwidth: Signal<bool>,
+    /// The server has a translation backend configured
+    pub translation_enabled: Signal<bool>,
+    /// Banner text while the server is read-only for maintenance
+    pub maintenance: Signal<Option<String>>,
+    /// Build the server reports, to compare with ours
+    pub server_build: Signal<Option<BuildInfo>>,
+    /// Latest activity digest not yet dismissed
+    pub digest: Signal<Option<Digest>>,
+    /// Adds to private rooms waiting for the user to accept
+    pub room_requests: Signal<Vec<RoomRequest>>,
+    /// A message to open, as (room, message): set by message links, replies
+    /// and pins, and acted on by the chat page
+    pub jump_to: Signal<Option<(Uuid, Uuid)>>,
+    /// Last message we sent `mark_read` for, so scrolling doesn't resend it
+    pub last_marked_read: Signal<Option<Uuid>>,
+    /// Other users' public keys as first seen in this browser
+    pub known_keys: Signal<KnownKeys>,
+    /// Key changes the user hasn't acknowledged yet
+    pub key_changes: Signal<Vec<KeyChange>>,
+    /// Members of the open room, to tell which key changes concern it
+    pub room_member_ids: Signal<Vec<Uuid>>,
+    /// Errors and retry offers shown by `ToastHost`, oldest first
+    pub toasts: Signal<Vec<toasts::Toast>>,
 }
 
 impl AppState {
@@ -34,9 +66,22 @@ impl AppState {
             current_user: Signal::new(None),
             rooms: Signal::new(Vec::new()),
             messages: Signal::new(Vec::new()),
+            message_window: Signal::new(MessageWindow::latest()),
             current_room: Signal::new(None),
             authenticated: Signal::new(false),
             admin_view_room: Signal::new(None),
+            low_bandwidth: Signal::new(crate::utils::storage::low_bandwidth()),
+            translation_enabled: Signal::new(false),
+            maintenance: Signal::new(None),
+            server_build: Signal::new(None),
+            digest: Signal::new(None),
+            room_requests: Signal::new(Vec::new()),
+            jump_to: Signal::new(None),
+            last_marked_read: Signal::new(None),
+            known_keys: Signal::new(KnownKeys::default()),
+            key_changes: Signal::new(Vec::new()),
+            room_member_ids: Signal::new(Vec::new()),
+            toasts: Signal::new(Vec::new()),
         }
     }
 
@@ -49,14 +94,253 @@ impl AppState {
 
     pub async fn load_messages(&self, room_id: &str) -> Result<(), String> {
         let messages = self.api.get_room_messages(room_id, 50, 0).await?;
+        self.check_keys(messages.iter().filter_map(|m| m.user.as_ref()));
+        self.follow_latest();
         let mut messages_sig = self.messages;
         messages_sig.set(messages);
+        self.load_member_keys(room_id).await;
         Ok(())
     }
 
+    /// Load the messages around `message_id` instead of the latest ones
+    pub async fn load_message_context(
+        &self,
+        room_id: &str,
+        message_id: &str,
+    ) -> Result<(), String> {
+        let messages = self
+            .api
+            .get_message_context(room_id, message_id, 25)
+            .await?;
+        self.check_keys(messages.iter().filter_map(|m| m.user.as_ref()));
+        self.follow_latest();
+        let mut messages_sig = self.messages;
+        messages_sig.set(messages);
+        self.load_member_keys(room_id).await;
+        Ok(())
+    }
+
+    /// Render the newest messages, and new ones as they arrive
+    pub fn follow_latest(&self) {
+        let mut window = self.message_window;
+        window.set(MessageWindow::latest());
+    }
+
+    /// Move the rendered slice so a loaded message is in it; false if the
+    /// message isn't loaded
+    pub fn reveal_message(&self, message_id: Uuid) -> bool {
+        let messages = self.messages.peek();
+        let Some(index) = messages.iter().position(|m| m.id == message_id) else {
+            return false;
+        };
+        let mut window = self.message_window;
+        let moved = window.peek().around(index, messages.len());
+        window.set(moved);
+        true
+    }
+
+    /// Ask the chat page to open a message, loading it if needed
+    pub fn open_message(&self, room_id: Uuid, message_id: Uuid) {
+        let mut jump_to = self.jump_to;
+        jump_to.set(Some((room_id, message_id)));
+    }
+
+    async fn load_member_keys(&self, room_id: &str) {
+        // Members who haven't written recently can still have changed keys
+        let mut member_ids = self.room_member_ids;
+        member_ids.set(Vec::new());
+        match self.api.get_room_members(room_id).await {
+            Ok(members) => {
+                let users: Vec<User> = members
+                    .iter()
+                    .filter_map(|m| serde_json::from_value(m["user"].clone()).ok())
+                    .collect();
+                self.check_keys(&users);
+                member_ids.set(users.iter().map(|u| u.id).collect());
+            }
+            Err(e) => tracing::warn!("Failed to load members for key check: {}", e),
+        }
+    }
+
+    /// Compare other users' keys with the ones remembered in this browser
+    pub fn check_keys<'a>(&self, users: impl IntoIterator<Item = &'a User>) {
+        for user in users {
+            if let Some(key) = &user.public_key {
+                self.check_key(user.id, &user.username, key);
+            }
+        }
+    }
+
+    /// A key seen for the first time is remembered; a different one is
+    /// queued as a warning until the user accepts it
+    pub fn check_key(&self, user_id: Uuid, username: &str, public_key: &str) {
+        let Some(me) = self.current_user.peek().as_ref().map(|u| u.id) else {
+            return;
+        };
+        if user_id == me {
+            return;
+        }
+        let mut known = self.known_keys;
+        let check = known
+            .write()
+            .observe(&user_id.to_string(), username, public_key);
+        match check {
+            KeyCheck::New => crate::utils::storage::save_known_keys(&me.to_string(), &known.peek()),
+            KeyCheck::Unchanged => {}
+            KeyCheck::Changed(change) => {
+                let mut changes = self.key_changes;
+                let mut changes = changes.write();
+                // Only the newest change per user matters
+                changes.retain(|c| c.user_id != change.user_id);
+                changes.push(change);
+            }
+        }
+    }
+
+    /// Unacknowledged key changes of people in the open room
+    pub fn room_key_changes(&self) -> Vec<KeyChange> {
+        let members = self.room_member_ids.read();
+        let messages = self.messages.read();
+        self.key_changes
+            .read()
+            .iter()
+            .filter(|c| {
+                members.iter().any(|id| id.to_string() == c.user_id)
+                    || messages.iter().any(|m| m.user_id.to_string() == c.user_id)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The user checked the new key (e.g. compared fingerprints): trust it
+    pub fn accept_key_change(&self, change: &KeyChange) {
+        let Some(me) = self.current_user.peek().as_ref().map(|u| u.id) else {
+            return;
+        };
+        let mut known = self.known_keys;
+        known.write().accept(change);
+        crate::utils::storage::save_known_keys(&me.to_string(), &known.peek());
+        let mut changes = self.key_changes;
+        changes.write().retain(|c| c.user_id != change.user_id);
+    }
+
+    /// Sync sidebar badges with the server's unread counts. The open room is
+    /// left alone since it's being read.
+    pub async fn refresh_unread(&self) -> Result<Vec<UnreadCount>, String> {
+        let counts = self.api.get_unread_counts().await?;
+        let current = self.current_room.peek().as_ref().map(|r| r.id);
+        let mut rooms_sig = self.rooms;
+        for room in rooms_sig.write().iter_mut() {
+            if Some(room.id) == current {
+                continue;
+            }
+            room.unread_count = counts
+                .iter()
+                .find(|c| c.room_id == room.id)
+                .map_or(0, |c| c.unread_count);
+        }
+        Ok(counts)
+    }
+
+    /// Apply edited room settings (from the API or a `room_updated` event)
+    /// to the room list and the open room, keeping client-only fields
+    pub fn apply_room_update(&self, updated: &Room) {
+        let mut rooms_sig = self.rooms;
+        let mut room_sig = self.current_room;
+        let apply = |room: &mut Room| {
+            room.name = updated.name.clone();
+            room.description = updated.description.clone();
+            room.max_members = updated.max_members;
+            room.member_count = updated.member_count;
+            room.encryption_mode = updated.encryption_mode.clone();
+            room.key_epoch = updated.key_epoch;
+            room.e2ee_since = updated.e2ee_since;
+        };
+        if let Some(room) = rooms_sig.write().iter_mut().find(|r| r.id == updated.id) {
+            apply(room);
+        }
+        if let Some(room) = room_sig.write().as_mut().filter(|r| r.id == updated.id) {
+            apply(room);
+        }
+    }
+
+    /// A room switched to E2EE (`room_encryption_changed`): update the room
+    /// and label the loaded history as legacy plaintext
+    pub fn apply_e2ee_switch(&self, room_id: Uuid, key_epoch: i32, since: Option<DateTime<Utc>>) {
+        let mut rooms_sig = self.rooms;
+        let mut room_sig = self.current_room;
+        let mut messages_sig = self.messages;
+        let apply = |room: &mut Room| {
+            room.encryption_mode = "e2ee".to_string();
+            room.key_epoch = key_epoch;
+            room.e2ee_since = since;
+        };
+        if let Some(room) = rooms_sig.write().iter_mut().find(|r| r.id == room_id) {
+            apply(room);
+        }
+        if let Some(room) = room_sig.write().as_mut().filter(|r| r.id == room_id) {
+            apply(room);
+        }
+        for msg in messages_sig
+            .write()
+            .iter_mut()
+            .filter(|m| m.room_id == room_id && m.key_epoch.is_none())
+        {
+            msg.legacy_plaintext = true;
+        }
+    }
+
+    /// Send `mark_read` for the newest message of the open room once it's
+    /// actually on screen: tab visible and the list scrolled to the end
+    pub fn mark_read_if_visible(&self) {
+        let Some(room_id) = self.current_room.peek().as_ref().map(|r| r.id) else {
+            return;
+        };
+        let latest = self
+            .messages
+            .peek()
+            .iter()
+            .rev()
+            .find(|m| m.room_id == room_id && m.send_state.is_none())
+            .map(|m| m.id);
+        let Some(latest) = latest else {
+            return;
+        };
+        if *self.last_marked_read.peek() == Some(latest)
+            || !self.message_window.peek().is_latest()
+            || !crate::utils::page_visible()
+            || !crate::utils::is_scrolled_to_bottom("messages-container")
+        {
+            return;
+        }
+
+        let mut marked = self.last_marked_read;
+        marked.set(Some(latest));
+        let socket = self.socket.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            socket
+                .emit(
+                    "mark_read",
+                    serde_json::json!({
+                        "roomId": room_id.to_string(),
+                        "messageId": latest.to_string(),
+                    }),
+                )
+                .await;
+        });
+    }
+
+    pub fn set_low_bandwidth(&self, enabled: bool) {
+        let mut sig = self.low_bandwidth;
+        sig.set(enabled);
+        crate::utils::storage::set_low_bandwidth(enabled);
+    }
+
     pub fn set_current_user(&self, user: User) {
         let mut user_sig = self.current_user;
         let mut auth_sig = self.authenticated;
+        let mut known = self.known_keys;
+        known.set(crate::utils::storage::get_known_keys(&user.id.to_string()));
         user_sig.set(Some(user));
         auth_sig.set(true);
     }
@@ -68,12 +352,24 @@ impl AppState {
         let mut messages_sig = self.messages;
         let mut room_sig = self.current_room;
         let mut admin_view = self.admin_view_room;
+        let mut digest = self.digest;
+        let mut room_requests = self.room_requests;
+        let mut marked = self.last_marked_read;
+        let mut known = self.known_keys;
+        let mut key_changes = self.key_changes;
+        let mut member_ids = self.room_member_ids;
         user_sig.set(None);
         auth_sig.set(false);
         rooms_sig.set(Vec::new());
         messages_sig.set(Vec::new());
         room_sig.set(None);
         admin_view.set(None);
+        digest.set(None);
+        room_requests.set(Vec::new());
+        marked.set(None);
+        known.set(KnownKeys::default());
+        key_changes.set(Vec::new());
+        member_ids.set(Vec::new());
         self.socket.disconnect().await;
         crate::utils::storage::remove_token();
     }
@@ -0,0 +1,75 @@
+use super::AppState;
+use dioxus::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen_futures::spawn_local;
+
+/// Toasts shown at once; older ones make room for new ones
+const MAX_TOASTS: usize = 4;
+/// How long a plain error stays up
+const TOAST_MS: u32 = 6_000;
+/// Toasts with an action stay longer, so there's time to use it
+const ACTION_TOAST_MS: u32 = 12_000;
+
+thread_local! {
+    static NEXT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A short-lived notice in the corner of the screen, for failures that
+/// would otherwise only reach the console
+#[derive(Clone)]
+pub struct Toast {
+    pub id: u64,
+    pub text: String,
+    /// Button label and what it does, e.g. ("Retry", resend)
+    pub action: Option<(String, Rc<dyn Fn()>)>,
+}
+
+impl AppState {
+    /// Show an error that goes away by itself
+    pub fn toast_error(&self, text: impl Into<String>) {
+        self.push_toast(text.into(), None);
+    }
+
+    /// Show an error with a button, such as "Send failed — Retry". The
+    /// toast closes when the button is used.
+    pub fn toast_action(&self, text: impl Into<String>, label: &str, action: impl Fn() + 'static) {
+        self.push_toast(text.into(), Some((label.to_string(), Rc::new(action))));
+    }
+
+    pub fn dismiss_toast(&self, id: u64) {
+        let mut toasts = self.toasts;
+        toasts.write().retain(|t| t.id != id);
+    }
+
+    fn push_toast(&self, text: String, action: Option<(String, Rc<dyn Fn()>)>) {
+        let id = NEXT_ID.with(|next| {
+            next.set(next.get() + 1);
+            next.get()
+        });
+        let lifetime = if action.is_some() {
+            ACTION_TOAST_MS
+        } else {
+            TOAST_MS
+        };
+
+        let mut toasts = self.toasts;
+        {
+            let mut list = toasts.write();
+            // The same failure repeating (e.g. every poll over a dead
+            // circuit) shows once, with the newest action
+            list.retain(|t| t.text != text);
+            list.push(Toast { id, text, action });
+            let excess = list.len().saturating_sub(MAX_TOASTS);
+            list.drain(..excess);
+        }
+
+        // Not a Dioxus task: toasts are also raised from socket callbacks,
+        // which run outside any component
+        let state = self.clone();
+        spawn_local(async move {
+            gloo_timers::future::TimeoutFuture::new(lifetime).await;
+            state.dismiss_toast(id);
+        });
+    }
+}
@@ -12,10 +12,10 @@ pub fn format_date(dt: &DateTime<Utc>) -> String {
     dt.format("%Y-%m-%d %H:%M").to_string()
 }
 
-pub fn format_date_separator(dt: &DateTime<Utc>) -> String {
+/// "Today", "Yesterday" or the date; `now` is the server's, like `dt`
+pub fn format_date_separator(dt: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
     let local = dt.with_timezone(&Local);
-    let now = Local::now();
-    let today = now.date_naive();
+    let today = now.with_timezone(&Local).date_naive();
     let msg_date = local.date_naive();
 
     if msg_date == today {
@@ -34,6 +34,40 @@ pub fn format_full_timestamp(dt: &DateTime<Utc>) -> String {
     local.format("%B %d, %Y at %H:%M").to_string()
 }
 
+/// Human-readable byte count ("512 B", "3.4 MB", "1.2 GB")
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Compact duration with the two largest units ("45s", "4m 10s", "1d 3h")
+pub fn format_duration(seconds: i64) -> String {
+    let (days, hours, minutes, secs) = (
+        seconds / 86400,
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+    );
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) if secs == 0 => format!("{}m", minutes),
+        (0, 0, _) => format!("{}m {}s", minutes, secs),
+        (0, _, 0) => format!("{}h", hours),
+        (0, _, _) => format!("{}h {}m", hours, minutes),
+        (_, 0, _) => format!("{}d", days),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
 pub fn scroll_to_bottom(container_id: &str) {
     if let Some(window) = web_sys::window() {
         if let Some(document) = window.document() {
@@ -45,6 +79,41 @@ pub fn scroll_to_bottom(container_id: &str) {
     }
 }
 
+/// Primary subtag of the browser's UI language ("pt-BR" -> "pt"), used as
+/// the translation target
+pub fn browser_language() -> String {
+    web_sys::window()
+        .and_then(|w| w.navigator().language())
+        .and_then(|lang| lang.split('-').next().map(str::to_lowercase))
+        .filter(|lang| !lang.is_empty())
+        .unwrap_or_else(|| "en".to_string())
+}
+
+/// Whether the container is scrolled (within a few pixels) to its end
+pub fn is_scrolled_to_bottom(container_id: &str) -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(container_id))
+        .is_some_and(|el| el.scroll_top() + el.client_height() >= el.scroll_height() - 40)
+}
+
+/// Cryptographically secure random bytes from the browser
+pub fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut buf = [0u8; N];
+    web_sys::window()
+        .and_then(|w| w.crypto().ok())
+        .and_then(|c| c.get_random_values_with_u8_array(&mut buf).ok())
+        .expect("crypto.getRandomValues is unavailable");
+    buf
+}
+
+/// False while the tab is in the background
+pub fn page_visible() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .is_some_and(|d| !d.hidden())
+}
+
 pub fn scroll_to_message(msg_id: &str) {
     if let Some(window) = web_sys::window() {
         if let Some(document) = window.document() {
@@ -77,3 +146,78 @@ pub fn highlight_message(msg_id: &str) {
         }
     }
 }
+
+/// The (room, message) in a `?room=<id>&message=<id>` query of the page URL
+pub fn message_link_from_url() -> Option<(uuid::Uuid, uuid::Uuid)> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let param = |key: &str| {
+        search
+            .trim_start_matches('?')
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, v)| uuid::Uuid::parse_str(v).ok())
+    };
+    Some((param("room")?, param("message")?))
+}
+
+/// Put `text` on the clipboard. Browsers only allow it in a secure context
+/// (https, localhost, or an onion address in Tor Browser); elsewhere this
+/// does nothing.
+pub fn copy_to_clipboard(text: &str) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.navigator().clipboard().write_text(text);
+    }
+}
+
+/// How well `query` matches `name` for quick search: its characters must
+/// appear in order but not necessarily together ("gnrl" finds "general").
+/// Runs of adjacent characters and word starts score higher, and shorter
+/// names win ties. None when it doesn't match.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut last: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let at = from + name[from..].iter().position(|&c| c == q)?;
+        score += 1;
+        if last.is_some_and(|l| l + 1 == at) {
+            score += 5;
+        }
+        if at == 0 || !name[at - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last = Some(at);
+        from = at + 1;
+    }
+    Some(score * 4 - name.len() as i32)
+}
+
+/// A keydown handler on the whole page, removed again when dropped
+pub struct KeyListener {
+    closure: wasm_bindgen::closure::Closure<dyn FnMut(web_sys::KeyboardEvent)>,
+}
+
+impl KeyListener {
+    pub fn new(handler: impl FnMut(web_sys::KeyboardEvent) + 'static) -> Option<Self> {
+        let document = web_sys::window()?.document()?;
+        let closure =
+            wasm_bindgen::closure::Closure::<dyn FnMut(web_sys::KeyboardEvent)>::new(handler);
+        document
+            .add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref())
+            .ok()?;
+        Some(Self { closure })
+    }
+}
+
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+            let _ = document.remove_event_listener_with_callback(
+                "keydown",
+                self.closure.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
@@ -1,7 +1,16 @@
-use gloo_storage::{LocalStorage, Storage};
+use gloo_storage::{LocalStorage, SessionStorage, Storage};
+use tor_chat_common::{IdentityKeys, KnownKeys};
 
 const TOKEN_KEY: &str = "auth_token";
+const REFRESH_TOKEN_KEY: &str = "refresh_token";
+const RETURN_TO_KEY: &str = "return_to";
+const PENDING_INVITE_KEY: &str = "pending_invite";
 const SERVER_URL_KEY: &str = "server_url";
+const ONION_PROMPT_DISMISSED_KEY: &str = "onion_prompt_dismissed";
+const LOW_BANDWIDTH_KEY: &str = "low_bandwidth";
+const DIGEST_DISMISSED_KEY: &str = "digest_dismissed";
+const IDENTITY_KEY_PREFIX: &str = "identity_keys:";
+const KNOWN_KEYS_PREFIX: &str = "known_keys:";
 
 pub fn save_token(token: &str) {
     let _ = LocalStorage::set(TOKEN_KEY, token);
@@ -11,8 +20,42 @@ pub fn get_token() -> Option<String> {
     LocalStorage::get(TOKEN_KEY).ok()
 }
 
+/// Forget the session: the access token and the refresh token with it
 pub fn remove_token() {
     LocalStorage::delete(TOKEN_KEY);
+    LocalStorage::delete(REFRESH_TOKEN_KEY);
+}
+
+pub fn save_refresh_token(token: &str) {
+    let _ = LocalStorage::set(REFRESH_TOKEN_KEY, token);
+}
+
+pub fn get_refresh_token() -> Option<String> {
+    LocalStorage::get(REFRESH_TOKEN_KEY).ok()
+}
+
+/// Where the user was when their session expired (path and open room), to
+/// go back there after logging in again. Per tab, like the place itself.
+pub fn save_return_to(path: &str, room_id: Option<&str>) {
+    let _ = SessionStorage::set(RETURN_TO_KEY, (path, room_id));
+}
+
+pub fn take_return_to() -> Option<(String, Option<String>)> {
+    let place = SessionStorage::get(RETURN_TO_KEY).ok();
+    SessionStorage::delete(RETURN_TO_KEY);
+    place
+}
+
+/// Invite the user chose to join before logging in or registering, accepted
+/// without asking again once they're back on its page. Per tab.
+pub fn save_pending_invite(code: &str) {
+    let _ = SessionStorage::set(PENDING_INVITE_KEY, code);
+}
+
+pub fn take_pending_invite() -> Option<String> {
+    let code = SessionStorage::get(PENDING_INVITE_KEY).ok();
+    SessionStorage::delete(PENDING_INVITE_KEY);
+    code
 }
 
 pub fn save_server_url(url: &str) {
@@ -22,3 +65,46 @@ pub fn save_server_url(url: &str) {
 pub fn get_server_url() -> Option<String> {
     LocalStorage::get(SERVER_URL_KEY).ok()
 }
+
+pub fn dismiss_onion_prompt() {
+    let _ = LocalStorage::set(ONION_PROMPT_DISMISSED_KEY, true);
+}
+
+pub fn onion_prompt_dismissed() -> bool {
+    LocalStorage::get(ONION_PROMPT_DISMISSED_KEY).unwrap_or(false)
+}
+
+pub fn set_low_bandwidth(enabled: bool) {
+    let _ = LocalStorage::set(LOW_BANDWIDTH_KEY, enabled);
+}
+
+pub fn low_bandwidth() -> bool {
+    LocalStorage::get(LOW_BANDWIDTH_KEY).unwrap_or(false)
+}
+
+/// Id of the last digest the user closed, so it isn't shown again
+pub fn dismiss_digest(id: &str) {
+    let _ = LocalStorage::set(DIGEST_DISMISSED_KEY, id);
+}
+
+pub fn digest_dismissed(id: &str) -> bool {
+    LocalStorage::get::<String>(DIGEST_DISMISSED_KEY).is_ok_and(|d| d == id)
+}
+
+/// The user's identity keypair on this browser, per account
+pub fn save_identity(user_id: &str, keys: &IdentityKeys) {
+    let _ = LocalStorage::set(format!("{}{}", IDENTITY_KEY_PREFIX, user_id), keys);
+}
+
+pub fn get_identity(user_id: &str) -> Option<IdentityKeys> {
+    LocalStorage::get(format!("{}{}", IDENTITY_KEY_PREFIX, user_id)).ok()
+}
+
+/// Other users' public keys as first seen by this account in this browser
+pub fn save_known_keys(user_id: &str, keys: &KnownKeys) {
+    let _ = LocalStorage::set(format!("{}{}", KNOWN_KEYS_PREFIX, user_id), keys);
+}
+
+pub fn get_known_keys(user_id: &str) -> KnownKeys {
+    LocalStorage::get(format!("{}{}", KNOWN_KEYS_PREFIX, user_id)).unwrap_or_default()
+}
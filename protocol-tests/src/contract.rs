@@ -0,0 +1,90 @@
+//! Shape checks for the JSON the server sends. Clients deserialize these
+//! payloads with camelCase field names; a key that silently turns into
+//! snake_case (or disappears) breaks them without any compile error.
+
+use serde_json::Value;
+
+/// Assert that `value` is an object containing every key in `keys`
+pub fn assert_keys(what: &str, value: &Value, keys: &[&str]) {
+    let obj = value
+        .as_object()
+        .unwrap_or_else(|| panic!("{}: expected an object, got {}", what, value));
+    for key in keys {
+        assert!(
+            obj.contains_key(*key),
+            "{}: missing key `{}` in {}",
+            what,
+            key,
+            value
+        );
+    }
+}
+
+/// Assert that no key anywhere in `value` uses snake_case. The only
+/// exceptions are map-like objects whose keys are data, such as `reactions`
+/// (emoji -> user IDs).
+pub fn assert_camel_case(what: &str, value: &Value) {
+    match value {
+        Value::Object(obj) => {
+            for (key, child) in obj {
+                assert!(
+                    !key.contains('_'),
+                    "{}: snake_case key `{}` in {}",
+                    what,
+                    key,
+                    value
+                );
+                if key != "reactions" && key != "metadata" {
+                    assert_camel_case(what, child);
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| assert_camel_case(what, item)),
+        _ => {}
+    }
+}
+
+/// Keys every user object must carry (REST and socket)
+pub const USER_KEYS: &[&str] = &["id", "username", "displayName", "isAdmin", "createdAt"];
+
+/// Keys of a room as returned to a member
+pub const ROOM_KEYS: &[&str] = &[
+    "id",
+    "name",
+    "description",
+    "type",
+    "roomKey",
+    "creatorId",
+    "isPublic",
+    "createdAt",
+    "maxMembers",
+    "memberCount",
+    "allowedMessageTypes",
+    "allowedUploadTypes",
+    "historyGateMinutes",
+];
+
+/// Keys of a message from `GET /messages`, the REST send and `new_message`
+pub const MESSAGE_KEYS: &[&str] = &[
+    "id",
+    "roomId",
+    "userId",
+    "content",
+    "messageType",
+    "replyTo",
+    "reactions",
+    "metadata",
+    "createdAt",
+    "user",
+];
+
+/// Check a message payload: shape, casing and that the shared decoder reads it
+pub fn assert_message(what: &str, message: &Value) {
+    assert_keys(what, message, MESSAGE_KEYS);
+    assert_camel_case(what, message);
+    assert_keys(
+        &format!("{}.user", what),
+        &message["user"],
+        &["id", "username"],
+    );
+}
@@ -0,0 +1,41 @@
+//! Harness for the protocol conformance tests in `tests/`.
+//!
+//! The tests talk to a running server over plain HTTP and Socket.IO and treat
+//! every JSON key the clients read as part of the contract. They are skipped
+//! unless `TOR_CHAT_TEST_URL` points at a server, e.g.
+//! `TOR_CHAT_TEST_URL=http://localhost:3000 cargo test`.
+
+pub mod contract;
+pub mod rest;
+pub mod socket;
+
+pub use rest::RestClient;
+pub use socket::TestSocket;
+
+/// Base URL of the server under test, or `None` when the suite should be skipped
+pub fn server_url() -> Option<String> {
+    std::env::var("TOR_CHAT_TEST_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .map(|url| url.trim_end_matches('/').to_string())
+}
+
+/// Resolve the server URL or return early from the test with a note
+#[macro_export]
+macro_rules! require_server {
+    () => {
+        match $crate::server_url() {
+            Some(url) => url,
+            None => {
+                eprintln!("TOR_CHAT_TEST_URL not set, skipping");
+                return;
+            }
+        }
+    };
+}
+
+/// Username that won't collide with other runs against the same database
+pub fn unique_name(prefix: &str) -> String {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    format!("{}_{}", prefix, &id[..12])
+}
@@ -0,0 +1,177 @@
+use crate::contract;
+use reqwest::{Client, Method, StatusCode};
+use serde_json::{json, Value};
+
+/// Minimal authenticated REST client. It returns raw JSON on purpose: the
+/// tests assert on the wire format, not on any client's structs.
+pub struct RestClient {
+    http: Client,
+    base_url: String,
+    pub token: String,
+    pub refresh_token: String,
+    pub user: Value,
+}
+
+impl RestClient {
+    /// Register a fresh account and keep its token
+    pub async fn register(base_url: &str, username: &str) -> Self {
+        let http = Client::new();
+        let (status, body) = send(
+            http.post(format!("{}/api/auth/register", base_url))
+                .json(&json!({ "username": username, "password": "conformance-pass-1" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "register failed: {}", body);
+
+        Self::from_auth(http, base_url, body)
+    }
+
+    /// Log in to an existing account
+    pub async fn login(base_url: &str, username: &str, password: &str) -> Self {
+        let http = Client::new();
+        let (status, body) = send(
+            http.post(format!("{}/api/auth/login", base_url))
+                .json(&json!({ "username": username, "password": password })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "login failed: {}", body);
+
+        Self::from_auth(http, base_url, body)
+    }
+
+    /// Start a guest session; None while the server has guest access off or
+    /// is turning guests away (rate limit, registration queue, guest cap)
+    pub async fn guest(base_url: &str, display_name: &str) -> Option<Self> {
+        let http = Client::new();
+        let (status, body) = send(
+            http.post(format!("{}/api/auth/guest", base_url))
+                .json(&json!({ "displayName": display_name })),
+        )
+        .await;
+        if matches!(
+            status,
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS
+        ) {
+            return None;
+        }
+        assert_eq!(status, StatusCode::CREATED, "guest failed: {}", body);
+        contract::assert_keys(
+            "guest session",
+            &body,
+            &["token", "expiresAt", "user", "roomIds", "canPost"],
+        );
+        contract::assert_camel_case("guest user", &body["user"]);
+
+        Some(Self {
+            http,
+            base_url: base_url.to_string(),
+            token: body["token"].as_str().unwrap_or_default().to_string(),
+            refresh_token: String::new(),
+            user: body["user"].clone(),
+        })
+    }
+
+    fn from_auth(http: Client, base_url: &str, body: Value) -> Self {
+        contract::assert_keys("auth response", &body, &["token", "refreshToken", "user"]);
+        contract::assert_keys("auth user", &body["user"], contract::USER_KEYS);
+        contract::assert_camel_case("auth user", &body["user"]);
+
+        Self {
+            http,
+            base_url: base_url.to_string(),
+            token: body["token"].as_str().unwrap_or_default().to_string(),
+            refresh_token: body["refreshToken"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            user: body["user"].clone(),
+        }
+    }
+
+    /// Spend the refresh token at /api/auth/refresh
+    pub async fn refresh(&self) -> (StatusCode, Value) {
+        send(
+            self.http
+                .post(format!("{}/api/auth/refresh", self.base_url))
+                .json(&json!({ "refreshToken": self.refresh_token })),
+        )
+        .await
+    }
+
+    /// The same account authenticating with another bearer token, such as
+    /// an API token
+    pub fn with_token(&self, token: &str) -> Self {
+        Self {
+            http: self.http.clone(),
+            base_url: self.base_url.clone(),
+            token: token.to_string(),
+            refresh_token: String::new(),
+            user: self.user.clone(),
+        }
+    }
+
+    pub fn user_id(&self) -> &str {
+        self.user["id"].as_str().unwrap_or_default()
+    }
+
+    pub async fn call(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut req = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path))
+            .bearer_auth(&self.token);
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        send(req).await
+    }
+
+    /// Call and require a 2xx, returning the body
+    pub async fn ok(&self, method: Method, path: &str, body: Option<Value>) -> Value {
+        let (status, body) = self.call(method.clone(), path, body).await;
+        assert!(
+            status.is_success(),
+            "{} {} returned {}: {}",
+            method,
+            path,
+            status,
+            body
+        );
+        body
+    }
+
+    pub async fn create_room(&self, name: &str, is_public: bool) -> Value {
+        let body = self
+            .ok(
+                Method::POST,
+                "/api/rooms",
+                Some(json!({ "name": name, "description": "conformance", "isPublic": is_public })),
+            )
+            .await;
+        contract::assert_keys("create room", &body["room"], contract::ROOM_KEYS);
+        contract::assert_camel_case("create room", &body["room"]);
+        body["room"].clone()
+    }
+
+    pub async fn messages(&self, room_id: &str) -> Vec<Value> {
+        let body = self
+            .ok(
+                Method::GET,
+                &format!("/api/rooms/{}/messages?limit=50", room_id),
+                None,
+            )
+            .await;
+        body["messages"].as_array().cloned().unwrap_or_default()
+    }
+}
+
+async fn send(req: reqwest::RequestBuilder) -> (StatusCode, Value) {
+    let response = req.send().await.expect("server unreachable");
+    let status = response.status();
+    let body = response.json().await.unwrap_or(Value::Null);
+    (status, body)
+}
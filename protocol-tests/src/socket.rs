@@ -0,0 +1,97 @@
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{tungstenite, MaybeTlsStream, WebSocketStream};
+
+/// How long to wait for an expected event before failing the test
+const EVENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Socket.IO client speaking the raw Engine.IO v4 framing over a websocket
+pub struct TestSocket {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl TestSocket {
+    /// Connect and authenticate, waiting for the `authenticated` event
+    pub async fn connect(base_url: &str, token: &str) -> Self {
+        let url = format!(
+            "{}/socket.io/?EIO=4&transport=websocket",
+            base_url
+                .replace("http://", "ws://")
+                .replace("https://", "wss://")
+        );
+        let (ws, _) = tokio_tungstenite::connect_async(&url)
+            .await
+            .expect("websocket connect failed");
+
+        let mut socket = Self { ws };
+        // Engine.IO open, then Socket.IO connect to the default namespace
+        socket.raw("40").await;
+        socket.emit("authenticate", json!({ "token": token })).await;
+        let auth = socket.expect("authenticated").await;
+        crate::contract::assert_keys(
+            "authenticated",
+            &auth,
+            &["userId", "username", "serverTime"],
+        );
+        assert!(auth["serverTime"].is_i64(), "serverTime: {}", auth);
+        socket
+    }
+
+    async fn raw(&mut self, frame: &str) {
+        self.ws
+            .send(tungstenite::Message::Text(frame.to_string().into()))
+            .await
+            .expect("websocket send failed");
+    }
+
+    /// Disconnect, the way a client closing the app would
+    pub async fn close(mut self) {
+        self.ws.close(None).await.ok();
+    }
+
+    pub async fn emit(&mut self, event: &str, data: Value) {
+        self.raw(&format!("42{}", json!([event, data]))).await;
+    }
+
+    /// Wait for `event`, skipping any others. An `error` event fails the test
+    /// unless that is what we're waiting for.
+    pub async fn expect(&mut self, event: &str) -> Value {
+        tokio::time::timeout(EVENT_TIMEOUT, self.next_matching(event))
+            .await
+            .unwrap_or_else(|_| panic!("timed out waiting for `{}`", event))
+    }
+
+    async fn next_matching(&mut self, event: &str) -> Value {
+        loop {
+            let frame = match self.ws.next().await {
+                Some(Ok(tungstenite::Message::Text(t))) => t.to_string(),
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => panic!("websocket error: {}", e),
+                None => panic!("socket closed while waiting for `{}`", event),
+            };
+
+            if frame == "2" {
+                self.raw("3").await;
+                continue;
+            }
+
+            let Some(payload) = frame.strip_prefix("42") else {
+                continue;
+            };
+            let Ok(Value::Array(parts)) = serde_json::from_str::<Value>(payload) else {
+                continue;
+            };
+
+            let name = parts.first().and_then(|v| v.as_str()).unwrap_or_default();
+            let data = parts.get(1).cloned().unwrap_or(Value::Null);
+            if name == event {
+                return data;
+            }
+            if name == "error" {
+                panic!("server error while waiting for `{}`: {}", event, data);
+            }
+        }
+    }
+}
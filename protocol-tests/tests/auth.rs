@@ -0,0 +1,372 @@
+use reqwest::{Method, StatusCode};
+use tor_chat_protocol_tests::{contract, require_server, unique_name, RestClient};
+
+#[tokio::test]
+async fn register_login_and_me_share_the_user_shape() {
+    let url = require_server!();
+    let username = unique_name("auth");
+
+    let registered = RestClient::register(&url, &username).await;
+    assert_eq!(registered.user["username"], username.as_str());
+
+    let logged_in = RestClient::login(&url, &username, "conformance-pass-1").await;
+    assert_eq!(logged_in.user["id"], registered.user["id"]);
+
+    let me = logged_in.ok(Method::GET, "/api/auth/me", None).await;
+    contract::assert_keys("me", &me["user"], contract::USER_KEYS);
+    contract::assert_camel_case("me", &me["user"]);
+    assert_eq!(me["user"]["id"], registered.user["id"]);
+}
+
+#[tokio::test]
+async fn refresh_tokens_renew_the_session_once() {
+    let url = require_server!();
+    let client = RestClient::register(&url, &unique_name("refresh")).await;
+
+    let (status, renewed) = client.refresh().await;
+    assert_eq!(status, StatusCode::OK, "refresh failed: {}", renewed);
+    contract::assert_keys("refresh", &renewed, &["token", "refreshToken", "user"]);
+    assert_eq!(renewed["user"]["id"], client.user["id"]);
+    assert_ne!(renewed["refreshToken"], client.refresh_token.as_str());
+
+    // Spent: a second use is refused
+    let (status, _) = client.refresh().await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_tokens_are_limited_to_their_scopes() {
+    let url = require_server!();
+    let client = RestClient::register(&url, &unique_name("pat")).await;
+    let room = client.create_room(&unique_name("pat-room"), false).await;
+    let room_id = room["id"].as_str().unwrap();
+
+    let created = client
+        .ok(
+            Method::POST,
+            "/api/auth/tokens",
+            Some(serde_json::json!({ "name": "reader", "scopes": ["read"], "roomIds": [room_id] })),
+        )
+        .await;
+    contract::assert_keys("create token", &created, &["token", "apiToken"]);
+    contract::assert_camel_case("api token", &created["apiToken"]);
+    let reader = client.with_token(created["token"].as_str().unwrap());
+
+    // Reads its room, but can't post, leave it or mint more tokens
+    reader.messages(room_id).await;
+    let messages = format!("/api/rooms/{}/messages", room_id);
+    let (status, _) = reader
+        .call(
+            Method::POST,
+            &messages,
+            Some(serde_json::json!({ "content": "hi" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = reader.call(Method::GET, "/api/auth/tokens", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Listed without its secret, and gone once revoked
+    let listed = client.ok(Method::GET, "/api/auth/tokens", None).await;
+    let token_id = created["apiToken"]["id"].as_str().unwrap();
+    assert!(listed["tokens"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|t| t["id"] == token_id && t.get("token").is_none()));
+    client
+        .ok(
+            Method::DELETE,
+            &format!("/api/auth/tokens/{}", token_id),
+            None,
+        )
+        .await;
+    let (status, _) = reader.call(Method::GET, &messages, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn well_known_user_lookup_is_signed() {
+    let url = require_server!();
+    let client = RestClient::register(&url, &unique_name("fed")).await;
+    let username = client.user["username"].as_str().unwrap();
+
+    let lookup = |name: String| {
+        let url = url.clone();
+        async move {
+            let response = reqwest::get(format!("{}/.well-known/torchat/user/{}", url, name))
+                .await
+                .expect("server unreachable");
+            let status = response.status();
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            (status, body)
+        }
+    };
+
+    let (status, _) = lookup(unique_name("nobody")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = lookup(username.to_string()).await;
+    match status {
+        StatusCode::OK => {
+            contract::assert_keys("user document", &body, &["document", "signature", "key"]);
+            assert!(body["signature"].as_str().is_some_and(|s| !s.is_empty()));
+        }
+        // No onion address (clearnet-only test server) or paranoid mode
+        StatusCode::SERVICE_UNAVAILABLE | StatusCode::NOT_FOUND => {}
+        other => panic!("user lookup returned {}: {}", other, body),
+    }
+}
+
+#[tokio::test]
+async fn server_info_advertises_features_and_addresses() {
+    let url = require_server!();
+    let response = reqwest::get(format!("{}/api/server-info", url))
+        .await
+        .expect("server unreachable");
+    let signature = response.headers().get("x-torchat-signature").cloned();
+    let info: serde_json::Value = response.json().await.expect("server-info is not JSON");
+
+    contract::assert_keys(
+        "server-info",
+        &info,
+        &[
+            "name",
+            "version",
+            "build",
+            "serverTime",
+            "features",
+            "messageTypes",
+            "limits",
+            "addresses",
+            "identityKey",
+        ],
+    );
+    // Signed once the identity key is loaded, which it is after startup
+    assert!(info["identityKey"].as_str().is_some_and(|k| !k.is_empty()));
+    assert!(signature.is_some(), "server-info is not signed");
+    contract::assert_keys(
+        "server-info.build",
+        &info["build"],
+        &["version", "commit", "timestamp", "profile"],
+    );
+    let build: tor_chat_common::BuildInfo =
+        serde_json::from_value(info["build"].clone()).expect("server-info.build shape");
+    assert_eq!(build.version, info["version"].as_str().unwrap_or_default());
+    contract::assert_keys(
+        "server-info.features",
+        &info["features"],
+        &["email", "securityKeys"],
+    );
+    contract::assert_keys(
+        "server-info.limits",
+        &info["limits"],
+        &[
+            "maxFileSize",
+            "socketUploadMaxSize",
+            "defaultRoomMembers",
+            "maxRoomMembers",
+        ],
+    );
+    contract::assert_keys(
+        "server-info.addresses",
+        &info["addresses"],
+        &["clearnet", "onion"],
+    );
+}
+
+#[tokio::test]
+async fn user_activity_is_for_server_admins() {
+    let url = require_server!();
+    let mut client = RestClient::register(&url, &unique_name("nosy")).await;
+    // On a fresh server the first account is the admin
+    if client.user["isAdmin"] == true {
+        client = RestClient::register(&url, &unique_name("nosy")).await;
+    }
+    let target = RestClient::register(&url, &unique_name("target")).await;
+
+    let (status, error) = client
+        .call(
+            Method::GET,
+            &format!("/api/admin/users/{}/activity", target.user_id()),
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", error);
+}
+
+#[tokio::test]
+async fn user_directory_lists_only_public_fields() {
+    let url = require_server!();
+    let client = RestClient::register(&url, &unique_name("directory")).await;
+
+    // Private unless an admin opened it, but admins always see it
+    let (status, body) = client.call(Method::GET, "/api/auth/users", None).await;
+    if status == StatusCode::FORBIDDEN && client.user["isAdmin"] != true {
+        return;
+    }
+    assert_eq!(status, StatusCode::OK, "user list failed: {}", body);
+    contract::assert_keys("user list", &body, &["users"]);
+    contract::assert_camel_case("user list", &body["users"]);
+
+    let users = body["users"].as_array().expect("users is not an array");
+    let me = users
+        .iter()
+        .find(|u| u["id"] == client.user["id"])
+        .expect("new user missing from the directory");
+    contract::assert_keys("directory user", me, &["id", "username", "displayName"]);
+    for hidden in ["isOnline", "lastSeen", "isAdmin", "email", "createdAt"] {
+        assert!(me.get(hidden).is_none(), "directory exposes `{}`", hidden);
+    }
+}
+
+#[tokio::test]
+async fn names_with_hidden_characters_are_refused() {
+    let url = require_server!();
+    let http = reqwest::Client::new();
+
+    let response = http
+        .post(format!("{}/api/auth/register", url))
+        .json(&serde_json::json!({
+            "username": format!("{} x", unique_name("spaced")),
+            "password": "conformance-pass-1",
+        }))
+        .send()
+        .await
+        .expect("register request failed");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // A right-to-left override makes a name read differently than it's stored
+    let client = RestClient::register(&url, &unique_name("names")).await;
+    let (status, error) = client
+        .call(
+            Method::POST,
+            "/api/rooms",
+            Some(serde_json::json!({ "name": "lobby\u{202E}nimda", "isPublic": false })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", error);
+}
+
+#[tokio::test]
+async fn guest_sessions_are_opt_in_and_limited() {
+    let url = require_server!();
+    let http = reqwest::Client::new();
+
+    // The prefix is kept for guests, whatever the server's guest setting
+    let response = http
+        .post(format!("{}/api/auth/register", url))
+        .json(&serde_json::json!({
+            "username": format!("guest-{}", unique_name("x")),
+            "password": "conformance-pass-1",
+        }))
+        .send()
+        .await
+        .expect("register request failed");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Off unless an admin turned it on
+    let Some(guest) = RestClient::guest(&url, "Visitor").await else {
+        return;
+    };
+    assert!(guest.refresh_token.is_empty());
+    assert!(guest.user["guestExpiresAt"].is_string());
+    guest.ok(Method::GET, "/api/auth/me", None).await;
+
+    // Nothing beyond the designated rooms
+    let (status, _) = guest
+        .call(
+            Method::POST,
+            "/api/rooms",
+            Some(serde_json::json!({ "name": unique_name("guest-room") })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn operator_console_ignores_chat_sessions() {
+    let url = require_server!();
+    let client = RestClient::register(&url, &unique_name("operator")).await;
+    let http = reqwest::Client::new();
+
+    // Off (404) without OPERATOR_TOKEN, a login prompt (401) with it; a chat
+    // session's token, even an admin's, is never the operator token
+    for token in [None, Some(client.token.as_str())] {
+        let mut req = http.get(format!("{}/admin-console", url));
+        if let Some(token) = token {
+            req = req.bearer_auth(token);
+        }
+        let response = req.send().await.unwrap();
+        assert!(
+            matches!(
+                response.status(),
+                StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED
+            ),
+            "console answered {}",
+            response.status()
+        );
+    }
+
+    let toggled = http
+        .post(format!("{}/admin-console/maintenance", url))
+        .bearer_auth(&client.token)
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body("csrf=x&enabled=true")
+        .send()
+        .await
+        .unwrap();
+    assert!(matches!(
+        toggled.status(),
+        StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED
+    ));
+}
+
+#[tokio::test]
+async fn security_keys_start_with_none_registered() {
+    let url = require_server!();
+    let client = RestClient::register(&url, &unique_name("webauthn")).await;
+
+    let keys = client
+        .ok(Method::GET, "/api/auth/webauthn/keys", None)
+        .await;
+    contract::assert_keys(
+        "webauthn keys",
+        &keys,
+        &["enabled", "securityKeys", "requireForLogin"],
+    );
+    assert_eq!(keys["securityKeys"], serde_json::json!([]));
+    assert_eq!(keys["requireForLogin"], false);
+
+    // Nothing to require yet, and no key to log in with
+    let (status, _) = client
+        .call(
+            Method::PUT,
+            "/api/auth/webauthn/settings",
+            Some(serde_json::json!({ "requireForLogin": true })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = client
+        .call(
+            Method::POST,
+            "/api/auth/webauthn/login/start",
+            Some(serde_json::json!({ "username": client.user["username"] })),
+        )
+        .await;
+    let expected = if keys["enabled"] == true {
+        StatusCode::UNAUTHORIZED
+    } else {
+        StatusCode::FORBIDDEN
+    };
+    assert_eq!(status, expected);
+
+    // The password still signs in on its own
+    let logged_in = RestClient::login(
+        &url,
+        client.user["username"].as_str().unwrap(),
+        "conformance-pass-1",
+    )
+    .await;
+    assert_eq!(logged_in.user["id"], client.user["id"]);
+}
@@ -0,0 +1,445 @@
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+use tor_chat_common::{MessageContent, NoKeys};
+use tor_chat_protocol_tests::{contract, require_server, unique_name, RestClient, TestSocket};
+
+/// Two members of one room, both with a socket joined to it
+async fn room_with_two_members(url: &str) -> (RestClient, TestSocket, TestSocket, String) {
+    let alice = RestClient::register(url, &unique_name("alice")).await;
+    let bob = RestClient::register(url, &unique_name("bob")).await;
+
+    let room = alice.create_room(&unique_name("room"), true).await;
+    let room_id = room["id"].as_str().unwrap().to_string();
+    bob.ok(Method::POST, &format!("/api/rooms/{}/join", room_id), None)
+        .await;
+
+    let mut alice_socket = TestSocket::connect(url, &alice.token).await;
+    let mut bob_socket = TestSocket::connect(url, &bob.token).await;
+    for socket in [&mut alice_socket, &mut bob_socket] {
+        socket.emit("join_room", json!({ "roomId": room_id })).await;
+        socket.expect("joined_room").await;
+    }
+
+    (alice, alice_socket, bob_socket, room_id)
+}
+
+/// A 1x1 PNG, small enough for any SOCKET_UPLOAD_MAX_SIZE but 0
+const PIXEL_PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+const PIXEL_PNG_SIZE: usize = 70;
+
+fn decode(message: &Value) -> MessageContent {
+    MessageContent::from_json(message, &NoKeys)
+}
+
+#[tokio::test]
+async fn rest_send_is_broadcast_and_echoes_the_nonce() {
+    let url = require_server!();
+    let (alice, mut alice_socket, mut bob_socket, room_id) = room_with_two_members(&url).await;
+
+    let sent = alice
+        .ok(
+            Method::POST,
+            &format!("/api/rooms/{}/messages", room_id),
+            Some(json!({
+                "content": "hello over rest",
+                "messageType": "text",
+                "clientNonce": "nonce-rest-1",
+            })),
+        )
+        .await;
+    let sent = &sent["message"];
+    contract::assert_message("rest send", sent);
+    assert_eq!(sent["clientNonce"], "nonce-rest-1");
+    assert_eq!(
+        decode(sent),
+        MessageContent::Plaintext("hello over rest".to_string())
+    );
+
+    let broadcast = bob_socket.expect("new_message").await;
+    contract::assert_message("new_message", &broadcast);
+    assert_eq!(broadcast["id"], sent["id"]);
+    // The sender's own sockets get it like any socket send
+    let own = alice_socket.expect("new_message").await;
+    assert_eq!(own["id"], sent["id"]);
+}
+
+#[tokio::test]
+async fn member_rooms_are_joined_without_join_room() {
+    let url = require_server!();
+    let alice = RestClient::register(&url, &unique_name("alice")).await;
+    let bob = RestClient::register(&url, &unique_name("bob")).await;
+
+    // One room joined before bob's socket connects, one after
+    let before = alice.create_room(&unique_name("room"), true).await;
+    let before = before["id"].as_str().unwrap().to_string();
+    bob.ok(Method::POST, &format!("/api/rooms/{}/join", before), None)
+        .await;
+    let mut bob_socket = TestSocket::connect(&url, &bob.token).await;
+    let after = alice.create_room(&unique_name("room"), true).await;
+    let after = after["id"].as_str().unwrap().to_string();
+    bob.ok(Method::POST, &format!("/api/rooms/{}/join", after), None)
+        .await;
+
+    for room_id in [&before, &after] {
+        let sent = alice
+            .ok(
+                Method::POST,
+                &format!("/api/rooms/{}/messages", room_id),
+                Some(json!({ "content": "no join needed", "messageType": "text" })),
+            )
+            .await;
+        let received = bob_socket.expect("new_message").await;
+        assert_eq!(received["id"], sent["message"]["id"]);
+        assert_eq!(&received["roomId"], room_id.as_str());
+    }
+}
+
+#[tokio::test]
+async fn socket_send_edit_react_delete_and_sync() {
+    let url = require_server!();
+    let (alice, mut alice_socket, mut bob_socket, room_id) = room_with_two_members(&url).await;
+
+    // Send
+    alice_socket
+        .emit(
+            "send_message",
+            json!({
+                "roomId": room_id,
+                "content": "hello over socket",
+                "messageType": "text",
+                "clientNonce": "nonce-socket-1",
+            }),
+        )
+        .await;
+    let own = alice_socket.expect("new_message").await;
+    contract::assert_message("new_message (sender)", &own);
+    assert_eq!(own["clientNonce"], "nonce-socket-1");
+    let received = bob_socket.expect("new_message").await;
+    contract::assert_message("new_message (member)", &received);
+    let message_id = received["id"].clone();
+    assert_eq!(message_id, own["id"]);
+
+    // Edit
+    alice_socket
+        .emit(
+            "edit_message",
+            json!({ "messageId": message_id, "content": "edited" }),
+        )
+        .await;
+    let edited = bob_socket.expect("message_edited").await;
+    contract::assert_keys(
+        "message_edited",
+        &edited,
+        &["messageId", "content", "updatedAt", "revisionCount"],
+    );
+    assert_eq!(edited["messageId"], message_id);
+    assert_eq!(edited["revisionCount"], 1);
+
+    // Alice created the room, so she may see what it said before
+    let revisions = alice
+        .ok(
+            Method::GET,
+            &format!("/api/messages/{}/history", message_id.as_str().unwrap()),
+            None,
+        )
+        .await;
+    contract::assert_camel_case("message history", &revisions);
+    assert_eq!(revisions["revisionCount"], 1);
+    assert_eq!(revisions["revisions"][0]["content"], "hello over socket");
+
+    // Reactions
+    alice_socket
+        .emit(
+            "add_reaction",
+            json!({ "messageId": message_id, "emoji": "👍" }),
+        )
+        .await;
+    let added = bob_socket.expect("reaction_added").await;
+    contract::assert_keys(
+        "reaction_added",
+        &added,
+        &["messageId", "roomId", "userId", "emoji", "count"],
+    );
+    assert_eq!(added["userId"].as_str(), Some(alice.user_id()));
+    assert_eq!(added["count"], 1);
+
+    let counts = alice
+        .ok(
+            Method::GET,
+            &format!(
+                "/api/rooms/{}/messages/{}/reactions",
+                room_id,
+                message_id.as_str().unwrap()
+            ),
+            None,
+        )
+        .await;
+    contract::assert_camel_case("reactions", &counts);
+    assert_eq!(counts["reactions"][0]["emoji"], "👍");
+    assert_eq!(counts["reactions"][0]["count"], 1);
+    assert_eq!(
+        counts["reactions"][0]["users"][0]["id"].as_str(),
+        Some(alice.user_id())
+    );
+
+    // Sync after edit + reaction
+    let history = alice.messages(&room_id).await;
+    let synced = history
+        .iter()
+        .find(|m| m["id"] == message_id)
+        .expect("sent message missing from history");
+    contract::assert_message("history", synced);
+    assert_eq!(
+        decode(synced),
+        MessageContent::Plaintext("edited".to_string())
+    );
+    assert!(synced["updatedAt"].is_string());
+    assert!(synced["reactions"]["👍"].is_array());
+
+    alice_socket
+        .emit(
+            "remove_reaction",
+            json!({ "messageId": message_id, "emoji": "👍" }),
+        )
+        .await;
+    let removed = bob_socket.expect("reaction_removed").await;
+    contract::assert_keys(
+        "reaction_removed",
+        &removed,
+        &["messageId", "roomId", "userId", "emoji", "count"],
+    );
+    assert_eq!(removed["count"], 0);
+
+    // Delete
+    alice_socket
+        .emit("delete_message", json!({ "messageId": message_id }))
+        .await;
+    let deleted = bob_socket.expect("message_deleted").await;
+    contract::assert_keys("message_deleted", &deleted, &["messageId"]);
+    assert_eq!(deleted["messageId"], message_id);
+
+    let history = alice.messages(&room_id).await;
+    assert!(history.iter().all(|m| m["id"] != message_id));
+}
+
+#[tokio::test]
+async fn socket_send_with_inline_attachment() {
+    let url = require_server!();
+    let (alice, mut alice_socket, mut bob_socket, room_id) = room_with_two_members(&url).await;
+
+    alice_socket
+        .emit(
+            "send_message",
+            json!({
+                "roomId": room_id,
+                "attachment": {
+                    "name": "pixel.png",
+                    "mimetype": "image/png",
+                    "data": PIXEL_PNG,
+                },
+                "clientNonce": "nonce-attachment-1",
+            }),
+        )
+        .await;
+    let own = alice_socket.expect("new_message").await;
+    contract::assert_message("new_message (attachment)", &own);
+    assert_eq!(own["clientNonce"], "nonce-attachment-1");
+    assert_eq!(own["messageType"], "image");
+    assert_eq!(own["metadata"]["originalName"], "pixel.png");
+    assert_eq!(own["metadata"]["mimetype"], "image/png");
+    assert_eq!(own["metadata"]["size"], PIXEL_PNG_SIZE);
+    let path = own["content"].as_str().unwrap_or_default();
+    assert!(path.starts_with("/uploads/"), "content is {}", path);
+
+    let received = bob_socket.expect("new_message").await;
+    assert_eq!(received["id"], own["id"]);
+
+    // Stored like any upload
+    let file = reqwest::get(format!("{}{}", url, path))
+        .await
+        .expect("server unreachable");
+    assert!(
+        file.status().is_success(),
+        "{} returned {}",
+        path,
+        file.status()
+    );
+    let bytes = file.bytes().await.expect("file body");
+    assert_eq!(bytes.len(), PIXEL_PNG_SIZE);
+
+    let history = alice.messages(&room_id).await;
+    assert!(history.iter().any(|m| m["id"] == own["id"]));
+}
+
+#[tokio::test]
+async fn message_types_and_metadata_are_validated() {
+    let url = require_server!();
+    let alice = RestClient::register(&url, &unique_name("alice")).await;
+    let room = alice.create_room(&unique_name("room"), true).await;
+    let path = format!("/api/rooms/{}/messages", room["id"].as_str().unwrap());
+
+    let rejected = [
+        json!({ "content": "hi", "messageType": "bogus" }),
+        // Only the server posts system messages
+        json!({ "content": "hi", "messageType": "system" }),
+        json!({ "content": "Lunch?", "messageType": "poll", "metadata": {
+            "question": "Lunch?", "options": ["yes"],
+        } }),
+        json!({ "content": "/uploads/x.pdf", "messageType": "image", "metadata": {
+            "mimetype": "application/pdf",
+        } }),
+        json!({ "content": "hi", "messageType": "text", "metadata": "not an object" }),
+    ];
+    for body in rejected {
+        let (status, error) = alice.call(Method::POST, &path, Some(body.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} -> {}", body, error);
+    }
+
+    let poll = alice
+        .ok(
+            Method::POST,
+            &path,
+            Some(
+                json!({ "content": "Lunch?", "messageType": "poll", "metadata": {
+                "question": "Lunch?", "options": ["yes", "no"], "multiple": false,
+            } }),
+            ),
+        )
+        .await;
+    contract::assert_message("poll", &poll["message"]);
+    assert_eq!(poll["message"]["messageType"], "poll");
+    assert_eq!(poll["message"]["metadata"]["options"][1], "no");
+}
+
+#[tokio::test]
+async fn export_mine_has_only_the_callers_messages() {
+    let url = require_server!();
+    let alice = RestClient::register(&url, &unique_name("alice")).await;
+    let bob = RestClient::register(&url, &unique_name("bob")).await;
+    let carol = RestClient::register(&url, &unique_name("carol")).await;
+    let room = alice.create_room(&unique_name("room"), true).await;
+    let room_id = room["id"].as_str().unwrap();
+    bob.ok(Method::POST, &format!("/api/rooms/{}/join", room_id), None)
+        .await;
+
+    for (client, text) in [
+        (&alice, "from alice"),
+        (&bob, "from bob"),
+        (&bob, "bob again"),
+    ] {
+        client
+            .ok(
+                Method::POST,
+                &format!("/api/rooms/{}/messages", room_id),
+                Some(json!({ "content": text, "messageType": "text" })),
+            )
+            .await;
+    }
+
+    let path = format!("/api/rooms/{}/export/mine", room_id);
+    let export = bob.ok(Method::GET, &path, None).await;
+    assert_eq!(export["room"]["id"], room_id);
+    assert_eq!(export["user"]["id"], bob.user["id"]);
+    let contents: Vec<&str> = export["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["content"].as_str().unwrap())
+        .collect();
+    assert_eq!(contents, ["from bob", "bob again"]);
+
+    let (status, error) = carol.call(Method::GET, &path, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", error);
+}
+
+#[tokio::test]
+async fn message_context_surrounds_the_linked_message() {
+    let url = require_server!();
+    let alice = RestClient::register(&url, &unique_name("alice")).await;
+    let stranger = RestClient::register(&url, &unique_name("stranger")).await;
+    let room = alice.create_room(&unique_name("room"), false).await;
+    let room_id = room["id"].as_str().unwrap();
+
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        let sent = alice
+            .ok(
+                Method::POST,
+                &format!("/api/rooms/{}/messages", room_id),
+                Some(json!({ "content": format!("message {}", i), "messageType": "text" })),
+            )
+            .await;
+        ids.push(sent["message"]["id"].as_str().unwrap().to_string());
+    }
+
+    let path = format!(
+        "/api/rooms/{}/messages/{}/context?around=1",
+        room_id, ids[2]
+    );
+    let context = alice.ok(Method::GET, &path, None).await;
+    let got: Vec<&str> = context["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(got, [&ids[1], &ids[2], &ids[3]]);
+    assert_eq!(context["messageId"], ids[2].as_str());
+    assert_eq!(context["hasMoreBefore"], true);
+    assert_eq!(context["hasMoreAfter"], true);
+
+    let (status, error) = stranger.call(Method::GET, &path, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", error);
+}
+
+#[tokio::test]
+async fn search_covers_only_the_callers_rooms() {
+    let url = require_server!();
+    let alice = RestClient::register(&url, &unique_name("alice")).await;
+    let stranger = RestClient::register(&url, &unique_name("stranger")).await;
+    // One word no other test's messages contain
+    let word = unique_name("needle").replace(['-', '_'], "");
+
+    let mut rooms = Vec::new();
+    for (client, text) in [
+        (&alice, format!("first {}", word)),
+        (&alice, format!("second {} here", word)),
+        (&stranger, format!("not yours {}", word)),
+    ] {
+        let room = client.create_room(&unique_name("room"), false).await;
+        let room_id = room["id"].as_str().unwrap().to_string();
+        client
+            .ok(
+                Method::POST,
+                &format!("/api/rooms/{}/messages", room_id),
+                Some(json!({ "content": text, "messageType": "text" })),
+            )
+            .await;
+        rooms.push(room_id);
+    }
+
+    let found = alice
+        .ok(
+            Method::GET,
+            &format!("/api/search/messages?q={}", word),
+            None,
+        )
+        .await;
+    let groups = found["rooms"].as_array().unwrap();
+    let room_ids: Vec<&str> = groups
+        .iter()
+        .map(|g| g["roomId"].as_str().unwrap())
+        .collect();
+    // Newest match first
+    assert_eq!(room_ids, [&rooms[1], &rooms[0]]);
+    assert_eq!(
+        groups[0]["messages"][0]["content"],
+        format!("second {} here", word)
+    );
+    assert!(groups[0]["roomName"].is_string());
+
+    let (status, error) = alice
+        .call(Method::GET, "/api/search/messages?q=%20", None)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", error);
+}
@@ -0,0 +1,564 @@
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+use std::time::Duration;
+use tor_chat_protocol_tests::{contract, require_server, unique_name, RestClient, TestSocket};
+
+#[tokio::test]
+async fn create_join_and_list_members() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let guest = RestClient::register(&url, &unique_name("guest")).await;
+
+    let room = owner.create_room(&unique_name("room"), true).await;
+    let room_id = room["id"].as_str().unwrap();
+
+    guest
+        .ok(Method::POST, &format!("/api/rooms/{}/join", room_id), None)
+        .await;
+
+    let listed = guest.ok(Method::GET, "/api/rooms", None).await;
+    let rooms = listed["rooms"].as_array().expect("rooms is not an array");
+    let entry = rooms
+        .iter()
+        .find(|r| r["id"] == room["id"])
+        .expect("joined room missing from list");
+    contract::assert_camel_case("room list", entry);
+
+    let members = guest
+        .ok(
+            Method::GET,
+            &format!("/api/rooms/{}/members", room_id),
+            None,
+        )
+        .await;
+    let members = members["members"]
+        .as_array()
+        .expect("members is not an array");
+    for member in members {
+        contract::assert_keys(
+            "member",
+            member,
+            &[
+                "userId",
+                "role",
+                "isCreator",
+                "permissions",
+                "joinedAt",
+                "user",
+            ],
+        );
+        contract::assert_camel_case("member", member);
+    }
+    let creator = members
+        .iter()
+        .find(|m| m["userId"] == owner.user["id"])
+        .expect("owner missing from members");
+    assert_eq!(creator["isCreator"], true);
+    assert!(creator["permissions"]
+        .as_array()
+        .is_some_and(|p| !p.is_empty()));
+    let joined = members
+        .iter()
+        .find(|m| m["userId"] == guest.user["id"])
+        .expect("guest missing from members");
+    assert_eq!(joined["isCreator"], false);
+    assert_eq!(joined["permissions"], json!([]));
+}
+
+#[tokio::test]
+async fn member_list_is_revalidated_with_its_etag() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let room = owner.create_room(&unique_name("room"), true).await;
+    let members_url = format!("{}/api/rooms/{}/members", url, room["id"].as_str().unwrap());
+    let http = reqwest::Client::new();
+    let fetch = |etag: Option<String>| {
+        let mut req = http.get(&members_url).bearer_auth(&owner.token);
+        if let Some(etag) = etag {
+            req = req.header("If-None-Match", etag);
+        }
+        async move { req.send().await.expect("server unreachable") }
+    };
+
+    let first = fetch(None).await;
+    assert_eq!(first.status(), StatusCode::OK);
+    let etag = first
+        .headers()
+        .get("etag")
+        .and_then(|v| v.to_str().ok())
+        .expect("member list has no ETag")
+        .to_string();
+    assert_eq!(
+        fetch(Some(etag.clone())).await.status(),
+        StatusCode::NOT_MODIFIED
+    );
+
+    // Someone joining changes it
+    let guest = RestClient::register(&url, &unique_name("guest")).await;
+    guest
+        .ok(
+            Method::POST,
+            &format!("/api/rooms/{}/join", room["id"].as_str().unwrap()),
+            None,
+        )
+        .await;
+    assert_eq!(fetch(Some(etag)).await.status(), StatusCode::OK);
+}
+
+/// Whether the room's member list shows `user_id` online
+async fn shown_online(client: &RestClient, room_id: &str, user_id: &serde_json::Value) -> bool {
+    let members = client
+        .ok(
+            Method::GET,
+            &format!("/api/rooms/{}/members", room_id),
+            None,
+        )
+        .await;
+    members["members"]
+        .as_array()
+        .expect("members is not an array")
+        .iter()
+        .find(|m| &m["userId"] == user_id)
+        .expect("member missing")["user"]["isOnline"]
+        == true
+}
+
+#[tokio::test]
+async fn user_stays_online_until_their_last_socket_closes() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let room = owner.create_room(&unique_name("room"), false).await;
+    let room_id = room["id"].as_str().unwrap();
+    let owner_id = &owner.user["id"];
+
+    let first = TestSocket::connect(&url, &owner.token).await;
+    let second = TestSocket::connect(&url, &owner.token).await;
+    if !shown_online(&owner, room_id, owner_id).await {
+        // Paranoid mode: presence isn't tracked
+        return;
+    }
+
+    first.close().await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(
+        shown_online(&owner, room_id, owner_id).await,
+        "closing one of two sockets marked the user offline"
+    );
+
+    second.close().await;
+    for _ in 0..20 {
+        if !shown_online(&owner, room_id, owner_id).await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    panic!("user still online after their last socket closed");
+}
+
+#[tokio::test]
+async fn socket_join_room_is_acknowledged() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let room = owner.create_room(&unique_name("room"), false).await;
+
+    let mut socket = TestSocket::connect(&url, &owner.token).await;
+    socket
+        .emit("join_room", json!({ "roomId": room["id"] }))
+        .await;
+    let joined = socket.expect("joined_room").await;
+    assert_eq!(joined["roomId"], room["id"]);
+}
+
+#[tokio::test]
+async fn atom_feed_needs_its_token() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let room = owner.create_room(&unique_name("room"), true).await;
+    let room_id = room["id"].as_str().unwrap();
+
+    owner
+        .ok(
+            Method::POST,
+            &format!("/api/rooms/{}/messages", room_id),
+            Some(json!({ "content": "announcement <1>", "messageType": "text" })),
+        )
+        .await;
+    let feed = owner
+        .ok(Method::POST, &format!("/api/rooms/{}/feed", room_id), None)
+        .await;
+    contract::assert_camel_case("feed", &feed);
+    assert_eq!(feed["enabled"], true);
+
+    // Fetched without an account, like a feed reader would
+    let path = feed["path"].as_str().expect("enabled feed has no path");
+    let response = reqwest::get(format!("{}{}", url, path)).await.unwrap();
+    assert!(response.status().is_success());
+    let atom = response.text().await.unwrap();
+    assert!(atom.contains("<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+    assert!(atom.contains("announcement &lt;1&gt;"));
+
+    let wrong = format!("{}/api/rooms/{}/feed.atom?token=nope", url, room_id);
+    assert_eq!(reqwest::get(&wrong).await.unwrap().status(), 404);
+
+    owner
+        .ok(
+            Method::DELETE,
+            &format!("/api/rooms/{}/feed", room_id),
+            None,
+        )
+        .await;
+    let disabled = reqwest::get(format!("{}{}", url, path)).await.unwrap();
+    assert_eq!(disabled.status(), 404);
+}
+
+#[tokio::test]
+async fn invite_page_describes_the_room() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("host")).await;
+    let name = format!("{} <party>", unique_name("room"));
+    let room = owner.create_room(&name, false).await;
+    let invite = owner
+        .ok(
+            Method::POST,
+            &format!("/api/rooms/{}/invites", room["id"].as_str().unwrap()),
+            Some(json!({})),
+        )
+        .await;
+    let code = invite["invite"]["code"].as_str().unwrap();
+
+    // Opened in a browser, without an account
+    let response = reqwest::get(format!("{}/invite/{}", url, code))
+        .await
+        .unwrap();
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        return; // other runs used up this minute's pages
+    }
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    let html = response.text().await.unwrap();
+    assert!(html.contains("&lt;party&gt;"), "room name not escaped");
+    assert!(html.contains(code));
+    assert!(html.contains("/register"));
+
+    let missing = reqwest::get(format!("{}/invite/{}", url, unique_name("nope")))
+        .await
+        .unwrap();
+    assert!(matches!(
+        missing.status(),
+        StatusCode::NOT_FOUND | StatusCode::TOO_MANY_REQUESTS
+    ));
+}
+
+#[tokio::test]
+async fn content_policy_limits_what_members_post() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let room = owner.create_room(&unique_name("room"), true).await;
+    let room_id = room["id"].as_str().unwrap();
+    let messages = format!("/api/rooms/{}/messages", room_id);
+
+    let updated = owner
+        .ok(
+            Method::PATCH,
+            &format!("/api/rooms/{}", room_id),
+            Some(json!({
+                "allowedMessageTypes": ["text", "image", "file"],
+                "allowedUploadTypes": ["image"],
+            })),
+        )
+        .await;
+    assert_eq!(
+        updated["room"]["allowedMessageTypes"],
+        json!(["text", "image", "file"])
+    );
+    assert_eq!(updated["room"]["allowedUploadTypes"], json!(["image"]));
+
+    owner
+        .ok(
+            Method::POST,
+            &messages,
+            Some(json!({ "content": "still fine", "messageType": "text" })),
+        )
+        .await;
+    for body in [
+        json!({ "content": "/uploads/a.mp3", "messageType": "audio" }),
+        json!({ "content": "/uploads/a.pdf", "messageType": "file", "metadata": {
+            "mimetype": "application/pdf",
+        } }),
+    ] {
+        let (status, error) = owner
+            .call(Method::POST, &messages, Some(body.clone()))
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "{} -> {}", body, error);
+    }
+    owner
+        .ok(
+            Method::POST,
+            &messages,
+            Some(
+                json!({ "content": "/uploads/a.png", "messageType": "image", "metadata": {
+                "mimetype": "image/png",
+            } }),
+            ),
+        )
+        .await;
+
+    // Empty lists lift the policy
+    let cleared = owner
+        .ok(
+            Method::PATCH,
+            &format!("/api/rooms/{}", room_id),
+            Some(json!({ "allowedMessageTypes": [], "allowedUploadTypes": [] })),
+        )
+        .await;
+    assert!(cleared["room"]["allowedMessageTypes"].is_null());
+    owner
+        .ok(
+            Method::POST,
+            &messages,
+            Some(json!({ "content": "/uploads/a.mp3", "messageType": "audio" })),
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn history_gate_hides_older_messages_until_approved() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let room = owner.create_room(&unique_name("gated"), true).await;
+    let room_id = room["id"].as_str().unwrap();
+    let messages = format!("/api/rooms/{}/messages", room_id);
+
+    // 0: only a room admin's approval opens the history
+    let updated = owner
+        .ok(
+            Method::PATCH,
+            &format!("/api/rooms/{}", room_id),
+            Some(json!({ "historyGateMinutes": 0 })),
+        )
+        .await;
+    assert_eq!(updated["room"]["historyGateMinutes"], 0);
+    owner
+        .ok(
+            Method::POST,
+            &messages,
+            Some(json!({ "content": "before you came", "messageType": "text" })),
+        )
+        .await;
+
+    let newcomer = RestClient::register(&url, &unique_name("newcomer")).await;
+    newcomer
+        .ok(Method::POST, &format!("/api/rooms/{}/join", room_id), None)
+        .await;
+    let gated = newcomer.ok(Method::GET, &messages, None).await;
+    assert_eq!(gated["messages"], json!([]));
+    assert!(gated["historyHiddenBefore"].is_string());
+
+    // Members can't let themselves in
+    let approve = format!(
+        "/api/rooms/{}/members/{}/history",
+        room_id,
+        newcomer.user_id()
+    );
+    let (status, _) = newcomer.call(Method::POST, &approve, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    owner.ok(Method::POST, &approve, None).await;
+    let open = newcomer.ok(Method::GET, &messages, None).await;
+    assert_eq!(open["messages"][0]["content"], "before you came");
+    assert!(open["historyHiddenBefore"].is_null());
+}
+
+#[tokio::test]
+async fn history_gate_keeps_older_messages_from_being_forwarded() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let room = owner.create_room(&unique_name("gated"), true).await;
+    let room_id = room["id"].as_str().unwrap();
+    owner
+        .ok(
+            Method::PATCH,
+            &format!("/api/rooms/{}", room_id),
+            Some(json!({ "historyGateMinutes": 0 })),
+        )
+        .await;
+    let sent = owner
+        .ok(
+            Method::POST,
+            &format!("/api/rooms/{}/messages", room_id),
+            Some(json!({ "content": "before you came", "messageType": "text" })),
+        )
+        .await;
+    let message_id = sent["message"]["id"].as_str().unwrap();
+
+    let newcomer = RestClient::register(&url, &unique_name("newcomer")).await;
+    newcomer
+        .ok(Method::POST, &format!("/api/rooms/{}/join", room_id), None)
+        .await;
+    let own_room = newcomer.create_room(&unique_name("own"), false).await;
+    let own_room_id = own_room["id"].as_str().unwrap();
+
+    let mut socket = TestSocket::connect(&url, &newcomer.token).await;
+    socket
+        .emit(
+            "forward_message",
+            json!({ "messageId": message_id, "targetRoomId": own_room_id }),
+        )
+        .await;
+    let error = socket.expect("error").await;
+    assert_eq!(error["error"], "Message not found");
+    assert!(newcomer.messages(own_room_id).await.is_empty());
+    socket.close().await;
+}
+
+#[tokio::test]
+async fn room_mentions_are_for_room_admins() {
+    let url = require_server!();
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let guest = RestClient::register(&url, &unique_name("guest")).await;
+    let room = owner.create_room(&unique_name("room"), true).await;
+    let room_id = room["id"].as_str().unwrap();
+    let messages = format!("/api/rooms/{}/messages", room_id);
+    guest
+        .ok(Method::POST, &format!("/api/rooms/{}/join", room_id), None)
+        .await;
+    let mut guest_socket = TestSocket::connect(&url, &guest.token).await;
+
+    let (status, error) = guest
+        .call(
+            Method::POST,
+            &messages,
+            Some(json!({ "content": "@here anyone?" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", error);
+    // Only the word itself counts
+    guest
+        .ok(
+            Method::POST,
+            &messages,
+            Some(json!({ "content": "my @roommate says hi" })),
+        )
+        .await;
+
+    let sent = owner
+        .ok(
+            Method::POST,
+            &messages,
+            Some(json!({ "content": "@room meeting in five" })),
+        )
+        .await;
+    let mention = guest_socket.expect("room_mention").await;
+    contract::assert_keys(
+        "room_mention",
+        &mention,
+        &["roomId", "messageId", "scope", "from"],
+    );
+    assert_eq!(mention["roomId"], room["id"]);
+    assert_eq!(mention["messageId"], sent["message"]["id"]);
+    assert_eq!(mention["scope"], "room");
+    assert_eq!(mention["from"]["id"], owner.user["id"]);
+}
+
+#[tokio::test]
+async fn room_size_follows_server_limits() {
+    let url = require_server!();
+    let info: serde_json::Value = reqwest::get(format!("{}/api/server-info", url))
+        .await
+        .expect("server unreachable")
+        .json()
+        .await
+        .expect("server-info is not JSON");
+    let default = info["limits"]["defaultRoomMembers"].as_i64().unwrap();
+    let cap = info["limits"]["maxRoomMembers"].as_i64().unwrap();
+
+    let owner = RestClient::register(&url, &unique_name("owner")).await;
+    let guest = RestClient::register(&url, &unique_name("guest")).await;
+    let room = owner.create_room(&unique_name("room"), true).await;
+    let room_id = room["id"].as_str().unwrap();
+    assert_eq!(room["maxMembers"], default);
+    assert_eq!(room["memberCount"], 1);
+
+    let joined = guest
+        .ok(Method::POST, &format!("/api/rooms/{}/join", room_id), None)
+        .await;
+    assert_eq!(joined["room"]["memberCount"], 2);
+
+    let (status, error) = owner
+        .call(
+            Method::POST,
+            "/api/rooms",
+            Some(json!({ "name": unique_name("huge"), "isPublic": false, "maxMembers": cap + 1 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", error);
+    let (status, error) = owner
+        .call(
+            Method::PATCH,
+            &format!("/api/rooms/{}", room_id),
+            Some(json!({ "maxMembers": cap + 1 })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", error);
+}
+
+#[tokio::test]
+async fn adds_from_strangers_become_message_requests() {
+    let url = require_server!();
+    let mut alice = RestClient::register(&url, &unique_name("alice")).await;
+    // Server admins skip requests; on a fresh server the first account is one
+    if alice.user["isAdmin"] == true {
+        alice = RestClient::register(&url, &unique_name("alice")).await;
+    }
+    let carol = RestClient::register(&url, &unique_name("carol")).await;
+    let settings = carol
+        .ok(
+            Method::PUT,
+            "/api/requests/settings",
+            Some(json!({ "acceptFrom": "members" })),
+        )
+        .await;
+    assert_eq!(settings["acceptFrom"], "members");
+
+    let room = alice.create_room(&unique_name("room"), false).await;
+    let room_id = room["id"].as_str().unwrap();
+    let added = alice
+        .ok(
+            Method::POST,
+            &format!("/api/rooms/{}/members", room_id),
+            Some(json!({ "userId": carol.user_id() })),
+        )
+        .await;
+    assert_eq!(added["requested"], true);
+    let (status, _) = carol
+        .call(Method::GET, &format!("/api/rooms/{}", room_id), None)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "added before accepting");
+
+    let inbox = carol.ok(Method::GET, "/api/requests", None).await;
+    let request = inbox["requests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["roomId"] == room_id)
+        .expect("request not in the inbox");
+    assert_eq!(request["from"]["id"], alice.user["id"]);
+
+    let accepted = carol
+        .ok(
+            Method::POST,
+            &format!("/api/requests/{}/accept", room_id),
+            None,
+        )
+        .await;
+    assert_eq!(accepted["room"]["memberCount"], 2);
+    let inbox = carol.ok(Method::GET, "/api/requests", None).await;
+    assert!(inbox["requests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|r| r["roomId"] != room_id));
+}
@@ -0,0 +1,52 @@
+//! Embeds the git commit, build time and profile reported by
+//! `/api/server-info`. The time is the commit's (or SOURCE_DATE_EPOCH), not
+//! the wall clock, so building the same commit twice gives the same binary.
+//! Docker builds have no checkout and pass GIT_COMMIT and SOURCE_DATE_EPOCH.
+
+use std::env;
+use std::process::Command;
+
+fn main() {
+    let commit = env::var("GIT_COMMIT")
+        .ok()
+        .filter(|c| !c.trim().is_empty())
+        .or_else(|| git(&["rev-parse", "HEAD"]));
+    let epoch = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .or_else(|| git(&["log", "-1", "--format=%ct"]));
+
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=BUILD_GIT_COMMIT={}", commit.trim());
+    }
+    if let Some(epoch) = epoch.filter(|e| e.trim().parse::<i64>().is_ok()) {
+        println!("cargo:rustc-env=BUILD_EPOCH={}", epoch.trim());
+    }
+    println!(
+        "cargo:rustc-env=BUILD_PROFILE={}",
+        env::var("PROFILE").unwrap_or_default()
+    );
+
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        let git_dir = std::path::Path::new(git_dir.trim());
+        println!("cargo:rerun-if-changed={}", git_dir.join("HEAD").display());
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            let ref_file = git_dir.join(head_ref.trim());
+            if ref_file.exists() {
+                println!("cargo:rerun-if-changed={}", ref_file.display());
+            }
+        }
+    }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+}
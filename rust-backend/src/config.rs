@@ -1,3 +1,4 @@
+use crate::models::MessageType;
 use anyhow::Result;
 use std::env;
 use std::path::PathBuf;
@@ -9,6 +10,7 @@ pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
     pub jwt_expires_in: i64,
+    pub refresh_token_expires_in: i64,
     pub bcrypt_cost: u32,
     pub tor_enabled: bool,
     pub tor_socks_host: String,
@@ -19,7 +21,78 @@ pub struct Config {
     pub rate_limit_per_second: u64,
     pub rate_limit_burst_size: u32,
     pub max_file_size: usize,
+    /// Files up to this size may be sent inline with a socket `send_message`
+    /// instead of a separate upload request; 0 disables it
+    pub socket_upload_max_size: usize,
     pub upload_dir: PathBuf,
+    /// Message types clients may send
+    pub message_types: Vec<MessageType>,
+    pub user_directory_public: bool,
+    pub email_enabled: bool,
+    pub public_url: Option<String>,
+    pub onion_url: Option<String>,
+    /// Origin the web client is served from (e.g. http://abc...xyz.onion);
+    /// security keys are bound to its host. Security keys are off when unset.
+    pub webauthn_origin: Option<String>,
+    pub onion_location: bool,
+    pub image_proxy_max_bytes: usize,
+    pub translate_url: Option<String>,
+    pub translate_api_key: Option<String>,
+    pub spam_filter_enabled: bool,
+    pub spam_flag_threshold: f32,
+    pub spam_hide_threshold: f32,
+    /// `@room`/`@here` mentions allowed per room and hour; 0 disables them
+    pub room_mention_limit: usize,
+    /// Invite landing pages served per minute, all visitors together, until
+    /// an admin changes it; 0 turns the page off
+    pub invite_page_limit: usize,
+    /// Guest sessions started per minute, all visitors together, until an
+    /// admin changes it
+    pub guest_session_limit: usize,
+    /// `maxMembers` of rooms created without one, until an admin changes it
+    pub room_default_max_members: i32,
+    /// Largest `maxMembers` a room may have, until an admin changes it
+    pub room_max_members_cap: i32,
+    /// Rooms a non-admin may create per hour; 0 means no limit
+    pub room_create_per_hour: i64,
+    /// Rooms a non-admin may have created and not deleted; 0 means no limit
+    pub max_owned_rooms: i64,
+    /// Words refused in usernames, display names and room names and
+    /// descriptions, until an admin changes the list
+    pub blocked_words: Vec<String>,
+    pub registration_max_per_minute: i64,
+    pub registration_activation_interval: u64,
+    pub db_max_connections: u32,
+    pub db_acquire_timeout: u64,
+    pub db_statement_timeout: u64,
+    pub db_slow_query_ms: u64,
+    /// Seconds before a JSON API request is cut off with 408
+    pub request_timeout: u64,
+    /// Seconds allowed for uploads, exports and file downloads
+    pub transfer_timeout: u64,
+    /// Requests slower than this are logged with their route and user; 0
+    /// disables it
+    pub slow_request_ms: u64,
+    pub metrics_token: Option<String>,
+    /// Secret for the `/admin-console` operator page; the page is off when
+    /// unset
+    pub operator_token: Option<String>,
+    pub tor_probe_interval: u64,
+    pub tor_probe_timeout: u64,
+    pub admin_error_spike_threshold: u64,
+    pub digest_check_interval: u64,
+    pub retention_check_interval: u64,
+    pub message_expiry_check_interval: u64,
+    pub room_stats_rollup_interval: u64,
+    pub paranoid_mode: bool,
+    pub paranoid_timestamp_granularity: i64,
+    pub alert_webhook_url: Option<String>,
+    pub alert_check_interval: u64,
+    pub alert_cooldown: u64,
+    pub alert_disk_free_percent: f64,
+    pub alert_tor_down_after: u64,
+    pub alert_registration_surge: i64,
+    pub alert_spam_surge: i64,
 }
 
 impl Config {
@@ -36,6 +109,9 @@ impl Config {
             jwt_expires_in: env::var("JWT_EXPIRES_IN")
                 .unwrap_or_else(|_| "86400".to_string())
                 .parse()?,
+            refresh_token_expires_in: env::var("REFRESH_TOKEN_EXPIRES_IN")
+                .unwrap_or_else(|_| "2592000".to_string())
+                .parse()?,
             bcrypt_cost: env::var("BCRYPT_COST")
                 .unwrap_or_else(|_| "12".to_string())
                 .parse()?,
@@ -65,10 +141,173 @@ impl Config {
             max_file_size: env::var("MAX_FILE_SIZE")
                 .unwrap_or_else(|_| "1073741824".to_string())
                 .parse()?,
+            socket_upload_max_size: env::var("SOCKET_UPLOAD_MAX_SIZE")
+                .unwrap_or_else(|_| "524288".to_string())
+                .parse()?,
             upload_dir: Self::validated_upload_dir()?,
+            message_types: Self::message_types()?,
+            user_directory_public: env::var("USER_DIRECTORY_PUBLIC")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()?,
+            email_enabled: env::var("EMAIL_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()?,
+            public_url: Self::optional_url("PUBLIC_URL"),
+            onion_url: Self::optional_url("ONION_URL"),
+            webauthn_origin: Self::optional_url("WEBAUTHN_ORIGIN"),
+            onion_location: env::var("ONION_LOCATION")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()?,
+            image_proxy_max_bytes: env::var("IMAGE_PROXY_MAX_BYTES")
+                .unwrap_or_else(|_| "5242880".to_string())
+                .parse()?,
+            translate_url: Self::optional_url("TRANSLATE_URL"),
+            translate_api_key: env::var("TRANSLATE_API_KEY").ok().filter(|v| !v.is_empty()),
+            spam_filter_enabled: env::var("SPAM_FILTER_ENABLED")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()?,
+            spam_flag_threshold: env::var("SPAM_FLAG_THRESHOLD")
+                .unwrap_or_else(|_| "0.5".to_string())
+                .parse()?,
+            spam_hide_threshold: env::var("SPAM_HIDE_THRESHOLD")
+                .unwrap_or_else(|_| "0.8".to_string())
+                .parse()?,
+            room_mention_limit: env::var("ROOM_MENTION_LIMIT")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()?,
+            invite_page_limit: env::var("INVITE_PAGE_LIMIT")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()?,
+            guest_session_limit: env::var("GUEST_SESSION_LIMIT")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()?,
+            room_default_max_members: env::var("ROOM_DEFAULT_MAX_MEMBERS")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()?,
+            room_max_members_cap: env::var("ROOM_MAX_MEMBERS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()?,
+            room_create_per_hour: env::var("ROOM_CREATE_PER_HOUR")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()?,
+            max_owned_rooms: env::var("MAX_OWNED_ROOMS")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse()?,
+            blocked_words: env::var("BLOCKED_WORDS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            registration_max_per_minute: env::var("REGISTRATION_MAX_PER_MINUTE")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()?,
+            registration_activation_interval: env::var("REGISTRATION_ACTIVATION_INTERVAL")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()?,
+            db_max_connections: env::var("DB_MAX_CONNECTIONS")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse()?,
+            db_acquire_timeout: env::var("DB_ACQUIRE_TIMEOUT")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()?,
+            db_statement_timeout: env::var("DB_STATEMENT_TIMEOUT")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()?,
+            db_slow_query_ms: env::var("DB_SLOW_QUERY_MS")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()?,
+            request_timeout: env::var("REQUEST_TIMEOUT")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()?,
+            transfer_timeout: env::var("TRANSFER_TIMEOUT")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()?,
+            slow_request_ms: env::var("SLOW_REQUEST_MS")
+                .unwrap_or_else(|_| "2000".to_string())
+                .parse()?,
+            metrics_token: env::var("METRICS_TOKEN").ok().filter(|v| !v.is_empty()),
+            operator_token: env::var("OPERATOR_TOKEN").ok().filter(|v| !v.is_empty()),
+            tor_probe_interval: env::var("TOR_PROBE_INTERVAL")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()?,
+            tor_probe_timeout: env::var("TOR_PROBE_TIMEOUT")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()?,
+            admin_error_spike_threshold: env::var("ADMIN_ERROR_SPIKE_THRESHOLD")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()?,
+            digest_check_interval: env::var("DIGEST_CHECK_INTERVAL")
+                .unwrap_or_else(|_| "600".to_string())
+                .parse()?,
+            retention_check_interval: env::var("RETENTION_CHECK_INTERVAL")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()?,
+            message_expiry_check_interval: env::var("MESSAGE_EXPIRY_CHECK_INTERVAL")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()?,
+            room_stats_rollup_interval: env::var("ROOM_STATS_ROLLUP_INTERVAL")
+                .unwrap_or_else(|_| "900".to_string())
+                .parse()?,
+            paranoid_mode: env::var("PARANOID_MODE")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()?,
+            paranoid_timestamp_granularity: env::var("PARANOID_TIMESTAMP_GRANULARITY")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()?,
+            alert_webhook_url: env::var("ALERT_WEBHOOK_URL")
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty()),
+            alert_check_interval: env::var("ALERT_CHECK_INTERVAL")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()?,
+            alert_cooldown: env::var("ALERT_COOLDOWN")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()?,
+            alert_disk_free_percent: env::var("ALERT_DISK_FREE_PERCENT")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()?,
+            alert_tor_down_after: env::var("ALERT_TOR_DOWN_AFTER")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()?,
+            alert_registration_surge: env::var("ALERT_REGISTRATION_SURGE")
+                .unwrap_or_else(|_| "100".to_string())
+                .parse()?,
+            alert_spam_surge: env::var("ALERT_SPAM_SURGE")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse()?,
         })
     }
 
+    /// Read a base URL from the environment, without trailing slash; empty means unset
+    fn optional_url(key: &str) -> Option<String> {
+        env::var(key)
+            .ok()
+            .map(|v| v.trim().trim_end_matches('/').to_string())
+            .filter(|v| !v.is_empty())
+    }
+
+    /// MESSAGE_TYPES: comma-separated subset of the client message types;
+    /// unset means all of them
+    fn message_types() -> Result<Vec<MessageType>> {
+        let Ok(raw) = env::var("MESSAGE_TYPES") else {
+            return Ok(MessageType::CLIENT_TYPES.to_vec());
+        };
+        let mut types = Vec::new();
+        for name in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let message_type: MessageType = name.parse().map_err(anyhow::Error::msg)?;
+            if !MessageType::CLIENT_TYPES.contains(&message_type) {
+                anyhow::bail!("MESSAGE_TYPES: clients can't send {} messages", name);
+            }
+            types.push(message_type);
+        }
+        if !types.contains(&MessageType::Text) {
+            anyhow::bail!("MESSAGE_TYPES must include text");
+        }
+        Ok(types)
+    }
+
     fn validated_upload_dir() -> Result<PathBuf> {
         let raw = env::var("UPLOAD_DIR").unwrap_or_else(|_| "./uploads".to_string());
 
@@ -1,4 +1,61 @@
-use sqlx::PgPool;
+use crate::config::Config;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{ConnectOptions, PgPool};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Requests that gave up waiting for a pooled connection (DB_ACQUIRE_TIMEOUT)
+static ACQUIRE_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+
+pub fn record_acquire_timeout() {
+    ACQUIRE_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn acquire_timeouts() -> u64 {
+    ACQUIRE_TIMEOUTS.load(Ordering::Relaxed)
+}
+
+/// Open the connection pool with the configured sizing and timeouts.
+/// Statements slower than DB_SLOW_QUERY_MS are logged as warnings, and
+/// Postgres cancels any statement running past DB_STATEMENT_TIMEOUT.
+pub async fn connect(config: &Config) -> anyhow::Result<PgPool> {
+    let statement_timeout = format!("{}s", config.db_statement_timeout);
+    let mut options = PgConnectOptions::from_str(&config.database_url)?
+        .options([("statement_timeout", statement_timeout.as_str())]);
+    if config.db_slow_query_ms > 0 {
+        options = options.log_slow_statements(
+            log::LevelFilter::Warn,
+            Duration::from_millis(config.db_slow_query_ms),
+        );
+    }
+
+    let pool = PgPoolOptions::new()
+        .max_connections(config.db_max_connections.max(1))
+        .acquire_timeout(Duration::from_secs(config.db_acquire_timeout.max(1)))
+        .connect_with(options)
+        .await?;
+    Ok(pool)
+}
+
+/// Paranoid mode: forget activity metadata recorded while it was off
+/// (presence, last seen, read positions, edit times and history)
+pub async fn scrub_metadata(pool: &PgPool) -> anyhow::Result<()> {
+    sqlx::raw_sql(
+        r#"
+        UPDATE users SET is_online = false, last_seen = NULL, updated_at = NOW()
+         WHERE is_online OR last_seen IS NOT NULL;
+        UPDATE room_members SET last_read_message_id = NULL, last_read_at = NULL
+         WHERE last_read_message_id IS NOT NULL OR last_read_at IS NOT NULL;
+        UPDATE messages SET updated_at = NULL, revision_count = 0
+         WHERE updated_at IS NOT NULL OR revision_count > 0;
+        DELETE FROM message_revisions;
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
 
 pub async fn create_schema(pool: &PgPool) -> anyhow::Result<()> {
     tracing::info!("Creating database schema...");
@@ -40,7 +97,6 @@ pub async fn create_schema(pool: &PgPool) -> anyhow::Result<()> {
             message_type VARCHAR(20) NOT NULL DEFAULT 'text',
             reply_to UUID REFERENCES messages(id),
             forwarded_from UUID REFERENCES messages(id),
-            reactions JSONB DEFAULT '{}',
             metadata JSONB DEFAULT '{}',
             created_at TIMESTAMPTZ DEFAULT NOW(),
             updated_at TIMESTAMPTZ
@@ -64,12 +120,215 @@ pub async fn create_schema(pool: &PgPool) -> anyhow::Result<()> {
         CREATE INDEX IF NOT EXISTS idx_messages_user_id ON messages(user_id);
         CREATE INDEX IF NOT EXISTS idx_messages_created_at ON messages(created_at DESC);
         CREATE INDEX IF NOT EXISTS idx_messages_room_created ON messages(room_id, created_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_messages_room_type_created ON messages(room_id, message_type, created_at DESC);
         CREATE INDEX IF NOT EXISTS idx_room_members_room_id ON room_members(room_id);
         CREATE INDEX IF NOT EXISTS idx_room_members_user_id ON room_members(user_id);
         CREATE INDEX IF NOT EXISTS idx_room_members_room_user ON room_members(room_id, user_id);
 
         ALTER TABLE messages ADD COLUMN IF NOT EXISTS pinned_by UUID REFERENCES users(id);
         ALTER TABLE messages ADD COLUMN IF NOT EXISTS pinned_at TIMESTAMPTZ;
+
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS spam_score REAL;
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS spam_reasons JSONB;
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS spam_action VARCHAR(20);
+        CREATE INDEX IF NOT EXISTS idx_messages_spam_review ON messages(created_at DESC) WHERE spam_action IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_messages_user_created ON messages(user_id, created_at DESC);
+
+        ALTER TABLE room_members ADD COLUMN IF NOT EXISTS muted_until TIMESTAMPTZ;
+        ALTER TABLE room_members ADD COLUMN IF NOT EXISTS notification_level VARCHAR(10) NOT NULL DEFAULT 'all';
+        ALTER TABLE room_members ADD COLUMN IF NOT EXISTS notifications_muted_until TIMESTAMPTZ;
+        -- Last change to what member lists show (role, profile, presence), for their ETag
+        ALTER TABLE room_members ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS is_shadow_banned BOOLEAN NOT NULL DEFAULT FALSE;
+
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS queued_at TIMESTAMPTZ;
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS queue_token UUID;
+        CREATE INDEX IF NOT EXISTS idx_users_queued_at ON users(queued_at) WHERE queued_at IS NOT NULL;
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_users_queue_token ON users(queue_token) WHERE queue_token IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_users_created_at ON users(created_at DESC);
+
+        -- Set on guest accounts (POST /api/auth/guest); purged once passed
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS guest_expires_at TIMESTAMPTZ;
+        CREATE INDEX IF NOT EXISTS idx_users_guest_expires_at ON users(guest_expires_at) WHERE guest_expires_at IS NOT NULL;
+
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS email VARCHAR(255);
+        ALTER TABLE users ALTER COLUMN email DROP NOT NULL;
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_unique ON users(email) WHERE email IS NOT NULL;
+
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS digest_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS digest_hour SMALLINT NOT NULL DEFAULT 8;
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS digest_sent_at TIMESTAMPTZ;
+        CREATE TABLE IF NOT EXISTS digests (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            rooms JSONB NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE INDEX IF NOT EXISTS idx_digests_user_created ON digests(user_id, created_at DESC);
+
+        CREATE TABLE IF NOT EXISTS message_retention (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            room_id UUID REFERENCES rooms(id) ON DELETE CASCADE,
+            days INTEGER NOT NULL CHECK (days > 0),
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_message_retention_global ON message_retention(user_id) WHERE room_id IS NULL;
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_message_retention_room ON message_retention(user_id, room_id) WHERE room_id IS NOT NULL;
+
+        CREATE TABLE IF NOT EXISTS room_activity_hourly (
+            room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+            hour TIMESTAMPTZ NOT NULL,
+            message_count INTEGER NOT NULL,
+            PRIMARY KEY (room_id, hour)
+        );
+        CREATE INDEX IF NOT EXISTS idx_room_activity_hourly_hour ON room_activity_hourly(hour);
+
+        CREATE TABLE IF NOT EXISTS room_invites (
+            code VARCHAR(32) PRIMARY KEY,
+            room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+            created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            max_uses INTEGER,
+            uses INTEGER NOT NULL DEFAULT 0,
+            expires_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE INDEX IF NOT EXISTS idx_room_invites_room ON room_invites(room_id);
+
+        ALTER TABLE rooms ADD COLUMN IF NOT EXISTS encryption_mode VARCHAR(10) NOT NULL DEFAULT 'server';
+        ALTER TABLE rooms ADD COLUMN IF NOT EXISTS key_epoch INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE rooms ADD COLUMN IF NOT EXISTS e2ee_since TIMESTAMPTZ;
+        -- Secret for the room's public Atom feed; NULL while the feed is off
+        ALTER TABLE rooms ADD COLUMN IF NOT EXISTS feed_token TEXT;
+        -- Room content policy; NULL allows whatever the server does
+        ALTER TABLE rooms ADD COLUMN IF NOT EXISTS allowed_message_types TEXT[];
+        ALTER TABLE rooms ADD COLUMN IF NOT EXISTS allowed_upload_types TEXT[];
+        -- Direct-message rooms refuse plaintext text messages unless this
+        -- is set; other room types ignore it
+        ALTER TABLE rooms ADD COLUMN IF NOT EXISTS allow_plaintext BOOLEAN NOT NULL DEFAULT FALSE;
+        -- History gating: members see messages from before they joined only
+        -- after this many minutes (0: only once a room admin approves them)
+        ALTER TABLE rooms ADD COLUMN IF NOT EXISTS history_gate_minutes INTEGER;
+        ALTER TABLE room_members ADD COLUMN IF NOT EXISTS history_approved_at TIMESTAMPTZ;
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS key_epoch INTEGER;
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS legacy_plaintext BOOLEAN NOT NULL DEFAULT FALSE;
+        -- Full-text search over messages the server can read (end-to-end
+        -- encrypted ones carry a key epoch)
+        CREATE INDEX IF NOT EXISTS idx_messages_content_search ON messages
+            USING GIN (to_tsvector('simple', content)) WHERE key_epoch IS NULL;
+        CREATE TABLE IF NOT EXISTS room_key_epochs (
+            room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+            epoch INTEGER NOT NULL,
+            issued_by UUID REFERENCES users(id) ON DELETE SET NULL,
+            issued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (room_id, epoch)
+        );
+
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS expires_in INTEGER;
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS expires_after_read BOOLEAN NOT NULL DEFAULT FALSE;
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+        CREATE INDEX IF NOT EXISTS idx_messages_expires_at ON messages(expires_at) WHERE expires_at IS NOT NULL;
+
+        -- One row per user and emoji, so concurrent reactions can't overwrite
+        -- each other like read-modify-writes of a JSONB blob did
+        CREATE TABLE IF NOT EXISTS message_reactions (
+            message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            emoji VARCHAR(64) NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (message_id, user_id, emoji)
+        );
+        CREATE INDEX IF NOT EXISTS idx_message_reactions_message_emoji ON message_reactions(message_id, emoji);
+
+        -- Earlier versions of edited messages, for moderation
+        ALTER TABLE messages ADD COLUMN IF NOT EXISTS revision_count INTEGER NOT NULL DEFAULT 0;
+        CREATE TABLE IF NOT EXISTS message_revisions (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+            content TEXT NOT NULL,
+            key_epoch INTEGER,
+            written_at TIMESTAMPTZ NOT NULL,
+            replaced_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE INDEX IF NOT EXISTS idx_message_revisions_message ON message_revisions(message_id, replaced_at);
+
+        -- Single-use tokens that renew an expired session; only their hash is kept
+        CREATE TABLE IF NOT EXISTS refresh_tokens (
+            token_hash BYTEA PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            expires_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user ON refresh_tokens(user_id);
+
+        -- Personal access tokens for scripts and bridges; only their hash is kept
+        CREATE TABLE IF NOT EXISTS api_tokens (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name VARCHAR(64) NOT NULL,
+            token_hash BYTEA NOT NULL UNIQUE,
+            scopes TEXT[] NOT NULL,
+            room_ids UUID[],
+            expires_at TIMESTAMPTZ,
+            last_used_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE INDEX IF NOT EXISTS idx_api_tokens_user ON api_tokens(user_id);
+
+        -- WebAuthn security keys; `passkey` is the credential as stored by
+        -- webauthn-rs (public key, signature counter, ...)
+        CREATE TABLE IF NOT EXISTS security_keys (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name VARCHAR(64) NOT NULL,
+            passkey JSONB NOT NULL,
+            last_used_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE INDEX IF NOT EXISTS idx_security_keys_user ON security_keys(user_id);
+        -- The password alone no longer signs in; a security key is needed too
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS security_key_required BOOLEAN NOT NULL DEFAULT FALSE;
+
+        -- Settings changed by admins at runtime (see services::settings)
+        CREATE TABLE IF NOT EXISTS server_settings (
+            key VARCHAR(64) PRIMARY KEY,
+            value JSONB NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+
+        -- Who may add a user to a private room directly: 'everyone', or
+        -- 'members' (people they already share a room with); anyone else's
+        -- add becomes a message request
+        ALTER TABLE users ADD COLUMN IF NOT EXISTS accept_rooms_from VARCHAR(10) NOT NULL DEFAULT 'everyone';
+        CREATE TABLE IF NOT EXISTS room_requests (
+            room_id UUID NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            requested_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (room_id, user_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_room_requests_user ON room_requests(user_id, created_at DESC);
+
+        -- Move reactions out of the old messages.reactions blob ({emoji: [userId]})
+        DO $$
+        BEGIN
+            IF EXISTS (SELECT 1 FROM information_schema.columns
+                       WHERE table_name = 'messages' AND column_name = 'reactions') THEN
+                INSERT INTO message_reactions (message_id, user_id, emoji)
+                SELECT m.id, u.id, r.key
+                  FROM messages m
+                 CROSS JOIN LATERAL jsonb_each(
+                       CASE WHEN jsonb_typeof(m.reactions) = 'object' THEN m.reactions END) r
+                 CROSS JOIN LATERAL jsonb_array_elements_text(
+                       CASE WHEN jsonb_typeof(r.value) = 'array' THEN r.value END) uid(value)
+                  JOIN users u ON u.id::text = uid.value
+                 WHERE length(r.key) <= 64
+                ON CONFLICT DO NOTHING;
+                ALTER TABLE messages DROP COLUMN reactions;
+            END IF;
+        END $$;
         "#,
     )
     .execute(pool)
@@ -40,11 +40,26 @@ pub enum AppError {
 
     #[error("File upload error: {0}")]
     Upload(String),
+
+    #[error("{0}")]
+    Maintenance(String),
+
+    #[error("{0}")]
+    RateLimited(String),
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_type, details) = match &self {
+            AppError::Database(sqlx::Error::PoolTimedOut) => {
+                crate::database::record_acquire_timeout();
+                tracing::warn!("Timed out waiting for a database connection");
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "server_busy",
+                    "Server is busy, please retry".to_string(),
+                )
+            }
             AppError::Database(e) => {
                 tracing::error!("Database error: {}", e);
                 (
@@ -91,8 +106,23 @@ impl IntoResponse for AppError {
                 )
             }
             AppError::Upload(_) => (StatusCode::BAD_REQUEST, "upload_error", self.to_string()),
+            AppError::Maintenance(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "maintenance",
+                self.to_string(),
+            ),
+            AppError::RateLimited(_) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                self.to_string(),
+            ),
         };
 
+        // Deliberate read-only mode isn't a failure worth alerting on
+        if status.is_server_error() && !matches!(self, AppError::Maintenance(_)) {
+            crate::services::record_server_error();
+        }
+
         let body = Json(json!({
             "error": error_type,
             "details": details,
@@ -9,16 +9,22 @@ mod socket;
 mod state;
 
 use crate::config::Config;
-use crate::database::create_schema;
-use crate::middleware::auth_middleware;
+use crate::database::{connect, create_schema, scrub_metadata};
+use crate::middleware::{
+    auth_middleware, coarse_timestamps, log_slow_requests, onion_location, read_only_guard,
+};
 use crate::routes::*;
+use crate::services::{
+    begin_migration, data_report, end_migration, run_guest_purge, run_settings_sync, AlertKind,
+};
+use crate::socket::guard::{on_guarded, on_guarded_write};
 use crate::socket::handlers::*;
 use crate::state::AppState;
 use axum::{
     extract::DefaultBodyLimit,
     http::StatusCode,
     middleware as axum_middleware,
-    routing::{delete, get, post},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use socketioxide::extract::{Data, SocketRef};
@@ -34,8 +40,23 @@ use tower_http::{
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+/// Body limit for login/register payloads
+const AUTH_BODY_LIMIT: usize = 4 * 1024;
+/// Body limit for every other JSON endpoint (messages, room settings, ...)
+const JSON_BODY_LIMIT: usize = 64 * 1024;
+/// Headroom on top of MAX_FILE_SIZE for multipart boundaries and headers
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+/// socketioxide's default max packet size, enough for every event but
+/// `send_message` with an inline attachment
+const SOCKET_BASE_PAYLOAD: usize = 100_000;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return run_command(&args).await;
+    }
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(
@@ -52,23 +73,65 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("TOR enabled: {}", config.tor_enabled);
 
     // Connect to database
-    let db_pool = sqlx::PgPool::connect(&config.database_url).await?;
-    tracing::info!("Connected to PostgreSQL database");
-
-    // Create database schema
-    create_schema(&db_pool).await?;
+    let db_pool = connect(&config).await?;
+    tracing::info!(
+        "Connected to PostgreSQL database (pool: {} connections)",
+        config.db_max_connections
+    );
+
+    // Create database schema. Nothing is served until it's up to date: every
+    // route and background job assumes the current columns. Other servers
+    // still serving from the database are read-only meanwhile.
+    if let Err(e) = begin_migration(&db_pool).await {
+        tracing::warn!(
+            "Failed to make other servers read-only for the migration: {}",
+            e
+        );
+    }
+    let migrated = create_schema(&db_pool).await;
+    if let Err(e) = end_migration(&db_pool).await {
+        tracing::error!("Failed to end read-only mode on other servers: {}", e);
+    }
+    migrated?;
     tracing::info!("Database schema initialized");
 
-    // Create Socket.IO layer first
-    let (socket_layer, io) = SocketIo::new_layer();
+    // Create Socket.IO layer first. Inline attachments arrive base64 encoded,
+    // a third larger than the file.
+    let (socket_layer, io) = SocketIo::builder()
+        .max_payload((SOCKET_BASE_PAYLOAD + config.socket_upload_max_size.div_ceil(3) * 4) as u64)
+        .build_layer();
 
     // Create app state with SocketIo
     let state = Arc::new(AppState::new(db_pool, config.clone(), io.clone()));
+    state
+        .settings
+        .room_limits()
+        .validate()
+        .map_err(|e| anyhow::anyhow!("ROOM_DEFAULT_MAX_MEMBERS / ROOM_MAX_MEMBERS: {}", e))?;
+
+    if let Err(e) = state.settings.load(&state.db).await {
+        tracing::error!("Failed to load server settings: {}", e);
+    }
+    // Pick up changes saved by other servers on the same database
+    tokio::spawn(run_settings_sync(
+        state.settings.clone(),
+        state.db.clone(),
+        state.io.clone(),
+    ));
+    if let Err(e) = state.identity.load(&state.db).await {
+        tracing::error!("Failed to load the server signing key: {}", e);
+    }
+    if config.paranoid_mode {
+        match scrub_metadata(&state.db).await {
+            Ok(()) => tracing::info!("Paranoid mode: stored activity metadata cleared"),
+            Err(e) => tracing::error!("Failed to clear activity metadata: {}", e),
+        }
+    }
 
     // Register Socket.IO event handlers
     // NOTE: We capture state via closures instead of using socketioxide's State
-    // extractor, because SocketIo::new_layer() doesn't register any state and
-    // AppState contains SocketIo (circular dependency prevents using the builder).
+    // extractor, because the layer is built without any registered state and
+    // AppState contains SocketIo (circular dependency prevents `with_state`).
     let ns_state = state.clone();
     io.ns("/", move |socket: SocketRef| {
         let state = ns_state.clone();
@@ -84,106 +147,20 @@ async fn main() -> anyhow::Result<()> {
                 },
             );
 
-            let s = state.clone();
-            socket.on(
-                "join_room",
-                move |socket: SocketRef, Data(data): Data<JoinRoomData>| {
-                    let state = s.clone();
-                    async move { on_join_room(socket, data, state).await }
-                },
-            );
-
-            socket.on("leave_room", on_leave_room);
-
-            let s = state.clone();
-            socket.on(
-                "send_message",
-                move |socket: SocketRef, Data(data): Data<SendMessageData>| {
-                    let state = s.clone();
-                    async move { on_send_message(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "typing",
-                move |socket: SocketRef, Data(data): Data<TypingData>| {
-                    let state = s.clone();
-                    async move { on_typing(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "add_reaction",
-                move |socket: SocketRef, Data(data): Data<ReactionData>| {
-                    let state = s.clone();
-                    async move { on_add_reaction(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "remove_reaction",
-                move |socket: SocketRef, Data(data): Data<ReactionData>| {
-                    let state = s.clone();
-                    async move { on_remove_reaction(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "edit_message",
-                move |socket: SocketRef, Data(data): Data<EditMessageData>| {
-                    let state = s.clone();
-                    async move { on_edit_message(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "delete_message",
-                move |socket: SocketRef, Data(data): Data<DeleteMessageData>| {
-                    let state = s.clone();
-                    async move { on_delete_message(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "mark_read",
-                move |socket: SocketRef, Data(data): Data<MarkReadData>| {
-                    let state = s.clone();
-                    async move { on_mark_read(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "forward_message",
-                move |socket: SocketRef, Data(data): Data<ForwardMessageData>| {
-                    let state = s.clone();
-                    async move { on_forward_message(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "pin_message",
-                move |socket: SocketRef, Data(data): Data<PinMessageData>| {
-                    let state = s.clone();
-                    async move { on_pin_message(socket, data, state).await }
-                },
-            );
-
-            let s = state.clone();
-            socket.on(
-                "unpin_message",
-                move |socket: SocketRef, Data(data): Data<PinMessageData>| {
-                    let state = s.clone();
-                    async move { on_unpin_message(socket, data, state).await }
-                },
-            );
+            // Every other event is dispatched through the guard, which resolves
+            // the authenticated user and re-checks bans before the handler runs
+            on_guarded(&socket, &state, "join_room", on_join_room);
+            on_guarded(&socket, &state, "leave_room", on_leave_room);
+            on_guarded_write(&socket, &state, "send_message", on_send_message);
+            on_guarded(&socket, &state, "typing", on_typing);
+            on_guarded_write(&socket, &state, "add_reaction", on_add_reaction);
+            on_guarded_write(&socket, &state, "remove_reaction", on_remove_reaction);
+            on_guarded_write(&socket, &state, "edit_message", on_edit_message);
+            on_guarded_write(&socket, &state, "delete_message", on_delete_message);
+            on_guarded_write(&socket, &state, "mark_read", on_mark_read);
+            on_guarded_write(&socket, &state, "forward_message", on_forward_message);
+            on_guarded_write(&socket, &state, "pin_message", on_pin_message);
+            on_guarded_write(&socket, &state, "unpin_message", on_unpin_message);
 
             let s = state.clone();
             socket.on_disconnect(move |socket: SocketRef| {
@@ -193,82 +170,286 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
+    socket::admin::register(&io, state.clone());
+
     tracing::info!("Socket.IO handlers registered");
 
+    if state.registration_queue.is_enabled() {
+        let queue = state.registration_queue.clone();
+        let db = state.db.clone();
+        tokio::spawn(async move { queue.run(db).await });
+        tracing::info!(
+            "Registration queue enabled above {} signups/minute",
+            config.registration_max_per_minute
+        );
+    }
+
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
 
+    // Per-route timeouts: JSON APIs are cut off sooner than transfers,
+    // which can take minutes over Tor
+    let json_timeout = TimeoutLayer::with_status_code(
+        StatusCode::REQUEST_TIMEOUT,
+        Duration::from_secs(config.request_timeout.max(1)),
+    );
+    let transfer_timeout = TimeoutLayer::with_status_code(
+        StatusCode::REQUEST_TIMEOUT,
+        Duration::from_secs(config.transfer_timeout.max(1)),
+    );
+
     // Public routes (no authentication required)
     let public_routes = Router::new()
         .route("/api/auth/register", post(register))
         .route("/api/auth/login", post(login))
-        .route("/api/tor-status", get(tor::get_status));
+        .route("/api/auth/guest", post(guest))
+        .route(
+            "/api/auth/webauthn/login/start",
+            post(security_keys::start_login),
+        )
+        .route(
+            "/api/auth/webauthn/login/finish",
+            post(security_keys::finish_login),
+        )
+        .route("/api/auth/refresh", post(refresh).delete(revoke_refresh))
+        .route(
+            "/api/auth/registration-queue/{token}",
+            get(registration_status),
+        )
+        .route("/api/tor-status", get(tor::get_status))
+        .route("/api/server-info", get(server::get_info))
+        .route("/api/rooms/{id}/feed.atom", get(feed::atom_feed))
+        .route(
+            "/.well-known/torchat/user/{username}",
+            get(federation::user_document),
+        )
+        .route("/invite/{code}", get(invites::invite_page))
+        .route("/admin-console", get(console::console_page))
+        .route(
+            "/admin-console/maintenance",
+            post(console::console_maintenance),
+        )
+        .layer(DefaultBodyLimit::max(AUTH_BODY_LIMIT))
+        .route_layer(json_timeout.clone());
 
     // Protected routes (authentication required)
     let protected_routes = Router::new()
         .route("/api/auth/logout", post(logout))
         .route("/api/auth/me", get(me))
+        .route("/api/auth/public-key", put(update_public_key))
         .route("/api/auth/users", get(list_users))
+        .route(
+            "/api/auth/tokens",
+            get(list_api_tokens).post(create_api_token),
+        )
+        .route("/api/auth/tokens/{id}", delete(revoke_api_token))
+        .route("/api/auth/webauthn/keys", get(security_keys::list_keys))
+        .route(
+            "/api/auth/webauthn/keys/{id}",
+            delete(security_keys::delete_key),
+        )
+        .route(
+            "/api/auth/webauthn/register/start",
+            post(security_keys::start_registration),
+        )
+        .route(
+            "/api/auth/webauthn/register/finish",
+            post(security_keys::finish_registration),
+        )
+        .route(
+            "/api/auth/webauthn/settings",
+            put(security_keys::update_settings),
+        )
+        .route("/api/cover", post(server::cover))
         // Rooms routes
         .route("/api/rooms", get(rooms::list_rooms))
         .route("/api/rooms", post(rooms::create_room))
+        .route("/api/rooms/unread", get(rooms::unread_counts))
         .route("/api/rooms/{id}", get(rooms::get_room))
         .route("/api/rooms/{id}/join", post(rooms::join_room))
         .route("/api/rooms/{id}/leave", post(rooms::leave_room))
         .route("/api/rooms/{id}", delete(rooms::delete_room))
+        .route("/api/rooms/{id}", patch(rooms::update_room))
+        .route("/api/rooms/{id}/e2ee", post(rooms::enable_e2ee))
         .route(
             "/api/rooms/{id}/messages",
             get(rooms::get_messages).post(rooms::send_message),
         )
+        .route(
+            "/api/rooms/{id}/messages/{message_id}/context",
+            get(rooms::get_message_context),
+        )
+        .route(
+            "/api/rooms/{id}/messages/{message_id}/reactions",
+            get(rooms::get_reactions),
+        )
         .route("/api/rooms/{id}/members", get(rooms::get_members))
         .route("/api/rooms/{id}/members", post(rooms::add_member))
         .route(
             "/api/rooms/{id}/members/{user_id}",
             delete(rooms::remove_member),
         )
+        .route(
+            "/api/rooms/{id}/members/{user_id}/role",
+            put(rooms::set_member_role),
+        )
+        .route(
+            "/api/rooms/{id}/members/{user_id}/mute",
+            put(rooms::mute_member),
+        )
+        .route(
+            "/api/rooms/{id}/members/{user_id}/history",
+            post(rooms::approve_history),
+        )
+        .route("/api/rooms/{id}/pins", get(rooms::list_pins))
+        .route(
+            "/api/rooms/{id}/feed",
+            get(feed::get_feed)
+                .post(feed::enable_feed)
+                .delete(feed::disable_feed),
+        )
+        .route("/api/messages/{id}/history", get(rooms::message_history))
+        .route(
+            "/api/rooms/{id}/notifications",
+            get(rooms::get_notifications).put(rooms::update_notifications),
+        )
+        .route(
+            "/api/rooms/{id}/invites",
+            get(invites::list_invites).post(invites::create_invite),
+        )
+        .route(
+            "/api/rooms/{id}/invites/{code}",
+            delete(invites::revoke_invite),
+        )
+        .route("/api/invites/{code}", get(invites::preview_invite))
+        .route("/api/invites/{code}/accept", post(invites::accept_invite))
+        .route("/api/requests", get(requests::list_requests))
+        .route(
+            "/api/requests/settings",
+            get(requests::get_settings).put(requests::update_settings),
+        )
+        .route(
+            "/api/requests/{room_id}/accept",
+            post(requests::accept_request),
+        )
+        .route("/api/requests/{room_id}", delete(requests::decline_request))
         .route("/api/rooms/{id}/search", get(rooms::search_messages))
-        // Upload route
-        .route("/api/upload", post(upload_file))
+        .route("/api/search/messages", get(rooms::search_all_messages))
+        .route("/api/rooms/{id}/files", get(rooms::list_files))
+        .route("/api/rooms/{id}/stats", get(rooms::room_stats))
+        .route("/api/proxy/image", get(proxy::image))
+        .route("/api/translate", post(translate::translate))
+        .route("/api/digest", get(digest::get_latest))
+        .route(
+            "/api/digest/settings",
+            get(digest::get_settings).put(digest::update_settings),
+        )
+        .route(
+            "/api/retention",
+            get(retention::get_rules).put(retention::set_rule),
+        )
         // Admin routes
         .route("/api/admin/users", get(admin::list_users))
         .route("/api/admin/users/{id}/promote", post(admin::promote_user))
         .route("/api/admin/users/{id}/demote", post(admin::demote_user))
         .route("/api/admin/users/{id}/ban", post(admin::ban_user))
         .route("/api/admin/users/{id}/unban", post(admin::unban_user))
+        .route(
+            "/api/admin/users/{id}/shadow-ban",
+            post(admin::shadow_ban_user),
+        )
+        .route(
+            "/api/admin/users/{id}/shadow-unban",
+            post(admin::shadow_unban_user),
+        )
         .route("/api/admin/users/{id}", delete(admin::delete_user))
+        .route("/api/admin/users/{id}/activity", get(admin::user_activity))
         .route("/api/admin/rooms", get(admin::list_rooms))
         .route("/api/admin/rooms/{id}", delete(admin::delete_room))
         .route("/api/admin/stats", get(admin::get_stats))
+        .route(
+            "/api/admin/storage/clean-orphans",
+            post(admin::clean_orphans),
+        )
+        .route("/api/admin/spam", get(admin::list_spam))
+        .route("/api/admin/spam/{id}/approve", post(admin::approve_spam))
+        .route(
+            "/api/admin/maintenance",
+            get(admin::get_maintenance).post(admin::set_maintenance),
+        )
+        .route(
+            "/api/admin/settings",
+            get(admin::get_settings).patch(admin::update_settings),
+        )
+        .route_layer(json_timeout.clone())
+        .route_layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ));
+
+    // Protected routes moving large bodies, with the longer timeout
+    let transfer_routes = Router::new()
+        // Upload route: the only endpoint allowed to receive large bodies
+        .route(
+            "/api/upload",
+            post(upload_file).layer(DefaultBodyLimit::max(
+                config.max_file_size + MULTIPART_OVERHEAD,
+            )),
+        )
+        .route(
+            "/api/rooms/{id}/export/mine",
+            get(rooms::export_my_messages),
+        )
+        .route_layer(transfer_timeout.clone())
         .route_layer(axum_middleware::from_fn_with_state(
             state.clone(),
             auth_middleware,
         ));
 
     // Health check route
-    let health_route = Router::new().route("/health", get(|| async { "OK" }));
+    let health_route = Router::new()
+        .route("/health", get(|| async { "OK" }))
+        .route("/metrics", get(metrics::get_metrics))
+        .route_layer(json_timeout);
 
     // Serve static files (uploads)
-    let static_routes = Router::new().nest_service("/uploads", ServeDir::new(&config.upload_dir));
+    let static_routes = Router::new()
+        .nest_service("/uploads", ServeDir::new(&config.upload_dir))
+        .layer(transfer_timeout);
 
     // Combine all routes
     let app = Router::new()
         .merge(health_route)
         .merge(public_routes)
         .merge(protected_routes)
+        .merge(transfer_routes)
         .merge(static_routes)
+        .layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            log_slow_requests,
+        ))
+        // Socket.IO manages its own connections and heartbeats; no timeout
         .layer(socket_layer)
-        .layer(DefaultBodyLimit::max(config.max_file_size))
+        .layer(DefaultBodyLimit::max(JSON_BODY_LIMIT))
+        .layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            read_only_guard,
+        ))
+        .layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            coarse_timestamps,
+        ))
+        .layer(axum_middleware::from_fn_with_state(
+            state.clone(),
+            onion_location,
+        ))
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
-                .layer(cors)
-                .layer(TimeoutLayer::with_status_code(
-                    StatusCode::REQUEST_TIMEOUT,
-                    Duration::from_secs(30),
-                )),
+                .layer(cors),
         )
         .with_state(state.clone());
 
@@ -280,6 +461,12 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("📡 Socket.IO endpoint: ws://{}/socket.io/", addr);
     tracing::info!("📁 Upload directory: {}", config.upload_dir.display());
     tracing::info!("🔒 Max file size: {} bytes", config.max_file_size);
+    if config.socket_upload_max_size > 0 {
+        tracing::info!(
+            "📎 Inline socket attachments up to {} bytes",
+            config.socket_upload_max_size
+        );
+    }
 
     if config.tor_enabled {
         tracing::info!(
@@ -306,7 +493,163 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
+    if state.tor_probe.is_enabled() {
+        let probe = state.tor_probe.clone();
+        tokio::spawn(async move { probe.run().await });
+        tracing::info!(
+            "Tor self-check every {}s through the SOCKS proxy",
+            config.tor_probe_interval
+        );
+    }
+
+    if state.digest.is_enabled() {
+        let digest_state = state.clone();
+        tokio::spawn(async move {
+            let service = digest_state.digest.clone();
+            service
+                .run(digest_state.db.clone(), |user_id, digest| {
+                    let state = digest_state.clone();
+                    async move { state.emit_to_user(user_id, "digest", &digest).await }
+                })
+                .await;
+        });
+    }
+
+    if state.retention.is_enabled() {
+        let retention_state = state.clone();
+        tokio::spawn(async move {
+            let service = retention_state.retention.clone();
+            service
+                .run(retention_state.db.clone(), |deleted| {
+                    let state = retention_state.clone();
+                    async move {
+                        for (message_id, room_id) in deleted {
+                            state
+                                .io
+                                .to(room_id.to_string())
+                                .emit(
+                                    "message_deleted",
+                                    &serde_json::json!({ "messageId": message_id }),
+                                )
+                                .await
+                                .ok();
+                        }
+                    }
+                })
+                .await;
+        });
+    }
+
+    if state.message_expiry.is_enabled() {
+        let expiry_state = state.clone();
+        tokio::spawn(async move {
+            let service = expiry_state.message_expiry.clone();
+            service
+                .run(expiry_state.db.clone(), |expired| {
+                    let state = expiry_state.clone();
+                    async move {
+                        for (message_id, room_id) in expired {
+                            state
+                                .io
+                                .to(room_id.to_string())
+                                .emit(
+                                    "message_expired",
+                                    &serde_json::json!({ "messageId": message_id, "roomId": room_id }),
+                                )
+                                .await
+                                .ok();
+                        }
+                    }
+                })
+                .await;
+        });
+    }
+
+    // Expired guest accounts go with their messages; clients drop those as
+    // they would expired ones
+    let guest_state = state.clone();
+    tokio::spawn(async move {
+        run_guest_purge(guest_state.db.clone(), |purged| {
+            let state = guest_state.clone();
+            async move {
+                for user_id in purged.user_ids {
+                    state.guard_cache.invalidate_user(user_id).await;
+                }
+                for (message_id, room_id) in purged.messages {
+                    state
+                        .io
+                        .to(room_id.to_string())
+                        .emit(
+                            "message_expired",
+                            &serde_json::json!({ "messageId": message_id, "roomId": room_id }),
+                        )
+                        .await
+                        .ok();
+                }
+            }
+        })
+        .await;
+    });
+
+    if state.room_stats.is_enabled() {
+        let stats = state.room_stats.clone();
+        let db = state.db.clone();
+        tokio::spawn(async move { stats.run(db).await });
+    }
+
+    if state.error_spikes.is_enabled() {
+        let spike_state = state.clone();
+        tokio::spawn(async move {
+            let monitor = spike_state.error_spikes.clone();
+            monitor
+                .run(|spike| {
+                    let state = spike_state.clone();
+                    async move {
+                        state
+                            .emit_admin(
+                                "error_spike",
+                                serde_json::json!({
+                                    "errors": spike.errors,
+                                    "windowSeconds": spike.window_seconds,
+                                }),
+                            )
+                            .await;
+                        let message = format!(
+                            "Error spike: {} server errors in {}s",
+                            spike.errors, spike.window_seconds
+                        );
+                        state.alerts.notify(AlertKind::ErrorSpike, &message).await;
+                    }
+                })
+                .await;
+        });
+    }
+
+    if state.alerts.is_enabled() {
+        let alerts = state.alerts.clone();
+        let probe = state.tor_probe.clone();
+        let db = state.db.clone();
+        tokio::spawn(async move { alerts.run(db, &probe).await });
+        tracing::info!("Operator alerts enabled");
+    }
+
     axum::serve(listener, app).await?;
 
     Ok(())
 }
+
+/// Operator commands, run against the configured database instead of
+/// starting the server. Output goes to stdout, so nothing is logged.
+async fn run_command(args: &[String]) -> anyhow::Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["admin", "data-report", who] => {
+            let config = Config::from_env()?;
+            let db = connect(&config).await?;
+            let report = data_report(&db, who).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        }
+        _ => anyhow::bail!("Usage: tor-chat-backend [admin data-report <username | user id>]"),
+    }
+}
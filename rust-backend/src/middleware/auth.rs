@@ -1,5 +1,5 @@
 use crate::error::{AppError, Result};
-use crate::models::User;
+use crate::models::{ApiToken, User};
 use crate::services::AuthService;
 use crate::state::AppState;
 use axum::{
@@ -29,9 +29,22 @@ pub async fn auth_middleware(
         .and_then(|h| h.strip_prefix("Bearer "))
         .ok_or_else(|| AppError::Authentication("Missing authorization token".to_string()))?;
 
-    // Verify token
+    // Verify token: a session JWT, or an API token limited to its scopes
     let auth_service = AuthService::new(state.config.clone());
-    let user_id = auth_service.verify_token(token)?;
+    let user_id = if token.starts_with(ApiToken::PREFIX) {
+        let api_token = auth_service
+            .find_api_token(&state.db, token)
+            .await?
+            .ok_or_else(|| AppError::Authentication("Invalid or expired API token".to_string()))?;
+        if !api_token.allows(req.method(), req.uri().path()) {
+            return Err(AppError::Authorization(
+                "This API token's scopes don't allow this request".to_string(),
+            ));
+        }
+        api_token.user_id
+    } else {
+        auth_service.verify_token(token)?
+    };
 
     // Get user from database
     let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
@@ -47,13 +60,35 @@ pub async fn auth_middleware(
         ));
     }
 
+    // Guests only reach their rooms, and only until their session ends
+    if user.is_guest() {
+        if user.is_expired_guest() {
+            return Err(AppError::Authentication(
+                "Your guest session has expired".to_string(),
+            ));
+        }
+        if !state
+            .settings
+            .guest_access()
+            .allows(req.method(), req.uri().path())
+        {
+            return Err(AppError::Authorization(
+                "Guests can't do that on this server".to_string(),
+            ));
+        }
+    }
+
     // Store auth user in request extensions
-    req.extensions_mut().insert(AuthUser {
+    let auth_user = AuthUser {
         user_id,
         user: user.clone(),
-    });
+    };
+    req.extensions_mut().insert(auth_user.clone());
 
-    Ok(next.run(req).await)
+    // and in the response's, for the slow request log
+    let mut response = next.run(req).await;
+    response.extensions_mut().insert(auth_user);
+    Ok(response)
 }
 
 pub async fn admin_middleware(req: Request, next: Next) -> Result<Response> {
@@ -0,0 +1,49 @@
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+/// POST endpoints that don't change anything (or must keep working) in
+/// maintenance mode. Refreshing is allowed so sessions outlive their access
+/// token; `/api/auth/guest` is not, as a guest session creates an account.
+const WRITES_ALLOWED: &[&str] = &[
+    "/api/auth/login",
+    "/api/auth/refresh",
+    "/api/auth/webauthn/login/start",
+    "/api/auth/webauthn/login/finish",
+    "/api/auth/logout",
+    "/api/translate",
+    "/api/admin/maintenance",
+];
+
+/// Rejects API writes with a `maintenance` error while the server is
+/// read-only. Reads, logins and the Socket.IO transport pass through; socket
+/// write events are refused by the socket guard instead.
+pub async fn read_only_guard(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    let is_write = !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let path = req.uri().path();
+
+    if is_write
+        && path.starts_with("/api/")
+        && !WRITES_ALLOWED.contains(&path)
+        && state.maintenance.is_active()
+    {
+        let message = state
+            .maintenance
+            .status()
+            .message
+            .unwrap_or_else(|| "Server is in read-only maintenance mode".to_string());
+        return Err(AppError::Maintenance(message));
+    }
+
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,83 @@
+use crate::services::SIGNATURE_HEADER;
+use crate::state::AppState;
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Paranoid mode: round every past timestamp in JSON responses down to
+/// PARANOID_TIMESTAMP_GRANULARITY, so API clients (and anyone holding their
+/// cache) only learn roughly when things happened. Future timestamps are
+/// deadlines such as expiries and mutes, which give nothing away and are
+/// passed through exactly.
+pub async fn coarse_timestamps(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(req).await;
+    let granularity = state.config.paranoid_timestamp_granularity;
+    if !state.config.paranoid_mode || granularity <= 1 {
+        return response;
+    }
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    // Signed bodies go out as signed; the signature covers their exact bytes
+    let is_signed = response.headers().contains_key(SIGNATURE_HEADER);
+    if !is_json || is_signed {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    coarsen(&mut json, granularity, Utc::now());
+    let body = serde_json::to_vec(&json).unwrap_or_else(|_| bytes.to_vec());
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn coarsen(value: &mut Value, granularity: i64, now: DateTime<Utc>) {
+    match value {
+        Value::String(s) => {
+            let Ok(ts) = DateTime::parse_from_rfc3339(s) else {
+                return;
+            };
+            let ts = ts.with_timezone(&Utc);
+            if ts > now {
+                return;
+            }
+            *s = round_down(ts, granularity).to_rfc3339_opts(SecondsFormat::Secs, true);
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| coarsen(item, granularity, now)),
+        // Message text is the user's, even if it looks like a timestamp
+        Value::Object(fields) => fields
+            .iter_mut()
+            .filter(|(key, _)| key.as_str() != "content")
+            .for_each(|(_, field)| coarsen(field, granularity, now)),
+        _ => {}
+    }
+}
+
+/// `ts` rounded down to a multiple of `granularity` seconds
+pub fn round_down(ts: DateTime<Utc>, granularity: i64) -> DateTime<Utc> {
+    let secs = ts.timestamp();
+    DateTime::from_timestamp(secs - secs.rem_euclid(granularity.max(1)), 0).unwrap_or(ts)
+}
@@ -1,5 +1,13 @@
 pub mod auth;
+pub mod maintenance;
+pub mod metadata;
+pub mod onion;
+pub mod timing;
 pub mod validation;
 
 pub use auth::*;
+pub use maintenance::*;
+pub use metadata::*;
+pub use onion::*;
+pub use timing::*;
 pub use validation::*;
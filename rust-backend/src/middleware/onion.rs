@@ -0,0 +1,42 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+
+/// Adds an `Onion-Location` header to responses served over clearnet so Tor
+/// Browser can offer to switch to the hidden service.
+pub async fn onion_location(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let via_onion = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .map(|host| host.split(':').next().unwrap_or(host).ends_with(".onion"))
+        .unwrap_or(false);
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_default();
+
+    let mut response = next.run(req).await;
+
+    if via_onion || !state.config.onion_location {
+        return response;
+    }
+
+    if let Some(onion) = state.onion_url().await {
+        if let Ok(value) = HeaderValue::from_str(&format!("{}{}", onion, path)) {
+            response.headers_mut().insert("onion-location", value);
+        }
+    }
+
+    response
+}
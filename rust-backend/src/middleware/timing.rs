@@ -0,0 +1,50 @@
+use crate::middleware::AuthUser;
+use crate::state::AppState;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Log requests slower than SLOW_REQUEST_MS with their route (the pattern,
+/// not the path, so room and message ids stay out of the log) and the
+/// signed-in user, to find the queries that hurt on slow onion hosts.
+pub async fn log_slow_requests(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let threshold = state.config.slow_request_ms;
+    if threshold == 0 {
+        return next.run(req).await;
+    }
+
+    let method = req.method().clone();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "(unmatched)".to_string());
+    let started = Instant::now();
+    let response = next.run(req).await;
+
+    let elapsed = started.elapsed().as_millis();
+    if elapsed >= u128::from(threshold) {
+        let user = response
+            .extensions()
+            .get::<AuthUser>()
+            .map(|u| u.user_id.to_string())
+            .unwrap_or_else(|| "anonymous".to_string());
+        tracing::warn!(
+            "Slow request: {} {} took {} ms (status {}, user {})",
+            method,
+            route,
+            elapsed,
+            response.status().as_u16(),
+            user
+        );
+    }
+    response
+}
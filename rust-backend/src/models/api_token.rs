@@ -0,0 +1,99 @@
+use axum::http::Method;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// A personal access token: a named, long-lived credential for scripts and
+/// bridges that can do less than the user's own session. Only its hash is
+/// stored, and it isn't part of this struct.
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// What the token may do; see `SCOPES`
+    pub scopes: Vec<String>,
+    /// Rooms the token is limited to; every room of the user when None
+    pub room_ids: Option<Vec<Uuid>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiToken {
+    /// Marks a bearer token as an API token rather than a session JWT
+    pub const PREFIX: &'static str = "tcpat_";
+    /// Columns of the struct, for queries (everything but the hash)
+    pub const COLUMNS: &'static str =
+        "id, user_id, name, scopes, room_ids, expires_at, last_used_at, created_at";
+    /// Read anything the user can (GET requests)
+    pub const SCOPE_READ: &'static str = "read";
+    /// Send messages and upload their attachments
+    pub const SCOPE_POST: &'static str = "post";
+    pub const SCOPES: [&'static str; 2] = [Self::SCOPE_READ, Self::SCOPE_POST];
+    /// Tokens a user may hold at once
+    pub const MAX_PER_USER: i64 = 20;
+
+    fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Whether the token may make this request. Tokens never manage tokens,
+    /// the account or the server; a room-limited token only reaches its
+    /// rooms' endpoints (plus `/api/auth/me` and uploads).
+    pub fn allows(&self, method: &Method, path: &str) -> bool {
+        if path.starts_with("/api/auth/tokens")
+            || path.starts_with("/api/auth/webauthn")
+            || path.starts_with("/api/admin/")
+        {
+            return false;
+        }
+
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let room_id = match segments.as_slice() {
+            ["api", "rooms", id, ..] => Uuid::parse_str(id).ok(),
+            _ => None,
+        };
+        if let Some(rooms) = &self.room_ids {
+            let in_scope = match room_id {
+                Some(id) => rooms.contains(&id),
+                None => path == "/api/auth/me" || path == "/api/upload",
+            };
+            if !in_scope {
+                return false;
+            }
+        }
+
+        if method == Method::GET || method == Method::HEAD {
+            self.has_scope(Self::SCOPE_READ)
+        } else if method == Method::POST {
+            let sends =
+                matches!(segments.as_slice(), ["api", "rooms", _, "messages"]) && room_id.is_some();
+            (sends || path == "/api/upload") && self.has_scope(Self::SCOPE_POST)
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+
+    /// `read` and/or `post`
+    #[validate(length(min = 1, max = 2))]
+    pub scopes: Vec<String>,
+
+    /// Limit the token to these rooms (the user must be in them)
+    #[validate(length(min = 1, max = 50))]
+    pub room_ids: Option<Vec<Uuid>>,
+
+    /// Days until the token stops working; never expires when absent
+    #[validate(range(min = 1, max = 3650))]
+    pub expires_in_days: Option<i64>,
+}
@@ -4,6 +4,172 @@ use sqlx::FromRow;
 use uuid::Uuid;
 use validator::Validate;
 
+/// Shortest per-message expiry, in seconds
+pub const MIN_EXPIRES_IN: i32 = 5;
+/// Longest per-message expiry (a week)
+pub const MAX_EXPIRES_IN: i32 = 7 * 24 * 3600;
+
+/// Poll answers allowed per poll
+const POLL_OPTIONS: std::ops::RangeInclusive<usize> = 2..=10;
+
+/// Every kind of message the server stores, by its `messageType` name.
+/// Clients may send any of them but `system`, as far as MESSAGE_TYPES allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageType {
+    Text,
+    Image,
+    Video,
+    Audio,
+    File,
+    Sticker,
+    Poll,
+    /// End-to-end encrypted; `metadata.keyId` names the room key
+    Encrypted,
+    /// Server notices; never accepted from clients
+    System,
+}
+
+impl MessageType {
+    /// What clients may send unless MESSAGE_TYPES narrows it down
+    pub const CLIENT_TYPES: [MessageType; 8] = [
+        MessageType::Text,
+        MessageType::Image,
+        MessageType::Video,
+        MessageType::Audio,
+        MessageType::File,
+        MessageType::Sticker,
+        MessageType::Poll,
+        MessageType::Encrypted,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageType::Text => "text",
+            MessageType::Image => "image",
+            MessageType::Video => "video",
+            MessageType::Audio => "audio",
+            MessageType::File => "file",
+            MessageType::Sticker => "sticker",
+            MessageType::Poll => "poll",
+            MessageType::Encrypted => "encrypted",
+            MessageType::System => "system",
+        }
+    }
+
+    /// Message types that carry an uploaded file
+    pub fn is_file(self) -> bool {
+        matches!(
+            self,
+            MessageType::Image | MessageType::Video | MessageType::Audio | MessageType::File
+        )
+    }
+
+    /// Check a message's content and metadata against what this type needs.
+    /// Unknown metadata keys are allowed, so clients can add to them.
+    pub fn validate(
+        self,
+        content: &str,
+        metadata: Option<&serde_json::Value>,
+    ) -> Result<(), String> {
+        let name = self.as_str();
+        let metadata = match metadata {
+            None | Some(serde_json::Value::Null) => None,
+            Some(serde_json::Value::Object(map)) => Some(map),
+            Some(_) => return Err(format!("{} message metadata must be an object", name)),
+        };
+        let field = |key: &str| metadata.and_then(|m| m.get(key)).filter(|v| !v.is_null());
+        let optional_str = |key: &str| match field(key) {
+            None => Ok(None),
+            Some(serde_json::Value::String(s)) => Ok(Some(s.as_str())),
+            Some(_) => Err(format!(
+                "{} message metadata.{} must be a string",
+                name, key
+            )),
+        };
+
+        if content.trim().is_empty() {
+            return Err("Message content is required".to_string());
+        }
+
+        match self {
+            MessageType::Text | MessageType::System => {}
+            MessageType::Image | MessageType::Video | MessageType::Audio | MessageType::File => {
+                // Older clients send file messages without metadata
+                if optional_str("originalName")?.is_some_and(|n| n.chars().count() > 255) {
+                    return Err("File name is too long".to_string());
+                }
+                if let Some(mimetype) = optional_str("mimetype")? {
+                    let category = mimetype.split('/').next().unwrap_or_default();
+                    if self != MessageType::File && category != name {
+                        return Err(format!("{} message with a {} file", name, mimetype));
+                    }
+                }
+                if field("size").is_some_and(|s| s.as_u64().is_none()) {
+                    return Err(format!(
+                        "{} message metadata.size must be a byte count",
+                        name
+                    ));
+                }
+            }
+            MessageType::Sticker => {
+                if content.chars().count() > 256 {
+                    return Err("Sticker reference is too long".to_string());
+                }
+                optional_str("pack")?;
+            }
+            MessageType::Poll => {
+                if optional_str("question")?.is_none_or(|q| q.trim().is_empty()) {
+                    return Err("Polls need metadata.question".to_string());
+                }
+                let options = field("options")
+                    .and_then(|o| o.as_array())
+                    .ok_or("Polls need metadata.options, a list of answers")?;
+                if !POLL_OPTIONS.contains(&options.len()) {
+                    return Err(format!(
+                        "Polls need {} to {} options",
+                        POLL_OPTIONS.start(),
+                        POLL_OPTIONS.end()
+                    ));
+                }
+                if !options
+                    .iter()
+                    .all(|o| o.as_str().is_some_and(|o| !o.trim().is_empty()))
+                {
+                    return Err("Poll options must be non-empty strings".to_string());
+                }
+                if field("multiple").is_some_and(|m| !m.is_boolean()) {
+                    return Err("poll message metadata.multiple must be true or false".to_string());
+                }
+            }
+            MessageType::Encrypted => {
+                if optional_str("keyId")?.is_none_or(str::is_empty) {
+                    return Err("Encrypted messages need metadata.keyId".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for MessageType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [MessageType::System]
+            .into_iter()
+            .chain(MessageType::CLIENT_TYPES)
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| format!("Unknown message type '{}'", s))
+    }
+}
+
+impl std::fmt::Display for MessageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Message {
     pub id: Uuid,
@@ -13,12 +179,75 @@ pub struct Message {
     pub message_type: String,
     pub reply_to: Option<Uuid>,
     pub forwarded_from: Option<Uuid>,
-    pub reactions: serde_json::Value,
     pub metadata: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub pinned_by: Option<Uuid>,
     pub pinned_at: Option<DateTime<Utc>>,
+    /// Spam heuristics result for public-room messages; moderators only
+    pub spam_score: Option<f32>,
+    pub spam_reasons: Option<serde_json::Value>,
+    /// `flagged` or `hidden` while awaiting review
+    pub spam_action: Option<String>,
+    /// Key epoch the content is encrypted under; None = server-readable
+    pub key_epoch: Option<i32>,
+    /// Sent before the room switched to E2EE, so stored as plaintext
+    pub legacy_plaintext: bool,
+    /// Lifetime in seconds for expiring ("burn after reading") messages
+    pub expires_in: Option<i32>,
+    /// The lifetime starts at the first read receipt instead of on sending
+    pub expires_after_read: bool,
+    /// When the message gets deleted; None until the countdown starts
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Times the message was edited; earlier versions are in `message_revisions`
+    pub revision_count: i32,
+}
+
+/// A version of a message that an edit replaced
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageRevision {
+    pub id: Uuid,
+    pub message_id: Uuid,
+    pub content: String,
+    pub key_epoch: Option<i32>,
+    /// When this version was sent or edited in
+    pub written_at: DateTime<Utc>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+impl Message {
+    /// `expiresIn` as sent by a client: in range, or a user-facing error
+    pub fn check_expires_in(expires_in: Option<i32>) -> Result<(), String> {
+        match expires_in {
+            Some(secs) if !(MIN_EXPIRES_IN..=MAX_EXPIRES_IN).contains(&secs) => Err(format!(
+                "expiresIn must be between {} seconds and {} days",
+                MIN_EXPIRES_IN,
+                MAX_EXPIRES_IN / 86400
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// `messageType`, content and metadata as sent by a client, checked
+    /// against the types this server accepts (`allowed`, from MESSAGE_TYPES).
+    /// A missing type means text.
+    pub fn check_type(
+        message_type: Option<&str>,
+        content: &str,
+        metadata: Option<&serde_json::Value>,
+        allowed: &[MessageType],
+    ) -> Result<MessageType, String> {
+        let message_type: MessageType = message_type.unwrap_or("text").parse()?;
+        if !allowed.contains(&message_type) {
+            return Err(format!(
+                "This server doesn't accept {} messages",
+                message_type
+            ));
+        }
+        message_type.validate(content, metadata)?;
+        Ok(message_type)
+    }
 }
 
 #[derive(Debug, Deserialize, Validate)]
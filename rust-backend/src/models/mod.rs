@@ -1,9 +1,17 @@
+pub mod api_token;
 pub mod message;
+pub mod reaction;
 pub mod room;
+pub mod room_invite;
 pub mod room_member;
+pub mod security_key;
 pub mod user;
 
+pub use api_token::*;
 pub use message::*;
+pub use reaction::*;
 pub use room::*;
+pub use room_invite::*;
 pub use room_member::*;
+pub use security_key::*;
 pub use user::*;
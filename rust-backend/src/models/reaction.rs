@@ -0,0 +1,30 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Longest accepted reaction, in characters (an emoji with skin tone and
+/// ZWJ sequences is several code points)
+pub const MAX_REACTION_LEN: usize = 16;
+
+/// All reactions of one emoji on a message, aggregated from
+/// `message_reactions` (one row per user and emoji)
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ReactionGroup {
+    pub message_id: Uuid,
+    pub emoji: String,
+    pub count: i64,
+    /// In the order they reacted
+    pub user_ids: Vec<Uuid>,
+}
+
+impl ReactionGroup {
+    /// A reaction as sent by a client: trimmed, non-empty and short
+    pub fn check_emoji(emoji: &str) -> Result<&str, String> {
+        let emoji = emoji.trim();
+        if emoji.is_empty() || emoji.chars().count() > MAX_REACTION_LEN {
+            return Err("Invalid reaction".to_string());
+        }
+        Ok(emoji)
+    }
+}
@@ -1,9 +1,46 @@
+use super::MessageType;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 use validator::Validate;
 
+/// File categories a room can limit uploads to (see [`upload_category`])
+pub const UPLOAD_CATEGORIES: [&str; 7] = [
+    "image", "video", "audio", "text", "document", "archive", "other",
+];
+
+/// Category of a MIME type, for rooms' upload policy
+pub fn upload_category(mimetype: &str) -> &'static str {
+    match mimetype.split('/').next().unwrap_or_default() {
+        "image" => "image",
+        "video" => "video",
+        "audio" => "audio",
+        "text" => "text",
+        _ if mimetype == "application/pdf"
+            || mimetype == "application/msword"
+            || mimetype.starts_with("application/vnd.openxmlformats-officedocument.")
+            || mimetype.starts_with("application/vnd.ms-")
+            || mimetype.starts_with("application/vnd.oasis.opendocument.") =>
+        {
+            "document"
+        }
+        _ if matches!(
+            mimetype,
+            "application/zip"
+                | "application/gzip"
+                | "application/x-tar"
+                | "application/x-7z-compressed"
+                | "application/x-rar-compressed"
+        ) =>
+        {
+            "archive"
+        }
+        _ => "other",
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Room {
     pub id: Uuid,
@@ -18,6 +55,116 @@ pub struct Room {
     pub is_public: bool,
     pub avatar: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// `server` (readable by the server) or `e2ee`
+    pub encryption_mode: String,
+    /// Current key epoch; 0 until the room switches to E2EE
+    pub key_epoch: i32,
+    /// When the room switched to E2EE; older messages are legacy plaintext
+    pub e2ee_since: Option<DateTime<Utc>>,
+    /// Secret in the Atom feed URL; None while the feed is disabled
+    #[serde(skip)]
+    pub feed_token: Option<String>,
+    /// Message types members may post; None allows all the server does
+    pub allowed_message_types: Option<Vec<String>>,
+    /// File categories members may post ([`UPLOAD_CATEGORIES`]); None allows all
+    pub allowed_upload_types: Option<Vec<String>>,
+    /// Minutes of membership before messages older than the join show up
+    /// (0: only with a room admin's approval); None shows all history
+    pub history_gate_minutes: Option<i32>,
+}
+
+/// What a room lets its members post, set by room admins to keep rooms
+/// text-only or free of media. Direct-message rooms also refuse anything
+/// that isn't end-to-end encrypted unless they were explicitly made
+/// unencrypted.
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct ContentPolicy {
+    pub allowed_message_types: Option<Vec<String>>,
+    pub allowed_upload_types: Option<Vec<String>>,
+    #[sqlx(rename = "type")]
+    pub room_type: String,
+    pub allow_plaintext: bool,
+}
+
+impl ContentPolicy {
+    /// Columns to select for [`ContentPolicy`]
+    pub const COLUMNS: &'static str =
+        "allowed_message_types, allowed_upload_types, type, allow_plaintext";
+
+    /// Whether a message of this type, with this metadata, may be posted;
+    /// the error is for the sender. A file's category comes from its
+    /// `metadata.mimetype`, or its message type when there is none.
+    pub fn check(
+        &self,
+        message_type: MessageType,
+        metadata: Option<&serde_json::Value>,
+    ) -> Result<(), String> {
+        // Direct messages start out end-to-end encrypted, rather than
+        // inheriting the server-readable default of rooms: only encrypted
+        // messages, and files encrypted under a key named like theirs
+        if self.room_type == Room::TYPE_DIRECT
+            && !self.allow_plaintext
+            && !(message_type == MessageType::Encrypted
+                || (message_type.is_file() && has_key_id(metadata)))
+        {
+            return Err(
+                "Direct messages must be end-to-end encrypted; send an encrypted message"
+                    .to_string(),
+            );
+        }
+        if let Some(types) = &self.allowed_message_types {
+            if !types.iter().any(|t| t == message_type.as_str()) {
+                return Err(format!("This room doesn't allow {} messages", message_type));
+            }
+        }
+        if let (true, Some(categories)) = (message_type.is_file(), &self.allowed_upload_types) {
+            let category = metadata
+                .and_then(|m| m.get("mimetype"))
+                .and_then(|m| m.as_str())
+                .map(upload_category)
+                .unwrap_or(match message_type {
+                    MessageType::Image => "image",
+                    MessageType::Video => "video",
+                    MessageType::Audio => "audio",
+                    _ => "other",
+                });
+            if !categories.iter().any(|c| c == category) {
+                return Err(format!("This room doesn't allow {} files", category));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `metadata.keyId` names the key a payload is encrypted under
+fn has_key_id(metadata: Option<&serde_json::Value>) -> bool {
+    metadata
+        .and_then(|m| m.get("keyId"))
+        .and_then(|k| k.as_str())
+        .is_some_and(|k| !k.is_empty())
+}
+
+impl Room {
+    pub const MODE_SERVER: &'static str = "server";
+    pub const MODE_E2EE: &'static str = "e2ee";
+    /// Type of a direct-message conversation, as opposed to `public` and
+    /// `private` rooms
+    pub const TYPE_DIRECT: &'static str = "direct";
+    /// Longest history gate a room may set (30 days)
+    pub const MAX_HISTORY_GATE_MINUTES: i32 = 43_200;
+
+    pub fn is_e2ee(&self) -> bool {
+        self.encryption_mode == Self::MODE_E2EE
+    }
+
+    /// Only public rooms whose messages the server can read can have a feed
+    pub fn can_have_feed(&self) -> bool {
+        self.is_public && !self.is_e2ee()
+    }
+
+    pub fn generate_feed_token() -> String {
+        URL_SAFE_NO_PAD.encode(Uuid::new_v4().as_bytes())
+    }
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -32,7 +179,7 @@ pub struct CreateRoomRequest {
     #[serde(rename = "type")]
     pub room_type: Option<String>,
 
-    #[validate(range(min = 2, max = 1000))]
+    /// Checked against the server's room limits; absent means the default
     pub max_members: Option<i32>,
 
     pub is_public: Option<bool>,
@@ -40,6 +187,29 @@ pub struct CreateRoomRequest {
     pub avatar: Option<String>,
 }
 
+/// Room settings editable by room admins; absent fields are left unchanged
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRoomRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: Option<String>,
+
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+
+    /// Checked against the server's room limits
+    pub max_members: Option<i32>,
+
+    /// Content policy; an empty list lifts the restriction
+    pub allowed_message_types: Option<Vec<MessageType>>,
+    /// Content policy, from [`UPLOAD_CATEGORIES`]; an empty list lifts the
+    /// restriction
+    pub allowed_upload_types: Option<Vec<String>>,
+
+    /// History gate in minutes (0: approval only); a negative value lifts it
+    pub history_gate_minutes: Option<i32>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RoomResponse {
@@ -51,13 +221,21 @@ pub struct RoomResponse {
     pub room_key: Option<String>,
     pub creator_id: Option<Uuid>,
     pub max_members: i32,
+    /// Members right now, so clients can tell when a room is nearly full
+    pub member_count: i64,
     pub is_public: bool,
     pub avatar: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub encryption_mode: String,
+    pub key_epoch: i32,
+    pub e2ee_since: Option<DateTime<Utc>>,
+    pub allowed_message_types: Option<Vec<String>>,
+    pub allowed_upload_types: Option<Vec<String>>,
+    pub history_gate_minutes: Option<i32>,
 }
 
 impl Room {
-    pub fn to_public_json(&self) -> RoomResponse {
+    pub fn to_public_json(&self, member_count: i64) -> RoomResponse {
         RoomResponse {
             id: self.id,
             name: self.name.clone(),
@@ -66,13 +244,20 @@ impl Room {
             room_key: None,
             creator_id: self.creator_id,
             max_members: self.max_members,
+            member_count,
             is_public: self.is_public,
             avatar: self.avatar.clone(),
             created_at: self.created_at,
+            encryption_mode: self.encryption_mode.clone(),
+            key_epoch: self.key_epoch,
+            e2ee_since: self.e2ee_since,
+            allowed_message_types: self.allowed_message_types.clone(),
+            allowed_upload_types: self.allowed_upload_types.clone(),
+            history_gate_minutes: self.history_gate_minutes,
         }
     }
 
-    pub fn to_member_json(&self) -> RoomResponse {
+    pub fn to_member_json(&self, member_count: i64) -> RoomResponse {
         RoomResponse {
             id: self.id,
             name: self.name.clone(),
@@ -81,9 +266,66 @@ impl Room {
             room_key: Some(self.encryption_key.clone()),
             creator_id: self.creator_id,
             max_members: self.max_members,
+            member_count,
             is_public: self.is_public,
             avatar: self.avatar.clone(),
             created_at: self.created_at,
+            encryption_mode: self.encryption_mode.clone(),
+            key_epoch: self.key_epoch,
+            e2ee_since: self.e2ee_since,
+            allowed_message_types: self.allowed_message_types.clone(),
+            allowed_upload_types: self.allowed_upload_types.clone(),
+            history_gate_minutes: self.history_gate_minutes,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn direct_message() -> ContentPolicy {
+        ContentPolicy {
+            room_type: Room::TYPE_DIRECT.to_string(),
+            ..ContentPolicy::default()
+        }
+    }
+
+    #[test]
+    fn direct_messages_take_encrypted_messages() {
+        let metadata = json!({ "keyId": "k1" });
+        assert!(direct_message()
+            .check(MessageType::Encrypted, Some(&metadata))
+            .is_ok());
+    }
+
+    #[test]
+    fn direct_messages_refuse_plaintext_of_every_type() {
+        let poll = json!({ "question": "Lunch?", "options": ["Yes", "No"] });
+        let policy = direct_message();
+        assert!(policy.check(MessageType::Text, None).is_err());
+        assert!(policy.check(MessageType::Sticker, None).is_err());
+        assert!(policy.check(MessageType::Poll, Some(&poll)).is_err());
+    }
+
+    #[test]
+    fn direct_messages_take_only_encrypted_files() {
+        let plain = json!({ "mimetype": "image/png" });
+        let encrypted = json!({ "mimetype": "image/png", "keyId": "k1" });
+        let policy = direct_message();
+        assert!(policy.check(MessageType::File, None).is_err());
+        assert!(policy.check(MessageType::Image, Some(&plain)).is_err());
+        assert!(policy.check(MessageType::Image, Some(&encrypted)).is_ok());
+    }
+
+    #[test]
+    fn unencrypted_direct_messages_take_plaintext() {
+        let policy = ContentPolicy {
+            allow_plaintext: true,
+            ..direct_message()
+        };
+        assert!(policy.check(MessageType::Text, None).is_ok());
+        assert!(policy.check(MessageType::Sticker, None).is_ok());
+    }
+}
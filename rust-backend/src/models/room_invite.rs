@@ -0,0 +1,36 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomInvite {
+    pub code: String,
+    pub room_id: Uuid,
+    pub created_by: Option<Uuid>,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RoomInvite {
+    /// Random, URL-safe invite code (a v4 UUID's bytes, base64url)
+    pub fn generate_code() -> String {
+        URL_SAFE_NO_PAD.encode(Uuid::new_v4().as_bytes())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateInviteRequest {
+    #[validate(range(min = 1, max = 10000))]
+    pub max_uses: Option<i32>,
+
+    /// Hours until the invite stops working; never expires when absent
+    #[validate(range(min = 1, max = 8760))]
+    pub expires_in_hours: Option<i64>,
+}
@@ -12,6 +12,7 @@ pub struct RoomMember {
     pub joined_at: DateTime<Utc>,
     pub last_read_message_id: Option<Uuid>,
     pub last_read_at: Option<DateTime<Utc>>,
+    pub muted_until: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -26,6 +27,63 @@ pub struct RoomMemberResponse {
     pub joined_at: DateTime<Utc>,
 }
 
+/// A member with the user fields the member list shows, from one join of
+/// `room_members` and `users`
+#[derive(Debug, Clone, FromRow)]
+pub struct MemberDetails {
+    pub user_id: Uuid,
+    pub role: String,
+    pub joined_at: DateTime<Utc>,
+    pub is_creator: bool,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub avatar: Option<String>,
+    pub public_key: Option<String>,
+    pub is_online: bool,
+    pub last_seen: Option<DateTime<Utc>>,
+    /// A room admin let the member see history early (history gating)
+    pub history_approved_at: Option<DateTime<Utc>>,
+}
+
+impl RoomMember {
+    pub const ROLE_ADMIN: &'static str = "admin";
+    /// What room admins may do; members may only read and post
+    pub const ADMIN_PERMISSIONS: [&'static str; 6] = [
+        "edit_room",
+        "manage_members",
+        "change_roles",
+        "manage_invites",
+        "manage_feed",
+        "view_stats",
+    ];
+
+    /// Permissions of a member with `role`, in the member list's
+    /// `permissions`
+    pub fn permissions(role: &str) -> &'static [&'static str] {
+        if role == Self::ROLE_ADMIN {
+            &Self::ADMIN_PERMISSIONS
+        } else {
+            &[]
+        }
+    }
+}
+
+/// Per-room notification preference of a member
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    /// `all`, `mentions` or `none`
+    #[sqlx(rename = "notification_level")]
+    pub level: String,
+    /// Notifications are silenced until then, whatever the level
+    #[sqlx(rename = "notifications_muted_until")]
+    pub muted_until: Option<DateTime<Utc>>,
+}
+
+impl NotificationSettings {
+    pub const LEVELS: [&'static str; 3] = ["all", "mentions", "none"];
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AddMemberRequest {
     pub user_id: Uuid,
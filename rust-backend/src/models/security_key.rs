@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+use webauthn_rs::prelude::{PublicKeyCredential, RegisterPublicKeyCredential};
+
+/// A WebAuthn credential (hardware security key or platform authenticator)
+/// bound to a user, for passwordless login or as a second factor. The
+/// credential itself (`passkey` column) isn't part of this struct.
+#[derive(Debug, Clone, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityKey {
+    pub id: Uuid,
+    pub name: String,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SecurityKey {
+    /// Columns of the struct, for queries
+    pub const COLUMNS: &'static str = "id, name, last_used_at, created_at";
+    /// Keys a user may register
+    pub const MAX_PER_USER: i64 = 10;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartKeyRegistrationRequest {
+    /// The account password, so a stolen session can't add a key
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishKeyRegistrationRequest {
+    pub challenge_id: Uuid,
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    /// The browser's `navigator.credentials.create()` result
+    pub credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct StartKeyLoginRequest {
+    #[validate(length(min = 3, max = 50))]
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinishKeyLoginRequest {
+    pub challenge_id: Uuid,
+    /// The browser's `navigator.credentials.get()` result
+    pub credential: PublicKeyCredential,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityKeySettingsRequest {
+    /// Password logins also need a security key
+    pub require_for_login: bool,
+}
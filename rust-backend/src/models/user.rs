@@ -18,6 +18,42 @@ pub struct User {
     pub is_admin: bool,
     pub is_banned: bool,
     pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing)]
+    pub email: Option<String>,
+    // The columns below came with later migrations; `sqlx(default)` keeps
+    // queries that list their columns from failing on them
+    /// Never serialized: the user must not be able to tell
+    #[serde(skip_serializing, default)]
+    #[sqlx(default)]
+    pub is_shadow_banned: bool,
+    /// Set while the account waits in the registration queue
+    #[serde(skip_serializing, default)]
+    #[sqlx(default)]
+    pub queued_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing, default)]
+    #[sqlx(default)]
+    pub queue_token: Option<Uuid>,
+    /// Set on guest accounts, which stop working and are purged after it
+    #[serde(skip_serializing, default)]
+    #[sqlx(default)]
+    pub guest_expires_at: Option<DateTime<Utc>>,
+    /// Password logins also need one of the user's security keys
+    #[serde(skip_serializing, default)]
+    #[sqlx(default)]
+    pub security_key_required: bool,
+}
+
+impl User {
+    /// Usernames of guest accounts start with this; registration refuses it
+    pub const GUEST_PREFIX: &'static str = "guest-";
+
+    pub fn is_guest(&self) -> bool {
+        self.guest_expires_at.is_some()
+    }
+
+    pub fn is_expired_guest(&self) -> bool {
+        self.guest_expires_at.is_some_and(|at| at <= Utc::now())
+    }
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -31,6 +67,27 @@ pub struct RegisterRequest {
     #[validate(length(max = 100))]
     #[serde(alias = "displayName")]
     pub display_name: Option<String>,
+
+    /// Only stored when the server runs with EMAIL_ENABLED
+    #[validate(email, length(max = 255))]
+    pub email: Option<String>,
+}
+
+/// Identity public key generated (or imported) on the user's device
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyRequest {
+    /// Base64 of a 32-byte X25519 public key
+    #[validate(length(equal = 44))]
+    pub public_key: String,
+}
+
+/// Optional name for a guest; a random one is made up otherwise
+#[derive(Debug, Default, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct GuestRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub display_name: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -43,9 +100,19 @@ pub struct LoginRequest {
 pub struct AuthResponse {
     pub message: String,
     pub token: String,
+    /// Single-use token for `POST /api/auth/refresh` once `token` expires
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
     pub user: UserResponse,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct RefreshRequest {
+    #[serde(rename = "refreshToken")]
+    #[validate(length(min = 1, max = 128))]
+    pub refresh_token: String,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UserResponse {
@@ -59,6 +126,37 @@ pub struct UserResponse {
     pub is_admin: bool,
     pub is_banned: bool,
     pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Only set in admin listings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_shadow_banned: Option<bool>,
+    /// Only set for guests: when the account stops working
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guest_expires_at: Option<DateTime<Utc>>,
+}
+
+/// Directory entry exposed to non-admins: no presence, role, or account metadata
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryUserResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub public_key: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar: Option<String>,
+}
+
+impl From<User> for DirectoryUserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            public_key: user.public_key,
+            display_name: user.display_name,
+            avatar: user.avatar,
+        }
+    }
 }
 
 impl From<User> for UserResponse {
@@ -74,6 +172,9 @@ impl From<User> for UserResponse {
             is_admin: user.is_admin,
             is_banned: user.is_banned,
             created_at: user.created_at,
+            email: user.email,
+            is_shadow_banned: None,
+            guest_expires_at: user.guest_expires_at,
         }
     }
 }
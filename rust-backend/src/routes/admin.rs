@@ -1,11 +1,13 @@
 use crate::error::{AppError, Result};
 use crate::middleware::AuthUser;
 use crate::models::{Room, User, UserResponse};
+use crate::services::{GuestAccess, OrphanCleanup, RateLimits, RoomLimits};
 use crate::state::AppState;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Extension, Json,
 };
+use serde::Deserialize;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -17,6 +19,25 @@ fn check_admin(auth: &AuthUser) -> Result<()> {
     Ok(())
 }
 
+/// Live `user_banned` / `user_unbanned` event for the admin dashboard
+async fn emit_ban_event(state: &AppState, banned: bool, shadow: bool, user: &User, by: &User) {
+    state
+        .emit_admin(
+            if banned {
+                "user_banned"
+            } else {
+                "user_unbanned"
+            },
+            serde_json::json!({
+                "userId": user.id,
+                "username": user.username,
+                "shadow": shadow,
+                "by": by.username,
+            }),
+        )
+        .await;
+}
+
 // GET /api/admin/users - List all users
 pub async fn list_users(
     State(state): State<Arc<AppState>>,
@@ -28,7 +49,13 @@ pub async fn list_users(
         .fetch_all(&state.db)
         .await?;
 
-    let user_responses: Vec<UserResponse> = users.into_iter().map(|u| u.into()).collect();
+    let user_responses: Vec<UserResponse> = users
+        .into_iter()
+        .map(|u| UserResponse {
+            is_shadow_banned: Some(u.is_shadow_banned),
+            ..u.into()
+        })
+        .collect();
 
     Ok(Json(serde_json::json!({ "users": user_responses })))
 }
@@ -106,6 +133,8 @@ pub async fn demote_user(
         .execute(&state.db)
         .await?;
 
+    crate::socket::admin::disconnect_user(&state.io, user_id).await;
+
     tracing::info!(
         "User {} demoted from admin by {}",
         user.username,
@@ -147,16 +176,25 @@ pub async fn ban_user(
         return Err(AppError::BadRequest("User is already banned".to_string()));
     }
 
-    sqlx::query("UPDATE users SET is_banned = true, is_online = false WHERE id = $1")
+    sqlx::query(
+        "UPDATE users SET is_banned = true, is_online = false, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+    // No renewing the sessions the ban just ended
+    sqlx::query("DELETE FROM refresh_tokens WHERE user_id = $1")
         .bind(user_id)
         .execute(&state.db)
         .await?;
+    state.guard_cache.invalidate_user(user_id).await;
 
     tracing::info!(
         "User {} banned by admin {}",
         user.username,
         auth.user.username
     );
+    emit_ban_event(&state, true, false, &user, &auth.user).await;
 
     Ok(Json(serde_json::json!({
         "message": "User banned successfully"
@@ -185,18 +223,95 @@ pub async fn unban_user(
         .bind(user_id)
         .execute(&state.db)
         .await?;
+    state.guard_cache.invalidate_user(user_id).await;
 
     tracing::info!(
         "User {} unbanned by admin {}",
         user.username,
         auth.user.username
     );
+    emit_ban_event(&state, false, false, &user, &auth.user).await;
 
     Ok(Json(serde_json::json!({
         "message": "User unbanned successfully"
     })))
 }
 
+// POST /api/admin/users/:id/shadow-ban - Hide a user's messages from everyone but themselves
+pub async fn shadow_ban_user(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    set_shadow_ban(&state, &auth, user_id, true).await?;
+    Ok(Json(serde_json::json!({
+        "message": "User shadow banned successfully"
+    })))
+}
+
+// POST /api/admin/users/:id/shadow-unban - Make a shadow-banned user's messages visible again
+pub async fn shadow_unban_user(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    set_shadow_ban(&state, &auth, user_id, false).await?;
+    Ok(Json(serde_json::json!({
+        "message": "Shadow ban lifted successfully"
+    })))
+}
+
+async fn set_shadow_ban(
+    state: &AppState,
+    auth: &AuthUser,
+    user_id: Uuid,
+    shadow_banned: bool,
+) -> Result<()> {
+    check_admin(auth)?;
+
+    if user_id == auth.user_id {
+        return Err(AppError::BadRequest(
+            "Cannot shadow ban yourself".to_string(),
+        ));
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    if shadow_banned && user.is_admin {
+        return Err(AppError::BadRequest(
+            "Cannot shadow ban an admin. Demote them first.".to_string(),
+        ));
+    }
+    if user.is_shadow_banned == shadow_banned {
+        return Err(AppError::BadRequest(if shadow_banned {
+            "User is already shadow banned".to_string()
+        } else {
+            "User is not shadow banned".to_string()
+        }));
+    }
+
+    sqlx::query("UPDATE users SET is_shadow_banned = $1 WHERE id = $2")
+        .bind(shadow_banned)
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+    state.guard_cache.invalidate_user(user_id).await;
+
+    tracing::info!(
+        "User {} shadow {} by admin {}",
+        user.username,
+        if shadow_banned { "banned" } else { "unbanned" },
+        auth.user.username
+    );
+    emit_ban_event(state, shadow_banned, true, &user, &auth.user).await;
+
+    Ok(())
+}
+
 // DELETE /api/admin/users/:id - Delete user
 pub async fn delete_user(
     State(state): State<Arc<AppState>>,
@@ -233,6 +348,7 @@ pub async fn delete_user(
         .bind(user_id)
         .execute(&state.db)
         .await?;
+    state.guard_cache.invalidate_user(user_id).await;
 
     tracing::info!(
         "User {} deleted by admin {}",
@@ -280,9 +396,8 @@ pub async fn list_rooms(
             "system".to_string()
         };
 
-        let mut room_resp = serde_json::to_value(room.to_member_json()).unwrap();
+        let mut room_resp = serde_json::to_value(room.to_member_json(member_count)).unwrap();
         if let Some(obj) = room_resp.as_object_mut() {
-            obj.insert("memberCount".to_string(), serde_json::json!(member_count));
             obj.insert("messageCount".to_string(), serde_json::json!(message_count));
             obj.insert("creatorName".to_string(), serde_json::json!(creator_name));
         }
@@ -310,6 +425,7 @@ pub async fn delete_room(
         .bind(room_id)
         .execute(&state.db)
         .await?;
+    state.guard_cache.invalidate_room(room_id).await;
 
     tracing::info!("Room {} deleted by admin {}", room.name, auth.user.username);
 
@@ -353,6 +469,11 @@ pub async fn get_stats(
         .fetch_one(&state.db)
         .await?;
 
+    let spam_pending: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE spam_action IS NOT NULL")
+            .fetch_one(&state.db)
+            .await?;
+
     // Get most active rooms (by message count)
     #[derive(sqlx::FromRow)]
     struct ActiveRoom {
@@ -395,6 +516,8 @@ pub async fn get_stats(
     // Get active sockets count
     let active_sockets = state.socket_users.read().await.len();
 
+    let storage = state.storage.report(&state.db).await?;
+
     Ok(Json(serde_json::json!({
         "users": {
             "total": total_users,
@@ -409,10 +532,575 @@ pub async fn get_stats(
         },
         "messages": {
             "total": total_messages,
+            "spamPending": spam_pending,
         },
         "sockets": {
             "active": active_sockets,
         },
         "activeRooms": active_rooms_json,
+        "storage": storage,
     })))
 }
+
+// POST /api/admin/storage/clean-orphans - Delete uploads no message or avatar references
+pub async fn clean_orphans(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<OrphanCleanup>> {
+    check_admin(&auth)?;
+
+    let cleanup = state.storage.clean_orphans(&state.db).await?;
+    tracing::info!(
+        "Admin {} removed {} orphaned uploads ({} bytes)",
+        auth.user.username,
+        cleanup.removed_files,
+        cleanup.removed_bytes
+    );
+    Ok(Json(cleanup))
+}
+
+#[derive(Deserialize)]
+pub struct ActivityQuery {
+    #[serde(default = "default_activity_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+}
+
+fn default_activity_limit() -> i64 {
+    50
+}
+
+#[derive(sqlx::FromRow)]
+struct ActivityMessageRow {
+    id: Uuid,
+    room_id: Uuid,
+    room_name: String,
+    content: String,
+    message_type: String,
+    key_epoch: Option<i32>,
+    spam_action: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ActivityRoomRow {
+    id: Uuid,
+    name: String,
+    is_public: bool,
+    role: String,
+    joined_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ActivityReportRow {
+    id: Uuid,
+    room_id: Uuid,
+    room_name: String,
+    spam_score: Option<f32>,
+    spam_reasons: Option<serde_json::Value>,
+    spam_action: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// GET /api/admin/users/:id/activity - One user's recent messages across
+// rooms (paginated), the rooms they're in and their spam filter history
+pub async fn user_activity(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<serde_json::Value>> {
+    check_admin(&auth)?;
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    let limit = query.limit.clamp(1, 100);
+    let offset = query.offset.max(0);
+
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    let messages = sqlx::query_as::<_, ActivityMessageRow>(
+        "SELECT m.id, m.room_id, r.name AS room_name, m.content, m.message_type,
+                m.key_epoch, m.spam_action, m.created_at, m.updated_at
+         FROM messages m
+         JOIN rooms r ON r.id = m.room_id
+         WHERE m.user_id = $1
+         ORDER BY m.created_at DESC
+         LIMIT $2 OFFSET $3",
+    )
+    .bind(user_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let rooms = sqlx::query_as::<_, ActivityRoomRow>(
+        "SELECT r.id, r.name, COALESCE(r.is_public, false) AS is_public, rm.role, rm.joined_at
+         FROM room_members rm
+         JOIN rooms r ON r.id = rm.room_id
+         WHERE rm.user_id = $1
+         ORDER BY rm.joined_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    // Everything the filter flagged or hid, including messages an admin has
+    // since approved (their score is kept)
+    let reports = sqlx::query_as::<_, ActivityReportRow>(
+        "SELECT m.id, m.room_id, r.name AS room_name, m.spam_score, m.spam_reasons,
+                m.spam_action, m.created_at
+         FROM messages m
+         JOIN rooms r ON r.id = m.room_id
+         WHERE m.user_id = $1
+           AND (m.spam_action IS NOT NULL OR m.spam_score >= $2)
+         ORDER BY m.created_at DESC
+         LIMIT 100",
+    )
+    .bind(user_id)
+    .bind(state.config.spam_flag_threshold)
+    .fetch_all(&state.db)
+    .await?;
+
+    let messages: Vec<serde_json::Value> = messages
+        .into_iter()
+        .map(|row| {
+            serde_json::json!({
+                "id": row.id,
+                "roomId": row.room_id,
+                "roomName": row.room_name,
+                // E2EE messages are ciphertext the server can't read
+                "content": row.content,
+                "encrypted": row.key_epoch.is_some(),
+                "messageType": row.message_type,
+                "spamAction": row.spam_action,
+                "createdAt": row.created_at,
+                "updatedAt": row.updated_at,
+            })
+        })
+        .collect();
+
+    let rooms: Vec<serde_json::Value> = rooms
+        .into_iter()
+        .map(|row| {
+            serde_json::json!({
+                "id": row.id,
+                "name": row.name,
+                "isPublic": row.is_public,
+                "role": row.role,
+                "joinedAt": row.joined_at,
+            })
+        })
+        .collect();
+
+    let reports: Vec<serde_json::Value> = reports
+        .into_iter()
+        .map(|row| {
+            serde_json::json!({
+                "messageId": row.id,
+                "roomId": row.room_id,
+                "roomName": row.room_name,
+                "spamScore": row.spam_score,
+                "spamReasons": row.spam_reasons,
+                // null once approved
+                "spamAction": row.spam_action,
+                "createdAt": row.created_at,
+            })
+        })
+        .collect();
+
+    tracing::info!(
+        "Admin {} viewed the activity of {}",
+        auth.user.username,
+        user.username
+    );
+
+    let user = UserResponse {
+        is_shadow_banned: Some(user.is_shadow_banned),
+        ..user.into()
+    };
+
+    Ok(Json(serde_json::json!({
+        "user": user,
+        "messages": messages,
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+        "rooms": rooms,
+        "reports": reports,
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct SpamQuery {
+    #[serde(default = "default_spam_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+    /// `flagged` or `hidden`; both when absent
+    action: Option<String>,
+}
+
+fn default_spam_limit() -> i64 {
+    50
+}
+
+#[derive(sqlx::FromRow)]
+struct SpamRow {
+    id: Uuid,
+    room_id: Uuid,
+    room_name: String,
+    user_id: Uuid,
+    username: String,
+    content: String,
+    message_type: String,
+    spam_score: Option<f32>,
+    spam_reasons: Option<serde_json::Value>,
+    spam_action: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+// GET /api/admin/spam - Messages flagged or hidden by the spam filter, newest first
+pub async fn list_spam(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Query(query): Query<SpamQuery>,
+) -> Result<Json<serde_json::Value>> {
+    check_admin(&auth)?;
+
+    let actions: Vec<&str> = match query.action.as_deref() {
+        None | Some("") => vec!["flagged", "hidden"],
+        Some(action @ ("flagged" | "hidden")) => vec![action],
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "action must be flagged or hidden".to_string(),
+            ))
+        }
+    };
+    let limit = query.limit.clamp(1, 100);
+    let offset = query.offset.max(0);
+
+    let rows = sqlx::query_as::<_, SpamRow>(
+        "SELECT m.id, m.room_id, r.name AS room_name, m.user_id, u.username,
+                m.content, m.message_type, m.spam_score, m.spam_reasons, m.spam_action,
+                m.created_at
+         FROM messages m
+         JOIN rooms r ON r.id = m.room_id
+         JOIN users u ON u.id = m.user_id
+         WHERE m.spam_action = ANY($1)
+         ORDER BY m.created_at DESC
+         LIMIT $2 OFFSET $3",
+    )
+    .bind(&actions)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+
+    let messages: Vec<serde_json::Value> = rows
+        .into_iter()
+        .map(|row| {
+            serde_json::json!({
+                "id": row.id,
+                "roomId": row.room_id,
+                "roomName": row.room_name,
+                "content": row.content,
+                "messageType": row.message_type,
+                "spamScore": row.spam_score,
+                "spamReasons": row.spam_reasons,
+                "spamAction": row.spam_action,
+                "createdAt": row.created_at,
+                "user": {
+                    "id": row.user_id,
+                    "username": row.username,
+                },
+            })
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "messages": messages,
+        "limit": limit,
+        "offset": offset,
+    })))
+}
+
+// POST /api/admin/spam/:id/approve - Clear a spam flag; hidden messages become visible
+pub async fn approve_spam(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(message_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    check_admin(&auth)?;
+
+    // The score is kept for the record; only the pending action is cleared
+    let updated = sqlx::query(
+        "UPDATE messages SET spam_action = NULL WHERE id = $1 AND spam_action IS NOT NULL",
+    )
+    .bind(message_id)
+    .execute(&state.db)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            "No pending spam flag for this message".to_string(),
+        ));
+    }
+
+    tracing::info!(
+        "Spam flag on message {} cleared by admin {}",
+        message_id,
+        auth.user.username
+    );
+
+    Ok(Json(serde_json::json!({
+        "message": "Message approved"
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct MaintenanceBody {
+    enabled: bool,
+    message: Option<String>,
+}
+
+// GET /api/admin/maintenance - Current read-only maintenance status
+pub async fn get_maintenance(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>> {
+    check_admin(&auth)?;
+    Ok(Json(serde_json::json!({
+        "maintenance": state.maintenance.status()
+    })))
+}
+
+// POST /api/admin/maintenance - Turn read-only maintenance mode on or off
+pub async fn set_maintenance(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Json(body): Json<MaintenanceBody>,
+) -> Result<Json<serde_json::Value>> {
+    check_admin(&auth)?;
+
+    if body.enabled {
+        let message = body
+            .message
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty());
+        if message.as_ref().is_some_and(|m| m.chars().count() > 500) {
+            return Err(AppError::Validation(
+                "Message must be at most 500 characters".to_string(),
+            ));
+        }
+        state.maintenance.enable(message);
+    } else {
+        state.maintenance.disable();
+    }
+    state.broadcast_maintenance().await;
+
+    tracing::info!(
+        "Maintenance mode {} by admin {}",
+        if body.enabled { "enabled" } else { "disabled" },
+        auth.user.username
+    );
+
+    Ok(Json(serde_json::json!({
+        "maintenance": state.maintenance.status()
+    })))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBody {
+    rooms: Option<RoomLimitsBody>,
+    /// Replaces the whole list; an empty list turns the filter off
+    blocked_words: Option<Vec<String>>,
+    guests: Option<GuestAccessBody>,
+    user_directory_public: Option<bool>,
+    rate_limits: Option<RateLimitsBody>,
+}
+
+/// Rate limit changes; absent fields are left as they are
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitsBody {
+    invite_pages_per_minute: Option<usize>,
+    guest_sessions_per_minute: Option<usize>,
+}
+
+/// Guest mode changes; absent fields are left as they are
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuestAccessBody {
+    enabled: Option<bool>,
+    room_ids: Option<Vec<Uuid>>,
+    can_post: Option<bool>,
+    session_hours: Option<i64>,
+    max_guests: Option<i64>,
+}
+
+/// Room size limits to change; absent fields are left as they are
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomLimitsBody {
+    default_max_members: Option<i32>,
+    max_members_cap: Option<i32>,
+}
+
+fn settings_json(state: &AppState) -> serde_json::Value {
+    serde_json::json!({
+        "settings": {
+            "rooms": state.settings.room_limits(),
+            "blockedWords": state.settings.blocked_words(),
+            "guests": state.settings.guest_access(),
+            "userDirectoryPublic": state.settings.user_directory_public(),
+            "rateLimits": state.settings.rate_limits(),
+        }
+    })
+}
+
+// GET /api/admin/settings - Server settings admins can change at runtime
+pub async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>> {
+    check_admin(&auth)?;
+    Ok(Json(settings_json(&state)))
+}
+
+// PATCH /api/admin/settings - Change server settings (kept across restarts)
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Json(body): Json<SettingsBody>,
+) -> Result<Json<serde_json::Value>> {
+    check_admin(&auth)?;
+
+    if let Some(rooms) = body.rooms {
+        let current = state.settings.room_limits();
+        let limits = RoomLimits {
+            default_max_members: rooms
+                .default_max_members
+                .unwrap_or(current.default_max_members),
+            max_members_cap: rooms.max_members_cap.unwrap_or(current.max_members_cap),
+        };
+        // Existing rooms keep their size; the cap applies when one is next set
+        state.settings.set_room_limits(&state.db, limits).await?;
+        tracing::info!(
+            "Room limits set to default {} / cap {} by admin {}",
+            limits.default_max_members,
+            limits.max_members_cap,
+            auth.user.username
+        );
+    }
+
+    if let Some(words) = body.blocked_words {
+        let words = state.settings.set_blocked_words(&state.db, &words).await?;
+        tracing::info!(
+            "Blocked word list set to {} words by admin {}",
+            words.len(),
+            auth.user.username
+        );
+    }
+
+    if let Some(guests) = body.guests {
+        let current = state.settings.guest_access();
+        let mut room_ids = guests.room_ids.unwrap_or(current.room_ids);
+        room_ids.sort();
+        room_ids.dedup();
+        let public: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM rooms WHERE id = ANY($1) AND is_public = true",
+        )
+        .bind(&room_ids)
+        .fetch_one(&state.db)
+        .await?;
+        if public != room_ids.len() as i64 {
+            return Err(AppError::Validation(
+                "Guests can only be let into existing public rooms".to_string(),
+            ));
+        }
+
+        let guests = GuestAccess {
+            enabled: guests.enabled.unwrap_or(current.enabled),
+            room_ids,
+            can_post: guests.can_post.unwrap_or(current.can_post),
+            session_hours: guests.session_hours.unwrap_or(current.session_hours),
+            max_guests: guests.max_guests.unwrap_or(current.max_guests),
+        };
+        state
+            .settings
+            .set_guest_access(&state.db, guests.clone())
+            .await?;
+
+        // Guests already in rooms that were dropped leave them now
+        let removed = sqlx::query_as::<_, (Uuid, Uuid)>(
+            "DELETE FROM room_members rm USING users u
+             WHERE rm.user_id = u.id AND u.guest_expires_at IS NOT NULL
+               AND NOT rm.room_id = ANY($1)
+             RETURNING rm.room_id, rm.user_id",
+        )
+        .bind(&guests.room_ids)
+        .fetch_all(&state.db)
+        .await?;
+        for (room_id, user_id) in removed {
+            state
+                .guard_cache
+                .invalidate_membership(room_id, user_id)
+                .await;
+            state.leave_user_sockets(user_id, room_id).await;
+        }
+
+        tracing::info!(
+            "Guest access set to enabled={} ({} rooms, can_post={}, {} h, at most {}) by admin {}",
+            guests.enabled,
+            guests.room_ids.len(),
+            guests.can_post,
+            guests.session_hours,
+            guests.max_guests,
+            auth.user.username
+        );
+    }
+
+    if let Some(public) = body.user_directory_public {
+        state
+            .settings
+            .set_user_directory_public(&state.db, public)
+            .await?;
+        tracing::info!(
+            "User directory set to public={} by admin {}",
+            public,
+            auth.user.username
+        );
+    }
+
+    if let Some(rate_limits) = body.rate_limits {
+        let current = state.settings.rate_limits();
+        let limits = RateLimits {
+            invite_pages_per_minute: rate_limits
+                .invite_pages_per_minute
+                .unwrap_or(current.invite_pages_per_minute),
+            guest_sessions_per_minute: rate_limits
+                .guest_sessions_per_minute
+                .unwrap_or(current.guest_sessions_per_minute),
+        };
+        state.settings.set_rate_limits(&state.db, limits).await?;
+        tracing::info!(
+            "Rate limits set to {} invite pages / {} guest sessions a minute by admin {}",
+            limits.invite_pages_per_minute,
+            limits.guest_sessions_per_minute,
+            auth.user.username
+        );
+    }
+
+    Ok(Json(settings_json(&state)))
+}
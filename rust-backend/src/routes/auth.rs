@@ -1,18 +1,42 @@
+use super::security_keys::load_passkeys;
 use crate::error::{AppError, Result};
 use crate::middleware::{AuthUser, ValidatedJson};
-use crate::models::{AuthResponse, LoginRequest, RegisterRequest, User, UserResponse};
-use crate::services::{AuthService, CryptoService};
+use crate::models::{
+    ApiToken, AuthResponse, CreateApiTokenRequest, DirectoryUserResponse, GuestRequest,
+    LoginRequest, PublicKeyRequest, RefreshRequest, RegisterRequest, User, UserResponse,
+};
+use crate::services::{check_name, AuthService, CryptoService, NameKind};
 use crate::state::AppState;
-use axum::{extract::State, Extension, Json};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{Duration, Utc};
 use std::sync::Arc;
+use uuid::Uuid;
 
 pub async fn register(
     State(state): State<Arc<AppState>>,
     ValidatedJson(req): ValidatedJson<RegisterRequest>,
-) -> Result<Json<AuthResponse>> {
+) -> Result<Response> {
     let auth_service = AuthService::new(state.config.clone());
     let crypto_service = CryptoService::new();
 
+    let blocked = state.settings.blocked_words();
+    check_name(NameKind::Username, &req.username, &blocked).map_err(AppError::Validation)?;
+    if req.username.to_lowercase().starts_with(User::GUEST_PREFIX) {
+        return Err(AppError::Validation(format!(
+            "Usernames starting with '{}' are reserved for guests",
+            User::GUEST_PREFIX
+        )));
+    }
+    if let Some(display_name) = &req.display_name {
+        check_name(NameKind::DisplayName, display_name, &blocked).map_err(AppError::Validation)?;
+    }
+
     // Check if username already exists
     let existing = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
         .bind(&req.username)
@@ -23,11 +47,33 @@ pub async fn register(
         return Err(AppError::Conflict("Username already exists".to_string()));
     }
 
+    // Email is an opt-in server feature; drop it silently when disabled
+    let email = req
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|e| state.config.email_enabled && !e.is_empty())
+        .map(str::to_lowercase);
+
+    if let Some(email) = &email {
+        let taken =
+            sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE email = $1)")
+                .bind(email)
+                .fetch_one(&state.db)
+                .await?;
+
+        if taken {
+            return Err(AppError::Conflict("Email already in use".to_string()));
+        }
+    }
+
     // Check if this is the first user
     let user_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
         .fetch_one(&state.db)
         .await?;
     let is_first_user = user_count == 0;
+    // The first account (the admin) is never made to wait
+    let queued = !is_first_user && state.registration_queue.should_queue(&state.db).await?;
 
     // Generate keypair
     let (public_key, _private_key) = crypto_service.generate_keypair()?;
@@ -37,8 +83,10 @@ pub async fn register(
 
     // Create user
     let user = sqlx::query_as::<_, User>(
-        "INSERT INTO users (username, password_hash, public_key, display_name, is_admin)
-         VALUES ($1, $2, $3, $4, $5)
+        "INSERT INTO users (username, password_hash, public_key, display_name, is_admin, email,
+                            queued_at, queue_token)
+         VALUES ($1, $2, $3, $4, $5, $6,
+                 CASE WHEN $7 THEN NOW() END, CASE WHEN $7 THEN gen_random_uuid() END)
          RETURNING *",
     )
     .bind(&req.username)
@@ -46,11 +94,46 @@ pub async fn register(
     .bind(&public_key)
     .bind(req.display_name.as_ref().unwrap_or(&req.username))
     .bind(is_first_user)
+    .bind(&email)
+    .bind(queued)
     .fetch_one(&state.db)
     .await?;
 
+    state
+        .emit_admin(
+            "user_registered",
+            serde_json::json!({
+                "userId": user.id,
+                "username": user.username,
+                "queued": queued,
+                "createdAt": user.created_at,
+            }),
+        )
+        .await;
+
+    // Queued: no token until the account is activated; the client polls
+    // the queue endpoint and logs in afterwards
+    if let Some(queue_token) = user.queue_token.filter(|_| queued) {
+        let status = state
+            .registration_queue
+            .status(&state.db, queue_token)
+            .await?;
+        tracing::info!("New user queued for activation: {}", user.username);
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "message": "Registration accepted. Your account will be activated shortly.",
+                "queued": true,
+                "queueToken": queue_token,
+                "status": status,
+            })),
+        )
+            .into_response());
+    }
+
     // Generate token
     let token = auth_service.generate_token(user.id)?;
+    let refresh_token = auth_service.issue_refresh_token(&state.db, user.id).await?;
 
     if is_first_user {
         tracing::info!("First user registered as ADMIN: {}", user.username);
@@ -61,14 +144,30 @@ pub async fn register(
     Ok(Json(AuthResponse {
         message: "User registered successfully".to_string(),
         token,
+        refresh_token,
         user: user.into(),
-    }))
+    })
+    .into_response())
+}
+
+// GET /api/auth/registration-queue/:token - Position of a queued registration
+pub async fn registration_status(
+    State(state): State<Arc<AppState>>,
+    Path(queue_token): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let status = state
+        .registration_queue
+        .status(&state.db, queue_token)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Unknown queue token".to_string()))?;
+
+    Ok(Json(serde_json::json!({ "status": status })))
 }
 
 pub async fn login(
     State(state): State<Arc<AppState>>,
     ValidatedJson(req): ValidatedJson<LoginRequest>,
-) -> Result<Json<AuthResponse>> {
+) -> Result<Response> {
     let auth_service = AuthService::new(state.config.clone());
 
     // Find user
@@ -78,45 +177,249 @@ pub async fn login(
         .await?
         .ok_or_else(|| AppError::Authentication("Invalid credentials".to_string()))?;
 
+    // Guests have no password to log in with
+    if user.is_guest() {
+        return Err(AppError::Authentication("Invalid credentials".to_string()));
+    }
+
     // Verify password
     let valid = auth_service.verify_password(&req.password, &user.password_hash)?;
     if !valid {
         return Err(AppError::Authentication("Invalid credentials".to_string()));
     }
 
-    // Check if banned
+    check_can_sign_in(&user)?;
+
+    // The password alone isn't enough: finish with a security key at
+    // POST /api/auth/webauthn/login/finish
+    if user.security_key_required {
+        let passkeys = load_passkeys(&state.db, user.id).await?;
+        if !passkeys.is_empty() {
+            let passkeys: Vec<_> = passkeys.into_iter().map(|(_, p)| p).collect();
+            let (challenge_id, options) = state.security_keys.start_login(user.id, &passkeys)?;
+            return Ok(Json(serde_json::json!({
+                "message": "Security key required",
+                "securityKeyRequired": true,
+                "challengeId": challenge_id,
+                "publicKey": options,
+            }))
+            .into_response());
+        }
+    }
+
+    Ok(Json(sign_in(&state, user, "Login successful").await?).into_response())
+}
+
+/// Banned and still-queued accounts can't sign in, whatever the credential
+pub(super) fn check_can_sign_in(user: &User) -> Result<()> {
     if user.is_banned {
         return Err(AppError::Authorization(
             "Your account has been banned. Please contact an administrator.".to_string(),
         ));
     }
 
+    if user.queued_at.is_some() {
+        return Err(AppError::Authorization(
+            "Your account is waiting in the registration queue. Please try again shortly."
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Start a session for a user whose credentials were checked
+pub(super) async fn sign_in(state: &AppState, user: User, message: &str) -> Result<AuthResponse> {
+    let auth_service = AuthService::new(state.config.clone());
+
     // Update last seen
-    sqlx::query("UPDATE users SET last_seen = NOW() WHERE id = $1")
-        .bind(user.id)
-        .execute(&state.db)
-        .await?;
+    if !state.config.paranoid_mode {
+        sqlx::query("UPDATE users SET last_seen = NOW(), updated_at = NOW() WHERE id = $1")
+            .bind(user.id)
+            .execute(&state.db)
+            .await?;
+    }
 
     // Generate token
     let token = auth_service.generate_token(user.id)?;
+    let refresh_token = auth_service.issue_refresh_token(&state.db, user.id).await?;
 
     tracing::info!("User logged in: {}", user.username);
 
+    Ok(AuthResponse {
+        message: message.to_string(),
+        token,
+        refresh_token,
+        user: user.into(),
+    })
+}
+
+// POST /api/auth/guest - Start a guest session, when admins allow it: a
+// throwaway account in the designated public rooms that stops working (and
+// is purged) after the configured hours. There is no refresh token. Limited
+// like registration: a per-minute total (GUEST_SESSION_LIMIT), nothing while
+// the registration queue is holding signups back, and `maxGuests` at once.
+pub async fn guest(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(req): ValidatedJson<GuestRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>)> {
+    let guests = state.settings.guest_access();
+    if !guests.enabled {
+        return Err(AppError::Authorization(
+            "Guest access is not enabled on this server".to_string(),
+        ));
+    }
+    // Guests have no password to log in with once activated, so instead of
+    // queueing them they're turned away until the burst is over
+    if !state.settings.guest_sessions.try_acquire()
+        || state.registration_queue.should_queue(&state.db).await?
+    {
+        return Err(AppError::RateLimited(
+            "Too many new accounts right now; try again in a few minutes".to_string(),
+        ));
+    }
+
+    let suffix = Uuid::new_v4().simple().to_string();
+    let username = format!("{}{}", User::GUEST_PREFIX, &suffix[..10]);
+    let display_name = match req.display_name.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => {
+            check_name(NameKind::DisplayName, name, &state.settings.blocked_words())
+                .map_err(AppError::Validation)?;
+            format!("{} (guest)", name)
+        }
+        _ => format!("Guest {}", &suffix[..4]),
+    };
+    let expires_at = Utc::now() + Duration::hours(guests.session_hours);
+
+    // Lock the guest rooms first: guest sessions then start one at a time,
+    // so neither the guest cap nor a room's `max_members` can be raced past
+    let mut tx = state.db.begin().await?;
+    sqlx::query("SELECT id FROM rooms WHERE id = ANY($1) ORDER BY id FOR UPDATE")
+        .bind(&guests.room_ids)
+        .execute(&mut *tx)
+        .await?;
+    let live: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE guest_expires_at > NOW()")
+        .fetch_one(&mut *tx)
+        .await?;
+    if live >= guests.max_guests {
+        return Err(AppError::RateLimited(
+            "This server has as many guests as it allows; try again later".to_string(),
+        ));
+    }
+
+    // No password: `!` is never a valid bcrypt hash
+    let user = sqlx::query_as::<_, User>(
+        "INSERT INTO users (username, password_hash, display_name, guest_expires_at)
+         VALUES ($1, '!', $2, $3)
+         RETURNING *",
+    )
+    .bind(&username)
+    .bind(&display_name)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // Public rooms with space left; the others are skipped
+    let room_ids = sqlx::query_scalar::<_, Uuid>(
+        "INSERT INTO room_members (room_id, user_id, role)
+         SELECT r.id, $1, 'member' FROM rooms r
+          WHERE r.id = ANY($2) AND r.is_public = true
+            AND (SELECT COUNT(*) FROM room_members m WHERE m.room_id = r.id) < r.max_members
+         RETURNING room_id",
+    )
+    .bind(user.id)
+    .bind(&guests.room_ids)
+    .fetch_all(&mut *tx)
+    .await?;
+    if room_ids.is_empty() {
+        return Err(AppError::BadRequest(
+            "No room is open to guests right now".to_string(),
+        ));
+    }
+    tx.commit().await?;
+
+    let token = AuthService::new(state.config.clone()).generate_token_until(user.id, expires_at)?;
+
+    tracing::info!(
+        "Guest {} joined {} room(s) until {}",
+        user.username,
+        room_ids.len(),
+        expires_at
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "message": "Guest session started",
+            "token": token,
+            "expiresAt": expires_at,
+            "user": UserResponse::from(user),
+            "roomIds": room_ids,
+            "canPost": guests.can_post,
+        })),
+    ))
+}
+
+// POST /api/auth/refresh - Trade a refresh token for a new token pair once
+// the access token has expired. Each refresh token works once.
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(req): ValidatedJson<RefreshRequest>,
+) -> Result<Json<AuthResponse>> {
+    let auth_service = AuthService::new(state.config.clone());
+
+    let expired = || AppError::Authentication("Session expired, please log in again".to_string());
+    let user_id = auth_service
+        .redeem_refresh_token(&state.db, &req.refresh_token)
+        .await?
+        .ok_or_else(expired)?;
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(expired)?;
+
+    if user.is_banned {
+        return Err(AppError::Authorization(
+            "Your account has been banned. Please contact an administrator.".to_string(),
+        ));
+    }
+
+    let token = auth_service.generate_token(user.id)?;
+    let refresh_token = auth_service.issue_refresh_token(&state.db, user.id).await?;
+
     Ok(Json(AuthResponse {
-        message: "Login successful".to_string(),
+        message: "Session renewed".to_string(),
         token,
+        refresh_token,
         user: user.into(),
     }))
 }
 
+// DELETE /api/auth/refresh - Revoke a refresh token at logout. Needs no
+// access token: the refresh token outlives it and is what must not leak.
+pub async fn revoke_refresh(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(req): ValidatedJson<RefreshRequest>,
+) -> Result<Json<serde_json::Value>> {
+    AuthService::new(state.config.clone())
+        .revoke_refresh_token(&state.db, &req.refresh_token)
+        .await?;
+    Ok(Json(
+        serde_json::json!({ "message": "Refresh token revoked" }),
+    ))
+}
+
 pub async fn logout(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthUser>,
 ) -> Result<Json<serde_json::Value>> {
-    sqlx::query("UPDATE users SET is_online = false, last_seen = NOW() WHERE id = $1")
-        .bind(auth.user_id)
-        .execute(&state.db)
-        .await?;
+    // Sessions still connected elsewhere keep the user online
+    if !state.config.paranoid_mode && !state.is_user_online(auth.user_id).await {
+        sqlx::query("UPDATE users SET is_online = false, last_seen = NOW(), updated_at = NOW() WHERE id = $1")
+            .bind(auth.user_id)
+            .execute(&state.db)
+            .await?;
+    }
 
     Ok(Json(
         serde_json::json!({ "message": "Logged out successfully" }),
@@ -129,16 +432,200 @@ pub async fn me(Extension(auth): Extension<AuthUser>) -> Result<Json<serde_json:
     ))
 }
 
-pub async fn list_users(State(state): State<Arc<AppState>>) -> Result<Json<serde_json::Value>> {
+// PUT /api/auth/public-key - Publish the identity key held by the user's device
+pub async fn update_public_key(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    ValidatedJson(req): ValidatedJson<PublicKeyRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let decoded = BASE64
+        .decode(&req.public_key)
+        .map_err(|_| AppError::Validation("publicKey must be base64".to_string()))?;
+    if decoded.len() != 32 {
+        return Err(AppError::Validation(
+            "publicKey must be a 32-byte X25519 key".to_string(),
+        ));
+    }
+
+    if auth.user.public_key.as_deref() == Some(req.public_key.as_str()) {
+        return Ok(Json(serde_json::json!({ "publicKey": req.public_key })));
+    }
+
+    sqlx::query("UPDATE users SET public_key = $1, updated_at = NOW() WHERE id = $2")
+        .bind(&req.public_key)
+        .bind(auth.user_id)
+        .execute(&state.db)
+        .await?;
+
+    tracing::info!("User {} published a new identity key", auth.user.username);
+
+    // Let the user's rooms know right away. Clients compare against the keys
+    // they remember themselves, so this only speeds up the warning.
+    let rooms: Vec<String> =
+        sqlx::query_scalar::<_, Uuid>("SELECT room_id FROM room_members WHERE user_id = $1")
+            .bind(auth.user_id)
+            .fetch_all(&state.db)
+            .await?
+            .into_iter()
+            .map(|id| id.to_string())
+            .collect();
+    if !rooms.is_empty() {
+        state
+            .io
+            .within(rooms)
+            .emit(
+                "public_key_changed",
+                &serde_json::json!({
+                    "userId": auth.user_id,
+                    "username": auth.user.username,
+                    "publicKey": req.public_key,
+                }),
+            )
+            .await
+            .ok();
+    }
+
+    Ok(Json(serde_json::json!({ "publicKey": req.public_key })))
+}
+
+// GET /api/auth/users - User directory (admins always, others only when the
+// userDirectoryPublic setting is on)
+pub async fn list_users(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>> {
+    if !auth.user.is_admin && !state.settings.user_directory_public() {
+        return Err(AppError::Authorization(
+            "User directory is not public on this server".to_string(),
+        ));
+    }
+
     let users = sqlx::query_as::<_, User>(
-        "SELECT id, username, password_hash, public_key, display_name, avatar,
-         is_online, last_seen, is_admin, is_banned, created_at
-         FROM users ORDER BY username ASC",
+        "SELECT * FROM users WHERE is_banned = false AND guest_expires_at IS NULL
+         ORDER BY username ASC",
     )
     .fetch_all(&state.db)
     .await?;
 
-    let user_responses: Vec<UserResponse> = users.into_iter().map(|u| u.into()).collect();
+    let user_responses: Vec<DirectoryUserResponse> = users.into_iter().map(|u| u.into()).collect();
 
     Ok(Json(serde_json::json!({ "users": user_responses })))
 }
+
+// GET /api/auth/tokens - The user's API tokens (never their secrets)
+pub async fn list_api_tokens(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>> {
+    let tokens = sqlx::query_as::<_, ApiToken>(&format!(
+        "SELECT {} FROM api_tokens WHERE user_id = $1 ORDER BY created_at DESC",
+        ApiToken::COLUMNS
+    ))
+    .bind(auth.user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(serde_json::json!({ "tokens": tokens })))
+}
+
+// POST /api/auth/tokens - Create a named, scoped API token for scripts and
+// bridges. The secret is in this response only.
+pub async fn create_api_token(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    ValidatedJson(req): ValidatedJson<CreateApiTokenRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>)> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation("Token name is required".to_string()));
+    }
+    let mut scopes = req.scopes;
+    scopes.sort();
+    scopes.dedup();
+    if let Some(unknown) = scopes
+        .iter()
+        .find(|s| !ApiToken::SCOPES.contains(&s.as_str()))
+    {
+        return Err(AppError::Validation(format!(
+            "Unknown scope '{}' (use {})",
+            unknown,
+            ApiToken::SCOPES.join(", ")
+        )));
+    }
+
+    let room_ids = match req.room_ids {
+        Some(mut ids) => {
+            ids.sort();
+            ids.dedup();
+            let joined: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM room_members WHERE user_id = $1 AND room_id = ANY($2)",
+            )
+            .bind(auth.user_id)
+            .bind(&ids)
+            .fetch_one(&state.db)
+            .await?;
+            if joined != ids.len() as i64 {
+                return Err(AppError::Validation(
+                    "A token can only be limited to rooms you're in".to_string(),
+                ));
+            }
+            Some(ids)
+        }
+        None => None,
+    };
+
+    let held: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM api_tokens WHERE user_id = $1")
+        .bind(auth.user_id)
+        .fetch_one(&state.db)
+        .await?;
+    if held >= ApiToken::MAX_PER_USER {
+        return Err(AppError::Validation(format!(
+            "You can have at most {} API tokens; revoke one first",
+            ApiToken::MAX_PER_USER
+        )));
+    }
+
+    let expires_at = req
+        .expires_in_days
+        .map(|days| Utc::now() + Duration::days(days));
+    let (token, api_token) = AuthService::new(state.config.clone())
+        .issue_api_token(
+            &state.db,
+            auth.user_id,
+            name,
+            &scopes,
+            room_ids.as_deref(),
+            expires_at,
+        )
+        .await?;
+
+    tracing::info!(
+        "API token '{}' created by {} with scopes {:?}",
+        api_token.name,
+        auth.user.username,
+        api_token.scopes
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "token": token, "apiToken": api_token })),
+    ))
+}
+
+// DELETE /api/auth/tokens/:id - Revoke one API token
+pub async fn revoke_api_token(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(token_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let result = sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND user_id = $2")
+        .bind(token_id)
+        .bind(auth.user_id)
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("API token not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({ "message": "API token revoked" })))
+}
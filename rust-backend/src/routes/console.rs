@@ -0,0 +1,339 @@
+use super::feed::escape;
+use crate::database::acquire_timeouts;
+use crate::services::{server_errors, TorService};
+use crate::state::AppState;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Form,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+use sodiumoxide::crypto::generichash;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Shown by the browser's login prompt
+const REALM: &str = "TOR Chat operator console";
+
+/// Check the request against OPERATOR_TOKEN, sent as a bearer token or as
+/// the password of HTTP Basic auth (any user name). Returns the token, or
+/// the response to send instead: 404 while the console is off, so it can't
+/// be told apart from a missing page, and a login prompt otherwise.
+fn check_operator<'a>(state: &'a AppState, headers: &HeaderMap) -> Result<&'a str, Response> {
+    let Some(token) = state.config.operator_token.as_deref() else {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            if let Some(bearer) = v.strip_prefix("Bearer ") {
+                return Some(bearer.to_string());
+            }
+            let basic = BASE64.decode(v.strip_prefix("Basic ")?.trim()).ok()?;
+            let credentials = String::from_utf8(basic).ok()?;
+            credentials
+                .split_once(':')
+                .map(|(_, password)| password.to_string())
+        });
+    match provided {
+        Some(p) if sodiumoxide::utils::memcmp(p.as_bytes(), token.as_bytes()) => Ok(token),
+        _ => Err((
+            StatusCode::UNAUTHORIZED,
+            [
+                (
+                    header::WWW_AUTHENTICATE,
+                    format!("Basic realm=\"{}\", charset=\"UTF-8\"", REALM),
+                ),
+                (header::CACHE_CONTROL, "no-store".to_string()),
+            ],
+            "Operator token required",
+        )
+            .into_response()),
+    }
+}
+
+/// Form token for the console's POSTs. Browsers resend Basic credentials on
+/// their own, so a form on another site could otherwise flip maintenance mode.
+fn form_token(operator_token: &str) -> String {
+    let hash = generichash::hash(
+        format!("admin-console:{}", operator_token).as_bytes(),
+        None,
+        None,
+    )
+    .unwrap();
+    BASE64.encode(hash.as_ref())
+}
+
+fn timestamp(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// One `<tr>` of a status table; `value` is escaped
+fn row(label: &str, value: impl AsRef<str>) -> String {
+    format!(
+        "<tr><th>{}</th><td>{}</td></tr>\n",
+        label,
+        escape(value.as_ref())
+    )
+}
+
+// GET /admin-console - Operator dashboard: health, Tor, metrics and maintenance mode (OPERATOR_TOKEN)
+pub async fn console_page(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let token = match check_operator(&state, &headers) {
+        Ok(token) => token,
+        Err(denied) => return denied,
+    };
+
+    // Health
+    let started = Instant::now();
+    let db_ok = sqlx::query("SELECT 1").execute(&state.db).await.is_ok();
+    let db_ms = started.elapsed().as_millis();
+    let counts = sqlx::query_as::<_, (i64, i64, i64)>(
+        "SELECT
+            (SELECT COUNT(*) FROM users WHERE guest_expires_at IS NULL),
+            (SELECT COUNT(*) FROM rooms),
+            (SELECT COUNT(*) FROM messages WHERE created_at > NOW() - INTERVAL '24 hours')",
+    )
+    .fetch_one(&state.db)
+    .await
+    .ok();
+
+    let max = state.config.db_max_connections.max(1);
+    let open = state.db.size();
+    let idle = state.db.num_idle() as u32;
+
+    let mut health = String::new();
+    health += &row(
+        "Database",
+        if db_ok {
+            format!("OK ({} ms)", db_ms)
+        } else {
+            "Unreachable".to_string()
+        },
+    );
+    health += &row(
+        "Pool",
+        format!(
+            "{} in use, {} idle, {} max",
+            open.saturating_sub(idle),
+            idle,
+            max
+        ),
+    );
+    health += &row("Pool acquire timeouts", acquire_timeouts().to_string());
+    health += &row("API 5xx responses", server_errors().to_string());
+    if let Some((users, rooms, messages)) = counts {
+        health += &row("Users", users.to_string());
+        health += &row("Rooms", rooms.to_string());
+        health += &row("Messages (24h)", messages.to_string());
+    }
+
+    // Tor
+    let tor_service = TorService::new(state.config.clone());
+    let info = tor_service.get_connection_info();
+    let mut tor = String::new();
+    if info.enabled {
+        let connected = tor_service.check_connection().await.unwrap_or(false);
+        tor += &row(
+            "SOCKS proxy",
+            format!(
+                "{}:{} ({})",
+                info.socks_host,
+                info.socks_port,
+                if connected {
+                    "connected"
+                } else {
+                    "unreachable"
+                }
+            ),
+        );
+        tor += &row(
+            "Onion address",
+            tor_service
+                .get_hidden_service_address()
+                .await
+                .unwrap_or_else(|| "Not found".to_string()),
+        );
+    } else {
+        tor += &row("Tor", "Disabled");
+    }
+    let probe = &state.tor_probe;
+    if probe.is_enabled() {
+        tor += &row(
+            "Self-check",
+            match probe.last() {
+                Some(r) if r.ok => format!(
+                    "OK at {} ({} ms)",
+                    timestamp(r.checked_at),
+                    r.latency_ms.unwrap_or_default()
+                ),
+                Some(r) => format!(
+                    "Failed at {}: {}",
+                    timestamp(r.checked_at),
+                    r.error.unwrap_or_default()
+                ),
+                None => "Not run yet".to_string(),
+            },
+        );
+        tor += &row(
+            "Last success",
+            probe.last_success().map_or("Never".to_string(), timestamp),
+        );
+        tor += &row(
+            "Self-checks",
+            format!("{} ok, {} failed", probe.successes(), probe.failures()),
+        );
+    }
+
+    // Maintenance
+    let status = state.maintenance.status();
+    let mut maintenance = String::new();
+    maintenance += &row(
+        "Read-only",
+        match (status.enabled, status.since) {
+            (true, Some(since)) => format!("On since {}", timestamp(since)),
+            (true, None) => "On".to_string(),
+            _ => "Off".to_string(),
+        },
+    );
+    if let Some(message) = &status.message {
+        maintenance += &row("Message", message);
+    }
+
+    let body = format!(
+        r#"<p><a href="/admin-console">Refresh</a> &middot; {now}</p>
+<h2>Health</h2>
+<table>
+{health}</table>
+<h2>Tor</h2>
+<table>
+{tor}</table>
+<h2>Maintenance</h2>
+<table>
+{maintenance}</table>
+<form method="post" action="/admin-console/maintenance">
+<input type="hidden" name="csrf" value="{csrf}">
+<label for="message">Banner message (optional)</label>
+<input id="message" name="message" maxlength="500" value="{message}">
+<button name="enabled" value="true">Turn on</button>
+<button name="enabled" value="false">Turn off</button>
+</form>
+<p class="muted">Read-only mode refuses writes to the chat API and sockets; reading and signing in keep working. Schema migrations by another server on the database keep it read-only on their own until they finish.</p>"#,
+        now = timestamp(Utc::now()),
+        csrf = escape(&form_token(token)),
+        message = escape(status.message.as_deref().unwrap_or_default()),
+    );
+
+    console_html(StatusCode::OK, "Operator console", &body)
+}
+
+#[derive(Deserialize)]
+pub struct MaintenanceForm {
+    csrf: String,
+    enabled: bool,
+    #[serde(default)]
+    message: String,
+}
+
+// POST /admin-console/maintenance - Turn read-only maintenance mode on or off from the console
+pub async fn console_maintenance(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Form(form): Form<MaintenanceForm>,
+) -> Response {
+    let token = match check_operator(&state, &headers) {
+        Ok(token) => token,
+        Err(denied) => return denied,
+    };
+    if !sodiumoxide::utils::memcmp(form.csrf.as_bytes(), form_token(token).as_bytes()) {
+        return console_html(
+            StatusCode::FORBIDDEN,
+            "Form expired",
+            r#"<p>Reload the <a href="/admin-console">console</a> and try again.</p>"#,
+        );
+    }
+
+    if form.enabled {
+        let message = Some(form.message.trim().to_string()).filter(|m| !m.is_empty());
+        if message.as_ref().is_some_and(|m| m.chars().count() > 500) {
+            return console_html(
+                StatusCode::BAD_REQUEST,
+                "Message too long",
+                r#"<p>The banner message must be at most 500 characters. <a href="/admin-console">Back</a></p>"#,
+            );
+        }
+        state.maintenance.enable(message);
+    } else {
+        state.maintenance.disable();
+    }
+    state.broadcast_maintenance().await;
+
+    tracing::info!(
+        "Maintenance mode {} from the operator console",
+        if form.enabled { "enabled" } else { "disabled" }
+    );
+
+    Redirect::to("/admin-console").into_response()
+}
+
+/// Standalone page for the console; like the invite page, nothing but
+/// inline styles, plus posting its own forms
+fn console_html(status: StatusCode, title: &str, body: &str) -> Response {
+    let title = escape(title);
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<meta name="referrer" content="no-referrer">
+<title>{title} - TOR Chat</title>
+<style>
+body {{ margin: 0; background: #1e1f22; color: #dbdee1; font: 15px/1.5 system-ui, sans-serif; }}
+main {{ max-width: 720px; margin: 24px auto; padding: 28px; background: #2b2d31; border-radius: 8px; }}
+h1 {{ margin: 0 0 12px; font-size: 22px; color: #fff; }}
+h2 {{ margin: 24px 0 8px; font-size: 17px; color: #fff; }}
+a {{ color: #00a8fc; }}
+table {{ width: 100%; border-collapse: collapse; }}
+th, td {{ padding: 6px 8px; border-bottom: 1px solid #3f4147; text-align: left; vertical-align: top; word-break: break-word; }}
+th {{ width: 35%; color: #949ba4; font-weight: normal; }}
+form {{ margin-top: 12px; }}
+label {{ display: block; font-size: 13px; color: #949ba4; }}
+input {{ box-sizing: border-box; width: 100%; margin: 4px 0 8px; padding: 8px 10px; background: #1e1f22; color: #dbdee1; border: 1px solid #3f4147; border-radius: 4px; }}
+button {{ padding: 8px 14px; margin-right: 8px; background: #5865f2; color: #fff; border: 0; border-radius: 4px; cursor: pointer; }}
+.server, .muted {{ color: #949ba4; font-size: 13px; }}
+</style>
+</head>
+<body>
+<main>
+<p class="server">TOR Chat</p>
+<h1>{title}</h1>
+{body}
+</main>
+</body>
+</html>
+"#
+    );
+
+    (
+        status,
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-store"),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            (header::X_FRAME_OPTIONS, "DENY"),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'; form-action 'self'; frame-ancestors 'none'",
+            ),
+        ],
+        html,
+    )
+        .into_response()
+}
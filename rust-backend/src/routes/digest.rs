@@ -0,0 +1,41 @@
+use crate::error::{AppError, Result};
+use crate::middleware::AuthUser;
+use crate::services::{Digest, DigestSettings};
+use crate::state::AppState;
+use axum::{extract::State, Extension, Json};
+use std::sync::Arc;
+
+// GET /api/digest - Latest activity digest for the current user (null if none yet)
+pub async fn get_latest(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<Option<Digest>>> {
+    Ok(Json(state.digest.latest(&state.db, auth.user_id).await?))
+}
+
+// GET /api/digest/settings - Whether and when the daily digest is sent
+pub async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<DigestSettings>> {
+    Ok(Json(state.digest.settings(&state.db, auth.user_id).await?))
+}
+
+// PUT /api/digest/settings - Opt in/out of the daily digest and pick its hour (UTC)
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Json(settings): Json<DigestSettings>,
+) -> Result<Json<DigestSettings>> {
+    if !(0..24).contains(&settings.hour_utc) {
+        return Err(AppError::Validation(
+            "hourUtc must be between 0 and 23".to_string(),
+        ));
+    }
+
+    state
+        .digest
+        .update_settings(&state.db, auth.user_id, &settings)
+        .await?;
+    Ok(Json(settings))
+}
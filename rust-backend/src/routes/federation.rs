@@ -0,0 +1,54 @@
+use crate::error::{AppError, Result};
+use crate::models::User;
+use crate::services::{SignedDocument, DOCUMENT_TTL_HOURS};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use std::sync::Arc;
+
+// GET /.well-known/torchat/user/:username - A user's identity key and this
+// server's onion address, signed by the server, for resolving the user from
+// other servers and clients
+pub async fn user_document(
+    State(state): State<Arc<AppState>>,
+    Path(username): Path<String>,
+) -> Result<Json<SignedDocument>> {
+    // Paranoid servers don't answer questions about their users from outside
+    if state.config.paranoid_mode {
+        return Err(AppError::NotFound("User not found".to_string()));
+    }
+
+    let user =
+        sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1 AND NOT is_banned")
+            .bind(&username)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    let server = state
+        .onion_url()
+        .await
+        .ok_or_else(|| AppError::Tor("This server has no onion address yet".to_string()))?;
+    let host = server
+        .trim_start_matches("http://")
+        .trim_start_matches("https://")
+        .trim_end_matches('/');
+
+    let issued_at = chrono::Utc::now();
+    let document = serde_json::json!({
+        "subject": format!("{}@{}", user.username, host),
+        "username": user.username,
+        "displayName": user.display_name,
+        "publicKey": user.public_key,
+        "server": server,
+        "serverKey": state.identity.public_key(),
+        "issuedAt": issued_at,
+        "expiresAt": issued_at + chrono::Duration::hours(DOCUMENT_TTL_HOURS),
+    });
+    let signed = state.identity.sign(&document).ok_or_else(|| {
+        AppError::Maintenance("Server is starting up, try again shortly".to_string())
+    })?;
+    Ok(Json(signed))
+}
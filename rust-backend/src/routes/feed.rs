@@ -0,0 +1,272 @@
+use super::rooms::require_room_admin;
+use crate::error::{AppError, Result};
+use crate::middleware::{round_down, AuthUser};
+use crate::models::Room;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+use std::fmt::Write;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Messages listed in a feed, newest first
+const FEED_ENTRIES: i64 = 50;
+/// Characters of a message used as its entry title
+const TITLE_CHARS: usize = 80;
+
+#[derive(Deserialize)]
+pub struct FeedQuery {
+    #[serde(default)]
+    token: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct FeedEntry {
+    id: Uuid,
+    content: String,
+    created_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+    username: String,
+    display_name: Option<String>,
+}
+
+async fn find_room(state: &AppState, room_id: Uuid) -> Result<Room> {
+    sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+        .bind(room_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Room not found".to_string()))
+}
+
+/// Feed settings as shown to room admins. `url` is only known once the
+/// onion address is.
+async fn feed_settings(state: &AppState, room: &Room) -> serde_json::Value {
+    let path = room
+        .feed_token
+        .as_ref()
+        .map(|token| format!("/api/rooms/{}/feed.atom?token={}", room.id, token));
+    let url = match &path {
+        Some(path) => state
+            .onion_url()
+            .await
+            .map(|base| format!("{}{}", base.trim_end_matches('/'), path)),
+        None => None,
+    };
+    serde_json::json!({
+        "enabled": path.is_some(),
+        "available": room.can_have_feed(),
+        "path": path,
+        "url": url,
+    })
+}
+
+// GET /api/rooms/:id/feed - Whether the room's Atom feed is on, and its URL (room admins)
+pub async fn get_feed(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "manage the feed").await?;
+    let room = find_room(&state, room_id).await?;
+    Ok(Json(feed_settings(&state, &room).await))
+}
+
+// POST /api/rooms/:id/feed - Turn the Atom feed on, or give it a new URL if
+// it already is (room admins; public, non-E2EE rooms only)
+pub async fn enable_feed(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "manage the feed").await?;
+    let room = find_room(&state, room_id).await?;
+    if !room.can_have_feed() {
+        return Err(AppError::BadRequest(
+            "Only public rooms without end-to-end encryption can have a feed".to_string(),
+        ));
+    }
+
+    let room =
+        sqlx::query_as::<_, Room>("UPDATE rooms SET feed_token = $2 WHERE id = $1 RETURNING *")
+            .bind(room_id)
+            .bind(Room::generate_feed_token())
+            .fetch_one(&state.db)
+            .await?;
+
+    tracing::info!(
+        "Feed enabled for room {} by {}",
+        room.name,
+        auth.user.username
+    );
+
+    Ok(Json(feed_settings(&state, &room).await))
+}
+
+// DELETE /api/rooms/:id/feed - Turn the Atom feed off (room admins)
+pub async fn disable_feed(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "manage the feed").await?;
+
+    let room =
+        sqlx::query_as::<_, Room>("UPDATE rooms SET feed_token = NULL WHERE id = $1 RETURNING *")
+            .bind(room_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
+
+    tracing::info!(
+        "Feed disabled for room {} by {}",
+        room.name,
+        auth.user.username
+    );
+
+    Ok(Json(feed_settings(&state, &room).await))
+}
+
+// GET /api/rooms/:id/feed.atom?token= - Recent messages as an Atom feed, for
+// feed readers without an account. A wrong token, a disabled feed and a room
+// that can't have one all look like a missing room.
+pub async fn atom_feed(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<Uuid>,
+    Query(query): Query<FeedQuery>,
+) -> Result<Response> {
+    let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1 AND feed_token = $2")
+        .bind(room_id)
+        .bind(&query.token)
+        .fetch_optional(&state.db)
+        .await?
+        .filter(|room| room.can_have_feed())
+        .ok_or_else(|| AppError::NotFound("Feed not found".to_string()))?;
+
+    // Same visibility as for a regular member, minus anything meant to
+    // disappear: expiring messages don't belong in a reader's archive
+    let entries = sqlx::query_as::<_, FeedEntry>(
+        "SELECT m.id, m.content, m.created_at, m.updated_at, u.username, u.display_name
+         FROM messages m
+         JOIN users u ON u.id = m.user_id
+         WHERE m.room_id = $1
+           AND m.message_type = 'text'
+           AND m.expires_in IS NULL
+           AND m.spam_action IS DISTINCT FROM 'hidden'
+           AND NOT u.is_shadow_banned
+         ORDER BY m.created_at DESC
+         LIMIT $2",
+    )
+    .bind(room_id)
+    .bind(FEED_ENTRIES)
+    .fetch_all(&state.db)
+    .await?;
+
+    let self_url = state.onion_url().await.map(|base| {
+        format!(
+            "{}/api/rooms/{}/feed.atom",
+            base.trim_end_matches('/'),
+            room_id
+        )
+    });
+    let body = render_atom(&state, &room, &entries, self_url.as_deref());
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/atom+xml; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-cache"),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn render_atom(
+    state: &AppState,
+    room: &Room,
+    entries: &[FeedEntry],
+    self_url: Option<&str>,
+) -> String {
+    // Paranoid mode: readers only learn roughly when things were said, as
+    // with the JSON API
+    let timestamp = |ts: DateTime<Utc>| {
+        let ts = if state.config.paranoid_mode {
+            round_down(ts, state.config.paranoid_timestamp_granularity)
+        } else {
+            ts
+        };
+        ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+    };
+    let updated = entries
+        .iter()
+        .map(|e| e.updated_at.unwrap_or(e.created_at))
+        .max()
+        .unwrap_or(room.created_at);
+
+    let mut xml = String::new();
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    let _ = writeln!(xml, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+    let _ = writeln!(xml, "  <id>urn:uuid:{}</id>", room.id);
+    let _ = writeln!(xml, "  <title>{}</title>", escape(&room.name));
+    if let Some(description) = &room.description {
+        let _ = writeln!(xml, "  <subtitle>{}</subtitle>", escape(description));
+    }
+    let _ = writeln!(xml, "  <updated>{}</updated>", timestamp(updated));
+    if let Some(url) = self_url {
+        let _ = writeln!(xml, r#"  <link rel="self" href="{}"/>"#, escape(url));
+    }
+    let _ = writeln!(xml, "  <generator>TOR Chat</generator>");
+
+    for entry in entries {
+        let author = entry.display_name.as_deref().unwrap_or(&entry.username);
+        let title: String = entry.content.chars().take(TITLE_CHARS).collect();
+        let title = title.lines().next().unwrap_or_default();
+        let _ = writeln!(xml, "  <entry>");
+        let _ = writeln!(xml, "    <id>urn:uuid:{}</id>", entry.id);
+        let _ = writeln!(xml, "    <title>{}</title>", escape(title));
+        let _ = writeln!(
+            xml,
+            "    <updated>{}</updated>",
+            timestamp(entry.updated_at.unwrap_or(entry.created_at))
+        );
+        let _ = writeln!(
+            xml,
+            "    <published>{}</published>",
+            timestamp(entry.created_at)
+        );
+        let _ = writeln!(xml, "    <author><name>{}</name></author>", escape(author));
+        let _ = writeln!(
+            xml,
+            r#"    <content type="text">{}</content>"#,
+            escape(&entry.content)
+        );
+        let _ = writeln!(xml, "  </entry>");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Escape text for XML (and HTML) content and attribute values, dropping
+/// characters XML 1.0 doesn't allow at all
+pub(super) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if (c as u32) < 0x20 || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,329 @@
+use super::feed::escape;
+use super::rooms::{member_count, require_room_admin};
+use crate::error::{AppError, Result};
+use crate::middleware::{AuthUser, ValidatedJson};
+use crate::models::{CreateInviteRequest, Room, RoomInvite};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Still-usable invite by code, or NotFound (expired and used-up invites
+/// look the same as unknown ones)
+async fn find_active(state: &AppState, code: &str) -> Result<RoomInvite> {
+    sqlx::query_as::<_, RoomInvite>(
+        "SELECT * FROM room_invites
+         WHERE code = $1
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND (max_uses IS NULL OR uses < max_uses)",
+    )
+    .bind(code)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invite not found or expired".to_string()))
+}
+
+// GET /api/rooms/:id/invites - Active invites of a room (room admins)
+pub async fn list_invites(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "manage invites").await?;
+
+    let invites = sqlx::query_as::<_, RoomInvite>(
+        "SELECT * FROM room_invites
+         WHERE room_id = $1
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND (max_uses IS NULL OR uses < max_uses)
+         ORDER BY created_at DESC",
+    )
+    .bind(room_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(serde_json::json!({ "invites": invites })))
+}
+
+// POST /api/rooms/:id/invites - Create an invite code (room admins)
+pub async fn create_invite(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<CreateInviteRequest>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "manage invites").await?;
+
+    let expires_at = req
+        .expires_in_hours
+        .map(|hours| Utc::now() + Duration::hours(hours));
+    let invite = sqlx::query_as::<_, RoomInvite>(
+        "INSERT INTO room_invites (code, room_id, created_by, max_uses, expires_at)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING *",
+    )
+    .bind(RoomInvite::generate_code())
+    .bind(room_id)
+    .bind(auth.user_id)
+    .bind(req.max_uses)
+    .bind(expires_at)
+    .fetch_one(&state.db)
+    .await?;
+
+    tracing::info!(
+        "Invite created for room {} by {}",
+        room_id,
+        auth.user.username
+    );
+
+    Ok(Json(serde_json::json!({ "invite": invite })))
+}
+
+// DELETE /api/rooms/:id/invites/:code - Revoke an invite (room admins)
+pub async fn revoke_invite(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path((room_id, code)): Path<(Uuid, String)>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "manage invites").await?;
+
+    let result = sqlx::query("DELETE FROM room_invites WHERE room_id = $1 AND code = $2")
+        .bind(room_id)
+        .bind(&code)
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Invite not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({ "message": "Invite revoked" })))
+}
+
+// GET /api/invites/:code - Preview the room an invite leads to
+pub async fn preview_invite(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    let invite = find_active(&state, &code).await?;
+    let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+        .bind(invite.room_id)
+        .fetch_one(&state.db)
+        .await?;
+    let member_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM room_members WHERE room_id = $1")
+            .bind(room.id)
+            .fetch_one(&state.db)
+            .await?;
+
+    Ok(Json(serde_json::json!({
+        "roomId": room.id,
+        "roomName": room.name,
+        "description": room.description,
+        "memberCount": member_count,
+        "expiresAt": invite.expires_at,
+    })))
+}
+
+// GET /invite/:code - Landing page for an invite link opened outside the
+// app: the server and room it leads to, and where to sign up. Served to
+// anyone, so it's rate-limited across all visitors and always answers in HTML.
+pub async fn invite_page(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+) -> Result<Response> {
+    if !state.settings.invite_pages.try_acquire() {
+        return Ok(html_page(
+            StatusCode::TOO_MANY_REQUESTS,
+            "Too many invites opened",
+            "<p>A lot of invite links are being opened right now. Try again in a minute.</p>",
+        ));
+    }
+
+    let invite = match find_active(&state, &code).await {
+        Ok(invite) => invite,
+        Err(AppError::NotFound(_)) => {
+            return Ok(html_page(
+                StatusCode::NOT_FOUND,
+                "Invite not found",
+                "<p>This invite link is wrong, has expired or has been used up. \
+                 Ask whoever sent it for a new one.</p>",
+            ))
+        }
+        Err(e) => return Err(e),
+    };
+    let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+        .bind(invite.room_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    let mut body = String::new();
+    if let Some(description) = room.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        body.push_str(&format!(
+            r#"<p class="description">{}</p>"#,
+            escape(description)
+        ));
+    }
+    // Paranoid mode: how big a room is stays between its members
+    if !state.config.paranoid_mode {
+        let count = member_count(&state, room.id).await?;
+        let noun = if count == 1 { "member" } else { "members" };
+        body.push_str(&format!(r#"<p class="muted">{} {}</p>"#, count, noun));
+    }
+    body.push_str(&format!(
+        r#"<p>To join, <a href="/register">create an account</a> on this server or <a href="/login">log in</a>, then accept the invite with this code:</p>
+<p><code>{}</code></p>"#,
+        escape(&code)
+    ));
+
+    Ok(html_page(
+        StatusCode::OK,
+        &format!("Join {}", room.name),
+        &body,
+    ))
+}
+
+/// Minimal standalone page for `invite_page`; `title` is escaped here, `body`
+/// must already be HTML
+fn html_page(status: StatusCode, title: &str, body: &str) -> Response {
+    let title = escape(title);
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<meta name="referrer" content="no-referrer">
+<title>{title} - TOR Chat</title>
+<style>
+body {{ margin: 0; min-height: 100vh; display: flex; align-items: center; justify-content: center; background: #1e1f22; color: #dbdee1; font: 15px/1.5 system-ui, sans-serif; }}
+main {{ max-width: 420px; margin: 24px; padding: 28px; background: #2b2d31; border-radius: 8px; }}
+h1 {{ margin: 0 0 12px; font-size: 22px; color: #fff; word-wrap: break-word; }}
+a {{ color: #00a8fc; }}
+code {{ display: block; padding: 8px 10px; background: #1e1f22; border-radius: 4px; user-select: all; word-break: break-all; }}
+.server, .muted {{ color: #949ba4; font-size: 13px; }}
+.description {{ white-space: pre-wrap; }}
+</style>
+</head>
+<body>
+<main>
+<p class="server">TOR Chat</p>
+<h1>{title}</h1>
+{body}
+</main>
+</body>
+</html>
+"#
+    );
+
+    (
+        status,
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-store"),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+            (header::X_FRAME_OPTIONS, "DENY"),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; style-src 'unsafe-inline'",
+            ),
+        ],
+        html,
+    )
+        .into_response()
+}
+
+// POST /api/invites/:code/accept - Join the invite's room
+pub async fn accept_invite(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(code): Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    let invite = find_active(&state, &code).await?;
+    let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+        .bind(invite.room_id)
+        .fetch_one(&state.db)
+        .await?;
+
+    // Already a member: nothing to do, and the invite isn't used up
+    let is_member = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM room_members WHERE room_id = $1 AND user_id = $2)",
+    )
+    .bind(room.id)
+    .bind(auth.user_id)
+    .fetch_one(&state.db)
+    .await?;
+    let member_count = member_count(&state, room.id).await?;
+    if is_member {
+        return Ok(Json(
+            serde_json::json!({ "room": room.to_member_json(member_count) }),
+        ));
+    }
+
+    if member_count >= room.max_members as i64 {
+        return Err(AppError::BadRequest("Room is full".to_string()));
+    }
+
+    // Claim a use atomically so concurrent accepts can't exceed max_uses
+    let mut tx = state.db.begin().await?;
+    let claimed = sqlx::query(
+        "UPDATE room_invites SET uses = uses + 1
+         WHERE code = $1
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND (max_uses IS NULL OR uses < max_uses)",
+    )
+    .bind(&code)
+    .execute(&mut *tx)
+    .await?;
+    if claimed.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            "Invite not found or expired".to_string(),
+        ));
+    }
+    sqlx::query(
+        "INSERT INTO room_members (room_id, user_id, role) VALUES ($1, $2, 'member')
+         ON CONFLICT (room_id, user_id) DO NOTHING",
+    )
+    .bind(room.id)
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    state
+        .guard_cache
+        .invalidate_membership(room.id, auth.user_id)
+        .await;
+    state.join_user_sockets(auth.user_id, room.id).await;
+
+    tracing::info!(
+        "User {} joined room {} with an invite",
+        auth.user.username,
+        room.name
+    );
+
+    state
+        .io
+        .within(room.id.to_string())
+        .emit(
+            "member_added",
+            &serde_json::json!({
+                "roomId": room.id,
+                "userId": auth.user_id,
+                "username": auth.user.username,
+                "displayName": auth.user.display_name,
+            }),
+        )
+        .await
+        .ok();
+
+    Ok(Json(
+        serde_json::json!({ "room": room.to_member_json(member_count + 1) }),
+    ))
+}
@@ -0,0 +1,125 @@
+use crate::database::acquire_timeouts;
+use crate::error::{AppError, Result};
+use crate::services::server_errors;
+use crate::state::AppState;
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::IntoResponse,
+};
+use std::fmt::Write;
+use std::sync::Arc;
+
+// GET /metrics - Prometheus metrics (database pool, server errors, Tor self-check); requires METRICS_TOKEN when set
+pub async fn get_metrics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse> {
+    if let Some(token) = &state.config.metrics_token {
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if provided != Some(token.as_str()) {
+            return Err(AppError::Authentication(
+                "Invalid metrics token".to_string(),
+            ));
+        }
+    }
+
+    let max = state.config.db_max_connections.max(1);
+    let open = state.db.size();
+    let idle = state.db.num_idle() as u32;
+    let in_use = open.saturating_sub(idle);
+    let saturation = in_use as f64 / max as f64;
+
+    let mut body = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} {}", name, kind);
+        let _ = writeln!(body, "{} {}", name, value);
+    };
+    metric(
+        "torchat_db_pool_max_connections",
+        "gauge",
+        "Configured maximum pool size (DB_MAX_CONNECTIONS)",
+        max.to_string(),
+    );
+    metric(
+        "torchat_db_pool_connections",
+        "gauge",
+        "Open connections, idle or in use",
+        open.to_string(),
+    );
+    metric(
+        "torchat_db_pool_idle_connections",
+        "gauge",
+        "Open connections waiting for work",
+        idle.to_string(),
+    );
+    metric(
+        "torchat_db_pool_in_use_connections",
+        "gauge",
+        "Connections currently checked out",
+        in_use.to_string(),
+    );
+    metric(
+        "torchat_db_pool_saturation",
+        "gauge",
+        "Checked-out connections as a fraction of the maximum (0-1)",
+        format!("{:.3}", saturation),
+    );
+    metric(
+        "torchat_db_pool_acquire_timeouts_total",
+        "counter",
+        "Requests that timed out waiting for a connection",
+        acquire_timeouts().to_string(),
+    );
+    metric(
+        "torchat_http_server_errors_total",
+        "counter",
+        "API responses with a 5xx status (maintenance excluded)",
+        server_errors().to_string(),
+    );
+
+    let probe = &state.tor_probe;
+    if probe.is_enabled() {
+        let last = probe.last();
+        metric(
+            "torchat_tor_probe_up",
+            "gauge",
+            "1 if the last self-check reached our onion address through Tor",
+            last.as_ref().map_or(0, |r| r.ok as u8).to_string(),
+        );
+        metric(
+            "torchat_tor_probe_latency_seconds",
+            "gauge",
+            "Round trip of the last successful self-check",
+            last.and_then(|r| r.latency_ms)
+                .map_or("NaN".to_string(), |ms| format!("{:.3}", ms as f64 / 1000.0)),
+        );
+        metric(
+            "torchat_tor_probe_last_success_timestamp_seconds",
+            "gauge",
+            "Unix time of the last successful self-check",
+            probe
+                .last_success()
+                .map_or(0, |t| t.timestamp())
+                .to_string(),
+        );
+        metric(
+            "torchat_tor_probe_success_total",
+            "counter",
+            "Successful self-checks",
+            probe.successes().to_string(),
+        );
+        metric(
+            "torchat_tor_probe_failures_total",
+            "counter",
+            "Failed self-checks",
+            probe.failures().to_string(),
+        );
+    }
+
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body))
+}
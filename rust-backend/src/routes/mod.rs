@@ -1,9 +1,24 @@
 pub mod admin;
 pub mod auth;
+pub mod console;
+pub mod digest;
+pub mod federation;
+pub mod feed;
+pub mod invites;
+pub mod metrics;
+pub mod proxy;
+pub mod requests;
+pub mod retention;
 pub mod rooms;
+pub mod security_keys;
+pub mod server;
 pub mod tor;
+pub mod translate;
 pub mod upload;
 
 // Re-export specific functions to avoid ambiguity
-pub use auth::{list_users, login, logout, me, register};
+pub use auth::{
+    create_api_token, guest, list_api_tokens, list_users, login, logout, me, refresh, register,
+    registration_status, revoke_api_token, revoke_refresh, update_public_key,
+};
 pub use upload::upload_file;
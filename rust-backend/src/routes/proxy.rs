@@ -0,0 +1,36 @@
+use crate::error::Result;
+use crate::state::AppState;
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+pub struct ImageQuery {
+    url: String,
+}
+
+// GET /api/proxy/image?url= - Fetch a remote image through TOR and re-serve it
+pub async fn image(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ImageQuery>,
+) -> Result<Response> {
+    let image = state.image_proxy.fetch(&query.url).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, image.content_type),
+            (header::CACHE_CONTROL, "private, max-age=3600".to_string()),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                "default-src 'none'; sandbox".to_string(),
+            ),
+        ],
+        image.bytes,
+    )
+        .into_response())
+}
@@ -0,0 +1,262 @@
+use super::rooms::member_count;
+use crate::error::{AppError, Result};
+use crate::middleware::AuthUser;
+use crate::models::{Room, User};
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Values of `users.accept_rooms_from`
+pub const ACCEPT_EVERYONE: &str = "everyone";
+pub const ACCEPT_MEMBERS: &str = "members";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestSettings {
+    /// `everyone`, or `members`: only people the user shares a room with
+    pub accept_from: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct RequestRow {
+    room_id: Uuid,
+    room_name: String,
+    requested_by: Uuid,
+    username: String,
+    display_name: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Whether `requester` adding `user_id` to `room` has to wait for the user
+/// to accept: they only take adds from people they share a room with, so
+/// strangers on an open server can't pull them into conversations. Global
+/// admins are never held back.
+pub(super) async fn needs_request(
+    state: &AppState,
+    requester: &User,
+    user_id: Uuid,
+    room: &Room,
+) -> Result<bool> {
+    if room.is_public || requester.is_admin {
+        return Ok(false);
+    }
+    let accept_from =
+        sqlx::query_scalar::<_, String>("SELECT accept_rooms_from FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&state.db)
+            .await?;
+    if accept_from != ACCEPT_MEMBERS {
+        return Ok(false);
+    }
+    // Sharing this room already counts too: adding them again is a no-op
+    let shares_room = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(
+             SELECT 1 FROM room_members a
+             JOIN room_members b ON b.room_id = a.room_id
+             WHERE a.user_id = $1 AND b.user_id = $2)",
+    )
+    .bind(requester.id)
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+    Ok(!shares_room)
+}
+
+/// Hold an add to `room` for `user_id` to accept, and tell them about it
+pub(super) async fn create_request(
+    state: &AppState,
+    requester: &User,
+    user_id: Uuid,
+    room: &Room,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO room_requests (room_id, user_id, requested_by) VALUES ($1, $2, $3)
+         ON CONFLICT (room_id, user_id) DO NOTHING",
+    )
+    .bind(room.id)
+    .bind(user_id)
+    .bind(requester.id)
+    .execute(&state.db)
+    .await?;
+
+    state
+        .emit_to_user(
+            user_id,
+            "room_request",
+            &serde_json::json!({
+                "roomId": room.id,
+                "roomName": room.name,
+                "from": {
+                    "id": requester.id,
+                    "username": requester.username,
+                    "displayName": requester.display_name,
+                },
+            }),
+        )
+        .await;
+    Ok(())
+}
+
+// GET /api/requests - Rooms the caller was asked to join, newest first
+pub async fn list_requests(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>> {
+    let rows = sqlx::query_as::<_, RequestRow>(
+        "SELECT rr.room_id, r.name AS room_name, rr.requested_by, u.username, u.display_name,
+                rr.created_at
+         FROM room_requests rr
+         JOIN rooms r ON r.id = rr.room_id
+         JOIN users u ON u.id = rr.requested_by
+         WHERE rr.user_id = $1
+         ORDER BY rr.created_at DESC",
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let requests: Vec<serde_json::Value> = rows
+        .into_iter()
+        .map(|row| {
+            serde_json::json!({
+                "roomId": row.room_id,
+                "roomName": row.room_name,
+                "from": {
+                    "id": row.requested_by,
+                    "username": row.username,
+                    "displayName": row.display_name,
+                },
+                "createdAt": row.created_at,
+            })
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({ "requests": requests })))
+}
+
+// POST /api/requests/:roomId/accept - Join the room a request is for
+pub async fn accept_request(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let room = sqlx::query_as::<_, Room>(
+        "SELECT r.* FROM rooms r
+         JOIN room_requests rr ON rr.room_id = r.id
+         WHERE r.id = $1 AND rr.user_id = $2",
+    )
+    .bind(room_id)
+    .bind(auth.user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Request not found".to_string()))?;
+
+    let member_count = member_count(&state, room.id).await?;
+    if member_count >= room.max_members as i64 {
+        return Err(AppError::BadRequest("Room is full".to_string()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    sqlx::query("DELETE FROM room_requests WHERE room_id = $1 AND user_id = $2")
+        .bind(room.id)
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "INSERT INTO room_members (room_id, user_id, role) VALUES ($1, $2, 'member')
+         ON CONFLICT (room_id, user_id) DO NOTHING",
+    )
+    .bind(room.id)
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    state
+        .guard_cache
+        .invalidate_membership(room.id, auth.user_id)
+        .await;
+    state.join_user_sockets(auth.user_id, room.id).await;
+
+    tracing::info!(
+        "User {} accepted a request to join room {}",
+        auth.user.username,
+        room.name
+    );
+
+    state
+        .io
+        .within(room.id.to_string())
+        .emit(
+            "member_added",
+            &serde_json::json!({
+                "roomId": room.id,
+                "userId": auth.user_id,
+                "username": auth.user.username,
+                "displayName": auth.user.display_name,
+            }),
+        )
+        .await
+        .ok();
+
+    Ok(Json(
+        serde_json::json!({ "room": room.to_member_json(member_count + 1) }),
+    ))
+}
+
+// DELETE /api/requests/:roomId - Decline a request; the requester isn't told
+pub async fn decline_request(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let deleted = sqlx::query("DELETE FROM room_requests WHERE room_id = $1 AND user_id = $2")
+        .bind(room_id)
+        .bind(auth.user_id)
+        .execute(&state.db)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound("Request not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({ "message": "Request declined" })))
+}
+
+// GET /api/requests/settings - Who may add the caller to private rooms directly
+pub async fn get_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<RequestSettings>> {
+    let accept_from =
+        sqlx::query_scalar::<_, String>("SELECT accept_rooms_from FROM users WHERE id = $1")
+            .bind(auth.user_id)
+            .fetch_one(&state.db)
+            .await?;
+    Ok(Json(RequestSettings { accept_from }))
+}
+
+// PUT /api/requests/settings - Accept adds from everyone, or only from people
+// the caller shares a room with
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Json(settings): Json<RequestSettings>,
+) -> Result<Json<RequestSettings>> {
+    if settings.accept_from != ACCEPT_EVERYONE && settings.accept_from != ACCEPT_MEMBERS {
+        return Err(AppError::Validation(
+            "acceptFrom must be everyone or members".to_string(),
+        ));
+    }
+
+    sqlx::query("UPDATE users SET accept_rooms_from = $1 WHERE id = $2")
+        .bind(&settings.accept_from)
+        .bind(auth.user_id)
+        .execute(&state.db)
+        .await?;
+    Ok(Json(settings))
+}
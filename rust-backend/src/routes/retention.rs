@@ -0,0 +1,63 @@
+use crate::error::{AppError, Result};
+use crate::middleware::AuthUser;
+use crate::services::{RetentionRule, MAX_RETENTION_DAYS};
+use crate::state::AppState;
+use axum::{extract::State, Extension, Json};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionBody {
+    /// Omitted or null for the global rule
+    room_id: Option<Uuid>,
+    /// Null removes the rule
+    days: Option<i32>,
+}
+
+// GET /api/retention - The current user's message retention rules
+pub async fn get_rules(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>> {
+    let rules: Vec<RetentionRule> = state.retention.rules(&state.db, auth.user_id).await?;
+    Ok(Json(serde_json::json!({ "rules": rules })))
+}
+
+// PUT /api/retention - Delete own messages older than N days, globally or in one room
+pub async fn set_rule(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Json(body): Json<RetentionBody>,
+) -> Result<Json<serde_json::Value>> {
+    if let Some(days) = body.days {
+        if !(1..=MAX_RETENTION_DAYS).contains(&days) {
+            return Err(AppError::Validation(format!(
+                "days must be between 1 and {}",
+                MAX_RETENTION_DAYS
+            )));
+        }
+    }
+
+    if let Some(room_id) = body.room_id {
+        let is_member = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM room_members WHERE room_id = $1 AND user_id = $2)",
+        )
+        .bind(room_id)
+        .bind(auth.user_id)
+        .fetch_one(&state.db)
+        .await?;
+        if !is_member {
+            return Err(AppError::NotFound("Room not found".to_string()));
+        }
+    }
+
+    state
+        .retention
+        .set_rule(&state.db, auth.user_id, body.room_id, body.days)
+        .await?;
+
+    let rules = state.retention.rules(&state.db, auth.user_id).await?;
+    Ok(Json(serde_json::json!({ "rules": rules })))
+}
@@ -1,13 +1,23 @@
+use super::requests::{create_request, needs_request};
 use crate::error::{AppError, Result};
 use crate::middleware::{AuthUser, ValidatedJson};
-use crate::models::{CreateRoomRequest, Message, Room, RoomMember, User};
-use crate::services::CryptoService;
-use crate::state::AppState;
+use crate::models::{
+    ContentPolicy, CreateRoomRequest, MemberDetails, Message, MessageRevision, MessageType,
+    NotificationSettings, ReactionGroup, Room, RoomMember, UpdateRoomRequest, User,
+    UPLOAD_CATEGORIES,
+};
+use crate::services::{
+    check_name, CryptoService, MentionScope, NameKind, RoomStats, FILE_MESSAGE_TYPES,
+};
+use crate::state::{AppState, Outgoing};
 use axum::{
     extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Extension, Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -23,17 +33,78 @@ fn default_limit() -> i64 {
     50
 }
 
+#[derive(Deserialize)]
+pub struct ContextQuery {
+    /// Messages to include on each side of the target
+    #[serde(default = "default_around")]
+    around: i64,
+}
+
+fn default_around() -> i64 {
+    25
+}
+
 #[derive(Deserialize)]
 pub struct SearchQuery {
     q: String,
 }
 
+#[derive(Deserialize)]
+pub struct GlobalSearchQuery {
+    q: String,
+    /// Cap on the messages returned, across all rooms
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+#[derive(Deserialize)]
+pub struct FilesQuery {
+    #[serde(default = "default_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+    /// One of `image`, `video`, `audio`, `file`; all of them when absent
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct StatsQuery {
+    #[serde(default = "default_stats_days")]
+    days: i32,
+}
+
+fn default_stats_days() -> i32 {
+    30
+}
+
+#[derive(sqlx::FromRow)]
+struct FileRow {
+    id: Uuid,
+    user_id: Uuid,
+    content: String,
+    message_type: String,
+    metadata: Option<serde_json::Value>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    username: String,
+    display_name: Option<String>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SendMessageBody {
     pub content: String,
     pub message_type: Option<String>,
     pub reply_to: Option<Uuid>,
+    /// File details for attachments (`originalName`, `mimetype`, `size`)
+    pub metadata: Option<serde_json::Value>,
+    /// Opaque client-generated ID echoed back so the sender can match its
+    /// optimistic copy; never stored
+    pub client_nonce: Option<String>,
+    /// Delete the message this many seconds after it's first read (or
+    /// after sending, with `expireAfterRead: false`)
+    pub expires_in: Option<i32>,
+    pub expire_after_read: Option<bool>,
 }
 
 #[derive(Serialize)]
@@ -54,6 +125,107 @@ pub struct MessageResponse {
     pub pinned_by: Option<Uuid>,
     pub pinned_at: Option<chrono::DateTime<chrono::Utc>>,
     pub reply_message: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_nonce: Option<String>,
+    /// Only filled in for moderators
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spam_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spam_action: Option<String>,
+    pub key_epoch: Option<i32>,
+    pub legacy_plaintext: bool,
+    pub expires_in: Option<i32>,
+    pub expires_after_read: bool,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// How many times the message has been edited
+    pub revision_count: i32,
+}
+
+/// Membership check for the read endpoints. Returns whether the viewer may
+/// moderate the room (global or room admin): moderators also see
+/// shadow-hidden messages and spam scores.
+async fn require_reader(state: &AppState, auth: &AuthUser, room_id: Uuid) -> Result<bool> {
+    let role = sqlx::query_scalar::<_, String>(
+        "SELECT role FROM room_members WHERE room_id = $1 AND user_id = $2",
+    )
+    .bind(room_id)
+    .bind(auth.user_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match role {
+        Some(role) => Ok(auth.user.is_admin || role == "admin"),
+        // Global admins can read any room (moderation)
+        None if auth.user.is_admin => Ok(true),
+        None => Err(AppError::Authorization(
+            "Not a member of this room".to_string(),
+        )),
+    }
+}
+
+/// The caller's history floor in a room (see [`AppState::history_floor`])
+async fn history_floor(
+    state: &AppState,
+    auth: &AuthUser,
+    room_id: Uuid,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+    Ok(state.history_floor(&auth.user, room_id).await?)
+}
+
+/// How many members a room has, for capacity checks and room payloads
+pub(super) async fn member_count(state: &AppState, room_id: Uuid) -> Result<i64> {
+    Ok(
+        sqlx::query_scalar("SELECT COUNT(*) FROM room_members WHERE room_id = $1")
+            .bind(room_id)
+            .fetch_one(&state.db)
+            .await?,
+    )
+}
+
+/// Room admin (or global admin) check for the settings endpoints
+pub(super) async fn require_room_admin(
+    state: &AppState,
+    auth: &AuthUser,
+    room_id: Uuid,
+    action: &str,
+) -> Result<()> {
+    if require_reader(state, auth, room_id).await? {
+        Ok(())
+    } else {
+        Err(AppError::Authorization(format!(
+            "Only room admins can {}",
+            action
+        )))
+    }
+}
+
+/// Reactions of the given messages, keyed by message id, in the
+/// `{emoji: [userId, ...]}` shape of `reactions` in message responses.
+/// Messages nobody reacted to are absent.
+async fn reactions_for(
+    state: &AppState,
+    message_ids: &[Uuid],
+) -> Result<HashMap<Uuid, serde_json::Value>> {
+    let groups = sqlx::query_as::<_, ReactionGroup>(
+        "SELECT message_id, emoji, COUNT(*) AS count,
+                array_agg(user_id ORDER BY created_at) AS user_ids
+         FROM message_reactions
+         WHERE message_id = ANY($1)
+         GROUP BY message_id, emoji
+         ORDER BY MIN(created_at)",
+    )
+    .bind(message_ids)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut reactions: HashMap<Uuid, serde_json::Value> = HashMap::new();
+    for group in groups {
+        reactions
+            .entry(group.message_id)
+            .or_insert_with(|| serde_json::json!({}))[&group.emoji] =
+            serde_json::json!(group.user_ids);
+    }
+    Ok(reactions)
 }
 
 // GET /api/rooms - List rooms (public + user's private rooms, or ALL for global admins)
@@ -78,24 +250,56 @@ pub async fn list_rooms(
         .await?
     };
 
-    let mut room_responses = Vec::new();
-    for r in &rooms {
-        let unread_count: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM messages m
-             JOIN room_members rm ON rm.room_id = m.room_id AND rm.user_id = $2
-             WHERE m.room_id = $1
-             AND (rm.last_read_message_id IS NULL
-                  OR m.created_at > (SELECT created_at FROM messages WHERE id = rm.last_read_message_id))"
+    let room_ids: Vec<Uuid> = rooms.iter().map(|r| r.id).collect();
+    let member_counts: HashMap<Uuid, i64> = sqlx::query_as::<_, (Uuid, i64)>(
+        "SELECT room_id, COUNT(*) FROM room_members WHERE room_id = ANY($1) GROUP BY room_id",
+    )
+    .bind(&room_ids)
+    .fetch_all(&state.db)
+    .await?
+    .into_iter()
+    .collect();
+    // The caller's own settings for the rooms they're in, so clients can
+    // honour them without asking room by room
+    let notifications: HashMap<Uuid, NotificationSettings> =
+        sqlx::query_as::<_, (Uuid, String, Option<chrono::DateTime<chrono::Utc>>)>(
+            "SELECT room_id, notification_level, notifications_muted_until FROM room_members
+             WHERE user_id = $1",
         )
-        .bind(r.id)
         .bind(auth.user_id)
-        .fetch_one(&state.db)
-        .await
-        .unwrap_or(0);
+        .fetch_all(&state.db)
+        .await?
+        .into_iter()
+        .map(|(room_id, level, muted_until)| (room_id, NotificationSettings { level, muted_until }))
+        .collect();
+
+    let mut room_responses = Vec::new();
+    for r in &rooms {
+        // Without stored read positions there's nothing to count from
+        let unread_count: i64 = if state.config.paranoid_mode {
+            0
+        } else {
+            sqlx::query_scalar(
+                "SELECT COUNT(*) FROM messages m
+                 JOIN room_members rm ON rm.room_id = m.room_id AND rm.user_id = $2
+                 WHERE m.room_id = $1
+                 AND (rm.last_read_message_id IS NULL
+                      OR m.created_at > (SELECT created_at FROM messages WHERE id = rm.last_read_message_id))"
+            )
+            .bind(r.id)
+            .bind(auth.user_id)
+            .fetch_one(&state.db)
+            .await
+            .unwrap_or(0)
+        };
 
-        let mut json = serde_json::to_value(r.to_public_json()).unwrap_or_default();
+        let member_count = member_counts.get(&r.id).copied().unwrap_or(0);
+        let mut json = serde_json::to_value(r.to_public_json(member_count)).unwrap_or_default();
         if let Some(obj) = json.as_object_mut() {
             obj.insert("unreadCount".to_string(), serde_json::json!(unread_count));
+            if let Some(settings) = notifications.get(&r.id) {
+                obj.insert("notifications".to_string(), serde_json::json!(settings));
+            }
         }
         room_responses.push(json);
     }
@@ -103,6 +307,84 @@ pub async fn list_rooms(
     Ok(Json(serde_json::json!({ "rooms": room_responses })))
 }
 
+#[derive(sqlx::FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreadCount {
+    room_id: Uuid,
+    unread_count: i64,
+    last_read_message_id: Option<Uuid>,
+}
+
+// GET /api/rooms/unread - Unread counts and read positions for the user's rooms
+pub async fn unread_counts(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>> {
+    // Read positions aren't stored in paranoid mode; clients count live
+    if state.config.paranoid_mode {
+        return Ok(Json(serde_json::json!({ "rooms": [] })));
+    }
+
+    let counts = sqlx::query_as::<_, UnreadCount>(
+        "SELECT rm.room_id, rm.last_read_message_id,
+                (SELECT COUNT(*) FROM messages m
+                 WHERE m.room_id = rm.room_id
+                   AND (rm.last_read_message_id IS NULL
+                        OR m.created_at > (SELECT created_at FROM messages WHERE id = rm.last_read_message_id))
+                ) AS unread_count
+         FROM room_members rm
+         WHERE rm.user_id = $1",
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(serde_json::json!({ "rooms": counts })))
+}
+
+/// Room names and descriptions show up in the public directory, so they get
+/// the same checks as usernames
+fn check_room_text(state: &AppState, name: Option<&str>, description: Option<&str>) -> Result<()> {
+    let blocked = state.settings.blocked_words();
+    if let Some(name) = name {
+        check_name(NameKind::RoomName, name, &blocked).map_err(AppError::Validation)?;
+    }
+    if let Some(description) = description {
+        check_name(NameKind::RoomDescription, description, &blocked)
+            .map_err(AppError::Validation)?;
+    }
+    Ok(())
+}
+
+/// Keep one account from filling the server with rooms: how many it may
+/// create an hour and have at once (ROOM_CREATE_PER_HOUR, MAX_OWNED_ROOMS).
+/// Rooms it has deleted count for neither.
+async fn check_room_quota(state: &AppState, user_id: Uuid) -> Result<()> {
+    let (owned, last_hour) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*), COUNT(*) FILTER (WHERE created_at > NOW() - INTERVAL '1 hour')
+         FROM rooms WHERE creator_id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(&state.db)
+    .await?;
+
+    let per_hour = state.config.room_create_per_hour;
+    if per_hour > 0 && last_hour >= per_hour {
+        return Err(AppError::Validation(format!(
+            "You can create at most {} rooms an hour; try again later",
+            per_hour
+        )));
+    }
+    let max_owned = state.config.max_owned_rooms;
+    if max_owned > 0 && owned >= max_owned {
+        return Err(AppError::Validation(format!(
+            "You can own at most {} rooms; delete one first",
+            max_owned
+        )));
+    }
+    Ok(())
+}
+
 // POST /api/rooms - Create room
 pub async fn create_room(
     State(state): State<Arc<AppState>>,
@@ -121,6 +403,16 @@ pub async fn create_room(
         ));
     }
 
+    let max_members = state
+        .settings
+        .room_limits()
+        .resolve(req.max_members)
+        .map_err(AppError::Validation)?;
+    check_room_text(&state, Some(&req.name), req.description.as_deref())?;
+    if !auth.user.is_admin {
+        check_room_quota(&state, auth.user_id).await?;
+    }
+
     // Generate room encryption key
     let room_key = crypto_service.generate_room_key();
 
@@ -135,7 +427,7 @@ pub async fn create_room(
     .bind(auth.user_id)
     .bind(&room_key)
     .bind(if is_public { "public" } else { "private" })
-    .bind(req.max_members.unwrap_or(100))
+    .bind(max_members)
     .fetch_one(&state.db)
     .await?;
 
@@ -146,12 +438,13 @@ pub async fn create_room(
         .bind("admin")
         .execute(&state.db)
         .await?;
+    state.join_user_sockets(auth.user_id, room.id).await;
 
     tracing::info!("Room created: {} by user {}", room.name, auth.user.username);
 
     Ok(Json(serde_json::json!({
         "message": "Room created successfully",
-        "room": room.to_member_json()
+        "room": room.to_member_json(1)
     })))
 }
 
@@ -177,13 +470,8 @@ pub async fn get_room(
     .await?;
 
     // Auto-join public rooms
+    let mut member_count = member_count(&state, room_id).await?;
     if !is_member && room.is_public {
-        let member_count: i64 =
-            sqlx::query_scalar("SELECT COUNT(*) FROM room_members WHERE room_id = $1")
-                .bind(room_id)
-                .fetch_one(&state.db)
-                .await?;
-
         if member_count >= room.max_members as i64 {
             return Err(AppError::BadRequest("Room is full".to_string()));
         }
@@ -194,7 +482,13 @@ pub async fn get_room(
             .bind("member")
             .execute(&state.db)
             .await?;
+        state
+            .guard_cache
+            .invalidate_membership(room_id, auth.user_id)
+            .await;
+        state.join_user_sockets(auth.user_id, room_id).await;
 
+        member_count += 1;
         tracing::info!(
             "User {} auto-joined public room {}",
             auth.user.username,
@@ -207,7 +501,186 @@ pub async fn get_room(
     }
 
     Ok(Json(serde_json::json!({
-        "room": room.to_member_json()
+        "room": room.to_member_json(member_count)
+    })))
+}
+
+// PATCH /api/rooms/:id - Update name, description, member limit, content policy
+// or history gate (room admins)
+pub async fn update_room(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<UpdateRoomRequest>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "edit room settings").await?;
+
+    let max_members = req
+        .max_members
+        .map(|n| state.settings.room_limits().resolve(Some(n)))
+        .transpose()
+        .map_err(AppError::Validation)?;
+    check_room_text(&state, req.name.as_deref(), req.description.as_deref())?;
+
+    let message_types = req
+        .allowed_message_types
+        .map(|types| {
+            if let Some(t) = types
+                .iter()
+                .find(|t| !MessageType::CLIENT_TYPES.contains(t))
+            {
+                return Err(AppError::Validation(format!(
+                    "{} messages can't be allowed in rooms",
+                    t
+                )));
+            }
+            Ok(types.iter().map(|t| t.to_string()).collect::<Vec<_>>())
+        })
+        .transpose()?;
+    if let Some(category) = req
+        .allowed_upload_types
+        .iter()
+        .flatten()
+        .find(|c| !UPLOAD_CATEGORIES.contains(&c.as_str()))
+    {
+        return Err(AppError::Validation(format!(
+            "Unknown file category '{}', expected one of {}",
+            category,
+            UPLOAD_CATEGORIES.join(", ")
+        )));
+    }
+
+    if req
+        .history_gate_minutes
+        .is_some_and(|m| m > Room::MAX_HISTORY_GATE_MINUTES)
+    {
+        return Err(AppError::Validation(format!(
+            "historyGateMinutes can be at most {}",
+            Room::MAX_HISTORY_GATE_MINUTES
+        )));
+    }
+
+    // An empty description clears it, as do empty content policy lists and
+    // a negative history gate
+    let description = req.description.as_deref().map(str::trim);
+    let room = sqlx::query_as::<_, Room>(
+        "UPDATE rooms SET
+             name = COALESCE($2, name),
+             description = CASE WHEN $3::TEXT IS NULL THEN description ELSE NULLIF($3, '') END,
+             max_members = COALESCE($4, max_members),
+             allowed_message_types = CASE WHEN $5::TEXT[] IS NULL THEN allowed_message_types
+                                          ELSE NULLIF($5, '{}') END,
+             allowed_upload_types = CASE WHEN $6::TEXT[] IS NULL THEN allowed_upload_types
+                                         ELSE NULLIF($6, '{}') END,
+             history_gate_minutes = CASE WHEN $7::INTEGER IS NULL THEN history_gate_minutes
+                                         WHEN $7 < 0 THEN NULL ELSE $7 END
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(room_id)
+    .bind(req.name.as_deref().map(str::trim))
+    .bind(description)
+    .bind(max_members)
+    .bind(message_types)
+    .bind(req.allowed_upload_types)
+    .bind(req.history_gate_minutes)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
+
+    tracing::info!("Room {} updated by {}", room.name, auth.user.username);
+
+    let member_count = member_count(&state, room_id).await?;
+    state
+        .io
+        .within(room_id.to_string())
+        .emit("room_updated", &room.to_public_json(member_count))
+        .await
+        .ok();
+
+    Ok(Json(
+        serde_json::json!({ "room": room.to_member_json(member_count) }),
+    ))
+}
+
+// POST /api/rooms/:id/e2ee - Switch the room to end-to-end encryption (creator only)
+pub async fn enable_e2ee(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+        .bind(room_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
+
+    if room.creator_id != Some(auth.user_id) {
+        return Err(AppError::Authorization(
+            "Only the room creator can change encryption".to_string(),
+        ));
+    }
+    if room.is_e2ee() {
+        return Err(AppError::BadRequest(
+            "Room is already end-to-end encrypted".to_string(),
+        ));
+    }
+
+    // Issue the first key epoch and mark everything sent so far as legacy
+    // plaintext, in one go so no message falls between the two
+    let mut tx = state.db.begin().await?;
+    let room = sqlx::query_as::<_, Room>(
+        "UPDATE rooms SET encryption_mode = $2, key_epoch = key_epoch + 1, e2ee_since = NOW()
+         WHERE id = $1 AND encryption_mode = $3
+         RETURNING *",
+    )
+    .bind(room_id)
+    .bind(Room::MODE_E2EE)
+    .bind(Room::MODE_SERVER)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Room is already end-to-end encrypted".to_string()))?;
+    sqlx::query("INSERT INTO room_key_epochs (room_id, epoch, issued_by) VALUES ($1, $2, $3)")
+        .bind(room_id)
+        .bind(room.key_epoch)
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await?;
+    let legacy = sqlx::query(
+        "UPDATE messages SET legacy_plaintext = TRUE WHERE room_id = $1 AND key_epoch IS NULL",
+    )
+    .bind(room_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+
+    tracing::info!(
+        "Room {} switched to E2EE by {} (epoch {}, {} legacy messages)",
+        room.name,
+        auth.user.username,
+        room.key_epoch,
+        legacy
+    );
+
+    state
+        .io
+        .within(room_id.to_string())
+        .emit(
+            "room_encryption_changed",
+            &serde_json::json!({
+                "roomId": room_id,
+                "encryptionMode": room.encryption_mode,
+                "keyEpoch": room.key_epoch,
+                "e2eeSince": room.e2ee_since,
+            }),
+        )
+        .await
+        .ok();
+
+    Ok(Json(serde_json::json!({
+        "room": room.to_member_json(member_count(&state, room_id).await?),
+        "legacyMessages": legacy,
     })))
 }
 
@@ -239,12 +712,7 @@ pub async fn join_room(
     }
 
     // Check capacity
-    let member_count: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM room_members WHERE room_id = $1")
-            .bind(room_id)
-            .fetch_one(&state.db)
-            .await?;
-
+    let member_count = member_count(&state, room_id).await?;
     if member_count >= room.max_members as i64 {
         return Err(AppError::BadRequest("Room is full".to_string()));
     }
@@ -255,12 +723,17 @@ pub async fn join_room(
         .bind("member")
         .execute(&state.db)
         .await?;
+    state
+        .guard_cache
+        .invalidate_membership(room_id, auth.user_id)
+        .await;
+    state.join_user_sockets(auth.user_id, room_id).await;
 
     tracing::info!("User {} joined room {}", auth.user.username, room.name);
 
     Ok(Json(serde_json::json!({
         "message": "Joined room successfully",
-        "room": room.to_member_json()
+        "room": room.to_member_json(member_count + 1)
     })))
 }
 
@@ -292,6 +765,11 @@ pub async fn leave_room(
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Not a member of this room".to_string()));
     }
+    state
+        .guard_cache
+        .invalidate_membership(room_id, auth.user_id)
+        .await;
+    state.leave_user_sockets(auth.user_id, room_id).await;
 
     tracing::info!("User {} left room {}", auth.user.username, room.name);
 
@@ -323,6 +801,7 @@ pub async fn delete_room(
         .bind(room_id)
         .execute(&state.db)
         .await?;
+    state.guard_cache.invalidate_room(room_id).await;
 
     tracing::info!("Room {} deleted by user {}", room.name, auth.user.username);
 
@@ -331,41 +810,20 @@ pub async fn delete_room(
     ))
 }
 
-// GET /api/rooms/:id/messages - Get messages
-pub async fn get_messages(
-    State(state): State<Arc<AppState>>,
-    Extension(auth): Extension<AuthUser>,
-    Path(room_id): Path<Uuid>,
-    Query(pagination): Query<PaginationQuery>,
-) -> Result<Json<serde_json::Value>> {
-    // Global admins can view any room's messages (moderation)
-    if !auth.user.is_admin {
-        let is_member = sqlx::query_scalar::<_, bool>(
-            "SELECT EXISTS(SELECT 1 FROM room_members WHERE room_id = $1 AND user_id = $2)",
-        )
-        .bind(room_id)
-        .bind(auth.user_id)
-        .fetch_one(&state.db)
-        .await?;
-
-        if !is_member {
-            return Err(AppError::Authorization(
-                "Not a member of this room".to_string(),
-            ));
-        }
-    }
-
-    let messages = sqlx::query_as::<_, Message>(
-        "SELECT * FROM messages
-         WHERE room_id = $1
-         ORDER BY created_at ASC
-         LIMIT $2 OFFSET $3",
-    )
-    .bind(room_id)
-    .bind(pagination.limit)
-    .bind(pagination.offset)
-    .fetch_all(&state.db)
-    .await?;
+/// Messages as the API returns them: with their author, reactions and the
+/// message they reply to. Spam details are only included for moderators.
+/// The replied-to message is only included when `auth` could see it in the
+/// message list: same room, not hidden from them, not expired and not before
+/// their history floor.
+async fn message_responses(
+    state: &AppState,
+    auth: &AuthUser,
+    messages: Vec<Message>,
+    can_moderate: bool,
+) -> Result<Vec<MessageResponse>> {
+    let ids: Vec<Uuid> = messages.iter().map(|m| m.id).collect();
+    let mut reactions = reactions_for(state, &ids).await?;
+    let mut floors: HashMap<Uuid, Option<chrono::DateTime<chrono::Utc>>> = HashMap::new();
 
     // Fetch user info for each message
     let mut message_responses = Vec::new();
@@ -377,12 +835,31 @@ pub async fn get_messages(
 
         // Fetch reply message if reply_to is set
         let reply_message = if let Some(reply_id) = msg.reply_to {
-            if let Ok(reply_msg) =
-                sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = $1")
-                    .bind(reply_id)
-                    .fetch_one(&state.db)
-                    .await
-            {
+            let floor = match floors.get(&msg.room_id) {
+                Some(floor) => *floor,
+                None => {
+                    let floor = history_floor(state, auth, msg.room_id).await?;
+                    floors.insert(msg.room_id, floor);
+                    floor
+                }
+            };
+            let reply_msg = sqlx::query_as::<_, Message>(
+                "SELECT * FROM messages
+                 WHERE id = $1 AND room_id = $2
+                   AND ((spam_action IS DISTINCT FROM 'hidden'
+                        AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned))
+                       OR user_id = $3 OR $4)
+                   AND (expires_at IS NULL OR expires_at > NOW())
+                   AND ($5::TIMESTAMPTZ IS NULL OR created_at >= $5)",
+            )
+            .bind(reply_id)
+            .bind(msg.room_id)
+            .bind(auth.user_id)
+            .bind(can_moderate)
+            .bind(floor)
+            .fetch_optional(&state.db)
+            .await?;
+            if let Some(reply_msg) = reply_msg {
                 let reply_user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
                     .bind(reply_msg.user_id)
                     .fetch_optional(&state.db)
@@ -415,13 +892,24 @@ pub async fn get_messages(
             message_type: msg.message_type,
             reply_to: msg.reply_to,
             forwarded_from: msg.forwarded_from,
-            reactions: msg.reactions,
+            reactions: reactions
+                .remove(&msg.id)
+                .unwrap_or_else(|| serde_json::json!({})),
             metadata: msg.metadata,
             created_at: msg.created_at,
             updated_at: msg.updated_at,
             pinned_by: msg.pinned_by,
             pinned_at: msg.pinned_at,
+            key_epoch: msg.key_epoch,
+            legacy_plaintext: msg.legacy_plaintext,
+            expires_in: msg.expires_in,
+            expires_after_read: msg.expires_after_read,
+            expires_at: msg.expires_at,
+            revision_count: msg.revision_count,
             reply_message,
+            client_nonce: None,
+            spam_score: msg.spam_score.filter(|_| can_moderate),
+            spam_action: msg.spam_action.filter(|_| can_moderate),
             user: serde_json::json!({
                 "id": user.id,
                 "username": user.username,
@@ -432,43 +920,233 @@ pub async fn get_messages(
         });
     }
 
-    Ok(Json(serde_json::json!({ "messages": message_responses })))
+    Ok(message_responses)
 }
 
-// POST /api/rooms/:id/messages - Send message
-pub async fn send_message(
+// GET /api/rooms/:id/messages - Get messages
+pub async fn get_messages(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthUser>,
     Path(room_id): Path<Uuid>,
-    Json(body): Json<SendMessageBody>,
+    Query(pagination): Query<PaginationQuery>,
 ) -> Result<Json<serde_json::Value>> {
-    // Check if user is member
-    let is_member = sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM room_members WHERE room_id = $1 AND user_id = $2)",
+    let can_moderate = require_reader(&state, &auth, room_id).await?;
+    let floor = history_floor(&state, &auth, room_id).await?;
+
+    let messages = sqlx::query_as::<_, Message>(
+        "SELECT * FROM messages
+         WHERE room_id = $1
+           AND ((spam_action IS DISTINCT FROM 'hidden'
+                AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned))
+               OR user_id = $4 OR $5)
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND ($6::TIMESTAMPTZ IS NULL OR created_at >= $6)
+         ORDER BY created_at ASC
+         LIMIT $2 OFFSET $3",
     )
     .bind(room_id)
+    .bind(pagination.limit)
+    .bind(pagination.offset)
     .bind(auth.user_id)
-    .fetch_one(&state.db)
+    .bind(can_moderate)
+    .bind(floor)
+    .fetch_all(&state.db)
     .await?;
 
-    if !is_member {
-        return Err(AppError::Authorization(
-            "Not a member of this room".to_string(),
-        ));
-    }
+    let message_responses = message_responses(&state, &auth, messages, can_moderate).await?;
 
-    let message_type = body.message_type.unwrap_or_else(|| "text".to_string());
+    // Set while history gating hides what was sent before this
+    Ok(Json(serde_json::json!({
+        "messages": message_responses,
+        "historyHiddenBefore": floor,
+    })))
+}
 
-    let msg = sqlx::query_as::<_, Message>(
-        "INSERT INTO messages (room_id, user_id, content, message_type, reply_to)
-         VALUES ($1, $2, $3, $4, $5)
-         RETURNING *",
-    )
-    .bind(room_id)
-    .bind(auth.user_id)
+// GET /api/rooms/:id/messages/:message_id/context - A message with the ones
+// around it, for opening a link to it
+pub async fn get_message_context(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path((room_id, message_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<ContextQuery>,
+) -> Result<Json<serde_json::Value>> {
+    let can_moderate = require_reader(&state, &auth, room_id).await?;
+    let floor = history_floor(&state, &auth, room_id).await?;
+    let around = query.around.clamp(0, 100);
+
+    // Same visibility as the message list: a link can't reveal what the
+    // caller wouldn't see by scrolling
+    let target = sqlx::query_as::<_, Message>(
+        "SELECT * FROM messages
+         WHERE id = $2 AND room_id = $1
+           AND ((spam_action IS DISTINCT FROM 'hidden'
+                AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned))
+               OR user_id = $3 OR $4)
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND ($5::TIMESTAMPTZ IS NULL OR created_at >= $5)",
+    )
+    .bind(room_id)
+    .bind(message_id)
+    .bind(auth.user_id)
+    .bind(can_moderate)
+    .bind(floor)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
+
+    // One extra on each side tells whether there's more to load; ties on
+    // created_at are broken by id so nothing is skipped or repeated
+    let mut before = sqlx::query_as::<_, Message>(
+        "SELECT * FROM messages
+         WHERE room_id = $1
+           AND (created_at, id) < ($2, $3)
+           AND ((spam_action IS DISTINCT FROM 'hidden'
+                AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned))
+               OR user_id = $4 OR $5)
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND ($7::TIMESTAMPTZ IS NULL OR created_at >= $7)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $6",
+    )
+    .bind(room_id)
+    .bind(target.created_at)
+    .bind(target.id)
+    .bind(auth.user_id)
+    .bind(can_moderate)
+    .bind(around + 1)
+    .bind(floor)
+    .fetch_all(&state.db)
+    .await?;
+    let mut after = sqlx::query_as::<_, Message>(
+        "SELECT * FROM messages
+         WHERE room_id = $1
+           AND (created_at, id) > ($2, $3)
+           AND ((spam_action IS DISTINCT FROM 'hidden'
+                AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned))
+               OR user_id = $4 OR $5)
+           AND (expires_at IS NULL OR expires_at > NOW())
+         ORDER BY created_at ASC, id ASC
+         LIMIT $6",
+    )
+    .bind(room_id)
+    .bind(target.created_at)
+    .bind(target.id)
+    .bind(auth.user_id)
+    .bind(can_moderate)
+    .bind(around + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let has_more_before = before.len() as i64 > around;
+    let has_more_after = after.len() as i64 > around;
+    before.truncate(around as usize);
+    after.truncate(around as usize);
+    before.reverse();
+
+    let messages: Vec<Message> = before
+        .into_iter()
+        .chain(std::iter::once(target))
+        .chain(after)
+        .collect();
+    let message_responses = message_responses(&state, &auth, messages, can_moderate).await?;
+
+    Ok(Json(serde_json::json!({
+        "messages": message_responses,
+        "messageId": message_id,
+        "hasMoreBefore": has_more_before,
+        "hasMoreAfter": has_more_after,
+    })))
+}
+
+// POST /api/rooms/:id/messages - Send message
+pub async fn send_message(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+    Json(body): Json<SendMessageBody>,
+) -> Result<Json<serde_json::Value>> {
+    // Check if user is member, and not muted
+    let muted = sqlx::query_scalar::<_, bool>(
+        "SELECT COALESCE(muted_until > NOW(), false) FROM room_members
+         WHERE room_id = $1 AND user_id = $2",
+    )
+    .bind(room_id)
+    .bind(auth.user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::Authorization("Not a member of this room".to_string()))?;
+
+    if muted {
+        return Err(AppError::Authorization(
+            "You are muted in this room".to_string(),
+        ));
+    }
+
+    Message::check_expires_in(body.expires_in).map_err(AppError::Validation)?;
+    let expires_after_read = body.expires_in.is_some() && body.expire_after_read.unwrap_or(true);
+
+    let message_type = Message::check_type(
+        body.message_type.as_deref(),
+        &body.content,
+        body.metadata.as_ref(),
+        &state.config.message_types,
+    )
+    .map_err(AppError::Validation)?;
+    let policy = sqlx::query_as::<_, ContentPolicy>(&format!(
+        "SELECT {} FROM rooms WHERE id = $1",
+        ContentPolicy::COLUMNS
+    ))
+    .bind(room_id)
+    .fetch_one(&state.db)
+    .await?;
+    policy
+        .check(message_type, body.metadata.as_ref())
+        .map_err(AppError::Authorization)?;
+    // @room and @here notify everyone, so only room admins get them
+    let mention = match message_type {
+        MessageType::Text => MentionScope::find(&body.content),
+        _ => None,
+    };
+    if mention.is_some() {
+        let is_room_admin = require_reader(&state, &auth, room_id).await?;
+        state
+            .room_mentions
+            .check(room_id, is_room_admin)
+            .await
+            .map_err(AppError::Authorization)?;
+    }
+    let spam = state
+        .spam_filter
+        .check(
+            &state.db,
+            room_id,
+            &auth.user,
+            &body.content,
+            message_type.as_str(),
+        )
+        .await;
+
+    // A fixed lifetime counts from now; burn-after-reading waits for a read
+    let msg = sqlx::query_as::<_, Message>(
+        "INSERT INTO messages (room_id, user_id, content, message_type, reply_to, metadata,
+                               spam_score, spam_reasons, spam_action, key_epoch,
+                               expires_in, expires_after_read, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9,
+                 (SELECT key_epoch FROM rooms WHERE id = $1 AND encryption_mode = 'e2ee'),
+                 $10, $11, CASE WHEN NOT $11 THEN NOW() + $10 * INTERVAL '1 second' END)
+         RETURNING *",
+    )
+    .bind(room_id)
+    .bind(auth.user_id)
     .bind(&body.content)
-    .bind(&message_type)
+    .bind(message_type.as_str())
     .bind(body.reply_to)
+    .bind(&body.metadata)
+    .bind(spam.as_ref().map(|s| s.score))
+    .bind(spam.as_ref().map(|s| serde_json::json!(s.reasons)))
+    .bind(spam.as_ref().and_then(|s| s.action_str()))
+    .bind(body.expires_in)
+    .bind(expires_after_read)
     .fetch_one(&state.db)
     .await?;
 
@@ -480,13 +1158,22 @@ pub async fn send_message(
         message_type: msg.message_type,
         reply_to: msg.reply_to,
         forwarded_from: msg.forwarded_from,
-        reactions: msg.reactions,
+        reactions: serde_json::json!({}),
         metadata: msg.metadata,
         created_at: msg.created_at,
         updated_at: msg.updated_at,
         pinned_by: msg.pinned_by,
         pinned_at: msg.pinned_at,
+        key_epoch: msg.key_epoch,
+        legacy_plaintext: msg.legacy_plaintext,
+        expires_in: msg.expires_in,
+        expires_after_read: msg.expires_after_read,
+        expires_at: msg.expires_at,
+        revision_count: msg.revision_count,
         reply_message: None,
+        client_nonce: body.client_nonce.map(|n| n.chars().take(64).collect()),
+        spam_score: None,
+        spam_action: None,
         user: serde_json::json!({
             "id": auth.user.id,
             "username": auth.user.username,
@@ -502,15 +1189,28 @@ pub async fn send_message(
         auth.user.username
     );
 
+    // Same delivery as a socket send, so socket clients see it live
+    let outgoing = Outgoing {
+        room_id,
+        message_id: msg.id,
+        sender: &auth.user,
+        shadow_banned: auth.user.is_shadow_banned,
+        spam: spam.as_ref(),
+        mention,
+    };
+    state.deliver_message(&response, outgoing).await;
+
     Ok(Json(serde_json::json!({ "message": response })))
 }
 
-// GET /api/rooms/:id/members - Get room members
+// GET /api/rooms/:id/members - Get room members, with their roles and
+// permissions. Answers 304 to an If-None-Match of the current ETag.
 pub async fn get_members(
     State(state): State<Arc<AppState>>,
     Extension(auth): Extension<AuthUser>,
     Path(room_id): Path<Uuid>,
-) -> Result<Json<serde_json::Value>> {
+    headers: HeaderMap,
+) -> Result<Response> {
     // Global admins can view any room's members (moderation)
     if !auth.user.is_admin {
         let is_member = sqlx::query_scalar::<_, bool>(
@@ -528,34 +1228,76 @@ pub async fn get_members(
         }
     }
 
-    let members = sqlx::query_as::<_, RoomMember>("SELECT * FROM room_members WHERE room_id = $1")
-        .bind(room_id)
-        .fetch_all(&state.db)
-        .await?;
-
-    let mut member_responses = Vec::new();
-    for member in members {
-        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
-            .bind(member.user_id)
-            .fetch_one(&state.db)
-            .await?;
-
-        member_responses.push(serde_json::json!({
-            "userId": member.user_id,
-            "role": member.role,
-            "joinedAt": member.joined_at,
-            "user": {
-                "id": user.id,
-                "username": user.username,
-                "displayName": user.display_name,
-                "avatar": user.avatar,
-                "isOnline": user.is_online,
-                "lastSeen": user.last_seen,
-            }
-        }));
+    // Unchanged since the caller's copy: member count and the newest change
+    // to a member or their user
+    let (count, changed): (i64, Option<chrono::DateTime<chrono::Utc>>) = sqlx::query_as(
+        "SELECT COUNT(*), GREATEST(MAX(rm.updated_at), MAX(u.updated_at))
+         FROM room_members rm
+         JOIN users u ON u.id = rm.user_id
+         WHERE rm.room_id = $1",
+    )
+    .bind(room_id)
+    .fetch_one(&state.db)
+    .await?;
+    let etag = format!(
+        "W/\"{}-{}\"",
+        count,
+        changed.map(|t| t.timestamp_micros()).unwrap_or(0)
+    );
+    let cached = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag));
+    if cached {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
     }
 
-    Ok(Json(serde_json::json!({ "members": member_responses })))
+    let members = sqlx::query_as::<_, MemberDetails>(
+        "SELECT rm.user_id, rm.role, rm.joined_at,
+                r.creator_id IS NOT DISTINCT FROM rm.user_id AS is_creator,
+                u.username, u.display_name, u.avatar, u.public_key, u.is_online, u.last_seen,
+                rm.history_approved_at
+         FROM room_members rm
+         JOIN users u ON u.id = rm.user_id
+         JOIN rooms r ON r.id = rm.room_id
+         WHERE rm.room_id = $1
+         ORDER BY rm.joined_at",
+    )
+    .bind(room_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let member_responses: Vec<_> = members
+        .into_iter()
+        .map(|member| {
+            serde_json::json!({
+                "userId": member.user_id,
+                "role": member.role,
+                "isCreator": member.is_creator,
+                "permissions": RoomMember::permissions(&member.role),
+                "joinedAt": member.joined_at,
+                "historyApprovedAt": member.history_approved_at,
+                "user": {
+                    "id": member.user_id,
+                    "username": member.username,
+                    "displayName": member.display_name,
+                    "avatar": member.avatar,
+                    "publicKey": member.public_key,
+                    "isOnline": member.is_online,
+                    "lastSeen": member.last_seen,
+                }
+            })
+        })
+        .collect();
+
+    Ok((
+        [
+            (header::ETAG, etag),
+            (header::CACHE_CONTROL, "private, no-cache".to_string()),
+        ],
+        Json(serde_json::json!({ "members": member_responses })),
+    )
+        .into_response())
 }
 
 // POST /api/rooms/:id/members - Add member (admin only)
@@ -610,6 +1352,20 @@ pub async fn add_member(
         return Err(AppError::BadRequest("Room is full".to_string()));
     }
 
+    if needs_request(&state, &auth.user, user_id, &room).await? {
+        create_request(&state, &auth.user, user_id, &room).await?;
+        tracing::info!(
+            "Request to join room {} sent to {} by {}",
+            room.name,
+            target_user.username,
+            auth.user.username
+        );
+        return Ok(Json(serde_json::json!({
+            "message": "This user only accepts rooms from people they know; they got a message request",
+            "requested": true,
+        })));
+    }
+
     // Add member
     sqlx::query(
         "INSERT INTO room_members (room_id, user_id, role) VALUES ($1, $2, $3)
@@ -620,6 +1376,11 @@ pub async fn add_member(
     .bind("member")
     .execute(&state.db)
     .await?;
+    state
+        .guard_cache
+        .invalidate_membership(room_id, user_id)
+        .await;
+    state.join_user_sockets(user_id, room_id).await;
 
     tracing::info!(
         "User {} added to room {} by {}",
@@ -692,6 +1453,11 @@ pub async fn remove_member(
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Member not found".to_string()));
     }
+    state
+        .guard_cache
+        .invalidate_membership(room_id, user_id)
+        .await;
+    state.leave_user_sockets(user_id, room_id).await;
 
     tracing::info!("User {} removed from room {}", user_id, room.name);
 
@@ -713,6 +1479,483 @@ pub async fn remove_member(
     ))
 }
 
+#[derive(Deserialize)]
+pub struct RoleBody {
+    role: String,
+}
+
+// PUT /api/rooms/:id/members/:userId/role - Promote to or demote from room admin
+pub async fn set_member_role(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path((room_id, user_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<RoleBody>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "change member roles").await?;
+
+    if body.role != "admin" && body.role != "member" {
+        return Err(AppError::Validation(
+            "role must be admin or member".to_string(),
+        ));
+    }
+
+    let creator_id: Option<Uuid> = sqlx::query_scalar("SELECT creator_id FROM rooms WHERE id = $1")
+        .bind(room_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
+    if creator_id == Some(user_id) {
+        return Err(AppError::BadRequest(
+            "The room creator's role can't be changed".to_string(),
+        ));
+    }
+
+    let result = sqlx::query(
+        "UPDATE room_members SET role = $3, updated_at = NOW() WHERE room_id = $1 AND user_id = $2",
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .bind(&body.role)
+    .execute(&state.db)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Member not found".to_string()));
+    }
+    state
+        .guard_cache
+        .invalidate_membership(room_id, user_id)
+        .await;
+
+    tracing::info!(
+        "User {} set role of {} in room {} to {}",
+        auth.user.username,
+        user_id,
+        room_id,
+        body.role
+    );
+
+    state
+        .io
+        .within(room_id.to_string())
+        .emit(
+            "member_role_changed",
+            &serde_json::json!({
+                "roomId": room_id,
+                "userId": user_id,
+                "role": body.role,
+            }),
+        )
+        .await
+        .ok();
+
+    Ok(Json(serde_json::json!({ "role": body.role })))
+}
+
+/// Longest a member can be muted for: 30 days
+const MAX_MUTE_MINUTES: i64 = 30 * 24 * 60;
+
+#[derive(Deserialize)]
+pub struct MuteBody {
+    /// Minutes from now; null or 0 lifts the mute
+    minutes: Option<i64>,
+}
+
+// PUT /api/rooms/:id/members/:userId/mute - Stop a member from posting for a
+// while, or let them post again
+pub async fn mute_member(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path((room_id, user_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<MuteBody>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "mute members").await?;
+
+    let minutes = body.minutes.unwrap_or(0);
+    if !(0..=MAX_MUTE_MINUTES).contains(&minutes) {
+        return Err(AppError::Validation(format!(
+            "minutes must be between 0 and {}",
+            MAX_MUTE_MINUTES
+        )));
+    }
+
+    let role = sqlx::query_scalar::<_, String>(
+        "SELECT role FROM room_members WHERE room_id = $1 AND user_id = $2",
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+    if minutes > 0 && role == RoomMember::ROLE_ADMIN {
+        return Err(AppError::BadRequest(
+            "Room admins can't be muted".to_string(),
+        ));
+    }
+
+    let muted_until = sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+        "UPDATE room_members
+         SET muted_until = CASE WHEN $3 > 0 THEN NOW() + make_interval(mins => $3::INT) END,
+             updated_at = NOW()
+         WHERE room_id = $1 AND user_id = $2
+         RETURNING muted_until",
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .bind(minutes)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+    state
+        .guard_cache
+        .invalidate_membership(room_id, user_id)
+        .await;
+
+    tracing::info!(
+        "User {} muted {} in room {} for {} minutes",
+        auth.user.username,
+        user_id,
+        room_id,
+        minutes
+    );
+
+    state
+        .io
+        .within(room_id.to_string())
+        .emit(
+            "member_muted",
+            &serde_json::json!({
+                "roomId": room_id,
+                "userId": user_id,
+                "mutedUntil": muted_until,
+            }),
+        )
+        .await
+        .ok();
+
+    Ok(Json(serde_json::json!({ "mutedUntil": muted_until })))
+}
+
+// POST /api/rooms/:id/members/:userId/history - Let a member see the history
+// from before they joined without waiting out the room's history gate
+pub async fn approve_history(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path((room_id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<serde_json::Value>> {
+    require_room_admin(&state, &auth, room_id, "approve history access").await?;
+
+    let approved_at = sqlx::query_scalar::<_, chrono::DateTime<chrono::Utc>>(
+        "UPDATE room_members
+         SET history_approved_at = COALESCE(history_approved_at, NOW()), updated_at = NOW()
+         WHERE room_id = $1 AND user_id = $2
+         RETURNING history_approved_at",
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+
+    tracing::info!(
+        "User {} approved history access of {} in room {}",
+        auth.user.username,
+        user_id,
+        room_id
+    );
+
+    // So the member's clients can load what they couldn't see before
+    state
+        .emit_to_user(
+            user_id,
+            "history_unlocked",
+            &serde_json::json!({ "roomId": room_id }),
+        )
+        .await;
+
+    Ok(Json(
+        serde_json::json!({ "historyApprovedAt": approved_at }),
+    ))
+}
+
+// GET /api/rooms/:id/pins - Pinned messages, most recently pinned first
+pub async fn list_pins(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    require_reader(&state, &auth, room_id).await?;
+    let floor = history_floor(&state, &auth, room_id).await?;
+
+    let pins = sqlx::query_as::<_, Message>(
+        "SELECT * FROM messages
+         WHERE room_id = $1 AND pinned_by IS NOT NULL
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND ($2::TIMESTAMPTZ IS NULL OR created_at >= $2)
+         ORDER BY pinned_at DESC",
+    )
+    .bind(room_id)
+    .bind(floor)
+    .fetch_all(&state.db)
+    .await?;
+
+    let ids: Vec<Uuid> = pins.iter().map(|m| m.id).collect();
+    let mut reactions = reactions_for(&state, &ids).await?;
+
+    let mut responses = Vec::with_capacity(pins.len());
+    for msg in pins {
+        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+            .bind(msg.user_id)
+            .fetch_optional(&state.db)
+            .await?;
+        responses.push(serde_json::json!({
+            "id": msg.id,
+            "roomId": msg.room_id,
+            "userId": msg.user_id,
+            "content": msg.content,
+            "messageType": msg.message_type,
+            "metadata": msg.metadata,
+            "reactions": reactions.remove(&msg.id).unwrap_or_else(|| serde_json::json!({})),
+            "createdAt": msg.created_at,
+            "pinnedBy": msg.pinned_by,
+            "pinnedAt": msg.pinned_at,
+            "keyEpoch": msg.key_epoch,
+            "legacyPlaintext": msg.legacy_plaintext,
+            "revisionCount": msg.revision_count,
+            "user": user.map(|u| serde_json::json!({
+                "id": u.id,
+                "username": u.username,
+                "displayName": u.display_name,
+            })),
+        }));
+    }
+
+    Ok(Json(serde_json::json!({ "pins": responses })))
+}
+
+#[derive(sqlx::FromRow)]
+struct ReactorRow {
+    emoji: String,
+    user_id: Uuid,
+    username: String,
+    display_name: Option<String>,
+}
+
+// GET /api/rooms/:id/messages/:message_id/reactions - Per-emoji counts and
+// who reacted, in order of the first reaction
+pub async fn get_reactions(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path((room_id, message_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<serde_json::Value>> {
+    require_reader(&state, &auth, room_id).await?;
+
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM messages WHERE id = $1 AND room_id = $2)")
+            .bind(message_id)
+            .bind(room_id)
+            .fetch_one(&state.db)
+            .await?;
+    if !exists {
+        return Err(AppError::NotFound("Message not found".to_string()));
+    }
+
+    let rows = sqlx::query_as::<_, ReactorRow>(
+        "SELECT r.emoji, r.user_id, u.username, u.display_name
+         FROM message_reactions r
+         JOIN users u ON u.id = r.user_id
+         WHERE r.message_id = $1
+         ORDER BY r.created_at",
+    )
+    .bind(message_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut groups: Vec<(String, Vec<serde_json::Value>)> = Vec::new();
+    for row in rows {
+        let user = serde_json::json!({
+            "id": row.user_id,
+            "username": row.username,
+            "displayName": row.display_name,
+        });
+        match groups.iter_mut().find(|(emoji, _)| *emoji == row.emoji) {
+            Some((_, users)) => users.push(user),
+            None => groups.push((row.emoji, vec![user])),
+        }
+    }
+
+    let reactions: Vec<serde_json::Value> = groups
+        .into_iter()
+        .map(|(emoji, users)| {
+            serde_json::json!({
+                "emoji": emoji,
+                "count": users.len(),
+                "users": users,
+            })
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({ "reactions": reactions })))
+}
+
+// GET /api/messages/:id/history - Earlier versions of an edited message,
+// oldest first (room admins only)
+pub async fn message_history(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(message_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let msg = sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = $1")
+        .bind(message_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
+
+    require_room_admin(&state, &auth, msg.room_id, "view edit history").await?;
+
+    let revisions = sqlx::query_as::<_, MessageRevision>(
+        "SELECT * FROM message_revisions WHERE message_id = $1 ORDER BY replaced_at, id",
+    )
+    .bind(message_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(serde_json::json!({
+        "messageId": msg.id,
+        "roomId": msg.room_id,
+        "content": msg.content,
+        "keyEpoch": msg.key_epoch,
+        "updatedAt": msg.updated_at,
+        "revisionCount": msg.revision_count,
+        "revisions": revisions,
+    })))
+}
+
+// GET /api/rooms/:id/export/mine - The caller's own messages in the room as
+// a JSON file, oldest first, for a personal archive
+pub async fn export_my_messages(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Response> {
+    require_reader(&state, &auth, room_id).await?;
+
+    let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+        .bind(room_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Room not found".to_string()))?;
+
+    // Expired messages are gone for the sender too
+    let messages = sqlx::query_as::<_, Message>(
+        "SELECT * FROM messages
+         WHERE room_id = $1 AND user_id = $2
+           AND (expires_at IS NULL OR expires_at > NOW())
+         ORDER BY created_at ASC",
+    )
+    .bind(room_id)
+    .bind(auth.user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let messages: Vec<serde_json::Value> = messages
+        .into_iter()
+        .map(|msg| {
+            serde_json::json!({
+                "id": msg.id,
+                "messageType": msg.message_type,
+                // Ciphertext for E2EE messages; the app holds the keys
+                "content": msg.content,
+                "metadata": msg.metadata,
+                "replyTo": msg.reply_to,
+                "forwardedFrom": msg.forwarded_from,
+                "keyEpoch": msg.key_epoch,
+                "createdAt": msg.created_at,
+                "updatedAt": msg.updated_at,
+            })
+        })
+        .collect();
+
+    tracing::info!(
+        "User {} exported {} of their messages from room {}",
+        auth.user.username,
+        messages.len(),
+        room_id
+    );
+
+    let export = serde_json::json!({
+        "room": {
+            "id": room.id,
+            "name": room.name,
+            "encryptionMode": room.encryption_mode,
+        },
+        "user": {
+            "id": auth.user.id,
+            "username": auth.user.username,
+        },
+        "exportedAt": chrono::Utc::now(),
+        "messages": messages,
+    });
+
+    // The room name isn't safe in a header; the id is
+    let disposition = format!("attachment; filename=\"messages-{}.json\"", room.id);
+    Ok((
+        [
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        Json(export),
+    )
+        .into_response())
+}
+
+// GET /api/rooms/:id/notifications - The caller's notification settings for the room
+pub async fn get_notifications(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+) -> Result<Json<NotificationSettings>> {
+    let settings = sqlx::query_as::<_, NotificationSettings>(
+        "SELECT notification_level, notifications_muted_until FROM room_members
+         WHERE room_id = $1 AND user_id = $2",
+    )
+    .bind(room_id)
+    .bind(auth.user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::Authorization("Not a member of this room".to_string()))?;
+
+    Ok(Json(settings))
+}
+
+// PUT /api/rooms/:id/notifications - Update the caller's notification settings
+pub async fn update_notifications(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+    Json(body): Json<NotificationSettings>,
+) -> Result<Json<NotificationSettings>> {
+    if !NotificationSettings::LEVELS.contains(&body.level.as_str()) {
+        return Err(AppError::Validation(
+            "level must be all, mentions or none".to_string(),
+        ));
+    }
+
+    let settings = sqlx::query_as::<_, NotificationSettings>(
+        "UPDATE room_members SET notification_level = $3, notifications_muted_until = $4
+         WHERE room_id = $1 AND user_id = $2
+         RETURNING notification_level, notifications_muted_until",
+    )
+    .bind(room_id)
+    .bind(auth.user_id)
+    .bind(&body.level)
+    .bind(body.muted_until)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::Authorization("Not a member of this room".to_string()))?;
+
+    Ok(Json(settings))
+}
+
 // GET /api/rooms/:id/search - Search messages
 pub async fn search_messages(
     State(state): State<Arc<AppState>>,
@@ -720,32 +1963,41 @@ pub async fn search_messages(
     Path(room_id): Path<Uuid>,
     Query(query): Query<SearchQuery>,
 ) -> Result<Json<serde_json::Value>> {
-    // Global admins can search any room's messages (moderation)
-    if !auth.user.is_admin {
-        let is_member = sqlx::query_scalar::<_, bool>(
-            "SELECT EXISTS(SELECT 1 FROM room_members WHERE room_id = $1 AND user_id = $2)",
-        )
-        .bind(room_id)
-        .bind(auth.user_id)
-        .fetch_one(&state.db)
-        .await?;
+    let can_moderate = require_reader(&state, &auth, room_id).await?;
 
-        if !is_member {
-            return Err(AppError::Authorization(
-                "Not a member of this room".to_string(),
-            ));
-        }
+    let e2ee =
+        sqlx::query_scalar::<_, bool>("SELECT encryption_mode = 'e2ee' FROM rooms WHERE id = $1")
+            .bind(room_id)
+            .fetch_one(&state.db)
+            .await?;
+    if e2ee {
+        return Err(AppError::BadRequest(
+            "Server-side search is disabled in end-to-end encrypted rooms".to_string(),
+        ));
     }
 
     // Return all messages for client-side decryption and search
     // Since messages are encrypted, we can't search server-side
     let messages = sqlx::query_as::<_, Message>(
-        "SELECT * FROM messages WHERE room_id = $1 ORDER BY created_at ASC",
+        "SELECT * FROM messages
+         WHERE room_id = $1
+           AND ((spam_action IS DISTINCT FROM 'hidden'
+                AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned))
+               OR user_id = $2 OR $3)
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+         ORDER BY created_at ASC",
     )
     .bind(room_id)
+    .bind(auth.user_id)
+    .bind(can_moderate)
+    .bind(history_floor(&state, &auth, room_id).await?)
     .fetch_all(&state.db)
     .await?;
 
+    let ids: Vec<Uuid> = messages.iter().map(|m| m.id).collect();
+    let mut reactions = reactions_for(&state, &ids).await?;
+
     let mut message_responses = Vec::new();
     for msg in messages {
         let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
@@ -761,13 +2013,24 @@ pub async fn search_messages(
             message_type: msg.message_type,
             reply_to: msg.reply_to,
             forwarded_from: msg.forwarded_from,
-            reactions: msg.reactions,
+            reactions: reactions
+                .remove(&msg.id)
+                .unwrap_or_else(|| serde_json::json!({})),
             metadata: msg.metadata,
             created_at: msg.created_at,
             updated_at: msg.updated_at,
             pinned_by: msg.pinned_by,
             pinned_at: msg.pinned_at,
+            key_epoch: msg.key_epoch,
+            legacy_plaintext: msg.legacy_plaintext,
+            expires_in: msg.expires_in,
+            expires_after_read: msg.expires_after_read,
+            expires_at: msg.expires_at,
+            revision_count: msg.revision_count,
             reply_message: None,
+            client_nonce: None,
+            spam_score: msg.spam_score.filter(|_| can_moderate),
+            spam_action: msg.spam_action.filter(|_| can_moderate),
             user: serde_json::json!({
                 "id": user.id,
                 "username": user.username,
@@ -783,3 +2046,185 @@ pub async fn search_messages(
         "query": query.q
     })))
 }
+
+// GET /api/search/messages - Search every room the user is in that isn't
+// end-to-end encrypted, newest matches first, grouped by room
+pub async fn search_all_messages(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Query(query): Query<GlobalSearchQuery>,
+) -> Result<Json<serde_json::Value>> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err(AppError::Validation("Search text is required".to_string()));
+    }
+    let limit = query.limit.clamp(1, 100);
+
+    // The expression and the key_epoch condition match the partial
+    // full-text index, so Postgres can use it
+    let messages = sqlx::query_as::<_, Message>(
+        "SELECT m.* FROM messages m
+         JOIN room_members rm ON rm.room_id = m.room_id AND rm.user_id = $1
+         JOIN rooms r ON r.id = m.room_id
+         WHERE r.encryption_mode <> 'e2ee'
+           AND m.key_epoch IS NULL
+           AND to_tsvector('simple', m.content) @@ plainto_tsquery('simple', $2)
+           AND ((m.spam_action IS DISTINCT FROM 'hidden'
+                AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = m.user_id AND su.is_shadow_banned))
+               OR m.user_id = $1)
+           AND (m.expires_at IS NULL OR m.expires_at > NOW())
+           AND (r.history_gate_minutes IS NULL
+                OR m.created_at >= rm.joined_at
+                OR rm.role = 'admin'
+                OR rm.history_approved_at IS NOT NULL
+                OR (r.history_gate_minutes > 0
+                    AND rm.joined_at + make_interval(mins => r.history_gate_minutes) <= NOW()))
+         ORDER BY m.created_at DESC
+         LIMIT $3",
+    )
+    .bind(auth.user_id)
+    .bind(q)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    let room_ids: Vec<Uuid> = messages.iter().map(|m| m.room_id).collect();
+    let names: HashMap<Uuid, String> =
+        sqlx::query_as::<_, (Uuid, String)>("SELECT id, name FROM rooms WHERE id = ANY($1)")
+            .bind(&room_ids)
+            .fetch_all(&state.db)
+            .await?
+            .into_iter()
+            .collect();
+
+    // Rooms in the order of their newest match
+    let mut groups: Vec<(Uuid, Vec<MessageResponse>)> = Vec::new();
+    for message in message_responses(&state, &auth, messages, false).await? {
+        match groups.iter_mut().find(|(id, _)| *id == message.room_id) {
+            Some((_, hits)) => hits.push(message),
+            None => groups.push((message.room_id, vec![message])),
+        }
+    }
+    let rooms: Vec<serde_json::Value> = groups
+        .into_iter()
+        .map(|(room_id, hits)| {
+            serde_json::json!({
+                "roomId": room_id,
+                "roomName": names.get(&room_id),
+                "messages": hits,
+            })
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "rooms": rooms,
+        "query": q,
+    })))
+}
+
+// GET /api/rooms/:id/files - List attachments shared in a room, newest first
+pub async fn list_files(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+    Query(query): Query<FilesQuery>,
+) -> Result<Json<serde_json::Value>> {
+    let can_moderate = require_reader(&state, &auth, room_id).await?;
+    let floor = history_floor(&state, &auth, room_id).await?;
+
+    let types: Vec<&str> = match query.kind.as_deref() {
+        None | Some("") => FILE_MESSAGE_TYPES.to_vec(),
+        Some(kind) if FILE_MESSAGE_TYPES.contains(&kind) => vec![kind],
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "type must be one of image, video, audio, file".to_string(),
+            ))
+        }
+    };
+    let limit = query.limit.clamp(1, 100);
+    let offset = query.offset.max(0);
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM messages
+         WHERE room_id = $1 AND message_type = ANY($2)
+           AND ((spam_action IS DISTINCT FROM 'hidden'
+                AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned))
+               OR user_id = $3 OR $4)
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND ($5::TIMESTAMPTZ IS NULL OR created_at >= $5)",
+    )
+    .bind(room_id)
+    .bind(&types)
+    .bind(auth.user_id)
+    .bind(can_moderate)
+    .bind(floor)
+    .fetch_one(&state.db)
+    .await?;
+
+    let rows = sqlx::query_as::<_, FileRow>(
+        "SELECT m.id, m.user_id, m.content, m.message_type, m.metadata, m.created_at,
+                u.username, u.display_name
+         FROM messages m
+         JOIN users u ON u.id = m.user_id
+         WHERE m.room_id = $1 AND m.message_type = ANY($2)
+           AND ((m.spam_action IS DISTINCT FROM 'hidden' AND NOT u.is_shadow_banned)
+               OR m.user_id = $5 OR $6)
+           AND (m.expires_at IS NULL OR m.expires_at > NOW())
+           AND ($7::TIMESTAMPTZ IS NULL OR m.created_at >= $7)
+         ORDER BY m.created_at DESC
+         LIMIT $3 OFFSET $4",
+    )
+    .bind(room_id)
+    .bind(&types)
+    .bind(limit)
+    .bind(offset)
+    .bind(auth.user_id)
+    .bind(can_moderate)
+    .bind(floor)
+    .fetch_all(&state.db)
+    .await?;
+
+    let files: Vec<serde_json::Value> = rows
+        .into_iter()
+        .map(|row| {
+            let meta = row.metadata.unwrap_or(serde_json::Value::Null);
+            serde_json::json!({
+                "messageId": row.id,
+                "url": row.content,
+                "messageType": row.message_type,
+                "name": meta.get("originalName"),
+                "mimeType": meta.get("mimetype"),
+                "size": meta.get("size"),
+                "createdAt": row.created_at,
+                "user": {
+                    "id": row.user_id,
+                    "username": row.username,
+                    "displayName": row.display_name,
+                },
+            })
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "files": files,
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+    })))
+}
+
+// GET /api/rooms/:id/stats - Activity statistics, for room admins
+pub async fn room_stats(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(room_id): Path<Uuid>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<RoomStats>> {
+    require_room_admin(&state, &auth, room_id, "view room statistics").await?;
+
+    let stats = state
+        .room_stats
+        .stats(&state.db, room_id, query.days)
+        .await?;
+    Ok(Json(stats))
+}
@@ -0,0 +1,255 @@
+use super::auth::{check_can_sign_in, sign_in};
+use crate::error::{AppError, Result};
+use crate::middleware::{AuthUser, ValidatedJson};
+use crate::models::{
+    AuthResponse, FinishKeyLoginRequest, FinishKeyRegistrationRequest, SecurityKey,
+    SecurityKeySettingsRequest, StartKeyLoginRequest, StartKeyRegistrationRequest, User,
+};
+use crate::services::AuthService;
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::PgPool;
+use std::sync::Arc;
+use uuid::Uuid;
+use webauthn_rs::prelude::Passkey;
+
+/// A user's registered keys as webauthn-rs credentials, by row id
+pub(super) async fn load_passkeys(db: &PgPool, user_id: Uuid) -> Result<Vec<(Uuid, Passkey)>> {
+    let rows = sqlx::query_as::<_, (Uuid, sqlx::types::Json<Passkey>)>(
+        "SELECT id, passkey FROM security_keys WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, passkey)| (id, passkey.0))
+        .collect())
+}
+
+// GET /api/auth/webauthn/keys - The user's security keys, and whether password logins need one
+pub async fn list_keys(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>> {
+    let keys = sqlx::query_as::<_, SecurityKey>(&format!(
+        "SELECT {} FROM security_keys WHERE user_id = $1 ORDER BY created_at",
+        SecurityKey::COLUMNS
+    ))
+    .bind(auth.user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(serde_json::json!({
+        "enabled": state.security_keys.is_enabled(),
+        "securityKeys": keys,
+        "requireForLogin": auth.user.security_key_required,
+    })))
+}
+
+// POST /api/auth/webauthn/register/start - Options for navigator.credentials.create(),
+// after checking the account password
+pub async fn start_registration(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<StartKeyRegistrationRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let valid = AuthService::new(state.config.clone())
+        .verify_password(&req.password, &auth.user.password_hash)?;
+    if !valid {
+        return Err(AppError::Authentication("Invalid password".to_string()));
+    }
+
+    let passkeys: Vec<Passkey> = load_passkeys(&state.db, auth.user_id)
+        .await?
+        .into_iter()
+        .map(|(_, p)| p)
+        .collect();
+    if passkeys.len() as i64 >= SecurityKey::MAX_PER_USER {
+        return Err(AppError::Validation(format!(
+            "You can have at most {} security keys; remove one first",
+            SecurityKey::MAX_PER_USER
+        )));
+    }
+
+    let (challenge_id, options) = state
+        .security_keys
+        .start_registration(&auth.user, &passkeys)?;
+    Ok(Json(serde_json::json!({
+        "challengeId": challenge_id,
+        "publicKey": options,
+    })))
+}
+
+// POST /api/auth/webauthn/register/finish - Store the key the browser created
+pub async fn finish_registration(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    ValidatedJson(req): ValidatedJson<FinishKeyRegistrationRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>)> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation("Key name is required".to_string()));
+    }
+    let passkey =
+        state
+            .security_keys
+            .finish_registration(auth.user_id, req.challenge_id, &req.credential)?;
+
+    let key = sqlx::query_as::<_, SecurityKey>(&format!(
+        "INSERT INTO security_keys (user_id, name, passkey) VALUES ($1, $2, $3) RETURNING {}",
+        SecurityKey::COLUMNS
+    ))
+    .bind(auth.user_id)
+    .bind(name)
+    .bind(sqlx::types::Json(&passkey))
+    .fetch_one(&state.db)
+    .await?;
+
+    tracing::info!(
+        "Security key '{}' registered by {}",
+        key.name,
+        auth.user.username
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "securityKey": key })),
+    ))
+}
+
+// DELETE /api/auth/webauthn/keys/{id} - Remove a security key; removing the last one
+// lets the password sign in alone again
+pub async fn delete_key(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Path(key_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>> {
+    let mut tx = state.db.begin().await?;
+    let result = sqlx::query("DELETE FROM security_keys WHERE id = $1 AND user_id = $2")
+        .bind(key_id)
+        .bind(auth.user_id)
+        .execute(&mut *tx)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Security key not found".to_string()));
+    }
+    sqlx::query(
+        "UPDATE users SET security_key_required = FALSE, updated_at = NOW()
+         WHERE id = $1 AND security_key_required
+           AND NOT EXISTS (SELECT 1 FROM security_keys WHERE user_id = $1)",
+    )
+    .bind(auth.user_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(
+        serde_json::json!({ "message": "Security key removed" }),
+    ))
+}
+
+// PUT /api/auth/webauthn/settings - Whether password logins also need a security key
+pub async fn update_settings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+    Json(req): Json<SecurityKeySettingsRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if req.require_for_login {
+        let held: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM security_keys WHERE user_id = $1")
+            .bind(auth.user_id)
+            .fetch_one(&state.db)
+            .await?;
+        if held == 0 {
+            return Err(AppError::Validation(
+                "Register a security key first".to_string(),
+            ));
+        }
+    }
+
+    sqlx::query("UPDATE users SET security_key_required = $1, updated_at = NOW() WHERE id = $2")
+        .bind(req.require_for_login)
+        .bind(auth.user_id)
+        .execute(&state.db)
+        .await?;
+
+    Ok(Json(serde_json::json!({
+        "requireForLogin": req.require_for_login,
+    })))
+}
+
+// POST /api/auth/webauthn/login/start - Passwordless login: options for
+// navigator.credentials.get() with the user's keys
+pub async fn start_login(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(req): ValidatedJson<StartKeyLoginRequest>,
+) -> Result<Json<serde_json::Value>> {
+    if !state.security_keys.is_enabled() {
+        return Err(AppError::Authorization(
+            "Security keys are not enabled on this server".to_string(),
+        ));
+    }
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
+        .bind(&req.username)
+        .fetch_optional(&state.db)
+        .await?
+        .filter(|u| !u.is_guest());
+    let passkeys = match &user {
+        Some(user) => load_passkeys(&state.db, user.id).await?,
+        None => Vec::new(),
+    };
+    let Some(user) = user.filter(|_| !passkeys.is_empty()) else {
+        return Err(AppError::Authentication(
+            "No security key is registered for this account".to_string(),
+        ));
+    };
+
+    let passkeys: Vec<Passkey> = passkeys.into_iter().map(|(_, p)| p).collect();
+    let (challenge_id, options) = state.security_keys.start_login(user.id, &passkeys)?;
+    Ok(Json(serde_json::json!({
+        "challengeId": challenge_id,
+        "publicKey": options,
+    })))
+}
+
+// POST /api/auth/webauthn/login/finish - Check the key's answer to a login started here
+// or by a password login that needs a key, and start the session
+pub async fn finish_login(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<FinishKeyLoginRequest>,
+) -> Result<Json<AuthResponse>> {
+    let (user_id, result) = state
+        .security_keys
+        .finish_login(req.challenge_id, &req.credential)?;
+
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::Authentication("Invalid credentials".to_string()))?;
+    check_can_sign_in(&user)?;
+
+    // Keep the key's signature counter, which is how cloned keys are noticed
+    let mut passkeys = load_passkeys(&state.db, user_id).await?;
+    let Some((key_id, passkey)) = passkeys
+        .iter_mut()
+        .find(|(_, p)| p.cred_id() == result.cred_id())
+    else {
+        return Err(AppError::Authentication(
+            "Security key check failed".to_string(),
+        ));
+    };
+    passkey.update_credential(&result);
+    sqlx::query("UPDATE security_keys SET passkey = $1, last_used_at = NOW() WHERE id = $2")
+        .bind(sqlx::types::Json(&*passkey))
+        .bind(*key_id)
+        .execute(&state.db)
+        .await?;
+
+    Ok(Json(sign_in(&state, user, "Login successful").await?))
+}
@@ -0,0 +1,102 @@
+use crate::services::SIGNATURE_HEADER;
+use crate::state::AppState;
+use axum::{
+    extract::State,
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, SecondsFormat};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Largest padding a cover traffic reply will carry
+const MAX_COVER_PADDING: usize = 16 * 1024;
+
+/// Anything besides `responseSize` (the client's `padding`) is ignored
+#[derive(Debug, Deserialize)]
+pub struct CoverRequest {
+    /// Filler bytes wanted in the reply
+    #[serde(rename = "responseSize", default)]
+    response_size: usize,
+}
+
+/// Commit, build time and profile of this binary (see build.rs), for clients
+/// to show and compare with their own build
+fn build_info() -> serde_json::Value {
+    let timestamp = option_env!("BUILD_EPOCH")
+        .and_then(|epoch| epoch.parse().ok())
+        .and_then(|epoch| DateTime::from_timestamp(epoch, 0))
+        .map(|ts| ts.to_rfc3339_opts(SecondsFormat::Secs, true));
+    serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "commit": option_env!("BUILD_GIT_COMMIT"),
+        "timestamp": timestamp,
+        "profile": env!("BUILD_PROFILE"),
+    })
+}
+
+/// `value` as a JSON response signed with the server identity, so a client
+/// that pinned the key can tell it came from this server. The body is
+/// serialized here, once, because the signature covers its exact bytes.
+fn signed_json(state: &AppState, value: &serde_json::Value) -> Response {
+    let body = serde_json::to_vec(value).unwrap_or_default();
+    let signature = state
+        .identity
+        .sign_bytes(&body)
+        .and_then(|s| HeaderValue::from_str(&s).ok());
+    let mut response = ([(header::CONTENT_TYPE, "application/json")], body).into_response();
+    if let Some(signature) = signature {
+        response.headers_mut().insert(SIGNATURE_HEADER, signature);
+    }
+    response
+}
+
+// GET /api/server-info - Public description of this server and its optional
+// features, signed with the server identity key it publishes
+pub async fn get_info(State(state): State<Arc<AppState>>) -> Response {
+    let room_limits = state.settings.room_limits();
+    let info = serde_json::json!({
+        "name": "TOR Chat",
+        "version": env!("CARGO_PKG_VERSION"),
+        "build": build_info(),
+        // Unix ms, so clients with a skewed clock can correct for it
+        "serverTime": chrono::Utc::now().timestamp_millis(),
+        "features": {
+            "email": state.config.email_enabled,
+            "translation": state.translator.is_enabled(),
+            "registrationQueue": state.registration_queue.is_enabled(),
+            "paranoidMode": state.config.paranoid_mode,
+            // `POST /api/auth/guest` works
+            "guests": state.settings.guest_access().enabled,
+            // Security keys can be registered and used to log in
+            "securityKeys": state.security_keys.is_enabled(),
+        },
+        "messageTypes": state.config.message_types,
+        "limits": {
+            "maxFileSize": state.config.max_file_size,
+            // Largest file `send_message` takes inline as `attachment`; 0 = off
+            "socketUploadMaxSize": state.config.socket_upload_max_size.min(state.config.max_file_size),
+            // Room sizes new and edited rooms may have
+            "defaultRoomMembers": room_limits.default_max_members,
+            "maxRoomMembers": room_limits.max_members_cap,
+        },
+        "maintenance": state.maintenance.status(),
+        "addresses": {
+            "clearnet": state.config.public_url,
+            "onion": state.onion_url().await,
+        },
+        // base64 Ed25519 key that signs this response (`X-TorChat-Signature`)
+        // and federation documents; null for a moment at first start
+        "identityKey": state.identity.public_key(),
+    });
+    signed_json(&state, &info)
+}
+
+// POST /api/cover - Deliberately does nothing. Clients with cover traffic
+// enabled call it at random intervals with random padding, so their real
+// requests don't stand out in timing or size; the reply is padded as asked.
+pub async fn cover(Json(req): Json<CoverRequest>) -> Json<serde_json::Value> {
+    let padding = "0".repeat(req.response_size.min(MAX_COVER_PADDING));
+    Json(serde_json::json!({ "padding": padding }))
+}
@@ -3,6 +3,7 @@ use crate::state::AppState;
 use axum::{extract::State, Json};
 use std::sync::Arc;
 
+// GET /api/tor-status - Tor proxy connectivity and the latest onion self-check
 pub async fn get_status(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
     let tor_service = TorService::new(state.config.clone());
     let connected = tor_service.check_connection().await.unwrap_or(false);
@@ -15,5 +16,7 @@ pub async fn get_status(State(state): State<Arc<AppState>>) -> Json<serde_json::
         "socks_host": info.socks_host,
         "socks_port": info.socks_port,
         "hidden_service": info.hidden_service,
+        // Latest self-check of our own onion address (null until the first run)
+        "probe": state.tor_probe.last(),
     }))
 }
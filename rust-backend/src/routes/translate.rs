@@ -0,0 +1,53 @@
+use crate::error::{AppError, Result};
+use crate::services::Translation;
+use crate::state::AppState;
+use axum::{extract::State, Json};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Longest text accepted in one request; plenty for a chat message
+const MAX_TEXT_CHARS: usize = 5000;
+
+#[derive(Deserialize)]
+pub struct TranslateBody {
+    text: String,
+    source: Option<String>,
+    target: String,
+}
+
+/// Language codes are short ASCII tags like "en", "pt-BR" or "zh-Hans"
+fn is_language_code(code: &str) -> bool {
+    !code.is_empty()
+        && code.len() <= 10
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// POST /api/translate - Translate text with the server's self-hosted LibreTranslate
+pub async fn translate(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<TranslateBody>,
+) -> Result<Json<Translation>> {
+    let text = body.text.trim();
+    if text.is_empty() {
+        return Err(AppError::Validation("Text is required".to_string()));
+    }
+    if text.chars().count() > MAX_TEXT_CHARS {
+        return Err(AppError::Validation(format!(
+            "Text must be at most {} characters",
+            MAX_TEXT_CHARS
+        )));
+    }
+
+    let source = body.source.as_deref().unwrap_or("auto");
+    if !is_language_code(source) || !is_language_code(&body.target) {
+        return Err(AppError::Validation("Invalid language code".to_string()));
+    }
+
+    let translation = state
+        .translator
+        .translate(text, source, &body.target)
+        .await?;
+    Ok(Json(translation))
+}
@@ -0,0 +1,279 @@
+use crate::config::Config;
+use crate::error::Result;
+use crate::services::TorProbe;
+use chrono::Utc;
+use reqwest::Url;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A webhook behind Tor can take a while to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Window the registration and spam surge thresholds are counted over
+const SURGE_WINDOW_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    TorDown,
+    TorRestored,
+    DiskLow,
+    ErrorSpike,
+    RegistrationSurge,
+    SpamSurge,
+}
+
+/// Body POSTed to ALERT_WEBHOOK_URL. `text` makes it readable as-is by chat
+/// webhooks (Mattermost, Slack-compatible bridges); the rest is for scripts.
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    kind: AlertKind,
+    text: String,
+    message: &'a str,
+    server: Option<&'a str>,
+    at: chrono::DateTime<Utc>,
+}
+
+/// Notifies the operator through a webhook when something needs a human:
+/// the onion service unreachable, the upload disk filling up, server error
+/// spikes, or bursts of signups or spam. Each kind of alert is sent at most
+/// once per ALERT_COOLDOWN so a lasting problem doesn't flood the channel.
+pub struct AlertService {
+    webhook: Option<Url>,
+    client: Option<reqwest::Client>,
+    server: Option<String>,
+    interval: Duration,
+    cooldown: Duration,
+    upload_dir: PathBuf,
+    disk_free_percent: f64,
+    tor_down_after: chrono::Duration,
+    registration_surge: i64,
+    spam_surge: i64,
+    last_sent: Mutex<HashMap<AlertKind, Instant>>,
+    tor_down: AtomicBool,
+}
+
+impl AlertService {
+    pub fn new(config: &Config) -> Self {
+        let webhook = config
+            .alert_webhook_url
+            .as_deref()
+            .and_then(|raw| match Url::parse(raw) {
+                Ok(url) => Some(url),
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid ALERT_WEBHOOK_URL: {}", e);
+                    None
+                }
+            });
+
+        let client = webhook.as_ref().and_then(|url| {
+            let is_onion = url.host_str().is_some_and(|h| h.ends_with(".onion"));
+            let mut builder = reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::none());
+            if is_onion {
+                if !config.tor_enabled {
+                    tracing::warn!("ALERT_WEBHOOK_URL is an onion address but TOR is disabled");
+                    return None;
+                }
+                // socks5h: the onion address is resolved by Tor
+                let proxy = reqwest::Proxy::all(format!(
+                    "socks5h://{}:{}",
+                    config.tor_socks_host, config.tor_socks_port
+                ))
+                .expect("Invalid TOR SOCKS address");
+                builder = builder.proxy(proxy);
+            }
+            Some(
+                builder
+                    .build()
+                    .expect("Failed to build alert webhook client"),
+            )
+        });
+
+        Self {
+            webhook,
+            client,
+            server: config
+                .onion_url
+                .clone()
+                .or_else(|| config.public_url.clone()),
+            interval: Duration::from_secs(config.alert_check_interval.max(1)),
+            cooldown: Duration::from_secs(config.alert_cooldown),
+            upload_dir: config.upload_dir.clone(),
+            disk_free_percent: config.alert_disk_free_percent,
+            tor_down_after: chrono::Duration::seconds(config.alert_tor_down_after as i64),
+            registration_surge: config.alert_registration_surge,
+            spam_surge: config.alert_spam_surge,
+            last_sent: Mutex::new(HashMap::new()),
+            tor_down: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// Send an alert unless one of the same kind went out within the cooldown
+    pub async fn notify(&self, kind: AlertKind, message: &str) {
+        let (Some(client), Some(url)) = (&self.client, &self.webhook) else {
+            return;
+        };
+        {
+            let mut last_sent = self.last_sent.lock().unwrap();
+            if last_sent
+                .get(&kind)
+                .is_some_and(|sent| sent.elapsed() < self.cooldown)
+            {
+                return;
+            }
+            last_sent.insert(kind, Instant::now());
+        }
+
+        let payload = WebhookPayload {
+            kind,
+            text: format!("[TOR Chat] {}", message),
+            message,
+            server: self.server.as_deref(),
+            at: Utc::now(),
+        };
+        match client.post(url.clone()).json(&payload).send().await {
+            Ok(response) if response.status().is_success() => {
+                tracing::info!("Alert sent: {}", message);
+            }
+            Ok(response) => {
+                tracing::warn!("Alert webhook returned {}", response.status());
+            }
+            Err(e) => tracing::warn!("Alert webhook unreachable: {}", e),
+        }
+    }
+
+    /// Check the conditions once per ALERT_CHECK_INTERVAL forever. Error
+    /// spikes are reported by the error spike monitor as they happen.
+    pub async fn run(&self, db: PgPool, tor_probe: &TorProbe) {
+        loop {
+            tokio::time::sleep(self.interval).await;
+
+            if tor_probe.is_enabled() {
+                self.check_tor(tor_probe).await;
+            }
+            self.check_disk().await;
+            if let Err(e) = self.check_surges(&db).await {
+                tracing::error!("Alert surge check failed: {}", e);
+            }
+        }
+    }
+
+    /// Down once the self-check has failed for ALERT_TOR_DOWN_AFTER in a
+    /// row; restored at the next success
+    async fn check_tor(&self, tor_probe: &TorProbe) {
+        let Some(last) = tor_probe.last() else {
+            return;
+        };
+        if last.ok {
+            if self.tor_down.swap(false, Ordering::Relaxed) {
+                self.notify(
+                    AlertKind::TorRestored,
+                    "Onion service reachable again through Tor",
+                )
+                .await;
+            }
+            return;
+        }
+
+        let failing_since = tor_probe.last_success().unwrap_or(tor_probe.started_at());
+        if Utc::now() - failing_since >= self.tor_down_after
+            && !self.tor_down.swap(true, Ordering::Relaxed)
+        {
+            let message = format!(
+                "Onion service unreachable through Tor since {}: {}",
+                failing_since.format("%Y-%m-%d %H:%M UTC"),
+                last.error.as_deref().unwrap_or("unknown error")
+            );
+            self.notify(AlertKind::TorDown, &message).await;
+        }
+    }
+
+    async fn check_disk(&self) {
+        if self.disk_free_percent <= 0.0 {
+            return;
+        }
+        let Some((free, total)) = disk_space(&self.upload_dir) else {
+            return;
+        };
+        let free_percent = free as f64 * 100.0 / total.max(1) as f64;
+        if free_percent < self.disk_free_percent {
+            let message = format!(
+                "Upload disk nearly full: {:.1}% free ({} MiB of {} MiB)",
+                free_percent,
+                free / (1024 * 1024),
+                total / (1024 * 1024)
+            );
+            self.notify(AlertKind::DiskLow, &message).await;
+        }
+    }
+
+    async fn check_surges(&self, db: &PgPool) -> Result<()> {
+        if self.registration_surge > 0 {
+            let signups: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM users WHERE created_at > NOW() - make_interval(mins => $1)",
+            )
+            .bind(SURGE_WINDOW_MINUTES as i32)
+            .fetch_one(db)
+            .await?;
+            if signups >= self.registration_surge {
+                let message = format!(
+                    "Registration surge: {} new accounts in the last hour",
+                    signups
+                );
+                self.notify(AlertKind::RegistrationSurge, &message).await;
+            }
+        }
+
+        if self.spam_surge > 0 {
+            let flagged: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM messages
+                 WHERE spam_action IS NOT NULL
+                   AND created_at > NOW() - make_interval(mins => $1)",
+            )
+            .bind(SURGE_WINDOW_MINUTES as i32)
+            .fetch_one(db)
+            .await?;
+            if flagged >= self.spam_surge {
+                let message = format!(
+                    "Spam surge: {} messages flagged or hidden in the last hour",
+                    flagged
+                );
+                self.notify(AlertKind::SpamSurge, &message).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Free (for unprivileged users) and total bytes of the filesystem holding `path`
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // the statvfs field types differ between platforms
+fn disk_space(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid out-pointer
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block = stat.f_frsize as u64;
+    Some((stat.f_bavail as u64 * block, stat.f_blocks as u64 * block))
+}
+
+#[cfg(not(unix))]
+fn disk_space(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
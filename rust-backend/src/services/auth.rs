@@ -1,8 +1,11 @@
 use crate::config::Config;
 use crate::error::{AppError, Result};
-use chrono::{Duration, Utc};
+use crate::models::ApiToken;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,9 +26,13 @@ impl AuthService {
 
     /// Generate JWT token
     pub fn generate_token(&self, user_id: Uuid) -> Result<String> {
-        let now = Utc::now();
-        let expires_at = now + Duration::seconds(self.config.jwt_expires_in);
+        let expires_at = Utc::now() + Duration::seconds(self.config.jwt_expires_in);
+        self.generate_token_until(user_id, expires_at)
+    }
 
+    /// Generate a JWT token that expires at `expires_at` (guest sessions)
+    pub fn generate_token_until(&self, user_id: Uuid, expires_at: DateTime<Utc>) -> Result<String> {
+        let now = Utc::now();
         let claims = Claims {
             sub: user_id.to_string(),
             exp: expires_at.timestamp(),
@@ -53,6 +60,101 @@ impl AuthService {
             .map_err(|e| AppError::Authentication(format!("Invalid user ID in token: {}", e)))
     }
 
+    /// Issue a refresh token for `user_id`, valid for REFRESH_TOKEN_EXPIRES_IN.
+    /// The user's expired ones are dropped on the way.
+    pub async fn issue_refresh_token(&self, db: &PgPool, user_id: Uuid) -> Result<String> {
+        let mut secret = Uuid::new_v4().as_bytes().to_vec();
+        secret.extend_from_slice(Uuid::new_v4().as_bytes());
+        let token = URL_SAFE_NO_PAD.encode(secret);
+
+        sqlx::query("DELETE FROM refresh_tokens WHERE user_id = $1 AND expires_at < NOW()")
+            .bind(user_id)
+            .execute(db)
+            .await?;
+        sqlx::query(
+            "INSERT INTO refresh_tokens (token_hash, user_id, expires_at)
+             VALUES (sha256(convert_to($1, 'UTF8')), $2, NOW() + make_interval(secs => $3))",
+        )
+        .bind(&token)
+        .bind(user_id)
+        .bind(self.config.refresh_token_expires_in as f64)
+        .execute(db)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Spend a refresh token. It's gone afterwards either way; the owner is
+    /// returned if it was still valid.
+    pub async fn redeem_refresh_token(&self, db: &PgPool, token: &str) -> Result<Option<Uuid>> {
+        let user_id = sqlx::query_scalar::<_, Option<Uuid>>(
+            "DELETE FROM refresh_tokens WHERE token_hash = sha256(convert_to($1, 'UTF8'))
+             RETURNING CASE WHEN expires_at > NOW() THEN user_id END",
+        )
+        .bind(token)
+        .fetch_optional(db)
+        .await?;
+        Ok(user_id.flatten())
+    }
+
+    /// Revoke one refresh token, e.g. at logout
+    pub async fn revoke_refresh_token(&self, db: &PgPool, token: &str) -> Result<()> {
+        sqlx::query("DELETE FROM refresh_tokens WHERE token_hash = sha256(convert_to($1, 'UTF8'))")
+            .bind(token)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Store a new API token for `user_id`; the secret is returned once and
+    /// only its hash is kept
+    pub async fn issue_api_token(
+        &self,
+        db: &PgPool,
+        user_id: Uuid,
+        name: &str,
+        scopes: &[String],
+        room_ids: Option<&[Uuid]>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(String, ApiToken)> {
+        let mut secret = Uuid::new_v4().as_bytes().to_vec();
+        secret.extend_from_slice(Uuid::new_v4().as_bytes());
+        let token = format!("{}{}", ApiToken::PREFIX, URL_SAFE_NO_PAD.encode(secret));
+
+        let api_token = sqlx::query_as::<_, ApiToken>(&format!(
+            "INSERT INTO api_tokens (user_id, name, token_hash, scopes, room_ids, expires_at)
+             VALUES ($1, $2, sha256(convert_to($3, 'UTF8')), $4, $5, $6)
+             RETURNING {}",
+            ApiToken::COLUMNS
+        ))
+        .bind(user_id)
+        .bind(name)
+        .bind(&token)
+        .bind(scopes)
+        .bind(room_ids)
+        .bind(expires_at)
+        .fetch_one(db)
+        .await?;
+
+        Ok((token, api_token))
+    }
+
+    /// The API token `token` stands for, if it exists and hasn't expired.
+    /// Its last use is recorded on the way.
+    pub async fn find_api_token(&self, db: &PgPool, token: &str) -> Result<Option<ApiToken>> {
+        let api_token = sqlx::query_as::<_, ApiToken>(&format!(
+            "UPDATE api_tokens SET last_used_at = NOW()
+             WHERE token_hash = sha256(convert_to($1, 'UTF8'))
+               AND (expires_at IS NULL OR expires_at > NOW())
+             RETURNING {}",
+            ApiToken::COLUMNS
+        ))
+        .bind(token)
+        .fetch_optional(db)
+        .await?;
+        Ok(api_token)
+    }
+
     /// Hash password
     pub fn hash_password(&self, password: &str) -> Result<String> {
         bcrypt::hash(password, self.config.bcrypt_cost)
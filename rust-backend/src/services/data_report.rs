@@ -0,0 +1,161 @@
+use crate::error::{AppError, Result};
+use crate::models::User;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Report format; bump when a field changes meaning
+const REPORT_VERSION: u32 = 1;
+
+/// What the server stores about one account, for operators answering data
+/// requests (`tor-chat-backend admin data-report <user>`). `who` is a
+/// username or user id. The report has the account's profile fields and
+/// settings, and counts of everything else; it never includes message,
+/// attachment or revision content, and secrets only appear as "stored".
+pub async fn data_report(db: &PgPool, who: &str) -> Result<serde_json::Value> {
+    let user = match Uuid::parse_str(who) {
+        Ok(id) => sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1").bind(id),
+        Err(_) => sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1").bind(who),
+    }
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("No user '{}'", who)))?;
+
+    let (digest_enabled, digest_hour, digest_sent_at, accept_rooms_from, updated_at) =
+        sqlx::query_as::<_, (bool, i16, Option<DateTime<Utc>>, String, DateTime<Utc>)>(
+            "SELECT digest_enabled, digest_hour, digest_sent_at, accept_rooms_from, updated_at
+             FROM users WHERE id = $1",
+        )
+        .bind(user.id)
+        .fetch_one(db)
+        .await?;
+
+    // Messages, split by whether the server can read them: ones sent in an
+    // end-to-end encrypted room carry a key epoch, and `encrypted` ones were
+    // encrypted by the client; both are ciphertext
+    let (messages, e2ee_messages, files, first_message, last_message) =
+        sqlx::query_as::<_, (i64, i64, i64, Option<DateTime<Utc>>, Option<DateTime<Utc>>)>(
+            "SELECT COUNT(*),
+                    COUNT(*) FILTER (WHERE key_epoch IS NOT NULL OR message_type = 'encrypted'),
+                    COUNT(*) FILTER (WHERE message_type IN ('file', 'image', 'video', 'audio')),
+                    MIN(created_at),
+                    MAX(created_at)
+             FROM messages WHERE user_id = $1",
+        )
+        .bind(user.id)
+        .fetch_one(db)
+        .await?;
+
+    let rooms = sqlx::query_as::<_, (Uuid, String, bool, String, Option<DateTime<Utc>>, i64)>(
+        "SELECT r.id, r.name, r.encryption_mode = 'e2ee', rm.role, rm.joined_at,
+                (SELECT COUNT(*) FROM messages m WHERE m.room_id = r.id AND m.user_id = $1)
+         FROM room_members rm JOIN rooms r ON r.id = rm.room_id
+         WHERE rm.user_id = $1
+         ORDER BY rm.joined_at",
+    )
+    .bind(user.id)
+    .fetch_all(db)
+    .await?;
+
+    let counts = sqlx::query_as::<_, (i64, i64, i64, i64, i64, i64, i64, i64, i64, i64)>(
+        "SELECT
+            (SELECT COUNT(*) FROM rooms WHERE creator_id = $1),
+            (SELECT COUNT(*) FROM message_reactions WHERE user_id = $1),
+            (SELECT COUNT(*) FROM message_revisions v JOIN messages m ON m.id = v.message_id
+              WHERE m.user_id = $1),
+            (SELECT COUNT(*) FROM room_invites WHERE created_by = $1),
+            (SELECT COUNT(*) FROM room_requests WHERE user_id = $1),
+            (SELECT COUNT(*) FROM message_retention WHERE user_id = $1),
+            (SELECT COUNT(*) FROM digests WHERE user_id = $1),
+            (SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1 AND expires_at > NOW()),
+            (SELECT COUNT(*) FROM api_tokens WHERE user_id = $1),
+            (SELECT COUNT(*) FROM security_keys WHERE user_id = $1)",
+    )
+    .bind(user.id)
+    .fetch_one(db)
+    .await?;
+    let (
+        rooms_created,
+        reactions,
+        revisions,
+        invites,
+        room_requests,
+        retention_rules,
+        digests,
+        sessions,
+        api_tokens,
+        security_keys,
+    ) = counts;
+
+    let stored = |present: bool| if present { "stored" } else { "none" };
+
+    Ok(json!({
+        "reportVersion": REPORT_VERSION,
+        "generatedAt": Utc::now(),
+        "account": {
+            "id": user.id,
+            "username": user.username,
+            "displayName": user.display_name,
+            "email": user.email,
+            "avatar": stored(user.avatar.is_some()),
+            "publicKey": stored(user.public_key.is_some()),
+            "password": "stored as a bcrypt hash",
+            "isAdmin": user.is_admin,
+            "isBanned": user.is_banned,
+            "isShadowBanned": user.is_shadow_banned,
+            "guestExpiresAt": user.guest_expires_at,
+            "queuedAt": user.queued_at,
+            "createdAt": user.created_at,
+            "updatedAt": updated_at,
+            // Both are cleared while paranoid mode is on
+            "isOnline": user.is_online,
+            "lastSeen": user.last_seen,
+        },
+        "settings": {
+            "digestEnabled": digest_enabled,
+            "digestHourUtc": digest_hour,
+            "digestSentAt": digest_sent_at,
+            "acceptRoomsFrom": accept_rooms_from,
+            "securityKeyRequired": user.security_key_required,
+            "retentionRules": retention_rules,
+        },
+        "messages": {
+            "total": messages,
+            "serverReadable": messages - e2ee_messages,
+            "endToEndEncrypted": e2ee_messages,
+            "files": files,
+            "firstAt": first_message,
+            "lastAt": last_message,
+            "earlierVersions": revisions,
+            "reactions": reactions,
+        },
+        "rooms": {
+            "created": rooms_created,
+            "invitesCreated": invites,
+            "pendingRequests": room_requests,
+            "memberships": rooms
+                .into_iter()
+                .map(|(id, name, e2ee, role, joined_at, messages)| json!({
+                    "id": id,
+                    "name": name,
+                    "endToEndEncrypted": e2ee,
+                    "role": role,
+                    "joinedAt": joined_at,
+                    "messages": messages,
+                }))
+                .collect::<Vec<_>>(),
+        },
+        "credentials": {
+            // Only hashes of session and API tokens are kept
+            "activeSessions": sessions,
+            "apiTokens": api_tokens,
+            "securityKeys": security_keys,
+        },
+        "digests": digests,
+        "notIncluded": [
+            "Message, attachment and edit-history content; the server can't read messages in end-to-end encrypted rooms at all",
+            "Password, token and key material",
+        ],
+    }))
+}
@@ -0,0 +1,218 @@
+use crate::config::Config;
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Rooms listed in one digest, busiest first
+const MAX_DIGEST_ROOMS: i64 = 10;
+/// Participants named per room
+const TOP_PARTICIPANTS: i64 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestSettings {
+    pub enabled: bool,
+    /// Hour of day (UTC) the digest is sent
+    pub hour_utc: i16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestRoom {
+    pub room_id: Uuid,
+    pub room_name: String,
+    pub message_count: i64,
+    pub top_participants: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Digest {
+    pub id: Uuid,
+    #[sqlx(json)]
+    pub rooms: Vec<DigestRoom>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow)]
+struct UnreadRoom {
+    room_id: Uuid,
+    room_name: String,
+    since: DateTime<Utc>,
+    message_count: i64,
+}
+
+/// Opt-in daily summary of activity in rooms a user belongs to but hasn't
+/// opened for a day: message counts and the most active participants.
+/// Digests are stored so offline users see them on their next visit.
+pub struct DigestService {
+    check_interval: Duration,
+}
+
+impl DigestService {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            check_interval: Duration::from_secs(config.digest_check_interval),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.check_interval.is_zero()
+    }
+
+    pub async fn settings(&self, db: &PgPool, user_id: Uuid) -> Result<DigestSettings> {
+        Ok(sqlx::query_as::<_, DigestSettings>(
+            "SELECT digest_enabled AS enabled, digest_hour AS hour_utc FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_one(db)
+        .await?)
+    }
+
+    pub async fn update_settings(
+        &self,
+        db: &PgPool,
+        user_id: Uuid,
+        settings: &DigestSettings,
+    ) -> Result<()> {
+        sqlx::query("UPDATE users SET digest_enabled = $1, digest_hour = $2 WHERE id = $3")
+            .bind(settings.enabled)
+            .bind(settings.hour_utc)
+            .bind(user_id)
+            .execute(db)
+            .await?;
+        Ok(())
+    }
+
+    /// Most recent digest for a user, if any
+    pub async fn latest(&self, db: &PgPool, user_id: Uuid) -> Result<Option<Digest>> {
+        Ok(sqlx::query_as::<_, Digest>(
+            "SELECT id, rooms, created_at FROM digests
+             WHERE user_id = $1
+             ORDER BY created_at DESC
+             LIMIT 1",
+        )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await?)
+    }
+
+    /// Users whose digest hour has come and who haven't had one today
+    async fn due_users(&self, db: &PgPool) -> Result<Vec<Uuid>> {
+        Ok(sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM users
+             WHERE digest_enabled AND NOT COALESCE(is_banned, false) AND queued_at IS NULL
+               AND digest_hour = EXTRACT(HOUR FROM NOW() AT TIME ZONE 'UTC')
+               AND (digest_sent_at IS NULL OR digest_sent_at < NOW() - INTERVAL '20 hours')",
+        )
+        .fetch_all(db)
+        .await?)
+    }
+
+    /// Build and store a user's digest. `None` when nothing happened in
+    /// their unopened rooms; the day counts as done either way.
+    async fn generate(&self, db: &PgPool, user_id: Uuid) -> Result<Option<Digest>> {
+        sqlx::query("UPDATE users SET digest_sent_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(db)
+            .await?;
+
+        // Rooms not read in the last day, counting other people's visible
+        // messages since the later of the last read and a day ago
+        let unread = sqlx::query_as::<_, UnreadRoom>(
+            "SELECT r.id AS room_id, r.name AS room_name,
+                    GREATEST(COALESCE(rm.last_read_at, rm.joined_at), NOW() - INTERVAL '24 hours') AS since,
+                    COUNT(m.id) AS message_count
+             FROM room_members rm
+             JOIN rooms r ON r.id = rm.room_id
+             JOIN messages m ON m.room_id = rm.room_id
+             JOIN users u ON u.id = m.user_id
+             WHERE rm.user_id = $1
+               AND (rm.last_read_at IS NULL OR rm.last_read_at < NOW() - INTERVAL '24 hours')
+               AND m.created_at > GREATEST(COALESCE(rm.last_read_at, rm.joined_at), NOW() - INTERVAL '24 hours')
+               AND m.user_id <> $1
+               AND m.spam_action IS DISTINCT FROM 'hidden'
+               AND NOT u.is_shadow_banned
+             GROUP BY r.id, r.name, rm.last_read_at, rm.joined_at
+             ORDER BY message_count DESC
+             LIMIT $2",
+        )
+        .bind(user_id)
+        .bind(MAX_DIGEST_ROOMS)
+        .fetch_all(db)
+        .await?;
+
+        if unread.is_empty() {
+            return Ok(None);
+        }
+
+        let mut rooms = Vec::with_capacity(unread.len());
+        for room in unread {
+            let top_participants = sqlx::query_scalar::<_, String>(
+                "SELECT COALESCE(u.display_name, u.username)
+                 FROM messages m
+                 JOIN users u ON u.id = m.user_id
+                 WHERE m.room_id = $1 AND m.created_at > $2 AND m.user_id <> $3
+                   AND m.spam_action IS DISTINCT FROM 'hidden'
+                   AND NOT u.is_shadow_banned
+                 GROUP BY u.id, u.display_name, u.username
+                 ORDER BY COUNT(*) DESC
+                 LIMIT $4",
+            )
+            .bind(room.room_id)
+            .bind(room.since)
+            .bind(user_id)
+            .bind(TOP_PARTICIPANTS)
+            .fetch_all(db)
+            .await?;
+
+            rooms.push(DigestRoom {
+                room_id: room.room_id,
+                room_name: room.room_name,
+                message_count: room.message_count,
+                top_participants,
+            });
+        }
+
+        let digest = sqlx::query_as::<_, Digest>(
+            "INSERT INTO digests (user_id, rooms) VALUES ($1, $2)
+             RETURNING id, rooms, created_at",
+        )
+        .bind(user_id)
+        .bind(sqlx::types::Json(&rooms))
+        .fetch_one(db)
+        .await?;
+
+        Ok(Some(digest))
+    }
+
+    /// Send due digests forever, calling `on_digest` with each user's new
+    /// digest so it can be pushed to their open sessions
+    pub async fn run<F, Fut>(&self, db: PgPool, on_digest: F)
+    where
+        F: Fn(Uuid, Digest) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        loop {
+            tokio::time::sleep(self.check_interval).await;
+
+            let users = match self.due_users(&db).await {
+                Ok(users) => users,
+                Err(e) => {
+                    tracing::error!("Digest lookup failed: {}", e);
+                    continue;
+                }
+            };
+            for user_id in users {
+                match self.generate(&db, user_id).await {
+                    Ok(Some(digest)) => on_digest(user_id, digest).await,
+                    Ok(None) => {}
+                    Err(e) => tracing::error!("Digest for {} failed: {}", user_id, e),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::config::Config;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Length of the window server errors are counted over
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Responses with a 5xx status since startup
+static SERVER_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+pub fn record_server_error() {
+    SERVER_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn server_errors() -> u64 {
+    SERVER_ERRORS.load(Ordering::Relaxed)
+}
+
+/// A minute with at least ADMIN_ERROR_SPIKE_THRESHOLD server errors
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorSpike {
+    pub errors: u64,
+    pub window_seconds: u64,
+}
+
+/// Watches the server error counter and reports minutes where it jumps,
+/// so admins hear about outages from the dashboard rather than from users
+pub struct ErrorSpikeMonitor {
+    threshold: u64,
+}
+
+impl ErrorSpikeMonitor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            threshold: config.admin_error_spike_threshold,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.threshold > 0
+    }
+
+    /// Check the counter once per window forever, calling `on_spike` for
+    /// every window at or above the threshold
+    pub async fn run<F, Fut>(&self, on_spike: F)
+    where
+        F: Fn(ErrorSpike) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        let mut previous = server_errors();
+        loop {
+            tokio::time::sleep(WINDOW).await;
+            let current = server_errors();
+            let errors = current - previous;
+            previous = current;
+
+            if errors >= self.threshold {
+                tracing::warn!("Error spike: {} server errors in the last minute", errors);
+                on_spike(ErrorSpike {
+                    errors,
+                    window_seconds: WINDOW.as_secs(),
+                })
+                .await;
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::error::{AppError, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::Serialize;
+use sodiumoxide::crypto::sign;
+use sodiumoxide::randombytes;
+use sqlx::PgPool;
+use std::sync::RwLock;
+
+/// How long a signed user document may be relied on before fetching it again
+pub const DOCUMENT_TTL_HOURS: i64 = 24;
+
+/// Response header with the server's base64 signature over the exact body,
+/// on responses clients pin the server key with (`/api/server-info`)
+pub const SIGNATURE_HEADER: &str = "x-torchat-signature";
+
+/// A JSON document with this server's signature. Like the release manifest
+/// the clients check, the document travels base64-encoded so the signed
+/// bytes reach the verifier unchanged.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedDocument {
+    /// base64 of the document's JSON
+    pub document: String,
+    /// base64 Ed25519 signature over the decoded document bytes
+    pub signature: String,
+    /// base64 public key the signature verifies with
+    pub key: String,
+}
+
+/// This server's long-term Ed25519 identity, which signs the documents and
+/// responses other servers and clients rely on. Made on first start and
+/// kept in `server_settings`, so it's stable across restarts and addresses:
+/// a client that pinned it notices when an onion address or a clearnet
+/// mirror answers with a different key.
+#[derive(Default)]
+pub struct ServerIdentity {
+    keys: RwLock<Option<(sign::PublicKey, sign::SecretKey)>>,
+}
+
+impl ServerIdentity {
+    const SETTINGS_KEY: &'static str = "signing_key";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the signing key, creating it if this server has none yet. When
+    /// several instances start at once the first one to store its key wins.
+    pub async fn load(&self, db: &PgPool) -> Result<()> {
+        sodiumoxide::init().map_err(|_| AppError::Internal("sodiumoxide init failed".into()))?;
+
+        let seed = BASE64.encode(randombytes::randombytes(sign::SEEDBYTES));
+        sqlx::query(
+            "INSERT INTO server_settings (key, value) VALUES ($1, $2)
+             ON CONFLICT (key) DO NOTHING",
+        )
+        .bind(Self::SETTINGS_KEY)
+        .bind(serde_json::Value::String(seed))
+        .execute(db)
+        .await?;
+
+        let stored = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT value FROM server_settings WHERE key = $1",
+        )
+        .bind(Self::SETTINGS_KEY)
+        .fetch_one(db)
+        .await?;
+        let seed = stored
+            .as_str()
+            .and_then(|s| BASE64.decode(s).ok())
+            .and_then(|bytes| sign::Seed::from_slice(&bytes))
+            .ok_or_else(|| AppError::Internal("Stored signing key is malformed".into()))?;
+
+        *self.keys.write().unwrap() = Some(sign::keypair_from_seed(&seed));
+        Ok(())
+    }
+
+    /// base64 public key, once loaded
+    pub fn public_key(&self) -> Option<String> {
+        self.keys
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|(pk, _)| BASE64.encode(pk.as_ref()))
+    }
+
+    /// base64 signature over `bytes`, or None while the key isn't loaded yet
+    pub fn sign_bytes(&self, bytes: &[u8]) -> Option<String> {
+        let keys = self.keys.read().unwrap();
+        let (_, sk) = keys.as_ref()?;
+        Some(BASE64.encode(sign::sign_detached(bytes, sk).as_ref()))
+    }
+
+    /// Sign `document`, or None while the key isn't loaded yet
+    pub fn sign(&self, document: &serde_json::Value) -> Option<SignedDocument> {
+        let bytes = serde_json::to_vec(document).ok()?;
+        Some(SignedDocument {
+            signature: self.sign_bytes(&bytes)?,
+            key: self.public_key()?,
+            document: BASE64.encode(&bytes),
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use super::retention::delete_messages;
+use crate::error::Result;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How often expired guest accounts are looked for
+const PURGE_INTERVAL: Duration = Duration::from_secs(300);
+/// Guests purged per statement
+const BATCH_SIZE: i64 = 100;
+
+/// Guests whose session has ended, and what deleting them took with it
+#[derive(Debug, Default)]
+pub struct PurgedGuests {
+    pub user_ids: Vec<Uuid>,
+    /// `(message_id, room_id)` of every message they had sent
+    pub messages: Vec<(Uuid, Uuid)>,
+}
+
+/// Delete one batch of guest accounts whose session has ended, with their
+/// messages; memberships and the rest go with the account
+pub async fn purge_expired_guests(db: &PgPool) -> Result<PurgedGuests> {
+    let user_ids = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM users
+         WHERE guest_expires_at <= NOW()
+         ORDER BY guest_expires_at
+         LIMIT $1",
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(db)
+    .await?;
+    if user_ids.is_empty() {
+        return Ok(PurgedGuests::default());
+    }
+
+    let messages = sqlx::query_as::<_, (Uuid, Uuid)>(
+        "SELECT id, room_id FROM messages WHERE user_id = ANY($1)",
+    )
+    .bind(&user_ids)
+    .fetch_all(db)
+    .await?;
+    if !messages.is_empty() {
+        let ids: Vec<Uuid> = messages.iter().map(|(id, _)| *id).collect();
+        delete_messages(db, &ids).await?;
+    }
+
+    sqlx::query("UPDATE messages SET pinned_by = NULL WHERE pinned_by = ANY($1)")
+        .bind(&user_ids)
+        .execute(db)
+        .await?;
+    sqlx::query("DELETE FROM users WHERE id = ANY($1) AND guest_expires_at <= NOW()")
+        .bind(&user_ids)
+        .execute(db)
+        .await?;
+
+    Ok(PurgedGuests { user_ids, messages })
+}
+
+/// Purge expired guests forever, calling `on_purged` for every batch
+pub async fn run_guest_purge<F, Fut>(db: PgPool, on_purged: F)
+where
+    F: Fn(PurgedGuests) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    loop {
+        tokio::time::sleep(PURGE_INTERVAL).await;
+
+        loop {
+            match purge_expired_guests(&db).await {
+                Ok(purged) if purged.user_ids.is_empty() => break,
+                Ok(purged) => {
+                    tracing::info!("Purged {} expired guest(s)", purged.user_ids.len());
+                    let full = purged.user_ids.len() as i64 == BATCH_SIZE;
+                    on_purged(purged).await;
+                    if !full {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Guest purge failed: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
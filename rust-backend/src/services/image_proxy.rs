@@ -0,0 +1,196 @@
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use bytes::Bytes;
+use reqwest::Url;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// How long a fetched image is re-served before it is fetched again
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+/// Upper bound on cached image bytes; oldest entries are evicted first
+const CACHE_MAX_BYTES: usize = 64 * 1024 * 1024;
+/// Tor circuits are slow, but a stalled fetch shouldn't hold a request forever
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct ProxiedImage {
+    pub content_type: String,
+    pub bytes: Bytes,
+}
+
+/// Fetches remote images (link preview thumbnails, ...) on behalf of clients.
+/// Requests only ever leave through the Tor SOCKS proxy; with Tor disabled
+/// the proxy refuses instead of falling back to a direct connection.
+pub struct ImageProxy {
+    client: Option<reqwest::Client>,
+    max_bytes: usize,
+    cache: RwLock<HashMap<String, (ProxiedImage, Instant)>>,
+}
+
+impl ImageProxy {
+    pub fn new(config: &Config) -> Self {
+        let client = config.tor_enabled.then(|| {
+            // socks5h: DNS is resolved by Tor too, not by the host resolver
+            let proxy = reqwest::Proxy::all(format!(
+                "socks5h://{}:{}",
+                config.tor_socks_host, config.tor_socks_port
+            ))
+            .expect("Invalid TOR SOCKS address");
+            reqwest::Client::builder()
+                .proxy(proxy)
+                .timeout(FETCH_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::limited(3))
+                .user_agent("Mozilla/5.0")
+                .build()
+                .expect("Failed to build image proxy client")
+        });
+
+        Self {
+            client,
+            max_bytes: config.image_proxy_max_bytes,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn fetch(&self, raw_url: &str) -> Result<ProxiedImage> {
+        let url = Self::validate_url(raw_url)?;
+        let key = url.to_string();
+
+        if let Some((image, at)) = self.cache.read().await.get(&key) {
+            if at.elapsed() < CACHE_TTL {
+                return Ok(image.clone());
+            }
+        }
+
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| AppError::Tor("Image proxy requires TOR".to_string()))?;
+
+        let mut response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| AppError::Tor(format!("Image fetch failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::NotFound(format!(
+                "Remote image returned {}",
+                response.status()
+            )));
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| {
+                v.split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_lowercase()
+            })
+            .unwrap_or_default();
+        // SVG can carry script, so only raster formats are re-served
+        if !content_type.starts_with("image/") || content_type.contains("svg") {
+            return Err(AppError::BadRequest(
+                "URL does not point to a supported image".to_string(),
+            ));
+        }
+
+        if response
+            .content_length()
+            .is_some_and(|len| len as usize > self.max_bytes)
+        {
+            return Err(AppError::BadRequest("Image too large".to_string()));
+        }
+
+        // Content-Length can lie or be missing; enforce the limit while reading
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| AppError::Tor(format!("Image fetch failed: {}", e)))?
+        {
+            if body.len() + chunk.len() > self.max_bytes {
+                return Err(AppError::BadRequest("Image too large".to_string()));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let image = ProxiedImage {
+            content_type,
+            bytes: Bytes::from(body),
+        };
+        self.store(key, image.clone()).await;
+        Ok(image)
+    }
+
+    async fn store(&self, key: String, image: ProxiedImage) {
+        let mut cache = self.cache.write().await;
+        cache.retain(|_, (_, at)| at.elapsed() < CACHE_TTL);
+
+        let mut total: usize = cache.values().map(|(img, _)| img.bytes.len()).sum();
+        while total + image.bytes.len() > CACHE_MAX_BYTES {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, (_, at))| *at)
+                .map(|(k, _)| k.clone());
+            match oldest.and_then(|k| cache.remove(&k)) {
+                Some((evicted, _)) => total -= evicted.bytes.len(),
+                None => break,
+            }
+        }
+
+        cache.insert(key, (image, Instant::now()));
+    }
+
+    /// Only plain http(s) URLs to public hosts. Tor exits refuse private
+    /// addresses anyway; this keeps the endpoint from being pointed at the
+    /// proxy's own network if that ever changes.
+    fn validate_url(raw: &str) -> Result<Url> {
+        let url = Url::parse(raw).map_err(|_| AppError::BadRequest("Invalid URL".to_string()))?;
+
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::BadRequest(
+                "Only http and https URLs can be proxied".to_string(),
+            ));
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(AppError::BadRequest(
+                "URLs with credentials can't be proxied".to_string(),
+            ));
+        }
+
+        let host = url
+            .host_str()
+            .ok_or_else(|| AppError::BadRequest("URL has no host".to_string()))?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let is_local_name = host.eq_ignore_ascii_case("localhost") || host.ends_with(".localhost");
+        let is_private_ip = host.parse::<IpAddr>().is_ok_and(|ip| match ip {
+            IpAddr::V4(v4) => {
+                v4.is_private()
+                    || v4.is_loopback()
+                    || v4.is_link_local()
+                    || v4.is_unspecified()
+                    || v4.is_broadcast()
+            }
+            IpAddr::V6(v6) => {
+                v6.is_loopback()
+                    || v6.is_unspecified()
+                    || (v6.segments()[0] & 0xfe00) == 0xfc00
+                    || (v6.segments()[0] & 0xffc0) == 0xfe80
+            }
+        });
+        if is_local_name || is_private_ip {
+            return Err(AppError::BadRequest(
+                "Local addresses can't be proxied".to_string(),
+            ));
+        }
+
+        Ok(url)
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::RwLock;
+
+/// Why the server is read-only
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaintenanceReason {
+    /// Switched on by an admin
+    Admin,
+    /// Another server on the database is migrating the schema
+    Migration,
+}
+
+/// Broadcast to clients as the `maintenance` event and returned by the admin API
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    pub reason: Option<MaintenanceReason>,
+    pub message: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Admin-set message and start time
+    manual: Option<(Option<String>, DateTime<Utc>)>,
+    migrating_since: Option<DateTime<Utc>>,
+}
+
+/// Read-only maintenance mode. While active, writes are rejected with a
+/// `maintenance` error; reads and logins keep working. Checked on every
+/// request, so it's a plain lock rather than a database flag.
+#[derive(Default)]
+pub struct Maintenance {
+    inner: RwLock<Inner>,
+}
+
+impl Maintenance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        let inner = self.inner.read().unwrap();
+        inner.manual.is_some() || inner.migrating_since.is_some()
+    }
+
+    pub fn is_migrating(&self) -> bool {
+        self.inner.read().unwrap().migrating_since.is_some()
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        let inner = self.inner.read().unwrap();
+        if let Some((message, since)) = &inner.manual {
+            return MaintenanceStatus {
+                enabled: true,
+                reason: Some(MaintenanceReason::Admin),
+                message: message.clone(),
+                since: Some(*since),
+            };
+        }
+        MaintenanceStatus {
+            enabled: inner.migrating_since.is_some(),
+            reason: inner.migrating_since.map(|_| MaintenanceReason::Migration),
+            message: inner
+                .migrating_since
+                .map(|_| "Database upgrade in progress".to_string()),
+            since: inner.migrating_since,
+        }
+    }
+
+    /// Admin toggle on, with an optional message for the client banner
+    pub fn enable(&self, message: Option<String>) {
+        self.inner.write().unwrap().manual = Some((message, Utc::now()));
+    }
+
+    /// Admin toggle off; a migration still keeps the server read-only
+    pub fn disable(&self) {
+        self.inner.write().unwrap().manual = None;
+    }
+
+    /// When another server started migrating the schema, or None once it's
+    /// done (see [`begin_migration`](super::begin_migration))
+    pub fn set_migrating(&self, since: Option<DateTime<Utc>>) {
+        self.inner.write().unwrap().migrating_since = since;
+    }
+}
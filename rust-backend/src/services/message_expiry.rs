@@ -0,0 +1,77 @@
+use super::retention::delete_messages;
+use crate::config::Config;
+use crate::error::Result;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Messages deleted per statement
+const BATCH_SIZE: i64 = 500;
+
+/// Per-message expiry ("burn after reading"): deletes messages whose
+/// `expires_at` has passed. The countdown is started on sending or by the
+/// first read receipt; this job only does the deleting.
+pub struct MessageExpiryService {
+    interval: Duration,
+}
+
+impl MessageExpiryService {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            interval: Duration::from_secs(config.message_expiry_check_interval),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.interval.is_zero()
+    }
+
+    /// Delete one batch of expired messages. Returns `(message_id, room_id)`
+    /// of each deleted message.
+    pub async fn purge_batch(&self, db: &PgPool) -> Result<Vec<(Uuid, Uuid)>> {
+        let expired = sqlx::query_as::<_, (Uuid, Uuid)>(
+            "SELECT id, room_id FROM messages
+             WHERE expires_at <= NOW()
+             ORDER BY expires_at
+             LIMIT $1",
+        )
+        .bind(BATCH_SIZE)
+        .fetch_all(db)
+        .await?;
+
+        if !expired.is_empty() {
+            let ids: Vec<Uuid> = expired.iter().map(|(id, _)| *id).collect();
+            delete_messages(db, &ids).await?;
+        }
+        Ok(expired)
+    }
+
+    /// Delete expired messages forever, calling `on_expired` for every batch
+    pub async fn run<F, Fut>(&self, db: PgPool, on_expired: F)
+    where
+        F: Fn(Vec<(Uuid, Uuid)>) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        loop {
+            tokio::time::sleep(self.interval).await;
+
+            loop {
+                match self.purge_batch(&db).await {
+                    Ok(expired) if expired.is_empty() => break,
+                    Ok(expired) => {
+                        tracing::debug!("{} messages expired", expired.len());
+                        let full = expired.len() as i64 == BATCH_SIZE;
+                        on_expired(expired).await;
+                        if !full {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Message expiry failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
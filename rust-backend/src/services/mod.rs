@@ -1,7 +1,49 @@
+pub mod alerts;
 pub mod auth;
 pub mod crypto;
+pub mod data_report;
+pub mod digest;
+pub mod error_spikes;
+pub mod federation;
+pub mod guests;
+pub mod image_proxy;
+pub mod maintenance;
+pub mod message_expiry;
+pub mod names;
+pub mod rate_limit;
+pub mod registration_queue;
+pub mod retention;
+pub mod room_mentions;
+pub mod room_stats;
+pub mod security_keys;
+pub mod settings;
+pub mod spam;
+pub mod storage;
 pub mod tor;
+pub mod tor_probe;
+pub mod translate;
 
+pub use alerts::*;
 pub use auth::*;
 pub use crypto::*;
+pub use data_report::*;
+pub use digest::*;
+pub use error_spikes::*;
+pub use federation::*;
+pub use guests::*;
+pub use image_proxy::*;
+pub use maintenance::*;
+pub use message_expiry::*;
+pub use names::*;
+pub use rate_limit::*;
+pub use registration_queue::*;
+pub use retention::*;
+pub use room_mentions::*;
+pub use room_stats::*;
+pub use security_keys::*;
+pub use settings::*;
+pub use spam::*;
+pub use storage::*;
 pub use tor::*;
+pub use tor_probe::*;
+pub use translate::*;
@@ -0,0 +1,124 @@
+/// Which user-chosen text is being checked; they differ in what characters
+/// they allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameKind {
+    Username,
+    DisplayName,
+    RoomName,
+    RoomDescription,
+}
+
+impl NameKind {
+    fn label(self) -> &'static str {
+        match self {
+            NameKind::Username => "Username",
+            NameKind::DisplayName => "Display name",
+            NameKind::RoomName => "Room name",
+            NameKind::RoomDescription => "Description",
+        }
+    }
+}
+
+/// Characters that render as nothing or reorder the text around them, used
+/// to pass off one name as another or to sneak words past the filter
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{061C}'
+            | '\u{115F}'
+            | '\u{1160}'
+            | '\u{17B4}'
+            | '\u{17B5}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{206F}'
+            | '\u{3164}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+    )
+}
+
+/// Lowercase `text` and undo the usual letter substitutions, so "B4dW0rd"
+/// reads like "badword"
+fn fold(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | '!' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            other => other,
+        })
+        .collect()
+}
+
+/// The blocked word `text` contains, if any. Words are matched whole so
+/// innocent names containing one aren't caught; the text is also matched
+/// with its separators removed, which catches "b.a.d" and "b a d".
+pub fn blocked_word<'a>(text: &str, blocked: &'a [String]) -> Option<&'a str> {
+    if blocked.is_empty() {
+        return None;
+    }
+    let folded = fold(text);
+    let words: Vec<&str> = folded
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let squashed: String = words.concat();
+    blocked
+        .iter()
+        .find(|b| {
+            let b = fold(b);
+            words.contains(&b.as_str()) || squashed == b
+        })
+        .map(String::as_str)
+}
+
+/// Check a name or description before it's stored: usernames are plain
+/// ASCII, other text may be any language but no control or invisible
+/// characters, and none may contain a blocked word. The error is meant for
+/// the user.
+pub fn check_name(kind: NameKind, text: &str, blocked: &[String]) -> Result<(), String> {
+    let label = kind.label();
+    match kind {
+        NameKind::Username => {
+            let valid = text.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && text
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+            if !valid {
+                return Err(format!(
+                    "{} may only contain letters, digits, '_', '.' and '-', and must start with a letter or digit",
+                    label
+                ));
+            }
+        }
+        NameKind::DisplayName | NameKind::RoomName | NameKind::RoomDescription => {
+            // Descriptions may span lines; names may not
+            let line_breaks_ok = kind == NameKind::RoomDescription;
+            if text
+                .chars()
+                .any(|c| (c.is_control() && !(line_breaks_ok && c == '\n')) || is_invisible(c))
+            {
+                return Err(format!(
+                    "{} contains control or invisible characters",
+                    label
+                ));
+            }
+            if kind != NameKind::RoomDescription && text.trim().is_empty() {
+                return Err(format!("{} can't be blank", label));
+            }
+        }
+    }
+
+    if blocked_word(text, blocked).is_some() {
+        return Err(format!("{} contains a word that isn't allowed here", label));
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Caps how often a public endpoint is served, across all visitors. Tor
+/// users share exit IPs and onion visitors all arrive from the local Tor
+/// daemon, so per-IP limits don't work here; a total that's plenty for people
+/// following links still stops a scraper from hammering the database.
+pub struct RateLimiter {
+    limit: AtomicUsize,
+    window: Duration,
+    recent: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    /// `limit` requests per `window`; 0 refuses everything
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit: AtomicUsize::new(limit),
+            window,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    /// Change the limit; requests already in the window still count
+    pub fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
+    /// Take one request from the current window; false once it's used up
+    pub fn try_acquire(&self) -> bool {
+        let now = Instant::now();
+        let mut recent = self.recent.lock().unwrap();
+        while recent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= self.window)
+        {
+            recent.pop_front();
+        }
+        if recent.len() >= self.limit() {
+            return false;
+        }
+        recent.push_back(now);
+        true
+    }
+}
@@ -0,0 +1,122 @@
+use crate::config::Config;
+use crate::error::Result;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+    pub activated: bool,
+    /// 1-based place in line; 0 once activated
+    pub position: i64,
+    pub estimated_wait_seconds: u64,
+}
+
+/// Delayed-activation queue for registration bursts. Above
+/// REGISTRATION_MAX_PER_MINUTE, new accounts are created inactive and
+/// activated one at a time, oldest first, with jittered spacing. Tor users
+/// all share a handful of exit IPs, so this replaces IP throttling and
+/// CAPTCHAs: nobody is refused, bursts are just slowed down.
+pub struct RegistrationQueue {
+    max_per_minute: i64,
+    interval: Duration,
+}
+
+impl RegistrationQueue {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            max_per_minute: config.registration_max_per_minute,
+            interval: Duration::from_secs(config.registration_activation_interval.max(1)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_per_minute > 0
+    }
+
+    /// Whether a new signup has to wait. Once anyone is queued, later
+    /// signups queue behind them so activation stays first-come first-served.
+    pub async fn should_queue(&self, db: &PgPool) -> Result<bool> {
+        if !self.is_enabled() {
+            return Ok(false);
+        }
+
+        let (queued, recent) = sqlx::query_as::<_, (bool, i64)>(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE queued_at IS NOT NULL),
+                    (SELECT COUNT(*) FROM users WHERE created_at > NOW() - INTERVAL '1 minute')",
+        )
+        .fetch_one(db)
+        .await?;
+
+        Ok(queued || recent >= self.max_per_minute)
+    }
+
+    /// Queue status for the token handed out at registration; `None` if the
+    /// token is unknown
+    pub async fn status(&self, db: &PgPool, queue_token: Uuid) -> Result<Option<QueueStatus>> {
+        let row = sqlx::query_as::<_, (Option<chrono::DateTime<chrono::Utc>>,)>(
+            "SELECT queued_at FROM users WHERE queue_token = $1",
+        )
+        .bind(queue_token)
+        .fetch_optional(db)
+        .await?;
+
+        let Some((queued_at,)) = row else {
+            return Ok(None);
+        };
+        let Some(queued_at) = queued_at else {
+            return Ok(Some(QueueStatus {
+                activated: true,
+                position: 0,
+                estimated_wait_seconds: 0,
+            }));
+        };
+
+        let position = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM users WHERE queued_at IS NOT NULL AND queued_at <= $1",
+        )
+        .bind(queued_at)
+        .fetch_one(db)
+        .await?;
+
+        Ok(Some(QueueStatus {
+            activated: false,
+            position,
+            estimated_wait_seconds: position.max(0) as u64 * self.interval.as_secs(),
+        }))
+    }
+
+    /// Activate queued accounts forever, one per (jittered) interval
+    pub async fn run(&self, db: PgPool) {
+        loop {
+            tokio::time::sleep(self.jittered_interval()).await;
+
+            match sqlx::query_scalar::<_, String>(
+                "UPDATE users SET queued_at = NULL
+                 WHERE id = (
+                     SELECT id FROM users WHERE queued_at IS NOT NULL
+                     ORDER BY queued_at ASC
+                     LIMIT 1
+                     FOR UPDATE SKIP LOCKED
+                 )
+                 RETURNING username",
+            )
+            .fetch_optional(&db)
+            .await
+            {
+                Ok(Some(username)) => tracing::info!("Activated queued account: {}", username),
+                Ok(None) => {}
+                Err(e) => tracing::error!("Registration queue activation failed: {}", e),
+            }
+        }
+    }
+
+    /// 50%-150% of the configured interval, so activation times don't reveal
+    /// queue order to an observer watching accounts come online
+    fn jittered_interval(&self) -> Duration {
+        let roll = (Uuid::new_v4().as_u128() % 1000) as u32;
+        self.interval.mul_f64(0.5 + roll as f64 / 1000.0)
+    }
+}
@@ -0,0 +1,165 @@
+use crate::config::Config;
+use crate::error::Result;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Messages deleted per statement, so one busy user can't hold long locks
+const BATCH_SIZE: i64 = 500;
+
+/// Longest personal retention period (ten years)
+pub const MAX_RETENTION_DAYS: i32 = 3650;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionRule {
+    /// `None` for the user's global rule
+    pub room_id: Option<Uuid>,
+    pub room_name: Option<String>,
+    pub days: i32,
+}
+
+/// Personal message retention: users ask for their own messages to be
+/// deleted once older than N days, globally or per room (a room rule
+/// overrides the global one). Applied by a periodic job.
+pub struct RetentionService {
+    interval: Duration,
+}
+
+impl RetentionService {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            interval: Duration::from_secs(config.retention_check_interval),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.interval.is_zero()
+    }
+
+    pub async fn rules(&self, db: &PgPool, user_id: Uuid) -> Result<Vec<RetentionRule>> {
+        Ok(sqlx::query_as::<_, RetentionRule>(
+            "SELECT mr.room_id, r.name AS room_name, mr.days
+             FROM message_retention mr
+             LEFT JOIN rooms r ON r.id = mr.room_id
+             WHERE mr.user_id = $1
+             ORDER BY mr.room_id NULLS FIRST, r.name",
+        )
+        .bind(user_id)
+        .fetch_all(db)
+        .await?)
+    }
+
+    /// Set or (with `days: None`) remove the global or a per-room rule
+    pub async fn set_rule(
+        &self,
+        db: &PgPool,
+        user_id: Uuid,
+        room_id: Option<Uuid>,
+        days: Option<i32>,
+    ) -> Result<()> {
+        sqlx::query(
+            "DELETE FROM message_retention WHERE user_id = $1 AND room_id IS NOT DISTINCT FROM $2",
+        )
+        .bind(user_id)
+        .bind(room_id)
+        .execute(db)
+        .await?;
+
+        if let Some(days) = days {
+            sqlx::query(
+                "INSERT INTO message_retention (user_id, room_id, days) VALUES ($1, $2, $3)",
+            )
+            .bind(user_id)
+            .bind(room_id)
+            .bind(days)
+            .execute(db)
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Delete one batch of expired messages. Returns `(message_id, room_id)`
+    /// of each deleted message.
+    pub async fn purge_batch(&self, db: &PgPool) -> Result<Vec<(Uuid, Uuid)>> {
+        let expired = sqlx::query_as::<_, (Uuid, Uuid)>(
+            "SELECT m.id, m.room_id
+             FROM messages m
+             JOIN message_retention mr ON mr.user_id = m.user_id
+              AND (mr.room_id = m.room_id
+                   OR (mr.room_id IS NULL AND NOT EXISTS (
+                       SELECT 1 FROM message_retention room_rule
+                       WHERE room_rule.user_id = m.user_id AND room_rule.room_id = m.room_id)))
+             WHERE m.created_at < NOW() - make_interval(days => mr.days)
+             LIMIT $1",
+        )
+        .bind(BATCH_SIZE)
+        .fetch_all(db)
+        .await?;
+
+        if !expired.is_empty() {
+            let ids: Vec<Uuid> = expired.iter().map(|(id, _)| *id).collect();
+            delete_messages(db, &ids).await?;
+        }
+        Ok(expired)
+    }
+
+    /// Apply retention rules forever, calling `on_deleted` for every batch
+    /// so clients can drop the messages live
+    pub async fn run<F, Fut>(&self, db: PgPool, on_deleted: F)
+    where
+        F: Fn(Vec<(Uuid, Uuid)>) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        loop {
+            tokio::time::sleep(self.interval).await;
+
+            loop {
+                match self.purge_batch(&db).await {
+                    Ok(deleted) if deleted.is_empty() => break,
+                    Ok(deleted) => {
+                        tracing::info!("Retention deleted {} messages", deleted.len());
+                        let full = deleted.len() as i64 == BATCH_SIZE;
+                        on_deleted(deleted).await;
+                        if !full {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Retention purge failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Delete messages by ID. Replies, forwards and read markers keep pointing
+/// at a message; they're detached first so the delete doesn't trip the
+/// foreign keys.
+pub async fn delete_messages(db: &PgPool, ids: &[Uuid]) -> Result<()> {
+    let mut tx = db.begin().await?;
+    sqlx::query("UPDATE messages SET reply_to = NULL WHERE reply_to = ANY($1)")
+        .bind(ids)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE messages SET forwarded_from = NULL WHERE forwarded_from = ANY($1)")
+        .bind(ids)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "UPDATE room_members SET last_read_message_id = NULL
+         WHERE last_read_message_id = ANY($1)",
+    )
+    .bind(ids)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM messages WHERE id = ANY($1)")
+        .bind(ids)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use crate::config::Config;
+use sqlx::PgPool;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Window the per-room cap applies to
+const WINDOW: Duration = Duration::from_secs(3600);
+
+/// Who a whole-room mention notifies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MentionScope {
+    /// `@room`: every member
+    Room,
+    /// `@here`: members who are online
+    Here,
+}
+
+impl MentionScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MentionScope::Room => "room",
+            MentionScope::Here => "here",
+        }
+    }
+
+    /// The widest whole-room mention in a message, if any. A mention is the
+    /// word on its own: "@roommate" and "me@here" don't count.
+    pub fn find(content: &str) -> Option<Self> {
+        let mut found = None;
+        for word in content.split(|c: char| !(c.is_alphanumeric() || c == '@' || c == '_')) {
+            match word {
+                "@room" => return Some(MentionScope::Room),
+                "@here" => found = Some(MentionScope::Here),
+                _ => {}
+            }
+        }
+        found
+    }
+}
+
+/// `@room` and `@here`: who may use them is up to the room's admins, how
+/// often is capped here, per room, so a compromised or overeager admin
+/// can't keep every member's notifications going off
+pub struct RoomMentions {
+    limit: usize,
+    recent: Mutex<HashMap<Uuid, VecDeque<Instant>>>,
+}
+
+impl RoomMentions {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            limit: config.room_mention_limit,
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether a message mentioning the whole room may go out, taking one of
+    /// the room's mentions for the hour if so. The error is for the sender.
+    pub async fn check(&self, room_id: Uuid, is_room_admin: bool) -> Result<(), String> {
+        if self.limit == 0 {
+            return Err("@room and @here are turned off on this server".to_string());
+        }
+        if !is_room_admin {
+            return Err("Only room admins can use @room and @here".to_string());
+        }
+
+        let now = Instant::now();
+        let mut recent = self.recent.lock().await;
+        recent.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= WINDOW)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = recent.entry(room_id).or_default();
+        if times.len() >= self.limit {
+            return Err(format!(
+                "@room and @here can be used {} times an hour in a room; try again later",
+                self.limit
+            ));
+        }
+        times.push_back(now);
+        Ok(())
+    }
+
+    /// Members a mention by `sender` should reach: everyone else in the
+    /// room who hasn't turned notifications off or muted them for now.
+    /// `@here` further narrows this to whoever is online.
+    pub async fn recipients(
+        &self,
+        db: &PgPool,
+        room_id: Uuid,
+        sender: Uuid,
+    ) -> sqlx::Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            "SELECT user_id FROM room_members
+             WHERE room_id = $1 AND user_id != $2
+               AND notification_level != 'none'
+               AND (notifications_muted_until IS NULL OR notifications_muted_until <= NOW())",
+        )
+        .bind(room_id)
+        .bind(sender)
+        .fetch_all(db)
+        .await
+    }
+}
@@ -0,0 +1,144 @@
+use crate::config::Config;
+use crate::error::Result;
+use chrono::{DateTime, NaiveDate, Timelike, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Longest window `stats` reports on
+pub const MAX_STATS_DAYS: i32 = 90;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyCount {
+    pub date: NaiveDate,
+    pub messages: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomStats {
+    pub member_count: i64,
+    pub total_messages: i64,
+    /// One entry per day of the window, oldest first, zero days included
+    pub daily: Vec<DailyCount>,
+    /// Messages per hour of day (UTC) over the window, index 0 = 00:00
+    pub active_hours: Vec<i64>,
+}
+
+/// Per-room activity statistics. A periodic job rolls message counts up into
+/// `room_activity_hourly` so the stats endpoint never scans a room's full
+/// history; only messages newer than the last rollup are counted live.
+/// Rollups count messages sent, so later deletions don't lower them.
+pub struct RoomStatsService {
+    interval: Duration,
+}
+
+impl RoomStatsService {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            interval: Duration::from_secs(config.room_stats_rollup_interval),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.interval.is_zero()
+    }
+
+    /// Count every complete hour since the last rollup (all history on the
+    /// first run). The newest rolled-up hour is recounted, which is harmless.
+    async fn rollup(&self, db: &PgPool) -> Result<u64> {
+        let result = sqlx::query(
+            "INSERT INTO room_activity_hourly (room_id, hour, message_count)
+             SELECT room_id, date_trunc('hour', created_at) AS hour, COUNT(*)
+             FROM messages
+             WHERE created_at >= COALESCE((SELECT MAX(hour) FROM room_activity_hourly), '-infinity')
+               AND created_at < date_trunc('hour', NOW())
+             GROUP BY room_id, hour
+             ON CONFLICT (room_id, hour) DO UPDATE SET message_count = EXCLUDED.message_count",
+        )
+        .execute(db)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn stats(&self, db: &PgPool, room_id: Uuid, days: i32) -> Result<RoomStats> {
+        let days = days.clamp(1, MAX_STATS_DAYS);
+
+        let member_count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM room_members WHERE room_id = $1")
+                .bind(room_id)
+                .fetch_one(db)
+                .await?;
+
+        // Rolled-up hours, plus live counts for anything after the last rollup
+        let total_messages: i64 = sqlx::query_scalar(
+            "WITH cutoff AS (
+                 SELECT COALESCE(MAX(hour) + INTERVAL '1 hour', '-infinity') AS at
+                 FROM room_activity_hourly
+             )
+             SELECT COALESCE((SELECT SUM(message_count) FROM room_activity_hourly WHERE room_id = $1), 0)::BIGINT
+                  + (SELECT COUNT(*) FROM messages, cutoff WHERE room_id = $1 AND created_at >= cutoff.at)",
+        )
+        .bind(room_id)
+        .fetch_one(db)
+        .await?;
+
+        let hours = sqlx::query_as::<_, (DateTime<Utc>, i64)>(
+            "WITH cutoff AS (
+                 SELECT COALESCE(MAX(hour) + INTERVAL '1 hour', '-infinity') AS at
+                 FROM room_activity_hourly
+             )
+             SELECT hour, message_count::BIGINT
+             FROM room_activity_hourly
+             WHERE room_id = $1 AND hour >= NOW() - make_interval(days => $2)
+             UNION ALL
+             SELECT date_trunc('hour', created_at), COUNT(*)
+             FROM messages, cutoff
+             WHERE room_id = $1
+               AND created_at >= cutoff.at
+               AND created_at >= NOW() - make_interval(days => $2)
+             GROUP BY 1",
+        )
+        .bind(room_id)
+        .bind(days)
+        .fetch_all(db)
+        .await?;
+
+        let today = Utc::now().date_naive();
+        let first_day = today - chrono::Duration::days(i64::from(days) - 1);
+        let mut daily: Vec<DailyCount> = first_day
+            .iter_days()
+            .take(days as usize)
+            .map(|date| DailyCount { date, messages: 0 })
+            .collect();
+        let mut active_hours = vec![0i64; 24];
+        for (hour, count) in hours {
+            let day = (hour.date_naive() - first_day).num_days();
+            if let Some(entry) = usize::try_from(day).ok().and_then(|d| daily.get_mut(d)) {
+                entry.messages += count;
+            }
+            active_hours[hour.hour() as usize] += count;
+        }
+
+        Ok(RoomStats {
+            member_count,
+            total_messages,
+            daily,
+            active_hours,
+        })
+    }
+
+    /// Roll up activity forever
+    pub async fn run(&self, db: PgPool) {
+        loop {
+            tokio::time::sleep(self.interval).await;
+
+            match self.rollup(&db).await {
+                Ok(rows) => tracing::debug!("Room activity rollup updated {} hours", rows),
+                Err(e) => tracing::error!("Room activity rollup failed: {}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::models::User;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    AuthenticationResult, CreationChallengeResponse, CredentialID, Passkey, PasskeyAuthentication,
+    PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential,
+    RequestChallengeResponse, Url, Webauthn, WebauthnBuilder, WebauthnError,
+};
+
+/// How long a started registration or login can be finished
+const CEREMONY_TTL: Duration = Duration::from_secs(300);
+/// Started ceremonies kept at once, per kind. Logins are started without an
+/// account, so past this the oldest are dropped rather than memory growing.
+const MAX_PENDING: usize = 10_000;
+
+struct Pending<T> {
+    user_id: Uuid,
+    state: T,
+    started: Instant,
+}
+
+/// Started ceremonies by the challenge id handed to the client
+struct Ceremonies<T>(Mutex<HashMap<Uuid, Pending<T>>>);
+
+impl<T> Ceremonies<T> {
+    fn new() -> Self {
+        Self(Mutex::new(HashMap::new()))
+    }
+
+    fn insert(&self, user_id: Uuid, state: T) -> Uuid {
+        let mut pending = self.0.lock().unwrap();
+        pending.retain(|_, p| p.started.elapsed() < CEREMONY_TTL);
+        if pending.len() >= MAX_PENDING {
+            let oldest = pending
+                .iter()
+                .min_by_key(|(_, p)| p.started)
+                .map(|(id, _)| *id);
+            if let Some(id) = oldest {
+                pending.remove(&id);
+            }
+        }
+        let id = Uuid::new_v4();
+        pending.insert(
+            id,
+            Pending {
+                user_id,
+                state,
+                started: Instant::now(),
+            },
+        );
+        id
+    }
+
+    /// The ceremony's user and state; each can be finished once
+    fn take(&self, id: Uuid) -> Option<(Uuid, T)> {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .filter(|p| p.started.elapsed() < CEREMONY_TTL)
+            .map(|p| (p.user_id, p.state))
+    }
+}
+
+/// WebAuthn security keys (hardware keys or platform authenticators):
+/// registering them and checking logins made with them. Keys are bound to
+/// the host of WEBAUTHN_ORIGIN; without it the feature is off. Started
+/// ceremonies are only kept in memory, so a restart means starting over.
+pub struct SecurityKeys {
+    webauthn: Option<Webauthn>,
+    registrations: Ceremonies<PasskeyRegistration>,
+    logins: Ceremonies<PasskeyAuthentication>,
+}
+
+impl SecurityKeys {
+    pub fn new(config: &Config) -> Self {
+        let webauthn = match config.webauthn_origin.as_deref().map(build_webauthn) {
+            Some(Ok(webauthn)) => Some(webauthn),
+            Some(Err(e)) => {
+                tracing::error!("Security keys disabled: bad WEBAUTHN_ORIGIN: {}", e);
+                None
+            }
+            None => None,
+        };
+        Self {
+            webauthn,
+            registrations: Ceremonies::new(),
+            logins: Ceremonies::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.webauthn.is_some()
+    }
+
+    fn webauthn(&self) -> Result<&Webauthn> {
+        self.webauthn.as_ref().ok_or_else(|| {
+            AppError::Authorization("Security keys are not enabled on this server".to_string())
+        })
+    }
+
+    /// Start registering a key for `user`: the options for the browser's
+    /// `navigator.credentials.create()`, and the challenge id to finish with.
+    /// Keys in `existing` can't be registered again.
+    pub fn start_registration(
+        &self,
+        user: &User,
+        existing: &[Passkey],
+    ) -> Result<(Uuid, CreationChallengeResponse)> {
+        let exclude: Vec<CredentialID> = existing.iter().map(|p| p.cred_id().clone()).collect();
+        let (options, state) = self
+            .webauthn()?
+            .start_passkey_registration(
+                user.id,
+                &user.username,
+                user.display_name.as_deref().unwrap_or(&user.username),
+                Some(exclude),
+            )
+            .map_err(ceremony_error)?;
+        Ok((self.registrations.insert(user.id, state), options))
+    }
+
+    /// The new key, if the browser's answer matches a registration `user_id`
+    /// started
+    pub fn finish_registration(
+        &self,
+        user_id: Uuid,
+        challenge_id: Uuid,
+        credential: &RegisterPublicKeyCredential,
+    ) -> Result<Passkey> {
+        let webauthn = self.webauthn()?;
+        let state = match self.registrations.take(challenge_id) {
+            Some((owner, state)) if owner == user_id => state,
+            _ => {
+                return Err(AppError::BadRequest(
+                    "Registration expired; start again".to_string(),
+                ))
+            }
+        };
+        webauthn
+            .finish_passkey_registration(credential, &state)
+            .map_err(ceremony_error)
+    }
+
+    /// Start a login with one of `passkeys`, all of them `user_id`'s: the
+    /// options for `navigator.credentials.get()` and the challenge id
+    pub fn start_login(
+        &self,
+        user_id: Uuid,
+        passkeys: &[Passkey],
+    ) -> Result<(Uuid, RequestChallengeResponse)> {
+        let (options, state) = self
+            .webauthn()?
+            .start_passkey_authentication(passkeys)
+            .map_err(ceremony_error)?;
+        Ok((self.logins.insert(user_id, state), options))
+    }
+
+    /// Check the browser's answer to a started login. Returns whose login
+    /// it was, and the result to update the used key with.
+    pub fn finish_login(
+        &self,
+        challenge_id: Uuid,
+        credential: &PublicKeyCredential,
+    ) -> Result<(Uuid, AuthenticationResult)> {
+        let webauthn = self.webauthn()?;
+        let (user_id, state) = self
+            .logins
+            .take(challenge_id)
+            .ok_or_else(|| AppError::Authentication("Login expired; start again".to_string()))?;
+        let result = webauthn
+            .finish_passkey_authentication(credential, &state)
+            .map_err(ceremony_error)?;
+        Ok((user_id, result))
+    }
+}
+
+fn build_webauthn(origin: &str) -> std::result::Result<Webauthn, String> {
+    let url = Url::parse(origin).map_err(|e| e.to_string())?;
+    let rp_id = url
+        .host_str()
+        .ok_or_else(|| "no host".to_string())?
+        .to_string();
+    WebauthnBuilder::new(&rp_id, &url)
+        .and_then(|builder| builder.rp_name("TOR Chat").build())
+        .map_err(|e| e.to_string())
+}
+
+/// Failed checks all look the same to the client
+fn ceremony_error(e: WebauthnError) -> AppError {
+    tracing::debug!("Security key ceremony failed: {}", e);
+    AppError::Authentication("Security key check failed".to_string())
+}
@@ -0,0 +1,547 @@
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::services::{Maintenance, RateLimiter};
+use axum::http::Method;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use socketioxide::SocketIo;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Smallest room anyone may create or shrink a room to
+pub const MIN_ROOM_MEMBERS: i32 = 2;
+/// Ceiling for the admin-set cap; rooms this big already strain fan-out
+pub const ROOM_MEMBERS_CEILING: i32 = 100_000;
+/// Most blocked words an admin may set, and the longest one
+pub const MAX_BLOCKED_WORDS: usize = 1000;
+pub const MAX_BLOCKED_WORD_LEN: usize = 50;
+
+/// Room size limits: what a new room gets when the creator doesn't say,
+/// and the most any room may be set to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomLimits {
+    pub default_max_members: i32,
+    pub max_members_cap: i32,
+}
+
+impl RoomLimits {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if !(MIN_ROOM_MEMBERS..=ROOM_MEMBERS_CEILING).contains(&self.max_members_cap) {
+            return Err(format!(
+                "maxMembersCap must be between {} and {}",
+                MIN_ROOM_MEMBERS, ROOM_MEMBERS_CEILING
+            ));
+        }
+        if !(MIN_ROOM_MEMBERS..=self.max_members_cap).contains(&self.default_max_members) {
+            return Err(format!(
+                "defaultMaxMembers must be between {} and maxMembersCap ({})",
+                MIN_ROOM_MEMBERS, self.max_members_cap
+            ));
+        }
+        Ok(())
+    }
+
+    /// `maxMembers` for a new or edited room: the requested size if within
+    /// the cap, the default when none was asked for
+    pub fn resolve(&self, requested: Option<i32>) -> std::result::Result<i32, String> {
+        match requested {
+            None => Ok(self.default_max_members),
+            Some(n) if (MIN_ROOM_MEMBERS..=self.max_members_cap).contains(&n) => Ok(n),
+            Some(_) => Err(format!(
+                "maxMembers must be between {} and {} on this server",
+                MIN_ROOM_MEMBERS, self.max_members_cap
+            )),
+        }
+    }
+}
+
+/// Longest a guest session may be set to last
+pub const MAX_GUEST_SESSION_HOURS: i64 = 72;
+/// Most rooms guests may be let into
+pub const MAX_GUEST_ROOMS: usize = 20;
+/// Ceiling for the number of guest accounts alive at once
+pub const MAX_LIVE_GUESTS: i64 = 10_000;
+
+/// Guest mode: short-lived accounts without a password, for reading (and,
+/// when allowed, posting in) a few designated public rooms. Off by default.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GuestAccess {
+    pub enabled: bool,
+    /// Rooms a guest joins and is limited to
+    pub room_ids: Vec<Uuid>,
+    /// Whether guests may send messages there
+    pub can_post: bool,
+    /// How long a guest account lasts before it's purged
+    pub session_hours: i64,
+    /// Guest accounts alive at once; new sessions are refused beyond it
+    #[serde(default = "default_max_guests")]
+    pub max_guests: i64,
+}
+
+fn default_max_guests() -> i64 {
+    100
+}
+
+impl Default for GuestAccess {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            room_ids: Vec::new(),
+            can_post: false,
+            session_hours: 24,
+            max_guests: default_max_guests(),
+        }
+    }
+}
+
+impl GuestAccess {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if !(1..=MAX_GUEST_SESSION_HOURS).contains(&self.session_hours) {
+            return Err(format!(
+                "sessionHours must be between 1 and {}",
+                MAX_GUEST_SESSION_HOURS
+            ));
+        }
+        if !(1..=MAX_LIVE_GUESTS).contains(&self.max_guests) {
+            return Err(format!(
+                "maxGuests must be between 1 and {}",
+                MAX_LIVE_GUESTS
+            ));
+        }
+        if self.room_ids.len() > MAX_GUEST_ROOMS {
+            return Err(format!(
+                "roomIds may have at most {} rooms",
+                MAX_GUEST_ROOMS
+            ));
+        }
+        if self.enabled && self.room_ids.is_empty() {
+            return Err("Pick at least one room for guests".to_string());
+        }
+        Ok(())
+    }
+
+    /// Whether a guest may make this request: read its rooms, send messages
+    /// there when posting is allowed, and look at or log out of its account
+    pub fn allows(&self, method: &Method, path: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        if method == Method::GET || method == Method::HEAD {
+            match segments.as_slice() {
+                ["api", "auth", "me"] | ["api", "rooms"] => true,
+                ["api", "rooms", id, rest @ ..] => {
+                    self.has_room(id) && matches!(rest, [] | ["messages"] | ["members"] | ["pins"])
+                }
+                _ => false,
+            }
+        } else if method == Method::POST {
+            match segments.as_slice() {
+                ["api", "auth", "logout"] => true,
+                ["api", "rooms", id, "messages"] => self.can_post && self.has_room(id),
+                _ => false,
+            }
+        } else {
+            false
+        }
+    }
+
+    fn has_room(&self, raw_id: &str) -> bool {
+        Uuid::parse_str(raw_id).is_ok_and(|id| self.room_ids.contains(&id))
+    }
+}
+
+/// Highest per-minute limit an admin may set for a public endpoint
+pub const MAX_RATE_LIMIT: usize = 10_000;
+
+/// Per-minute caps on public endpoints, across all visitors (see
+/// [`RateLimiter`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimits {
+    /// `/invite/{code}` pages; 0 turns them off
+    pub invite_pages_per_minute: usize,
+    /// `POST /api/auth/guest`; 0 refuses them all
+    pub guest_sessions_per_minute: usize,
+}
+
+impl RateLimits {
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.invite_pages_per_minute > MAX_RATE_LIMIT {
+            return Err(format!(
+                "invitePagesPerMinute must be at most {}",
+                MAX_RATE_LIMIT
+            ));
+        }
+        if self.guest_sessions_per_minute > MAX_RATE_LIMIT {
+            return Err(format!(
+                "guestSessionsPerMinute must be at most {}",
+                MAX_RATE_LIMIT
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Postgres channel a saved setting's key is sent on
+const SETTINGS_CHANNEL: &str = "server_settings";
+/// Wait before listening again after losing the connection
+const RELISTEN_DELAY: Duration = Duration::from_secs(5);
+
+/// Settings admins change at runtime through `/api/admin/settings`. The
+/// environment provides the starting values; changes are kept in the
+/// `server_settings` table and win over the environment from then on.
+/// Every server on the database keeps a copy, reloaded when any of them
+/// saves a change (see [`run_settings_sync`]).
+pub struct ServerSettings {
+    rooms: RwLock<RoomLimits>,
+    blocked_words: RwLock<Vec<String>>,
+    guests: RwLock<GuestAccess>,
+    user_directory_public: RwLock<bool>,
+    /// Limiters for the endpoints in [`RateLimits`], changed in place
+    pub invite_pages: Arc<RateLimiter>,
+    pub guest_sessions: Arc<RateLimiter>,
+    /// Made read-only while another server migrates the schema
+    maintenance: Arc<Maintenance>,
+}
+
+/// Trimmed, lowercased and deduplicated; `Err` for a list that's too long or
+/// has a word that is too long
+pub fn normalize_blocked_words(words: &[String]) -> std::result::Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = words
+        .iter()
+        .map(|w| w.trim().to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    if normalized.len() > MAX_BLOCKED_WORDS {
+        return Err(format!(
+            "blockedWords may have at most {} words",
+            MAX_BLOCKED_WORDS
+        ));
+    }
+    if let Some(word) = normalized
+        .iter()
+        .find(|w| w.chars().count() > MAX_BLOCKED_WORD_LEN)
+    {
+        return Err(format!(
+            "Blocked word '{}' is longer than {} characters",
+            word, MAX_BLOCKED_WORD_LEN
+        ));
+    }
+    Ok(normalized)
+}
+
+impl ServerSettings {
+    const ROOMS_KEY: &'static str = "rooms";
+    const BLOCKED_WORDS_KEY: &'static str = "blocked_words";
+    const GUESTS_KEY: &'static str = "guest_access";
+    const USER_DIRECTORY_KEY: &'static str = "user_directory_public";
+    const RATE_LIMITS_KEY: &'static str = "rate_limits";
+    /// Present while a server migrates the schema, with when it started
+    const MIGRATION_KEY: &'static str = "migration";
+    /// Window of the limiters in [`RateLimits`]
+    const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+    pub fn new(config: &Config, maintenance: Arc<Maintenance>) -> Self {
+        Self {
+            rooms: RwLock::new(RoomLimits {
+                default_max_members: config.room_default_max_members,
+                max_members_cap: config.room_max_members_cap,
+            }),
+            blocked_words: RwLock::new(config.blocked_words.clone()),
+            guests: RwLock::new(GuestAccess::default()),
+            user_directory_public: RwLock::new(config.user_directory_public),
+            invite_pages: Arc::new(RateLimiter::new(
+                config.invite_page_limit,
+                Self::RATE_WINDOW,
+            )),
+            guest_sessions: Arc::new(RateLimiter::new(
+                config.guest_session_limit,
+                Self::RATE_WINDOW,
+            )),
+            maintenance,
+        }
+    }
+
+    /// Pick up settings saved by admins; ones that no longer validate (or
+    /// don't parse) are logged and the environment's are kept
+    pub async fn load(&self, db: &PgPool) -> Result<()> {
+        let saved = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT value FROM server_settings WHERE key = $1",
+        )
+        .bind(Self::ROOMS_KEY)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some(value) = saved {
+            match serde_json::from_value::<RoomLimits>(value)
+                .map_err(|e| e.to_string())
+                .and_then(|limits| limits.validate().map(|()| limits))
+            {
+                Ok(limits) => *self.rooms.write().unwrap() = limits,
+                Err(e) => tracing::warn!("Ignoring saved room limits: {}", e),
+            }
+        }
+
+        let saved = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT value FROM server_settings WHERE key = $1",
+        )
+        .bind(Self::BLOCKED_WORDS_KEY)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some(value) = saved {
+            match serde_json::from_value::<Vec<String>>(value)
+                .map_err(|e| e.to_string())
+                .and_then(|words| normalize_blocked_words(&words))
+            {
+                Ok(words) => *self.blocked_words.write().unwrap() = words,
+                Err(e) => tracing::warn!("Ignoring saved blocked words: {}", e),
+            }
+        }
+
+        let saved = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT value FROM server_settings WHERE key = $1",
+        )
+        .bind(Self::GUESTS_KEY)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some(value) = saved {
+            match serde_json::from_value::<GuestAccess>(value)
+                .map_err(|e| e.to_string())
+                .and_then(|guests| guests.validate().map(|()| guests))
+            {
+                Ok(guests) => *self.guests.write().unwrap() = guests,
+                Err(e) => tracing::warn!("Ignoring saved guest access: {}", e),
+            }
+        }
+
+        let saved = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT value FROM server_settings WHERE key = $1",
+        )
+        .bind(Self::USER_DIRECTORY_KEY)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some(value) = saved {
+            match serde_json::from_value::<bool>(value) {
+                Ok(public) => *self.user_directory_public.write().unwrap() = public,
+                Err(e) => tracing::warn!("Ignoring saved user directory setting: {}", e),
+            }
+        }
+
+        let saved = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT value FROM server_settings WHERE key = $1",
+        )
+        .bind(Self::RATE_LIMITS_KEY)
+        .fetch_optional(db)
+        .await?;
+
+        if let Some(value) = saved {
+            match serde_json::from_value::<RateLimits>(value)
+                .map_err(|e| e.to_string())
+                .and_then(|limits| limits.validate().map(|()| limits))
+            {
+                Ok(limits) => self.apply_rate_limits(limits),
+                Err(e) => tracing::warn!("Ignoring saved rate limits: {}", e),
+            }
+        }
+
+        // Unlike the settings above, this one is cleared when it's gone
+        let migrating = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT value FROM server_settings WHERE key = $1",
+        )
+        .bind(Self::MIGRATION_KEY)
+        .fetch_optional(db)
+        .await?;
+        self.maintenance.set_migrating(
+            migrating.map(|since| serde_json::from_value(since).unwrap_or_else(|_| Utc::now())),
+        );
+        Ok(())
+    }
+
+    pub fn room_limits(&self) -> RoomLimits {
+        *self.rooms.read().unwrap()
+    }
+
+    pub async fn set_room_limits(&self, db: &PgPool, limits: RoomLimits) -> Result<()> {
+        limits.validate().map_err(AppError::Validation)?;
+        save(db, Self::ROOMS_KEY, serde_json::json!(limits)).await?;
+        *self.rooms.write().unwrap() = limits;
+        Ok(())
+    }
+
+    pub fn blocked_words(&self) -> Vec<String> {
+        self.blocked_words.read().unwrap().clone()
+    }
+
+    /// Replace the blocked word list. Names already taken aren't affected;
+    /// the list applies when names are next chosen or changed.
+    pub async fn set_blocked_words(&self, db: &PgPool, words: &[String]) -> Result<Vec<String>> {
+        let words = normalize_blocked_words(words).map_err(AppError::Validation)?;
+        save(db, Self::BLOCKED_WORDS_KEY, serde_json::json!(words)).await?;
+        *self.blocked_words.write().unwrap() = words.clone();
+        Ok(words)
+    }
+
+    pub fn guest_access(&self) -> GuestAccess {
+        self.guests.read().unwrap().clone()
+    }
+
+    /// Change guest mode. Rooms must be checked to be public by the caller;
+    /// guests already in a room that's dropped lose access to it right away.
+    pub async fn set_guest_access(&self, db: &PgPool, guests: GuestAccess) -> Result<()> {
+        guests.validate().map_err(AppError::Validation)?;
+        save(db, Self::GUESTS_KEY, serde_json::json!(guests)).await?;
+        *self.guests.write().unwrap() = guests;
+        Ok(())
+    }
+
+    /// Whether non-admins may list users through `/api/auth/users`
+    pub fn user_directory_public(&self) -> bool {
+        *self.user_directory_public.read().unwrap()
+    }
+
+    pub async fn set_user_directory_public(&self, db: &PgPool, public: bool) -> Result<()> {
+        save(db, Self::USER_DIRECTORY_KEY, serde_json::json!(public)).await?;
+        *self.user_directory_public.write().unwrap() = public;
+        Ok(())
+    }
+
+    pub fn rate_limits(&self) -> RateLimits {
+        RateLimits {
+            invite_pages_per_minute: self.invite_pages.limit(),
+            guest_sessions_per_minute: self.guest_sessions.limit(),
+        }
+    }
+
+    pub async fn set_rate_limits(&self, db: &PgPool, limits: RateLimits) -> Result<()> {
+        limits.validate().map_err(AppError::Validation)?;
+        save(db, Self::RATE_LIMITS_KEY, serde_json::json!(limits)).await?;
+        self.apply_rate_limits(limits);
+        Ok(())
+    }
+
+    fn apply_rate_limits(&self, limits: RateLimits) {
+        self.invite_pages.set_limit(limits.invite_pages_per_minute);
+        self.guest_sessions
+            .set_limit(limits.guest_sessions_per_minute);
+    }
+}
+
+/// Store a setting and tell every server on the database to reload. The
+/// notification is only delivered once the new value is committed.
+async fn save(db: &PgPool, key: &str, value: serde_json::Value) -> Result<()> {
+    let mut tx = db.begin().await?;
+    sqlx::query(
+        "INSERT INTO server_settings (key, value) VALUES ($1, $2)
+         ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()",
+    )
+    .bind(key)
+    .bind(value)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(SETTINGS_CHANNEL)
+        .bind(key)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Tell the other servers on the database that this one is about to migrate
+/// the schema, so they go read-only until [`end_migration`]. A fresh
+/// database has no settings table yet, and no other server to tell.
+pub async fn begin_migration(db: &PgPool) -> Result<()> {
+    let has_settings =
+        sqlx::query_scalar::<_, bool>("SELECT to_regclass('server_settings') IS NOT NULL")
+            .fetch_one(db)
+            .await?;
+    if has_settings {
+        save(
+            db,
+            ServerSettings::MIGRATION_KEY,
+            serde_json::json!(Utc::now()),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Let the other servers write again, whether the migration worked or not.
+/// A server that died mid-migration leaves them read-only until one
+/// starts up again.
+pub async fn end_migration(db: &PgPool) -> Result<()> {
+    let mut tx = db.begin().await?;
+    sqlx::query("DELETE FROM server_settings WHERE key = $1")
+        .bind(ServerSettings::MIGRATION_KEY)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(SETTINGS_CHANNEL)
+        .bind(ServerSettings::MIGRATION_KEY)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Reload `settings`, and show or hide the clients' maintenance banner when
+/// another server's migration started or ended
+async fn reload(settings: &ServerSettings, db: &PgPool, io: &SocketIo) {
+    let was_migrating = settings.maintenance.is_migrating();
+    if let Err(e) = settings.load(db).await {
+        tracing::error!("Failed to reload server settings: {}", e);
+        return;
+    }
+    if settings.maintenance.is_migrating() != was_migrating {
+        io.emit("maintenance", &settings.maintenance.status())
+            .await
+            .ok();
+    }
+}
+
+/// Keep `settings` in step with changes saved by any server on the database,
+/// this one included, so admins don't have to restart the others. After a
+/// lost connection everything is reloaded, in case a change was missed.
+pub async fn run_settings_sync(settings: Arc<ServerSettings>, db: PgPool, io: SocketIo) {
+    loop {
+        match PgListener::connect_with(&db).await {
+            Ok(mut listener) => match listener.listen(SETTINGS_CHANNEL).await {
+                Ok(()) => {
+                    // Listening from here on; catch up on what came before
+                    reload(&settings, &db, &io).await;
+                    loop {
+                        match listener.try_recv().await {
+                            Ok(Some(notification)) => {
+                                tracing::debug!("Setting '{}' changed", notification.payload());
+                                reload(&settings, &db, &io).await;
+                            }
+                            Ok(None) => {
+                                tracing::warn!("Lost the settings listener's connection");
+                                break;
+                            }
+                            Err(e) => {
+                                tracing::warn!("Settings listener failed: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Failed to listen for settings changes: {}", e),
+            },
+            Err(e) => tracing::warn!("Failed to listen for settings changes: {}", e),
+        }
+        tokio::time::sleep(RELISTEN_DELAY).await;
+    }
+}
@@ -0,0 +1,197 @@
+use crate::config::Config;
+use crate::models::User;
+use chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Messages this short ("ok", "lol", ...) are repeated innocently all the time
+const MIN_DUPLICATE_LEN: usize = 8;
+
+/// What happens to a message once its score crosses a threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpamAction {
+    /// Delivered as usual, listed for moderator review
+    Flag,
+    /// Only the sender and moderators see it (shadow-hide)
+    Hide,
+}
+
+impl SpamAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SpamAction::Flag => "flagged",
+            SpamAction::Hide => "hidden",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpamVerdict {
+    /// 0.0 (clean) to 1.0 (certainly spam)
+    pub score: f32,
+    pub reasons: Vec<&'static str>,
+    pub action: Option<SpamAction>,
+}
+
+impl SpamVerdict {
+    pub fn action_str(&self) -> Option<&'static str> {
+        self.action.map(SpamAction::as_str)
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.action == Some(SpamAction::Hide)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RecentActivity {
+    last_minute: i64,
+    duplicates: i64,
+}
+
+/// Heuristic spam scoring for messages posted in public rooms: sending
+/// rate, repeated content, link density and account age. Private rooms are
+/// invite-only and never scored.
+pub struct SpamFilter {
+    enabled: bool,
+    flag_threshold: f32,
+    hide_threshold: f32,
+}
+
+impl SpamFilter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.spam_filter_enabled,
+            flag_threshold: config.spam_flag_threshold,
+            hide_threshold: config.spam_hide_threshold,
+        }
+    }
+
+    /// Score a message about to be stored. `None` means the message wasn't
+    /// scored (filter off, private room, or the lookups failed).
+    pub async fn check(
+        &self,
+        db: &PgPool,
+        room_id: Uuid,
+        user: &User,
+        content: &str,
+        message_type: &str,
+    ) -> Option<SpamVerdict> {
+        if !self.enabled {
+            return None;
+        }
+
+        let is_public = sqlx::query_scalar::<_, bool>(
+            "SELECT COALESCE(is_public, false) FROM rooms WHERE id = $1",
+        )
+        .bind(room_id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| tracing::error!("Spam check room lookup failed: {}", e))
+        .ok()
+        .flatten()?;
+        if !is_public {
+            return None;
+        }
+
+        // Counted before the insert, so the current message isn't included
+        let activity = sqlx::query_as::<_, RecentActivity>(
+            "SELECT COUNT(*) FILTER (WHERE created_at > NOW() - INTERVAL '1 minute') AS last_minute,
+                    COUNT(*) FILTER (WHERE content = $2) AS duplicates
+             FROM messages
+             WHERE user_id = $1 AND created_at > NOW() - INTERVAL '10 minutes'",
+        )
+        .bind(user.id)
+        .bind(content)
+        .fetch_one(db)
+        .await
+        .map_err(|e| tracing::error!("Spam check activity lookup failed: {}", e))
+        .ok()?;
+
+        let mut score = 0.0;
+        let mut reasons = Vec::new();
+        let mut add = |points: f32, reason: &'static str| {
+            if points > 0.0 {
+                score += points;
+                reasons.push(reason);
+            }
+        };
+
+        add(
+            match activity.last_minute {
+                n if n >= 20 => 0.5,
+                n if n >= 10 => 0.3,
+                n if n >= 5 => 0.15,
+                _ => 0.0,
+            },
+            "rate",
+        );
+
+        if content.chars().count() >= MIN_DUPLICATE_LEN {
+            add(
+                match activity.duplicates {
+                    n if n >= 3 => 0.4,
+                    n if n >= 1 => 0.2,
+                    _ => 0.0,
+                },
+                "duplicate",
+            );
+        }
+
+        // File messages carry an upload URL, which isn't a link someone typed
+        if message_type == "text" {
+            add(Self::link_points(content), "links");
+        }
+
+        let age = Utc::now() - user.created_at;
+        add(
+            if age.num_minutes() < 10 {
+                0.25
+            } else if age.num_hours() < 24 {
+                0.1
+            } else {
+                0.0
+            },
+            "new_account",
+        );
+
+        let score: f32 = score.min(1.0);
+        let action = if score >= self.hide_threshold {
+            Some(SpamAction::Hide)
+        } else if score >= self.flag_threshold {
+            Some(SpamAction::Flag)
+        } else {
+            None
+        };
+
+        Some(SpamVerdict {
+            score,
+            reasons,
+            action,
+        })
+    }
+
+    /// Many links, or a message that is mostly links
+    fn link_points(content: &str) -> f32 {
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let links = words
+            .iter()
+            .filter(|w| {
+                let w = w.to_ascii_lowercase();
+                w.starts_with("http://") || w.starts_with("https://") || w.starts_with("www.")
+            })
+            .count();
+        if links == 0 {
+            return 0.0;
+        }
+
+        let density = links as f32 / words.len() as f32;
+        if links >= 3 || density > 0.5 {
+            0.3
+        } else if density > 0.2 {
+            0.15
+        } else {
+            0.0
+        }
+    }
+}
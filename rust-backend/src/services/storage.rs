@@ -0,0 +1,348 @@
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// Message types that carry an uploaded file
+pub const FILE_MESSAGE_TYPES: [&str; 4] = ["image", "video", "audio", "file"];
+
+/// Unreferenced files younger than this may still be on their way into a
+/// message (upload first, send second), so they never count as orphans
+const ORPHAN_GRACE: Duration = Duration::from_secs(3600);
+
+/// Rooms listed in the storage breakdown
+const TOP_ROOMS: usize = 5;
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeUsage {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomUsage {
+    pub id: Uuid,
+    pub name: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    pub total_files: u64,
+    pub total_bytes: u64,
+    /// Keyed by message type; files not posted in any message are "unattached"
+    pub by_type: BTreeMap<String, TypeUsage>,
+    pub top_rooms: Vec<RoomUsage>,
+    pub orphaned_files: u64,
+    pub orphaned_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanCleanup {
+    pub removed_files: u64,
+    pub removed_bytes: u64,
+}
+
+/// A file saved to UPLOAD_DIR, as described to clients (the `file` object of
+/// `POST /api/upload`)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredUpload {
+    pub url: String,
+    pub filename: String,
+    pub original_name: String,
+    pub mimetype: String,
+    pub size: usize,
+}
+
+struct UploadedFile {
+    name: String,
+    bytes: u64,
+    modified: SystemTime,
+}
+
+/// Files in UPLOAD_DIR: saving new uploads, and disk usage attributed to rooms
+/// and types through the messages that reference each file
+pub struct UploadStorage {
+    upload_dir: PathBuf,
+}
+
+impl UploadStorage {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            upload_dir: config.upload_dir.clone(),
+        }
+    }
+
+    /// Allow broad categories, block dangerous types
+    pub fn check_type(content_type: &str) -> Result<()> {
+        let blocked_types = [
+            "application/x-executable",
+            "application/x-sharedlib",
+            "application/x-mach-binary",
+            "application/x-dosexec",
+            "application/x-msdownload",
+            "application/x-shellscript",
+            "application/x-bat",
+            "application/x-msdos-program",
+        ];
+
+        let is_allowed = content_type.starts_with("image/")
+            || content_type.starts_with("video/")
+            || content_type.starts_with("audio/")
+            || content_type.starts_with("text/")
+            || content_type == "application/pdf"
+            || content_type == "application/msword"
+            || content_type.starts_with("application/vnd.openxmlformats-officedocument.")
+            || content_type.starts_with("application/vnd.ms-")
+            || content_type.starts_with("application/vnd.oasis.opendocument.")
+            || content_type == "application/zip"
+            || content_type == "application/gzip"
+            || content_type == "application/x-tar"
+            || content_type == "application/x-7z-compressed"
+            || content_type == "application/x-rar-compressed"
+            || content_type == "application/octet-stream";
+
+        if !is_allowed || blocked_types.contains(&content_type) {
+            return Err(AppError::Upload(format!(
+                "File type '{}' is not allowed.",
+                content_type
+            )));
+        }
+        Ok(())
+    }
+
+    /// Write an upload under a fresh unique name. Size limits are the
+    /// caller's: multipart uploads and files sent inline over the socket
+    /// have different ones.
+    pub async fn save(
+        &self,
+        original_name: &str,
+        content_type: &str,
+        data: &[u8],
+    ) -> Result<StoredUpload> {
+        use tokio::io::AsyncWriteExt;
+
+        Self::check_type(content_type)?;
+
+        // Generate unique filename with sanitized extension
+        let ext = std::path::Path::new(original_name)
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("bin");
+
+        // Sanitize extension: only allow alphanumeric characters
+        let safe_ext: String = ext
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .take(10)
+            .collect();
+        let safe_ext = if safe_ext.is_empty() {
+            "bin".to_string()
+        } else {
+            safe_ext
+        };
+
+        let unique_filename = format!(
+            "{}-{}.{}",
+            chrono::Utc::now().timestamp_millis(),
+            Uuid::new_v4(),
+            safe_ext
+        );
+
+        // upload_dir is validated and canonicalized at startup (Config::from_env)
+        let file_path = self.upload_dir.join(&unique_filename);
+
+        // Verify the resolved path stays within the upload directory
+        if !file_path.starts_with(&self.upload_dir) {
+            return Err(AppError::Upload("Invalid file path".to_string()));
+        }
+
+        // Write file
+        let mut file = tokio::fs::File::create(&file_path)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create file: {}", e)))?;
+
+        file.write_all(data)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write file: {}", e)))?;
+
+        Ok(StoredUpload {
+            url: format!("/uploads/{}", unique_filename),
+            filename: unique_filename,
+            original_name: original_name.to_string(),
+            mimetype: content_type.to_string(),
+            size: data.len(),
+        })
+    }
+
+    pub async fn report(&self, db: &PgPool) -> Result<StorageReport> {
+        let files = self.list_files().await?;
+        let referenced = Self::referenced_names(db).await?;
+        let posted = Self::posted_files(db).await?;
+
+        let mut report = StorageReport {
+            total_files: 0,
+            total_bytes: 0,
+            by_type: BTreeMap::new(),
+            top_rooms: Vec::new(),
+            orphaned_files: 0,
+            orphaned_bytes: 0,
+        };
+        let mut rooms: HashMap<Uuid, (String, u64, u64)> = HashMap::new();
+
+        for file in &files {
+            report.total_files += 1;
+            report.total_bytes += file.bytes;
+
+            let kind = match posted.get(&file.name) {
+                Some((room_id, room_name, kind)) => {
+                    let room = rooms
+                        .entry(*room_id)
+                        .or_insert_with(|| (room_name.clone(), 0, 0));
+                    room.1 += 1;
+                    room.2 += file.bytes;
+                    kind.as_str()
+                }
+                None => "unattached",
+            };
+            let usage = report.by_type.entry(kind.to_string()).or_default();
+            usage.files += 1;
+            usage.bytes += file.bytes;
+
+            if Self::is_orphan(file, &referenced) {
+                report.orphaned_files += 1;
+                report.orphaned_bytes += file.bytes;
+            }
+        }
+
+        let mut top_rooms: Vec<RoomUsage> = rooms
+            .into_iter()
+            .map(|(id, (name, files, bytes))| RoomUsage {
+                id,
+                name,
+                files,
+                bytes,
+            })
+            .collect();
+        top_rooms.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+        top_rooms.truncate(TOP_ROOMS);
+        report.top_rooms = top_rooms;
+
+        Ok(report)
+    }
+
+    /// Delete uploads nothing references (messages, avatars) any more
+    pub async fn clean_orphans(&self, db: &PgPool) -> Result<OrphanCleanup> {
+        let files = self.list_files().await?;
+        let referenced = Self::referenced_names(db).await?;
+
+        let mut cleanup = OrphanCleanup {
+            removed_files: 0,
+            removed_bytes: 0,
+        };
+        for file in files.iter().filter(|f| Self::is_orphan(f, &referenced)) {
+            match tokio::fs::remove_file(self.upload_dir.join(&file.name)).await {
+                Ok(()) => {
+                    cleanup.removed_files += 1;
+                    cleanup.removed_bytes += file.bytes;
+                }
+                Err(e) => tracing::warn!("Failed to remove orphaned upload {}: {}", file.name, e),
+            }
+        }
+        Ok(cleanup)
+    }
+
+    fn is_orphan(file: &UploadedFile, referenced: &HashSet<String>) -> bool {
+        let old_enough = file
+            .modified
+            .elapsed()
+            .map(|age| age > ORPHAN_GRACE)
+            .unwrap_or(false);
+        old_enough && !referenced.contains(&file.name)
+    }
+
+    async fn list_files(&self) -> Result<Vec<UploadedFile>> {
+        let io_err =
+            |e: std::io::Error| AppError::Internal(format!("Failed to read uploads: {}", e));
+        let mut entries = tokio::fs::read_dir(&self.upload_dir)
+            .await
+            .map_err(io_err)?;
+        let mut files = Vec::new();
+        while let Some(entry) = entries.next_entry().await.map_err(io_err)? {
+            let meta = entry.metadata().await.map_err(io_err)?;
+            if !meta.is_file() {
+                continue;
+            }
+            files.push(UploadedFile {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                bytes: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+        Ok(files)
+    }
+
+    /// File name -> (room id, room name, message type) for posted files
+    async fn posted_files(db: &PgPool) -> Result<HashMap<String, (Uuid, String, String)>> {
+        let rows = sqlx::query_as::<_, (String, Uuid, String, String)>(
+            "SELECT m.content, m.room_id, r.name, m.message_type
+             FROM messages m
+             JOIN rooms r ON r.id = m.room_id
+             WHERE m.message_type = ANY($1) AND m.content LIKE '%/uploads/%'",
+        )
+        .bind(FILE_MESSAGE_TYPES.to_vec())
+        .fetch_all(db)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(content, room_id, room_name, kind)| {
+                Self::upload_names(&content)
+                    .next()
+                    .map(|name| (name, (room_id, room_name, kind)))
+            })
+            .collect())
+    }
+
+    /// Every upload name mentioned anywhere a file can be referenced from:
+    /// message content and metadata, user and room avatars
+    async fn referenced_names(db: &PgPool) -> Result<HashSet<String>> {
+        let texts = sqlx::query_scalar::<_, String>(
+            "SELECT content FROM messages WHERE content LIKE '%/uploads/%'
+             UNION ALL
+             SELECT metadata::text FROM messages WHERE metadata::text LIKE '%/uploads/%'
+             UNION ALL
+             SELECT avatar FROM users WHERE avatar LIKE '%/uploads/%'
+             UNION ALL
+             SELECT avatar FROM rooms WHERE avatar LIKE '%/uploads/%'",
+        )
+        .fetch_all(db)
+        .await?;
+
+        Ok(texts
+            .iter()
+            .flat_map(|text| Self::upload_names(text))
+            .collect())
+    }
+
+    /// File names following each `/uploads/` in a text
+    fn upload_names(text: &str) -> impl Iterator<Item = String> + '_ {
+        text.split("/uploads/").skip(1).filter_map(|rest| {
+            let name: String = rest
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                .collect();
+            (!name.is_empty()).then_some(name)
+        })
+    }
+}
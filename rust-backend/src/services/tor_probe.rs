@@ -0,0 +1,175 @@
+use crate::config::Config;
+use crate::services::TorService;
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_socks::tcp::Socks5Stream;
+
+/// Outcome of the latest self-check
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+    pub ok: bool,
+    /// Address that was dialed, e.g. "abc...xyz.onion:80"
+    pub target: String,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Periodically dials this server's own onion address through the Tor SOCKS
+/// proxy and sends a minimal HTTP request. Catches the "tor daemon died but
+/// the backend keeps running" failure that a local health check can't see.
+pub struct TorProbe {
+    config: Config,
+    interval: Duration,
+    timeout: Duration,
+    last: RwLock<Option<ProbeResult>>,
+    last_success: RwLock<Option<DateTime<Utc>>>,
+    started_at: DateTime<Utc>,
+    successes: AtomicU64,
+    failures: AtomicU64,
+}
+
+impl TorProbe {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.clone(),
+            interval: Duration::from_secs(config.tor_probe_interval),
+            timeout: Duration::from_secs(config.tor_probe_timeout.max(1)),
+            last: RwLock::new(None),
+            last_success: RwLock::new(None),
+            started_at: Utc::now(),
+            successes: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.tor_enabled && !self.interval.is_zero()
+    }
+
+    pub fn last(&self) -> Option<ProbeResult> {
+        self.last.read().unwrap().clone()
+    }
+
+    pub fn last_success(&self) -> Option<DateTime<Utc>> {
+        *self.last_success.read().unwrap()
+    }
+
+    /// When probing began, for how long a probe that never succeeded has been failing
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    pub fn successes(&self) -> u64 {
+        self.successes.load(Ordering::Relaxed)
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Probe forever, once per TOR_PROBE_INTERVAL
+    pub async fn run(&self) {
+        loop {
+            let result = self.probe().await;
+            if result.ok {
+                self.successes.fetch_add(1, Ordering::Relaxed);
+                *self.last_success.write().unwrap() = Some(result.checked_at);
+            } else {
+                self.failures.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!(
+                    "Tor self-check to {} failed: {}",
+                    result.target,
+                    result.error.as_deref().unwrap_or("unknown error")
+                );
+            }
+            *self.last.write().unwrap() = Some(result);
+
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    async fn probe(&self) -> ProbeResult {
+        let checked_at = Utc::now();
+        let Some((host, port)) = self.target().await else {
+            return ProbeResult {
+                ok: false,
+                target: "-".to_string(),
+                latency_ms: None,
+                error: Some("Onion address not known yet".to_string()),
+                checked_at,
+            };
+        };
+        let target = format!("{}:{}", host, port);
+
+        let started = Instant::now();
+        let outcome = tokio::time::timeout(self.timeout, self.request(&host, port)).await;
+        let error = match outcome {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e),
+            Err(_) => Some(format!("Timed out after {}s", self.timeout.as_secs())),
+        };
+
+        ProbeResult {
+            ok: error.is_none(),
+            target,
+            latency_ms: error
+                .is_none()
+                .then(|| started.elapsed().as_millis() as u64),
+            error,
+            checked_at,
+        }
+    }
+
+    /// Our onion host and port: ONION_URL, else the hidden service hostname
+    async fn target(&self) -> Option<(String, u16)> {
+        let raw = match &self.config.onion_url {
+            Some(url) => url.clone(),
+            None => {
+                let host = TorService::new(self.config.clone())
+                    .get_hidden_service_address()
+                    .await?;
+                format!("http://{}", host)
+            }
+        };
+        let url = Url::parse(&raw).ok()?;
+        Some((url.host_str()?.to_string(), url.port_or_known_default()?))
+    }
+
+    /// Any HTTP status line counts: it proves the circuit and the service behind it work
+    async fn request(&self, host: &str, port: u16) -> std::result::Result<(), String> {
+        let proxy = format!(
+            "{}:{}",
+            self.config.tor_socks_host, self.config.tor_socks_port
+        );
+        let mut stream = Socks5Stream::connect(proxy.as_str(), (host, port))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let request = format!(
+            "HEAD / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            host
+        );
+        stream
+            .write_all(request.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut head = [0u8; 12];
+        stream
+            .read_exact(&mut head)
+            .await
+            .map_err(|e| e.to_string())?;
+        if head.starts_with(b"HTTP/") {
+            Ok(())
+        } else {
+            Err("Unexpected response from hidden service".to_string())
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// LibreTranslate on a small box (or behind Tor) can take a while
+const TRANSLATE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Translation {
+    pub translated_text: String,
+    pub detected_language: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibreResponse {
+    translated_text: String,
+    detected_language: Option<LibreDetected>,
+}
+
+#[derive(Deserialize)]
+struct LibreDetected {
+    language: String,
+}
+
+/// Client for the operator's own LibreTranslate instance (TRANSLATE_URL).
+/// Message text only ever goes to that instance; `.onion` instances are
+/// reached through the Tor SOCKS proxy and refused when Tor is disabled.
+pub struct Translator {
+    endpoint: Option<Url>,
+    api_key: Option<String>,
+    client: Option<reqwest::Client>,
+}
+
+impl Translator {
+    pub fn new(config: &Config) -> Self {
+        let endpoint = config.translate_url.as_deref().and_then(|raw| {
+            match Url::parse(&format!("{}/translate", raw)) {
+                Ok(url) => Some(url),
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid TRANSLATE_URL {}: {}", raw, e);
+                    None
+                }
+            }
+        });
+
+        let client = endpoint.as_ref().and_then(|url| {
+            let is_onion = url.host_str().is_some_and(|h| h.ends_with(".onion"));
+            let mut builder = reqwest::Client::builder()
+                .timeout(TRANSLATE_TIMEOUT)
+                .redirect(reqwest::redirect::Policy::none());
+            if is_onion {
+                if !config.tor_enabled {
+                    tracing::warn!("TRANSLATE_URL is an onion address but TOR is disabled");
+                    return None;
+                }
+                // socks5h: the onion address is resolved by Tor
+                let proxy = reqwest::Proxy::all(format!(
+                    "socks5h://{}:{}",
+                    config.tor_socks_host, config.tor_socks_port
+                ))
+                .expect("Invalid TOR SOCKS address");
+                builder = builder.proxy(proxy);
+            }
+            Some(builder.build().expect("Failed to build translate client"))
+        });
+
+        Self {
+            endpoint,
+            api_key: config.translate_api_key.clone(),
+            client,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// Translate `text` into `target`; `source` may be "auto"
+    pub async fn translate(&self, text: &str, source: &str, target: &str) -> Result<Translation> {
+        let (Some(client), Some(endpoint)) = (&self.client, &self.endpoint) else {
+            return Err(AppError::NotFound(
+                "Translation is not enabled on this server".to_string(),
+            ));
+        };
+
+        let mut body = serde_json::json!({
+            "q": text,
+            "source": source,
+            "target": target,
+            "format": "text",
+        });
+        if let Some(key) = &self.api_key {
+            body["api_key"] = serde_json::Value::String(key.clone());
+        }
+
+        let response = client
+            .post(endpoint.clone())
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Translate request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error = response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|v| v["error"].as_str().map(str::to_string))
+                .unwrap_or_else(|| format!("Translator returned {}", status));
+            return Err(if status.is_client_error() {
+                AppError::BadRequest(error)
+            } else {
+                AppError::Internal(error)
+            });
+        }
+
+        let data: LibreResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("Invalid translate response: {}", e)))?;
+
+        Ok(Translation {
+            translated_text: data.translated_text,
+            detected_language: data.detected_language.map(|d| d.language),
+        })
+    }
+}
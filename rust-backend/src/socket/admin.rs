@@ -0,0 +1,72 @@
+use super::handlers::{get_user_from_token, AuthData};
+use crate::state::AppState;
+use socketioxide::extract::{Data, SocketRef};
+use socketioxide::SocketIo;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Namespace streaming live server events to admins
+pub const ADMIN_NAMESPACE: &str = "/admin";
+
+/// Room holding every authenticated admin socket
+const ADMINS_ROOM: &str = "admins";
+
+/// Register the `/admin` namespace. Sockets must send `authenticate` with an
+/// admin's token before they receive anything; everyone else is disconnected.
+pub fn register(io: &SocketIo, state: Arc<AppState>) {
+    io.ns(ADMIN_NAMESPACE, move |socket: SocketRef| {
+        let state = state.clone();
+        async move {
+            socket.on(
+                "authenticate",
+                move |socket: SocketRef, Data(data): Data<AuthData>| {
+                    let state = state.clone();
+                    async move { on_admin_authenticate(socket, data, state).await }
+                },
+            );
+        }
+    });
+}
+
+async fn on_admin_authenticate(socket: SocketRef, data: AuthData, state: Arc<AppState>) {
+    match get_user_from_token(&data.token, &state).await {
+        Some((user_id, user)) if user.is_admin && !user.is_banned => {
+            // Per-user room so a demotion can drop just that admin's sockets
+            socket.join(ADMINS_ROOM);
+            socket.join(user_id.to_string());
+            tracing::info!("Admin {} subscribed to live events", user.username);
+            socket
+                .emit(
+                    "authenticated",
+                    &serde_json::json!({
+                        "userId": user_id,
+                        "username": user.username
+                    }),
+                )
+                .ok();
+        }
+        _ => {
+            socket
+                .emit(
+                    "error",
+                    &serde_json::json!({ "error": "Admin access required" }),
+                )
+                .ok();
+            let _ = socket.disconnect();
+        }
+    }
+}
+
+/// Send a live event to every connected admin
+pub async fn emit(io: &SocketIo, event: &str, data: serde_json::Value) {
+    if let Some(ns) = io.of(ADMIN_NAMESPACE) {
+        ns.to(ADMINS_ROOM).emit(event, &data).await.ok();
+    }
+}
+
+/// Drop a user's admin event sockets, e.g. after they are demoted
+pub async fn disconnect_user(io: &SocketIo, user_id: Uuid) {
+    if let Some(ns) = io.of(ADMIN_NAMESPACE) {
+        ns.to(user_id.to_string()).disconnect().await.ok();
+    }
+}
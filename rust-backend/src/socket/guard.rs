@@ -0,0 +1,412 @@
+use crate::models::User;
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use socketioxide::extract::{Data, SocketRef};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// How long membership and ban lookups are trusted before going back to the database
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Authenticated user attached to a socket, handed to every guarded handler
+#[derive(Debug, Clone)]
+pub struct SocketUser {
+    pub user_id: Uuid,
+    pub user: User,
+    /// Events are accepted but only echoed back to this user
+    pub shadow_banned: bool,
+}
+
+/// Level of room access an event needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomAccess {
+    /// Room member, or a global admin (moderation)
+    Observe,
+    /// Room member
+    Member,
+    /// Room member who is not muted
+    Post,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Membership {
+    pub role: String,
+    pub muted_until: Option<DateTime<Utc>>,
+}
+
+impl Membership {
+    pub fn is_muted(&self) -> bool {
+        self.muted_until
+            .map(|until| until > Utc::now())
+            .unwrap_or(false)
+    }
+}
+
+/// Result of a successful room check
+#[derive(Debug, Clone)]
+pub struct RoomGrant {
+    pub membership: Option<Membership>,
+    pub is_global_admin: bool,
+}
+
+impl RoomGrant {
+    /// Room admins and global admins may moderate (pin, unpin, ...)
+    pub fn can_moderate(&self) -> bool {
+        self.is_global_admin
+            || self
+                .membership
+                .as_ref()
+                .map(|m| m.role == "admin")
+                .unwrap_or(false)
+    }
+}
+
+/// Account flags re-checked on every guarded event
+#[derive(Debug, Clone, Copy, sqlx::FromRow)]
+struct Standing {
+    is_banned: bool,
+    is_shadow_banned: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardError {
+    NotAuthenticated,
+    Banned,
+    InvalidId,
+    NotMember,
+    Muted,
+    Maintenance,
+    GuestExpired,
+    GuestNotAllowed,
+}
+
+impl GuardError {
+    pub fn message(self) -> &'static str {
+        match self {
+            GuardError::NotAuthenticated => "Not authenticated",
+            GuardError::Banned => "Your account has been banned",
+            GuardError::InvalidId => "Invalid ID",
+            GuardError::NotMember => "Not a member of this room",
+            GuardError::Muted => "You are muted in this room",
+            GuardError::Maintenance => "Server is in read-only maintenance mode",
+            GuardError::GuestExpired => "Your guest session has expired",
+            GuardError::GuestNotAllowed => "Guests can't do that on this server",
+        }
+    }
+
+    pub fn emit(self, socket: &SocketRef) {
+        socket
+            .emit("error", &serde_json::json!({ "error": self.message() }))
+            .ok();
+    }
+}
+
+/// Short-lived cache of membership rows and ban flags used by the guard.
+/// REST handlers that change either must invalidate the affected entries.
+/// Expired entries are swept out on insert, at most once per TTL, so the
+/// maps only hold what was looked up recently.
+pub struct GuardCache {
+    memberships: RwLock<HashMap<(Uuid, Uuid), (Option<Membership>, Instant)>>,
+    bans: RwLock<HashMap<Uuid, (Standing, Instant)>>,
+    last_sweep: Mutex<Instant>,
+}
+
+impl Default for GuardCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GuardCache {
+    pub fn new() -> Self {
+        Self {
+            memberships: RwLock::new(HashMap::new()),
+            bans: RwLock::new(HashMap::new()),
+            last_sweep: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn insert_membership(&self, key: (Uuid, Uuid), membership: Option<Membership>) {
+        let mut memberships = self.memberships.write().await;
+        if self.sweep_due() {
+            sweep(&mut memberships);
+            sweep(&mut *self.bans.write().await);
+        }
+        memberships.insert(key, (membership, Instant::now()));
+    }
+
+    async fn insert_standing(&self, user_id: Uuid, standing: Standing) {
+        if self.sweep_due() {
+            sweep(&mut *self.memberships.write().await);
+            sweep(&mut *self.bans.write().await);
+        }
+        self.bans
+            .write()
+            .await
+            .insert(user_id, (standing, Instant::now()));
+    }
+
+    /// True once per TTL, for the caller to sweep
+    fn sweep_due(&self) -> bool {
+        let mut last_sweep = self.last_sweep.lock().unwrap();
+        if last_sweep.elapsed() < CACHE_TTL {
+            return false;
+        }
+        *last_sweep = Instant::now();
+        true
+    }
+
+    pub async fn invalidate_membership(&self, room_id: Uuid, user_id: Uuid) {
+        self.memberships.write().await.remove(&(room_id, user_id));
+    }
+
+    pub async fn invalidate_room(&self, room_id: Uuid) {
+        self.memberships
+            .write()
+            .await
+            .retain(|(room, _), _| *room != room_id);
+    }
+
+    pub async fn invalidate_user(&self, user_id: Uuid) {
+        self.bans.write().await.remove(&user_id);
+        self.memberships
+            .write()
+            .await
+            .retain(|(_, user), _| *user != user_id);
+    }
+}
+
+fn sweep<K: Eq + Hash, V>(map: &mut HashMap<K, (V, Instant)>) {
+    map.retain(|_, (_, at)| at.elapsed() < CACHE_TTL);
+}
+
+/// Register a socket event whose handler only runs for an authenticated,
+/// non-banned user. All events except `authenticate` go through here so a
+/// new event can't skip the check.
+pub fn on_guarded<T, F, Fut>(
+    socket: &SocketRef,
+    state: &Arc<AppState>,
+    event: &'static str,
+    handler: F,
+) where
+    T: DeserializeOwned + Send + Sync + 'static,
+    F: Fn(SocketRef, T, Arc<AppState>, SocketUser) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    register_guarded(socket, state, event, handler, false);
+}
+
+/// Like [`on_guarded`], for events that write (messages, reactions, pins, ...).
+/// These are refused while the server is in read-only maintenance mode.
+pub fn on_guarded_write<T, F, Fut>(
+    socket: &SocketRef,
+    state: &Arc<AppState>,
+    event: &'static str,
+    handler: F,
+) where
+    T: DeserializeOwned + Send + Sync + 'static,
+    F: Fn(SocketRef, T, Arc<AppState>, SocketUser) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    register_guarded(socket, state, event, handler, true);
+}
+
+fn register_guarded<T, F, Fut>(
+    socket: &SocketRef,
+    state: &Arc<AppState>,
+    event: &'static str,
+    handler: F,
+    writes: bool,
+) where
+    T: DeserializeOwned + Send + Sync + 'static,
+    F: Fn(SocketRef, T, Arc<AppState>, SocketUser) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let state = state.clone();
+    socket.on(event, move |socket: SocketRef, Data(data): Data<T>| {
+        let state = state.clone();
+        let handler = handler.clone();
+        async move {
+            if writes && state.maintenance.is_active() {
+                GuardError::Maintenance.emit(&socket);
+                return;
+            }
+            match require_user(&socket, &state).await {
+                Ok(user) if user.user.is_guest() => {
+                    match guest_check(&state, &user, event, writes) {
+                        Ok(()) => handler(socket, data, state, user).await,
+                        Err(GuardError::GuestExpired) => {
+                            GuardError::GuestExpired.emit(&socket);
+                            let _ = socket.disconnect();
+                        }
+                        Err(e) => e.emit(&socket),
+                    }
+                }
+                Ok(user) => handler(socket, data, state, user).await,
+                Err(GuardError::Banned) => {
+                    GuardError::Banned.emit(&socket);
+                    let _ = socket.disconnect();
+                }
+                Err(e) => e.emit(&socket),
+            }
+        }
+    });
+}
+
+/// Guests may follow their rooms and, when posting is allowed, send
+/// messages; every other write is refused, and nothing works once the
+/// session has ended
+fn guest_check(
+    state: &AppState,
+    user: &SocketUser,
+    event: &str,
+    writes: bool,
+) -> Result<(), GuardError> {
+    if user.user.is_expired_guest() {
+        return Err(GuardError::GuestExpired);
+    }
+    let guests = state.settings.guest_access();
+    if !guests.enabled || (writes && !(guests.can_post && event == "send_message")) {
+        return Err(GuardError::GuestNotAllowed);
+    }
+    Ok(())
+}
+
+/// Resolve the socket's user and make sure the account is still in good standing
+pub async fn require_user(socket: &SocketRef, state: &AppState) -> Result<SocketUser, GuardError> {
+    let (user_id, user) = state
+        .get_socket_user(&socket.id.to_string())
+        .await
+        .ok_or(GuardError::NotAuthenticated)?;
+
+    let standing = standing(state, user_id).await?;
+    if standing.is_banned {
+        return Err(GuardError::Banned);
+    }
+
+    Ok(SocketUser {
+        user_id,
+        user,
+        shadow_banned: standing.is_shadow_banned,
+    })
+}
+
+/// Check room access, emitting an error to the socket on failure
+pub async fn check_room(
+    socket: &SocketRef,
+    state: &AppState,
+    user: &SocketUser,
+    raw_room_id: &str,
+    access: RoomAccess,
+) -> Option<(Uuid, RoomGrant)> {
+    let room_id = match Uuid::parse_str(raw_room_id) {
+        Ok(id) => id,
+        Err(_) => {
+            GuardError::InvalidId.emit(socket);
+            return None;
+        }
+    };
+
+    check_room_id(socket, state, user, room_id, access)
+        .await
+        .map(|grant| (room_id, grant))
+}
+
+/// Same as [`check_room`] for an already parsed room ID
+pub async fn check_room_id(
+    socket: &SocketRef,
+    state: &AppState,
+    user: &SocketUser,
+    room_id: Uuid,
+    access: RoomAccess,
+) -> Option<RoomGrant> {
+    match require_room(state, user, room_id, access).await {
+        Ok(grant) => Some(grant),
+        Err(e) => {
+            e.emit(socket);
+            None
+        }
+    }
+}
+
+pub async fn require_room(
+    state: &AppState,
+    user: &SocketUser,
+    room_id: Uuid,
+    access: RoomAccess,
+) -> Result<RoomGrant, GuardError> {
+    let membership = membership(state, room_id, user.user_id).await;
+
+    match (&membership, access) {
+        (None, RoomAccess::Observe) if user.user.is_admin => {}
+        (None, _) => return Err(GuardError::NotMember),
+        (Some(m), RoomAccess::Post) if m.is_muted() => return Err(GuardError::Muted),
+        _ => {}
+    }
+
+    Ok(RoomGrant {
+        membership,
+        is_global_admin: user.user.is_admin,
+    })
+}
+
+async fn membership(state: &AppState, room_id: Uuid, user_id: Uuid) -> Option<Membership> {
+    let key = (room_id, user_id);
+    if let Some((cached, at)) = state.guard_cache.memberships.read().await.get(&key) {
+        if at.elapsed() < CACHE_TTL {
+            return cached.clone();
+        }
+    }
+
+    let fetched = match sqlx::query_as::<_, Membership>(
+        "SELECT role, muted_until FROM room_members WHERE room_id = $1 AND user_id = $2",
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(m) => m,
+        Err(e) => {
+            tracing::error!("Membership lookup failed: {}", e);
+            return None;
+        }
+    };
+
+    state
+        .guard_cache
+        .insert_membership(key, fetched.clone())
+        .await;
+
+    fetched
+}
+
+async fn standing(state: &AppState, user_id: Uuid) -> Result<Standing, GuardError> {
+    if let Some((standing, at)) = state.guard_cache.bans.read().await.get(&user_id) {
+        if at.elapsed() < CACHE_TTL {
+            return Ok(*standing);
+        }
+    }
+
+    let standing = sqlx::query_as::<_, Standing>(
+        "SELECT is_banned, is_shadow_banned FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Ban lookup failed: {}", e);
+        GuardError::NotAuthenticated
+    })?
+    .ok_or(GuardError::NotAuthenticated)?;
+
+    state.guard_cache.insert_standing(user_id, standing).await;
+
+    Ok(standing)
+}
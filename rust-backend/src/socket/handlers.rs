@@ -1,19 +1,17 @@
-use crate::models::{Message, Room, User};
-use crate::services::AuthService;
-use crate::state::AppState;
+use super::guard::{self, GuardError, RoomAccess, SocketUser};
+use crate::error::AppError;
+use crate::models::{ContentPolicy, Message, MessageType, ReactionGroup, Room, User};
+use crate::services::{AuthService, MentionScope, StoredUpload, UploadStorage};
+use crate::state::{AppState, Outgoing};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use serde::{Deserialize, Serialize};
-use socketioxide::extract::{Data, SocketRef};
+use socketioxide::extract::SocketRef;
 use std::sync::Arc;
 use uuid::Uuid;
 
-// Helper to get user info from socket
-async fn get_socket_user_info(socket: &SocketRef, state: &AppState) -> Option<(Uuid, User)> {
-    state.get_socket_user(&socket.id.to_string()).await
-}
-
 #[derive(Debug, Deserialize)]
 pub struct AuthData {
-    token: String,
+    pub token: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,12 +30,30 @@ pub struct LeaveRoomData {
 pub struct SendMessageData {
     #[serde(rename = "roomId")]
     room_id: String,
-    content: String,
+    /// Required unless `attachment` is set
+    content: Option<String>,
     #[serde(rename = "messageType")]
     message_type: Option<String>,
     #[serde(rename = "replyTo")]
     reply_to: Option<String>,
     metadata: Option<serde_json::Value>,
+    #[serde(rename = "clientNonce")]
+    client_nonce: Option<String>,
+    #[serde(rename = "expiresIn")]
+    expires_in: Option<i32>,
+    #[serde(rename = "expireAfterRead")]
+    expire_after_read: Option<bool>,
+    attachment: Option<InlineAttachment>,
+}
+
+/// A small file sent with `send_message` instead of through
+/// `POST /api/upload`, saving a round trip; up to SOCKET_UPLOAD_MAX_SIZE
+#[derive(Debug, Deserialize)]
+pub struct InlineAttachment {
+    name: String,
+    mimetype: String,
+    /// File contents, base64
+    data: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -95,7 +111,7 @@ pub struct ErrorResponse {
 }
 
 // Helper to get user from token
-async fn get_user_from_token(token: &str, state: &AppState) -> Option<(Uuid, User)> {
+pub(super) async fn get_user_from_token(token: &str, state: &AppState) -> Option<(Uuid, User)> {
     let auth_service = AuthService::new(state.config.clone());
     let user_id = auth_service.verify_token(token).ok()?;
 
@@ -108,35 +124,39 @@ async fn get_user_from_token(token: &str, state: &AppState) -> Option<(Uuid, Use
     Some((user_id, user))
 }
 
-// Helper to check room membership
-async fn check_room_membership(room_id: Uuid, user_id: Uuid, state: &AppState) -> bool {
-    sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM room_members WHERE room_id = $1 AND user_id = $2)",
-    )
-    .bind(room_id)
-    .bind(user_id)
-    .fetch_one(&state.db)
-    .await
-    .unwrap_or(false)
-}
-
 // 1. authenticate - Handle socket authentication
 pub async fn on_authenticate(socket: SocketRef, data: AuthData, state: Arc<AppState>) {
     match get_user_from_token(&data.token, &state).await {
+        Some((_, user)) if user.is_banned => {
+            GuardError::Banned.emit(&socket);
+            let _ = socket.disconnect();
+        }
         Some((user_id, user)) => {
             // Associate socket with user
             state
                 .associate_socket_user(socket.id.to_string(), user_id, user.clone())
                 .await;
 
-            // Track socket connection
-            state.add_user_socket(user_id, socket.id.to_string()).await;
+            // Track socket connection; the user comes online with their
+            // first one (presence isn't tracked in paranoid mode)
+            let came_online = state.add_user_socket(user_id, socket.id.to_string()).await;
+            socket.join(crate::state::user_room(user_id));
 
-            // Update user online status
-            let _ = sqlx::query("UPDATE users SET is_online = true WHERE id = $1")
-                .bind(user_id)
-                .execute(&state.db)
-                .await;
+            // Receive every member room's messages from the start, so unread
+            // badges update for rooms that aren't open
+            match sqlx::query_scalar::<_, Uuid>(
+                "SELECT room_id FROM room_members WHERE user_id = $1",
+            )
+            .bind(user_id)
+            .fetch_all(&state.db)
+            .await
+            {
+                Ok(room_ids) => {
+                    socket.join(room_ids.iter().map(Uuid::to_string).collect::<Vec<_>>());
+                }
+                Err(e) => tracing::error!("Failed to load rooms of user {}: {}", user_id, e),
+            }
+            let paranoid = state.config.paranoid_mode;
 
             tracing::info!(
                 "User {} authenticated on socket {}",
@@ -149,23 +169,31 @@ pub async fn on_authenticate(socket: SocketRef, data: AuthData, state: Arc<AppSt
                     "authenticated",
                     &serde_json::json!({
                         "userId": user_id,
-                        "username": user.username
+                        "username": user.username,
+                        "serverTime": chrono::Utc::now().timestamp_millis(),
                     }),
                 )
                 .ok();
 
+            // Late joiners still get the read-only banner
+            if state.maintenance.is_active() {
+                socket.emit("maintenance", &state.maintenance.status()).ok();
+            }
+
             // Broadcast user online to all sockets
-            socket
-                .broadcast()
-                .emit(
-                    "user_online",
-                    &serde_json::json!({
-                        "userId": user_id,
-                        "username": user.username
-                    }),
-                )
-                .await
-                .ok();
+            if came_online && !paranoid {
+                socket
+                    .broadcast()
+                    .emit(
+                        "user_online",
+                        &serde_json::json!({
+                            "userId": user_id,
+                            "username": user.username
+                        }),
+                    )
+                    .await
+                    .ok();
+            }
         }
         None => {
             socket
@@ -182,54 +210,23 @@ pub async fn on_authenticate(socket: SocketRef, data: AuthData, state: Arc<AppSt
 }
 
 // 2. join_room - Join a room
-pub async fn on_join_room(socket: SocketRef, data: JoinRoomData, state: Arc<AppState>) {
-    let (user_id, user) = match get_socket_user_info(&socket, &state).await {
-        Some((id, u)) => (id, u),
-        None => {
-            socket
-                .emit(
-                    "error",
-                    &ErrorResponse {
-                        error: "Not authenticated".to_string(),
-                    },
-                )
-                .ok();
-            return;
-        }
-    };
-
-    let room_id = match Uuid::parse_str(&data.room_id) {
-        Ok(id) => id,
-        Err(_) => {
-            socket
-                .emit(
-                    "error",
-                    &ErrorResponse {
-                        error: "Invalid room ID".to_string(),
-                    },
-                )
-                .ok();
-            return;
-        }
-    };
-
+pub async fn on_join_room(
+    socket: SocketRef,
+    data: JoinRoomData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
     // Global admins can join any room socket for moderation
-    if !user.is_admin && !check_room_membership(room_id, user_id, &state).await {
-        socket
-            .emit(
-                "error",
-                &ErrorResponse {
-                    error: "Not a member of this room".to_string(),
-                },
-            )
-            .ok();
-        return;
-    }
+    let room_id =
+        match guard::check_room(&socket, &state, &user, &data.room_id, RoomAccess::Observe).await {
+            Some((id, _)) => id,
+            None => return,
+        };
 
     // Join socket room
     socket.join(data.room_id.clone());
 
-    tracing::info!("User {} joined room {}", user_id, room_id);
+    tracing::info!("User {} joined room {}", user.user_id, room_id);
 
     socket
         .emit(
@@ -242,7 +239,12 @@ pub async fn on_join_room(socket: SocketRef, data: JoinRoomData, state: Arc<AppS
 }
 
 // 3. leave_room - Leave a room
-pub async fn on_leave_room(socket: SocketRef, Data(data): Data<LeaveRoomData>) {
+pub async fn on_leave_room(
+    socket: SocketRef,
+    data: LeaveRoomData,
+    _state: Arc<AppState>,
+    _user: SocketUser,
+) {
     socket.leave(data.room_id.clone());
 
     socket
@@ -256,45 +258,162 @@ pub async fn on_leave_room(socket: SocketRef, Data(data): Data<LeaveRoomData>) {
 }
 
 // 4. send_message - Send a message to a room
-pub async fn on_send_message(socket: SocketRef, data: SendMessageData, state: Arc<AppState>) {
-    let (user_id, user) = match get_socket_user_info(&socket, &state).await {
-        Some((id, u)) => (id, u),
-        None => return,
+pub async fn on_send_message(
+    socket: SocketRef,
+    data: SendMessageData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
+    let (room_id, grant) =
+        match guard::check_room(&socket, &state, &user, &data.room_id, RoomAccess::Post).await {
+            Some(checked) => checked,
+            None => return,
+        };
+    let SocketUser {
+        user_id,
+        user,
+        shadow_banned,
+    } = user;
+
+    if let Err(error) = Message::check_expires_in(data.expires_in) {
+        socket.emit("error", &ErrorResponse { error }).ok();
+        return;
+    }
+    let expires_after_read = data.expires_in.is_some() && data.expire_after_read.unwrap_or(true);
+
+    let policy = match content_policy(&state, room_id).await {
+        Some(policy) => policy,
+        None => {
+            socket
+                .emit(
+                    "error",
+                    &ErrorResponse {
+                        error: "Failed to send message".to_string(),
+                    },
+                )
+                .ok();
+            return;
+        }
     };
 
-    let room_id = match Uuid::parse_str(&data.room_id) {
-        Ok(id) => id,
-        Err(_) => return,
+    // An inline attachment goes through the same checks and storage as
+    // uploads, then is posted like any file message
+    let (content, message_type, metadata) = match data.attachment {
+        Some(attachment) => {
+            let message_type = data
+                .message_type
+                .and_then(|t| t.parse::<MessageType>().ok())
+                .filter(|t| t.is_file())
+                .unwrap_or_else(|| file_message_type(&attachment.mimetype));
+            // Don't store a file the message would be refused for
+            let refused = if !state.config.message_types.contains(&message_type) {
+                Err(format!(
+                    "This server doesn't accept {} messages",
+                    message_type
+                ))
+            } else {
+                let file = serde_json::json!({ "mimetype": attachment.mimetype });
+                policy.check(message_type, Some(&file))
+            };
+            if let Err(error) = refused {
+                socket.emit("error", &ErrorResponse { error }).ok();
+                return;
+            }
+            let file = match save_attachment(&state, &attachment).await {
+                Ok(file) => file,
+                Err(error) => {
+                    socket.emit("error", &ErrorResponse { error }).ok();
+                    return;
+                }
+            };
+            tracing::info!(
+                "File uploaded by user {} with a message: {}",
+                user_id,
+                file.filename
+            );
+            let mut metadata = data
+                .metadata
+                .filter(|m| m.is_object())
+                .unwrap_or_else(|| serde_json::json!({}));
+            metadata["originalName"] = serde_json::json!(file.original_name);
+            metadata["mimetype"] = serde_json::json!(file.mimetype);
+            metadata["size"] = serde_json::json!(file.size);
+            (file.url, Some(message_type.to_string()), Some(metadata))
+        }
+        None => {
+            let Some(content) = data.content else {
+                socket
+                    .emit(
+                        "error",
+                        &ErrorResponse {
+                            error: "Message content is required".to_string(),
+                        },
+                    )
+                    .ok();
+                return;
+            };
+            (content, data.message_type, data.metadata)
+        }
+    };
+    let message_type = match Message::check_type(
+        message_type.as_deref(),
+        &content,
+        metadata.as_ref(),
+        &state.config.message_types,
+    )
+    .and_then(|t| policy.check(t, metadata.as_ref()).map(|()| t))
+    {
+        Ok(message_type) => message_type,
+        Err(error) => {
+            socket.emit("error", &ErrorResponse { error }).ok();
+            return;
+        }
     };
 
-    // Check membership
-    if !check_room_membership(room_id, user_id, &state).await {
-        socket
-            .emit(
-                "error",
-                &ErrorResponse {
-                    error: "Not a member of this room".to_string(),
-                },
-            )
-            .ok();
-        return;
+    // @room and @here notify everyone, so only room admins get them
+    let mention = match message_type {
+        MessageType::Text => MentionScope::find(&content),
+        _ => None,
+    };
+    if mention.is_some() {
+        if let Err(error) = state
+            .room_mentions
+            .check(room_id, grant.can_moderate())
+            .await
+        {
+            socket.emit("error", &ErrorResponse { error }).ok();
+            return;
+        }
     }
 
     let reply_to = data.reply_to.and_then(|s| Uuid::parse_str(&s).ok());
-    let message_type = data.message_type.unwrap_or_else(|| "text".to_string());
+    let spam = state
+        .spam_filter
+        .check(&state.db, room_id, &user, &content, message_type.as_str())
+        .await;
 
-    // Create message
+    // Create message; a fixed lifetime counts from now, burn-after-reading
+    // waits for a read receipt
     let message = match sqlx::query_as::<_, Message>(
-        "INSERT INTO messages (room_id, user_id, content, message_type, reply_to, metadata)
-         VALUES ($1, $2, $3, $4, $5, $6)
+        "INSERT INTO messages (room_id, user_id, content, message_type, reply_to, metadata,
+                               spam_score, spam_reasons, spam_action, key_epoch,
+                               expires_in, expires_after_read, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9,
+                 (SELECT key_epoch FROM rooms WHERE id = $1 AND encryption_mode = 'e2ee'),
+                 $10, $11, CASE WHEN NOT $11 THEN NOW() + $10 * INTERVAL '1 second' END)
          RETURNING *",
     )
     .bind(room_id)
     .bind(user_id)
-    .bind(&data.content)
-    .bind(&message_type)
+    .bind(&content)
+    .bind(message_type.as_str())
     .bind(reply_to)
-    .bind(&data.metadata)
+    .bind(&metadata)
+    .bind(spam.as_ref().map(|s| s.score))
+    .bind(spam.as_ref().map(|s| serde_json::json!(s.reasons)))
+    .bind(spam.as_ref().and_then(|s| s.action_str()))
+    .bind(data.expires_in)
+    .bind(expires_after_read)
     .fetch_one(&state.db)
     .await
     {
@@ -313,12 +432,22 @@ pub async fn on_send_message(socket: SocketRef, data: SendMessageData, state: Ar
         }
     };
 
-    // Fetch reply message if reply_to is set
+    // Fetch reply message if reply_to is set. It goes out to the whole room,
+    // so only a message from this room that everyone there can see; in
+    // history-gated rooms newer members may not, and clients load it later.
     let reply_message_json = if let Some(reply_id) = message.reply_to {
-        if let Ok(reply_msg) = sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = $1")
-            .bind(reply_id)
-            .fetch_one(&state.db)
-            .await
+        if let Ok(Some(reply_msg)) = sqlx::query_as::<_, Message>(
+            "SELECT * FROM messages
+             WHERE id = $1 AND room_id = $2
+               AND spam_action IS DISTINCT FROM 'hidden'
+               AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned)
+               AND (expires_at IS NULL OR expires_at > NOW())
+               AND NOT EXISTS (SELECT 1 FROM rooms r WHERE r.id = $2 AND r.history_gate_minutes IS NOT NULL)",
+        )
+        .bind(reply_id)
+        .bind(room_id)
+        .fetch_optional(&state.db)
+        .await
         {
             let reply_user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
                 .bind(reply_msg.user_id)
@@ -344,6 +473,11 @@ pub async fn on_send_message(socket: SocketRef, data: SendMessageData, state: Ar
         None
     };
 
+    let client_nonce: Option<String> = data
+        .client_nonce
+        .as_deref()
+        .map(|n| n.chars().take(64).collect());
+
     let message_response = serde_json::json!({
         "id": message.id,
         "roomId": message.room_id,
@@ -352,13 +486,20 @@ pub async fn on_send_message(socket: SocketRef, data: SendMessageData, state: Ar
         "messageType": message.message_type,
         "replyTo": message.reply_to,
         "forwardedFrom": message.forwarded_from,
-        "reactions": message.reactions,
+        "reactions": {},
         "metadata": message.metadata,
         "createdAt": message.created_at,
         "updatedAt": message.updated_at,
         "pinnedBy": message.pinned_by,
         "pinnedAt": message.pinned_at,
+        "keyEpoch": message.key_epoch,
+        "legacyPlaintext": message.legacy_plaintext,
+        "revisionCount": message.revision_count,
+        "expiresIn": message.expires_in,
+        "expiresAfterRead": message.expires_after_read,
+        "expiresAt": message.expires_at,
         "replyMessage": reply_message_json,
+        "clientNonce": client_nonce,
         "user": {
             "id": user.id,
             "username": user.username,
@@ -368,30 +509,107 @@ pub async fn on_send_message(socket: SocketRef, data: SendMessageData, state: Ar
         }
     });
 
-    // Broadcast to room (within() should include sender per docs, but
-    // also emit directly to sender as a safety net — client deduplicates)
-    socket
-        .within(data.room_id)
-        .emit("new_message", &message_response)
-        .await
-        .ok();
-    socket.emit("new_message", &message_response).ok();
+    let outgoing = Outgoing {
+        room_id,
+        message_id: message.id,
+        sender: &user,
+        shadow_banned,
+        spam: spam.as_ref(),
+        mention,
+    };
+    // The room includes this socket, but emit to it directly as well as a
+    // safety net; clients deduplicate
+    if state.deliver_message(&message_response, outgoing).await {
+        socket.emit("new_message", &message_response).ok();
+    }
 }
 
-// 5. typing - Indicate typing status
-pub async fn on_typing(socket: SocketRef, data: TypingData, state: Arc<AppState>) {
-    let (user_id, user) = match get_socket_user_info(&socket, &state).await {
-        Some((id, u)) => (id, u),
-        None => return,
-    };
+/// The room's content policy; None (logged) if it couldn't be read
+async fn content_policy(state: &AppState, room_id: Uuid) -> Option<ContentPolicy> {
+    sqlx::query_as::<_, ContentPolicy>(&format!(
+        "SELECT {} FROM rooms WHERE id = $1",
+        ContentPolicy::COLUMNS
+    ))
+    .bind(room_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| tracing::error!("Failed to load content policy of room {}: {}", room_id, e))
+    .ok()
+}
 
-    let room_id = match Uuid::parse_str(&data.room_id) {
-        Ok(id) => id,
-        Err(_) => return,
+/// Decode and store an inline attachment; the error is for the sender
+async fn save_attachment(
+    state: &AppState,
+    attachment: &InlineAttachment,
+) -> Result<StoredUpload, String> {
+    let limit = state
+        .config
+        .socket_upload_max_size
+        .min(state.config.max_file_size);
+    if limit == 0 {
+        return Err(
+            "This server doesn't take attachments with messages, upload the file instead"
+                .to_string(),
+        );
+    }
+    let too_large = || {
+        format!(
+            "Attachment too large to send with a message (max {} bytes), upload it instead",
+            limit
+        )
     };
+    // Rule out oversized files before decoding them
+    if attachment.data.len() > limit.div_ceil(3) * 4 {
+        return Err(too_large());
+    }
+    let bytes = BASE64
+        .decode(&attachment.data)
+        .map_err(|_| "Attachment data must be base64".to_string())?;
+    if bytes.len() > limit {
+        return Err(too_large());
+    }
+
+    UploadStorage::new(&state.config)
+        .save(&attachment.name, &attachment.mimetype, &bytes)
+        .await
+        .map_err(|e| match e {
+            AppError::Upload(message) => message,
+            e => {
+                tracing::error!("Failed to save attachment: {}", e);
+                "Failed to save attachment".to_string()
+            }
+        })
+}
+
+/// Message type for a file, from its MIME type
+fn file_message_type(mimetype: &str) -> MessageType {
+    match mimetype.split('/').next() {
+        Some("image") => MessageType::Image,
+        Some("video") => MessageType::Video,
+        Some("audio") => MessageType::Audio,
+        _ => MessageType::File,
+    }
+}
 
-    // Check membership
-    if !check_room_membership(room_id, user_id, &state).await {
+// 5. typing - Indicate typing status
+pub async fn on_typing(
+    socket: SocketRef,
+    data: TypingData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
+    if guard::check_room(&socket, &state, &user, &data.room_id, RoomAccess::Post)
+        .await
+        .is_none()
+    {
+        return;
+    }
+    let SocketUser {
+        user_id,
+        user,
+        shadow_banned,
+    } = user;
+    if shadow_banned {
         return;
     }
 
@@ -413,11 +631,13 @@ pub async fn on_typing(socket: SocketRef, data: TypingData, state: Arc<AppState>
 }
 
 // 6. add_reaction - Add reaction to a message
-pub async fn on_add_reaction(socket: SocketRef, data: ReactionData, state: Arc<AppState>) {
-    let user_id = match get_socket_user_info(&socket, &state).await {
-        Some((id, _)) => id,
-        None => return,
-    };
+pub async fn on_add_reaction(
+    socket: SocketRef,
+    data: ReactionData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
+    let user_id = user.user_id;
 
     let message_id = match Uuid::parse_str(&data.message_id) {
         Ok(id) => id,
@@ -434,50 +654,52 @@ pub async fn on_add_reaction(socket: SocketRef, data: ReactionData, state: Arc<A
         _ => return,
     };
 
-    if !check_room_membership(message.room_id, user_id, &state).await {
+    if guard::check_room_id(&socket, &state, &user, message.room_id, RoomAccess::Post)
+        .await
+        .is_none()
+    {
         return;
     }
 
-    // Update reactions (assuming reactions is JSONB)
-    let mut reactions: serde_json::Value = message.reactions;
-    if let Some(obj) = reactions.as_object_mut() {
-        let emoji_key = data.emoji.clone();
-        let users = obj.entry(emoji_key).or_insert(serde_json::json!([]));
-        if let Some(arr) = users.as_array_mut() {
-            let user_id_str = user_id.to_string();
-            if !arr.iter().any(|v| v.as_str() == Some(&user_id_str)) {
-                arr.push(serde_json::json!(user_id_str));
-            }
+    let emoji = match ReactionGroup::check_emoji(&data.emoji) {
+        Ok(emoji) => emoji,
+        Err(error) => {
+            socket.emit("error", &ErrorResponse { error }).ok();
+            return;
         }
-    }
+    };
 
-    let _ = sqlx::query("UPDATE messages SET reactions = $1 WHERE id = $2")
-        .bind(&reactions)
-        .bind(message_id)
-        .execute(&state.db)
-        .await;
+    // One row per user and emoji: concurrent reactions can't overwrite each
+    // other, and repeating one changes nothing
+    let inserted = sqlx::query(
+        "INSERT INTO message_reactions (message_id, user_id, emoji) VALUES ($1, $2, $3)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(message_id)
+    .bind(user_id)
+    .bind(emoji)
+    .execute(&state.db)
+    .await;
+    match inserted {
+        Ok(result) if result.rows_affected() > 0 => {}
+        Ok(_) => return,
+        Err(e) => {
+            tracing::error!("Failed to add reaction: {}", e);
+            return;
+        }
+    }
 
-    // Broadcast reaction to room
-    let reaction_response = serde_json::json!({
-        "messageId": message_id,
-        "userId": user_id,
-        "emoji": data.emoji,
-        "reactions": reactions
-    });
-    socket
-        .within(message.room_id.to_string())
-        .emit("reaction_added", &reaction_response)
-        .await
-        .ok();
-    socket.emit("reaction_added", &reaction_response).ok();
+    emit_reaction_delta(&socket, &state, &user, &message, emoji, "reaction_added").await;
 }
 
 // 7. remove_reaction - Remove reaction from a message
-pub async fn on_remove_reaction(socket: SocketRef, data: ReactionData, state: Arc<AppState>) {
-    let user_id = match get_socket_user_info(&socket, &state).await {
-        Some((id, _)) => id,
-        None => return,
-    };
+pub async fn on_remove_reaction(
+    socket: SocketRef,
+    data: ReactionData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
+    let user_id = user.user_id;
 
     let message_id = match Uuid::parse_str(&data.message_id) {
         Ok(id) => id,
@@ -493,49 +715,78 @@ pub async fn on_remove_reaction(socket: SocketRef, data: ReactionData, state: Ar
         _ => return,
     };
 
-    if !check_room_membership(message.room_id, user_id, &state).await {
+    if guard::check_room_id(&socket, &state, &user, message.room_id, RoomAccess::Post)
+        .await
+        .is_none()
+    {
         return;
     }
 
-    let mut reactions: serde_json::Value = message.reactions;
-    if let Some(obj) = reactions.as_object_mut() {
-        if let Some(users) = obj.get_mut(&data.emoji) {
-            if let Some(arr) = users.as_array_mut() {
-                let user_id_str = user_id.to_string();
-                arr.retain(|v| v.as_str() != Some(&user_id_str));
-                if arr.is_empty() {
-                    obj.remove(&data.emoji);
-                }
-            }
+    let emoji = data.emoji.trim();
+    let removed = sqlx::query(
+        "DELETE FROM message_reactions WHERE message_id = $1 AND user_id = $2 AND emoji = $3",
+    )
+    .bind(message_id)
+    .bind(user_id)
+    .bind(emoji)
+    .execute(&state.db)
+    .await;
+    match removed {
+        Ok(result) if result.rows_affected() > 0 => {}
+        Ok(_) => return,
+        Err(e) => {
+            tracing::error!("Failed to remove reaction: {}", e);
+            return;
         }
     }
 
-    let _ = sqlx::query("UPDATE messages SET reactions = $1 WHERE id = $2")
-        .bind(&reactions)
-        .bind(message_id)
-        .execute(&state.db)
-        .await;
+    emit_reaction_delta(&socket, &state, &user, &message, emoji, "reaction_removed").await;
+}
+
+/// Broadcast a reaction change as a delta: who added or removed which emoji,
+/// and that emoji's new total on the message
+async fn emit_reaction_delta(
+    socket: &SocketRef,
+    state: &AppState,
+    user: &SocketUser,
+    message: &Message,
+    emoji: &str,
+    event: &'static str,
+) {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM message_reactions WHERE message_id = $1 AND emoji = $2",
+    )
+    .bind(message.id)
+    .bind(emoji)
+    .fetch_one(&state.db)
+    .await
+    .unwrap_or(0);
 
     let reaction_response = serde_json::json!({
-        "messageId": message_id,
-        "userId": user_id,
-        "emoji": data.emoji,
-        "reactions": reactions
+        "messageId": message.id,
+        "roomId": message.room_id,
+        "userId": user.user_id,
+        "emoji": emoji,
+        "count": count,
     });
-    socket
-        .within(message.room_id.to_string())
-        .emit("reaction_removed", &reaction_response)
-        .await
-        .ok();
-    socket.emit("reaction_removed", &reaction_response).ok();
+    if !user.shadow_banned {
+        socket
+            .within(message.room_id.to_string())
+            .emit(event, &reaction_response)
+            .await
+            .ok();
+    }
+    socket.emit(event, &reaction_response).ok();
 }
 
 // 8. edit_message - Edit a message
-pub async fn on_edit_message(socket: SocketRef, data: EditMessageData, state: Arc<AppState>) {
-    let user_id = match get_socket_user_info(&socket, &state).await {
-        Some((id, _)) => id,
-        None => return,
-    };
+pub async fn on_edit_message(
+    socket: SocketRef,
+    data: EditMessageData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
+    let user_id = user.user_id;
 
     let message_id = match Uuid::parse_str(&data.message_id) {
         Ok(id) => id,
@@ -564,32 +815,85 @@ pub async fn on_edit_message(socket: SocketRef, data: EditMessageData, state: Ar
         return;
     }
 
-    let _ = sqlx::query("UPDATE messages SET content = $1, updated_at = NOW() WHERE id = $2")
-        .bind(&data.content)
-        .bind(message_id)
-        .execute(&state.db)
-        .await;
+    if guard::check_room_id(&socket, &state, &user, message.room_id, RoomAccess::Post)
+        .await
+        .is_none()
+    {
+        return;
+    }
+
+    // Paranoid mode doesn't record that (or when) a message was edited
+    let updated_at = (!state.config.paranoid_mode).then(chrono::Utc::now);
+    let revision_count = match store_edit(&state, message_id, &data.content, updated_at).await {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!("Failed to edit message {}: {}", message_id, e);
+            return;
+        }
+    };
 
     let edit_response = serde_json::json!({
         "messageId": message_id,
         "content": data.content,
-        "updatedAt": chrono::Utc::now()
+        "updatedAt": updated_at,
+        "revisionCount": revision_count,
     });
-    socket
-        .within(message.room_id.to_string())
-        .emit("message_edited", &edit_response)
-        .await
-        .ok();
+    if !user.shadow_banned {
+        socket
+            .within(message.room_id.to_string())
+            .emit("message_edited", &edit_response)
+            .await
+            .ok();
+    }
     socket.emit("message_edited", &edit_response).ok();
 }
 
-// 9. delete_message - Delete a message
-pub async fn on_delete_message(socket: SocketRef, data: DeleteMessageData, state: Arc<AppState>) {
-    let (user_id, user) = match get_socket_user_info(&socket, &state).await {
-        Some((id, u)) => (id, u),
-        None => return,
-    };
+/// Replace a message's content, keeping the old version in
+/// `message_revisions` for moderators (except in paranoid mode). Returns the
+/// new revision count.
+async fn store_edit(
+    state: &AppState,
+    message_id: Uuid,
+    content: &str,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> sqlx::Result<i32> {
+    let mut tx = state.db.begin().await?;
+    if !state.config.paranoid_mode {
+        // The row lock keeps concurrent edits from saving the same old version twice
+        sqlx::query(
+            "INSERT INTO message_revisions (message_id, content, key_epoch, written_at)
+             SELECT id, content, key_epoch, COALESCE(updated_at, created_at)
+             FROM messages WHERE id = $1
+             FOR UPDATE",
+        )
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    let revision_count = sqlx::query_scalar(
+        "UPDATE messages
+         SET content = $1, updated_at = $2,
+             revision_count = revision_count + CASE WHEN $4 THEN 0 ELSE 1 END
+         WHERE id = $3
+         RETURNING revision_count",
+    )
+    .bind(content)
+    .bind(updated_at)
+    .bind(message_id)
+    .bind(state.config.paranoid_mode)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(revision_count)
+}
 
+// 9. delete_message - Delete a message
+pub async fn on_delete_message(
+    socket: SocketRef,
+    data: DeleteMessageData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
     let message_id = match Uuid::parse_str(&data.message_id) {
         Ok(id) => id,
         Err(_) => return,
@@ -604,8 +908,15 @@ pub async fn on_delete_message(socket: SocketRef, data: DeleteMessageData, state
         _ => return,
     };
 
+    if guard::check_room_id(&socket, &state, &user, message.room_id, RoomAccess::Observe)
+        .await
+        .is_none()
+    {
+        return;
+    }
+
     // Only message owner or admin can delete
-    if message.user_id != user_id && !user.is_admin {
+    if message.user_id != user.user_id && !user.user.is_admin {
         socket
             .emit(
                 "error",
@@ -634,35 +945,85 @@ pub async fn on_delete_message(socket: SocketRef, data: DeleteMessageData, state
 }
 
 // 10. mark_read - Mark message as read
-pub async fn on_mark_read(socket: SocketRef, data: MarkReadData, state: Arc<AppState>) {
-    let user_id = match get_socket_user_info(&socket, &state).await {
-        Some((id, _)) => id,
-        None => return,
-    };
-
-    let room_id = match Uuid::parse_str(&data.room_id) {
-        Ok(id) => id,
-        Err(_) => return,
-    };
-
+pub async fn on_mark_read(
+    socket: SocketRef,
+    data: MarkReadData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
     let message_id = match Uuid::parse_str(&data.message_id) {
         Ok(id) => id,
         Err(_) => return,
     };
 
-    if !check_room_membership(room_id, user_id, &state).await {
+    let room_id =
+        match guard::check_room(&socket, &state, &user, &data.room_id, RoomAccess::Member).await {
+            Some((id, _)) => id,
+            None => return,
+        };
+    let user_id = user.user_id;
+
+    // Only receipts for messages of this room
+    let in_room = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM messages WHERE id = $1 AND room_id = $2)",
+    )
+    .bind(message_id)
+    .bind(room_id)
+    .fetch_one(&state.db)
+    .await
+    .unwrap_or(false);
+    if !in_room {
+        return;
+    }
+
+    // Update last_read_message_id in DB; read positions aren't stored in
+    // paranoid mode
+    if !state.config.paranoid_mode {
+        let _ = sqlx::query(
+            "UPDATE room_members SET last_read_message_id = $1, last_read_at = NOW() WHERE room_id = $2 AND user_id = $3"
+        )
+        .bind(message_id)
+        .bind(room_id)
+        .bind(user_id)
+        .execute(&state.db)
+        .await;
+    }
+
+    if user.shadow_banned {
         return;
     }
 
-    // Update last_read_message_id in DB
-    let _ = sqlx::query(
-        "UPDATE room_members SET last_read_message_id = $1, last_read_at = NOW() WHERE room_id = $2 AND user_id = $3"
+    // Burn-after-reading messages from others, up to the one read, start
+    // their countdown with the first receipt
+    let started = sqlx::query_as::<_, (Uuid, chrono::DateTime<chrono::Utc>)>(
+        "UPDATE messages SET expires_at = NOW() + expires_in * INTERVAL '1 second'
+         WHERE room_id = $1
+           AND expires_after_read AND expires_at IS NULL
+           AND user_id <> $2
+           AND created_at <= (SELECT created_at FROM messages WHERE id = $3 AND room_id = $1)
+         RETURNING id, expires_at",
     )
-    .bind(message_id)
     .bind(room_id)
     .bind(user_id)
-    .execute(&state.db)
-    .await;
+    .bind(message_id)
+    .fetch_all(&state.db)
+    .await
+    .unwrap_or_default();
+    for (id, expires_at) in started {
+        state
+            .io
+            .within(room_id.to_string())
+            .emit(
+                "message_expiry_started",
+                &serde_json::json!({
+                    "roomId": room_id,
+                    "messageId": id,
+                    "expiresAt": expires_at,
+                }),
+            )
+            .await
+            .ok();
+    }
 
     // Broadcast read receipt to room
     socket
@@ -681,12 +1042,12 @@ pub async fn on_mark_read(socket: SocketRef, data: MarkReadData, state: Arc<AppS
 }
 
 // 11. forward_message - Forward a message to another room
-pub async fn on_forward_message(socket: SocketRef, data: ForwardMessageData, state: Arc<AppState>) {
-    let (user_id, user) = match get_socket_user_info(&socket, &state).await {
-        Some((id, u)) => (id, u),
-        None => return,
-    };
-
+pub async fn on_forward_message(
+    socket: SocketRef,
+    data: ForwardMessageData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
     let message_id = match Uuid::parse_str(&data.message_id) {
         Ok(id) => id,
         Err(_) => return,
@@ -697,29 +1058,145 @@ pub async fn on_forward_message(socket: SocketRef, data: ForwardMessageData, sta
         Err(_) => return,
     };
 
-    // Get original message
-    let original_message =
-        match sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = $1")
+    let not_found = || {
+        socket
+            .emit(
+                "error",
+                &ErrorResponse {
+                    error: "Message not found".to_string(),
+                },
+            )
+            .ok();
+    };
+
+    let source_room_id =
+        match sqlx::query_scalar::<_, Uuid>("SELECT room_id FROM messages WHERE id = $1")
             .bind(message_id)
             .fetch_optional(&state.db)
             .await
         {
-            Ok(Some(msg)) => msg,
-            _ => return,
+            Ok(Some(room_id)) => room_id,
+            Ok(None) => return not_found(),
+            Err(_) => return,
         };
 
     // Check membership in both rooms
-    if !check_room_membership(original_message.room_id, user_id, &state).await {
+    let Some(grant) =
+        guard::check_room_id(&socket, &state, &user, source_room_id, RoomAccess::Member).await
+    else {
+        return;
+    };
+
+    // Only what the caller could read in the source room: nothing from
+    // before their history floor, hidden as spam, from a shadow-banned
+    // author or already expired
+    let Ok(floor) = state.history_floor(&user.user, source_room_id).await else {
+        return;
+    };
+    let original_message = match sqlx::query_as::<_, Message>(
+        "SELECT * FROM messages
+         WHERE id = $1
+           AND ((spam_action IS DISTINCT FROM 'hidden'
+                AND NOT EXISTS (SELECT 1 FROM users su WHERE su.id = messages.user_id AND su.is_shadow_banned))
+               OR user_id = $2 OR $3)
+           AND (expires_at IS NULL OR expires_at > NOW())
+           AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)",
+    )
+    .bind(message_id)
+    .bind(user.user_id)
+    .bind(grant.can_moderate())
+    .bind(floor)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(msg)) => msg,
+        Ok(None) => return not_found(),
+        Err(_) => return,
+    };
+
+    if guard::check_room_id(&socket, &state, &user, target_room_id, RoomAccess::Post)
+        .await
+        .is_none()
+    {
         return;
     }
-    if !check_room_membership(target_room_id, user_id, &state).await {
+    let SocketUser {
+        user_id,
+        user,
+        shadow_banned,
+    } = user;
+
+    // E2EE content is encrypted under its room's key, so a copy elsewhere
+    // would be unreadable (or, going the other way, leak plaintext)
+    let involves_e2ee = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM rooms WHERE id IN ($1, $2) AND encryption_mode = 'e2ee')",
+    )
+    .bind(original_message.room_id)
+    .bind(target_room_id)
+    .fetch_one(&state.db)
+    .await
+    .unwrap_or(true);
+    if involves_e2ee {
+        socket
+            .emit(
+                "error",
+                &ErrorResponse {
+                    error: "Messages can't be forwarded to or from end-to-end encrypted rooms"
+                        .to_string(),
+                },
+            )
+            .ok();
+        return;
+    }
+
+    // The target room may not take this kind of message. Messages stored
+    // before types were checked may have one the registry doesn't know.
+    let Some(policy) = content_policy(&state, target_room_id).await else {
+        return;
+    };
+    let allowed = match original_message.message_type.parse::<MessageType>() {
+        Ok(message_type) => policy.check(message_type, original_message.metadata.as_ref()),
+        Err(_) if policy.allowed_message_types.is_some() => Err(format!(
+            "This room doesn't allow {} messages",
+            original_message.message_type
+        )),
+        Err(_) => Ok(()),
+    };
+    if let Err(error) = allowed {
+        socket.emit("error", &ErrorResponse { error }).ok();
+        return;
+    }
+
+    // A copy would outlive the original
+    if original_message.expires_in.is_some() {
+        socket
+            .emit(
+                "error",
+                &ErrorResponse {
+                    error: "Expiring messages can't be forwarded".to_string(),
+                },
+            )
+            .ok();
         return;
     }
 
+    // Scored like anything else the forwarder posts in the target room
+    let spam = state
+        .spam_filter
+        .check(
+            &state.db,
+            target_room_id,
+            &user,
+            &original_message.content,
+            &original_message.message_type,
+        )
+        .await;
+
     // Create forwarded message
     let forwarded_message = match sqlx::query_as::<_, Message>(
-        "INSERT INTO messages (room_id, user_id, content, message_type, forwarded_from, metadata)
-         VALUES ($1, $2, $3, $4, $5, $6)
+        "INSERT INTO messages (room_id, user_id, content, message_type, forwarded_from, metadata,
+                               spam_score, spam_reasons, spam_action)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
          RETURNING *",
     )
     .bind(target_room_id)
@@ -728,6 +1205,9 @@ pub async fn on_forward_message(socket: SocketRef, data: ForwardMessageData, sta
     .bind(&original_message.message_type)
     .bind(message_id)
     .bind(&original_message.metadata)
+    .bind(spam.as_ref().map(|s| s.score))
+    .bind(spam.as_ref().map(|s| serde_json::json!(s.reasons)))
+    .bind(spam.as_ref().and_then(|s| s.action_str()))
     .fetch_one(&state.db)
     .await
     {
@@ -742,6 +1222,7 @@ pub async fn on_forward_message(socket: SocketRef, data: ForwardMessageData, sta
         "content": forwarded_message.content,
         "messageType": forwarded_message.message_type,
         "forwardedFrom": forwarded_message.forwarded_from,
+        "reactions": {},
         "metadata": forwarded_message.metadata,
         "createdAt": forwarded_message.created_at,
         "user": {
@@ -753,21 +1234,26 @@ pub async fn on_forward_message(socket: SocketRef, data: ForwardMessageData, sta
         }
     });
 
-    socket
-        .within(data.target_room_id)
-        .emit("new_message", &message_response)
-        .await
-        .ok();
-    socket.emit("new_message", &message_response).ok();
+    let outgoing = Outgoing {
+        room_id: target_room_id,
+        message_id: forwarded_message.id,
+        sender: &user,
+        shadow_banned,
+        spam: spam.as_ref(),
+        mention: None,
+    };
+    if state.deliver_message(&message_response, outgoing).await {
+        socket.emit("new_message", &message_response).ok();
+    }
 }
 
 // 12. pin_message - Pin a message
-pub async fn on_pin_message(socket: SocketRef, data: PinMessageData, state: Arc<AppState>) {
-    let (user_id, user) = match get_socket_user_info(&socket, &state).await {
-        Some((id, u)) => (id, u),
-        None => return,
-    };
-
+pub async fn on_pin_message(
+    socket: SocketRef,
+    data: PinMessageData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
     let message_id = match Uuid::parse_str(&data.message_id) {
         Ok(id) => id,
         Err(_) => return,
@@ -782,24 +1268,16 @@ pub async fn on_pin_message(socket: SocketRef, data: PinMessageData, state: Arc<
         _ => return,
     };
 
-    if !check_room_membership(message.room_id, user_id, &state).await {
-        return;
-    }
-
-    // Only admins or room creator can pin
-    let is_room_admin = sqlx::query_scalar::<_, String>(
-        "SELECT role FROM room_members WHERE room_id = $1 AND user_id = $2",
-    )
-    .bind(message.room_id)
-    .bind(user_id)
-    .fetch_optional(&state.db)
-    .await
-    .ok()
-    .flatten()
-    .map(|r| r == "admin")
-    .unwrap_or(false);
+    let grant =
+        match guard::check_room_id(&socket, &state, &user, message.room_id, RoomAccess::Member)
+            .await
+        {
+            Some(grant) => grant,
+            None => return,
+        };
 
-    if !is_room_admin && !user.is_admin {
+    // Only room admins or global admins can pin
+    if !grant.can_moderate() {
         socket
             .emit(
                 "error",
@@ -811,6 +1289,7 @@ pub async fn on_pin_message(socket: SocketRef, data: PinMessageData, state: Arc<
         return;
     }
 
+    let user_id = user.user_id;
     let now = chrono::Utc::now();
     let _ = sqlx::query("UPDATE messages SET pinned_by = $1, pinned_at = $2 WHERE id = $3")
         .bind(user_id)
@@ -834,12 +1313,12 @@ pub async fn on_pin_message(socket: SocketRef, data: PinMessageData, state: Arc<
 }
 
 // 13. unpin_message - Unpin a message
-pub async fn on_unpin_message(socket: SocketRef, data: PinMessageData, state: Arc<AppState>) {
-    let (user_id, user) = match get_socket_user_info(&socket, &state).await {
-        Some((id, u)) => (id, u),
-        None => return,
-    };
-
+pub async fn on_unpin_message(
+    socket: SocketRef,
+    data: PinMessageData,
+    state: Arc<AppState>,
+    user: SocketUser,
+) {
     let message_id = match Uuid::parse_str(&data.message_id) {
         Ok(id) => id,
         Err(_) => return,
@@ -854,24 +1333,16 @@ pub async fn on_unpin_message(socket: SocketRef, data: PinMessageData, state: Ar
         _ => return,
     };
 
-    if !check_room_membership(message.room_id, user_id, &state).await {
-        return;
-    }
-
-    // Only admins or room creator can unpin
-    let is_room_admin = sqlx::query_scalar::<_, String>(
-        "SELECT role FROM room_members WHERE room_id = $1 AND user_id = $2",
-    )
-    .bind(message.room_id)
-    .bind(user_id)
-    .fetch_optional(&state.db)
-    .await
-    .ok()
-    .flatten()
-    .map(|r| r == "admin")
-    .unwrap_or(false);
+    let grant =
+        match guard::check_room_id(&socket, &state, &user, message.room_id, RoomAccess::Member)
+            .await
+        {
+            Some(grant) => grant,
+            None => return,
+        };
 
-    if !is_room_admin && !user.is_admin {
+    // Only room admins or global admins can unpin
+    if !grant.can_moderate() {
         socket
             .emit(
                 "error",
@@ -902,21 +1373,20 @@ pub async fn on_unpin_message(socket: SocketRef, data: PinMessageData, state: Ar
 
 // 14. disconnect - Handle socket disconnect
 pub async fn on_disconnect(socket: SocketRef, state: Arc<AppState>) {
-    if let Some((user_id, _)) = get_socket_user_info(&socket, &state).await {
-        // Remove from tracking
+    if let Some((user_id, _)) = state.get_socket_user(&socket.id.to_string()).await {
+        // Remove from tracking; the user goes offline with their last socket
         state.remove_socket_user(&socket.id.to_string()).await;
-        state
+        let went_offline = state
             .remove_user_socket(user_id, &socket.id.to_string())
             .await;
 
-        // Update user online status
-        let _ = sqlx::query("UPDATE users SET is_online = false, last_seen = NOW() WHERE id = $1")
-            .bind(user_id)
-            .execute(&state.db)
-            .await;
-
         tracing::info!("User {} disconnected from socket {}", user_id, socket.id);
 
+        // Presence isn't tracked in paranoid mode
+        if state.config.paranoid_mode || !went_offline {
+            return;
+        }
+
         // Broadcast user offline
         socket
             .broadcast()
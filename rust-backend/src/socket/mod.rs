@@ -1 +1,3 @@
+pub mod admin;
+pub mod guard;
 pub mod handlers;
@@ -1,5 +1,12 @@
 use crate::config::Config;
 use crate::models::user::User;
+use crate::services::{
+    AlertService, DigestService, ErrorSpikeMonitor, ImageProxy, Maintenance, MentionScope,
+    MessageExpiryService, RegistrationQueue, RetentionService, RoomMentions, RoomStatsService,
+    SecurityKeys, ServerIdentity, ServerSettings, SpamFilter, SpamVerdict, TorProbe, TorService,
+    Translator, UploadStorage,
+};
+use crate::socket::guard::GuardCache;
 use socketioxide::SocketIo;
 use sqlx::PgPool;
 use std::collections::HashMap;
@@ -7,6 +14,23 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+/// Socket.IO room joined by all of a user's authenticated sockets
+pub fn user_room(user_id: Uuid) -> String {
+    format!("user:{}", user_id)
+}
+
+/// A newly stored message and what decides who gets it, for
+/// [`AppState::deliver_message`]
+pub struct Outgoing<'a> {
+    pub room_id: Uuid,
+    pub message_id: Uuid,
+    pub sender: &'a User,
+    /// Shadow-banned sender: only they see the message
+    pub shadow_banned: bool,
+    pub spam: Option<&'a SpamVerdict>,
+    pub mention: Option<MentionScope>,
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
@@ -14,37 +38,300 @@ pub struct AppState {
     pub io: SocketIo,
     pub user_sockets: Arc<RwLock<HashMap<Uuid, Vec<String>>>>, // user_id -> socket_ids
     pub socket_users: Arc<RwLock<HashMap<String, (Uuid, User)>>>, // socket_id -> (user_id, user)
+    pub guard_cache: Arc<GuardCache>,
+    pub image_proxy: Arc<ImageProxy>,
+    pub translator: Arc<Translator>,
+    pub spam_filter: Arc<SpamFilter>,
+    pub registration_queue: Arc<RegistrationQueue>,
+    pub maintenance: Arc<Maintenance>,
+    pub tor_probe: Arc<TorProbe>,
+    pub error_spikes: Arc<ErrorSpikeMonitor>,
+    pub digest: Arc<DigestService>,
+    pub retention: Arc<RetentionService>,
+    pub message_expiry: Arc<MessageExpiryService>,
+    pub storage: Arc<UploadStorage>,
+    pub room_stats: Arc<RoomStatsService>,
+    pub room_mentions: Arc<RoomMentions>,
+    pub settings: Arc<ServerSettings>,
+    pub alerts: Arc<AlertService>,
+    /// Signing key for documents other servers rely on
+    pub identity: Arc<ServerIdentity>,
+    pub security_keys: Arc<SecurityKeys>,
+    onion_url: Arc<RwLock<Option<String>>>, // hidden service URL, once Tor has published it
 }
 
 impl AppState {
     pub fn new(db: PgPool, config: Config, io: SocketIo) -> Self {
+        let image_proxy = Arc::new(ImageProxy::new(&config));
+        let translator = Arc::new(Translator::new(&config));
+        let spam_filter = Arc::new(SpamFilter::new(&config));
+        let registration_queue = Arc::new(RegistrationQueue::new(&config));
+        let tor_probe = Arc::new(TorProbe::new(&config));
+        let error_spikes = Arc::new(ErrorSpikeMonitor::new(&config));
+        let digest = Arc::new(DigestService::new(&config));
+        let retention = Arc::new(RetentionService::new(&config));
+        let message_expiry = Arc::new(MessageExpiryService::new(&config));
+        let storage = Arc::new(UploadStorage::new(&config));
+        let room_stats = Arc::new(RoomStatsService::new(&config));
+        let alerts = Arc::new(AlertService::new(&config));
+        let room_mentions = Arc::new(RoomMentions::new(&config));
+        let maintenance = Arc::new(Maintenance::new());
+        let settings = Arc::new(ServerSettings::new(&config, maintenance.clone()));
+        let security_keys = Arc::new(SecurityKeys::new(&config));
         Self {
             db,
             config,
             io,
             user_sockets: Arc::new(RwLock::new(HashMap::new())),
             socket_users: Arc::new(RwLock::new(HashMap::new())),
+            guard_cache: Arc::new(GuardCache::new()),
+            image_proxy,
+            translator,
+            spam_filter,
+            registration_queue,
+            maintenance,
+            tor_probe,
+            error_spikes,
+            digest,
+            retention,
+            message_expiry,
+            storage,
+            room_stats,
+            room_mentions,
+            settings,
+            alerts,
+            identity: Arc::new(ServerIdentity::new()),
+            security_keys,
+            onion_url: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Onion address of this server: ONION_URL if set, otherwise the Tor
+    /// hidden service hostname once it exists
+    pub async fn onion_url(&self) -> Option<String> {
+        if let Some(url) = &self.config.onion_url {
+            return Some(url.clone());
+        }
+        if let Some(url) = self.onion_url.read().await.clone() {
+            return Some(url);
+        }
+
+        let found = TorService::new(self.config.clone())
+            .get_hidden_service_address()
+            .await
+            .map(|host| format!("http://{}", host));
+        if found.is_some() {
+            *self.onion_url.write().await = found.clone();
         }
+        found
     }
 
-    pub async fn add_user_socket(&self, user_id: Uuid, socket_id: String) {
+    /// Tell every connected client the current maintenance status (banner on/off)
+    pub async fn broadcast_maintenance(&self) {
+        self.io
+            .emit("maintenance", &self.maintenance.status())
+            .await
+            .ok();
+    }
+
+    /// Live event for the admin dashboard (`/admin` namespace)
+    pub async fn emit_admin(&self, event: &str, data: serde_json::Value) {
+        crate::socket::admin::emit(&self.io, event, data).await;
+    }
+
+    /// Emit to every socket the user has authenticated
+    pub async fn emit_to_user<T: serde::Serialize>(&self, user_id: Uuid, event: &str, data: &T) {
+        self.io.to(user_room(user_id)).emit(event, data).await.ok();
+    }
+
+    /// Send a newly stored message on its way, the same whether it came in
+    /// over the socket or REST: flagged and hidden messages land in the
+    /// moderation queue, hidden ones (spam or shadow-banned sender) reach
+    /// only the sender's own sockets so they don't notice, everything else
+    /// goes to the room, followed by any `@room`/`@here` notifications.
+    /// Returns whether the room got it.
+    pub async fn deliver_message<T: serde::Serialize>(
+        &self,
+        message: &T,
+        outgoing: Outgoing<'_>,
+    ) -> bool {
+        let sender = outgoing.sender;
+        if let Some(verdict) = outgoing.spam.filter(|s| s.action.is_some()) {
+            self.emit_admin(
+                "message_flagged",
+                serde_json::json!({
+                    "messageId": outgoing.message_id,
+                    "roomId": outgoing.room_id,
+                    "userId": sender.id,
+                    "username": sender.username,
+                    "score": verdict.score,
+                    "reasons": verdict.reasons,
+                    "action": verdict.action_str(),
+                }),
+            )
+            .await;
+        }
+
+        if outgoing.shadow_banned || outgoing.spam.is_some_and(|s| s.is_hidden()) {
+            tracing::info!(
+                "Message {} from {} hidden",
+                outgoing.message_id,
+                sender.username
+            );
+            self.emit_to_user(sender.id, "new_message", message).await;
+            return false;
+        }
+
+        self.io
+            .within(outgoing.room_id.to_string())
+            .emit("new_message", message)
+            .await
+            .ok();
+        if let Some(scope) = outgoing.mention {
+            self.notify_room_mention(outgoing.room_id, outgoing.message_id, sender, scope)
+                .await;
+        }
+        true
+    }
+
+    /// `room_mention` to the members an `@room`/`@here` in a delivered
+    /// message reaches, as its own event so clients can notify even in
+    /// rooms set to mentions only
+    async fn notify_room_mention(
+        &self,
+        room_id: Uuid,
+        message_id: Uuid,
+        sender: &User,
+        scope: MentionScope,
+    ) {
+        let recipients = match self
+            .room_mentions
+            .recipients(&self.db, room_id, sender.id)
+            .await
+        {
+            Ok(recipients) => recipients,
+            Err(e) => {
+                tracing::error!("Failed to load mention recipients: {}", e);
+                return;
+            }
+        };
+        let notification = serde_json::json!({
+            "roomId": room_id,
+            "messageId": message_id,
+            "scope": scope.as_str(),
+            "from": {
+                "id": sender.id,
+                "username": sender.username,
+                "displayName": sender.display_name,
+            },
+        });
+        for user_id in recipients {
+            if scope == MentionScope::Here && !self.is_user_online(user_id).await {
+                continue;
+            }
+            self.emit_to_user(user_id, "room_mention", &notification)
+                .await;
+        }
+    }
+
+    /// Count an authenticated socket for the user. Returns whether it's
+    /// their first, in which case `users.is_online` is set (outside paranoid
+    /// mode). The flag is written while the socket map is locked so a
+    /// connect and a disconnect racing each other can't leave it stale.
+    /// Earliest message `user` may see in a room with history gating: their
+    /// join time, until they've been a member long enough or a room admin
+    /// let them in. None when all history is visible, as it always is to
+    /// room and global admins.
+    pub async fn history_floor(
+        &self,
+        user: &User,
+        room_id: Uuid,
+    ) -> sqlx::Result<Option<chrono::DateTime<chrono::Utc>>> {
+        if user.is_admin {
+            return Ok(None);
+        }
+        let floor = sqlx::query_scalar::<_, Option<chrono::DateTime<chrono::Utc>>>(
+            "SELECT CASE WHEN r.history_gate_minutes IS NULL
+                           OR rm.role = 'admin'
+                           OR rm.history_approved_at IS NOT NULL
+                           OR (r.history_gate_minutes > 0
+                               AND rm.joined_at + make_interval(mins => r.history_gate_minutes) <= NOW())
+                         THEN NULL ELSE rm.joined_at END
+             FROM rooms r
+             JOIN room_members rm ON rm.room_id = r.id AND rm.user_id = $2
+             WHERE r.id = $1",
+        )
+        .bind(room_id)
+        .bind(user.id)
+        .fetch_optional(&self.db)
+        .await?;
+        Ok(floor.flatten())
+    }
+
+    pub async fn add_user_socket(&self, user_id: Uuid, socket_id: String) -> bool {
         let mut sockets = self.user_sockets.write().await;
-        sockets
-            .entry(user_id)
-            .or_insert_with(Vec::new)
-            .push(socket_id);
+        let user_sockets = sockets.entry(user_id).or_insert_with(Vec::new);
+        if user_sockets.contains(&socket_id) {
+            return false; // Authenticated again on the same socket
+        }
+        user_sockets.push(socket_id);
+        let first = user_sockets.len() == 1;
+        if first && !self.config.paranoid_mode {
+            if let Err(e) =
+                sqlx::query("UPDATE users SET is_online = true, updated_at = NOW() WHERE id = $1")
+                    .bind(user_id)
+                    .execute(&self.db)
+                    .await
+            {
+                tracing::error!("Failed to mark user {} online: {}", user_id, e);
+            }
+        }
+        first
     }
 
+    /// Stop counting a socket. Returns whether it was the user's last, in
+    /// which case they're marked offline; other sessions keep them online.
     pub async fn remove_user_socket(&self, user_id: Uuid, socket_id: &str) -> bool {
         let mut sockets = self.user_sockets.write().await;
-        if let Some(user_sockets) = sockets.get_mut(&user_id) {
-            user_sockets.retain(|id| id != socket_id);
-            if user_sockets.is_empty() {
-                sockets.remove(&user_id);
-                return true; // User is now offline
+        let Some(user_sockets) = sockets.get_mut(&user_id) else {
+            return false;
+        };
+        let before = user_sockets.len();
+        user_sockets.retain(|id| id != socket_id);
+        if user_sockets.len() == before || !user_sockets.is_empty() {
+            return false;
+        }
+        sockets.remove(&user_id);
+        if !self.config.paranoid_mode {
+            if let Err(e) =
+                sqlx::query("UPDATE users SET is_online = false, last_seen = NOW(), updated_at = NOW() WHERE id = $1")
+                    .bind(user_id)
+                    .execute(&self.db)
+                    .await
+            {
+                tracing::error!("Failed to mark user {} offline: {}", user_id, e);
             }
         }
-        false
+        true
+    }
+
+    /// Put every socket the user has authenticated in a room they just became
+    /// a member of, so its messages reach them without a `join_room`
+    pub async fn join_user_sockets(&self, user_id: Uuid, room_id: Uuid) {
+        self.io
+            .to(user_room(user_id))
+            .join(room_id.to_string())
+            .await
+            .ok();
+    }
+
+    /// Take the user's sockets out of a room they're no longer a member of
+    pub async fn leave_user_sockets(&self, user_id: Uuid, room_id: Uuid) {
+        self.io
+            .to(user_room(user_id))
+            .leave(room_id.to_string())
+            .await
+            .ok();
     }
 
     pub async fn is_user_online(&self, user_id: Uuid) -> bool {
@@ -0,0 +1,372 @@
+use crate::stats::Recorder;
+use crate::Args;
+use anyhow::{anyhow, bail, Context};
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+use tokio_tungstenite::{tungstenite, MaybeTlsStream, WebSocketStream};
+
+const PASSWORD: &str = "loadgen-password";
+
+type Ws = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Send times keyed by clientNonce, shared by every user so receivers can
+/// measure broadcast latency against the original send
+pub type SentAt = Arc<Mutex<HashMap<String, Instant>>>;
+
+pub struct Account {
+    pub index: usize,
+    pub user_id: String,
+    pub token: String,
+    pub room_id: String,
+}
+
+/// Users and rooms created over REST before the sockets connect
+pub struct Setup {
+    pub accounts: Vec<Account>,
+    room_sizes: Vec<usize>,
+}
+
+impl Setup {
+    pub async fn prepare(args: &Args) -> anyhow::Result<Self> {
+        let http = http_client(args.socks.as_deref())?;
+        let run = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+
+        let mut accounts = Vec::with_capacity(args.users);
+        let mut rooms: Vec<String> = Vec::with_capacity(args.rooms);
+        let mut room_sizes = vec![0; args.rooms];
+
+        for index in 0..args.users {
+            let (user_id, token) = register(&http, &args.url, &format!("lg_{}_{}", run, index))
+                .await
+                .with_context(|| format!("registering user {}", index))?;
+
+            let slot = index % args.rooms;
+            if rooms.len() <= slot {
+                rooms.push(
+                    create_room(
+                        &http,
+                        &args.url,
+                        &token,
+                        &format!("loadgen {} #{}", run, slot),
+                    )
+                    .await?,
+                );
+            } else {
+                post(
+                    &http,
+                    &args.url,
+                    &token,
+                    &format!("/api/rooms/{}/join", rooms[slot]),
+                    None,
+                )
+                .await?;
+            }
+            room_sizes[slot] += 1;
+
+            accounts.push(Account {
+                index,
+                user_id,
+                token,
+                room_id: rooms[slot].clone(),
+            });
+        }
+
+        Ok(Self {
+            accounts,
+            room_sizes,
+        })
+    }
+
+    /// Every message should reach every other member of its room once
+    pub fn expected_deliveries(&self, messages_per_user: usize) -> usize {
+        self.room_sizes
+            .iter()
+            .map(|&n| n * n.saturating_sub(1) * messages_per_user)
+            .sum()
+    }
+}
+
+fn http_client(socks: Option<&str>) -> anyhow::Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(60));
+    if let Some(socks) = socks {
+        builder = builder.proxy(reqwest::Proxy::all(format!("socks5h://{}", socks))?);
+    }
+    Ok(builder.build()?)
+}
+
+async fn post(
+    http: &reqwest::Client,
+    base: &str,
+    token: &str,
+    path: &str,
+    body: Option<Value>,
+) -> anyhow::Result<Value> {
+    let mut req = http.post(format!("{}{}", base, path));
+    if !token.is_empty() {
+        req = req.bearer_auth(token);
+    }
+    if let Some(body) = body {
+        req = req.json(&body);
+    }
+    let response = req.send().await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or_default();
+    if !status.is_success() {
+        bail!("POST {} returned {}: {}", path, status, body);
+    }
+    Ok(body)
+}
+
+async fn register(
+    http: &reqwest::Client,
+    base: &str,
+    username: &str,
+) -> anyhow::Result<(String, String)> {
+    let body = post(
+        http,
+        base,
+        "",
+        "/api/auth/register",
+        Some(json!({ "username": username, "password": PASSWORD })),
+    )
+    .await?;
+    let user_id = body["user"]["id"]
+        .as_str()
+        .ok_or_else(|| anyhow!("no user id"))?;
+    let token = body["token"].as_str().ok_or_else(|| anyhow!("no token"))?;
+    Ok((user_id.to_string(), token.to_string()))
+}
+
+async fn create_room(
+    http: &reqwest::Client,
+    base: &str,
+    token: &str,
+    name: &str,
+) -> anyhow::Result<String> {
+    let body = post(
+        http,
+        base,
+        token,
+        "/api/rooms",
+        Some(json!({ "name": name, "isPublic": true })),
+    )
+    .await?;
+    body["room"]["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("no room id"))
+}
+
+/// Open the Engine.IO websocket, through the SOCKS proxy when configured
+async fn open_socket(base: &str, socks: Option<&str>) -> anyhow::Result<Ws> {
+    let mut url = url::Url::parse(base)?;
+    let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+    url.set_scheme(scheme)
+        .map_err(|_| anyhow!("bad url scheme"))?;
+    url.set_path("/socket.io/");
+    url.set_query(Some("EIO=4&transport=websocket"));
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("url has no host"))?
+        .to_string();
+    let port = url.port_or_known_default().unwrap_or(80);
+    let stream = match socks {
+        Some(proxy) => Socks5Stream::connect(proxy, (host.as_str(), port))
+            .await?
+            .into_inner(),
+        None => TcpStream::connect((host.as_str(), port)).await?,
+    };
+
+    let (ws, _) = tokio_tungstenite::client_async_tls(url.as_str(), stream).await?;
+    Ok(ws)
+}
+
+/// One simulated user: an authenticated socket joined to its room
+pub struct SocketUser {
+    account_index: usize,
+    user_id: String,
+    room_id: String,
+    write: SplitSink<Ws, tungstenite::Message>,
+    read: SplitStream<Ws>,
+    recorder: Arc<Recorder>,
+    sent_at: SentAt,
+    seen: HashSet<String>,
+}
+
+impl SocketUser {
+    pub async fn connect(
+        args: &Args,
+        account: &Account,
+        recorder: Arc<Recorder>,
+        sent_at: SentAt,
+    ) -> anyhow::Result<Self> {
+        let (write, read) = open_socket(&args.url, args.socks.as_deref()).await?.split();
+        let mut user = Self {
+            account_index: account.index,
+            user_id: account.user_id.clone(),
+            room_id: account.room_id.clone(),
+            write,
+            read,
+            recorder,
+            sent_at,
+            seen: HashSet::new(),
+        };
+
+        user.raw("40").await?;
+        user.emit("authenticate", json!({ "token": account.token }))
+            .await?;
+        user.wait_for("authenticated").await?;
+
+        let started = Instant::now();
+        user.emit("join_room", json!({ "roomId": user.room_id }))
+            .await?;
+        user.wait_for("joined_room").await?;
+        Recorder::record(&user.recorder.join, started.elapsed());
+
+        Ok(user)
+    }
+
+    async fn raw(&mut self, frame: &str) -> anyhow::Result<()> {
+        self.write
+            .send(tungstenite::Message::Text(frame.to_string().into()))
+            .await?;
+        Ok(())
+    }
+
+    async fn emit(&mut self, event: &str, data: Value) -> anyhow::Result<()> {
+        self.raw(&format!("42{}", json!([event, data]))).await
+    }
+
+    async fn wait_for(&mut self, event: &str) -> anyhow::Result<()> {
+        let deadline = Duration::from_secs(60);
+        tokio::time::timeout(deadline, async {
+            loop {
+                let frame = self.read.next().await;
+                if let Some((name, _)) = self.handle_frame(frame).await? {
+                    if name == event {
+                        return Ok(());
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| anyhow!("timed out waiting for {}", event))?
+    }
+
+    /// Answer pings and decode Socket.IO events from one websocket frame
+    async fn handle_frame(
+        &mut self,
+        frame: Option<Result<tungstenite::Message, tungstenite::Error>>,
+    ) -> anyhow::Result<Option<(String, Value)>> {
+        let frame = match frame {
+            Some(Ok(tungstenite::Message::Text(t))) => t.to_string(),
+            Some(Ok(_)) => return Ok(None),
+            Some(Err(e)) => return Err(e.into()),
+            None => bail!("socket closed"),
+        };
+
+        if frame == "2" {
+            self.raw("3").await?;
+            return Ok(None);
+        }
+
+        let Some(payload) = frame.strip_prefix("42") else {
+            return Ok(None);
+        };
+        let Ok(Value::Array(parts)) = serde_json::from_str::<Value>(payload) else {
+            return Ok(None);
+        };
+        let name = parts
+            .first()
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        Ok(Some((name, parts.get(1).cloned().unwrap_or(Value::Null))))
+    }
+
+    fn on_event(&mut self, name: &str, data: &Value) {
+        match name {
+            "new_message" => {
+                let id = data["id"].as_str().unwrap_or_default().to_string();
+                // The sender may get its own message twice (room + direct emit)
+                if !self.seen.insert(id) {
+                    return;
+                }
+                let Some(nonce) = data["clientNonce"].as_str() else {
+                    return;
+                };
+                let Some(sent) = self.sent_at.lock().unwrap().get(nonce).copied() else {
+                    return;
+                };
+                let samples = if data["userId"].as_str() == Some(self.user_id.as_str()) {
+                    &self.recorder.ack
+                } else {
+                    &self.recorder.broadcast
+                };
+                Recorder::record(samples, sent.elapsed());
+            }
+            "error" => self
+                .recorder
+                .errors
+                .lock()
+                .unwrap()
+                .push(data["error"].as_str().unwrap_or_default().to_string()),
+            _ => {}
+        }
+    }
+
+    /// Send `messages` messages spaced by `interval`, then keep receiving for `drain`
+    pub async fn run(
+        mut self,
+        messages: usize,
+        interval: Duration,
+        drain: Duration,
+    ) -> anyhow::Result<()> {
+        let mut tick = tokio::time::interval(interval);
+        let mut sent = 0;
+        let mut drain_until = (messages == 0).then(|| tokio::time::Instant::now() + drain);
+
+        loop {
+            let until = drain_until;
+            let drain_sleep = async move {
+                match until {
+                    Some(at) => tokio::time::sleep_until(at).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                _ = tick.tick(), if sent < messages => {
+                    let nonce = format!("{}-{}", self.account_index, sent);
+                    self.sent_at.lock().unwrap().insert(nonce.clone(), Instant::now());
+                    self.emit("send_message", json!({
+                        "roomId": self.room_id,
+                        "content": format!("loadgen message {} from user {}", sent, self.account_index),
+                        "messageType": "text",
+                        "clientNonce": nonce,
+                    })).await?;
+                    sent += 1;
+                    if sent == messages {
+                        drain_until = Some(tokio::time::Instant::now() + drain);
+                    }
+                }
+                frame = self.read.next() => {
+                    if let Some((name, data)) = self.handle_frame(frame).await? {
+                        self.on_event(&name, &data);
+                    }
+                }
+                _ = drain_sleep => break,
+            }
+        }
+
+        let _ = self.write.close().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+const SAMPLE_EVERY: Duration = Duration::from_millis(250);
+
+/// Aggregated `pg_stat_activity` readings taken while the load ran
+#[derive(Debug, Default)]
+pub struct Contention {
+    pub samples: usize,
+    pub avg_active: f64,
+    pub max_active: i64,
+    pub avg_lock_waits: f64,
+    pub max_lock_waits: i64,
+}
+
+/// Background task polling the server's database for busy and lock-waiting backends
+pub struct Sampler {
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<Contention>,
+}
+
+impl Sampler {
+    pub async fn start(database_url: &str) -> anyhow::Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(database_url)
+            .await?;
+        let (stop, mut stopped) = oneshot::channel();
+
+        let handle = tokio::spawn(async move {
+            let mut out = Contention::default();
+            let (mut active_sum, mut waits_sum) = (0i64, 0i64);
+            let mut tick = tokio::time::interval(SAMPLE_EVERY);
+
+            loop {
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = tick.tick() => {}
+                }
+
+                let row: Result<(i64, i64), _> = sqlx::query_as(
+                    "SELECT count(*) FILTER (WHERE state = 'active'),
+                            count(*) FILTER (WHERE wait_event_type = 'Lock')
+                     FROM pg_stat_activity
+                     WHERE datname = current_database() AND pid <> pg_backend_pid()",
+                )
+                .fetch_one(&pool)
+                .await;
+
+                if let Ok((active, waits)) = row {
+                    out.samples += 1;
+                    active_sum += active;
+                    waits_sum += waits;
+                    out.max_active = out.max_active.max(active);
+                    out.max_lock_waits = out.max_lock_waits.max(waits);
+                }
+            }
+
+            if out.samples > 0 {
+                out.avg_active = active_sum as f64 / out.samples as f64;
+                out.avg_lock_waits = waits_sum as f64 / out.samples as f64;
+            }
+            out
+        });
+
+        Ok(Self { stop, handle })
+    }
+
+    pub async fn stop(self) -> Contention {
+        let _ = self.stop.send(());
+        self.handle.await.unwrap_or_default()
+    }
+}
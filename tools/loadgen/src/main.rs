@@ -0,0 +1,116 @@
+//! Load generator: N concurrent socket users chatting in shared rooms,
+//! optionally through a SOCKS5 proxy (Tor), reporting delivery latencies.
+//!
+//! ```text
+//! cargo run --release -- --url http://localhost:3000 --users 50 --rooms 5
+//! cargo run --release -- --url http://xxxx.onion --socks 127.0.0.1:9050 --users 10
+//! ```
+
+mod client;
+mod db;
+mod stats;
+
+use clap::Parser;
+use client::{SentAt, Setup, SocketUser};
+use stats::{Recorder, Report};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Simulate concurrent chat users and measure broadcast latency")]
+pub struct Args {
+    /// Server base URL (clearnet or .onion)
+    #[arg(long, env = "LOADGEN_URL", default_value = "http://localhost:3000")]
+    pub url: String,
+
+    /// SOCKS5 proxy for all traffic, e.g. 127.0.0.1:9050 for Tor
+    #[arg(long, env = "LOADGEN_SOCKS")]
+    pub socks: Option<String>,
+
+    /// Concurrent socket users
+    #[arg(long, default_value_t = 20)]
+    pub users: usize,
+
+    /// Rooms to spread users over (round-robin)
+    #[arg(long, default_value_t = 1)]
+    pub rooms: usize,
+
+    /// Messages each user sends
+    #[arg(long, default_value_t = 20)]
+    pub messages: usize,
+
+    /// Delay between a user's messages
+    #[arg(long, default_value_t = 500)]
+    pub interval_ms: u64,
+
+    /// How long to keep listening for broadcasts after the last send
+    #[arg(long, default_value_t = 15)]
+    pub drain_secs: u64,
+
+    /// Postgres URL of the server's database; samples lock waits while running
+    #[arg(long, env = "LOADGEN_DATABASE_URL")]
+    pub database_url: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    anyhow::ensure!(
+        args.users > 0 && args.rooms > 0,
+        "--users and --rooms must be > 0"
+    );
+
+    println!(
+        "Setting up {} users in {} room(s) on {}{}",
+        args.users,
+        args.rooms,
+        args.url,
+        args.socks
+            .as_deref()
+            .map(|s| format!(" via socks5 {}", s))
+            .unwrap_or_default()
+    );
+    let setup = Setup::prepare(&args).await?;
+
+    let recorder = Arc::new(Recorder::default());
+    let sent_at = SentAt::default();
+    let sampler = match &args.database_url {
+        Some(url) => Some(db::Sampler::start(url).await?),
+        None => None,
+    };
+
+    println!("Connecting sockets...");
+    let mut users = Vec::with_capacity(args.users);
+    for account in &setup.accounts {
+        users.push(SocketUser::connect(&args, account, recorder.clone(), sent_at.clone()).await?);
+    }
+
+    println!(
+        "Running: {} messages per user every {}ms",
+        args.messages, args.interval_ms
+    );
+    let interval = Duration::from_millis(args.interval_ms);
+    let drain = Duration::from_secs(args.drain_secs);
+    let tasks: Vec<_> = users
+        .into_iter()
+        .map(|user| tokio::spawn(user.run(args.messages, interval, drain)))
+        .collect();
+    for task in tasks {
+        if let Err(e) = task.await? {
+            eprintln!("user task failed: {:#}", e);
+        }
+    }
+
+    let contention = match sampler {
+        Some(sampler) => Some(sampler.stop().await),
+        None => None,
+    };
+
+    Report::new(
+        &recorder,
+        setup.expected_deliveries(args.messages),
+        contention,
+    )
+    .print();
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use crate::db::Contention;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Latency samples collected by every user task
+#[derive(Default)]
+pub struct Recorder {
+    /// `join_room` -> `joined_room`
+    pub join: Mutex<Vec<Duration>>,
+    /// `send_message` -> the sender's own `new_message` (insert + fanout start)
+    pub ack: Mutex<Vec<Duration>>,
+    /// `send_message` -> `new_message` at every other room member
+    pub broadcast: Mutex<Vec<Duration>>,
+    pub errors: Mutex<Vec<String>>,
+}
+
+impl Recorder {
+    pub fn record(samples: &Mutex<Vec<Duration>>, latency: Duration) {
+        samples.lock().unwrap().push(latency);
+    }
+}
+
+struct Summary {
+    count: usize,
+    p50: Duration,
+    p95: Duration,
+    p99: Duration,
+    max: Duration,
+}
+
+impl Summary {
+    fn of(samples: &Mutex<Vec<Duration>>) -> Option<Self> {
+        let mut sorted = samples.lock().unwrap().clone();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort();
+        let at = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+        Some(Self {
+            count: sorted.len(),
+            p50: at(0.50),
+            p95: at(0.95),
+            p99: at(0.99),
+            max: *sorted.last().unwrap(),
+        })
+    }
+}
+
+pub struct Report<'a> {
+    recorder: &'a Recorder,
+    expected_deliveries: usize,
+    contention: Option<Contention>,
+}
+
+impl<'a> Report<'a> {
+    pub fn new(
+        recorder: &'a Recorder,
+        expected_deliveries: usize,
+        contention: Option<Contention>,
+    ) -> Self {
+        Self {
+            recorder,
+            expected_deliveries,
+            contention,
+        }
+    }
+
+    pub fn print(&self) {
+        println!();
+        println!(
+            "{:<10} {:>8} {:>10} {:>10} {:>10} {:>10}",
+            "latency", "samples", "p50", "p95", "p99", "max"
+        );
+        for (name, samples) in [
+            ("join", &self.recorder.join),
+            ("ack", &self.recorder.ack),
+            ("broadcast", &self.recorder.broadcast),
+        ] {
+            match Summary::of(samples) {
+                Some(s) => println!(
+                    "{:<10} {:>8} {:>10} {:>10} {:>10} {:>10}",
+                    name,
+                    s.count,
+                    ms(s.p50),
+                    ms(s.p95),
+                    ms(s.p99),
+                    ms(s.max)
+                ),
+                None => println!("{:<10} {:>8}", name, 0),
+            }
+        }
+
+        let delivered = self.recorder.broadcast.lock().unwrap().len();
+        println!();
+        println!(
+            "broadcast deliveries: {}/{} ({} missing)",
+            delivered,
+            self.expected_deliveries,
+            self.expected_deliveries.saturating_sub(delivered)
+        );
+
+        let errors = self.recorder.errors.lock().unwrap();
+        if !errors.is_empty() {
+            println!("server errors: {} (first: {})", errors.len(), errors[0]);
+        }
+
+        if let Some(c) = &self.contention {
+            println!(
+                "db: {} samples, active backends avg {:.1} / max {}, lock waits avg {:.2} / max {}",
+                c.samples, c.avg_active, c.max_active, c.avg_lock_waits, c.max_lock_waits
+            );
+        }
+    }
+}
+
+fn ms(d: Duration) -> String {
+    format!("{:.1}ms", d.as_secs_f64() * 1000.0)
+}